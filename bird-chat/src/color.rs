@@ -1,8 +1,9 @@
-use std::fmt::{Display, Formatter};
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
-#[serde(try_from = "&str", into = "String")]
+#[serde(try_from = "String", into = "String")]
 pub enum Color {
     Black,
     DarkBlue,
@@ -61,7 +62,7 @@ impl Color {
             0x55ff55 => Self::BrightGreen,
             0x55ffff => Self::Cyan,
             0xff5555 => Self::Red,
-            0xff55ff => Self::Purple,
+            0xff55ff => Self::Pink,
             0xffff55 => Self::Yellow,
             0xffffff => Self::White,
             other => Self::Custom {
@@ -73,10 +74,17 @@ impl Color {
     }
 }
 
-#[derive(thiserror::Error, Debug)]
-#[error("Parsing of color is failed")]
+#[derive(Debug)]
 pub struct ColorParseError;
 
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Parsing of color is failed")
+    }
+}
+
+impl core::error::Error for ColorParseError {}
+
 impl TryFrom<&str> for Color {
     type Error = ColorParseError;
 
@@ -125,7 +133,7 @@ impl From<Color> for String {
 }
 
 impl Display for Color {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Black => write!(f, "black"),
             Self::DarkBlue => write!(f, "dark_blue"),
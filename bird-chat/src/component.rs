@@ -1,41 +1,168 @@
-use std::borrow::Cow;
-use serde::{Deserialize, Serialize};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
 use crate::color::Color;
 use crate::identifier::Identifier;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Component<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub obfuscated: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub font: Option<Identifier<'a>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub insertion: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub click_event: Option<ClickEvent<'a>>,
+    #[serde(default, skip_serializing_if = "is_empty", deserialize_with = "deserialize_components")]
     pub extra: Cow<'a, [Component<'a>]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hover_event: Option<HoverEvent<'a>>,
     #[serde(flatten)]
     pub ty: Option<ComponentType<'a>>,
 }
 
+// Vanilla client fails on null values, so empty fields are not serialized
+fn is_empty<T: Clone>(value: &Cow<[T]>) -> bool {
+    value.is_empty()
+}
+
+/// Vanilla reads plain strings as text components, they are written as objects
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextOrComponent<'a> {
+    Text(String),
+    Component(Component<'a>),
+}
+
+impl<'a> From<TextOrComponent<'a>> for Component<'a> {
+    fn from(value: TextOrComponent<'a>) -> Self {
+        match value {
+            TextOrComponent::Text(text) => Component::text(text),
+            TextOrComponent::Component(component) => component,
+        }
+    }
+}
+
+fn deserialize_component<'de, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Box<Component<'a>>, D::Error> {
+    TextOrComponent::deserialize(deserializer).map(|component| Box::new(component.into()))
+}
+
+fn deserialize_components<'de, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'a, [Component<'a>]>, D::Error> {
+    Vec::<TextOrComponent>::deserialize(deserializer)
+        .map(|components| Cow::Owned(components.into_iter().map(Component::from).collect()))
+}
+
+impl<'a> Component<'a> {
+    pub const fn new(ty: Option<ComponentType<'a>>) -> Self {
+        Self {
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            font: None,
+            color: None,
+            insertion: None,
+            click_event: None,
+            extra: Cow::Borrowed(&[]),
+            hover_event: None,
+            ty,
+        }
+    }
+
+    pub fn text(text: impl Into<Cow<'a, str>>) -> Self {
+        Self::new(Some(ComponentType::Text { text: text.into() }))
+    }
+
+    pub fn translation(key: impl Into<Cow<'a, str>>, with: impl Into<Cow<'a, [Component<'a>]>>) -> Self {
+        Self::new(Some(ComponentType::Translation { key: key.into(), with: with.into() }))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case", tag = "action", content = "value")]
 pub enum ClickEvent<'a> {
     OpenUrl(Cow<'a, str>),
     RunCommand(Cow<'a, str>),
     SuggestCommand(Cow<'a, str>),
-    ChangePage(i32),
+    /// Vanilla sends the page as a string
+    ChangePage(#[serde(with = "page")] i32),
+    CopyToClipboard(Cow<'a, str>),
+}
+
+mod page {
+    use alloc::string::String;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Page {
+        Number(i32),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(page: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(page)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+        match Page::deserialize(deserializer)? {
+            Page::Number(page) => Ok(page),
+            Page::Text(page) => page.parse().map_err(D::Error::custom),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case", tag = "action", content = "value")]
+#[serde(rename_all = "snake_case", tag = "action", content = "contents")]
 pub enum HoverEvent<'a> {
-    ShowText(either::Either<Box<Component<'a>>, Cow<'a, str>>),
-    ShowItem(Cow<'a, str>),
-    ShowEntity(Cow<'a, str>),
+    /// Vanilla accepts plain strings too
+    ShowText(#[serde(deserialize_with = "deserialize_component")] Box<Component<'a>>),
+    ShowItem(HoverItem<'a>),
+    ShowEntity(HoverEntity<'a>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HoverItem<'a> {
+    pub id: Identifier<'a>,
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub count: i32,
+    /// Item nbt in the SNBT format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Cow<'a, str>>,
+}
+
+fn one() -> i32 {
+    1
+}
+
+fn is_one(count: &i32) -> bool {
+    *count == 1
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HoverEntity<'a> {
+    #[serde(rename = "type")]
+    pub ty: Identifier<'a>,
+    pub id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Box<Component<'a>>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -45,7 +172,10 @@ pub enum ComponentType<'a> {
         text: Cow<'a, str>,
     },
     Translation {
+        /// Vanilla writes arguments which are not components as strings
+        #[serde(default, skip_serializing_if = "is_empty", deserialize_with = "deserialize_components")]
         with: Cow<'a, [Component<'a>]>,
+        #[serde(rename = "translate")]
         key: Cow<'a, str>,
     },
     KeyBind {
@@ -62,7 +192,121 @@ pub enum ComponentType<'a> {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Score<'a> {
-    name: Cow<'a, str>, // possible uuid but actually string in json
-    objective: Cow<'a, str>,
-    value: Cow<'a, str>,
-}
\ No newline at end of file
+    pub name: Cow<'a, str>, // possible uuid but actually string in json
+    pub objective: Cow<'a, str>,
+    /// Vanilla doesn't send the value since 1.19
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Cow<'a, str>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use crate::color::Color;
+    use super::*;
+
+    /// Fixtures contain components in the format which is sent by vanilla server
+    fn golden(fixture: &str) -> Vec<Value> {
+        serde_json::from_str(fixture).unwrap()
+    }
+
+    fn assert_golden(fixture: &str) {
+        for expected in golden(fixture) {
+            let component: Component = serde_json::from_value(expected.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&component).unwrap(), expected);
+            let text = serde_json::to_string(&component).unwrap();
+            assert_eq!(serde_json::from_str::<Component>(&text).unwrap(), component);
+        }
+    }
+
+    #[test]
+    fn text_styles_test() {
+        assert_golden(include_str!("../tests/golden/text_styles.json"));
+        let mut component = Component::text("bold");
+        component.bold = Some(true);
+        assert_eq!(serde_json::to_value(&component).unwrap(), golden(include_str!("../tests/golden/text_styles.json"))[1]);
+    }
+
+    #[test]
+    fn colors_test() {
+        assert_golden(include_str!("../tests/golden/colors.json"));
+        let colors = golden(include_str!("../tests/golden/colors.json"));
+        for (index, expected) in colors.iter().take(16).enumerate() {
+            let component: Component = serde_json::from_value(expected.clone()).unwrap();
+            let color = component.color.unwrap();
+            assert_eq!(Color::from_color(color.get_color()), color);
+            assert_eq!(color.get_color(), LEGACY_COLORS[index]);
+        }
+    }
+
+    /// Colors of legacy codes from 0 to f
+    const LEGACY_COLORS: [u32; 16] = [
+        0x000000, 0x0000aa, 0x00aa00, 0x00aaaa, 0xaa0000, 0xaa00aa, 0xffaa00, 0xaaaaaa,
+        0x555555, 0x5555ff, 0x55ff55, 0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
+    ];
+
+    #[test]
+    fn click_events_test() {
+        assert_golden(include_str!("../tests/golden/click_events.json"));
+        let component: Component = serde_json::from_value(golden(include_str!("../tests/golden/click_events.json"))[3].clone()).unwrap();
+        assert_eq!(component.click_event, Some(ClickEvent::ChangePage(2)));
+        // Numeric pages are accepted too
+        let component: Component = serde_json::from_str(r#"{"text":"","clickEvent":{"action":"change_page","value":3}}"#).unwrap();
+        assert_eq!(component.click_event, Some(ClickEvent::ChangePage(3)));
+    }
+
+    #[test]
+    fn hover_events_test() {
+        assert_golden(include_str!("../tests/golden/hover_events.json"));
+        let hover_events = golden(include_str!("../tests/golden/hover_events.json"));
+        let component: Component = serde_json::from_value(hover_events[1].clone()).unwrap();
+        match component.hover_event {
+            Some(HoverEvent::ShowItem(item)) => assert_eq!(item.count, 1),
+            _ => panic!("Expected show_item hover event"),
+        }
+    }
+
+    #[test]
+    fn translate_test() {
+        assert_golden(include_str!("../tests/golden/translate.json"));
+        let component = Component::translation("chat.type.text", vec![Component::text("Notch"), Component::text("Hello")]);
+        assert_eq!(serde_json::to_value(&component).unwrap(), golden(include_str!("../tests/golden/translate.json"))[1]);
+    }
+
+    #[test]
+    fn nested_extras_test() {
+        assert_golden(include_str!("../tests/golden/nested_extras.json"));
+        let mut component = Component::text("");
+        let mut bold = Component::text("b");
+        bold.bold = Some(true);
+        component.extra = Cow::Owned(vec![Component::text("a"), bold]);
+        assert_eq!(serde_json::to_value(&component).unwrap(), golden(include_str!("../tests/golden/nested_extras.json"))[0]);
+    }
+
+    /// Arguments of vanilla translations which are not components are written as strings,
+    /// and plain strings are accepted wherever a component is read
+    #[test]
+    fn vanilla_strings_test() {
+        let fixtures = golden(include_str!("../tests/golden/vanilla_strings.json"));
+        for expected in &fixtures {
+            let component: Component = serde_json::from_value(expected.clone()).unwrap();
+            let written = serde_json::to_value(&component).unwrap();
+            assert_eq!(serde_json::from_value::<Component>(written).unwrap(), component);
+        }
+        let component: Component = serde_json::from_value(fixtures[0].clone()).unwrap();
+        assert_eq!(component, Component::translation("commands.time.query", vec![Component::text("1000")]));
+        let component: Component = serde_json::from_value(fixtures[2].clone()).unwrap();
+        assert_eq!(component.hover_event, Some(HoverEvent::ShowText(Box::new(Component::text("tooltip")))));
+        let component: Component = serde_json::from_value(fixtures[3].clone()).unwrap();
+        assert_eq!(component.extra[0], Component::text("a"));
+    }
+
+    #[test]
+    fn contents_types_test() {
+        assert_golden(include_str!("../tests/golden/contents_types.json"));
+        let component: Component = serde_json::from_value(golden(include_str!("../tests/golden/contents_types.json"))[2].clone()).unwrap();
+        assert_eq!(component.ty, Some(ComponentType::Score {
+            score: Score { name: "Notch".into(), objective: "kills".into(), value: None },
+        }));
+    }
+}
@@ -1,6 +1,8 @@
-use std::borrow::Cow;
-use std::fmt::{Debug, Display, Formatter};
-use std::str::pattern::{Pattern, Searcher};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Debug, Display, Formatter};
+use core::str::pattern::{Pattern, Searcher};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
@@ -107,7 +109,7 @@ impl<'a> Identifier<'a> {
 }
 
 impl<'a> Display for Identifier<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self.get_inner() {
             IdentifierInner::Full(full) => write!(f, "{}", full),
             IdentifierInner::Partial(key, value) => write!(f, "{}:{}", key, value)
@@ -139,10 +141,17 @@ impl<'a> From<Identifier<'a>> for Cow<'a, str> {
     }
 }
 
-#[derive(thiserror::Error, Debug)]
-#[error("Parsing of identifier is failed")]
+#[derive(Debug)]
 pub struct IdentifierParseError;
 
+impl Display for IdentifierParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Parsing of identifier is failed")
+    }
+}
+
+impl core::error::Error for IdentifierParseError {}
+
 impl<'a> TryFrom<&'a str> for Identifier<'a> {
     type Error = IdentifierParseError;
 
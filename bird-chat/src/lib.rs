@@ -1,4 +1,7 @@
 #![feature(pattern)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod identifier;
 pub mod component;
@@ -28,8 +28,8 @@ pub fn generate_biomes(api: &Api) -> syn::Result<TokenStream> {
         let dimension_enum_ident = Ident::new(dimension.to_case(Case::Pascal).as_str(), Span::call_site());
         let category_enum_ident = Ident::new(category.to_case(Case::Pascal).as_str(), Span::call_site());
         let precipitation_enum_ident = Ident::new(precipitation.to_case(Case::Pascal).as_str(), Span::call_site());
-        biome_from_id_ts.push(quote! { #id => std::option::Option::Some(&biome_data:: #biome_const_ident)});
-        biome_from_name_ts.push(quote! { #name => std::option::Option::Some(&biome_data:: #biome_const_ident)});
+        biome_from_id_ts.push(quote! { #id => core::option::Option::Some(&biome_data:: #biome_const_ident)});
+        biome_from_name_ts.push(quote! { #name => core::option::Option::Some(&biome_data:: #biome_const_ident)});
         biome_consts.push(quote! { 
             pub const #biome_const_ident: super::BiomeData<'static> = super::BiomeData::new(
                     #id, #name, super::BiomeCategory:: #category_enum_ident,
@@ -78,17 +78,17 @@ pub fn generate_biomes(api: &Api) -> syn::Result<TokenStream> {
                 Self { id, name, category, temperature, precipitation, dimension, color, rain_fall }
             } 
 
-            pub const fn from_id(id: u32) -> std::option::Option<&'static Self> {
+            pub const fn from_id(id: u32) -> core::option::Option<&'static Self> {
                 match id { 
                     #(#biome_from_id_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub fn from_name(name: &str) -> std::option::Option<&'static Self> {
+            pub fn from_name(name: &str) -> core::option::Option<&'static Self> {
                 match name {
                     #(#biome_from_name_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
         }
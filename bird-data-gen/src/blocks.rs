@@ -104,6 +104,20 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
         let default_state_id = default_state.expect("default state id is none") as usize;
 
         let states = states.filter(|states| !states.is_empty());
+        let properties_ts = states.iter()
+            .flatten()
+            .map(|state| {
+                let property_name = &state.name;
+                let values = match state.state_type {
+                    StateType::Bool => vec![String::from("true"), String::from("false")],
+                    _ => state.values.clone().expect("state values are none"),
+                };
+                quote! { super::BlockProperty::new(#property_name, &[#(#values,)*]) }
+            })
+            .collect::<Vec<TokenStream>>();
+        let min_state_id_u32 = min_state_id as u32;
+        let max_state_id_u32 = max_state_id as u32;
+        let default_state_id_u32 = default_state_id as u32;
 
         let (default_creator, creators, block_enum_repr, block_enum_in_match_repr) = match states {
             Some(states) => {
@@ -172,25 +186,26 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
             }
         };
 
-        blocks_from_id_ts.push(quote! { #id => std::option::Option::Some(#default_creator) });
-        blocks_from_name_ts.push(quote! { #name => std::option::Option::Some(#default_creator) });
+        blocks_from_id_ts.push(quote! { #id => core::option::Option::Some(#default_creator) });
+        blocks_from_name_ts.push(quote! { #name => core::option::Option::Some(#default_creator) });
         { 
             let mut current_state = min_state_id as u32;
             for creator in &creators {
-                blocks_from_state_ts.push(quote! { #current_state => std::option::Option::Some(#creator) });
-                blocks_state_ts.push(quote!{ #creator => std::option::Option::Some(#current_state) });
+                blocks_from_state_ts.push(quote! { #current_state => core::option::Option::Some(#creator) });
+                blocks_state_ts.push(quote!{ #creator => core::option::Option::Some(#current_state) });
                 current_state += 1;
             }
         }
         let block_data_const_ident = Ident::new(name.to_case(Case::UpperSnake).as_str(), Span::call_site());
         blocks_const_data_ts.push(quote! { 
             pub const #block_data_const_ident: super::BlockData<'static> = super::BlockData::new(
-                #id, #name, #hardness, #blast_resistance, #diggable, #material, #transparent, #emit_light, #filter_light, &[#(#drops,)*]
+                #id, #name, #hardness, #blast_resistance, #diggable, #material, #transparent, #emit_light, #filter_light, &[#(#drops,)*],
+                #min_state_id_u32, #max_state_id_u32, #default_state_id_u32, &[#(#properties_ts,)*]
             );
         });
         blocks_data_ts.push(quote! { #block_enum_in_match_repr => &block_data:: #block_data_const_ident });
-        blocks_data_from_id_ts.push(quote! { #id => std::option::Option::Some(&block_data:: #block_data_const_ident ) });
-        blocks_data_from_name_ts.push(quote! { #name => std::option::Option::Some(&block_data:: #block_data_const_ident ) });
+        blocks_data_from_id_ts.push(quote! { #id => core::option::Option::Some(&block_data:: #block_data_const_ident ) });
+        blocks_data_from_name_ts.push(quote! { #name => core::option::Option::Some(&block_data:: #block_data_const_ident ) });
         blocks_enum_ts.push(block_enum_repr);
     }
     let registry_count = blocks_const_data_ts.len();
@@ -214,6 +229,28 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
             pub emit_light: u8,
             pub filter_light: u8,
             pub drops: &'a [u32],
+            pub min_state_id: u32,
+            pub max_state_id: u32,
+            pub default_state_id: u32,
+            /// Properties in the order of state ids, the first property changes the slowest
+            pub properties: &'a [BlockProperty<'a>],
+        }
+
+        /// Property of block states with values in the order of state ids
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct BlockProperty<'a> {
+            pub name: &'a str,
+            pub values: &'a [&'a str],
+        }
+
+        impl<'a> BlockProperty<'a> {
+            pub const fn new(name: &'a str, values: &'a [&'a str]) -> Self {
+                Self { name, values }
+            }
+
+            pub fn value_index(&self, value: &str) -> core::option::Option<usize> {
+                self.values.iter().position(|current| *current == value)
+            }
         }
 
         #[derive(Clone, Copy, Debug, PartialEq)]
@@ -227,24 +264,24 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
         }
 
         impl Block {
-            pub const fn from_id(id: u32) -> std::option::Option<Self> {
+            pub const fn from_id(id: u32) -> core::option::Option<Self> {
                 match id {
                     #(#blocks_from_id_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub fn from_name(name: &str) -> std::option::Option<Self> {
+            pub fn from_name(name: &str) -> core::option::Option<Self> {
                 match name {
                     #(#blocks_from_name_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub const fn from_state(state: u32) -> std::option::Option<Self> {
+            pub const fn from_state(state: u32) -> core::option::Option<Self> {
                 match state {
                     #(#blocks_from_state_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
@@ -254,10 +291,10 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
                 }
             }
 
-            pub const fn get_state(&self) -> std::option::Option<u32> {
+            pub const fn get_state(&self) -> core::option::Option<u32> {
                 match self {
                     #(#blocks_state_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
         }
@@ -267,33 +304,43 @@ pub fn generate_blocks(api: &Api) -> syn::Result<TokenStream> {
                 id: u32, name: &'a str, hardness: f32, 
                 blast_resistance: f32, diggable: bool, material: &'a str,
                 transparent: bool, emit_light: u8, filter_light: u8,
-                drops: &'a [u32]
+                drops: &'a [u32], min_state_id: u32, max_state_id: u32,
+                default_state_id: u32, properties: &'a [BlockProperty<'a>]
             ) -> Self {
                 Self { 
                     id, name, hardness, blast_resistance, diggable, 
-                    material, transparent, emit_light, filter_light, drops 
+                    material, transparent, emit_light, filter_light, drops,
+                    min_state_id, max_state_id, default_state_id, properties
                 }
             }
 
-            pub const fn from_id(id: u32) -> std::option::Option<&'static Self> {
+            pub fn from_state(state: u32) -> core::option::Option<&'static Self> {
+                Block::from_state(state).map(|block| block.get_data())
+            }
+
+            pub fn property(&self, name: &str) -> core::option::Option<&BlockProperty<'a>> {
+                self.properties.iter().find(|property| property.name == name)
+            }
+
+            pub const fn from_id(id: u32) -> core::option::Option<&'static Self> {
                 match id {
                     #(#blocks_data_from_id_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub fn from_name(name: &str) -> std::option::Option<&'static Self> {
+            pub fn from_name(name: &str) -> core::option::Option<&'static Self> {
                 match name {
                     #(#blocks_data_from_name_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub fn get_material(&self) -> std::option::Option<Material> {
+            pub fn get_material(&self) -> core::option::Option<Material> {
                 Material::from_name(self.material)
             } 
 
-            pub fn as_item_data(&self) -> std::option::Option<&'static ItemData> {
+            pub fn as_item_data(&self) -> core::option::Option<&'static ItemData> {
                 ItemData::from_name(self.name)
             }
         }
@@ -20,8 +20,8 @@ pub fn generate_items(api: &Api) -> syn::Result<TokenStream> {
                 #id, #name, #stack_size
             );
         });
-        item_from_id_ts.push(quote! { #id => std::option::Option::Some(&item_data:: #item_const_ident ) });
-        item_from_name_ts.push(quote! { #name => std::option::Option::Some(&item_data:: #item_const_ident) });
+        item_from_id_ts.push(quote! { #id => core::option::Option::Some(&item_data:: #item_const_ident ) });
+        item_from_name_ts.push(quote! { #name => core::option::Option::Some(&item_data:: #item_const_ident) });
     }
     Ok(quote! {
         #[derive(Clone, Copy, Debug, PartialEq)]
@@ -40,17 +40,17 @@ pub fn generate_items(api: &Api) -> syn::Result<TokenStream> {
                 Self { id, name, stack_size }
             }
 
-            pub const fn from_id(id: u32) -> std::option::Option<&'static Self> {
+            pub const fn from_id(id: u32) -> core::option::Option<&'static Self> {
                 match id {
                     #(#item_from_id_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
-            pub fn from_name(name: &str) -> std::option::Option<&'static Self> {
+            pub fn from_name(name: &str) -> core::option::Option<&'static Self> {
                 match name {
                     #(#item_from_name_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
         }
@@ -2,6 +2,8 @@ use biomes::generate_biomes;
 use blocks::generate_blocks;
 use items::generate_items;
 use materials::generate_materials;
+use shapes::generate_shapes;
+use sounds::generate_sounds;
 use minecraft_data_rs::{api::versions_by_minecraft_version, Api};
 use proc_macro::TokenTree;
 use proc_macro2::Span;
@@ -11,6 +13,8 @@ mod biomes;
 mod items;
 mod materials;
 mod blocks;
+mod sounds;
+mod shapes;
 
 #[proc_macro]
 pub fn generate_data(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -27,8 +31,9 @@ fn generate_data_impl(input: proc_macro::TokenStream) -> syn::Result<proc_macro2
         .ok_or_else(|| syn::Error::new(Span::call_site(), "Input should be string literal"))?;
     let version_str = version.to_string();
     let mut versions = versions_by_minecraft_version().unwrap();
+    let version_name = &version_str[1..version_str.len()-1];
     let version = versions
-        .remove(&version_str[1..version_str.len()-1].to_owned())
+        .remove(&version_name.to_owned())
         .ok_or_else(|| syn::Error::new(Span::call_site(), format!("Unknown version {}", version_str).as_str()))?;
     let api = Api::new(version);
     let mut result = Vec::new();
@@ -38,5 +43,7 @@ fn generate_data_impl(input: proc_macro::TokenStream) -> syn::Result<proc_macro2
     // let blocks = generate_blocks(&api)?;
     // println!("{}", blocks);
     result.push(generate_blocks(&api)?);
+    result.push(generate_sounds(version_name)?);
+    result.push(generate_shapes(&api, version_name)?);
     Ok(quote! { #(#result)* })
 }
\ No newline at end of file
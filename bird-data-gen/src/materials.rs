@@ -10,16 +10,16 @@ pub fn generate_materials(api: &Api) -> syn::Result<TokenStream> {
     let mut material_value_ts = Vec::new();
     for (name, ids) in api.materials.materials().unwrap() {
         let material_enum_ident = Ident::new(name.replace(|ch: char| ch == ';' || ch == '/', "_").to_case(Case::Pascal).as_str(), Span::call_site());
-        material_from_name_ts.push(quote! { #name => std::option::Option::Some(Self:: #material_enum_ident)});
+        material_from_name_ts.push(quote! { #name => core::option::Option::Some(Self:: #material_enum_ident)});
         material_name_ts.push(quote! { Self:: #material_enum_ident => #name });
         let mut material_id_to_value_ts = Vec::new();
         for (id, value) in ids {
             let id = id.parse::<i32>().unwrap();
-            material_id_to_value_ts.push(quote! { #id => std::option::Option::Some(#value) });
+            material_id_to_value_ts.push(quote! { #id => core::option::Option::Some(#value) });
         }
         material_value_ts.push(quote! { Self:: #material_enum_ident => match item {
             #(#material_id_to_value_ts,)*
-            _ => std::option::Option::None
+            _ => core::option::Option::None
         }});
         material_enum_ts.push(material_enum_ident);
     }
@@ -31,10 +31,10 @@ pub fn generate_materials(api: &Api) -> syn::Result<TokenStream> {
         pub enum Material { #(#material_enum_ts,)* }
 
         impl Material {
-            pub fn from_name(name: &str) -> std::option::Option<Self> {
+            pub fn from_name(name: &str) -> core::option::Option<Self> {
                 match name {
                     #(#material_from_name_ts,)*
-                    _ => std::option::Option::None
+                    _ => core::option::Option::None
                 }
             }
 
@@ -44,7 +44,7 @@ pub fn generate_materials(api: &Api) -> syn::Result<TokenStream> {
                 }
             }
 
-            pub const fn get_value(&self, item: i32) -> std::option::Option<f32> {
+            pub const fn get_value(&self, item: i32) -> core::option::Option<f32> {
                 match self {
                     #(#material_value_ts,)*
                 }
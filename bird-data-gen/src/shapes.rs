@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use minecraft_data_rs::Api;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use serde_derive::Deserialize;
+use crate::sounds::{data_path, read_json};
+
+const SHAPE_OCCLUDES: u8 = 1;
+const SHAPE_SUFFOCATES: u8 = 2;
+const SHAPE_SPAWNABLE: u8 = 4;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BlockShapeIds {
+    All(u16),
+    /// Shape of each state from the minimal state id
+    PerState(Vec<u16>),
+}
+
+#[derive(Deserialize)]
+struct CollisionShapes {
+    blocks: HashMap<String, BlockShapeIds>,
+    shapes: HashMap<String, Vec<[f64; 6]>>,
+}
+
+fn is_full_cube(boxes: &[[f64; 6]]) -> bool {
+    boxes == [[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]]
+}
+
+/// Mobs are standing on the top face, so it must cover the whole block
+fn has_full_top(boxes: &[[f64; 6]]) -> bool {
+    boxes.iter().any(|shape| shape[0] <= 0.0 && shape[2] <= 0.0 && shape[3] >= 1.0 && shape[4] >= 1.0 && shape[5] >= 1.0)
+}
+
+/// minecraft-data-rs doesn't expose collision shapes so they are read from the data paths of minecraft-data.
+/// Flags are derived from shapes, transparency and names of blocks, because minecraft-data doesn't have them
+pub fn generate_shapes(api: &Api, version: &str) -> syn::Result<TokenStream> {
+    let data_paths: serde_json::Value = read_json(data_path().join("dataPaths.json"))?;
+    let shapes_path = data_paths["pc"][version]["blockCollisionShapes"].as_str()
+        .ok_or_else(|| syn::Error::new(Span::call_site(), format!("No collision shapes for version {}", version)))?;
+    let shapes: CollisionShapes = read_json(data_path().join(shapes_path).join("blockCollisionShapes.json"))?;
+    let shape_count = shapes.shapes.keys().filter_map(|id| id.parse::<usize>().ok()).max().map_or(0, |max| max + 1);
+    let mut shape_boxes = vec![Vec::new(); shape_count];
+    for (id, boxes) in &shapes.shapes {
+        let id = id.parse::<usize>()
+            .map_err(|_| syn::Error::new(Span::call_site(), format!("Invalid shape id {}", id)))?;
+        shape_boxes[id] = boxes.clone();
+    }
+
+    let blocks_array = api.blocks.blocks_array().unwrap();
+    let state_count = blocks_array.iter()
+        .map(|block| block.max_state_id.expect("max state id is none") as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut states = vec![(0u16, 0u8); state_count];
+    for block in &blocks_array {
+        let min_state_id = block.min_state_id.expect("min state id is none") as usize;
+        let max_state_id = block.max_state_id.expect("max state id is none") as usize;
+        let material = block.material.as_deref().unwrap_or("");
+        // Glass and leaves are full blocks which don't suffocate
+        let suffocating = !block.name.ends_with("glass") && !material.contains("leaves");
+        for state in min_state_id..=max_state_id {
+            let shape = match shapes.blocks.get(&block.name) {
+                Some(BlockShapeIds::All(shape)) => *shape,
+                Some(BlockShapeIds::PerState(ids)) => ids.get(state - min_state_id).copied().unwrap_or(0),
+                None => 0,
+            };
+            let boxes = shape_boxes.get(shape as usize).map_or(&[][..], |boxes| boxes.as_slice());
+            let mut flags = 0;
+            if is_full_cube(boxes) && !block.transparent {
+                flags |= SHAPE_OCCLUDES;
+            }
+            if is_full_cube(boxes) && suffocating {
+                flags |= SHAPE_SUFFOCATES;
+            }
+            if has_full_top(boxes) && !block.transparent {
+                flags |= SHAPE_SPAWNABLE;
+            }
+            states[state] = (shape, flags);
+        }
+    }
+
+    let shapes_ts = shape_boxes.iter()
+        .map(|boxes| {
+            let boxes = boxes.iter().map(|shape| quote! { [#(#shape,)*] });
+            quote! { &[#(#boxes,)*] }
+        })
+        .collect::<Vec<TokenStream>>();
+    let states_ts = states.iter()
+        .map(|(shape, flags)| quote! { (#shape, #flags) })
+        .collect::<Vec<TokenStream>>();
+    Ok(quote! {
+        const COLLISION_SHAPES: &[&[[f64; 6]]] = &[#(#shapes_ts,)*];
+        /// Collision shape and flags of block states in the order of state ids
+        const BLOCK_STATE_SHAPES: &[(u16, u8)] = &[#(#states_ts,)*];
+
+        /// Collision shape of a block state with flags which are derived from it
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct BlockShape {
+            /// Boxes are `[min_x, min_y, min_z, max_x, max_y, max_z]` relative to the block
+            pub collision: &'static [[f64; 6]],
+            flags: u8,
+        }
+
+        impl BlockShape {
+            pub fn from_state(state: u32) -> core::option::Option<Self> {
+                let (shape, flags) = *BLOCK_STATE_SHAPES.get(state as usize)?;
+                core::option::Option::Some(Self { collision: COLLISION_SHAPES[shape as usize], flags })
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.collision.is_empty()
+            }
+
+            pub fn is_full_cube(&self) -> bool {
+                self.collision == [[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]]
+            }
+
+            /// Opaque full block which hides faces of neighbours
+            pub fn occludes(&self) -> bool {
+                self.flags & #SHAPE_OCCLUDES != 0
+            }
+
+            /// Entities whose eyes are inside of the block take suffocation damage
+            pub fn suffocates(&self) -> bool {
+                self.flags & #SHAPE_SUFFOCATES != 0
+            }
+
+            /// Mobs can spawn on the top of the block
+            pub fn is_spawnable(&self) -> bool {
+                self.flags & #SHAPE_SPAWNABLE != 0
+            }
+        }
+    })
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+struct Sound {
+    id: u32,
+    name: String,
+}
+
+pub(crate) fn data_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../minecraft-data/data")
+}
+
+pub(crate) fn read_json<T: serde::de::DeserializeOwned>(path: PathBuf) -> syn::Result<T> {
+    let file = std::fs::File::open(&path)
+        .map_err(|err| syn::Error::new(Span::call_site(), format!("Failed to open {}: {}", path.display(), err)))?;
+    serde_json::from_reader(file)
+        .map_err(|err| syn::Error::new(Span::call_site(), format!("Failed to parse {}: {}", path.display(), err)))
+}
+
+/// minecraft-data-rs doesn't expose sounds so they are read from the data paths of minecraft-data
+pub fn generate_sounds(version: &str) -> syn::Result<TokenStream> {
+    let data_paths: serde_json::Value = read_json(data_path().join("dataPaths.json"))?;
+    let sounds_path = data_paths["pc"][version]["sounds"].as_str()
+        .ok_or_else(|| syn::Error::new(Span::call_site(), format!("No sounds for version {}", version)))?;
+    let sounds: Vec<Sound> = read_json(data_path().join(sounds_path).join("sounds.json"))?;
+    let mut sound_enum_ts = Vec::new();
+    let mut sound_from_id_ts = Vec::new();
+    let mut sound_from_name_ts = Vec::new();
+    let mut sound_id_ts = Vec::new();
+    let mut sound_name_ts = Vec::new();
+    for Sound { id, name } in sounds {
+        let sound_enum_ident = Ident::new(name.replace('.', "_").to_case(Case::Pascal).as_str(), Span::call_site());
+        sound_from_id_ts.push(quote! { #id => core::option::Option::Some(Self:: #sound_enum_ident) });
+        sound_from_name_ts.push(quote! { #name => core::option::Option::Some(Self:: #sound_enum_ident) });
+        sound_id_ts.push(quote! { Self:: #sound_enum_ident => #id });
+        sound_name_ts.push(quote! { Self:: #sound_enum_ident => #name });
+        sound_enum_ts.push(sound_enum_ident);
+    }
+    let registry_count = sound_enum_ts.len();
+    Ok(quote! {
+        pub const SOUND_COUNT: usize = #registry_count;
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Sound { #(#sound_enum_ts,)* }
+
+        impl Sound {
+            pub const fn from_id(id: u32) -> core::option::Option<Self> {
+                match id {
+                    #(#sound_from_id_ts,)*
+                    _ => core::option::Option::None
+                }
+            }
+
+            /// Name without the `minecraft:` namespace
+            pub fn from_name(name: &str) -> core::option::Option<Self> {
+                match name {
+                    #(#sound_from_name_ts,)*
+                    _ => core::option::Option::None
+                }
+            }
+
+            pub const fn get_id(&self) -> u32 {
+                match self {
+                    #(#sound_id_ts,)*
+                }
+            }
+
+            pub const fn get_name(&self) -> &'static str {
+                match self {
+                    #(#sound_name_ts,)*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,588 @@
+use crate::noise::{NoiseParameters, NormalNoise, XoroshiroRandom};
+
+/// Climate values are compared as longs like in vanilla
+const QUANTIZATION_FACTOR: f32 = 10000.0;
+/// Constant part of the terrain offset, the spline of the offset is not sampled, so depth is approximate
+const DEPTH_OFFSET: f64 = -0.50375;
+
+pub const TEMPERATURE_NOISE: NoiseParameters = NoiseParameters { first_octave: -10, amplitudes: &[1.5, 0.0, 1.0, 0.0, 0.0, 0.0] };
+pub const VEGETATION_NOISE: NoiseParameters = NoiseParameters { first_octave: -8, amplitudes: &[1.0, 1.0, 0.0, 0.0, 0.0, 0.0] };
+pub const CONTINENTALNESS_NOISE: NoiseParameters = NoiseParameters {
+    first_octave: -9,
+    amplitudes: &[1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 1.0, 1.0, 1.0],
+};
+pub const EROSION_NOISE: NoiseParameters = NoiseParameters { first_octave: -9, amplitudes: &[1.0, 1.0, 0.0, 1.0, 1.0] };
+pub const RIDGE_NOISE: NoiseParameters = NoiseParameters { first_octave: -7, amplitudes: &[1.0, 2.0, 1.0, 0.0, 0.0, 0.0] };
+pub const SHIFT_NOISE: NoiseParameters = NoiseParameters { first_octave: -3, amplitudes: &[1.0, 1.0, 1.0, 0.0] };
+
+pub fn quantize(value: f32) -> i64 {
+    (value * QUANTIZATION_FACTOR) as i64
+}
+
+/// Range of one climate value
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Parameter {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Parameter {
+    pub const FULL_RANGE: Parameter = Parameter { min: -10000, max: 10000 };
+
+    pub fn point(value: f32) -> Self {
+        Self::span(value, value)
+    }
+
+    pub fn span(min: f32, max: f32) -> Self {
+        Self { min: quantize(min), max: quantize(max) }
+    }
+
+    /// From the start of the first range to the end of the second one
+    pub fn join(from: Parameter, to: Parameter) -> Self {
+        Self { min: from.min, max: to.max }
+    }
+
+    pub fn distance(&self, value: i64) -> i64 {
+        match value - self.max {
+            above if above > 0 => above,
+            _ => (self.min - value).max(0),
+        }
+    }
+}
+
+/// Sampled climate of one position
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TargetPoint {
+    pub temperature: i64,
+    pub humidity: i64,
+    pub continentalness: i64,
+    pub erosion: i64,
+    pub depth: i64,
+    pub weirdness: i64,
+}
+
+impl TargetPoint {
+    pub fn new(temperature: f32, humidity: f32, continentalness: f32, erosion: f32, depth: f32, weirdness: f32) -> Self {
+        Self {
+            temperature: quantize(temperature),
+            humidity: quantize(humidity),
+            continentalness: quantize(continentalness),
+            erosion: quantize(erosion),
+            depth: quantize(depth),
+            weirdness: quantize(weirdness),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParameterPoint {
+    pub temperature: Parameter,
+    pub humidity: Parameter,
+    pub continentalness: Parameter,
+    pub erosion: Parameter,
+    pub depth: Parameter,
+    pub weirdness: Parameter,
+    pub offset: i64,
+}
+
+impl ParameterPoint {
+    /// Squared distance to the target, the biome with the lowest fitness is picked
+    pub fn fitness(&self, target: &TargetPoint) -> i64 {
+        let square = |value: i64| value * value;
+        square(self.temperature.distance(target.temperature))
+            + square(self.humidity.distance(target.humidity))
+            + square(self.continentalness.distance(target.continentalness))
+            + square(self.erosion.distance(target.erosion))
+            + square(self.depth.distance(target.depth))
+            + square(self.weirdness.distance(target.weirdness))
+            + square(self.offset)
+    }
+}
+
+/// Multi-noise biome source parameters, biomes are names of [`crate::BiomeData`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BiomeParameters {
+    pub entries: Vec<(ParameterPoint, &'static str)>,
+}
+
+impl BiomeParameters {
+    /// Parameters of `OverworldBiomeBuilder`
+    pub fn overworld() -> Self {
+        let mut builder = OverworldBuilder { entries: Vec::new() };
+        builder.add_off_coast_biomes();
+        builder.add_inland_biomes();
+        builder.add_underground_biomes();
+        Self { entries: builder.entries }
+    }
+
+    /// Entry with the lowest fitness, the first one wins in ties.
+    /// Vanilla searches a tree, which gives the same result except rare ties
+    pub fn find(&self, target: &TargetPoint) -> &'static str {
+        self.entries.iter()
+            .min_by_key(|(point, _)| point.fitness(target))
+            .map_or("plains", |(_, biome)| *biome)
+    }
+}
+
+/// Climate noises of the overworld noise router, they are seeded by the world seed like in vanilla
+#[derive(Clone, Debug)]
+pub struct ClimateSampler {
+    shift: NormalNoise,
+    temperature: NormalNoise,
+    vegetation: NormalNoise,
+    continentalness: NormalNoise,
+    erosion: NormalNoise,
+    ridge: NormalNoise,
+}
+
+impl ClimateSampler {
+    pub fn new(seed: i64) -> Self {
+        let random = XoroshiroRandom::new(seed).fork_positional();
+        let noise = |name: &str, parameters| NormalNoise::new(&mut random.from_hash_of(name), parameters);
+        Self {
+            shift: noise("minecraft:offset", SHIFT_NOISE),
+            temperature: noise("minecraft:temperature", TEMPERATURE_NOISE),
+            vegetation: noise("minecraft:vegetation", VEGETATION_NOISE),
+            continentalness: noise("minecraft:continentalness", CONTINENTALNESS_NOISE),
+            erosion: noise("minecraft:erosion", EROSION_NOISE),
+            ridge: noise("minecraft:ridge", RIDGE_NOISE),
+        }
+    }
+
+    /// Climate at the block position
+    pub fn sample_block(&self, x: i32, y: i32, z: i32) -> TargetPoint {
+        let (x, z) = (x as f64, z as f64);
+        let shift_x = self.shift.get_value(x * 0.25, 0.0, z * 0.25) * 4.0;
+        let shift_z = self.shift.get_value(z * 0.25, x * 0.25, 0.0) * 4.0;
+        let shifted = |noise: &NormalNoise| noise.get_value(x * 0.25 + shift_x, 0.0, z * 0.25 + shift_z) as f32;
+        let gradient = (1.5 - 3.0 * (y as f64 + 64.0) / 384.0).clamp(-1.5, 1.5);
+        TargetPoint::new(
+            shifted(&self.temperature),
+            shifted(&self.vegetation),
+            shifted(&self.continentalness),
+            shifted(&self.erosion),
+            (gradient + DEPTH_OFFSET) as f32,
+            shifted(&self.ridge),
+        )
+    }
+
+    /// Biomes are stored per 4x4x4 cell, so they are sampled at the corner of the cell
+    pub fn sample(&self, quart_x: i32, quart_y: i32, quart_z: i32) -> TargetPoint {
+        self.sample_block(quart_x << 2, quart_y << 2, quart_z << 2)
+    }
+}
+
+const OCEANS: [[&'static str; 5]; 2] = [
+    ["deep_frozen_ocean", "deep_cold_ocean", "deep_ocean", "deep_lukewarm_ocean", "warm_ocean"],
+    ["frozen_ocean", "cold_ocean", "ocean", "lukewarm_ocean", "warm_ocean"],
+];
+const MIDDLE_BIOMES: [[&'static str; 5]; 5] = [
+    ["snowy_plains", "snowy_plains", "snowy_plains", "snowy_taiga", "taiga"],
+    ["plains", "plains", "forest", "taiga", "old_growth_spruce_taiga"],
+    ["flower_forest", "plains", "forest", "birch_forest", "dark_forest"],
+    ["savanna", "savanna", "forest", "jungle", "jungle"],
+    ["desert", "desert", "desert", "desert", "desert"],
+];
+const MIDDLE_BIOMES_VARIANT: [[Option<&'static str>; 5]; 5] = [
+    [Some("ice_spikes"), None, Some("snowy_taiga"), None, None],
+    [None, None, None, None, Some("old_growth_pine_taiga")],
+    [Some("sunflower_plains"), None, None, Some("old_growth_birch_forest"), None],
+    [None, None, Some("plains"), Some("sparse_jungle"), Some("bamboo_jungle")],
+    [None, None, None, None, None],
+];
+const PLATEAU_BIOMES: [[&'static str; 5]; 5] = [
+    ["snowy_plains", "snowy_plains", "snowy_plains", "snowy_taiga", "snowy_taiga"],
+    ["meadow", "meadow", "forest", "taiga", "old_growth_spruce_taiga"],
+    ["meadow", "meadow", "meadow", "meadow", "dark_forest"],
+    ["savanna_plateau", "savanna_plateau", "forest", "forest", "jungle"],
+    ["badlands", "badlands", "badlands", "wooded_badlands", "wooded_badlands"],
+];
+const PLATEAU_BIOMES_VARIANT: [[Option<&'static str>; 5]; 5] = [
+    [Some("ice_spikes"), None, None, None, None],
+    [None, None, Some("meadow"), Some("meadow"), Some("old_growth_pine_taiga")],
+    [None, None, Some("forest"), Some("birch_forest"), None],
+    [None, None, None, None, None],
+    [Some("eroded_badlands"), Some("eroded_badlands"), None, None, None],
+];
+const SHATTERED_BIOMES: [[Option<&'static str>; 5]; 5] = [
+    [Some("windswept_gravelly_hills"), Some("windswept_gravelly_hills"), Some("windswept_hills"), Some("windswept_forest"), Some("windswept_forest")],
+    [Some("windswept_gravelly_hills"), Some("windswept_gravelly_hills"), Some("windswept_hills"), Some("windswept_forest"), Some("windswept_forest")],
+    [Some("windswept_hills"), Some("windswept_hills"), Some("windswept_hills"), Some("windswept_forest"), Some("windswept_forest")],
+    [None, None, None, None, None],
+    [None, None, None, None, None],
+];
+
+fn temperatures() -> [Parameter; 5] {
+    [
+        Parameter::span(-1.0, -0.45),
+        Parameter::span(-0.45, -0.15),
+        Parameter::span(-0.15, 0.2),
+        Parameter::span(0.2, 0.55),
+        Parameter::span(0.55, 1.0),
+    ]
+}
+
+fn humidities() -> [Parameter; 5] {
+    [
+        Parameter::span(-1.0, -0.35),
+        Parameter::span(-0.35, -0.1),
+        Parameter::span(-0.1, 0.1),
+        Parameter::span(0.1, 0.3),
+        Parameter::span(0.3, 1.0),
+    ]
+}
+
+fn erosions() -> [Parameter; 7] {
+    [
+        Parameter::span(-1.0, -0.78),
+        Parameter::span(-0.78, -0.375),
+        Parameter::span(-0.375, -0.2225),
+        Parameter::span(-0.2225, 0.05),
+        Parameter::span(0.05, 0.45),
+        Parameter::span(0.45, 0.55),
+        Parameter::span(0.55, 1.0),
+    ]
+}
+
+/// Continentalness ranges of `OverworldBiomeBuilder`
+struct Continentalness;
+
+impl Continentalness {
+    fn mushroom_fields() -> Parameter { Parameter::span(-1.2, -1.05) }
+    fn deep_ocean() -> Parameter { Parameter::span(-1.05, -0.455) }
+    fn ocean() -> Parameter { Parameter::span(-0.455, -0.19) }
+    fn coast() -> Parameter { Parameter::span(-0.19, -0.11) }
+    fn inland() -> Parameter { Parameter::span(-0.11, 0.55) }
+    fn near_inland() -> Parameter { Parameter::span(-0.11, 0.03) }
+    fn mid_inland() -> Parameter { Parameter::span(0.03, 0.3) }
+    fn far_inland() -> Parameter { Parameter::span(0.3, 1.0) }
+}
+
+/// Port of `OverworldBiomeBuilder`, `t` and `h` are indices of the temperature and the humidity
+struct OverworldBuilder {
+    entries: Vec<(ParameterPoint, &'static str)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+impl OverworldBuilder {
+    fn add(&mut self, t: Parameter, h: Parameter, c: Parameter, e: Parameter, d: Parameter, w: Parameter, biome: &'static str) {
+        let point = ParameterPoint {
+            temperature: t,
+            humidity: h,
+            continentalness: c,
+            erosion: e,
+            depth: d,
+            weirdness: w,
+            offset: 0,
+        };
+        self.entries.push((point, biome));
+    }
+
+    /// Surface biomes are added at the surface and at the bottom of the surface layer
+    fn add_surface(&mut self, t: Parameter, h: Parameter, c: Parameter, e: Parameter, w: Parameter, biome: &'static str) {
+        self.add(t, h, c, e, Parameter::point(0.0), w, biome);
+        self.add(t, h, c, e, Parameter::point(1.0), w, biome);
+    }
+
+    fn add_off_coast_biomes(&mut self) {
+        let full = Parameter::FULL_RANGE;
+        self.add_surface(full, full, Continentalness::mushroom_fields(), full, full, "mushroom_fields");
+        for (index, temperature) in temperatures().into_iter().enumerate() {
+            self.add_surface(temperature, full, Continentalness::deep_ocean(), full, full, OCEANS[0][index]);
+            self.add_surface(temperature, full, Continentalness::ocean(), full, full, OCEANS[1][index]);
+        }
+    }
+
+    fn add_inland_biomes(&mut self) {
+        self.add_mid_slice(Parameter::span(-1.0, -0.93333334));
+        self.add_high_slice(Parameter::span(-0.93333334, -0.7666667));
+        self.add_peaks(Parameter::span(-0.7666667, -0.56666666));
+        self.add_high_slice(Parameter::span(-0.56666666, -0.4));
+        self.add_mid_slice(Parameter::span(-0.4, -0.26666668));
+        self.add_low_slice(Parameter::span(-0.26666668, -0.05));
+        self.add_valleys(Parameter::span(-0.05, 0.05));
+        self.add_low_slice(Parameter::span(0.05, 0.26666668));
+        self.add_mid_slice(Parameter::span(0.26666668, 0.4));
+        self.add_high_slice(Parameter::span(0.4, 0.56666666));
+        self.add_peaks(Parameter::span(0.56666666, 0.7666667));
+        self.add_high_slice(Parameter::span(0.7666667, 0.93333334));
+        self.add_mid_slice(Parameter::span(0.93333334, 1.0));
+    }
+
+    fn add_peaks(&mut self, w: Parameter) {
+        let (e, coast, near, mid, far) = (erosions(), Continentalness::coast(), Continentalness::near_inland(),
+                                          Continentalness::mid_inland(), Continentalness::far_inland());
+        for (i, t) in temperatures().into_iter().enumerate() {
+            for (j, h) in humidities().into_iter().enumerate() {
+                let middle = pick_middle_biome(i, j, w);
+                let middle_or_badlands = pick_middle_biome_or_badlands_if_hot(i, j, w);
+                let middle_or_badlands_or_slope = pick_middle_biome_or_badlands_if_hot_or_slope_if_cold(i, j, w);
+                let plateau = pick_plateau_biome(i, j, w);
+                let shattered = pick_shattered_biome(i, j, w);
+                let shattered_or_savanna = maybe_pick_windswept_savanna_biome(i, j, w, shattered);
+                let peak = pick_peak_biome(i, j, w);
+                self.add_surface(t, h, Parameter::join(coast, far), e[0], w, peak);
+                self.add_surface(t, h, Parameter::join(coast, near), e[1], w, middle_or_badlands_or_slope);
+                self.add_surface(t, h, Parameter::join(mid, far), e[1], w, peak);
+                self.add_surface(t, h, Parameter::join(coast, near), Parameter::join(e[2], e[3]), w, middle);
+                self.add_surface(t, h, Parameter::join(mid, far), e[2], w, plateau);
+                self.add_surface(t, h, mid, e[3], w, middle_or_badlands);
+                self.add_surface(t, h, far, e[3], w, plateau);
+                self.add_surface(t, h, Parameter::join(coast, far), e[4], w, middle);
+                self.add_surface(t, h, Parameter::join(coast, near), e[5], w, shattered_or_savanna);
+                self.add_surface(t, h, Parameter::join(mid, far), e[5], w, shattered);
+                self.add_surface(t, h, Parameter::join(coast, far), e[6], w, middle);
+            }
+        }
+    }
+
+    fn add_high_slice(&mut self, w: Parameter) {
+        let (e, coast, near, mid, far) = (erosions(), Continentalness::coast(), Continentalness::near_inland(),
+                                          Continentalness::mid_inland(), Continentalness::far_inland());
+        for (i, t) in temperatures().into_iter().enumerate() {
+            for (j, h) in humidities().into_iter().enumerate() {
+                let middle = pick_middle_biome(i, j, w);
+                let middle_or_badlands = pick_middle_biome_or_badlands_if_hot(i, j, w);
+                let middle_or_badlands_or_slope = pick_middle_biome_or_badlands_if_hot_or_slope_if_cold(i, j, w);
+                let plateau = pick_plateau_biome(i, j, w);
+                let shattered = pick_shattered_biome(i, j, w);
+                let middle_or_savanna = maybe_pick_windswept_savanna_biome(i, j, w, middle);
+                let slope = pick_slope_biome(i, j, w);
+                let peak = pick_peak_biome(i, j, w);
+                self.add_surface(t, h, coast, Parameter::join(e[0], e[1]), w, middle);
+                self.add_surface(t, h, near, e[0], w, slope);
+                self.add_surface(t, h, Parameter::join(mid, far), e[0], w, peak);
+                self.add_surface(t, h, near, e[1], w, middle_or_badlands_or_slope);
+                self.add_surface(t, h, Parameter::join(mid, far), e[1], w, slope);
+                self.add_surface(t, h, Parameter::join(coast, near), Parameter::join(e[2], e[3]), w, middle);
+                self.add_surface(t, h, Parameter::join(mid, far), e[2], w, plateau);
+                self.add_surface(t, h, mid, e[3], w, middle_or_badlands);
+                self.add_surface(t, h, far, e[3], w, plateau);
+                self.add_surface(t, h, Parameter::join(coast, far), e[4], w, middle);
+                self.add_surface(t, h, Parameter::join(coast, near), e[5], w, middle_or_savanna);
+                self.add_surface(t, h, Parameter::join(mid, far), e[5], w, shattered);
+                self.add_surface(t, h, Parameter::join(coast, far), e[6], w, middle);
+            }
+        }
+    }
+
+    /// Stony shores and swamps which are shared by the mid and the low slices
+    fn add_shores_and_swamps(&mut self, w: Parameter) {
+        let (full, temperatures, e) = (Parameter::FULL_RANGE, temperatures(), erosions());
+        let inland = Parameter::join(Continentalness::near_inland(), Continentalness::far_inland());
+        self.add_surface(full, full, Continentalness::coast(), Parameter::join(e[0], e[2]), w, "stony_shore");
+        self.add_surface(Parameter::join(temperatures[1], temperatures[2]), full, inland, e[6], w, "swamp");
+        self.add_surface(Parameter::join(temperatures[3], temperatures[4]), full, inland, e[6], w, "mangrove_swamp");
+    }
+
+    fn add_mid_slice(&mut self, w: Parameter) {
+        self.add_shores_and_swamps(w);
+        let (e, coast, near, mid, far) = (erosions(), Continentalness::coast(), Continentalness::near_inland(),
+                                          Continentalness::mid_inland(), Continentalness::far_inland());
+        for (i, t) in temperatures().into_iter().enumerate() {
+            for (j, h) in humidities().into_iter().enumerate() {
+                let middle = pick_middle_biome(i, j, w);
+                let middle_or_badlands = pick_middle_biome_or_badlands_if_hot(i, j, w);
+                let middle_or_badlands_or_slope = pick_middle_biome_or_badlands_if_hot_or_slope_if_cold(i, j, w);
+                let shattered = pick_shattered_biome(i, j, w);
+                let plateau = pick_plateau_biome(i, j, w);
+                let beach = pick_beach_biome(i);
+                let middle_or_savanna = maybe_pick_windswept_savanna_biome(i, j, w, middle);
+                let shattered_coast = pick_shattered_coast_biome(i, j, w);
+                let slope = pick_slope_biome(i, j, w);
+                self.add_surface(t, h, Parameter::join(near, far), e[0], w, slope);
+                self.add_surface(t, h, Parameter::join(near, mid), e[1], w, middle_or_badlands_or_slope);
+                self.add_surface(t, h, far, e[1], w, if i == 0 { slope } else { plateau });
+                self.add_surface(t, h, near, e[2], w, middle);
+                self.add_surface(t, h, mid, e[2], w, middle_or_badlands);
+                self.add_surface(t, h, far, e[2], w, plateau);
+                self.add_surface(t, h, Parameter::join(coast, near), e[3], w, middle);
+                self.add_surface(t, h, Parameter::join(mid, far), e[3], w, middle_or_badlands);
+                if w.max < 0 {
+                    self.add_surface(t, h, coast, e[4], w, beach);
+                    self.add_surface(t, h, Parameter::join(near, far), e[4], w, middle);
+                } else {
+                    self.add_surface(t, h, Parameter::join(coast, far), e[4], w, middle);
+                }
+                self.add_surface(t, h, coast, e[5], w, shattered_coast);
+                self.add_surface(t, h, near, e[5], w, middle_or_savanna);
+                self.add_surface(t, h, Parameter::join(mid, far), e[5], w, shattered);
+                self.add_surface(t, h, coast, e[6], w, if w.max < 0 { beach } else { middle });
+                if i == 0 {
+                    self.add_surface(t, h, Parameter::join(near, far), e[6], w, middle);
+                }
+            }
+        }
+    }
+
+    fn add_low_slice(&mut self, w: Parameter) {
+        self.add_shores_and_swamps(w);
+        let (e, coast, near, mid, far) = (erosions(), Continentalness::coast(), Continentalness::near_inland(),
+                                          Continentalness::mid_inland(), Continentalness::far_inland());
+        for (i, t) in temperatures().into_iter().enumerate() {
+            for (j, h) in humidities().into_iter().enumerate() {
+                let middle = pick_middle_biome(i, j, w);
+                let middle_or_badlands = pick_middle_biome_or_badlands_if_hot(i, j, w);
+                let middle_or_badlands_or_slope = pick_middle_biome_or_badlands_if_hot_or_slope_if_cold(i, j, w);
+                let beach = pick_beach_biome(i);
+                let middle_or_savanna = maybe_pick_windswept_savanna_biome(i, j, w, middle);
+                let shattered_coast = pick_shattered_coast_biome(i, j, w);
+                self.add_surface(t, h, near, Parameter::join(e[0], e[1]), w, middle_or_badlands);
+                self.add_surface(t, h, Parameter::join(mid, far), Parameter::join(e[0], e[1]), w, middle_or_badlands_or_slope);
+                self.add_surface(t, h, near, Parameter::join(e[2], e[3]), w, middle);
+                self.add_surface(t, h, Parameter::join(mid, far), Parameter::join(e[2], e[3]), w, middle_or_badlands);
+                self.add_surface(t, h, coast, Parameter::join(e[3], e[4]), w, beach);
+                self.add_surface(t, h, Parameter::join(near, far), e[4], w, middle);
+                self.add_surface(t, h, coast, e[5], w, shattered_coast);
+                self.add_surface(t, h, near, e[5], w, middle_or_savanna);
+                self.add_surface(t, h, Parameter::join(mid, far), e[5], w, middle);
+                self.add_surface(t, h, coast, e[6], w, beach);
+                if i == 0 {
+                    self.add_surface(t, h, Parameter::join(near, far), e[6], w, middle);
+                }
+            }
+        }
+    }
+
+    fn add_valleys(&mut self, w: Parameter) {
+        let (full, temperatures, e) = (Parameter::FULL_RANGE, temperatures(), erosions());
+        let (frozen, unfrozen) = (temperatures[0], Parameter::join(temperatures[1], temperatures[4]));
+        let (coast, near, far) = (Continentalness::coast(), Continentalness::near_inland(), Continentalness::far_inland());
+        let inland = Parameter::join(Continentalness::inland(), far);
+        self.add_surface(frozen, full, coast, Parameter::join(e[0], e[1]), w, if w.max < 0 { "stony_shore" } else { "frozen_river" });
+        self.add_surface(unfrozen, full, coast, Parameter::join(e[0], e[1]), w, if w.max < 0 { "stony_shore" } else { "river" });
+        self.add_surface(frozen, full, near, Parameter::join(e[0], e[1]), w, "frozen_river");
+        self.add_surface(unfrozen, full, near, Parameter::join(e[0], e[1]), w, "river");
+        self.add_surface(frozen, full, Parameter::join(coast, far), Parameter::join(e[2], e[5]), w, "frozen_river");
+        self.add_surface(unfrozen, full, Parameter::join(coast, far), Parameter::join(e[2], e[5]), w, "river");
+        self.add_surface(frozen, full, coast, e[6], w, "frozen_river");
+        self.add_surface(unfrozen, full, coast, e[6], w, "river");
+        self.add_surface(Parameter::join(temperatures[1], temperatures[2]), full, inland, e[6], w, "swamp");
+        self.add_surface(Parameter::join(temperatures[3], temperatures[4]), full, inland, e[6], w, "mangrove_swamp");
+        self.add_surface(frozen, full, inland, e[6], w, "frozen_river");
+        let mid_to_far = Parameter::join(Continentalness::mid_inland(), far);
+        for (i, t) in temperatures.into_iter().enumerate() {
+            for (j, h) in humidities().into_iter().enumerate() {
+                self.add_surface(t, h, mid_to_far, Parameter::join(e[0], e[1]), w, pick_middle_biome_or_badlands_if_hot(i, j, w));
+            }
+        }
+    }
+
+    fn add_underground_biomes(&mut self) {
+        let (full, underground) = (Parameter::FULL_RANGE, Parameter::span(0.2, 0.9));
+        let e = erosions();
+        self.add(full, full, Parameter::span(0.8, 1.0), full, underground, full, "dripstone_caves");
+        self.add(full, Parameter::span(0.7, 1.0), full, full, underground, full, "lush_caves");
+        self.add(full, full, full, Parameter::join(e[0], e[1]), Parameter::point(1.1), full, "deep_dark");
+    }
+}
+
+fn pick_middle_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    match w.max < 0 {
+        true => MIDDLE_BIOMES[i][j],
+        false => MIDDLE_BIOMES_VARIANT[i][j].unwrap_or(MIDDLE_BIOMES[i][j]),
+    }
+}
+
+fn pick_middle_biome_or_badlands_if_hot(i: usize, j: usize, w: Parameter) -> &'static str {
+    match i {
+        4 => pick_badlands_biome(j, w),
+        _ => pick_middle_biome(i, j, w),
+    }
+}
+
+fn pick_middle_biome_or_badlands_if_hot_or_slope_if_cold(i: usize, j: usize, w: Parameter) -> &'static str {
+    match i {
+        0 => pick_slope_biome(i, j, w),
+        _ => pick_middle_biome_or_badlands_if_hot(i, j, w),
+    }
+}
+
+fn maybe_pick_windswept_savanna_biome(i: usize, j: usize, w: Parameter, fallback: &'static str) -> &'static str {
+    match i > 1 && j < 4 && w.max >= 0 {
+        true => "windswept_savanna",
+        false => fallback,
+    }
+}
+
+fn pick_shattered_coast_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    let beach_or_middle = match w.max >= 0 {
+        true => pick_middle_biome(i, j, w),
+        false => pick_beach_biome(i),
+    };
+    maybe_pick_windswept_savanna_biome(i, j, w, beach_or_middle)
+}
+
+fn pick_beach_biome(i: usize) -> &'static str {
+    match i {
+        0 => "snowy_beach",
+        4 => "desert",
+        _ => "beach",
+    }
+}
+
+fn pick_badlands_biome(j: usize, w: Parameter) -> &'static str {
+    match j {
+        0 | 1 if w.max < 0 => "eroded_badlands",
+        0..=2 => "badlands",
+        _ => "wooded_badlands",
+    }
+}
+
+fn pick_plateau_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    match w.max < 0 {
+        true => PLATEAU_BIOMES[i][j],
+        false => PLATEAU_BIOMES_VARIANT[i][j].unwrap_or(PLATEAU_BIOMES[i][j]),
+    }
+}
+
+fn pick_peak_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    match i {
+        0..=2 if w.max < 0 => "jagged_peaks",
+        0..=2 => "frozen_peaks",
+        3 => "stony_peaks",
+        _ => pick_badlands_biome(j, w),
+    }
+}
+
+fn pick_slope_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    match (i, j) {
+        (3.., _) => pick_plateau_biome(i, j, w),
+        (_, 0 | 1) => "snowy_slopes",
+        _ => "grove",
+    }
+}
+
+fn pick_shattered_biome(i: usize, j: usize, w: Parameter) -> &'static str {
+    SHATTERED_BIOMES[i][j].unwrap_or_else(|| pick_middle_biome(i, j, w))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BiomeData;
+    use super::*;
+
+    #[test]
+    fn overworld_parameters_test() {
+        let parameters = BiomeParameters::overworld();
+        assert!(parameters.entries.iter().all(|(_, biome)| BiomeData::from_name(biome).is_some()));
+        assert_eq!(parameters.find(&TargetPoint::new(0.0, 0.0, -1.1, 0.0, 0.0, 0.0)), "mushroom_fields");
+        assert_eq!(parameters.find(&TargetPoint::new(-0.8, 0.0, -0.6, 0.0, 0.0, 0.0)), "deep_frozen_ocean");
+        assert_eq!(parameters.find(&TargetPoint::new(0.8, 0.0, 0.5, 0.2, 0.0, -0.3)), "desert");
+        assert_eq!(parameters.find(&TargetPoint::new(0.0, 0.0, 0.0, 0.0, 1.1, 0.0)), "deep_dark");
+    }
+
+    #[test]
+    fn climate_sampler_test() {
+        let sampler = ClimateSampler::new(42);
+        assert_eq!(sampler.sample(10, 16, -10), ClimateSampler::new(42).sample(10, 16, -10));
+        assert_ne!(sampler.sample(100, 16, 100), ClimateSampler::new(43).sample(100, 16, 100));
+        let max = quantize(sampler.temperature.max_value() as f32);
+        for x in -50..50 {
+            let target = sampler.sample(x * 64, 16, 0);
+            assert!(target.temperature.abs() <= max);
+        }
+        // Depth grows downwards, underground biomes are below the surface
+        assert!(sampler.sample(0, -8, 0).depth > sampler.sample(0, 16, 0).depth);
+    }
+}
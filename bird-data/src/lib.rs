@@ -1,5 +1,13 @@
+// World generation needs float functions of std, the generated data is usable without it
+#![cfg_attr(not(feature = "std"), no_std)]
+
 use bird_data_gen::generate_data;
 
+#[cfg(feature = "std")]
+pub mod noise;
+#[cfg(feature = "std")]
+pub mod climate;
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum WorldDimension {
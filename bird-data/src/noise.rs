@@ -0,0 +1,265 @@
+use md5::{Digest, Md5};
+
+const SILVER_RATIO_64: u64 = 0x6A09E667F3BCC909;
+const GOLDEN_RATIO_64: u64 = 0x9E3779B97F4A7C15;
+const DOUBLE_UNIT: f64 = 1.0 / (1u64 << 53) as f64;
+/// Inputs of octaves are wrapped by this to keep the precision like in vanilla
+const ROUND_OFF: f64 = 33554432.0;
+const NORMAL_NOISE_INPUT_FACTOR: f64 = 1.0181268882175227;
+const GRADIENT: [[i8; 3]; 16] = [
+    [1, 1, 0], [-1, 1, 0], [1, -1, 0], [-1, -1, 0], [1, 0, 1], [-1, 0, 1], [1, 0, -1], [-1, 0, -1],
+    [0, 1, 1], [0, -1, 1], [0, 1, -1], [0, -1, -1], [1, 1, 0], [0, -1, 1], [-1, 1, 0], [0, -1, -1],
+];
+
+fn mix_stafford13(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+    value ^ (value >> 31)
+}
+
+/// Xoroshiro128++ random of vanilla world generation
+#[derive(Clone, Debug)]
+pub struct XoroshiroRandom {
+    lo: u64,
+    hi: u64,
+}
+
+impl XoroshiroRandom {
+    /// Seed is upgraded to 128 bits like vanilla does it
+    pub fn new(seed: i64) -> Self {
+        let lo = seed as u64 ^ SILVER_RATIO_64;
+        Self::from_parts(mix_stafford13(lo), mix_stafford13(lo.wrapping_add(GOLDEN_RATIO_64)))
+    }
+
+    pub fn from_parts(lo: u64, hi: u64) -> Self {
+        match lo | hi {
+            0 => Self { lo: GOLDEN_RATIO_64, hi: SILVER_RATIO_64 },
+            _ => Self { lo, hi },
+        }
+    }
+
+    pub fn next_long(&mut self) -> i64 {
+        let (lo, mut hi) = (self.lo, self.hi);
+        let result = lo.wrapping_add(hi).rotate_left(17).wrapping_add(lo);
+        hi ^= lo;
+        self.lo = lo.rotate_left(49) ^ hi ^ (hi << 21);
+        self.hi = hi.rotate_left(28);
+        result as i64
+    }
+
+    pub fn next_int(&mut self) -> i32 {
+        self.next_long() as i32
+    }
+
+    /// Lemire's method like `XoroshiroRandomSource.nextInt`
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        let bound = bound as u64;
+        let mut product = self.next_int() as u32 as u64 * bound;
+        if product & 0xFFFF_FFFF < bound {
+            let threshold = (bound as u32).wrapping_neg() as u64 % bound;
+            while product & 0xFFFF_FFFF < threshold {
+                product = self.next_int() as u32 as u64 * bound;
+            }
+        }
+        (product >> 32) as i32
+    }
+
+    pub fn next_double(&mut self) -> f64 {
+        (self.next_long() as u64 >> 11) as f64 * DOUBLE_UNIT
+    }
+
+    pub fn fork_positional(&mut self) -> PositionalRandom {
+        PositionalRandom { lo: self.next_long() as u64, hi: self.next_long() as u64 }
+    }
+}
+
+/// Factory of randoms which are seeded by names, like `minecraft:temperature`
+#[derive(Clone, Copy, Debug)]
+pub struct PositionalRandom {
+    lo: u64,
+    hi: u64,
+}
+
+impl PositionalRandom {
+    pub fn from_hash_of(&self, name: &str) -> XoroshiroRandom {
+        let hash = Md5::digest(name.as_bytes());
+        let lo = u64::from_be_bytes(hash[..8].try_into().unwrap());
+        let hi = u64::from_be_bytes(hash[8..].try_into().unwrap());
+        XoroshiroRandom::from_parts(lo ^ self.lo, hi ^ self.hi)
+    }
+}
+
+fn smoothstep(value: f64) -> f64 {
+    value * value * value * (value * (value * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(delta: f64, start: f64, end: f64) -> f64 {
+    start + delta * (end - start)
+}
+
+fn grad_dot(hash: i32, x: f64, y: f64, z: f64) -> f64 {
+    let gradient = GRADIENT[(hash & 15) as usize];
+    gradient[0] as f64 * x + gradient[1] as f64 * y + gradient[2] as f64 * z
+}
+
+/// Perlin noise of one octave
+#[derive(Clone, Debug)]
+pub struct ImprovedNoise {
+    origin: [f64; 3],
+    permutation: [u8; 256],
+}
+
+impl ImprovedNoise {
+    pub fn new(random: &mut XoroshiroRandom) -> Self {
+        let origin = [random.next_double() * 256.0, random.next_double() * 256.0, random.next_double() * 256.0];
+        let mut permutation = [0u8; 256];
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = index as u8;
+        }
+        for index in 0..256 {
+            let offset = random.next_int_bounded(256 - index as i32) as usize;
+            permutation.swap(index, index + offset);
+        }
+        Self { origin, permutation }
+    }
+
+    fn hash(&self, value: i32) -> i32 {
+        self.permutation[(value & 255) as usize] as i32
+    }
+
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.origin[0], y + self.origin[1], z + self.origin[2]);
+        let (grid_x, grid_y, grid_z) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (x, y, z) = (x - grid_x as f64, y - grid_y as f64, z - grid_z as f64);
+        let first = self.hash(grid_x);
+        let second = self.hash(grid_x.wrapping_add(1));
+        let first_low = self.hash(first.wrapping_add(grid_y));
+        let first_high = self.hash(first.wrapping_add(grid_y).wrapping_add(1));
+        let second_low = self.hash(second.wrapping_add(grid_y));
+        let second_high = self.hash(second.wrapping_add(grid_y).wrapping_add(1));
+        let corner = |hash: i32, offset: i32, dx: f64, dy: f64, dz: f64|
+            grad_dot(self.hash(hash.wrapping_add(grid_z).wrapping_add(offset)), dx, dy, dz);
+        let d000 = corner(first_low, 0, x, y, z);
+        let d100 = corner(second_low, 0, x - 1.0, y, z);
+        let d010 = corner(first_high, 0, x, y - 1.0, z);
+        let d110 = corner(second_high, 0, x - 1.0, y - 1.0, z);
+        let d001 = corner(first_low, 1, x, y, z - 1.0);
+        let d101 = corner(second_low, 1, x - 1.0, y, z - 1.0);
+        let d011 = corner(first_high, 1, x, y - 1.0, z - 1.0);
+        let d111 = corner(second_high, 1, x - 1.0, y - 1.0, z - 1.0);
+        let (sx, sy, sz) = (smoothstep(x), smoothstep(y), smoothstep(z));
+        lerp(
+            sz,
+            lerp(sy, lerp(sx, d000, d100), lerp(sx, d010, d110)),
+            lerp(sy, lerp(sx, d001, d101), lerp(sx, d011, d111)),
+        )
+    }
+}
+
+/// Octaves of the `firstOctave` and `amplitudes` of the vanilla noise parameters
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NoiseParameters {
+    pub first_octave: i32,
+    pub amplitudes: &'static [f64],
+}
+
+#[derive(Clone, Debug)]
+pub struct PerlinNoise {
+    octaves: Vec<Option<ImprovedNoise>>,
+    amplitudes: &'static [f64],
+    lowest_input_factor: f64,
+    lowest_value_factor: f64,
+}
+
+fn wrap(value: f64) -> f64 {
+    value - (value / ROUND_OFF + 0.5).floor() * ROUND_OFF
+}
+
+impl PerlinNoise {
+    pub fn new(random: &mut XoroshiroRandom, parameters: NoiseParameters) -> Self {
+        let positional = random.fork_positional();
+        let octaves = parameters.amplitudes.iter()
+            .enumerate()
+            .map(|(index, amplitude)| match *amplitude != 0.0 {
+                true => Some(ImprovedNoise::new(&mut positional.from_hash_of(&format!(
+                    "octave_{}", parameters.first_octave + index as i32,
+                )))),
+                false => None,
+            })
+            .collect();
+        let count = parameters.amplitudes.len() as i32;
+        Self {
+            octaves,
+            amplitudes: parameters.amplitudes,
+            lowest_input_factor: 2f64.powi(parameters.first_octave),
+            lowest_value_factor: 2f64.powi(count - 1) / (2f64.powi(count) - 1.0),
+        }
+    }
+
+    /// Highest absolute value of the noise
+    pub fn max_value(&self) -> f64 {
+        let mut value_factor = self.lowest_value_factor;
+        let mut result = 0.0;
+        for (octave, amplitude) in self.octaves.iter().zip(self.amplitudes) {
+            if octave.is_some() {
+                result += amplitude * 2.0 * value_factor;
+            }
+            value_factor /= 2.0;
+        }
+        result
+    }
+
+    pub fn get_value(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut input_factor = self.lowest_input_factor;
+        let mut value_factor = self.lowest_value_factor;
+        let mut result = 0.0;
+        for (octave, amplitude) in self.octaves.iter().zip(self.amplitudes) {
+            if let Some(octave) = octave {
+                let noise = octave.noise(wrap(x * input_factor), wrap(y * input_factor), wrap(z * input_factor));
+                result += amplitude * noise * value_factor;
+            }
+            input_factor *= 2.0;
+            value_factor /= 2.0;
+        }
+        result
+    }
+}
+
+/// Sum of two perlin noises which is normalized like `NormalNoise` of vanilla
+#[derive(Clone, Debug)]
+pub struct NormalNoise {
+    first: PerlinNoise,
+    second: PerlinNoise,
+    value_factor: f64,
+}
+
+impl NormalNoise {
+    pub fn new(random: &mut XoroshiroRandom, parameters: NoiseParameters) -> Self {
+        let first = PerlinNoise::new(random, parameters);
+        let second = PerlinNoise::new(random, parameters);
+        let octaves: Vec<_> = parameters.amplitudes.iter()
+            .enumerate()
+            .filter(|(_, amplitude)| **amplitude != 0.0)
+            .map(|(index, _)| index)
+            .collect();
+        let span = match (octaves.first(), octaves.last()) {
+            (Some(first), Some(last)) => (last - first) as f64,
+            _ => 0.0,
+        };
+        let expected_deviation = 0.1 * (1.0 + 1.0 / (span + 1.0));
+        Self { first, second, value_factor: 1.0 / 6.0 / expected_deviation }
+    }
+
+    pub fn max_value(&self) -> f64 {
+        (self.first.max_value() + self.second.max_value()) * self.value_factor
+    }
+
+    pub fn get_value(&self, x: f64, y: f64, z: f64) -> f64 {
+        let second = self.second.get_value(
+            x * NORMAL_NOISE_INPUT_FACTOR,
+            y * NORMAL_NOISE_INPUT_FACTOR,
+            z * NORMAL_NOISE_INPUT_FACTOR,
+        );
+        (self.first.get_value(x, y, z) + second) * self.value_factor
+    }
+}
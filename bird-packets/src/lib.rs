@@ -0,0 +1,10 @@
+#![feature(generic_const_exprs)]
+// Packet structs only need alloc, so they are shared with plugins and proxies which don't have std
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod nbt;
+mod packets;
+
+pub use packets::*;
@@ -1,6 +1,9 @@
-use std::borrow::Cow;
-use std::collections::HashMap;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use uuid::Uuid;
 use bird_protocol::{anyhow, ProtocolCursor, ProtocolError, ProtocolReadable, ProtocolResult, ProtocolWritable, ProtocolWriter};
+use bird_protocol::nbt::{NBT_TAG_INT_ARRAY, NbtTag, from_java_utf8, write_nbt_str};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum NbtElement<'a> {
@@ -14,7 +17,7 @@ pub enum NbtElement<'a> {
     ByteArray(&'a [u8]),
     String(Cow<'a, str>),
     List(Vec<NbtElement<'a>>),
-    Compound(HashMap<Cow<'a, str>, NbtElement<'a>>),
+    Compound(BTreeMap<Cow<'a, str>, NbtElement<'a>>),
     IntArray(&'a [u8]), // in little endian
     LongArray(&'a [u8]), // in little endian
 }
@@ -40,8 +43,7 @@ pub fn read_named_nbt_tag<'a, C: ProtocolCursor<'a>>(name: &str, cursor: &mut C)
 
 pub fn read_nbt_string<'a, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Cow<'a, str>> {
     let length = u16::read(cursor)?;
-    cesu8::from_java_cesu8(cursor.take_bytes(length as usize)?)
-        .map_err(|err| ProtocolError::Any(err.into()))
+    Ok(from_java_utf8(cursor.take_bytes(length as usize)?)?)
 }
 
 pub fn read_nbt_tag<'a, C: ProtocolCursor<'a>>(id: i8, cursor: &mut C) -> ProtocolResult<NbtElement<'a>> {
@@ -76,7 +78,7 @@ pub fn read_nbt_tag<'a, C: ProtocolCursor<'a>>(id: i8, cursor: &mut C) -> Protoc
             }
         }),
         10 => NbtElement::Compound({
-            let mut result = HashMap::new();
+            let mut result = BTreeMap::new();
             loop {
                 let tag = i8::read(cursor)?;
                 if tag == 0 { break; }
@@ -104,16 +106,7 @@ pub fn write_compound_enter<W: ProtocolWriter>(writer: &mut W) -> anyhow::Result
 }
 
 pub fn write_nbt_string<W: ProtocolWriter>(str: &str, writer: &mut W) -> anyhow::Result<()> {
-    match str.len() > u16::MAX as _ {
-        true => Err(anyhow::Error::msg("Too big string")),
-        false => {
-            (str.len() as u16).write(writer)?;
-            Ok(match cesu8::to_java_cesu8(str) {
-                Cow::Owned(ref owned) => writer.write_bytes(owned),
-                Cow::Borrowed(borrowed) => writer.write_bytes(borrowed),
-            })
-        }
-    }
+    write_nbt_str(str, writer)
 }
 
 pub fn nbt_key(element: &NbtElement) -> i8 {
@@ -153,4 +146,35 @@ pub fn write_nbt_element<W: ProtocolWriter>(element: &NbtElement, writer: &mut W
         NbtElement::IntArray(_) => unimplemented!(),
         NbtElement::LongArray(_) => unimplemented!(),
     })
-}
\ No newline at end of file
+}
+
+/// Uuid which is stored as an int array of 4 elements like in vanilla
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NbtUuid(pub Uuid);
+
+impl<'a> NbtTag<'a> for NbtUuid {
+    const NBT_TAG: u8 = NBT_TAG_INT_ARRAY;
+
+    fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        4i32.write_nbt(writer)?;
+        writer.write_bytes(self.0.as_bytes());
+        Ok(())
+    }
+
+    fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        if i32::read_nbt(cursor)? != 4 {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Uuid must contain 4 ints")));
+        }
+        Ok(NbtUuid(Uuid::from_bytes(*cursor.take_fixed_bytes::<16>()?)))
+    }
+
+    fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        let mut result = 0;
+        for _ in 0..amount {
+            let len = i32::read_nbt(cursor)?.max(0) as usize * 4;
+            cursor.take_bytes(len)?;
+            result += 4 + len;
+        }
+        Ok(result)
+    }
+}
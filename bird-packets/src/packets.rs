@@ -0,0 +1,5676 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::Range;
+use bitfield_struct::bitfield;
+use euclid::default::{Vector2D, Vector3D};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use bird_data::Sound;
+use bird_protocol::{*, ProtocolPacketState::*, ProtocolPacketBound::*};
+use bird_protocol::derive::{BirdNbt, ProtocolAll, ProtocolPacket, ProtocolSize, ProtocolWritable};
+#[cfg(any(test, feature = "sample"))]
+use bird_protocol::derive::ProtocolSample;
+use bird_protocol::nbt::{NBT_TAG_STRING, NbtTag, NbtByteArray, write_nbt_str};
+use bird_util::*;
+use crate::nbt::{NbtElement, read_compound_enter, read_nbt_string, read_nbt_tag, write_compound_enter, write_nbt_string};
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct Slot<'a> {
+    #[bp(variant = VarInt)]
+    pub item_id: i32,
+    pub item_count: i8,
+    /// `None` if the item has no nbt
+    #[bp(variant = OptionalNbt)]
+    pub nbt: Option<&'a [u8]>,
+}
+
+pub const EMPTY_NBT: &'static [u8] = &[0];
+
+/// Owned nbt of items is [`EMPTY_NBT`] if there is no nbt
+pub fn optional_nbt(nbt: &[u8]) -> Option<&[u8]> {
+    match nbt == EMPTY_NBT || nbt.is_empty() {
+        true => None,
+        false => Some(nbt),
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct OwnedSlot {
+    pub item_id: i32,
+    pub item_count: i8,
+    pub nbt: Vec<u8>,
+}
+
+impl OwnedSlot {
+    pub fn new(item_id: i32, item_count: i8) -> Self {
+        Self { item_id, item_count, nbt: EMPTY_NBT.to_vec() }
+    }
+
+    pub fn as_slot(&self) -> Slot {
+        Slot { item_id: self.item_id, item_count: self.item_count, nbt: optional_nbt(&self.nbt) }
+    }
+}
+
+impl<'a> From<Slot<'a>> for OwnedSlot {
+    fn from(slot: Slot<'a>) -> Self {
+        Self { item_id: slot.item_id, item_count: slot.item_count, nbt: slot.nbt.unwrap_or(EMPTY_NBT).to_vec() }
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    pub const fn offset(&self) -> Vector3D<i32> {
+        match self {
+            Direction::Down => Vector3D::new(0, -1, 0),
+            Direction::Up => Vector3D::new(0, 1, 0),
+            Direction::North => Vector3D::new(0, 0, -1),
+            Direction::South => Vector3D::new(0, 0, 1),
+            Direction::West => Vector3D::new(-1, 0, 0),
+            Direction::East => Vector3D::new(1, 0, 0),
+        }
+    }
+
+    pub const fn opposite(&self) -> Self {
+        match self {
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum HandshakeNextState {
+    #[bp(value = 1)]
+    Status = 1,
+    Login,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Handshake, bound = Server)]
+pub struct Handshake<'a> {
+    #[bp(variant = VarInt)]
+    pub protocol_version: i32,
+    pub server_address: &'a str,
+    pub server_port: u16,
+    pub next_state: HandshakeNextState,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponseObject<'a> {
+    #[serde(borrow)]
+    pub version: StatusResponseVersion<'a>,
+    #[serde(borrow)]
+    pub players: StatusResponsePlayers<'a>,
+    #[serde(borrow)]
+    pub description: either::Either<&'a str, Component<'a>>,
+    #[serde(borrow)]
+    pub favicon: Option<&'a str>,
+    #[serde(default)]
+    pub previews_chat: bool,
+    #[serde(default)]
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct StatusResponseVersion<'a> {
+    #[serde(borrow)]
+    pub name: &'a str,
+    pub protocol: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct StatusResponsePlayers<'a> {
+    pub max: i32,
+    #[serde(borrow)]
+    pub sample: Cow<'a, [StatusResponsePlayersSample<'a>]>,
+    pub online: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct StatusResponsePlayersSample<'a> {
+    #[serde(borrow)]
+    pub name: &'a str,
+    pub id: Uuid,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Status, bound = Client)]
+pub struct StatusResponseSS2C<'a>(
+    #[bp(variant = Json)]
+    pub StatusResponseObject<'a>
+);
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1, state = Status, bound = Client)]
+pub struct PingResponseSS2C {
+    pub payload: u64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Status, bound = Server)]
+pub struct StatusRequest;
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1, state = Status, bound = Server)]
+pub struct PingRequestSC2S {
+    pub payload: u64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Login, bound = Client)]
+pub struct LoginDisconnectLS2C<'a> {
+    #[bp(variant = Json)]
+    pub reason: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1, state = Login, bound = Client)]
+pub struct EncryptionRequestLS2C<'a> {
+    pub server_id: &'a str,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub public_key: &'a [u8],
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub verify_token: &'a [u8],
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct Property<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub signature: Option<&'a str>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct OwnedProperty {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl OwnedProperty {
+    pub fn as_property(&self) -> Property {
+        Property { name: &self.name, value: &self.value, signature: self.signature.as_deref() }
+    }
+}
+
+impl<'a> From<Property<'a>> for OwnedProperty {
+    fn from(property: Property<'a>) -> Self {
+        Self {
+            name: property.name.into(),
+            value: property.value.into(),
+            signature: property.signature.map(|signature| signature.into()),
+        }
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2, state = Login, bound = Client)]
+pub struct LoginSuccessLS2C<'a> {
+    pub uuid: Uuid,
+    pub username: &'a str,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Property<'a>, Property<'a>>")]
+    pub properties: Cow<'a, [Property<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3, state = Login, bound = Client)]
+pub struct SetCompressionLS2C {
+    #[bp(variant = VarInt)]
+    pub threshold: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4, state = Login, bound = Client)]
+pub struct LoginPluginRequestLS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub message_id: i32,
+    pub channel: Identifier<'a>,
+    #[bp(variant = RemainingBytesArray)]
+    pub data: &'a [u8],
+}
+
+pub const MAX_USERNAME_LENGTH: usize = 16;
+
+/// Since 1.19.3 the key of the player is sent in the play state by the player session packet
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Login, bound = Server)]
+pub struct LoginStartLC2S<'a> {
+    #[bp(validate = "name.chars().count() <= MAX_USERNAME_LENGTH")]
+    pub name: &'a str,
+    pub uuid: Option<Uuid>,
+}
+
+/// Since 1.19.3 the verify token is always sent instead of the signed salt
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1, state = Login, bound = Server)]
+pub struct EncryptionResponseLC2S<'a> {
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub shared_secret: &'a [u8],
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub verify_token: &'a [u8],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2, state = Login, bound = Server)]
+pub struct LoginPluginResponseLC2S<'a> {
+    #[bp(variant = VarInt)]
+    pub message_id: i32,
+    pub successful: bool,
+    #[bp(variant = RemainingBytesArray)]
+    pub data: &'a [u8],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0, state = Play, bound = Client)]
+pub struct SpawnEntityPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub entity_uuid: Uuid,
+    #[bp(variant = VarInt)]
+    pub entity_type: i32,
+    pub position: Vector3D<f64>,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub head_yaw: f32,
+    #[bp(variant = VarInt)]
+    pub data: i32,
+    pub velocity: Vector3D<i16>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1, state = Play, bound = Client)]
+pub struct SpawnExperienceOrbPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub position: Vector3D<f64>,
+    pub count: i16,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2, state = Play, bound = Client)]
+pub struct SpawnPlayerPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub player_uuid: Uuid,
+    pub position: Vector3D<f64>,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum EntityAnimation {
+    SwingMainArm,
+    TakeDamage,
+    LeaveBed,
+    SwingOffHand,
+    CriticalEffect,
+    MagicCriticalEffect,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3, state = Play, bound = Client)]
+pub struct EntityAnimationPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub animation: EntityAnimation,
+}
+
+// Identifies block id in award statistics
+pub type AwardStatisticBlock = i32;
+
+// Identified item id in award statistics
+pub type AwardStatisticItem = i32;
+
+// Identifier entity id in award statistics
+pub type AwardStatisticEntity = i32;
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum AwardStatisticCustom {
+    LeaveGame,
+    PlayOneMinute,
+    TimeSinceDeath,
+    TimeSinceRest,
+    SneakTime,
+    WalkOneCm,
+    CrouchOneCm,
+    SprintOneCm,
+    WalkOnWaterOneCm,
+    FallOneCm,
+    ClimbOneCm,
+    FlyOneCm,
+    WalkUnderWaterOneCm,
+    MinecartOneCm,
+    BoatOneCm,
+    PigOneCm,
+    HorseOneCm,
+    AviateOneCm,
+    SwimOneCm,
+    StriderOneCm,
+    Jump,
+    Drop,
+    DamageDealt,
+    DamageDealtAbsorbed,
+    DamageDealtResisted,
+    DamageTaken,
+    DamageBlockedByShield,
+    DamageAbsorbed,
+    DamageResisted,
+    Deaths,
+    MobKills,
+    AnimalsBred,
+    PlayerKills,
+    FishCaught,
+    TalkedToVillager,
+    TradedWithVillager,
+    EatCakeSlice,
+    FillCauldron,
+    UseCauldron,
+    CleanArmor,
+    CleanBanner,
+    CleanShulkerBox,
+    InteractWithBrewingStand,
+    InteractWithBeacon,
+    InspectDropper,
+    InspectHopper,
+    InspectDispenser,
+    PlayNoteBlock,
+    TuneNoteBlock,
+    PotFlower,
+    TriggerTrappedChest,
+    OpenEnderchest,
+    EnchantItem,
+    PlayRecord,
+    InteractWithFurnace,
+    InteractWithCraftingTable,
+    OpenChest,
+    SleepInBed,
+    OpenShulkerBox,
+    OpenBarrel,
+    InteractWithBlastFurnace,
+    InteractWithSmoker,
+    InteractWithLectern,
+    InteractWithCampfire,
+    InteractWithCartographyTable,
+    InteractWithLoom,
+    InteractWithStoneCutter,
+    BellRing,
+    RaidTrigger,
+    RaidWin,
+    InteractWithAnvil,
+    InteractWithGrindstone,
+    TargetHit,
+    InteractWithSmithingTable,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum AwardStatistic {
+    Mined(
+        #[bp(variant = VarInt)]
+        AwardStatisticBlock
+    ),
+    Crafted(
+        #[bp(variant = VarInt)]
+        AwardStatisticItem
+    ),
+    Used(
+        #[bp(variant = VarInt)]
+        AwardStatisticItem
+    ),
+    Broken(
+        #[bp(variant = VarInt)]
+        AwardStatisticItem
+    ),
+    PickedUp(
+        #[bp(variant = VarInt)]
+        AwardStatisticItem
+    ),
+    Dropped(
+        #[bp(variant = VarInt)]
+        AwardStatisticItem
+    ),
+    Killed(
+        #[bp(variant = VarInt)]
+        AwardStatisticEntity
+    ),
+    KilledBy(
+        #[bp(variant = VarInt)]
+        AwardStatisticEntity
+    ),
+    Custom(AwardStatisticCustom),
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4, state = Play, bound = Client)]
+pub struct AwardStatisticsPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AwardStatisticsEntry, AwardStatisticsEntry>")]
+    pub statistics: Cow<'a, [AwardStatisticsEntry]>,
+}
+
+/// Every statistic has its own value
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct AwardStatisticsEntry {
+    pub statistic: AwardStatistic,
+    #[bp(variant = VarInt)]
+    pub value: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x5, state = Play, bound = Client)]
+pub struct AcknowledgeBlockChangePS2C {
+    #[bp(variant = VarInt)]
+    pub sequence_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x6, state = Play, bound = Client)]
+pub struct SetBlockDestroyStagePS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub destroy_stage: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x7, state = Play, bound = Client)]
+pub struct BlockEntityDataPS2C<'a> {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    #[bp(variant = VarInt)]
+    pub ty: i32,
+    /// `None` removes the block entity data on the client
+    #[bp(variant = OptionalNbt)]
+    pub nbt_data: Option<&'a [u8]>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum BlockActionVariantPistonDirection {
+    Down,
+    Up,
+    South,
+    West,
+    North,
+    East,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum BlockActionVariantBellDirection {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt, key_reverse = true)]
+pub enum BlockActionVariant {
+    #[bp(value = "(bird_data::block_data::NOTE_BLOCK.id) as i32", ghost = [(order = begin, value = 0u8), (order = end, value = 0u8)])]
+    NoteBlock,
+    #[bp(value = "(bird_data::block_data::PISTON.id) as i32")]
+    Piston {
+        retract: bool,
+        direction: BlockActionVariantPistonDirection,
+    },
+    #[bp(value = "(bird_data::block_data::CHEST.id) as i32", ghost = [(order = begin, value = 1u8)])]
+    Chest {
+        players_looking_in: u8,
+    },
+    #[bp(value = "(bird_data::block_data::ENDER_CHEST.id) as i32", ghost = [(order = begin, value = 1u8)])]
+    EnderChest {
+        players_looking_in: u8,
+    },
+    #[bp(value = "(bird_data::block_data::BEACON.id) as i32", ghost = [(order = begin, value = 1u8), (order = end, value = 0u8)])]
+    Beacon,
+    #[bp(value = "(bird_data::block_data::SPAWNER.id) as i32", ghost = [(order = begin, value = 1u8), (order = end, value = 0u8)])]
+    Spawner,
+    #[bp(value = "(bird_data::block_data::END_GATEWAY.id) as i32", ghost = [(order = begin, value = 1u8), (order = end, value = 0u8)])]
+    EndGateway,
+    #[bp(value = "(bird_data::block_data::SHULKER_BOX.id) as i32", ghost = [(order = begin, value = 1u8)])]
+    ShulkerBox {
+        players_looking_in: u8,
+    },
+    #[bp(value = "(bird_data::block_data::BELL.id) as i32", ghost = [(order = begin, value = 1u8)])]
+    Bell {
+        direction: BlockActionVariantBellDirection,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x8, state = Play, bound = Client)]
+pub struct BlockActionPS2C {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub variant: BlockActionVariant,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x9, state = Play, bound = Client)]
+pub struct BlockUpdatePS2C {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    #[bp(variant = VarInt)]
+    pub block_id: i32,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum BossBarColor {
+    Pink,
+    Blue,
+    Red,
+    Green,
+    Yellow,
+    Purple,
+    White,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum BossBarDivision {
+    Zero,
+    Six,
+    Ten,
+    Twelve,
+    Twenty,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct BossBarFlags {
+    pub dark_sky: bool,
+    pub dragon_bar: bool,
+    pub fog: bool,
+    #[bits(5)]
+    _pad: u8,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum BossBarAction<'a> {
+    Add {
+        title: Component<'a>,
+        health: f32,
+        color: BossBarColor,
+        division: BossBarDivision,
+        flags: BossBarFlags,
+    },
+    Remove,
+    UpdateHealth {
+        health: f32,
+    },
+    UpdateTitle {
+        title: Component<'a>,
+    },
+    UpdateStyle {
+        color: BossBarColor,
+        division: BossBarDivision,
+    },
+    UpdateFlags {
+        flags: BossBarFlags,
+    },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xA, state = Play, bound = Client)]
+pub struct BossBarPS2C<'a> {
+    pub uuid: Uuid,
+    pub action: BossBarAction<'a>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xB, state = Play, bound = Client)]
+pub struct ChangeDifficultyPS2C {
+    pub difficulty: Difficulty,
+    pub locked: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xC, state = Play, bound = Client)]
+pub struct ClearTitles {
+    pub reset: bool,
+}
+
+// #[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+// #[bp(id = 0xC, state = Play, bound = Client)]
+// pub struct ChatPreviewPS2C<'a> {
+//     pub query_id: i32,
+//     pub message: Option<Component<'a>>,
+// }
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct CommandSuggestionsMatch<'a> {
+    pub insert: &'a str,
+    pub tooltip: Option<Component<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xD, state = Play, bound = Client)]
+pub struct CommandSuggestionsResponsePS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub id: i32,
+    #[bp(variant = VarInt)]
+    pub start: i32,
+    #[bp(variant = VarInt)]
+    pub length: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, CommandSuggestionsMatch<'a>, CommandSuggestionsMatch<'a>>")]
+    pub matches: Cow<'a, [CommandSuggestionsMatch<'a>]>,
+}
+
+pub const ROOT_NODE_TYPE: u8 = 0;
+pub const LITERAL_NODE_TYPE: u8 = 1;
+pub const ARGUMENT_NODE_TYPE: u8 = 2;
+
+#[bitfield(i8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct BrigadierNodeFlags {
+    #[bits(2)]
+    pub node_type: u8,
+    pub executable: bool,
+    pub redirect: bool,
+    pub suggestions_type: bool,
+    #[bits(3)]
+    _pad: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct BrigadierNodeRangeProperties<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T> ProtocolSize for BrigadierNodeRangeProperties<T>
+    where T: ProtocolSize {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(
+        Option<T>,
+        Option<T>,
+        u8
+    );
+}
+
+impl<T> ProtocolWritable for BrigadierNodeRangeProperties<T>
+    where T: ProtocolWritable {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let flags = if self.min.is_some() { 1u8 } else { 0u8 } | if self.max.is_some() { 2u8 } else { 0u8 };
+        flags.write(writer)?;
+        if let Some(ref to_write) = self.min { to_write.write(writer)? };
+        if let Some(ref to_write) = self.max { to_write.write(writer)? };
+        Ok(())
+    }
+}
+
+impl<'a, T> ProtocolReadable<'a> for BrigadierNodeRangeProperties<T>
+    where T: ProtocolReadable<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let flags = u8::read(cursor)?;
+        let min = match flags & 0x1 != 0 {
+            true => Some(T::read(cursor)?),
+            false => None,
+        };
+        let max = match flags & 0x2 != 0 {
+            true => Some(T::read(cursor)?),
+            false => None,
+        };
+        Ok(Self { min, max })
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum BrigadierNodeParserString {
+    SingleWord,
+    QuotablePhrase,
+    GreedyPhrase,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct BrigadierNodeParseEntity {
+    pub single: bool,
+    pub only_players: bool,
+    #[bits(6)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum BrigadierNodeParser<'a> {
+    Bool,
+    Float(BrigadierNodeRangeProperties<f32>),
+    Double(BrigadierNodeRangeProperties<f64>),
+    Integer(BrigadierNodeRangeProperties<i32>),
+    Long(BrigadierNodeRangeProperties<i64>),
+    String(BrigadierNodeParserString),
+    Entity(BrigadierNodeParseEntity),
+    GameProfile,
+    BlockPos,
+    ColumnPos,
+    Vec3,
+    Vec2,
+    BlockState,
+    BlockPredicate,
+    ItemStack,
+    ItemPredicate,
+    Color,
+    Component,
+    Message,
+    Nbt,
+    NbtTag,
+    NbtPath,
+    Objective,
+    ObjectiveCriteria,
+    Operation,
+    Particle,
+    Angle,
+    Rotation,
+    ScoreboardSlot,
+    ScoreHolder {
+        multiple: bool,
+    },
+    Swizzle,
+    Team,
+    ItemSlot,
+    ResourceLocation,
+    MobEffect,
+    Function,
+    EntityAnchor,
+    IntRange,
+    FloatRange,
+    ItemEnchantment,
+    EntitySummon,
+    Dimension,
+    Time,
+    ResourceOrTag {
+        registry: Identifier<'a>,
+    },
+    Resource {
+        registry: Identifier<'a>,
+    },
+    TemplateMirror,
+    // ?
+    TemplateRotation,
+    // ?
+    Uuid,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BrigadierNode<'a> {
+    pub executable: bool,
+    pub children: Cow<'a, [i32]>,
+    pub redirect_node: Option<i32>,
+    pub name: Option<&'a str>,
+    pub parser: Option<BrigadierNodeParser<'a>>,
+    pub suggestions_type: Option<Identifier<'a>>,
+}
+
+impl<'a> ProtocolSize for BrigadierNode<'a> {
+    const SIZE: Range<u32> = (
+        add_protocol_sizes_ty!(
+            u8,
+            LengthProvidedArray<i32, VarInt, i32, i32>,
+        ).start
+            ..
+            add_protocol_sizes_ty!(
+            u8,
+            LengthProvidedArray<i32, VarInt, i32, i32>,
+            VarInt,
+            &'a str,
+            BrigadierNodeParser<'a>,
+            Identifier<'a>,
+        ).end
+    );
+}
+
+impl<'a> ProtocolWritable for BrigadierNode<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let flags = BrigadierNodeFlags::new()
+            .with_node_type(match self.name {
+                Some(_) => match self.parser {
+                    Some(_) => ARGUMENT_NODE_TYPE,
+                    None => LITERAL_NODE_TYPE,
+                },
+                None => ROOT_NODE_TYPE,
+            })
+            .with_executable(self.executable)
+            .with_redirect(self.redirect_node.is_some())
+            .with_suggestions_type(self.suggestions_type.is_some());
+        flags.write(writer)?;
+        LengthProvidedArray::<i32, VarInt, i32, i32>::write_variant(&self.children, writer)?;
+        if let Some(ref to_write) = self.redirect_node { VarInt::write_variant(to_write, writer)? };
+        if let Some(to_write) = self.name { to_write.write(writer)? };
+        if let Some(ref to_write) = self.parser { to_write.write(writer)? };
+        if let Some(ref to_write) = self.suggestions_type { to_write.write(writer)? };
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for BrigadierNode<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let flags = BrigadierNodeFlags::read(cursor)?;
+        let children = LengthProvidedArray::<i32, VarInt, i32, i32>::read_variant(cursor)?;
+        let redirect_node = match flags.redirect() {
+            true => Some(VarInt::read_variant(cursor)?),
+            false => None,
+        };
+        let (name, parser) = match flags.node_type() {
+            ROOT_NODE_TYPE => (None, None),
+            LITERAL_NODE_TYPE => (Some(<&'a str>::read(cursor)?), None),
+            _ => (Some(<&'a str>::read(cursor)?), Some(BrigadierNodeParser::read(cursor)?)),
+        };
+        let suggestions_type = match flags.suggestions_type() {
+            true => Some(Identifier::read(cursor)?),
+            false => None,
+        };
+        Ok(Self {
+            executable: flags.executable(),
+            children,
+            redirect_node,
+            name,
+            parser,
+            suggestions_type,
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xE, state = Play, bound = Client)]
+pub struct CommandsPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, BrigadierNode<'a>, BrigadierNode<'a>>")]
+    pub nodes: Cow<'a, [BrigadierNode<'a>]>,
+    #[bp(variant = VarInt)]
+    pub root_index: i32,
+}
+
+pub const PLAYER_INVENTORY_ID: u8 = 0;
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0xF, state = Play, bound = Client)]
+pub struct CloseContainerPS2C {
+    pub window_id: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x10, state = Play, bound = Client)]
+pub struct SetContainerContentPS2C<'a> {
+    pub window_id: u8,
+    #[bp(variant = VarInt)]
+    pub state_id: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Option<Slot<'a>>, Option<Slot<'a>>>")]
+    pub slot_data: Cow<'a, [Option<Slot<'a>>]>,
+    pub carried_item: Option<Slot<'a>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FurnaceProperty {
+    FireIcon,
+    MaximumFuelBurnTime,
+    ProgressArrow,
+    MaximumProgress,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EnchantmentTableSlot {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EnchantmentTableProperty {
+    LevelRequirement(EnchantmentTableSlot),
+    Seed,
+    EnchantmentId(EnchantmentTableSlot),
+    EnchantmentLevel(EnchantmentTableSlot),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BeaconProperty {
+    PowerLevel,
+    FirstPotionEffect,
+    SecondPotionEffect,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BrewingStandProperty {
+    BrewTime,
+    FuelTime,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x11, state = Play, bound = Client)]
+pub struct SetContainerPropertyPS2C {
+    pub window_id: u8,
+    pub property: i16,
+    pub value: i16,
+}
+
+impl TryFrom<i16> for FurnaceProperty {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FurnaceProperty::FireIcon),
+            1 => Ok(FurnaceProperty::MaximumFuelBurnTime),
+            2 => Ok(FurnaceProperty::ProgressArrow),
+            3 => Ok(FurnaceProperty::MaximumProgress),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<FurnaceProperty> for i16 {
+    fn from(value: FurnaceProperty) -> Self {
+        match value {
+            FurnaceProperty::FireIcon => 0,
+            FurnaceProperty::MaximumFuelBurnTime => 1,
+            FurnaceProperty::ProgressArrow => 2,
+            FurnaceProperty::MaximumProgress => 3,
+        }
+    }
+}
+
+impl TryFrom<i16> for EnchantmentTableProperty {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Top)),
+            1 => Ok(EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Middle)),
+            2 => Ok(EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Bottom)),
+            3 => Ok(EnchantmentTableProperty::Seed),
+            4 => Ok(EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Top)),
+            5 => Ok(EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Middle)),
+            6 => Ok(EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Bottom)),
+            7 => Ok(EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Top)),
+            8 => Ok(EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Middle)),
+            9 => Ok(EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Bottom)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<EnchantmentTableProperty> for i16 {
+    fn from(value: EnchantmentTableProperty) -> Self {
+        match value {
+            EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Top) => 0,
+            EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Middle) => 1,
+            EnchantmentTableProperty::LevelRequirement(EnchantmentTableSlot::Bottom) => 2,
+            EnchantmentTableProperty::Seed => 3,
+            EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Top) => 4,
+            EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Middle) => 5,
+            EnchantmentTableProperty::EnchantmentId(EnchantmentTableSlot::Bottom) => 6,
+            EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Top) => 7,
+            EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Middle) => 8,
+            EnchantmentTableProperty::EnchantmentLevel(EnchantmentTableSlot::Bottom) => 9,
+        }
+    }
+}
+
+impl TryFrom<i16> for BeaconProperty {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BeaconProperty::PowerLevel),
+            1 => Ok(BeaconProperty::FirstPotionEffect),
+            2 => Ok(BeaconProperty::SecondPotionEffect),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<BeaconProperty> for i16 {
+    fn from(value: BeaconProperty) -> Self {
+        match value {
+            BeaconProperty::PowerLevel => 0,
+            BeaconProperty::FirstPotionEffect => 1,
+            BeaconProperty::SecondPotionEffect => 2,
+        }
+    }
+}
+
+impl TryFrom<i16> for BrewingStandProperty {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BrewingStandProperty::BrewTime),
+            1 => Ok(BrewingStandProperty::FuelTime),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<BrewingStandProperty> for i16 {
+    fn from(value: BrewingStandProperty) -> Self {
+        match value {
+            BrewingStandProperty::BrewTime => 0,
+            BrewingStandProperty::FuelTime => 1,
+        }
+    }
+}
+
+/// Property of the opened window, variants are kinds of windows, so indices of different windows are not mixed up
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ContainerProperty {
+    /// Furnace, blast furnace and smoker
+    Furnace(FurnaceProperty),
+    EnchantmentTable(EnchantmentTableProperty),
+    Beacon(BeaconProperty),
+    BrewingStand(BrewingStandProperty),
+    AnvilRepairCost,
+    LecternPage,
+    LoomSelectedPattern,
+    StonecutterSelectedRecipe,
+}
+
+impl ContainerProperty {
+    /// Index of the property in the window
+    pub fn index(&self) -> i16 {
+        match self {
+            ContainerProperty::Furnace(property) => (*property).into(),
+            ContainerProperty::EnchantmentTable(property) => (*property).into(),
+            ContainerProperty::Beacon(property) => (*property).into(),
+            ContainerProperty::BrewingStand(property) => (*property).into(),
+            ContainerProperty::AnvilRepairCost | ContainerProperty::LecternPage |
+            ContainerProperty::LoomSelectedPattern | ContainerProperty::StonecutterSelectedRecipe => 0,
+        }
+    }
+
+    pub fn is_supported_by(&self, window_type: InventorySizeType) -> bool {
+        match self {
+            ContainerProperty::Furnace(_) => matches!(
+                window_type,
+                InventorySizeType::Furnace | InventorySizeType::BlastFurnace | InventorySizeType::Smoker
+            ),
+            ContainerProperty::EnchantmentTable(_) => matches!(window_type, InventorySizeType::Enchantment),
+            ContainerProperty::Beacon(_) => matches!(window_type, InventorySizeType::Beacon),
+            ContainerProperty::BrewingStand(_) => matches!(window_type, InventorySizeType::BrewingStand),
+            ContainerProperty::AnvilRepairCost => matches!(window_type, InventorySizeType::Anvil),
+            ContainerProperty::LecternPage => matches!(window_type, InventorySizeType::Lectern),
+            ContainerProperty::LoomSelectedPattern => matches!(window_type, InventorySizeType::Loom),
+            ContainerProperty::StonecutterSelectedRecipe => matches!(window_type, InventorySizeType::Stonecutter),
+        }
+    }
+
+    /// Property of the window type with the index, `None` if the window doesn't have it
+    pub fn from_index(window_type: InventorySizeType, index: i16) -> Option<Self> {
+        match window_type {
+            InventorySizeType::Furnace | InventorySizeType::BlastFurnace | InventorySizeType::Smoker =>
+                FurnaceProperty::try_from(index).ok().map(ContainerProperty::Furnace),
+            InventorySizeType::Enchantment => EnchantmentTableProperty::try_from(index).ok().map(ContainerProperty::EnchantmentTable),
+            InventorySizeType::Beacon => BeaconProperty::try_from(index).ok().map(ContainerProperty::Beacon),
+            InventorySizeType::BrewingStand => BrewingStandProperty::try_from(index).ok().map(ContainerProperty::BrewingStand),
+            InventorySizeType::Anvil if index == 0 => Some(ContainerProperty::AnvilRepairCost),
+            InventorySizeType::Lectern if index == 0 => Some(ContainerProperty::LecternPage),
+            InventorySizeType::Loom if index == 0 => Some(ContainerProperty::LoomSelectedPattern),
+            InventorySizeType::Stonecutter if index == 0 => Some(ContainerProperty::StonecutterSelectedRecipe),
+            _ => None,
+        }
+    }
+}
+
+impl From<ContainerProperty> for i16 {
+    fn from(value: ContainerProperty) -> Self {
+        value.index()
+    }
+}
+
+impl From<FurnaceProperty> for ContainerProperty {
+    fn from(value: FurnaceProperty) -> Self {
+        ContainerProperty::Furnace(value)
+    }
+}
+
+impl From<EnchantmentTableProperty> for ContainerProperty {
+    fn from(value: EnchantmentTableProperty) -> Self {
+        ContainerProperty::EnchantmentTable(value)
+    }
+}
+
+impl From<BeaconProperty> for ContainerProperty {
+    fn from(value: BeaconProperty) -> Self {
+        ContainerProperty::Beacon(value)
+    }
+}
+
+impl From<BrewingStandProperty> for ContainerProperty {
+    fn from(value: BrewingStandProperty) -> Self {
+        ContainerProperty::BrewingStand(value)
+    }
+}
+
+impl SetContainerPropertyPS2C {
+    pub fn new(window_id: u8, property: impl Into<ContainerProperty>, value: i16) -> Self {
+        Self { window_id, property: property.into().index(), value }
+    }
+
+    pub fn furnace(window_id: u8, property: FurnaceProperty, value: i16) -> Self {
+        Self::new(window_id, property, value)
+    }
+
+    pub fn enchantment_table(window_id: u8, property: EnchantmentTableProperty, value: i16) -> Self {
+        Self::new(window_id, property, value)
+    }
+
+    pub fn beacon(window_id: u8, property: BeaconProperty, value: i16) -> Self {
+        Self::new(window_id, property, value)
+    }
+
+    pub fn brewing_stand(window_id: u8, property: BrewingStandProperty, value: i16) -> Self {
+        Self::new(window_id, property, value)
+    }
+
+    /// Typed property for the window type of the window
+    pub fn typed_property(&self, window_type: InventorySizeType) -> Option<ContainerProperty> {
+        ContainerProperty::from_index(window_type, self.property)
+    }
+}
+
+pub const CURSOR_SLOT_ID: i16 = -1;
+pub const CURSOR_WINDOW_ID: i8 = -1;
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x12, state = Play, bound = Client)]
+pub struct SetContainerSlotPS2C<'a> {
+    pub window_id: i8,
+    #[bp(variant = VarInt)]
+    pub state_id: i32,
+    pub slot: i16,
+    pub slot_data: Option<Slot<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x13, state = Play, bound = Client)]
+pub struct SetCooldownPS2C {
+    #[bp(variant = VarInt)]
+    pub item_id: i32,
+    #[bp(variant = VarInt)]
+    pub cooldown_ticks: i32,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ChatSuggestionAction {
+    Add,
+    Remove,
+    Set,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x14, state = Play, bound = Client)]
+pub struct ChatSuggestionsPS2C<'a> {
+    pub action: ChatSuggestionAction,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, &'a str, &'a str>")]
+    pub entries: Cow<'a, [&'a str]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x15, state = Play, bound = Client)]
+pub struct PluginMessagePS2C<'a> {
+    pub channel: Identifier<'a>,
+    #[bp(variant = RemainingBytesArray)]
+    pub data: &'a [u8],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x16, state = Play, bound = Client)]
+pub struct DeleteMessagePS2C<'a> {
+    pub signature: PackedMessageSignature<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x17, state = Play, bound = Client)]
+pub struct DisconnectPS2C<'a> {
+    pub reason: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x18, state = Play, bound = Client)]
+pub struct DisguisedChatMessagePS2C<'a> {
+    pub message: Component<'a>,
+    #[bp(variant = VarInt)]
+    pub chat_type: i32,
+    pub chat_type_name: Component<'a>,
+    pub target_name: Option<Component<'a>>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CustomSoundCategory {
+    Master,
+    Music,
+    Record,
+    Weather,
+    Block,
+    Hostile,
+    Neutral,
+    Player,
+    Ambient,
+    Voice,
+}
+
+// #[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+// #[bp(id = 0x17, state = Play, bound = Client)]
+// pub struct CustomSoundEffectPS2C<'a> {
+//     pub sound_name: Identifier<'a>,
+//     pub sound_category: CustomSoundCategory,
+//     #[bp(variant = "FixedPointNumber<i32, 3>")]
+//     pub effect_position_x: f32,
+//     #[bp(variant = "FixedPointNumber<i32, 3>")]
+//     pub effect_position_y: f32,
+//     #[bp(variant = "FixedPointNumber<i32, 3>")]
+//     pub effect_position_z: f32,
+//     pub volume: f32,
+//     pub pitch: f32,
+//     pub seed: i64,
+// }
+
+// #[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+// #[bp(id = 0x18, state = Play, bound = Client)]
+// pub struct HideMessagePS2C<'a> {
+//     #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+//     pub signature: &'a [u8],
+// }
+
+/// `EntityEventStatus::packet` of bird-server checks the status against the entity type
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x19, state = Play, bound = Client)]
+pub struct EntityEventPS2C {
+    pub entity_id: i32,
+    /// Id of `EntityEventStatus` of bird-server, the same id means different events for different entities
+    pub status: i8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1A, state = Play, bound = Client)]
+pub struct ExplosionPS2C<'a> {
+    pub location: Vector3D<f32>,
+    pub strength: f32,
+    #[bp(variant = "LengthProvidedRawArray<i32, VarInt, Vector3D<i8>, Vector3D<i8>>")]
+    pub records: &'a [Vector3D<i8>],
+    pub motion: Vector3D<f32>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1B, state = Play, bound = Client)]
+pub struct UnloadChunkPS2C {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = f32)]
+pub enum GameEventGameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = f32)]
+pub enum GameEventDemo {
+    ShowWelcome,
+    #[bp(value = 101f32)]
+    TellMovementControls,
+    TellJumpControl,
+    TellInventoryControl,
+    TellDemoIsOver,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = f32)]
+pub enum GameEventWinGame {
+    RespawnPlayer,
+    RollTheCredits,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = f32)]
+pub enum GameEventRespawnScreen {
+    EnableScreen,
+    ImmediatelyRespawn,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1C, state = Play, bound = Client, ty = u8)]
+pub enum GameEventPS2C {
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    NoRespawnBlockAvailable,
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    EndRaining,
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    BeginRaining,
+    ChangeGameMode(GameEventGameMode),
+    WinGame(GameEventWinGame),
+    DemoEvent(GameEventDemo),
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    ArrowHitPlayer,
+    RainLevelChange(f32),
+    ThunderLevelChange(f32),
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    PufferfishSting,
+    #[bp(ghost = [(order = begin, value = 0f32)])]
+    ElderGuardianMobAppearance,
+    EnableRespawnScreen(GameEventRespawnScreen),
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1D, state = Play, bound = Client)]
+pub struct OpenHorseScreenPS2C {
+    pub window_id: u8,
+    #[bp(variant = VarInt)]
+    pub slots: i32,
+    pub entity_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1E, state = Play, bound = Client)]
+pub struct InitializeWorldBorderPS2C {
+    pub x: f64,
+    pub y: f64,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    #[bp(variant = VarLong)]
+    pub speed: i64,
+    #[bp(variant = VarInt)]
+    pub portal_teleport_boundary: i32,
+    #[bp(variant = VarInt)]
+    pub warning_blocks: i32,
+    #[bp(variant = VarInt)]
+    pub warning_seconds: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1F, state = Play, bound = Client)]
+pub struct KeepAlivePS2C {
+    pub keep_alive_id: i64,
+}
+
+/// Packs entries like vanilla `SimpleBitStorage`: from the lowest bit of each long,
+/// bits which are left at the top of the long are unused
+#[derive(Debug)]
+pub struct GapCompactLongsWriter<'a, W: ProtocolWriter> {
+    writer: &'a mut W,
+    current: u64,
+    bits: u8,
+    elements_in_long: u8,
+    current_index: u8,
+}
+
+impl<'a, W: ProtocolWriter> GapCompactLongsWriter<'a, W> {
+    /// # Safety
+    /// The caller must ensure that the number of bits is less or equals to 64
+    pub unsafe fn new(writer: &'a mut W, bits: u8) -> Self {
+        debug_assert!(bits <= 64);
+        Self {
+            writer,
+            current: 0,
+            bits,
+            elements_in_long: 64 / bits,
+            current_index: 0,
+        }
+    }
+
+    /// # Safety.
+    /// The caller must ensure that the number is not longer than bits
+    pub unsafe fn write(&mut self, number: u64) -> anyhow::Result<()> {
+        debug_assert!(number < (1 << (self.bits + 1)));
+        if self.current_index == self.elements_in_long {
+            self.current.write(self.writer)?;
+            self.current = 0;
+            self.current_index = 0;
+        }
+        self.current |= number << (self.current_index * self.bits);
+        self.current_index += 1;
+        Ok(())
+    }
+
+    /// # Safety
+    /// The caller must ensure that each number in iterator is not longer than bits
+    pub unsafe fn write_all(&mut self, iterator: impl Iterator<Item=u64>) -> anyhow::Result<()> {
+        for num in iterator {
+            self.write(num)?
+        }
+        Ok(())
+    }
+
+    /// # Safety.
+    /// The caller must ensure that the number is not longer than bits
+    pub unsafe fn write_and_finish(mut self, number: u64) -> anyhow::Result<()> {
+        self.write(number)?;
+        self.finish()
+    }
+
+    /// # Safety
+    /// The caller must ensure that each number in iterator is not longer than bits
+    pub unsafe fn write_all_and_finish(mut self, iterator: impl Iterator<Item=u64>) -> anyhow::Result<()> {
+        self.write_all(iterator)?;
+        self.finish()
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        if self.current_index != 0 {
+            self.current.write(self.writer)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_compact_bits(bits: u8) -> anyhow::Result<()> {
+    match (1..=64).contains(&bits) {
+        true => Ok(()),
+        false => Err(anyhow::Error::msg(format!("Bits per entry must be in 1..=64, got {}", bits))),
+    }
+}
+
+fn check_compact_number(number: u64, bits: u8) -> anyhow::Result<()> {
+    match bits == 64 || number >> bits == 0 {
+        true => Ok(()),
+        false => Err(anyhow::Error::msg(format!("Number {} doesn't fit in {} bits", number, bits))),
+    }
+}
+
+impl<'a, W: ProtocolWriter> GapCompactLongsWriter<'a, W> {
+    /// Safe variant of [`GapCompactLongsWriter::new`] for bits which are known only at runtime
+    pub fn checked(writer: &'a mut W, bits: u8) -> anyhow::Result<Self> {
+        check_compact_bits(bits)?;
+        // SAFETY: bits are checked above
+        Ok(unsafe { Self::new(writer, bits) })
+    }
+
+    /// Returns an error if the number is longer than bits
+    pub fn push(&mut self, number: u64) -> anyhow::Result<()> {
+        check_compact_number(number, self.bits)?;
+        // SAFETY: the number is checked above
+        unsafe { self.write(number) }
+    }
+
+    pub fn push_all(&mut self, iterator: impl Iterator<Item=u64>) -> anyhow::Result<()> {
+        for number in iterator {
+            self.push(number)?;
+        }
+        Ok(())
+    }
+
+    pub fn push_all_and_finish(mut self, iterator: impl Iterator<Item=u64>) -> anyhow::Result<()> {
+        self.push_all(iterator)?;
+        self.finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GapCompactLongsReader<I, const COUNT: usize> {
+    iterator: I,
+    current_long: u64,
+    next_long: Option<u64>,
+    bits: u8,
+    elements_in_long: u8,
+    end_index: u8,
+    current_index: u8,
+    mask: u64,
+}
+
+impl<I: Iterator<Item=u64>, const COUNT: usize> GapCompactLongsReader<I, COUNT> {
+    /// # Safety
+    /// The caller must ensure that number of bits is less or equals to 64
+    pub unsafe fn new(mut iterator: I, bits: u8) -> Option<Self> {
+        debug_assert!(bits <= 64);
+        let elements_in_long = 64 / bits;
+        let current_long = iterator.next()?;
+        let next_long = iterator.next();
+        Some(Self {
+            iterator,
+            current_long,
+            next_long,
+            bits,
+            elements_in_long,
+            mask: (1 << (bits as u64)) - 1,
+            end_index: {
+                let result = COUNT % (elements_in_long as usize);
+                if result == 0 { elements_in_long } else { result as u8 }
+            },
+            current_index: 0,
+        })
+    }
+}
+
+impl<I: Iterator<Item=u64>, const COUNT: usize> Iterator for GapCompactLongsReader<I, COUNT> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_long.is_none() && self.current_index == self.end_index {
+            return None;
+        }
+        if self.current_index == self.elements_in_long {
+            self.current_index = 0;
+            self.current_long = unsafe { self.next_long.unwrap_unchecked() };
+            self.next_long = self.iterator.next();
+        }
+        let result = self.current_long & self.mask;
+        self.current_long >>= self.bits;
+        self.current_index += 1;
+        Some(result)
+    }
+}
+
+/// Owned compact longs with bits per entry chosen at runtime, entries are packed like [`GapCompactLongsWriter`] does
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CompactLongs {
+    longs: Vec<u64>,
+    bits: u8,
+    length: usize,
+}
+
+impl CompactLongs {
+    /// Zeroed entries
+    pub fn new(bits: u8, length: usize) -> anyhow::Result<Self> {
+        check_compact_bits(bits)?;
+        // SAFETY: bits are checked above
+        let longs = vec![0; unsafe { compact_longs_array_length(length, bits) }];
+        Ok(Self { longs, bits, length })
+    }
+
+    /// Empty array which is filled by [`CompactLongs::push`]
+    pub fn with_capacity(bits: u8, capacity: usize) -> anyhow::Result<Self> {
+        check_compact_bits(bits)?;
+        // SAFETY: bits are checked above
+        let longs = Vec::with_capacity(unsafe { compact_longs_array_length(capacity, bits) });
+        Ok(Self { longs, bits, length: 0 })
+    }
+
+    /// Returns an error if the amount of longs doesn't match the length
+    pub fn from_slice(bits: u8, length: usize, longs: &[u64]) -> anyhow::Result<Self> {
+        check_compact_bits(bits)?;
+        // SAFETY: bits are checked above
+        let expected = unsafe { compact_longs_array_length(length, bits) };
+        if longs.len() != expected {
+            return Err(anyhow::Error::msg(format!("Expected {} longs for {} entries, got {}", expected, length, longs.len())));
+        }
+        Ok(Self { longs: longs.to_vec(), bits, length })
+    }
+
+    /// Packs the values, returns an error if any of them is longer than bits
+    pub fn from_values(bits: u8, values: impl IntoIterator<Item=u64>) -> anyhow::Result<Self> {
+        let mut compact = Self::with_capacity(bits, 0)?;
+        for value in values {
+            compact.push(value)?;
+        }
+        Ok(compact)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn longs(&self) -> &[u64] {
+        &self.longs
+    }
+
+    fn elements_in_long(&self) -> usize {
+        (64 / self.bits) as usize
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.bits)
+    }
+
+    /// Long index and shift of the entry, unused bits are left at the top of the long like in vanilla
+    fn position(&self, index: usize) -> (usize, u32) {
+        let elements_in_long = self.elements_in_long();
+        (index / elements_in_long, (index % elements_in_long) as u32 * self.bits as u32)
+    }
+
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.length {
+            return None;
+        }
+        let (long, shift) = self.position(index);
+        Some((self.longs[long] >> shift) & self.mask())
+    }
+
+    /// Returns the previous value
+    pub fn set(&mut self, index: usize, value: u64) -> anyhow::Result<u64> {
+        check_compact_number(value, self.bits)?;
+        let previous = self.get(index)
+            .ok_or_else(|| anyhow::Error::msg(format!("Index {} is out of bounds of {} entries", index, self.length)))?;
+        let (long, shift) = self.position(index);
+        let mask = self.mask() << shift;
+        self.longs[long] = (self.longs[long] & !mask) | (value << shift);
+        Ok(previous)
+    }
+
+    /// Appends the value, returns an error if it is longer than bits
+    pub fn push(&mut self, value: u64) -> anyhow::Result<()> {
+        check_compact_number(value, self.bits)?;
+        if self.length % self.elements_in_long() == 0 {
+            self.longs.push(0);
+        }
+        self.length += 1;
+        self.set(self.length - 1, value).map(|_| ())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=u64> + '_ {
+        (0..self.length).map(|index| self.get(index).unwrap())
+    }
+
+    /// Writes longs without their amount
+    pub fn write_longs<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for long in &self.longs {
+            long.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// # Safety
+/// The caller must ensure that number of bits is less or equals to 64
+pub const unsafe fn compact_longs_array_length(elements: usize, bits: u8) -> usize {
+    debug_assert!(bits <= 64);
+    let elements_in_long = (64 / bits) as usize;
+    elements / elements_in_long + (if elements % elements_in_long == 0 { 0 } else { 1 })
+}
+
+pub const CHUNK_DATA_HEIGHT_MAP_KEY: &'static str = "MOTION_BLOCKING";
+pub const CHUNK_DATA_WORLD_SURFACE_KEY: &'static str = "WORLD_SURFACE";
+/// Longs of one height map (256 entries, 9 bits each)
+pub const CHUNK_DATA_HEIGHT_MAP_LONGS: usize = 37;
+
+/// Compound with `MOTION_BLOCKING` and optionally `WORLD_SURFACE` height maps
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ChunkDataHeightMap<'a> {
+    motion_blocking: BorrowedLongArray<'a>,
+    world_surface: Option<BorrowedLongArray<'a>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[doc(hidden)]
+pub enum BorrowedLongArray<'a> {
+    Raw(&'a [u8]),
+    Longs(&'a [u64]),
+}
+
+/// Read arrays are raw, so arrays are equal if they have the same longs
+impl<'a> PartialEq for BorrowedLongArray<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        Iterator::eq(*self, *other)
+    }
+}
+
+impl<'a> Iterator for BorrowedLongArray<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Raw(raw) => u64::read(raw).ok(),
+            Self::Longs(long) => {
+                let number = *long.get(0)?;
+                *long = &long[1..];
+                Some(number)
+            }
+        }
+    }
+}
+
+impl<'a> BorrowedLongArray<'a> {
+    fn heights(self) -> GapCompactLongsReader<Self, 256> {
+        // SAFETY: It is sure that the array is not empty.
+        unsafe { GapCompactLongsReader::new(self, 9).unwrap_unchecked() }
+    }
+
+    fn write_entry<W: ProtocolWriter>(self, key: &str, writer: &mut W) -> anyhow::Result<()> {
+        12i8.write(writer)?;
+        write_nbt_string(key, writer)?;
+        match self {
+            BorrowedLongArray::Raw(raw) => {
+                (CHUNK_DATA_HEIGHT_MAP_LONGS as i32).write(writer)?; // the length of raw
+                writer.write_bytes(raw);
+                Ok(())
+            }
+            BorrowedLongArray::Longs(array) => LengthProvidedArray::<i32, i32, u64, u64>::write_variant(array, writer),
+        }
+    }
+}
+
+/// Iterates over heights of `MOTION_BLOCKING`
+impl<'a> IntoIterator for ChunkDataHeightMap<'a> {
+    type Item = u64;
+    type IntoIter = GapCompactLongsReader<BorrowedLongArray<'a>, 256>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.motion_blocking.heights()
+    }
+}
+
+impl<'a> ChunkDataHeightMap<'a> {
+    /// Height maps must be packed by [`GapCompactLongsWriter`] with 9 bits per entry
+    pub const fn new(
+        motion_blocking: &'a [u64; CHUNK_DATA_HEIGHT_MAP_LONGS],
+        world_surface: Option<&'a [u64; CHUNK_DATA_HEIGHT_MAP_LONGS]>,
+    ) -> Self {
+        Self {
+            motion_blocking: BorrowedLongArray::Longs(motion_blocking),
+            world_surface: match world_surface {
+                Some(world_surface) => Some(BorrowedLongArray::Longs(world_surface)),
+                None => None,
+            },
+        }
+    }
+
+    /// # Safety.
+    /// The caller must ensure that the length of data slice is 37 * 8
+    pub const unsafe fn new_raw(data: &'a [u8]) -> Self {
+        debug_assert!(data.len() == CHUNK_DATA_HEIGHT_MAP_LONGS * 8);
+        Self { motion_blocking: BorrowedLongArray::Raw(data), world_surface: None }
+    }
+
+    /// # Safety.
+    /// The caller must ensure that the length of data is 37
+    pub const unsafe fn new_longs(data: &'a [u64]) -> Self {
+        debug_assert!(data.len() == CHUNK_DATA_HEIGHT_MAP_LONGS);
+        Self { motion_blocking: BorrowedLongArray::Longs(data), world_surface: None }
+    }
+
+    pub fn world_surface(&self) -> Option<GapCompactLongsReader<BorrowedLongArray<'a>, 256>> {
+        self.world_surface.map(BorrowedLongArray::heights)
+    }
+}
+
+impl<'a> ProtocolSize for ChunkDataHeightMap<'a> {
+    const SIZE: Range<u32> = Nbt::SIZE;
+}
+
+impl<'a> ProtocolReadable<'a> for ChunkDataHeightMap<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        read_compound_enter(cursor)?;
+        let mut motion_blocking = None;
+        let mut world_surface = None;
+        loop {
+            let id = i8::read(cursor)?;
+            if id == 0 { break; }
+            let name = read_nbt_string(cursor)?;
+            let value = read_nbt_tag(id, cursor)?;
+            let target = match name.as_ref() {
+                CHUNK_DATA_HEIGHT_MAP_KEY => &mut motion_blocking,
+                CHUNK_DATA_WORLD_SURFACE_KEY => &mut world_surface,
+                _ => continue,
+            };
+            match value {
+                NbtElement::LongArray(data) if data.len() == CHUNK_DATA_HEIGHT_MAP_LONGS * 8 => *target = Some(BorrowedLongArray::Raw(data)),
+                _ => return Err(ProtocolError::Any(anyhow::Error::msg(format!("{} must be NbtLongArray with exactly 37 length", name)))),
+            }
+        }
+        match motion_blocking {
+            Some(motion_blocking) => Ok(Self { motion_blocking, world_surface }),
+            None => Err(ProtocolError::Any(anyhow::Error::msg("MOTION_BLOCKING is not present"))),
+        }
+    }
+}
+
+impl<'a> ProtocolWritable for ChunkDataHeightMap<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        write_compound_enter(writer)?;
+        self.motion_blocking.write_entry(CHUNK_DATA_HEIGHT_MAP_KEY, writer)?;
+        if let Some(world_surface) = self.world_surface {
+            world_surface.write_entry(CHUNK_DATA_WORLD_SURFACE_KEY, writer)?;
+        }
+        0i8.write(writer)
+    }
+}
+
+pub trait PalettedContainerBitsDeterminer {
+    /// Longer entries are read as direct values by the vanilla client
+    const MAX_INDIRECT_BITS: u8;
+
+    fn get(values: usize) -> u8;
+}
+
+#[derive(Clone, Debug)]
+pub struct PalettedContainer<T, const MAX_VALUE: i32, const LENGTH: usize> {
+    inner: PalettedContainerInner<LENGTH>,
+    _marker: PhantomData<T>,
+}
+
+#[derive(Clone, Debug)]
+enum PalettedContainerInner<const LENGTH: usize> {
+    Single(i32),
+    Indirect(Vec<i32>, Box<[i32; LENGTH]>),
+    Direct(Box<[i32; LENGTH]>),
+}
+
+impl<T, const MAX_VALUE: i32, const LENGTH: usize> PalettedContainer<T, MAX_VALUE, LENGTH>
+    where
+        T: PalettedContainerBitsDeterminer {
+    const DIRECT_START: u8 = const_log2_ceil(MAX_VALUE as u64) as u8;
+
+    pub fn new_direct(values: Box<[i32; LENGTH]>) -> Self {
+        Self {
+            inner: PalettedContainerInner::Direct(values),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn new_indirect(values: Vec<i32>, indexes: Box<[i32; LENGTH]>) -> Self {
+        Self {
+            inner: PalettedContainerInner::Indirect(values, indexes),
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn new_single(value: i32) -> Self {
+        Self {
+            inner: PalettedContainerInner::Single(value),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the value at the index, panics if the index is out of bounds
+    pub fn get(&self, index: usize) -> i32 {
+        assert!(index < LENGTH);
+        match &self.inner {
+            PalettedContainerInner::Single(value) => *value,
+            PalettedContainerInner::Indirect(values, indexes) => values[indexes[index] as usize],
+            PalettedContainerInner::Direct(values) => values[index],
+        }
+    }
+
+    /// Sets the value at the index, the palette is grown (and replaced by direct values if it is too big) when needed
+    pub fn set(&mut self, index: usize, value: i32) {
+        assert!(index < LENGTH);
+        match &mut self.inner {
+            PalettedContainerInner::Single(single) if *single == value => {}
+            PalettedContainerInner::Single(single) => {
+                let mut indexes = Box::new([0; LENGTH]);
+                indexes[index] = 1;
+                self.inner = PalettedContainerInner::Indirect(vec![*single, value], indexes);
+            }
+            PalettedContainerInner::Indirect(values, indexes) => match values.iter().position(|current| *current == value) {
+                Some(position) => indexes[index] = position as i32,
+                None if T::get(values.len() + 1) <= T::MAX_INDIRECT_BITS => {
+                    values.push(value);
+                    indexes[index] = values.len() as i32 - 1;
+                }
+                None => {
+                    let mut direct = Box::new([0; LENGTH]);
+                    for (direct, current) in direct.iter_mut().zip(indexes.iter()) {
+                        *direct = values[*current as usize];
+                    }
+                    direct[index] = value;
+                    self.inner = PalettedContainerInner::Direct(direct);
+                }
+            },
+            PalettedContainerInner::Direct(values) => values[index] = value,
+        }
+    }
+}
+
+impl<T, const MAX_VALUE: i32, const LENGTH: usize> ProtocolSize for PalettedContainer<T, MAX_VALUE, LENGTH> {
+    const SIZE: Range<u32> = u8::SIZE.start + VarInt::SIZE.start..u32::MAX;
+}
+
+impl<T, const MAX_VALUE: i32, const LENGTH: usize> PalettedContainer<T, MAX_VALUE, LENGTH>
+    where
+        T: PalettedContainerBitsDeterminer {
+    const MAX_BITS: u8 = {
+        let result = const_log2_ceil(MAX_VALUE as u64) as u8;
+        assert!(result <= 64);
+        result
+    };
+}
+
+impl<T, const MAX_VALUE: i32, const LENGTH: usize> ProtocolWritable for PalettedContainer<T, MAX_VALUE, LENGTH>
+    where
+        T: PalettedContainerBitsDeterminer {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self.inner {
+            PalettedContainerInner::Single(single) => {
+                0u8.write(writer)?;
+                VarInt::write_variant(&single, writer)?;
+                VarInt::write_variant(&0, writer)
+            }
+            PalettedContainerInner::Indirect(ref values, ref indexes) if T::get(values.len()) > T::MAX_INDIRECT_BITS => {
+                Self::MAX_BITS.write(writer)?;
+                VarInt::write_variant(&(unsafe { compact_longs_array_length(LENGTH, Self::MAX_BITS) } as i32), writer)?;
+                GapCompactLongsWriter::checked(writer, Self::MAX_BITS)?
+                    .push_all_and_finish(indexes.iter().map(|index| values[*index as usize] as u64))
+            }
+            PalettedContainerInner::Indirect(ref values, ref indexes) => {
+                let bits_per_entry = T::get(values.len());
+                bits_per_entry.write(writer)?;
+                LengthProvidedArray::<i32, VarInt, i32, i32>::write_variant(values, writer)?;
+                VarInt::write_variant(&(unsafe { compact_longs_array_length(LENGTH, bits_per_entry) } as i32), writer)?;
+                GapCompactLongsWriter::checked(writer, bits_per_entry)?.push_all_and_finish(indexes.iter().map(|val| *val as u64))
+            }
+            PalettedContainerInner::Direct(ref direct) => {
+                Self::MAX_BITS.write(writer)?;
+                VarInt::write_variant(&(unsafe { compact_longs_array_length(LENGTH, Self::MAX_BITS) } as i32), writer)?;
+                GapCompactLongsWriter::checked(writer, Self::MAX_BITS)?.push_all_and_finish(direct.iter().map(|val| *val as u64))
+            }
+        }
+    }
+}
+
+impl<'a, T, const MAX_VALUE: i32, const LENGTH: usize> ProtocolReadable<'a> for PalettedContainer<T, MAX_VALUE, LENGTH>
+    where
+        T: PalettedContainerBitsDeterminer + 'a {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let bits = u8::read(cursor)?;
+        Ok(if bits == 0 {
+            let single = VarInt::read_variant(cursor)?;
+            let _: i32 = VarInt::read_variant(cursor)?;
+            Self::new_single(single)
+        } else if bits <= T::MAX_INDIRECT_BITS {
+            let values = LengthProvidedArray::<i32, VarInt, i32, i32>::read_variant(cursor)?;
+            let count: i32 = VarInt::read_variant(cursor)?;
+            // It is said that count is ignored by vanilla client (should we ignore it also and calculate count by ourselves?)
+            debug_assert!(count == unsafe { compact_longs_array_length(LENGTH, bits) as i32 });
+            let indexes_iter: GapCompactLongsReader<_, LENGTH> = unsafe {
+                GapCompactLongsReader::new(
+                    ProtocolCursorIterator::<'_, 'a, _, _, u64, u64>::new(
+                        cursor,
+                        ProtocolCursorIteratorCountLimiter { count: count as _ }),
+                    bits,
+                )
+            }
+                .ok_or(ProtocolError::Any(anyhow::Error::msg("Empty array in paletted container (indirect variant)")))?;
+            Self::new_indirect(
+                values,
+                Box::new(
+                    indexes_iter
+                        .map(|val| val as i32)
+                        .collect::<Vec<_>>()
+                        .try_into()
+                        .map_err(|_| ProtocolError::Any(anyhow::Error::msg("Bad length of indexes")))?
+                ),
+            )
+        } else {
+            let count: i32 = VarInt::read_variant(cursor)?;
+            debug_assert!(count == unsafe { compact_longs_array_length(LENGTH, Self::MAX_BITS) as i32 });
+            let iter: GapCompactLongsReader<_, LENGTH> = unsafe {
+                GapCompactLongsReader::new(
+                    ProtocolCursorIterator::<'_, 'a, _, _, u64, u64>::new(
+                        cursor,
+                        ProtocolCursorIteratorCountLimiter { count: count as _ },
+                    ),
+                    Self::MAX_BITS,
+                )
+            }
+                .ok_or(ProtocolError::Any(anyhow::Error::msg("Empty array in paletted container (direct variant)")))?;
+            Self::new_direct(Box::new(
+                iter
+                    .map(|val| val as i32)
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .map_err(|_| ProtocolError::Any(anyhow::Error::msg("Bad length of direct")))?
+            ))
+        })
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ChunkSectionsData<'a> {
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub data: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BlockStatesBits;
+
+#[derive(Clone, Copy, Debug)]
+pub struct BiomesBits;
+
+impl PalettedContainerBitsDeterminer for BlockStatesBits {
+    const MAX_INDIRECT_BITS: u8 = 8;
+
+    fn get(values: usize) -> u8 {
+        match values <= 16 {
+            true => 4,
+            false => const_log2_ceil(values as u64) as u8,
+        }
+    }
+}
+
+impl PalettedContainerBitsDeterminer for BiomesBits {
+    const MAX_INDIRECT_BITS: u8 = 3;
+
+    fn get(values: usize) -> u8 {
+        const_log2_ceil(values as u64) as u8
+    }
+}
+
+#[derive(ProtocolSize, ProtocolWritable, Clone, Debug)]
+pub struct ChunkSectionData {
+    pub block_count: i16,
+    pub block_states: PalettedContainer<BlockStatesBits, { bird_data::BLOCK_STATE_COUNT as i32 }, 4096>,
+    pub biomes: PalettedContainer<BiomesBits, { bird_data::BIOME_COUNT as i32 }, 64>,
+}
+
+// TODO fix issue with ProtocolReadable proc-macro (now it is not working)
+
+impl<'a> ProtocolReadable<'a> for ChunkSectionData {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        Ok(Self {
+            block_count: i16::read(cursor)?,
+            block_states: PalettedContainer::read(cursor)?,
+            biomes: PalettedContainer::read(cursor)?,
+        })
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ChunkData<'a> {
+    pub height_map: ChunkDataHeightMap<'a>,
+    pub chunk_sections: ChunkSectionsData<'a>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BitSet<'a>(BorrowedLongArray<'a>);
+
+impl<'a> BitSet<'a> {
+    fn get_bit_from_words(words: &[u64], index: usize) -> Option<bool> {
+        words.get(Self::get_word_index(index)).map(|val| val & (1u64.overflowing_shl(index as u32).0) != 0)
+    }
+
+    /// # Safety
+    /// The caller must ensure that the length of raw can be divided by 8
+    unsafe fn get_bit_from_raw(raw: &[u8], index: usize) -> Option<bool> {
+        // All longs are inverted so how we can get required byte?
+        // Each byte contains 8 bits
+        // So to get the position of our index we should divide by 8 (or move right by 3)
+        // Because our long is inverted we should get the real position of byte
+        // When our index is 0 (0b0) the real index is 7 (0b111)
+        // When our index is 1 (0b1) the real index is 6 (0b110)
+        // And so on...
+        // So we should reverse last 3 bits
+        // And so we can get the real position of required bit
+        const MASK: usize = usize::MAX.overflowing_shl(3).0;
+        let mut order = (index & MASK) >> 3;
+        order = (order & MASK) | ((!order) & 0b111);
+        raw.get(order).map(|val| val & (1u8.overflowing_shl(index as u32).0) != 0)
+    }
+
+    #[inline]
+    const fn get_word_index(index: usize) -> usize {
+        index >> 6
+    }
+
+    pub const fn new_words(words: &'a [u64]) -> Self {
+        Self(BorrowedLongArray::Longs(words))
+    }
+
+    /// # Safety
+    /// The caller must ensure that the length of raw can be divided by 8
+    pub const unsafe fn new_raw(raw: &'a [u8]) -> Self {
+        debug_assert!(raw.len() % 8 == 0);
+        Self(BorrowedLongArray::Raw(raw))
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        match self.0 {
+            BorrowedLongArray::Raw(raw) => unsafe { Self::get_bit_from_raw(raw, index) },
+            BorrowedLongArray::Longs(words) => Self::get_bit_from_words(words, index)
+        }
+    }
+
+    pub fn get_or_false(&self, index: usize) -> bool {
+        self.get(index).unwrap_or(false)
+    }
+
+    pub fn long_iter(&self) -> impl Iterator<Item=u64> + 'a {
+        self.clone().0
+    }
+}
+
+impl<'a> ProtocolSize for BitSet<'a> {
+    const SIZE: Range<u32> = (0..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for BitSet<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self.0 {
+            BorrowedLongArray::Raw(raw) => {
+                VarInt::write_variant(&(raw.len() as i32 / 8), writer)?;
+                writer.write_bytes(raw);
+                Ok(())
+            }
+            BorrowedLongArray::Longs(longs) =>
+                LengthProvidedArray::<i32, VarInt, u64, u64>::write_variant(longs, writer),
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for BitSet<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let length: i32 = VarInt::read_variant(cursor)?;
+        Ok(Self(BorrowedLongArray::Raw(cursor.take_bytes((length * 8) as usize)?)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OwnedBitSet {
+    pub words: Vec<u64>,
+}
+
+impl OwnedBitSet {
+    pub fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    pub fn get_bit_set(&self) -> BitSet {
+        BitSet::new_words(&self.words)
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        BitSet::get_bit_from_words(&self.words, index)
+    }
+
+    pub fn set(&mut self, index: usize) {
+        let word_index = BitSet::get_word_index(index);
+        if word_index >= self.words.len() {
+            self.words.resize(word_index + 1, 0);
+        }
+        self.words[word_index] |= 1u64.overflowing_shl(index as u32).0
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        let word_index = BitSet::get_word_index(index);
+        if word_index < self.words.len() {
+            self.words[word_index] &= !(1u64.overflowing_shl(index as u32).0);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LightArray<'a> {
+    // TODO change it to &'a [u8; 2048]
+    bytes: &'a [u8],
+}
+
+impl<'a> ProtocolSize for LightArray<'a> {
+    const SIZE: Range<u32> = (VarInt::SIZE.start + 2048..VarInt::SIZE.end + 2048);
+}
+
+impl<'a> ProtocolWritable for LightArray<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&2048, writer)?;
+        writer.write_bytes(self.bytes);
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for LightArray<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let length: i32 = VarInt::read_variant(cursor)?;
+        if length != 2048i32 {
+            return Err(ProtocolError::Any(anyhow::Error::msg("The length of light array is not 2048")));
+        }
+        Ok(Self { bytes: cursor.take_bytes(2048)? })
+    }
+}
+
+impl<'a> LightArray<'a> {
+    const unsafe fn get_index(position: Vector3D<u8>) -> (usize, bool) {
+        debug_assert!(position.x < 16);
+        debug_assert!(position.y < 16);
+        debug_assert!(position.z < 16);
+        let index = ((position.y as usize) << 8) | ((position.z as usize) << 4) | (position.x as usize);
+        (index >> 1, index & 0x1 == 0)
+    }
+
+    const unsafe fn get_from_array(data: &[u8], position: Vector3D<u8>) -> u8 {
+        let (index, right) = Self::get_index(position);
+        match right {
+            true => data[index] & 0xf,
+            false => (data[index] & 0xf0) >> 4,
+        }
+    }
+
+    /// # Safety
+    /// The caller must ensure that the length of bytes is 2048
+    pub const unsafe fn new(bytes: &'a [u8]) -> Self {
+        debug_assert!(bytes.len() == 2048);
+        Self { bytes }
+    }
+
+    pub const fn get_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// # Safety
+    /// The caller must ensure that each parameter is less than 16
+    pub const unsafe fn get(&self, position: Vector3D<u8>) -> u8 {
+        Self::get_from_array(self.bytes, position)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OwnedLightArray {
+    data: [u8; 2048],
+    // We are counting not empty bytes
+    not_empty: u16,
+}
+
+impl OwnedLightArray {
+    pub const fn new() -> Self {
+        Self { data: [0; 2048], not_empty: 0 }
+    }
+
+    /// # Safety
+    /// The caller must ensure that each parameter is less than 16
+    pub unsafe fn set(&mut self, position: Vector3D<u8>, value: u8) {
+        debug_assert!(value < 16);
+        let (index, right) = LightArray::get_index(position);
+        let res = match right {
+            true => self.data[index] & 0xf0 | value,
+            false => self.data[index] & 0x0f | (value << 4),
+        };
+        if self.data[index] != 0 { self.not_empty -= 1 };
+        if res != 0 { self.not_empty += 1 };
+        self.data[index] = res;
+    }
+
+    /// # Safety
+    /// The caller must ensure that each parameter is less than 16
+    pub const unsafe fn get(&self, position: Vector3D<u8>) -> u8 {
+        LightArray::get_from_array(&self.data, position)
+    }
+
+    pub const fn as_light_array(&self) -> LightArray {
+        unsafe { LightArray::new(&self.data) }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.not_empty == 0
+    }
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct LightData<'a> {
+    pub trust_edges: bool,
+    pub sky_light_mask: BitSet<'a>,
+    pub block_light_mask: BitSet<'a>,
+    pub empty_sky_light_mask: BitSet<'a>,
+    pub empty_block_light_mask: BitSet<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, LightArray<'a>, LightArray<'a>>")]
+    pub sky_light_arrays: Cow<'a, [LightArray<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, LightArray<'a>, LightArray<'a>>")]
+    pub block_light_arrays: Cow<'a, [LightArray<'a>]>,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PackedBlockChunkXZ {
+    #[bits(4)]
+    pub x: u8,
+    #[bits(4)]
+    pub z: u8,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ChunkDataAndUpdateLightBlockEntity<'a> {
+    pub xz: PackedBlockChunkXZ,
+    pub y: i16,
+    #[bp(variant = VarInt)]
+    pub ty: i32,
+    #[bp(variant = NbtBytes)]
+    pub data: &'a [u8],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x20, state = Play, bound = Client)]
+pub struct ChunkDataAndUpdateLightPS2C<'a> {
+    pub chunk: Vector2D<i32>,
+    pub chunk_data: ChunkData<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, ChunkDataAndUpdateLightBlockEntity<'a>, ChunkDataAndUpdateLightBlockEntity<'a>>")]
+    pub block_entities: Cow<'a, [ChunkDataAndUpdateLightBlockEntity<'a>]>,
+    pub light_data: LightData<'a>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SmokeDirection {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl TryFrom<u8> for SmokeDirection {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SmokeDirection::Down),
+            1 => Ok(SmokeDirection::Up),
+            2 => Ok(SmokeDirection::North),
+            3 => Ok(SmokeDirection::South),
+            4 => Ok(SmokeDirection::West),
+            5 => Ok(SmokeDirection::East),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Sounds and particles of `WorldEventPS2C` with their data
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldEvent {
+    // Sounds
+    Dispense,
+    FailedDispense,
+    DispenserShoots,
+    EnderEyeLaunches,
+    FireworkShots,
+    IronDoorOpens,
+    WoodenDoorOpens,
+    WoodenTrapdoorOpens,
+    FenceGateOpens,
+    FireExtinguishes,
+    /// Item id of the music disc, id of an item which is not a disc stops the music
+    PlayRecord { record_id: i32 },
+    IronDoorCloses,
+    WoodenDoorCloses,
+    WoodenTrapDoorCloses,
+    FenceGateCloses,
+    GhastWarns,
+    GhastShoots,
+    EnderDragonShoots,
+    BlazeShoots,
+    ZombieAttacksWoodenDoor,
+    ZombieAttacksIronDoor,
+    ZombieBreaksWoodenDoor,
+    WitherBreaksBlock,
+    WitherSpawns,
+    WitherShoots,
+    BatTakesOff,
+    ZombieInfects,
+    ZombieVillagerConverts,
+    EnderDragonDeath,
+    AnvilDestroy,
+    AnvilUse,
+    AnvilLand,
+    PortalTravel,
+    ChorusFlowerGrown,
+    ChorusFlowerDeath,
+    BrewingStandBrew,
+    IronTrapdoorOpens,
+    IronTrapdoorCloses,
+    OverworldEndPortalCreates,
+    PhantomBites,
+    ZombieConvertsToDrowned,
+    HuskConvertsToZombie,
+    GrindstoneUsed,
+    BookPageTurned,
+    SmithingTableUsed,
+    PointedDripstoneLands,
+    PointedDripstoneDripsLavaIntoCauldron,
+    PointedDripstoneDripsWaterIntoCauldron,
+    SkeletonConvertsToStray,
+    // 1048
+    // Particles
+    ComposterComposts,
+    LavaConvertsBlock,
+    RedstoneTorchBurnsOut,
+    EnderEyePlace,
+    /// Particles of the fluid which is dripping from the pointed dripstone
+    PointedDripstoneDrips,
+    // 1504
+    Smoke { direction: SmokeDirection },
+    BlockBreak { block_state: i32 },
+    SplashPotion { color: i32 },
+    EyeOfEnderBreak,
+    MobSpawn,
+    BonemealParticles { amount: i32 },
+    DragonBreath,
+    InstantSplashPotion { color: i32 },
+    EnderDragonDestroysBlock,
+    WetSpongeVaporizesInNether,
+    // 2009
+    EndGatewaySpawn,
+    EnderDragonGrowl,
+    ElectricSpark,
+    CopperApplyWax,
+    CopperRemoveWax,
+    CopperScrapeOxidation,
+    SculkCharge { data: i32 },
+    SculkShriekerShrieks, // 3007
+}
+
+impl WorldEvent {
+    pub fn new(id: i32, value: i32) -> Option<Self> {
+        Some(match id {
+            1000 => WorldEvent::Dispense,
+            1001 => WorldEvent::FailedDispense,
+            1002 => WorldEvent::DispenserShoots,
+            1003 => WorldEvent::EnderEyeLaunches,
+            1004 => WorldEvent::FireworkShots,
+            1005 => WorldEvent::IronDoorOpens,
+            1006 => WorldEvent::WoodenDoorOpens,
+            1007 => WorldEvent::WoodenTrapdoorOpens,
+            1008 => WorldEvent::FenceGateOpens,
+            1009 => WorldEvent::FireExtinguishes,
+            1010 => WorldEvent::PlayRecord { record_id: value },
+            1011 => WorldEvent::IronDoorCloses,
+            1012 => WorldEvent::WoodenDoorCloses,
+            1013 => WorldEvent::WoodenTrapDoorCloses,
+            1014 => WorldEvent::FenceGateCloses,
+            1015 => WorldEvent::GhastWarns,
+            1016 => WorldEvent::GhastShoots,
+            1017 => WorldEvent::EnderDragonShoots,
+            1018 => WorldEvent::BlazeShoots,
+            1019 => WorldEvent::ZombieAttacksWoodenDoor,
+            1020 => WorldEvent::ZombieAttacksIronDoor,
+            1021 => WorldEvent::ZombieBreaksWoodenDoor,
+            1022 => WorldEvent::WitherBreaksBlock,
+            1023 => WorldEvent::WitherSpawns,
+            1024 => WorldEvent::WitherShoots,
+            1025 => WorldEvent::BatTakesOff,
+            1026 => WorldEvent::ZombieInfects,
+            1027 => WorldEvent::ZombieVillagerConverts,
+            1028 => WorldEvent::EnderDragonDeath,
+            1029 => WorldEvent::AnvilDestroy,
+            1030 => WorldEvent::AnvilUse,
+            1031 => WorldEvent::AnvilLand,
+            1032 => WorldEvent::PortalTravel,
+            1033 => WorldEvent::ChorusFlowerGrown,
+            1034 => WorldEvent::ChorusFlowerDeath,
+            1035 => WorldEvent::BrewingStandBrew,
+            1036 => WorldEvent::IronTrapdoorOpens,
+            1037 => WorldEvent::IronTrapdoorCloses,
+            1038 => WorldEvent::OverworldEndPortalCreates,
+            1039 => WorldEvent::PhantomBites,
+            1040 => WorldEvent::ZombieConvertsToDrowned,
+            1041 => WorldEvent::HuskConvertsToZombie,
+            1042 => WorldEvent::GrindstoneUsed,
+            1043 => WorldEvent::BookPageTurned,
+            1044 => WorldEvent::SmithingTableUsed,
+            1045 => WorldEvent::PointedDripstoneLands,
+            1046 => WorldEvent::PointedDripstoneDripsLavaIntoCauldron,
+            1047 => WorldEvent::PointedDripstoneDripsWaterIntoCauldron,
+            1048 => WorldEvent::SkeletonConvertsToStray,
+            1500 => WorldEvent::ComposterComposts,
+            1501 => WorldEvent::LavaConvertsBlock,
+            1502 => WorldEvent::RedstoneTorchBurnsOut,
+            1503 => WorldEvent::EnderEyePlace,
+            1504 => WorldEvent::PointedDripstoneDrips,
+            2000 => WorldEvent::Smoke { direction: SmokeDirection::try_from(value as u8).ok()? },
+            2001 => WorldEvent::BlockBreak { block_state: value },
+            2002 => WorldEvent::SplashPotion { color: value },
+            2003 => WorldEvent::EyeOfEnderBreak,
+            2004 => WorldEvent::MobSpawn,
+            2005 => WorldEvent::BonemealParticles { amount: value },
+            2006 => WorldEvent::DragonBreath,
+            2007 => WorldEvent::InstantSplashPotion { color: value },
+            2008 => WorldEvent::EnderDragonDestroysBlock,
+            2009 => WorldEvent::WetSpongeVaporizesInNether,
+            3000 => WorldEvent::EndGatewaySpawn,
+            3001 => WorldEvent::EnderDragonGrowl,
+            3002 => WorldEvent::ElectricSpark,
+            3003 => WorldEvent::CopperApplyWax,
+            3004 => WorldEvent::CopperRemoveWax,
+            3005 => WorldEvent::CopperScrapeOxidation,
+            3006 => WorldEvent::SculkCharge { data: value },
+            3007 => WorldEvent::SculkShriekerShrieks,
+            _ => None?
+        })
+    }
+
+    pub fn get_id_value(&self) -> (i32, i32) {
+        match self {
+            WorldEvent::Dispense => (1000, 0),
+            WorldEvent::FailedDispense => (1001, 0),
+            WorldEvent::DispenserShoots => (1002, 0),
+            WorldEvent::EnderEyeLaunches => (1003, 0),
+            WorldEvent::FireworkShots => (1004, 0),
+            WorldEvent::IronDoorOpens => (1005, 0),
+            WorldEvent::WoodenDoorOpens => (1006, 0),
+            WorldEvent::WoodenTrapdoorOpens => (1007, 0),
+            WorldEvent::FenceGateOpens => (1008, 0),
+            WorldEvent::FireExtinguishes => (1009, 0),
+            WorldEvent::PlayRecord { record_id } => (1010, *record_id),
+            WorldEvent::IronDoorCloses => (1011, 0),
+            WorldEvent::WoodenDoorCloses => (1012, 0),
+            WorldEvent::WoodenTrapDoorCloses => (1013, 0),
+            WorldEvent::FenceGateCloses => (1014, 0),
+            WorldEvent::GhastWarns => (1015, 0),
+            WorldEvent::GhastShoots => (1016, 0),
+            WorldEvent::EnderDragonShoots => (1017, 0),
+            WorldEvent::BlazeShoots => (1018, 0),
+            WorldEvent::ZombieAttacksWoodenDoor => (1019, 0),
+            WorldEvent::ZombieAttacksIronDoor => (1020, 0),
+            WorldEvent::ZombieBreaksWoodenDoor => (1021, 0),
+            WorldEvent::WitherBreaksBlock => (1022, 0),
+            WorldEvent::WitherSpawns => (1023, 0),
+            WorldEvent::WitherShoots => (1024, 0),
+            WorldEvent::BatTakesOff => (1025, 0),
+            WorldEvent::ZombieInfects => (1026, 0),
+            WorldEvent::ZombieVillagerConverts => (1027, 0),
+            WorldEvent::EnderDragonDeath => (1028, 0),
+            WorldEvent::AnvilDestroy => (1029, 0),
+            WorldEvent::AnvilUse => (1030, 0),
+            WorldEvent::AnvilLand => (1031, 0),
+            WorldEvent::PortalTravel => (1032, 0),
+            WorldEvent::ChorusFlowerGrown => (1033, 0),
+            WorldEvent::ChorusFlowerDeath => (1034, 0),
+            WorldEvent::BrewingStandBrew => (1035, 0),
+            WorldEvent::IronTrapdoorOpens => (1036, 0),
+            WorldEvent::IronTrapdoorCloses => (1037, 0),
+            WorldEvent::OverworldEndPortalCreates => (1038, 0),
+            WorldEvent::PhantomBites => (1039, 0),
+            WorldEvent::ZombieConvertsToDrowned => (1040, 0),
+            WorldEvent::HuskConvertsToZombie => (1041, 0),
+            WorldEvent::GrindstoneUsed => (1042, 0),
+            WorldEvent::BookPageTurned => (1043, 0),
+            WorldEvent::SmithingTableUsed => (1044, 0),
+            WorldEvent::PointedDripstoneLands => (1045, 0),
+            WorldEvent::PointedDripstoneDripsLavaIntoCauldron => (1046, 0),
+            WorldEvent::PointedDripstoneDripsWaterIntoCauldron => (1047, 0),
+            WorldEvent::SkeletonConvertsToStray => (1048, 0),
+            WorldEvent::ComposterComposts => (1500, 0),
+            WorldEvent::LavaConvertsBlock => (1501, 0),
+            WorldEvent::RedstoneTorchBurnsOut => (1502, 0),
+            WorldEvent::EnderEyePlace => (1503, 0),
+            WorldEvent::PointedDripstoneDrips => (1504, 0),
+            WorldEvent::Smoke { direction } => (2000, (*direction) as i32),
+            WorldEvent::BlockBreak { block_state } => (2001, *block_state),
+            WorldEvent::SplashPotion { color } => (2002, *color),
+            WorldEvent::EyeOfEnderBreak => (2003, 0),
+            WorldEvent::MobSpawn => (2004, 0),
+            WorldEvent::BonemealParticles { amount } => (2005, *amount),
+            WorldEvent::DragonBreath => (2006, 0),
+            WorldEvent::InstantSplashPotion { color } => (2007, *color),
+            WorldEvent::EnderDragonDestroysBlock => (2008, 0),
+            WorldEvent::WetSpongeVaporizesInNether => (2009, 0),
+            WorldEvent::EndGatewaySpawn => (3000, 0),
+            WorldEvent::EnderDragonGrowl => (3001, 0),
+            WorldEvent::ElectricSpark => (3002, 0),
+            WorldEvent::CopperApplyWax => (3003, 0),
+            WorldEvent::CopperRemoveWax => (3004, 0),
+            WorldEvent::CopperScrapeOxidation => (3005, 0),
+            WorldEvent::SculkCharge { data } => (3006, *data),
+            WorldEvent::SculkShriekerShrieks => (3007, 0),
+        }
+    }
+
+    /// Sounds which vanilla plays for all players with the same volume
+    pub const fn is_global(&self) -> bool {
+        matches!(self, WorldEvent::WitherSpawns | WorldEvent::EnderDragonDeath | WorldEvent::OverworldEndPortalCreates)
+    }
+}
+
+#[derive(ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[bp(id = 0x21, state = Play, bound = Client)]
+pub struct WorldEventPS2C {
+    pub event: WorldEvent,
+    pub location: Vector3D<i32>,
+    pub disable_relative_volume: bool,
+}
+
+impl ProtocolSize for WorldEventPS2C {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(i32, BlockPosition, i32, bool);
+}
+
+impl WorldEventPS2C {
+    /// Relative volume is disabled for global events like in vanilla
+    pub const fn new(event: WorldEvent, location: Vector3D<i32>) -> Self {
+        Self { event, location, disable_relative_volume: event.is_global() }
+    }
+}
+
+impl ProtocolWritable for WorldEventPS2C {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let (event, event_data) = self.event.get_id_value();
+        event.write(writer)?;
+        BlockPosition::write_variant(&self.location, writer)?;
+        event_data.write(writer)?;
+        self.disable_relative_volume.write(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for WorldEventPS2C {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let event_id = i32::read(cursor)?;
+        let location = BlockPosition::read_variant(cursor)?;
+        let event_data = i32::read(cursor)?;
+        let disable_relative_volume = bool::read(cursor)?;
+        Ok(Self {
+            event: WorldEvent::new(event_id, event_data)
+                .ok_or_else(|| ProtocolError::Any(anyhow::Error::msg("Bad world event id")))?,
+            location,
+            disable_relative_volume,
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(ProtocolSize, Clone, Copy, Debug, PartialEq)]
+#[bp(variant = VarInt, ty = i32)]
+pub enum Particle<'a> {
+    AmbientEntityEffect,
+    AngryVillager,
+    Block {
+        #[bp(variant = VarInt)]
+        block_state: i32
+    },
+    BlockMarker {
+        #[bp(variant = VarInt)]
+        block_state: i32
+    },
+    Bubble,
+    Cloud,
+    Crit,
+    DamageIndicator,
+    DragonBreath,
+    DrippingLava,
+    FallingLava,
+    LandingLava,
+    DrippingWater,
+    FallingWater,
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+    DustColorTransition {
+        from_red: f32,
+        from_green: f32,
+        from_blue: f32,
+        scale: f32,
+        to_red: f32,
+        to_green: f32,
+        to_blue: f32,
+    },
+    Effect,
+    ElderGuardian,
+    EnchantedHit,
+    Enchant,
+    EndRod,
+    EntityEffect,
+    ExplosionEmitter,
+    Explosion,
+    FallingDust {
+        #[bp(variant = VarInt)]
+        block_state: i32
+    },
+    Firework,
+    Fishing,
+    Flame,
+    SoulFireFlame,
+    Soul,
+    Flash,
+    HappyVillager,
+    Composter,
+    Heart,
+    InstantEffect,
+    Item {
+        slot: Option<Slot<'a>>
+    },
+    Vibration {
+        variant: VibrationVariant<'a>,
+        ticks: i32,
+    },
+    ItemSlime,
+    ItemSnowball,
+    LargeSmoke,
+    Lava,
+    Mycelium,
+    Note,
+    Poof,
+    Portal,
+    Rain,
+    Smoke,
+    Sneeze,
+    Spit,
+    SquidInk,
+    SweepAttack,
+    TotemOfUndying,
+    Underwater,
+    Splash,
+    Witch,
+    BubblePop,
+    CurrentDown,
+    BubbleColumnUp,
+    Nautilus,
+    Dolphin,
+    CampfireCosySmoke,
+    CampfireSignalSmoke,
+    DrippingHoney,
+    FallingHoney,
+    LandingHoney,
+    FallingNectar,
+    FallingSporeBlossom,
+    Ash,
+    CrimsonSpore,
+    WarpedSpore,
+    SporeBlossomAir,
+    DrippingObsidianTear,
+    FallingObsidianTear,
+    LandingObsidianTear,
+    ReversePortal,
+    WhiteAsh,
+    SmallFlame,
+    Snowflake,
+    DrippingDripstoneLava,
+    FallingDripstoneLava,
+    DrippingDripstoneWater,
+    FallingDripstoneWater,
+    GlowSquidInk,
+    Glow,
+    WaxOn,
+    WaxOff,
+    ElectricSpark,
+    Scrape,
+}
+
+impl<'a> Particle<'a> {
+    pub fn read<C: ProtocolCursor<'a>>(id: i32, cursor: &mut C) -> ProtocolResult<Self> {
+        Ok(match id {
+            2 => Self::Block { block_state: VarInt::read_variant(cursor)? },
+            3 => Self::BlockMarker { block_state: VarInt::read_variant(cursor)? },
+            24 => Self::FallingDust { block_state: VarInt::read_variant(cursor)? },
+            14 => Self::Dust {
+                red: f32::read(cursor)?,
+                green: f32::read(cursor)?,
+                blue: f32::read(cursor)?,
+                scale: f32::read(cursor)?.clamp(0.01, 4f32),
+            },
+            15 => Self::DustColorTransition {
+                from_red: f32::read(cursor)?,
+                from_green: f32::read(cursor)?,
+                from_blue: f32::read(cursor)?,
+                scale: f32::read(cursor)?.clamp(0.01, 4f32),
+                to_red: f32::read(cursor)?,
+                to_green: f32::read(cursor)?,
+                to_blue: f32::read(cursor)?,
+            },
+            35 => Self::Item { slot: Option::read(cursor)? },
+            36 => Self::Vibration {
+                variant: match <&'a str>::read(cursor)? {
+                    "minecraft:block" => VibrationVariant::Block { position: BlockPosition::read_variant(cursor)? },
+                    "minecraft:entity" => VibrationVariant::Entity {
+                        entity_id: VarInt::read_variant(cursor)?,
+                        entity_eye_height: f32::read(cursor)?,
+                    },
+                    other => VibrationVariant::Other { source_type: other },
+                },
+                ticks: i32::read(cursor)?,
+            },
+            0..=87 => unsafe {
+                core::mem::transmute({
+                    #[allow(invalid_value)]
+                        let mut arr = MaybeUninit::<[u8; core::mem::size_of::<Self>()]>::uninit().assume_init();
+                    arr[0] = id as u8;
+                    arr
+                })
+            },
+            _ => Err(ProtocolError::Any(anyhow::Error::msg("Bad particle id")))?,
+        })
+    }
+
+    pub const fn get_id(&self) -> i32 {
+        (unsafe { (&*(self as *const Self as *const () as *const [u8; core::mem::size_of::<Self>()]))[0] }) as i32
+    }
+
+    pub fn write_data<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            Self::Block { block_state } => VarInt::write_variant(block_state, writer),
+            Self::BlockMarker { block_state } => VarInt::write_variant(block_state, writer),
+            Self::FallingDust { block_state } => VarInt::write_variant(block_state, writer),
+            Self::Dust { red, green, blue, scale, } => {
+                red.write(writer)?;
+                green.write(writer)?;
+                blue.write(writer)?;
+                scale.write(writer)
+            }
+            Self::DustColorTransition {
+                from_red, from_green, from_blue, scale,
+                to_red, to_green, to_blue,
+            } => {
+                from_red.write(writer)?;
+                from_green.write(writer)?;
+                from_blue.write(writer)?;
+                scale.write(writer)?;
+                to_red.write(writer)?;
+                to_green.write(writer)?;
+                to_blue.write(writer)
+            }
+            Self::Item { slot } => slot.write(writer),
+            Self::Vibration { variant, ticks } => {
+                match variant {
+                    VibrationVariant::Block { position } => {
+                        "minecraft:block".write(writer)?;
+                        BlockPosition::write_variant(position, writer)?
+                    }
+                    VibrationVariant::Entity { entity_id, entity_eye_height } => {
+                        "minecraft:entity".write(writer)?;
+                        VarInt::write_variant(entity_id, writer)?;
+                        entity_eye_height.write(writer)?
+                    }
+                    VibrationVariant::Other { source_type } => source_type.write(writer)?,
+                };
+                ticks.write(writer)
+            }
+            _ => Ok(())
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VibrationVariant<'a> {
+    Block {
+        position: Vector3D<i32>
+    },
+    Entity {
+        entity_id: i32,
+        entity_eye_height: f32,
+    },
+    Other {
+        source_type: &'a str,
+    },
+}
+
+impl<'a> ProtocolSize for VibrationVariant<'a> {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(&str).start..add_protocol_sizes_ty!(&str, Vector3D<i32>).end;
+}
+
+#[derive(ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x22, state = Play, bound = Client)]
+pub struct ParticlePS2C<'a> {
+    pub particle: Particle<'a>,
+    pub long_distance: bool,
+    pub position: Vector3D<f64>,
+    pub offset: Vector3D<f32>,
+    pub max_speed: f32,
+    pub particle_count: i32,
+}
+
+impl<'a> ProtocolSize for ParticlePS2C<'a> {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(Particle, bool, Vector3D<f64>, Vector3D<f32>, f32, i32);
+}
+
+impl<'a> ProtocolWritable for ParticlePS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&self.particle.get_id(), writer)?;
+        self.long_distance.write(writer)?;
+        self.position.write(writer)?;
+        self.offset.write(writer)?;
+        self.max_speed.write(writer)?;
+        self.particle_count.write(writer)?;
+        self.particle.write_data(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for ParticlePS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let particle_id = VarInt::read_variant(cursor)?;
+        Ok(Self {
+            long_distance: bool::read(cursor)?,
+            position: Vector3D::read(cursor)?,
+            offset: Vector3D::read(cursor)?,
+            max_speed: f32::read(cursor)?,
+            particle_count: i32::read(cursor)?,
+            particle: Particle::read(particle_id, cursor)?,
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x23, state = Play, bound = Client)]
+pub struct UpdateLightPS2C<'a> {
+    pub chunk: Vector2D<i32>,
+    pub light_data: LightData<'a>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i8)]
+pub enum PreviousLoginGameMode {
+    #[bp(value = - 1)]
+    None,
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum LoginGameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct LoginDeathLocation<'a> {
+    pub dimension_name: Identifier<'a>,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x24, state = Play, bound = Client)]
+pub struct LoginPS2C<'a> {
+    pub entity_id: i32,
+    pub is_hardcore: bool,
+    pub game_mode: LoginGameMode,
+    pub previous_game_mode: PreviousLoginGameMode,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub dimensions: Cow<'a, [Identifier<'a>]>,
+    #[bp(variant = NbtBytes)]
+    pub registry_codec: &'a [u8],
+    pub dimension_type: Identifier<'a>,
+    pub dimension_name: Identifier<'a>,
+    pub hashed_seed: i64,
+    #[bp(variant = VarInt)]
+    pub max_players: i32,
+    #[bp(variant = VarInt)]
+    pub view_distance: i32,
+    #[bp(variant = VarInt)]
+    pub simulation_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub death_location: Option<LoginDeathLocation<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodec<'a> {
+    #[serde(rename = "minecraft:dimension_type", borrow)]
+    pub dimension_type: LoginRegistryCodecRegistry<'a, LoginRegistryCodecDimension<'a>>,
+    #[serde(rename = "minecraft:worldgen/biome", borrow)]
+    pub worldgen_biome: LoginRegistryCodecRegistry<'a, LoginRegistryCodecWorldgenBiome<'a>>,
+    #[serde(rename = "minecraft:chat_type", borrow)]
+    pub chat_type: LoginRegistryCodecRegistry<'a, LoginRegistryCodecChatType<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecRegistry<'a, T: Clone> {
+    #[serde(rename = "type", borrow)]
+    pub ty: Cow<'a, str>,
+    #[serde(borrow)]
+    pub value: Cow<'a, [LoginRegistryCodecValue<'a, T>]>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecValue<'a, T: Clone> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    pub id: i32,
+    pub element: T,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecDimension<'a> {
+    pub piglin_safe: bool,
+    pub has_raids: bool,
+    pub monster_spawn_light_level: i32,
+    pub monster_spawn_block_light_limit: i32,
+    pub natural: bool,
+    pub ambient_light: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_time: Option<i64>,
+    #[serde(borrow)]
+    pub infiniburn: Cow<'a, str>,
+    pub respawn_anchor_works: bool,
+    pub has_skylight: bool,
+    pub bed_works: bool,
+    #[serde(borrow)]
+    pub effects: Cow<'a, str>,
+    pub min_y: i32,
+    pub height: i32,
+    pub logical_height: i32,
+    pub coordinate_scale: f64,
+    pub ultrawarm: bool,
+    pub has_ceiling: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiome<'a> {
+    /// `none`, `rain` or `snow`
+    #[serde(borrow)]
+    pub precipitation: Cow<'a, str>,
+    pub temperature: f32,
+    pub downfall: f32,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub temperature_modifier: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub effects: LoginRegistryCodecWorldgenBiomeEffects<'a>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeEffects<'a> {
+    pub sky_color: i32,
+    pub water_fog_color: i32,
+    pub fog_color: i32,
+    pub water_color: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foliage_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grass_color: Option<i32>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub grass_color_modifier: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub music: Option<LoginRegistryCodecWorldgenBiomeMusic<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub ambient_sound: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub additions_sound: Option<LoginRegistryCodecWorldgenBiomeAdditionsSound<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub mood_sound: Option<LoginRegistryCodecWorldgenBiomeMoodSound<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub particle: Option<LoginRegistryCodecWorldgenBiomeParticle<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeMusic<'a> {
+    pub replace_current_music: bool,
+    #[serde(borrow)]
+    pub sound: Cow<'a, str>,
+    pub max_delay: i32,
+    pub min_delay: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeAdditionsSound<'a> {
+    #[serde(borrow)]
+    pub sound: Cow<'a, str>,
+    pub tick_chance: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeMoodSound<'a> {
+    #[serde(borrow)]
+    pub sound: Cow<'a, str>,
+    pub tick_delay: i32,
+    pub offset: f64,
+    pub block_search_extent: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeParticle<'a> {
+    pub probability: f32,
+    #[serde(rename = "options", borrow)]
+    pub ty: LoginRegistryCodecWorldgenBiomeParticleType<'a>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecWorldgenBiomeParticleType<'a> {
+    #[serde(rename = "type", borrow)]
+    pub ty: Cow<'a, str>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecChatType<'a> {
+    #[serde(borrow)]
+    pub chat: LoginRegistryCodecChatDecoration<'a>,
+    #[serde(borrow)]
+    pub narration: LoginRegistryCodecChatDecoration<'a>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecChatDecoration<'a> {
+    #[serde(borrow)]
+    pub translation_key: Cow<'a, str>,
+    /// `sender`, `target` or `content`
+    #[serde(borrow)]
+    pub parameters: Cow<'a, [Cow<'a, str>]>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub style: Option<LoginRegistryCodecChatStyle<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoginRegistryCodecChatStyle<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x25, state = Play, bound = Client)]
+pub struct MapDataPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub map_id: i32,
+    pub scale: i8,
+    pub locked: bool,
+    /// `None` keeps icons of the client
+    #[bp(variant = "ProtocolVariantOption<Cow<'a, [MapDataIcon<'a>]>, LengthProvidedArray<i32, VarInt, MapDataIcon<'a>, MapDataIcon<'a>>>")]
+    pub icons: Option<Cow<'a, [MapDataIcon<'a>]>>,
+    /// Colors of the rectangle which is changed, `None` keeps colors of the client
+    #[bp(variant = "ProtocolSizeOption<MapDataColRows<'a>, 1>")]
+    pub col_rows: Option<MapDataColRows<'a>>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct MapDataColRows<'a> {
+    pub columns: u8,
+    pub rows: u8,
+    pub x: i8,
+    pub z: i8,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub data: &'a [u8],
+}
+
+#[derive(BirdNbt, Clone, Debug)]
+pub struct MapDataColRowsDataRoot<'a> {
+    pub data: MapDataColRowsData<'a>,
+    #[bnbt(name = "DataVersion")]
+    pub data_version: Option<i32>,
+}
+
+#[derive(BirdNbt, Clone, Debug)]
+pub struct MapDataColRowsData<'a> {
+    pub scale: i8,
+    pub dimension: Cow<'a, str>,
+    #[bnbt(name = "trackingPosition")]
+    pub tracking_position: bool,
+    #[bnbt(name = "unlimitedTracking")]
+    pub unlimited_tracking: bool,
+    pub locked: bool,
+    #[bnbt(name = "xCenter")]
+    pub x_center: i32,
+    #[bnbt(name = "zCenter")]
+    pub z_center: i32,
+    pub banners: MapDataColRowsBannersData<'a>,
+    pub frames: MapDataColRowsFramesData,
+    #[bnbt(variant = "NbtByteArray")]
+    pub colors: &'a [u8],
+}
+
+#[derive(Clone, Debug)]
+pub enum MapDataColRowsBannersColorData {
+    White,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+}
+
+impl<'a> NbtTag<'a> for MapDataColRowsBannersColorData {
+    const NBT_TAG: u8 = NBT_TAG_STRING;
+
+    fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        write_nbt_str(match self {
+            Self::White => "white",
+            Self::Orange => "orange",
+            Self::Magenta => "magenta",
+            Self::LightBlue => "light_blue",
+            Self::Yellow => "yellow",
+            Self::Lime => "lime",
+            Self::Pink => "pink",
+            Self::Gray => "gray",
+            Self::LightGray => "light_gray",
+            Self::Cyan => "cyan",
+            Self::Purple => "purple",
+            Self::Blue => "blue",
+            Self::Brown => "brown",
+            Self::Green => "green",
+            Self::Red => "red",
+            Self::Black => "black",
+        }, writer)
+    }
+
+    fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        Ok(match Cow::read_nbt(cursor)?.as_ref() {
+            "white" => Self::White,
+            "orange" => Self::Orange,
+            "magenta" => Self::Magenta,
+            "light_blue" => Self::LightBlue,
+            "yellow" => Self::Yellow,
+            "lime" => Self::Lime,
+            "pink" => Self::Pink,
+            "gray" => Self::Gray,
+            "light_gray" => Self::LightGray,
+            "cyan" => Self::Cyan,
+            "purple" => Self::Purple,
+            "blue" => Self::Blue,
+            "brown" => Self::Brown,
+            "green" => Self::Green,
+            "red" => Self::Red,
+            "black" => Self::Black,
+            _ => Err(ProtocolError::Any(anyhow::Error::msg("Bad color")))?,
+        })
+    }
+
+    fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        Cow::<str>::skip_nbt(cursor, amount)
+    }
+}
+
+#[derive(BirdNbt, Clone, Debug)]
+pub struct MapDataColRowsBannersData<'a> {
+    #[bnbt(name = "Color")]
+    pub color: MapDataColRowsBannersColorData,
+    #[bnbt(name = "Name")]
+    pub name: Option<Cow<'a, str>>,
+    #[bnbt(name = "Pos")]
+    pub pos: Vector3D<i32>,
+}
+
+#[derive(BirdNbt, Clone, Debug)]
+pub struct MapDataColRowsFramesData {
+    #[bnbt(name = "EntityId")]
+    pub entity_id: i32,
+    #[bnbt(name = "Rotation")]
+    pub rotation: i32,
+    #[bnbt(name = "Pos")]
+    pub pos: Vector3D<i32>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(variant = VarInt, ty = i32)]
+pub enum MapDataIconType {
+    WhiteArrow,
+    GreenArrow,
+    RedArrow,
+    BlueArrow,
+    WhiteCross,
+    RedPointer,
+    WhiteCircle,
+    SmallWhiteCircle,
+    Mansion,
+    Temple,
+    WhiteBanner,
+    OrangeBanner,
+    MagentaBanner,
+    LightBlueBanner,
+    YellowBanner,
+    LimeBanner,
+    PinkBanner,
+    GrayBanner,
+    LightGrayBanner,
+    CyanBanner,
+    PurpleBanner,
+    BlueBanner,
+    BrownBanner,
+    GreenBanner,
+    RedBanner,
+    BlackBanner,
+    TreasureMarker,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct MapDataIcon<'a> {
+    pub ty: MapDataIconType,
+    pub x: i8,
+    pub z: i8,
+    pub direction: i8,
+    pub display_name: Option<Component<'a>>,
+}
+
+/// Trade list of the merchant window, it is sent after the window is opened
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x26, state = Play, bound = Client)]
+pub struct MerchantOffersPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub window_id: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, MerchantOffersTrades<'a>, MerchantOffersTrades<'a>>")]
+    pub trades: Cow<'a, [MerchantOffersTrades<'a>]>,
+    /// From 1 to 5, it is shown only for regular villagers
+    #[bp(variant = VarInt)]
+    pub villager_level: i32,
+    #[bp(variant = VarInt)]
+    pub experience: i32,
+    /// Wandering traders don't have the level and the experience bar
+    pub is_regular_villager: bool,
+    pub can_restock: bool,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct MerchantOffersTrades<'a> {
+    pub input_item1: Option<Slot<'a>>,
+    pub output_item: Option<Slot<'a>>,
+    /// `None` if the trade has only one input, it is an empty slot and not an absent field
+    pub input_item2: Option<Slot<'a>>,
+    pub disabled: bool,
+    pub already_used: i32,
+    pub maximum_uses: i32,
+    pub xp: i32,
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x27, state = Play, bound = Client)]
+pub struct UpdateEntityPositionPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub delta: Vector3D<i16>,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x28, state = Play, bound = Client)]
+pub struct UpdateEntityPositionAndRotationPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub delta: Vector3D<i16>,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x29, state = Play, bound = Client)]
+pub struct UpdateEntityRotationPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2A, state = Play, bound = Client)]
+pub struct MoveVehiclePS2C {
+    pub pos: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2B, state = Play, bound = Client)]
+pub struct OpenBookPS2C {
+    pub hand: Hand,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(variant = VarInt, ty = i32)]
+pub enum InventorySizeType {
+    Inventory1,
+    Inventory2,
+    Inventory3,
+    Inventory4,
+    Inventory5,
+    Inventory6,
+    Inventory3x3,
+    Anvil,
+    Beacon,
+    BlastFurnace,
+    BrewingStand,
+    Crafting,
+    Enchantment,
+    Furnace,
+    Grindstone,
+    Hopper,
+    Lectern,
+    Loom,
+    Merchant,
+    ShulkerBox,
+    Smithing,
+    Smoker,
+    Cartography,
+    Stonecutter,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2C, state = Play, bound = Client)]
+pub struct OpenScreenPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub window_id: i32,
+    pub window_type: InventorySizeType,
+    pub window_title: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2D, state = Play, bound = Client)]
+pub struct OpenSignEditorPS2C {
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2E, state = Play, bound = Client)]
+pub struct PingPS2C {
+    pub id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x2F, state = Play, bound = Client)]
+pub struct PlaceGhostRecipePS2C<'a> {
+    pub window_id: i8,
+    pub recipe: Identifier<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x30, state = Play, bound = Client)]
+pub struct PlayerAbilitiesPS2C {
+    pub flags: PlayerAbilitiesFlags,
+    pub flying_speed: f32,
+    pub view_modifier: f32,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PlayerAbilitiesFlags {
+    pub invulnerable: bool,
+    pub flying: bool,
+    pub allow_flying: bool,
+    pub creative_mode: bool,
+    #[bits(4)]
+    _gap: u8,
+}
+
+pub const MESSAGE_SIGNATURE_LENGTH: usize = 256;
+
+/// Message signature which is either sent in full or referenced by the index in the client signature cache
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PackedMessageSignature<'a> {
+    Cached(i32),
+    Full(&'a [u8; MESSAGE_SIGNATURE_LENGTH]),
+}
+
+impl<'a> ProtocolSize for PackedMessageSignature<'a> {
+    const SIZE: Range<u32> = VarInt::SIZE.start..VarInt::SIZE.end + MESSAGE_SIGNATURE_LENGTH as u32;
+}
+
+impl<'a> ProtocolWritable for PackedMessageSignature<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            // 0 means that the full signature follows, otherwise the cache index + 1
+            PackedMessageSignature::Cached(index) => VarInt::write_variant(&(index + 1), writer),
+            PackedMessageSignature::Full(signature) => {
+                VarInt::write_variant(&0, writer)?;
+                ConstLengthRawArray::<u8, MESSAGE_SIGNATURE_LENGTH>::write_variant(signature, writer)
+            }
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for PackedMessageSignature<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        Ok(match VarInt::read_variant(cursor)? {
+            0 => PackedMessageSignature::Full(ConstLengthRawArray::<u8, MESSAGE_SIGNATURE_LENGTH>::read_variant(cursor)?),
+            index => PackedMessageSignature::Cached(index - 1),
+        })
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(variant = VarInt, ty = i32)]
+pub enum PlayerChatFilter<'a> {
+    PassThrough,
+    FullyFiltered,
+    PartiallyFiltered { bits: BitSet<'a> },
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x31, state = Play, bound = Client)]
+pub struct PlayerChatMessagePS2C<'a> {
+    pub sender: Uuid,
+    #[bp(variant = VarInt)]
+    pub index: i32,
+    #[bp(variant = "ProtocolVariantOption<&'a [u8; 256], ConstLengthRawArray<u8, 256>>")]
+    pub message_signature_bytes: Option<&'a [u8; 256]>,
+    pub message: &'a str,
+    pub timestamp: i64,
+    pub salt: i64,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, PackedMessageSignature<'a>, PackedMessageSignature<'a>>")]
+    pub previous_messages: Cow<'a, [PackedMessageSignature<'a>]>,
+    pub unsigned_content: Option<Component<'a>>,
+    pub filter: PlayerChatFilter<'a>,
+    #[bp(variant = VarInt)]
+    pub chat_ty: i32,
+    pub network_name: Component<'a>,
+    pub network_target_name: Option<Component<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x32, state = Play, bound = Client)]
+pub struct EndCombatPS2C {
+    #[bp(variant = VarInt)]
+    pub duration: i32,
+    pub entity_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x33, state = Play, bound = Client)]
+pub struct EnterCombatPS2C;
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x34, state = Play, bound = Client)]
+pub struct CombatDeathPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub player_id: i32,
+    pub entity_id: i32,
+    pub message: Component<'a>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x35, state = Play, bound = Client)]
+pub struct PlayerInfoRemovePS2C<'a> {
+    #[bp(variant = "LengthProvidedRawArray<i32, VarInt, Uuid, Uuid>")]
+    pub players: Cow<'a, [Uuid]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PlayerInfoUpdateAddAction<'a> {
+    pub name: &'a str,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Property<'a>, Property<'a>>")]
+    pub properties: Cow<'a, [Property<'a>]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PlayerInfoUpdateInitializeChat<'a> {
+    pub chat_session_id: Uuid,
+    pub public_key_expire_time: i64,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub encoded_public_key: &'a [u8],
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub public_key_signature: &'a [u8],
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum PlayerInfoUpdateGameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PlayerInfoUpdateAction<'a> {
+    pub add: Option<PlayerInfoUpdateAddAction<'a>>,
+    pub initialize_chat: Option<PlayerInfoUpdateInitializeChat<'a>>,
+    pub update_game_mode: Option<PlayerInfoUpdateGameMode>,
+    pub update_listed: Option<bool>,
+    pub update_latency: Option<i32>,
+    pub update_display_name: Option<Option<Component<'a>>>,
+}
+
+impl<'a> PlayerInfoUpdateAction<'a> {
+    fn write<W: ProtocolWriter>(&self, self_bits: usize, bit_set: &mut OwnedBitSet, writer: &mut W) -> anyhow::Result<()> {
+        if let Some(ref to_write) = self.add {
+            bit_set.set(self_bits);
+            to_write.write(writer)?;
+        }
+        if let Some(ref to_write) = self.initialize_chat {
+            bit_set.set(self_bits + 1);
+            to_write.write(writer)?;
+        }
+        if let Some(ref to_write) = self.update_game_mode {
+            bit_set.set(self_bits + 2);
+            to_write.write(writer)?;
+        }
+        if let Some(ref to_write) = self.update_listed {
+            bit_set.set(self_bits + 3);
+            to_write.write(writer)?;
+        }
+        if let Some(ref to_write) = self.update_latency {
+            bit_set.set(self_bits + 4);
+            VarInt::write_variant(to_write, writer)?;
+        }
+        if let Some(ref to_write) = self.update_display_name {
+            bit_set.set(self_bits + 5);
+            to_write.write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read<C: ProtocolCursor<'a>>(self_bits: usize, bit_set: &BitSet<'a>, cursor: &mut C) -> ProtocolResult<Self> {
+        Ok(Self {
+            add: if bit_set.get_or_false(self_bits) { Some(PlayerInfoUpdateAddAction::read(cursor)?) } else { None },
+            initialize_chat: if bit_set.get_or_false(self_bits + 1) { Some(PlayerInfoUpdateInitializeChat::read(cursor)?) } else { None },
+            update_game_mode: if bit_set.get_or_false(self_bits + 2) { Some(PlayerInfoUpdateGameMode::read(cursor)?) } else { None },
+            update_listed: if bit_set.get_or_false(self_bits + 3) { Some(bool::read(cursor)?) } else { None },
+            update_latency: if bit_set.get_or_false(self_bits + 4) { Some(VarInt::read_variant(cursor)?) } else { None },
+            update_display_name: if bit_set.get_or_false(self_bits + 5) { Some(Option::read(cursor)?) } else { None },
+        })
+    }
+
+    fn move_bits(self_bits: &mut usize) {
+        *self_bits += 6;
+    }
+}
+
+#[derive(ProtocolPacket, Clone, PartialEq, Debug)]
+#[bp(id = 0x36, state = Play, bound = Client)]
+pub struct PlayerInfoUpdatePS2C<'a> {
+    pub actions: Cow<'a, [(Uuid, PlayerInfoUpdateAction<'a>)]>,
+}
+
+impl<'a> ProtocolSize for PlayerInfoUpdatePS2C<'a> {
+    const SIZE: Range<u32> = (BitSet::SIZE.start + VarInt::SIZE.start..u32::MAX);
+}
+
+impl<'a> ProtocolWritable for PlayerInfoUpdatePS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let mut bit_set = OwnedBitSet::new();
+        let mut actions_writer = Vec::new();
+        let mut bits = 0;
+        for (player_uuid, action) in self.actions.as_ref() {
+            player_uuid.write(&mut actions_writer)?;
+            action.write(bits, &mut bit_set, &mut actions_writer)?;
+            PlayerInfoUpdateAction::move_bits(&mut bits);
+        }
+        bit_set.get_bit_set().write(writer)?;
+        VarInt::write_variant(&(self.actions.len() as i32), writer)?;
+        writer.write_vec_bytes(actions_writer);
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for PlayerInfoUpdatePS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let bit_set = BitSet::read(cursor)?;
+        let mut actions = Vec::new();
+        let mut bits = 0;
+        for _ in 0i32..VarInt::read_variant(cursor)? {
+            actions.push((
+                Uuid::read(cursor)?,
+                PlayerInfoUpdateAction::read(bits, &bit_set, cursor)?,
+            ));
+            PlayerInfoUpdateAction::move_bits(&mut bits);
+        }
+        Ok(Self { actions: Cow::Owned(actions) })
+    }
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum RecipeBookAction {
+    Init,
+    Add,
+    Remove,
+}
+
+/// Opened books and active crafting filters of every recipe book
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct RecipeBookSettings {
+    pub crafting_open: bool,
+    pub crafting_filter_active: bool,
+    pub smelting_open: bool,
+    pub smelting_filter_active: bool,
+    pub blast_furnace_open: bool,
+    pub blast_furnace_filter_active: bool,
+    pub smoker_open: bool,
+    pub smoker_filter_active: bool,
+}
+
+/// Relative flags, the field is added to the current value of the client if it is set
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct SynchronizePlayerPositionFlags {
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+    pub y_rot: bool,
+    pub x_rot: bool,
+    #[bits(3)]
+    _pad: u8,
+}
+
+/// Teleports the player, movement packets are ignored until the teleport id is confirmed
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x38, state = Play, bound = Client)]
+pub struct SynchronizePlayerPositionPS2C {
+    pub pos: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    pub flags: SynchronizePlayerPositionFlags,
+    #[bp(variant = VarInt)]
+    pub teleport_id: i32,
+    pub dismount_vehicle: bool,
+}
+
+#[derive(ProtocolPacket, Clone, PartialEq, Debug)]
+#[bp(id = 0x39, state = Play, bound = Client)]
+pub struct UpdateRecipeBookPS2C<'a> {
+    pub action: RecipeBookAction,
+    pub settings: RecipeBookSettings,
+    pub recipes: Cow<'a, [Identifier<'a>]>,
+    /// Recipes which are shown as new, it is sent only with [`RecipeBookAction::Init`]
+    pub highlighted: Cow<'a, [Identifier<'a>]>,
+}
+
+impl<'a> ProtocolSize for UpdateRecipeBookPS2C<'a> {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(RecipeBookAction, RecipeBookSettings, VarInt).start..u32::MAX;
+}
+
+impl<'a> ProtocolWritable for UpdateRecipeBookPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.action.write(writer)?;
+        self.settings.write(writer)?;
+        LengthProvidedArray::<i32, VarInt, Identifier<'a>, Identifier<'a>>::write_variant(&self.recipes, writer)?;
+        match self.action {
+            RecipeBookAction::Init => LengthProvidedArray::<i32, VarInt, Identifier<'a>, Identifier<'a>>::write_variant(&self.highlighted, writer),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for UpdateRecipeBookPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let action = RecipeBookAction::read(cursor)?;
+        Ok(Self {
+            action,
+            settings: RecipeBookSettings::read(cursor)?,
+            recipes: LengthProvidedArray::<i32, VarInt, Identifier<'a>, Identifier<'a>>::read_variant(cursor)?,
+            highlighted: match action {
+                RecipeBookAction::Init => LengthProvidedArray::<i32, VarInt, Identifier<'a>, Identifier<'a>>::read_variant(cursor)?,
+                _ => Cow::Borrowed(&[]),
+            },
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3A, state = Play, bound = Client)]
+pub struct RemoveEntitiesPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub entity_ids: Cow<'a, [i32]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3C, state = Play, bound = Client)]
+pub struct ResourcePackPS2C<'a> {
+    pub url: &'a str,
+    /// Lowercase hex SHA1 of the pack, the client doesn't check it if it is empty
+    pub hash: &'a str,
+    pub forced: bool,
+    pub prompt_message: Option<Component<'a>>,
+}
+
+/// Player is moved to another dimension or respawned after the death
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3D, state = Play, bound = Client)]
+pub struct RespawnPS2C<'a> {
+    pub dimension_type: Identifier<'a>,
+    pub dimension_name: Identifier<'a>,
+    pub hashed_seed: i64,
+    pub game_mode: LoginGameMode,
+    pub previous_game_mode: PreviousLoginGameMode,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    /// Metadata is kept when the dimension is changed, it is reset after the death
+    pub copy_metadata: bool,
+    pub death_location: Option<LoginDeathLocation<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3E, state = Play, bound = Client)]
+pub struct SetHeadRotationPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = Angle)]
+    pub head_yaw: f32,
+}
+
+/// Block changes of one chunk section, blocks are packed by [`section_block_entry`]
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x3F, state = Play, bound = Client)]
+pub struct UpdateSectionBlocksPS2C<'a> {
+    /// Block coordinates shifted by 4, `y` is negative for sections below zero
+    #[bp(variant = SectionPosition)]
+    pub section: Vector3D<i32>,
+    /// Opposite of [`LightData::trust_edges`]
+    pub suppress_light_updates: bool,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i64, VarLong>")]
+    pub blocks: Cow<'a, [i64]>,
+}
+
+/// State id and the position inside of the section
+pub const fn section_block_entry(state: i32, x: u8, y: u8, z: u8) -> i64 {
+    ((state as i64) << 12) | ((x as i64 & 15) << 8) | ((z as i64 & 15) << 4) | (y as i64 & 15)
+}
+
+/// Returns the state id and the position inside of the section
+pub const fn read_section_block_entry(entry: i64) -> (i32, Vector3D<u8>) {
+    let position = Vector3D::new((entry >> 8 & 15) as u8, (entry & 15) as u8, (entry >> 4 & 15) as u8);
+    ((entry >> 12) as i32, position)
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x43, state = Play, bound = Client)]
+pub struct SetBorderCenterPS2C {
+    pub x: f64,
+    pub z: f64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x44, state = Play, bound = Client)]
+pub struct SetBorderLerpSizePS2C {
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// Milliseconds until the new diameter is reached
+    #[bp(variant = VarLong)]
+    pub speed: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x45, state = Play, bound = Client)]
+pub struct SetBorderSizePS2C {
+    pub diameter: f64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x46, state = Play, bound = Client)]
+pub struct SetBorderWarningDelayPS2C {
+    #[bp(variant = VarInt)]
+    pub warning_time: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x47, state = Play, bound = Client)]
+pub struct SetBorderWarningDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub warning_blocks: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4A, state = Play, bound = Client)]
+pub struct SetCenterChunkPS2C {
+    #[bp(variant = VarInt)]
+    pub chunk_x: i32,
+    #[bp(variant = VarInt)]
+    pub chunk_z: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4B, state = Play, bound = Client)]
+pub struct SetRenderDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub view_distance: i32,
+}
+
+/// Metadata is written by `EntityMetadata` of bird-server and it includes the `0xFF` terminator
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4E, state = Play, bound = Client)]
+pub struct SetEntityMetadataPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = RemainingBytesArray)]
+    pub metadata: &'a [u8],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x4F, state = Play, bound = Client)]
+pub struct LinkEntitiesPS2C {
+    pub attached_entity_id: i32,
+    /// 0 to detach
+    pub holding_entity_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x50, state = Play, bound = Client)]
+pub struct SetEntityVelocityPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub velocity: Vector3D<i16>,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Boots,
+    Leggings,
+    Chestplate,
+    Helmet,
+}
+
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 6] = [
+        EquipmentSlot::MainHand,
+        EquipmentSlot::OffHand,
+        EquipmentSlot::Boots,
+        EquipmentSlot::Leggings,
+        EquipmentSlot::Chestplate,
+        EquipmentSlot::Helmet,
+    ];
+}
+
+#[derive(ProtocolPacket, Clone, PartialEq, Debug)]
+#[bp(id = 0x51, state = Play, bound = Client)]
+pub struct SetEquipmentPS2C<'a> {
+    pub entity_id: i32,
+    pub equipment: Cow<'a, [(EquipmentSlot, Option<Slot<'a>>)]>,
+}
+
+impl<'a> ProtocolSize for SetEquipmentPS2C<'a> {
+    const SIZE: Range<u32> = VarInt::SIZE.start..u32::MAX;
+}
+
+impl<'a> ProtocolWritable for SetEquipmentPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        VarInt::write_variant(&self.entity_id, writer)?;
+        for (index, (slot, item)) in self.equipment.iter().enumerate() {
+            // The top bit says that there is another entry
+            let has_next = index + 1 != self.equipment.len();
+            (*slot as u8 | if has_next { 0x80 } else { 0 }).write(writer)?;
+            item.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for SetEquipmentPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let entity_id = VarInt::read_variant(cursor)?;
+        let mut equipment = Vec::new();
+        loop {
+            let slot = u8::read(cursor)?;
+            let item = Option::<Slot>::read(cursor)?;
+            equipment.push((
+                *EquipmentSlot::ALL.get((slot & 0x7F) as usize)
+                    .ok_or_else(|| ProtocolError::Any(anyhow::Error::msg("Bad equipment slot")))?,
+                item,
+            ));
+            if slot & 0x80 == 0 { break; }
+        }
+        Ok(Self { entity_id, equipment: Cow::Owned(equipment) })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x55, state = Play, bound = Client)]
+pub struct SetPassengersPS2C<'a> {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub passengers: Cow<'a, [i32]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x58, state = Play, bound = Client)]
+pub struct SetSimulationDistancePS2C {
+    #[bp(variant = VarInt)]
+    pub simulation_distance: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x5A, state = Play, bound = Client)]
+pub struct UpdateTimePS2C {
+    pub world_age: i64,
+    /// Negative if the daylight cycle is stopped
+    pub time_of_day: i64,
+}
+
+/// Sound of the registry or a sound of the resource pack
+#[derive(Clone, PartialEq, Debug)]
+pub enum SoundEvent<'a> {
+    Registry(Sound),
+    Custom {
+        name: Identifier<'a>,
+        /// Fixed range of the sound, otherwise it depends on the volume
+        range: Option<f32>,
+    },
+}
+
+impl<'a> ProtocolSize for SoundEvent<'a> {
+    const SIZE: Range<u32> = VarInt::SIZE.start..add_protocol_sizes_ty!(VarInt, Identifier, Option<f32>).end;
+}
+
+impl<'a> ProtocolWritable for SoundEvent<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            // Zero means that the sound is written inline
+            Self::Registry(sound) => VarInt::write_variant(&(sound.get_id() as i32 + 1), writer),
+            Self::Custom { name, range } => {
+                VarInt::write_variant(&0, writer)?;
+                name.write(writer)?;
+                range.write(writer)
+            }
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for SoundEvent<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        match id {
+            0 => Ok(Self::Custom { name: Identifier::read(cursor)?, range: Option::read(cursor)? }),
+            id => Sound::from_id((id - 1) as u32)
+                .map(Self::Registry)
+                .ok_or_else(|| ProtocolError::Any(anyhow::Error::msg("Unknown sound id"))),
+        }
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x5D, state = Play, bound = Client)]
+pub struct EntitySoundEffectPS2C<'a> {
+    pub sound: SoundEvent<'a>,
+    pub sound_category: CustomSoundCategory,
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x5E, state = Play, bound = Client)]
+pub struct SoundEffectPS2C<'a> {
+    pub sound: SoundEvent<'a>,
+    pub sound_category: CustomSoundCategory,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_x: f32,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_y: f32,
+    #[bp(variant = "FixedPointNumber<i32, 3>")]
+    pub effect_position_z: f32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+/// Stops all sounds if both are `None`
+#[derive(ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x5F, state = Play, bound = Client)]
+pub struct StopSoundPS2C<'a> {
+    pub sound_category: Option<CustomSoundCategory>,
+    pub sound: Option<Identifier<'a>>,
+}
+
+impl<'a> ProtocolSize for StopSoundPS2C<'a> {
+    const SIZE: Range<u32> = u8::SIZE.start..add_protocol_sizes_ty!(u8, CustomSoundCategory, Identifier).end;
+}
+
+impl<'a> ProtocolWritable for StopSoundPS2C<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let flags = self.sound_category.is_some() as u8 | (self.sound.is_some() as u8) << 1;
+        flags.write(writer)?;
+        if let Some(ref category) = self.sound_category {
+            category.write(writer)?;
+        }
+        if let Some(ref sound) = self.sound {
+            sound.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for StopSoundPS2C<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let flags = u8::read(cursor)?;
+        Ok(Self {
+            sound_category: match flags & 1 != 0 {
+                true => Some(CustomSoundCategory::read(cursor)?),
+                false => None,
+            },
+            sound: match flags & 2 != 0 {
+                true => Some(Identifier::read(cursor)?),
+                false => None,
+            },
+        })
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x60, state = Play, bound = Client)]
+pub struct SystemChatMessagePS2C<'a> {
+    pub content: Component<'a>,
+    /// Shows the message above the hotbar
+    pub overlay: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x64, state = Play, bound = Client)]
+pub struct TeleportEntityPS2C {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub position: Vector3D<f64>,
+    #[bp(variant = Angle)]
+    pub yaw: f32,
+    #[bp(variant = Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum AdvancementFrameType {
+    Task,
+    Challenge,
+    Goal,
+}
+
+/// Display of the advancement in the advancements screen, advancements without it are invisible
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct AdvancementDisplay<'a> {
+    pub title: Component<'a>,
+    pub description: Component<'a>,
+    pub icon: Option<Slot<'a>>,
+    pub frame_type: AdvancementFrameType,
+    pub show_toast: bool,
+    pub hidden: bool,
+    /// Only root advancements have the background of the tab
+    pub background_texture: Option<Identifier<'a>>,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl<'a> ProtocolSize for AdvancementDisplay<'a> {
+    const SIZE: Range<u32> =
+        add_protocol_sizes_ty!(Component<'a>, Component<'a>, Option<Slot<'a>>, AdvancementFrameType, i32, f32, f32).start..
+            add_protocol_sizes_ty!(Component<'a>, Component<'a>, Option<Slot<'a>>, AdvancementFrameType, i32, Identifier<'a>, f32, f32).end;
+}
+
+impl<'a> ProtocolWritable for AdvancementDisplay<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.title.write(writer)?;
+        self.description.write(writer)?;
+        self.icon.write(writer)?;
+        self.frame_type.write(writer)?;
+        let flags = self.background_texture.is_some() as i32 | (self.show_toast as i32) << 1 | (self.hidden as i32) << 2;
+        flags.write(writer)?;
+        if let Some(ref background_texture) = self.background_texture {
+            background_texture.write(writer)?;
+        }
+        self.x.write(writer)?;
+        self.y.write(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for AdvancementDisplay<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let title = Component::read(cursor)?;
+        let description = Component::read(cursor)?;
+        let icon = Option::read(cursor)?;
+        let frame_type = AdvancementFrameType::read(cursor)?;
+        let flags = i32::read(cursor)?;
+        Ok(Self {
+            title,
+            description,
+            icon,
+            frame_type,
+            show_toast: flags & 2 != 0,
+            hidden: flags & 4 != 0,
+            background_texture: match flags & 1 != 0 {
+                true => Some(Identifier::read(cursor)?),
+                false => None,
+            },
+            x: f32::read(cursor)?,
+            y: f32::read(cursor)?,
+        })
+    }
+}
+
+/// The requirement is done when any of its criteria is done
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct AdvancementRequirement<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, &'a str, &'a str>")]
+    pub criteria: Cow<'a, [&'a str]>,
+}
+
+/// The advancement is done when all requirements are done
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct Advancement<'a> {
+    pub parent: Option<Identifier<'a>>,
+    pub display: Option<AdvancementDisplay<'a>>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub criteria: Cow<'a, [Identifier<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementRequirement<'a>, AdvancementRequirement<'a>>")]
+    pub requirements: Cow<'a, [AdvancementRequirement<'a>]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct AdvancementMapping<'a> {
+    pub key: Identifier<'a>,
+    pub value: Advancement<'a>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct CriterionProgress<'a> {
+    pub criterion: Identifier<'a>,
+    /// Milliseconds since the epoch when the criterion was done, `None` if it is not done
+    pub achieved_at: Option<i64>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct AdvancementProgress<'a> {
+    pub advancement: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, CriterionProgress<'a>, CriterionProgress<'a>>")]
+    pub criteria: Cow<'a, [CriterionProgress<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x65, state = Play, bound = Client)]
+pub struct UpdateAdvancementsPS2C<'a> {
+    /// Removes all advancements of the client before adding
+    pub reset: bool,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementMapping<'a>, AdvancementMapping<'a>>")]
+    pub advancements: Cow<'a, [AdvancementMapping<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Identifier<'a>, Identifier<'a>>")]
+    pub removed: Cow<'a, [Identifier<'a>]>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, AdvancementProgress<'a>, AdvancementProgress<'a>>")]
+    pub progress: Cow<'a, [AdvancementProgress<'a>]>,
+}
+
+pub const RECIPE_CRAFTING_SHAPELESS: &'static str = "minecraft:crafting_shapeless";
+pub const RECIPE_CRAFTING_SHAPED: &'static str = "minecraft:crafting_shaped";
+/// Special recipes have hardcoded logic, like `minecraft:crafting_special_armordye`
+pub const RECIPE_CRAFTING_SPECIAL_PREFIX: &'static str = "minecraft:crafting_special_";
+pub const RECIPE_SMELTING: &'static str = "minecraft:smelting";
+pub const RECIPE_BLASTING: &'static str = "minecraft:blasting";
+pub const RECIPE_SMOKING: &'static str = "minecraft:smoking";
+pub const RECIPE_CAMPFIRE_COOKING: &'static str = "minecraft:campfire_cooking";
+pub const RECIPE_STONECUTTING: &'static str = "minecraft:stonecutting";
+pub const RECIPE_SMITHING: &'static str = "minecraft:smithing";
+
+/// Tab of the crafting recipe book
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CraftingRecipeCategory {
+    Building,
+    Redstone,
+    Equipment,
+    Misc,
+}
+
+/// Tab of furnace recipe books
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum CookingRecipeCategory {
+    Food,
+    Blocks,
+    Misc,
+}
+
+/// Any of the items matches the ingredient, an empty ingredient matches an empty slot
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct RecipeIngredient<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Option<Slot<'a>>, Option<Slot<'a>>>")]
+    pub items: Cow<'a, [Option<Slot<'a>>]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ShapelessRecipeData<'a> {
+    /// Recipes with the same group are shown as one in the recipe book
+    pub group: &'a str,
+    pub category: CraftingRecipeCategory,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, RecipeIngredient<'a>, RecipeIngredient<'a>>")]
+    pub ingredients: Cow<'a, [RecipeIngredient<'a>]>,
+    pub result: Option<Slot<'a>>,
+}
+
+/// Ingredients are written row by row without the length, there are `width * height` of them
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShapedRecipeData<'a> {
+    pub width: i32,
+    pub height: i32,
+    pub group: &'a str,
+    pub category: CraftingRecipeCategory,
+    pub ingredients: Cow<'a, [RecipeIngredient<'a>]>,
+    pub result: Option<Slot<'a>>,
+}
+
+impl<'a> ProtocolSize for ShapedRecipeData<'a> {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(VarInt, VarInt, &'a str, CraftingRecipeCategory, Option<Slot<'a>>).start..u32::MAX;
+}
+
+impl<'a> ProtocolWritable for ShapedRecipeData<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        if self.width < 0 || self.height < 0 || self.ingredients.len() != self.width as usize * self.height as usize {
+            return Err(anyhow::Error::msg("Shaped recipe must have width * height ingredients"));
+        }
+        VarInt::write_variant(&self.width, writer)?;
+        VarInt::write_variant(&self.height, writer)?;
+        self.group.write(writer)?;
+        self.category.write(writer)?;
+        for ingredient in self.ingredients.iter() {
+            ingredient.write(writer)?;
+        }
+        self.result.write(writer)
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for ShapedRecipeData<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let width: i32 = VarInt::read_variant(cursor)?;
+        let height: i32 = VarInt::read_variant(cursor)?;
+        if width < 0 || height < 0 || width.checked_mul(height).map_or(true, |count| count > cursor.remaining_bytes() as i32) {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Wrong size of shaped recipe")));
+        }
+        let group = <&'a str>::read(cursor)?;
+        let category = CraftingRecipeCategory::read(cursor)?;
+        let ingredients = (0..width * height)
+            .map(|_| RecipeIngredient::read(cursor))
+            .collect::<ProtocolResult<Vec<_>>>()?;
+        Ok(Self { width, height, group, category, ingredients: Cow::Owned(ingredients), result: Option::read(cursor)? })
+    }
+}
+
+/// Recipe of furnaces and campfires
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct CookingRecipeData<'a> {
+    pub group: &'a str,
+    pub category: CookingRecipeCategory,
+    pub ingredient: RecipeIngredient<'a>,
+    pub result: Option<Slot<'a>>,
+    pub experience: f32,
+    #[bp(variant = VarInt)]
+    pub cooking_time: i32,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct StonecuttingRecipeData<'a> {
+    pub group: &'a str,
+    pub ingredient: RecipeIngredient<'a>,
+    pub result: Option<Slot<'a>>,
+}
+
+/// The addition is combined with the base, nbt of the base is kept
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct SmithingRecipeData<'a> {
+    pub base: RecipeIngredient<'a>,
+    pub addition: RecipeIngredient<'a>,
+    pub result: Option<Slot<'a>>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DeclaredRecipeVariant<'a> {
+    Shapeless(ShapelessRecipeData<'a>),
+    Shaped(ShapedRecipeData<'a>),
+    /// `ty` is the full type with [`RECIPE_CRAFTING_SPECIAL_PREFIX`]
+    Special {
+        ty: Identifier<'a>,
+        category: CraftingRecipeCategory,
+    },
+    Smelting(CookingRecipeData<'a>),
+    Blasting(CookingRecipeData<'a>),
+    Smoking(CookingRecipeData<'a>),
+    CampfireCooking(CookingRecipeData<'a>),
+    Stonecutting(StonecuttingRecipeData<'a>),
+    Smithing(SmithingRecipeData<'a>),
+}
+
+/// The type of the recipe is written before its id
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct DeclaredRecipe<'a> {
+    pub id: Identifier<'a>,
+    pub variant: DeclaredRecipeVariant<'a>,
+}
+
+impl<'a> ProtocolSize for DeclaredRecipe<'a> {
+    const SIZE: Range<u32> = add_protocol_sizes_ty!(Identifier<'a>, Identifier<'a>).start..u32::MAX;
+}
+
+impl<'a> DeclaredRecipeVariant<'a> {
+    /// Full type identifier of the recipe
+    pub fn ty(&self) -> Cow<str> {
+        Cow::Borrowed(match self {
+            DeclaredRecipeVariant::Shapeless(_) => RECIPE_CRAFTING_SHAPELESS,
+            DeclaredRecipeVariant::Shaped(_) => RECIPE_CRAFTING_SHAPED,
+            DeclaredRecipeVariant::Special { ty, .. } => return ty.get_full(),
+            DeclaredRecipeVariant::Smelting(_) => RECIPE_SMELTING,
+            DeclaredRecipeVariant::Blasting(_) => RECIPE_BLASTING,
+            DeclaredRecipeVariant::Smoking(_) => RECIPE_SMOKING,
+            DeclaredRecipeVariant::CampfireCooking(_) => RECIPE_CAMPFIRE_COOKING,
+            DeclaredRecipeVariant::Stonecutting(_) => RECIPE_STONECUTTING,
+            DeclaredRecipeVariant::Smithing(_) => RECIPE_SMITHING,
+        })
+    }
+}
+
+impl<'a> ProtocolWritable for DeclaredRecipe<'a> {
+    fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let ty = self.variant.ty();
+        if let DeclaredRecipeVariant::Special { .. } = self.variant {
+            if !ty.starts_with(RECIPE_CRAFTING_SPECIAL_PREFIX) {
+                return Err(anyhow::Error::msg(format!("{} is not a special recipe", ty)));
+            }
+        }
+        ty.as_ref().write(writer)?;
+        self.id.write(writer)?;
+        match self.variant {
+            DeclaredRecipeVariant::Shapeless(ref recipe) => recipe.write(writer),
+            DeclaredRecipeVariant::Shaped(ref recipe) => recipe.write(writer),
+            DeclaredRecipeVariant::Special { category, .. } => category.write(writer),
+            DeclaredRecipeVariant::Smelting(ref recipe) |
+            DeclaredRecipeVariant::Blasting(ref recipe) |
+            DeclaredRecipeVariant::Smoking(ref recipe) |
+            DeclaredRecipeVariant::CampfireCooking(ref recipe) => recipe.write(writer),
+            DeclaredRecipeVariant::Stonecutting(ref recipe) => recipe.write(writer),
+            DeclaredRecipeVariant::Smithing(ref recipe) => recipe.write(writer),
+        }
+    }
+}
+
+impl<'a> ProtocolReadable<'a> for DeclaredRecipe<'a> {
+    fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        let ty = <&'a str>::read(cursor)?;
+        let id = Identifier::read(cursor)?;
+        let variant = match ty {
+            RECIPE_CRAFTING_SHAPELESS => DeclaredRecipeVariant::Shapeless(ShapelessRecipeData::read(cursor)?),
+            RECIPE_CRAFTING_SHAPED => DeclaredRecipeVariant::Shaped(ShapedRecipeData::read(cursor)?),
+            RECIPE_SMELTING => DeclaredRecipeVariant::Smelting(CookingRecipeData::read(cursor)?),
+            RECIPE_BLASTING => DeclaredRecipeVariant::Blasting(CookingRecipeData::read(cursor)?),
+            RECIPE_SMOKING => DeclaredRecipeVariant::Smoking(CookingRecipeData::read(cursor)?),
+            RECIPE_CAMPFIRE_COOKING => DeclaredRecipeVariant::CampfireCooking(CookingRecipeData::read(cursor)?),
+            RECIPE_STONECUTTING => DeclaredRecipeVariant::Stonecutting(StonecuttingRecipeData::read(cursor)?),
+            RECIPE_SMITHING => DeclaredRecipeVariant::Smithing(SmithingRecipeData::read(cursor)?),
+            special if special.starts_with(RECIPE_CRAFTING_SPECIAL_PREFIX) => DeclaredRecipeVariant::Special {
+                ty: Identifier::new_full(Cow::Borrowed(special))
+                    .ok_or_else(|| ProtocolError::Any(anyhow::Error::msg("Wrong recipe type")))?,
+                category: CraftingRecipeCategory::read(cursor)?,
+            },
+            ty => return Err(ProtocolError::Any(anyhow::Error::msg(format!("Unknown recipe type {}", ty)))),
+        };
+        Ok(Self { id, variant })
+    }
+}
+
+/// Replaces all recipes of the client, the recipe book shows only unlocked ones
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x69, state = Play, bound = Client)]
+pub struct UpdateRecipesPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, DeclaredRecipe<'a>, DeclaredRecipe<'a>>")]
+    pub recipes: Cow<'a, [DeclaredRecipe<'a>]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct UpdateTagsTag<'a> {
+    pub name: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+    pub entries: Cow<'a, [i32]>,
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct UpdateTagsRegistry<'a> {
+    /// `minecraft:block`, `minecraft:item`, `minecraft:fluid`, `minecraft:entity_type` or `minecraft:game_event`
+    pub registry: Identifier<'a>,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, UpdateTagsTag<'a>, UpdateTagsTag<'a>>")]
+    pub tags: Cow<'a, [UpdateTagsTag<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x6A, state = Play, bound = Client)]
+pub struct UpdateTagsPS2C<'a> {
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, UpdateTagsRegistry<'a>, UpdateTagsRegistry<'a>>")]
+    pub registries: Cow<'a, [UpdateTagsRegistry<'a>]>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x00, state = Play, bound = Server)]
+pub struct ConfirmTeleportationPC2S {
+    #[bp(variant = VarInt)]
+    pub teleport_id: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x03, state = Play, bound = Server)]
+pub struct MessageAcknowledgmentPC2S {
+    #[bp(variant = VarInt)]
+    pub offset: i32,
+}
+
+pub const LAST_SEEN_MESSAGES_LENGTH: usize = 20;
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ChatCommandArgumentSignature<'a> {
+    pub argument_name: &'a str,
+    #[bp(variant = "ConstLengthRawArray<u8, 256>")]
+    pub signature: &'a [u8; 256],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x04, state = Play, bound = Server)]
+pub struct ChatCommandPC2S<'a> {
+    #[bp(validate = "command.chars().count() <= MAX_CHAT_MESSAGE_LENGTH")]
+    pub command: &'a str,
+    pub timestamp: i64,
+    pub salt: i64,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, ChatCommandArgumentSignature<'a>, ChatCommandArgumentSignature<'a>>")]
+    pub argument_signatures: Cow<'a, [ChatCommandArgumentSignature<'a>]>,
+    #[bp(variant = VarInt)]
+    pub message_count: i32,
+    /// Fixed bit set of [`LAST_SEEN_MESSAGES_LENGTH`] bits
+    #[bp(variant = "ConstLengthRawArray<u8, 3>")]
+    pub acknowledged: &'a [u8; 3],
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x05, state = Play, bound = Server)]
+pub struct ChatMessagePC2S<'a> {
+    #[bp(validate = "message.chars().count() <= MAX_CHAT_MESSAGE_LENGTH")]
+    pub message: &'a str,
+    /// Milliseconds since the unix epoch
+    pub timestamp: i64,
+    pub salt: i64,
+    #[bp(variant = "ProtocolVariantOption<&'a [u8; 256], ConstLengthRawArray<u8, 256>>")]
+    pub signature: Option<&'a [u8; 256]>,
+    #[bp(variant = VarInt)]
+    pub message_count: i32,
+    /// Fixed bit set of [`LAST_SEEN_MESSAGES_LENGTH`] bits
+    #[bp(variant = "ConstLengthRawArray<u8, 3>")]
+    pub acknowledged: &'a [u8; 3],
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ClickContainerMode {
+    Pickup,
+    QuickMove,
+    Swap,
+    Clone,
+    Throw,
+    QuickCraft,
+    PickupAll,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct ClickContainerChangedSlot<'a> {
+    pub slot: i16,
+    pub slot_data: Option<Slot<'a>>,
+}
+
+pub const OUTSIDE_SLOT_ID: i16 = -999;
+
+/// Sent when a button of the window is clicked, like a recipe of the stonecutter
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x09, state = Play, bound = Server)]
+pub struct ClickContainerButtonPC2S {
+    pub window_id: i8,
+    pub button_id: i8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0A, state = Play, bound = Server)]
+pub struct ClickContainerPC2S<'a> {
+    pub window_id: u8,
+    #[bp(variant = VarInt)]
+    pub state_id: i32,
+    pub slot: i16,
+    pub button: i8,
+    pub mode: ClickContainerMode,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, ClickContainerChangedSlot<'a>, ClickContainerChangedSlot<'a>>")]
+    pub changed_slots: Cow<'a, [ClickContainerChangedSlot<'a>]>,
+    pub carried_item: Option<Slot<'a>>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0B, state = Play, bound = Server)]
+pub struct CloseContainerPC2S {
+    pub window_id: u8,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0C, state = Play, bound = Server)]
+pub struct PluginMessagePC2S<'a> {
+    pub channel: Identifier<'a>,
+    #[bp(variant = RemainingBytesArray)]
+    pub data: &'a [u8],
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum Hand {
+    Main,
+    Off,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum InteractAction {
+    Interact {
+        hand: Hand,
+    },
+    Attack,
+    InteractAt {
+        /// Relative to the entity position
+        target: Vector3D<f32>,
+        hand: Hand,
+    },
+}
+
+// Limits of the protocol, the server has stricter limits of the vanilla client in `book.rs`
+pub const MAX_EDIT_BOOK_PAGES: usize = 200;
+pub const MAX_EDIT_BOOK_PAGE_LENGTH: usize = 8192;
+pub const MAX_EDIT_BOOK_TITLE_LENGTH: usize = 128;
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0D, state = Play, bound = Server)]
+pub struct EditBookPC2S<'a> {
+    /// Hotbar slot or 40 for the off hand
+    #[bp(variant = VarInt)]
+    pub slot: i32,
+    #[bp(
+        variant = "LengthLimitedArray<i32, VarInt, &'a str, &'a str, MAX_EDIT_BOOK_PAGES>",
+        validate = "pages.iter().all(|page| page.chars().count() <= MAX_EDIT_BOOK_PAGE_LENGTH)",
+    )]
+    pub pages: Cow<'a, [&'a str]>,
+    /// Present if the book is signed
+    #[bp(validate = "title.map(|title| title.chars().count() <= MAX_EDIT_BOOK_TITLE_LENGTH).unwrap_or(true)")]
+    pub title: Option<&'a str>,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x0F, state = Play, bound = Server)]
+pub struct InteractPC2S {
+    #[bp(variant = VarInt)]
+    pub entity_id: i32,
+    pub action: InteractAction,
+    pub sneaking: bool,
+}
+
+/// Position of the feet
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x13, state = Play, bound = Server)]
+pub struct SetPlayerPositionPC2S {
+    pub pos: Vector3D<f64>,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x14, state = Play, bound = Server)]
+pub struct SetPlayerPositionAndRotationPC2S {
+    pub pos: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x15, state = Play, bound = Server)]
+pub struct SetPlayerRotationPC2S {
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x16, state = Play, bound = Server)]
+pub struct SetPlayerOnGroundPC2S {
+    pub on_ground: bool,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x17, state = Play, bound = Server)]
+pub struct MoveVehiclePC2S {
+    pub pos: Vector3D<f64>,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+}
+
+/// Click on a recipe in the recipe book, the server moves the ingredients into the crafting grid
+#[derive(ProtocolAll, ProtocolPacket, Clone, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1A, state = Play, bound = Server)]
+pub struct PlaceRecipePC2S<'a> {
+    pub window_id: i8,
+    pub recipe: Identifier<'a>,
+    /// Shift click, as many items as possible are placed
+    pub make_all: bool,
+}
+
+#[bitfield(u8)]
+#[derive(ProtocolAll, PartialEq)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+pub struct PlayerInputFlags {
+    pub jump: bool,
+    pub unmount: bool,
+    #[bits(6)]
+    _gap: u8,
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum PlayerActionStatus {
+    StartedDigging,
+    CancelledDigging,
+    FinishedDigging,
+    DropItemStack,
+    DropItem,
+    /// Shooting arrows and finishing eating
+    ReleaseUseItem,
+    SwapItemInHand,
+}
+
+/// Same as [`Direction`], but it is sent as a byte
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = u8)]
+pub enum BlockFace {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl From<BlockFace> for Direction {
+    fn from(face: BlockFace) -> Self {
+        match face {
+            BlockFace::Down => Direction::Down,
+            BlockFace::Up => Direction::Up,
+            BlockFace::North => Direction::North,
+            BlockFace::South => Direction::South,
+            BlockFace::West => Direction::West,
+            BlockFace::East => Direction::East,
+        }
+    }
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1C, state = Play, bound = Server)]
+pub struct PlayerActionPC2S {
+    pub status: PlayerActionStatus,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub face: BlockFace,
+    /// Acknowledged by [`AcknowledgeBlockChangePS2C`]
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x1E, state = Play, bound = Server)]
+pub struct PlayerInputPC2S {
+    /// Positive to the left
+    pub sideways: f32,
+    /// Positive forward
+    pub forward: f32,
+    pub flags: PlayerInputFlags,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x20, state = Play, bound = Server)]
+pub struct PlayerSessionPC2S<'a> {
+    pub session_id: Uuid,
+    /// Milliseconds since the unix epoch
+    pub expires_at: i64,
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub public_key: &'a [u8],
+    #[bp(variant = "LengthProvidedBytesArray<i32, VarInt>")]
+    pub key_signature: &'a [u8],
+}
+
+#[derive(ProtocolAll, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(ty = i32, variant = VarInt)]
+pub enum ResourcePackStatus {
+    SuccessfullyLoaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x24, state = Play, bound = Server)]
+pub struct ResourcePackPC2S {
+    pub status: ResourcePackStatus,
+}
+
+/// The player clicks the face of the block with the item, blocks are placed against the face
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x31, state = Play, bound = Server)]
+pub struct UseItemOnPC2S {
+    pub hand: Hand,
+    #[bp(variant = BlockPosition)]
+    pub location: Vector3D<i32>,
+    pub face: Direction,
+    /// Position of the cursor on the face from 0 to 1
+    pub cursor: Vector3D<f32>,
+    pub inside_block: bool,
+    /// Acknowledged by [`AcknowledgeBlockChangePS2C`]
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(ProtocolAll, ProtocolPacket, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(any(test, feature = "sample"), derive(ProtocolSample))]
+#[bp(id = 0x32, state = Play, bound = Server)]
+pub struct UseItemPC2S {
+    pub hand: Hand,
+    /// Acknowledged by [`AcknowledgeBlockChangePS2C`]
+    #[bp(variant = VarInt)]
+    pub sequence: i32,
+}
+
+/// Samples of types whose fields depend on each other, so they can not be sampled field by field
+#[cfg(any(test, feature = "sample"))]
+mod samples {
+    use bird_protocol::sample::{ProtocolSample, ProtocolVariantSample, SampleRandom};
+    use super::*;
+
+    fn sample_vec<T: ProtocolSample>(random: &mut SampleRandom) -> Vec<T> {
+        (0..random.next_length()).map(|_| T::sample(random)).collect()
+    }
+
+    fn leak<T, const N: usize>(value: [T; N]) -> &'static [T; N] {
+        Box::leak(Box::new(value))
+    }
+
+    impl<'a> ProtocolSample for StatusResponseObject<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Self {
+                version: StatusResponseVersion::sample(random),
+                players: StatusResponsePlayers::sample(random),
+                description: match random.next_bool() {
+                    true => either::Either::Left(<&'a str>::sample(random)),
+                    false => either::Either::Right(Component::sample(random)),
+                },
+                favicon: Option::sample(random),
+                previews_chat: random.next_bool(),
+                enforces_secure_chat: random.next_bool(),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for StatusResponsePlayers<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Self { max: i32::sample(random), sample: Cow::Owned(sample_vec(random)), online: i32::sample(random) }
+        }
+    }
+
+    impl<'a> ProtocolSample for BrigadierNode<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            // The node type is written by the presence of the name and the parser
+            let (name, parser) = match random.next_below(3) as u8 {
+                ROOT_NODE_TYPE => (None, None),
+                LITERAL_NODE_TYPE => (Some(<&'a str>::sample(random)), None),
+                _ => (Some(<&'a str>::sample(random)), Some(BrigadierNodeParser::sample(random))),
+            };
+            Self {
+                executable: random.next_bool(),
+                children: Cow::Owned(sample_vec(random)),
+                redirect_node: Option::sample(random),
+                name,
+                parser,
+                suggestions_type: Option::sample(random),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for PackedMessageSignature<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            match random.next_bool() {
+                // The index is written increased by one, so it is not negative
+                true => Self::Cached(random.next_below(i32::MAX as u64) as i32),
+                false => Self::Full(leak(<[u8; MESSAGE_SIGNATURE_LENGTH]>::sample(random))),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for ChunkDataHeightMap<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            let motion_blocking = leak(<[u64; CHUNK_DATA_HEIGHT_MAP_LONGS]>::sample(random));
+            let world_surface = match random.next_bool() {
+                true => Some(leak(<[u64; CHUNK_DATA_HEIGHT_MAP_LONGS]>::sample(random))),
+                false => None,
+            };
+            ChunkDataHeightMap::new(motion_blocking, world_surface)
+        }
+    }
+
+    impl<'a> ProtocolSample for BitSet<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            BitSet::new_words(Box::leak(sample_vec::<u64>(random).into_boxed_slice()))
+        }
+    }
+
+    impl<'a> ProtocolSample for LightArray<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Self { bytes: leak(<[u8; 2048]>::sample(random)) }
+        }
+    }
+
+    impl ProtocolSample for WorldEvent {
+        fn sample(random: &mut SampleRandom) -> Self {
+            const IDS: [Range<i32>; 4] = [1000..1049, 1500..1505, 2000..2010, 3000..3008];
+            let ids = &IDS[random.next_below(IDS.len() as u64) as usize];
+            let id = ids.start + random.next_below((ids.end - ids.start) as u64) as i32;
+            let value = match id {
+                2000 => random.next_below(6) as i32,
+                _ => i32::sample(random),
+            };
+            WorldEvent::new(id, value).unwrap()
+        }
+    }
+
+    impl ProtocolSample for WorldEventPS2C {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Self {
+                event: WorldEvent::sample(random),
+                location: BlockPosition::sample_variant(random),
+                disable_relative_volume: random.next_bool(),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for Particle<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            match random.next_below(88) as i32 {
+                2 => Self::Block { block_state: i32::sample(random) },
+                3 => Self::BlockMarker { block_state: i32::sample(random) },
+                // The scale is clamped by the reader
+                14 => Self::Dust {
+                    red: f32::sample(random),
+                    green: f32::sample(random),
+                    blue: f32::sample(random),
+                    scale: f32::sample(random).clamp(0.01, 4f32),
+                },
+                15 => Self::DustColorTransition {
+                    from_red: f32::sample(random),
+                    from_green: f32::sample(random),
+                    from_blue: f32::sample(random),
+                    scale: f32::sample(random).clamp(0.01, 4f32),
+                    to_red: f32::sample(random),
+                    to_green: f32::sample(random),
+                    to_blue: f32::sample(random),
+                },
+                24 => Self::FallingDust { block_state: i32::sample(random) },
+                35 => Self::Item { slot: Option::sample(random) },
+                36 => Self::Vibration { variant: VibrationVariant::sample(random), ticks: i32::sample(random) },
+                // Particles without data
+                id => {
+                    let mut empty: &'a [u8] = &[];
+                    Particle::read(id, &mut empty).unwrap()
+                }
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for VibrationVariant<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            match random.next_below(3) {
+                0 => Self::Block { position: BlockPosition::sample_variant(random) },
+                1 => Self::Entity { entity_id: i32::sample(random), entity_eye_height: f32::sample(random) },
+                // Sampled strings have no namespace, so they are never read as the other variants
+                _ => Self::Other { source_type: <&'a str>::sample(random) },
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for PlayerInfoUpdatePS2C<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Self { actions: Cow::Owned(sample_vec(random)) }
+        }
+    }
+
+    impl<'a> ProtocolSample for UpdateRecipeBookPS2C<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            let action = RecipeBookAction::sample(random);
+            Self {
+                action,
+                settings: RecipeBookSettings::sample(random),
+                recipes: Cow::Owned(sample_vec(random)),
+                highlighted: match action {
+                    RecipeBookAction::Init => Cow::Owned(sample_vec(random)),
+                    _ => Cow::Borrowed(&[]),
+                },
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for SetEquipmentPS2C<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            // The last entry is marked by the top bit, so there is at least one
+            let length = random.next_below(EquipmentSlot::ALL.len() as u64) as usize + 1;
+            Self {
+                entity_id: i32::sample(random),
+                equipment: Cow::Owned((0..length).map(|_| <(EquipmentSlot, Option<Slot>)>::sample(random)).collect()),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for SoundEvent<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            let sound = match random.next_bool() {
+                true => Sound::from_id(random.next_below(bird_data::SOUND_COUNT as u64) as u32),
+                false => None,
+            };
+            match sound {
+                Some(sound) => Self::Registry(sound),
+                None => Self::Custom { name: Identifier::sample(random), range: Option::sample(random) },
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for ShapedRecipeData<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            let width = random.next_below(4) as i32;
+            let height = random.next_below(4) as i32;
+            Self {
+                width,
+                height,
+                group: <&'a str>::sample(random),
+                category: CraftingRecipeCategory::sample(random),
+                ingredients: Cow::Owned((0..width * height).map(|_| RecipeIngredient::sample(random)).collect()),
+                result: Option::sample(random),
+            }
+        }
+    }
+
+    impl<'a> ProtocolSample for DeclaredRecipeVariant<'a> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            match random.next_below(9) {
+                0 => Self::Shapeless(ShapelessRecipeData::sample(random)),
+                1 => Self::Shaped(ShapedRecipeData::sample(random)),
+                2 => Self::Special {
+                    ty: Identifier::new_full(Cow::Owned(format!("{}{}", RECIPE_CRAFTING_SPECIAL_PREFIX, random.next_string(1)))).unwrap(),
+                    category: CraftingRecipeCategory::sample(random),
+                },
+                3 => Self::Smelting(CookingRecipeData::sample(random)),
+                4 => Self::Blasting(CookingRecipeData::sample(random)),
+                5 => Self::Smoking(CookingRecipeData::sample(random)),
+                6 => Self::CampfireCooking(CookingRecipeData::sample(random)),
+                7 => Self::Stonecutting(StonecuttingRecipeData::sample(random)),
+                _ => Self::Smithing(SmithingRecipeData::sample(random)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::sample::{assert_samples_round_trip, ProtocolSample};
+    use super::*;
+
+    const SAMPLES: usize = 32;
+
+    #[derive(ProtocolAll, ProtocolSample, Clone, PartialEq, Debug)]
+    struct GenericEntry<T> {
+        #[bp(variant = VarInt)]
+        count: i32,
+        value: T,
+    }
+
+    #[derive(ProtocolAll, ProtocolSample, Clone, PartialEq, Debug)]
+    struct BorrowedEntry<'a> {
+        name: &'a str,
+        #[bp(variant = "LengthProvidedArray<i32, VarInt, i32, VarInt>")]
+        values: Cow<'a, [i32]>,
+    }
+
+    trait EntryKey {
+        type Value;
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    struct UuidKey;
+
+    impl EntryKey for UuidKey {
+        type Value = Uuid;
+    }
+
+    /// The key is only a marker, so bounds are given for its value
+    #[derive(ProtocolAll, ProtocolSample, Clone, PartialEq, Debug)]
+    #[bp(where = "K::Value: ProtocolSize + ProtocolWritable + ProtocolReadable<'a> + ProtocolSample")]
+    struct KeyedEntry<'a, K: EntryKey> {
+        name: &'a str,
+        value: K::Value,
+    }
+
+    #[test]
+    fn derive_generics_test() {
+        assert_samples_round_trip::<GenericEntry<Option<Uuid>>>(1, SAMPLES);
+        assert_samples_round_trip::<BorrowedEntry>(2, SAMPLES);
+        assert_samples_round_trip::<KeyedEntry<UuidKey>>(3, SAMPLES);
+    }
+
+    #[test]
+    fn gap_compact_longs_reader_test() {
+        let mut compact_longs_reader = unsafe {
+            GapCompactLongsReader::<_, 19>::new(
+                vec![0b111111111_001111111_000011111_000000111_000000001; 3].into_iter(),
+                9,
+            ).unwrap()
+        };
+        for i in 0..3 {
+            assert_eq!(compact_longs_reader.next(), Some(0b1));
+            assert_eq!(compact_longs_reader.next(), Some(0b111));
+            assert_eq!(compact_longs_reader.next(), Some(0b11111));
+            assert_eq!(compact_longs_reader.next(), Some(0b1111111));
+            assert_eq!(compact_longs_reader.next(), Some(0b111111111));
+            if i == 2 {
+                assert_eq!(compact_longs_reader.next(), None);
+            } else {
+                assert_eq!(compact_longs_reader.next(), Some(0b0));
+                assert_eq!(compact_longs_reader.next(), Some(0b0));
+            }
+        }
+    }
+
+    #[test]
+    fn gap_compact_longs_writer_test() {
+        let mut vec = Vec::new();
+        let mut compact_longs_writer = unsafe { GapCompactLongsWriter::new(&mut vec, 9) };
+        unsafe {
+            for i in 0..3 {
+                compact_longs_writer.write(0b1).unwrap();
+                compact_longs_writer.write(0b111).unwrap();
+                compact_longs_writer.write(0b11111).unwrap();
+                compact_longs_writer.write(0b1111111).unwrap();
+                compact_longs_writer.write(0b111111111).unwrap();
+                if i != 2 {
+                    compact_longs_writer.write(0b0).unwrap();
+                    compact_longs_writer.write(0b0).unwrap();
+                }
+            }
+        }
+        compact_longs_writer.finish().unwrap();
+        let mut res_vec = Vec::new();
+        for _ in 0..3 {
+            0b111111111_001111111_000011111_000000111_000000001_u64.write(&mut res_vec).unwrap();
+        }
+        assert_eq!(vec, res_vec);
+    }
+
+    #[test]
+    fn checked_compact_longs_test() {
+        let mut vec = Vec::new();
+        let mut writer = GapCompactLongsWriter::checked(&mut vec, 9).unwrap();
+        assert!(writer.push(1 << 9).is_err());
+        writer.push_all_and_finish([0b1, 0b111, 0b11111, 0b1111111, 0b111111111].into_iter()).unwrap();
+        assert!(GapCompactLongsWriter::checked(&mut Vec::new(), 0).is_err());
+
+        let long = 0b111111111_001111111_000011111_000000111_000000001_u64;
+        let mut compact = CompactLongs::from_slice(9, 5, &[long]).unwrap();
+        let mut expected = Vec::new();
+        compact.write_longs(&mut expected).unwrap();
+        assert_eq!(vec, expected);
+        assert!(CompactLongs::from_slice(9, 8, &[long]).is_err());
+        assert_eq!(compact.get(2), Some(0b11111));
+        assert_eq!(compact.get(5), None);
+        assert_eq!(compact.set(2, 3).unwrap(), 0b11111);
+        assert!(compact.set(2, 1 << 9).is_err());
+        assert!(compact.set(5, 0).is_err());
+        assert_eq!(compact.iter().collect::<Vec<_>>(), vec![0b1, 0b111, 3, 0b1111111, 0b111111111]);
+
+        let values: Vec<u64> = (0..20).map(|value| value * 1000).collect();
+        let compact = CompactLongs::from_values(15, values.iter().copied()).unwrap();
+        assert_eq!(compact.longs().len(), unsafe { compact_longs_array_length(20, 15) });
+        assert_eq!(compact.iter().collect::<Vec<_>>(), values);
+        assert_eq!(CompactLongs::new(64, 2).unwrap().set(1, u64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn vanilla_compact_longs_test() {
+        // Block indexes of a section with 5 bits per entry, encoded by vanilla
+        let longs = [0x0020863148418841, 0x01018A7260F68C87];
+        let values = [1, 2, 2, 3, 4, 4, 5, 6, 6, 4, 8, 0, 7, 4, 3, 13, 15, 16, 9, 14, 10, 12, 0, 2];
+        let compact = CompactLongs::from_values(5, values.into_iter()).unwrap();
+        assert_eq!(compact.longs(), &longs);
+        assert_eq!(CompactLongs::from_slice(5, 24, &longs).unwrap(), compact);
+        let mut vec = Vec::new();
+        GapCompactLongsWriter::checked(&mut vec, 5).unwrap().push_all_and_finish(values.into_iter()).unwrap();
+        let mut expected = Vec::new();
+        compact.write_longs(&mut expected).unwrap();
+        assert_eq!(vec, expected);
+        let reader = unsafe { GapCompactLongsReader::<_, 24>::new(longs.into_iter(), 5).unwrap() };
+        assert_eq!(reader.collect::<Vec<_>>(), values);
+    }
+
+    #[test]
+    fn paletted_container_test() {
+        type Biomes = PalettedContainer<BiomesBits, { bird_data::BIOME_COUNT as i32 }, 64>;
+        let mut biomes = Biomes::new_single(0);
+        for index in 0..8 {
+            biomes.set(index, index as i32);
+        }
+        let mut bytes = Vec::new();
+        biomes.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], 3);
+        // 9th biome doesn't fit in 3 bits, so the biomes are written directly
+        biomes.set(8, 8);
+        let mut bytes = Vec::new();
+        biomes.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], Biomes::MAX_BITS);
+        let read = Biomes::read(&mut bytes.as_slice()).unwrap();
+        assert!((0..64).all(|index| read.get(index) == biomes.get(index)));
+
+        type BlockStates = PalettedContainer<BlockStatesBits, { bird_data::BLOCK_STATE_COUNT as i32 }, 4096>;
+        let mut block_states = BlockStates::new_single(0);
+        for index in 0..256 {
+            block_states.set(index, index as i32);
+        }
+        let mut bytes = Vec::new();
+        block_states.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], 8);
+        block_states.set(256, 256);
+        let mut bytes = Vec::new();
+        block_states.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], BlockStates::MAX_BITS);
+        let read = BlockStates::read(&mut bytes.as_slice()).unwrap();
+        assert!((0..4096).all(|index| read.get(index) == block_states.get(index)));
+
+        // Palettes which are too big for the client are written directly too
+        let indexes = Box::new([15; 64]);
+        let mut bytes = Vec::new();
+        Biomes::new_indirect((0..16).collect(), indexes).write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], Biomes::MAX_BITS);
+        assert_eq!(Biomes::read(&mut bytes.as_slice()).unwrap().get(63), 15);
+    }
+
+    #[test]
+    fn gap_compact_longs_length_test() {
+        unsafe {
+            assert_eq!(compact_longs_array_length(11, 15), 3);
+            assert_eq!(compact_longs_array_length(12, 15), 3);
+            assert_eq!(compact_longs_array_length(13, 15), 4);
+            assert_eq!(compact_longs_array_length(14, 15), 4);
+            assert_eq!(compact_longs_array_length(15, 15), 4);
+            assert_eq!(compact_longs_array_length(16, 15), 4);
+        }
+    }
+
+    #[test]
+    fn bit_set_test() {
+        let mut owned_bit_set = OwnedBitSet::new();
+        owned_bit_set.set(0);
+        owned_bit_set.set(3);
+        {
+            let bit_set = owned_bit_set.get_bit_set();
+            assert_eq!(bit_set.get(0), Some(true));
+            assert_eq!(bit_set.get(3), Some(true));
+            assert_eq!(bit_set.get(1), Some(false));
+            assert_eq!(bit_set.get(64), None);
+            let mut iter = bit_set.long_iter();
+            assert_eq!(iter.next(), Some(0b1001));
+            assert_eq!(iter.next(), None);
+        }
+        owned_bit_set.set(64);
+        owned_bit_set.set(65);
+        owned_bit_set.set(67);
+        {
+            let bit_set = owned_bit_set.get_bit_set();
+            assert_eq!(bit_set.get(0), Some(true));
+            assert_eq!(bit_set.get(3), Some(true));
+            assert_eq!(bit_set.get(1), Some(false));
+            assert_eq!(bit_set.get(64), Some(true));
+            assert_eq!(bit_set.get(65), Some(true));
+            assert_eq!(bit_set.get(67), Some(true));
+            assert_eq!(bit_set.get(66), Some(false));
+            let mut iter = bit_set.long_iter();
+            assert_eq!(iter.next(), Some(0b1001));
+            assert_eq!(iter.next(), Some(0b1011));
+            assert_eq!(iter.next(), None);
+            let mut raw = Vec::new();
+            bit_set.long_iter().for_each(|n| n.write(&mut raw).unwrap());
+            let bit_set = unsafe { BitSet::new_raw(&raw) };
+            for i in 0..127 {
+                assert_eq!(
+                    bit_set.get(i),
+                    Some(match i {
+                        0 | 3 | 64 | 65 | 67 => true,
+                        _ => false,
+                    })
+                )
+            }
+            assert_eq!(bit_set.get(128), None);
+        }
+    }
+
+    #[test]
+    fn light_array_test() {
+        unsafe {
+            let mut owned_light_array = OwnedLightArray::new();
+            assert_eq!(owned_light_array.is_empty(), true);
+            owned_light_array.set(Vector3D::new(0, 0, 0), 15);
+            owned_light_array.set(Vector3D::new(1, 1, 1), 13);
+            owned_light_array.set(Vector3D::new(2, 1, 1), 11);
+            {
+                let light_array = owned_light_array.as_light_array();
+                assert_eq!(light_array.get(Vector3D::new(0, 0, 0)), 15);
+                assert_eq!(light_array.get(Vector3D::new(1, 1, 1)), 13);
+                assert_eq!(light_array.get(Vector3D::new(2, 1, 1)), 11);
+                assert_eq!(light_array.get(Vector3D::new(2, 2, 1)), 0);
+                assert_eq!(light_array.get_bytes()[0], 15);
+            }
+            owned_light_array.set(Vector3D::new(0, 0, 0), 0);
+            owned_light_array.set(Vector3D::new(1, 1, 1), 0);
+            owned_light_array.set(Vector3D::new(2, 1, 1), 0);
+            assert_eq!(owned_light_array.is_empty(), true);
+        }
+    }
+
+    #[test]
+    fn particle_test() {
+        let mut empty_slice = [].as_slice();
+        let mut zero_slice = [0].as_slice();
+        assert_eq!(Particle::read(83, &mut empty_slice).unwrap(), Particle::Glow);
+        assert_eq!(Particle::read(37, &mut empty_slice).unwrap(), Particle::ItemSlime);
+        assert_eq!(Particle::read(2, &mut zero_slice).unwrap(), Particle::Block { block_state: 0 });
+        assert_eq!(Particle::Glow.get_id(), 83);
+        assert_eq!(Particle::ItemSlime.get_id(), 37);
+        assert_eq!(Particle::Block { block_state: 2 }.get_id(), 2);
+    }
+
+    #[test]
+    fn sound_test() {
+        let packet = SoundEffectPS2C {
+            sound: SoundEvent::Registry(Sound::EntityEnderDragonGrowl),
+            sound_category: CustomSoundCategory::Hostile,
+            effect_position_x: 0.5,
+            effect_position_y: 64.0,
+            effect_position_z: -0.5,
+            volume: 1.0,
+            pitch: 1.0,
+            seed: 0,
+        };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(VarInt::read_variant(&mut bytes.as_slice()).ok(), Some(Sound::EntityEnderDragonGrowl.get_id() as i32 + 1));
+        assert_eq!(SoundEffectPS2C::read(&mut bytes.as_slice()).unwrap(), packet);
+        let packet = StopSoundPS2C {
+            sound_category: None,
+            sound: Some(Identifier::new_full(Cow::Borrowed("custom:music")).unwrap()),
+        };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(StopSoundPS2C::read(&mut bytes.as_slice()).unwrap(), packet);
+    }
+
+    #[test]
+    fn world_event_test() {
+        let packet = WorldEventPS2C::new(WorldEvent::Smoke { direction: SmokeDirection::West }, Vector3D::new(1, -2, 3));
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len() as u32, WorldEventPS2C::SIZE.start);
+        assert_eq!(i32::read(&mut bytes.as_slice()).unwrap(), 2000);
+        assert_eq!(WorldEventPS2C::read(&mut bytes.as_slice()).unwrap(), packet);
+        assert!(WorldEventPS2C::new(WorldEvent::WitherSpawns, Vector3D::zero()).disable_relative_volume);
+    }
+
+    #[test]
+    fn container_property_test() {
+        let packet = SetContainerPropertyPS2C::furnace(1, FurnaceProperty::ProgressArrow, 12);
+        assert_eq!(packet, SetContainerPropertyPS2C { window_id: 1, property: 2, value: 12 });
+        assert_eq!(packet.typed_property(InventorySizeType::Smoker), Some(ContainerProperty::Furnace(FurnaceProperty::ProgressArrow)));
+        assert_eq!(packet.typed_property(InventorySizeType::Lectern), None);
+        assert!(ContainerProperty::LecternPage.is_supported_by(InventorySizeType::Lectern));
+        assert!(!ContainerProperty::from(BeaconProperty::PowerLevel).is_supported_by(InventorySizeType::Furnace));
+    }
+
+    #[test]
+    fn field_validation_test() {
+        let mut packet = LoginStartLC2S { name: "simon", uuid: None };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert_eq!(LoginStartLC2S::read(&mut bytes.as_slice()).unwrap(), packet);
+        packet.name = "a_very_long_username";
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes).unwrap();
+        assert!(LoginStartLC2S::read(&mut bytes.as_slice()).is_err());
+
+        // Length of pages is rejected before the pages are read
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&0, &mut bytes).unwrap();
+        VarInt::write_variant(&i32::MAX, &mut bytes).unwrap();
+        assert!(matches!(EditBookPC2S::read(&mut bytes.as_slice()), Err(ProtocolError::Any(_))));
+        let pages = vec![""; MAX_EDIT_BOOK_PAGES + 1];
+        let packet = EditBookPC2S { slot: 0, pages: Cow::Borrowed(&pages), title: None };
+        assert!(packet.write(&mut Vec::new()).is_err());
+    }
+}
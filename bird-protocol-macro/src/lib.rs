@@ -4,6 +4,7 @@ mod writable;
 mod size;
 mod packet;
 mod nbt;
+mod sample;
 
 macro_rules! derive_impl {
     ($func: expr) => {
@@ -44,6 +45,12 @@ pub fn protocol_all_derive(item: proc_macro::TokenStream) -> proc_macro::TokenSt
     writable
 }
 
+/// Opt-in derive of random samples for round trip tests, needs the `sample` feature of bird-protocol
+#[proc_macro_derive(ProtocolSample, attributes(bp))]
+pub fn protocol_sample_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    derive_impl!(sample::impl_derive(item))
+}
+
 #[proc_macro_derive(BirdNbt, attributes(bnbt))]
 pub fn bird_nbt_derive(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // println!("{}", nbt::impl_derive(item).unwrap());
@@ -111,7 +111,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                         .map(|(_variant, _name, field)| {
                             let Field { ident, ty, .. } = field;
                             quote! {
-                                let mut #ident: std::option::Option::<#ty> = std::option::Option::None;
+                                let mut #ident: core::option::Option::<#ty> = core::option::Option::None;
                             }
                         });
                     let read_fields = fields.iter()
@@ -165,7 +165,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             fn read_nbt<C: bird_protocol::ProtocolCursor<'a>>(__cursor: &mut C) -> bird_protocol::ProtocolResult<Self> {
                                 #(#read_prepare)*
                                 bird_protocol::nbt::compound::read_nbt_compound(__cursor, |__tag, __name, __cursor| {
-                                    match <Cow<#lifetime, str> as std::convert::AsRef<str>>::as_ref(&__name) {
+                                    match <Cow<#lifetime, str> as core::convert::AsRef<str>>::as_ref(&__name) {
                                         #(#read_fields,)*
                                         _ => bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::Any(
                                             bird_protocol::anyhow::Error::msg("Bad name")
@@ -180,7 +180,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                 let mut __result: usize = 0;
                                 for _ in 0..__amount {
                                     bird_protocol::nbt::compound::read_nbt_compound(__cursor, |__tag, __name, __cursor| {
-                                        match <Cow<#lifetime, str> as std::convert::AsRef<str>>::as_ref(&__name) {
+                                        match <Cow<#lifetime, str> as core::convert::AsRef<str>>::as_ref(&__name) {
                                             #(#skip_fields,)*
                                             _ => bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::Any(
                                                 bird_protocol::anyhow::Error::msg("Bad name")
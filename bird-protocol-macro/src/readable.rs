@@ -1,7 +1,8 @@
+use std::collections::HashMap;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields, Variant};
-use crate::shared::{create_prepared_fields, create_prepared_variants, GhostValue, ObjectAttributes, obligate_lifetime, parse_attributes};
+use crate::shared::{bounded_generics, create_prepared_fields, create_prepared_variants, FieldAttributes, GhostValue, ObjectAttributes, obligate_lifetime, parse_attributes};
 use crate::size::enum_key_size;
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
@@ -15,6 +16,10 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
     let (lifetime, spec_impl_generics) = obligate_lifetime(&mut generics)?;
+    let lifetime_ident = &lifetime.lifetime;
+    let spec_impl_generics = bounded_generics(
+        spec_impl_generics, &object_attributes, quote! { bird_protocol::ProtocolReadable<#lifetime_ident> },
+    )?;
     let function_body = match data {
         Data::Struct(data_struct) => {
             let read = read_fields(data_struct.fields, quote! { Self }, &lifetime, object_attributes.ghost_values.into_iter())?;
@@ -54,7 +59,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                     let (min_key, max_key) = enum_key_size(&object_attributes)?;
                     quote! {
                         const __RCSIZE: usize = {
-                            std::assert!(
+                            core::assert!(
                                 <#ident as bird_protocol::ProtocolSize>::SIZE.start - #min_key ==
                                 <#ident as bird_protocol::ProtocolSize>::SIZE.end - #max_key
                             );
@@ -76,8 +81,8 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         }
         Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
     };
-    let (_, type_generics, where_clause) = generics.split_for_impl();
-    let (impl_generics, ..) = spec_impl_generics.split_for_impl();
+    let (_, type_generics, _) = generics.split_for_impl();
+    let (impl_generics, _, where_clause) = spec_impl_generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolReadable<#lifetime> for #ident #type_generics #where_clause {
             fn read<C: bird_protocol::ProtocolCursor<#lifetime>>(__cursor: &mut C) -> bird_protocol::ProtocolResult<Self> {
@@ -105,11 +110,30 @@ fn read_fields(fields: Fields, key: TokenStream, lifetime: &impl ToTokens, ghost
             quote! { Ok(#key{#(#idents,)*}) }
         }
     };
+    let mut validations = HashMap::new();
+    for (counter, field) in fields.iter().enumerate() {
+        let field_attributes: FieldAttributes = parse_attributes(&field.attrs, "bp")?;
+        if let Some(validate) = field_attributes.validate {
+            let field_ident = match field.ident {
+                Some(ref ident) => ident.clone(),
+                None => Ident::new(format!("__{}", counter).as_str(), Span::call_site()),
+            };
+            validations.insert(field_ident.to_string(), validate);
+        }
+    }
     let fields = create_prepared_fields(fields, ghost_values)?;
     let mut variables_ts = Vec::new();
     for (field_ident, field_value_expr, field_ty, field_variant) in fields {
         let read_ts = read_ts(field_ty.as_ref(), field_value_expr.as_ref(), lifetime, field_variant.as_ref());
         variables_ts.push(quote! { let #field_ident = #read_ts; });
+        if let Some(validate) = validations.get(&field_ident.to_string()) {
+            let message = format!("Field {} is not valid", field_ident);
+            variables_ts.push(quote! {
+                if !(#validate) {
+                    return bird_protocol::ProtocolResult::Err(bird_protocol::ProtocolError::Any(bird_protocol::anyhow::Error::msg(#message)));
+                }
+            });
+        }
     }
     Ok(quote! {
         #(#variables_ts;)*
@@ -0,0 +1,70 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields};
+use crate::shared::{bounded_generics, FieldAttributes, ObjectAttributes, parse_attributes};
+
+pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let item: DeriveInput = syn::parse(item)?;
+    let DeriveInput {
+        ident,
+        data,
+        generics,
+        attrs,
+        ..
+    } = item;
+    let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
+    let generics = bounded_generics(generics, &object_attributes, quote! { bird_protocol::sample::ProtocolSample })?;
+    let function_body = match data {
+        Data::Struct(data_struct) => sample_fields(quote! { Self }, data_struct.fields)?,
+        Data::Enum(data_enum) => {
+            let count = data_enum.variants.len() as u64;
+            if count == 0 {
+                return Err(syn::Error::new(Span::call_site(), "Enum without variants has no samples"));
+            }
+            let mut variant_samples = Vec::new();
+            for (index, variant) in data_enum.variants.into_iter().enumerate() {
+                let index = index as u64;
+                let variant_ident = variant.ident;
+                let sample = sample_fields(quote! { Self::#variant_ident }, variant.fields)?;
+                variant_samples.push(quote! { #index => #sample });
+            }
+            quote! {
+                match __random.next_below(#count) {
+                    #(#variant_samples,)*
+                    _ => unreachable!()
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
+    };
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics bird_protocol::sample::ProtocolSample for #ident #type_generics #where_clause {
+            fn sample(__random: &mut bird_protocol::sample::SampleRandom) -> Self {
+                #function_body
+            }
+        }
+    })
+}
+
+/// Fields are sampled by their variants, ghost values are not stored so they are skipped
+fn sample_fields(key: impl ToTokens, fields: Fields) -> syn::Result<TokenStream> {
+    let mut samples = Vec::new();
+    for field in fields.iter() {
+        let field_attributes: FieldAttributes = parse_attributes(&field.attrs, "bp")?;
+        let ty = &field.ty;
+        let sample = match field_attributes.variant {
+            Some(variant) => quote! { <#variant as bird_protocol::sample::ProtocolVariantSample<#ty>>::sample_variant(__random) },
+            None => quote! { <#ty as bird_protocol::sample::ProtocolSample>::sample(__random) },
+        };
+        samples.push(match field.ident {
+            Some(ref field_ident) => quote! { #field_ident: #sample },
+            None => sample,
+        });
+    }
+    Ok(match fields {
+        Fields::Unit => quote! { #key },
+        Fields::Named(_) => quote! { #key { #(#samples,)* } },
+        Fields::Unnamed(_) => quote! { #key(#(#samples,)*) },
+    })
+}
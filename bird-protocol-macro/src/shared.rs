@@ -3,7 +3,8 @@ use std::str::FromStr;
 use either::Either;
 use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
-use syn::{Expr, ExprPath, ExprTuple, Fields, GenericParam, Generics, Lifetime, LifetimeDef, Lit, Token, Variant};
+use syn::{Expr, ExprLit, ExprPath, ExprTuple, Fields, GenericParam, Generics, Lifetime, LifetimeDef, Lit, Token, TypeParamBound, Variant, WherePredicate};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
@@ -17,6 +18,8 @@ pub struct ObjectAttributes {
     pub packet_bound: Option<TokenStream>,
     pub packet_state: Option<TokenStream>,
     pub ghost_values: Vec<GhostValue>,
+    /// Replaces the bounds which are added to every type parameter
+    pub bounds: Option<Punctuated<WherePredicate, Token![,]>>,
 }
 
 impl Default for ObjectAttributes {
@@ -29,7 +32,8 @@ impl Default for ObjectAttributes {
             packet_id: None,
             packet_bound: None,
             packet_state: None,
-            ghost_values: vec![]
+            ghost_values: vec![],
+            bounds: None,
         }
     }
 }
@@ -59,6 +63,9 @@ pub enum GhostValueOrder {
 pub struct FieldAttributes {
     pub order: Option<(u32, Span)>,
     pub variant: Option<TokenStream>,
+    /// Boolean expression which is checked after the field is read, the field is available by its name.
+    /// Lengths of arrays are limited by `LengthLimitedArray` instead, so long arrays are not read at all
+    pub validate: Option<TokenStream>,
 }
 
 pub struct Attributes {
@@ -233,7 +240,8 @@ impl Parse for Attributes {
                         };
                     }
                     false => {
-                        current_expr_assign_key.replace(input.parse()?);
+                        // Keys can be keywords like `where`
+                        current_expr_assign_key.replace(Ident::parse_any(input)?);
                         let _: Token![=] = input.parse()?;
                     }
                 }
@@ -287,6 +295,12 @@ impl Parse for GhostValue {
 impl Parse for ObjectAttributes {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut attributes: Attributes = input.parse()?;
+        // Where predicates are written as a string literal like in serde
+        let bounds = match attributes.remove_attribute(&"where".into()) {
+            Some(Expr::Lit(ExprLit { lit: Lit::Str(lit_str), .. })) => Some(lit_str.parse_with(Punctuated::parse_terminated)?),
+            Some(expr) => return Err(syn::Error::new(expr.span(), "Must be string literal of where predicates")),
+            None => None,
+        };
         Ok(Self {
             key_variant: attributes.remove_ts_attribute(&"variant".into())?,
             key_ty: attributes.remove_ts_attribute(&"ty".into())?,
@@ -296,6 +310,7 @@ impl Parse for ObjectAttributes {
             packet_bound: attributes.remove_ts_attribute(&"bound".into())?,
             packet_state: attributes.remove_ts_attribute(&"state".into())?,
             ghost_values: attributes.remove_ghost_values(&"ghost".into())?,
+            bounds,
         })
     }
 }
@@ -316,6 +331,7 @@ impl Parse for FieldAttributes {
         Ok(Self {
             order: attributes.remove_str_parse_attribute(&"order".into())?,
             variant: attributes.remove_ts_attribute(&"variant".into())?,
+            validate: attributes.remove_ts_attribute(&"validate".into())?,
         })
     }
 }
@@ -426,4 +442,19 @@ pub fn obligate_lifetime(generics: &mut Generics) -> syn::Result<(LifetimeDef, G
             })
         }
     }
+}
+
+/// Adds the trait bound to every type parameter,
+/// if bounds are given with `#[bp(where = "...")]` only they are added to the where clause
+pub fn bounded_generics(mut generics: Generics, object_attributes: &ObjectAttributes, bound: TokenStream) -> syn::Result<Generics> {
+    match object_attributes.bounds {
+        Some(ref predicates) => generics.make_where_clause().predicates.extend(predicates.iter().cloned()),
+        None => {
+            let bound: TypeParamBound = syn::parse2(bound)?;
+            for type_param in generics.type_params_mut() {
+                type_param.bounds.push(bound.clone());
+            }
+        }
+    }
+    Ok(generics)
 }
\ No newline at end of file
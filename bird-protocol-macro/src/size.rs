@@ -1,7 +1,7 @@
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields};
-use crate::shared::{FieldAttributes, GhostValue, ObjectAttributes, parse_attributes, VariantAttributes};
+use crate::shared::{bounded_generics, FieldAttributes, GhostValue, ObjectAttributes, parse_attributes, VariantAttributes};
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let item: DeriveInput = syn::parse(item)?;
@@ -13,6 +13,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         ..
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
+    let generics = bounded_generics(generics, &object_attributes, quote! { bird_protocol::ProtocolSize })?;
     let size = match data {
         Data::Struct(data_struct) => {
             let (min, max) = fields_size(data_struct.fields, object_attributes.ghost_values.into_iter())?;
@@ -48,7 +49,7 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
     Ok(quote! {
         impl #impl_generics bird_protocol::ProtocolSize for #ident #type_generics #where_clause {
-            const SIZE: std::ops::Range<u32> = #size;
+            const SIZE: core::ops::Range<u32> = #size;
         }
     })
 }
@@ -1,7 +1,7 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Fields, Variant};
-use crate::shared::{create_prepared_fields, create_prepared_variants, GhostValue, ObjectAttributes, parse_attributes};
+use crate::shared::{bounded_generics, create_prepared_fields, create_prepared_variants, GhostValue, ObjectAttributes, parse_attributes};
 
 pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let item: DeriveInput = syn::parse(item)?;
@@ -13,16 +13,22 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
         ..
     } = item;
     let object_attributes: ObjectAttributes = parse_attributes(&attrs, "bp")?;
-    let function_body = match data {
+    let generics = bounded_generics(generics, &object_attributes, quote! { bird_protocol::ProtocolWritable })?;
+    let (function_body, size_body) = match data {
         Data::Struct(data_struct) => {
             let write_match = write_match(quote! { Self }, &data_struct.fields)?;
-            let write_fields = write_fields(data_struct.fields, object_attributes.ghost_values.into_iter())?;
-            quote! { #write_match => { #write_fields }, }
+            let write_fields = write_fields(data_struct.fields.clone(), object_attributes.ghost_values.iter().cloned())?;
+            let size_fields = size_fields(data_struct.fields, object_attributes.ghost_values.into_iter())?;
+            (
+                quote! { #write_match => { #write_fields }, },
+                quote! { #write_match => { #size_fields }, },
+            )
         }
         Data::Enum(data_enum) => {
             let key_ty = object_attributes.key_ty.as_ref().ok_or_else(|| syn::Error::new(Span::call_site(), "You should provide key_ty for enum object"))?;
             let variants = create_prepared_variants(data_enum.variants.into_iter(), &object_attributes)?;
             let mut variant_matches = Vec::new();
+            let mut variant_sizes = Vec::new();
             for (variant, variant_value, variant_attributes) in variants {
                 let Variant {
                     fields,
@@ -31,19 +37,31 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 } = variant;
                 let write_match = write_match(quote! { Self::#ident }, &fields)?;
                 let write_key = write_ts(&quote! { (#variant_value) }, key_ty, object_attributes.key_variant.as_ref());
+                let size_key = size_ts(&quote! { (#variant_value) }, key_ty, object_attributes.key_variant.as_ref());
                 let write_fields = write_fields(
+                    fields.clone(),
+                    object_attributes.ghost_values.iter().cloned().chain(variant_attributes.ghost_values.iter().cloned()),
+                )?;
+                let size_fields = size_fields(
                     fields,
                     object_attributes.ghost_values.iter().cloned().chain(variant_attributes.ghost_values.into_iter()),
                 )?;
+                variant_sizes.push(quote! { #write_match => { __size += #size_key; #size_fields } });
                 variant_matches.push(match object_attributes.key_reverse.0 {
                     false => quote! { #write_match => { #write_key; #write_fields } },
                     true => quote! { #write_match => { #write_fields #write_key; } }
                 });
             }
-            quote! {
-                #(#variant_matches,)*
-                _ => unreachable!()
-            }
+            (
+                quote! {
+                    #(#variant_matches,)*
+                    _ => unreachable!()
+                },
+                quote! {
+                    #(#variant_sizes,)*
+                    _ => unreachable!()
+                },
+            )
         }
         Data::Union(_) => return Err(syn::Error::new(Span::mixed_site(), "Union is not supported")),
     };
@@ -56,6 +74,18 @@ pub fn impl_derive(item: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 }
                 bird_protocol::anyhow::Result::Ok(())
             }
+
+            fn exact_size(&self) -> bird_protocol::anyhow::Result<usize> {
+                if <Self as bird_protocol::ProtocolSize>::SIZE.start == <Self as bird_protocol::ProtocolSize>::SIZE.end {
+                    return bird_protocol::anyhow::Result::Ok(<Self as bird_protocol::ProtocolSize>::SIZE.start as usize);
+                }
+                #[allow(unused_mut)]
+                let mut __size = 0usize;
+                match self {
+                    #size_body
+                }
+                bird_protocol::anyhow::Result::Ok(__size)
+            }
         }
     })
 }
@@ -90,6 +120,23 @@ pub fn write_fields(fields: Fields, ghost_values: impl Iterator<Item = GhostValu
     Ok(quote! { #(#writes_ts;)* })
 }
 
+/// Adds exact sizes of the fields to `__size`
+pub fn size_fields(fields: Fields, ghost_values: impl Iterator<Item = GhostValue>) -> syn::Result<TokenStream> {
+    let fields = create_prepared_fields(fields, ghost_values)?;
+    let mut sizes_ts = Vec::new();
+    for (field_ident, field_value_expr, field_ty, field_variant) in fields {
+        sizes_ts.push(size_ts(&field_value_expr.unwrap_or(field_ident), &field_ty.unwrap_or_else(|| quote! { _ }), field_variant.as_ref()));
+    }
+    Ok(quote! { #(__size += #sizes_ts;)* })
+}
+
+pub fn size_ts(value: &impl ToTokens, ty: &impl ToTokens, variant: Option<&impl ToTokens>) -> TokenStream {
+    match variant {
+        Some(variant) => quote! { <#variant as bird_protocol::ProtocolVariantWritable<#ty>>::variant_exact_size(&#value)? },
+        None => quote! { <#ty as bird_protocol::ProtocolWritable>::exact_size(&#value)? },
+    }
+}
+
 pub fn write_ts(write: &impl ToTokens, ty: &impl ToTokens, variant: Option<&impl ToTokens>) -> TokenStream {
     match variant {
         Some(variant) => quote! { <#variant as bird_protocol::ProtocolVariantWritable<#ty>>::write_variant(&#write, __writer)? },
@@ -0,0 +1,74 @@
+#![feature(test)]
+
+extern crate test;
+
+use test::{black_box, Bencher};
+use bird_protocol::*;
+
+const INT_VALUES: [i32; 6] = [0, 127, 300, 2097151, i32::MAX, -1];
+const LONG_VALUES: [i64; 6] = [0, 127, 300, i32::MAX as i64, i64::MAX, -1];
+
+fn encoded<T, V: ProtocolVariantWritable<T>>(values: &[T]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in values {
+        V::write_variant(value, &mut bytes).unwrap();
+    }
+    bytes
+}
+
+#[bench]
+fn var_int_write(bencher: &mut Bencher) {
+    let mut bytes = Vec::with_capacity(INT_VALUES.len() * MAX_VAR_INT_SIZE);
+    bencher.iter(|| {
+        bytes.clear();
+        for value in black_box(&INT_VALUES) {
+            VarInt::write_variant(value, &mut bytes).unwrap();
+        }
+        black_box(&bytes);
+    });
+}
+
+#[bench]
+fn var_int_read(bencher: &mut Bencher) {
+    let bytes = encoded::<i32, VarInt>(&INT_VALUES);
+    bencher.iter(|| {
+        let mut cursor = black_box(bytes.as_slice());
+        for _ in 0..INT_VALUES.len() {
+            let value: i32 = VarInt::read_variant(&mut cursor).unwrap();
+            black_box(value);
+        }
+    });
+}
+
+#[bench]
+fn var_int_size_of(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        for value in black_box(&INT_VALUES) {
+            black_box(var_int_size(*value as u32));
+        }
+    });
+}
+
+#[bench]
+fn var_long_write(bencher: &mut Bencher) {
+    let mut bytes = Vec::with_capacity(LONG_VALUES.len() * MAX_VAR_LONG_SIZE);
+    bencher.iter(|| {
+        bytes.clear();
+        for value in black_box(&LONG_VALUES) {
+            VarLong::write_variant(value, &mut bytes).unwrap();
+        }
+        black_box(&bytes);
+    });
+}
+
+#[bench]
+fn var_long_read(bencher: &mut Bencher) {
+    let bytes = encoded::<i64, VarLong>(&LONG_VALUES);
+    bencher.iter(|| {
+        let mut cursor = black_box(bytes.as_slice());
+        for _ in 0..LONG_VALUES.len() {
+            let value: i64 = VarLong::read_variant(&mut cursor).unwrap();
+            black_box(value);
+        }
+    });
+}
@@ -0,0 +1,34 @@
+use alloc::string::{String, ToString};
+use core::fmt::{Debug, Display, Formatter};
+
+/// Error with the message of its cause, the api is the part of `anyhow::Error` which is used by protocol types
+pub struct Error {
+    message: String,
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+impl Error {
+    pub fn msg<M: Display + Debug + Send + Sync + 'static>(message: M) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// `Error` doesn't implement the error trait, otherwise this conflicts with the conversion into itself
+impl<E: core::error::Error + Send + Sync + 'static> From<E> for Error {
+    fn from(error: E) -> Self {
+        Self::msg(error)
+    }
+}
@@ -0,0 +1,200 @@
+use std::io::IoSlice;
+use std::ops::Range;
+use std::sync::Mutex;
+use crate::{anyhow, encode_var_int, ProtocolWriter, MAX_VAR_INT_SIZE};
+
+/// Maximum size of the VarInt length which is reserved in front of every frame
+pub const FRAME_HEADER_SIZE: usize = MAX_VAR_INT_SIZE;
+/// Vanilla client doesn't accept longer frames
+pub const MAX_FRAME_LENGTH: usize = 2097151;
+pub const DEFAULT_POOL_BUFFERS: usize = 64;
+pub const DEFAULT_POOL_BUFFER_CAPACITY: usize = 1 << 20;
+
+/// Buffer of outgoing length prefixed frames.
+/// The body is written first after the reserved header and the length is patched in front of it,
+/// so the body is never copied. Headers are shorter than the reservation, so frames are written
+/// to the socket with [`PacketBuffer::io_slices`]
+#[derive(Clone, Debug, Default)]
+pub struct PacketBuffer {
+    bytes: Vec<u8>,
+    /// Ranges of finished frames including their headers
+    frames: Vec<Range<usize>>,
+    /// Start of the reserved header of the unfinished frame
+    frame_start: Option<usize>,
+}
+
+impl PacketBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { bytes: Vec::with_capacity(capacity), frames: Vec::new(), frame_start: None }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Reserves the header of a new frame, the unfinished frame is discarded
+    pub fn begin_frame(&mut self) {
+        self.cancel_frame();
+        self.frame_start = Some(self.bytes.len());
+        self.bytes.resize(self.bytes.len() + FRAME_HEADER_SIZE, 0);
+    }
+
+    /// Writes the length of the body in front of it
+    pub fn finish_frame(&mut self) -> anyhow::Result<()> {
+        let body_start = self.frame_start.take()
+            .ok_or_else(|| anyhow::Error::msg("Frame is not started"))? + FRAME_HEADER_SIZE;
+        let length = self.bytes.len() - body_start;
+        if length > MAX_FRAME_LENGTH {
+            self.bytes.truncate(body_start - FRAME_HEADER_SIZE);
+            return Err(anyhow::Error::msg("Frame is too long"));
+        }
+        let (header, header_size) = encode_var_int(length as u32);
+        let start = body_start - header_size;
+        self.bytes[start..body_start].copy_from_slice(&header[..header_size]);
+        self.frames.push(start..self.bytes.len());
+        Ok(())
+    }
+
+    /// Removes the body of the unfinished frame
+    pub fn cancel_frame(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            self.bytes.truncate(start);
+        }
+    }
+
+    /// Writes one frame, nothing is written if the function fails
+    pub fn write_frame<F: FnOnce(&mut Self) -> anyhow::Result<()>>(&mut self, function: F) -> anyhow::Result<()> {
+        self.begin_frame();
+        match function(self) {
+            Ok(()) => self.finish_frame(),
+            Err(err) => {
+                self.cancel_frame();
+                Err(err)
+            }
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Bytes of all finished frames
+    pub fn len(&self) -> usize {
+        self.frames.iter().map(|frame| frame.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.frames.iter().map(|frame| &self.bytes[frame.clone()])
+    }
+
+    /// Finished frames for a vectored write into the socket
+    pub fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.frames().map(IoSlice::new).collect()
+    }
+
+    /// Copies finished frames into one contiguous vector
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        for frame in self.frames() {
+            bytes.extend_from_slice(frame);
+        }
+        bytes
+    }
+
+    /// Removes the given amount of bytes of finished frames after they were written into the socket.
+    /// Partially written frame keeps the rest of its bytes
+    pub fn consume(&mut self, mut written: usize) {
+        let mut finished = 0;
+        for frame in self.frames.iter_mut() {
+            if written < frame.len() {
+                frame.start += written;
+                break;
+            }
+            written -= frame.len();
+            finished += 1;
+        }
+        self.frames.drain(..finished);
+        if self.frames.is_empty() && self.frame_start.is_none() {
+            self.bytes.clear();
+        }
+    }
+
+    /// Removes all frames keeping the allocated memory
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.frames.clear();
+        self.frame_start = None;
+    }
+}
+
+impl ProtocolWriter for PacketBuffer {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.write_bytes(bytes)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.bytes.push(byte)
+    }
+
+    fn write_vectored_bytes(&mut self, slices: &[&[u8]]) {
+        self.bytes.write_vectored_bytes(slices)
+    }
+}
+
+/// Buffers which are shared between connections, so memory of sent packets is reused
+#[derive(Debug)]
+pub struct PacketBufferPool {
+    buffers: Mutex<Vec<PacketBuffer>>,
+    max_buffers: usize,
+    /// Buffers which have grown above it are dropped, so one big packet doesn't hold the memory forever
+    max_capacity: usize,
+}
+
+impl Default for PacketBufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_BUFFERS, DEFAULT_POOL_BUFFER_CAPACITY)
+    }
+}
+
+impl PacketBufferPool {
+    pub const fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self { buffers: Mutex::new(Vec::new()), max_buffers, max_capacity }
+    }
+
+    /// Takes a free buffer or allocates a new one
+    pub fn acquire(&self) -> PacketBuffer {
+        self.buffers.lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Gives the buffer back after its frames were sent
+    pub fn release(&self, mut buffer: PacketBuffer) {
+        if buffer.capacity() > self.max_capacity {
+            return;
+        }
+        buffer.clear();
+        let mut buffers = self.buffers.lock().unwrap_or_else(|err| err.into_inner());
+        if buffers.len() < self.max_buffers {
+            buffers.push(buffer);
+        }
+    }
+
+    /// Amount of free buffers
+    pub fn len(&self) -> usize {
+        self.buffers.lock().unwrap_or_else(|err| err.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::{self, Read, Write};
+use aes::Aes128;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::generic_array::GenericArray;
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use tokio_util::codec::{Decoder, Encoder};
+use crate::{anyhow, decode_var_int, encode_var_int, var_int_size, ProtocolError, MAX_FRAME_LENGTH};
+
+/// Vanilla rejects compressed frames which are longer after the decompression
+pub const MAX_DECOMPRESSED_LENGTH: usize = 8388608;
+
+type Encryptor = cfb8::Encryptor<Aes128>;
+type Decryptor = cfb8::Decryptor<Aes128>;
+
+/// Length prefixed frames of a connection, items are ids and bodies of packets.
+/// The compression and the encryption are enabled during the login, so they are changed between frames
+pub struct McFrameCodec {
+    max_frame_length: usize,
+    compression_threshold: Option<i32>,
+    encryptor: Option<Encryptor>,
+    decryptor: Option<Decryptor>,
+    /// Bytes at the start of the read buffer which are already decrypted
+    decrypted: usize,
+}
+
+impl Default for McFrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McFrameCodec {
+    pub fn new() -> Self {
+        Self {
+            max_frame_length: MAX_FRAME_LENGTH,
+            compression_threshold: None,
+            encryptor: None,
+            decryptor: None,
+            decrypted: 0,
+        }
+    }
+
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    /// Longer frames are rejected in both directions
+    pub fn set_max_frame_length(&mut self, max_frame_length: usize) {
+        self.max_frame_length = max_frame_length;
+    }
+
+    pub fn compression_threshold(&self) -> Option<i32> {
+        self.compression_threshold
+    }
+
+    /// Frames of this size and bigger are compressed, `None` disables the compression
+    pub fn set_compression_threshold(&mut self, threshold: Option<i32>) {
+        self.compression_threshold = threshold;
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryptor.is_some()
+    }
+
+    /// Enables AES/CFB8 with the shared secret as the key and the iv.
+    /// Bytes which are already read after the last frame are decrypted with the next frame
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> anyhow::Result<()> {
+        let invalid = |_| anyhow::Error::msg("Shared secret must have 16 bytes");
+        self.encryptor = Some(Encryptor::new_from_slices(shared_secret, shared_secret).map_err(invalid)?);
+        self.decryptor = Some(Decryptor::new_from_slices(shared_secret, shared_secret).map_err(invalid)?);
+        self.decrypted = 0;
+        Ok(())
+    }
+
+    /// Decrypts bytes which were read after the previous call
+    fn decrypt(&mut self, src: &mut BytesMut) {
+        if let Some(ref mut decryptor) = self.decryptor {
+            for byte in src[self.decrypted..].chunks_mut(1) {
+                decryptor.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+            self.decrypted = src.len();
+        }
+    }
+
+    fn put_header(&self, dst: &mut BytesMut, length: usize) -> anyhow::Result<()> {
+        if length > self.max_frame_length {
+            return Err(anyhow::Error::msg("Frame is too long"));
+        }
+        put_var_int(dst, length);
+        dst.reserve(length);
+        Ok(())
+    }
+}
+
+fn put_var_int(dst: &mut BytesMut, value: usize) {
+    let (bytes, size) = encode_var_int(value as u32);
+    dst.extend_from_slice(&bytes[..size]);
+}
+
+/// Vanilla rejects frames which are compressed but shorter than the threshold
+fn decompress(mut frame: BytesMut, threshold: i32) -> anyhow::Result<BytesMut> {
+    let (length, size) = decode_var_int(&frame)?;
+    let length = length as usize;
+    frame.advance(size);
+    if length == 0 {
+        return Ok(frame);
+    }
+    if length < threshold.max(0) as usize {
+        return Err(anyhow::Error::msg("Compressed frame is shorter than the threshold"));
+    }
+    if length > MAX_DECOMPRESSED_LENGTH {
+        return Err(anyhow::Error::msg("Decompressed frame is too long"));
+    }
+    let mut decompressed = BytesMut::with_capacity(length).writer();
+    io::copy(&mut ZlibDecoder::new(&frame[..]).take(length as u64 + 1), &mut decompressed)?;
+    let decompressed = decompressed.into_inner();
+    match decompressed.len() == length {
+        true => Ok(decompressed),
+        false => Err(anyhow::Error::msg("Decompressed frame has the wrong length")),
+    }
+}
+
+impl Decoder for McFrameCodec {
+    type Item = BytesMut;
+    type Error = anyhow::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> anyhow::Result<Option<BytesMut>> {
+        self.decrypt(src);
+        let (length, header_size) = match decode_var_int(&src[..]) {
+            Ok(header) => header,
+            Err(ProtocolError::End) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let length = length as usize;
+        if length > self.max_frame_length {
+            return Err(anyhow::Error::msg("Frame is too long"));
+        }
+        if src.len() < header_size + length {
+            src.reserve(header_size + length - src.len());
+            return Ok(None);
+        }
+        src.advance(header_size);
+        let frame = src.split_to(length);
+        self.decrypted = self.decrypted.saturating_sub(header_size + length);
+        match self.compression_threshold {
+            Some(threshold) => decompress(frame, threshold).map(Some),
+            None => Ok(Some(frame)),
+        }
+    }
+}
+
+impl<'a> Encoder<&'a [u8]> for McFrameCodec {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, frame: &'a [u8], dst: &mut BytesMut) -> anyhow::Result<()> {
+        let start = dst.len();
+        match self.compression_threshold {
+            Some(threshold) if frame.len() >= threshold.max(0) as usize => {
+                let mut encoder = ZlibEncoder::new(Vec::with_capacity(frame.len() / 2), Compression::default());
+                encoder.write_all(frame)?;
+                let compressed = encoder.finish()?;
+                self.put_header(dst, var_int_size(frame.len() as u32) + compressed.len())?;
+                put_var_int(dst, frame.len());
+                dst.extend_from_slice(&compressed);
+            }
+            Some(_) => {
+                self.put_header(dst, frame.len() + 1)?;
+                dst.put_u8(0);
+                dst.extend_from_slice(frame);
+            }
+            None => {
+                self.put_header(dst, frame.len())?;
+                dst.extend_from_slice(frame);
+            }
+        }
+        if let Some(ref mut encryptor) = self.encryptor {
+            for byte in dst[start..].chunks_mut(1) {
+                encryptor.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_codec_test() {
+        let mut client = McFrameCodec::new();
+        let mut server = McFrameCodec::new();
+        let mut bytes = BytesMut::new();
+        client.encode(&[0x00, 1, 2][..], &mut bytes).unwrap();
+        assert_eq!(&bytes[..], &[3, 0x00, 1, 2]);
+        let mut partial = BytesMut::from(&bytes[..2]);
+        assert!(server.decode(&mut partial).unwrap().is_none());
+
+        // Frames after the enabling are encrypted, they are read together with the previous one
+        client.enable_encryption(&[7; 16]).unwrap();
+        client.set_compression_threshold(Some(256));
+        let long = vec![1; 1024];
+        client.encode(&[5][..], &mut bytes).unwrap();
+        client.encode(long.as_slice(), &mut bytes).unwrap();
+        assert!(bytes.len() < long.len());
+        let mut received = bytes.split_to(10);
+        assert_eq!(&server.decode(&mut received).unwrap().unwrap()[..], &[0x00, 1, 2]);
+        server.enable_encryption(&[7; 16]).unwrap();
+        server.set_compression_threshold(Some(256));
+        assert_eq!(&server.decode(&mut received).unwrap().unwrap()[..], &[5]);
+        assert!(server.decode(&mut received).unwrap().is_none());
+        received.extend_from_slice(&bytes);
+        assert_eq!(&server.decode(&mut received).unwrap().unwrap()[..], long.as_slice());
+        assert!(received.is_empty());
+
+        assert!(client.enable_encryption(&[0; 3]).is_err());
+        let mut plain = McFrameCodec::new();
+        plain.set_max_frame_length(16);
+        assert!(plain.encode(long.as_slice(), &mut BytesMut::new()).is_err());
+        assert!(plain.decode(&mut BytesMut::from(&[100][..])).is_err());
+    }
+}
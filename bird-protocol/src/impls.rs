@@ -1,6 +1,8 @@
-use std::{borrow::Cow, str::from_utf8};
-use std::mem::{MaybeUninit, size_of};
-use euclid::{Vector2D, Vector3D};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::from_utf8;
+use core::mem::{MaybeUninit, size_of};
 use bird_chat::component::Component;
 use bird_chat::identifier::{Identifier, IdentifierInner};
 use bird_util::*;
@@ -21,14 +23,6 @@ protocol_raw!(u8, i8, bool, Uuid,);
 #[cfg(target_endian = "big")]
 protocol_raw!(u16, i16, u32, i32, u64, i64, u128, i128);
 
-unsafe impl<U> ProtocolRaw for Vector3D<u8, U> {}
-
-unsafe impl<U> ProtocolRaw for Vector3D<i8, U> {}
-
-unsafe impl<U> ProtocolRaw for Vector2D<u8, U> {}
-
-unsafe impl<U> ProtocolRaw for Vector2D<i8, U> {}
-
 macro_rules! gen_u32_operation {
     ($ty: ty, $new_name: ident, $func: ident, $default_value: expr) => {
         pub const fn $new_name<const N: usize>(array: [$ty; N]) -> $ty {
@@ -108,6 +102,10 @@ impl<T: ProtocolWritable> ProtocolVariantWritable<T> for T {
     fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
         object.write(writer)
     }
+
+    fn variant_exact_size(object: &T) -> anyhow::Result<usize> {
+        object.exact_size()
+    }
 }
 
 macro_rules! delegate_size {
@@ -140,15 +138,15 @@ macro_rules! fixed_size {
 
 macro_rules! number_impl {
     ($ty: ty) => {
-        fixed_size!($ty = std::mem::size_of::<$ty>() as u32);
+        fixed_size!($ty = core::mem::size_of::<$ty>() as u32);
 
         impl<'a> ProtocolReadable<'a> for $ty {
             fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
-                let mut bytes = [0u8; std::mem::size_of::<Self>()];
+                let mut bytes = [0u8; core::mem::size_of::<Self>()];
                 let slice = cursor.take_bytes(bytes.len())?;
                 unsafe {
                     // Safety. Slice reference is valid, bytes reference also. They don't overlap
-                    std::ptr::copy_nonoverlapping(slice.as_ptr(), bytes.as_mut_ptr(), bytes.len())
+                    core::ptr::copy_nonoverlapping(slice.as_ptr(), bytes.as_mut_ptr(), bytes.len())
                 }
                 Ok(Self::from_be_bytes(bytes))
             }
@@ -186,40 +184,30 @@ impl ProtocolWritable for bool {
 }
 
 macro_rules! var_number_impl {
-    ($($ty: ty = ($signed: ty, $unsigned: ty)$(,)*)*) => {
+    ($($ty: ty = ($signed: ty, $unsigned: ty, $read: ident, $encode: ident)$(,)*)*) => {
         $(
             impl<'a> ProtocolVariantReadable<'a, $signed> for $ty {
+                #[inline]
                 fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<$signed> {
-                    let mut value: $signed = 0;
-                    let mut position = 0u8;
-                    loop {
-                        let current_byte = cursor.take_byte()?;
-                        value |= ((current_byte & 0x7F) << position) as $signed;
-                        if ((current_byte & 0x80) == 0) {
-                            break;
-                        }
-                        position += 7;
-                        if (position >= (std::mem::size_of::<$signed>() * 8) as u8) {
-                            return Err(anyhow::Error::msg("Var number is too big").into());
-                        }
-                    }
-                    Ok(value)
+                    $read(cursor).map(|value| value as $signed)
                 }
             }
 
             impl ProtocolVariantWritable<$signed> for $ty {
+                #[inline]
                 fn write_variant<W: ProtocolWriter>(object: &$signed, writer: &mut W) -> anyhow::Result<()> {
-                    let mut object = *object as $unsigned;
-                    loop {
-                        if ((object & !0x7F) == 0) {
-                            writer.write_byte(object as u8);
-                            break;
-                        }
-                        writer.write_byte((object as u8 & 0x7F) | 0x80);
-                        object >>= 7;
+                    let (bytes, size) = $encode(*object as $unsigned);
+                    match size {
+                        1 => writer.write_byte(bytes[0]),
+                        _ => writer.write_bytes(&bytes[..size]),
                     }
                     Ok(())
                 }
+
+                #[inline]
+                fn variant_exact_size(object: &$signed) -> anyhow::Result<usize> {
+                    Ok($encode(*object as $unsigned).1)
+                }
             }
         )*
     }
@@ -273,7 +261,10 @@ impl<'a> ProtocolVariantWritable<bool> for VarLong {
     }
 }
 
-var_number_impl!(VarInt = (i32, u32), VarLong = (i64, u64));
+var_number_impl!(
+    VarInt = (i32, u32, read_var_int, encode_var_int),
+    VarLong = (i64, u64, read_var_long, encode_var_long),
+);
 
 impl<T: ProtocolSize> ProtocolSize for Option<T> {
     const SIZE: Range<u32> = (1..add_u32_without_overflow(T::SIZE.end, 1));
@@ -289,6 +280,13 @@ impl<T: ProtocolWritable> ProtocolWritable for Option<T> {
             None => false.write(writer),
         }
     }
+
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        match self {
+            Some(object) => Ok(1 + object.exact_size()?),
+            None => Ok(1),
+        }
+    }
 }
 
 impl<'a, T: ProtocolReadable<'a>> ProtocolReadable<'a> for Option<T> {
@@ -331,6 +329,14 @@ pub fn write_str_with_limit<W: ProtocolWriter, const LIMIT: usize>(
     }
 }
 
+/// Size of the string which is written by [`write_str_with_limit`]
+pub fn str_exact_size_with_limit<const LIMIT: usize>(length: usize) -> anyhow::Result<usize> {
+    match length <= LIMIT {
+        true => Ok(var_int_size(length as u32) + length),
+        false => Err(too_long_string()),
+    }
+}
+
 pub fn read_str_with_limit<'a, C: ProtocolCursor<'a>, const LIMIT: usize>(
     cursor: &mut C,
 ) -> ProtocolResult<&'a str> {
@@ -351,6 +357,10 @@ impl ProtocolWritable for &str {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         write_str_with_limit::<W, DEFAULT_LIMIT>(self, writer)
     }
+
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        str_exact_size_with_limit::<DEFAULT_LIMIT>(self.len())
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for &'a str {
@@ -365,6 +375,10 @@ impl ProtocolWritable for String {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
         self.as_str().write(writer)
     }
+
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        self.as_str().exact_size()
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for String {
@@ -380,6 +394,11 @@ impl<'a> ProtocolWritable for Cow<'a, str> {
             Cow::Borrowed(borrowed) => borrowed.write(writer),
         }
     }
+
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        let str: &str = self;
+        str.exact_size()
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for Cow<'a, str> {
@@ -389,11 +408,11 @@ impl<'a> ProtocolReadable<'a> for Cow<'a, str> {
 }
 
 const fn byte_array_into_t_array<T: Sized>(array: &[u8]) -> &[T] {
-    unsafe { std::slice::from_raw_parts(array.as_ptr() as *const T, array.len() / std::mem::size_of::<T>()) }
+    unsafe { core::slice::from_raw_parts(array.as_ptr() as *const T, array.len() / core::mem::size_of::<T>()) }
 }
 
 const fn t_array_into_byte_array<T: Sized>(array: &[T]) -> &[u8] {
-    unsafe { std::slice::from_raw_parts(array.as_ptr() as *const u8, array.len() * std::mem::size_of::<T>()) }
+    unsafe { core::slice::from_raw_parts(array.as_ptr() as *const u8, array.len() * core::mem::size_of::<T>()) }
 }
 
 macro_rules! primitive_length {
@@ -426,12 +445,45 @@ impl<L: ProtocolLength, LV: ProtocolVariantWritable<L>> ProtocolVariantWritable<
     fn write_variant<W: ProtocolWriter>(object: &usize, writer: &mut W) -> anyhow::Result<()> {
         LV::write_variant(&L::from_usize(*object), writer)
     }
+
+    fn variant_exact_size(object: &usize) -> anyhow::Result<usize> {
+        LV::variant_exact_size(&L::from_usize(*object))
+    }
 }
 
 impl<'a, L: ProtocolLength, LV: ProtocolVariantReadable<'a, L> + ProtocolVariantWritable<L> + ProtocolSize> ProtocolLengthDeterminer<'a> for ProtocolLengthProvidedDeterminer<L, LV> {
     const ELEMENT_COUNT: bool = true;
 }
 
+fn check_limited_length<const MAX: usize>(length: usize) -> anyhow::Result<usize> {
+    match length <= MAX {
+        true => Ok(length),
+        false => Err(anyhow::Error::msg("Array is too long")),
+    }
+}
+
+impl<'a, L: ProtocolLength, LV: ProtocolVariantReadable<'a, L>, const MAX: usize> ProtocolVariantReadable<'a, usize> for ProtocolLengthLimitedDeterminer<L, LV, MAX> {
+    fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<usize> {
+        Ok(check_limited_length::<MAX>(LV::read_variant(cursor)?.into_usize())?)
+    }
+}
+
+impl<L, LV: ProtocolSize, const MAX: usize> ProtocolSize for ProtocolLengthLimitedDeterminer<L, LV, MAX> { const SIZE: Range<u32> = LV::SIZE; }
+
+impl<L: ProtocolLength, LV: ProtocolVariantWritable<L>, const MAX: usize> ProtocolVariantWritable<usize> for ProtocolLengthLimitedDeterminer<L, LV, MAX> {
+    fn write_variant<W: ProtocolWriter>(object: &usize, writer: &mut W) -> anyhow::Result<()> {
+        LV::write_variant(&L::from_usize(check_limited_length::<MAX>(*object)?), writer)
+    }
+
+    fn variant_exact_size(object: &usize) -> anyhow::Result<usize> {
+        LV::variant_exact_size(&L::from_usize(check_limited_length::<MAX>(*object)?))
+    }
+}
+
+impl<'a, L: ProtocolLength, LV: ProtocolVariantReadable<'a, L> + ProtocolVariantWritable<L> + ProtocolSize, const MAX: usize> ProtocolLengthDeterminer<'a> for ProtocolLengthLimitedDeterminer<L, LV, MAX> {
+    const ELEMENT_COUNT: bool = true;
+}
+
 impl<'a> ProtocolVariantReadable<'a, usize> for ProtocolLengthRemainingDeterminer {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<usize> {
         Ok(cursor.remaining_bytes())
@@ -476,21 +528,34 @@ impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVar
 for LengthFunctionRawArray<V, VV, T>
 {
     fn write_variant<W: ProtocolWriter>(object: &[V], writer: &mut W) -> anyhow::Result<()> {
-        T::write_variant(&(object.len() * if T::ELEMENT_COUNT { 1 } else { std::mem::size_of::<V>() }), writer)?;
+        T::write_variant(&(object.len() * if T::ELEMENT_COUNT { 1 } else { core::mem::size_of::<V>() }), writer)?;
         Ok(writer.write_bytes(t_array_into_byte_array(object)))
     }
+
+    fn variant_exact_size(object: &[V]) -> anyhow::Result<usize> {
+        let length = T::variant_exact_size(&(object.len() * if T::ELEMENT_COUNT { 1 } else { core::mem::size_of::<V>() }))?;
+        Ok(length + object.len() * core::mem::size_of::<V>())
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<&'a [V]> for LengthFunctionRawArray<V, VV, T> {
     fn write_variant<W: ProtocolWriter>(object: &&'a [V], writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(*object, writer)
     }
+
+    fn variant_exact_size(object: &&'a [V]) -> anyhow::Result<usize> {
+        Self::variant_exact_size(*object)
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Vec<V>> for LengthFunctionRawArray<V, VV, T> {
     fn write_variant<W: ProtocolWriter>(object: &Vec<V>, writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(object.as_slice(), writer)
     }
+
+    fn variant_exact_size(object: &Vec<V>) -> anyhow::Result<usize> {
+        Self::variant_exact_size(object.as_slice())
+    }
 }
 
 impl<'a, V: Sized + Clone, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Cow<'_, [V]>> for LengthFunctionRawArray<V, VV, T>
@@ -507,12 +572,16 @@ impl<'a, V: Sized + Clone, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> Pro
             writer,
         )
     }
+
+    fn variant_exact_size(object: &Cow<'_, [V]>) -> anyhow::Result<usize> {
+        Self::variant_exact_size(object.as_ref())
+    }
 }
 
 impl<'a, V: Sized, VV: ProtocolRaw, T: ProtocolLengthDeterminer<'a>> ProtocolVariantReadable<'a, &'a [V]> for LengthFunctionRawArray<V, VV, T>
 {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<&'a [V]> {
-        let length = T::read_variant(cursor)? / if T::ELEMENT_COUNT { 1 } else { std::mem::size_of::<V>() };
+        let length = T::read_variant(cursor)? / if T::ELEMENT_COUNT { 1 } else { core::mem::size_of::<V>() };
         Ok(byte_array_into_t_array(cursor.take_bytes(length)?))
     }
 }
@@ -546,6 +615,14 @@ impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> Pro
         }
         Ok(())
     }
+
+    fn variant_exact_size(object: &[V]) -> anyhow::Result<usize> {
+        let mut size = T::variant_exact_size(&object.len())?;
+        for value in object {
+            size += VV::variant_exact_size(value)?;
+        }
+        Ok(size)
+    }
 }
 
 impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> ProtocolVariantWritable<Vec<V>> for LengthFunctionArray<V, VV, T>
@@ -554,6 +631,10 @@ impl<'a, V, VV: ProtocolVariantWritable<V>, T: ProtocolLengthDeterminer<'a>> Pro
     fn write_variant<W: ProtocolWriter>(object: &Vec<V>, writer: &mut W) -> anyhow::Result<()> {
         Self::write_variant(object.as_slice(), writer)
     }
+
+    fn variant_exact_size(object: &Vec<V>) -> anyhow::Result<usize> {
+        Self::variant_exact_size(object.as_slice())
+    }
 }
 
 impl<
@@ -576,6 +657,10 @@ impl<
             writer,
         )
     }
+
+    fn variant_exact_size(object: &Cow<'_, [V]>) -> anyhow::Result<usize> {
+        Self::variant_exact_size(object.as_ref())
+    }
 }
 
 impl<
@@ -613,14 +698,14 @@ fixed_range_size!(Json = (VarInt::SIZE.start, (CHAT_LIMIT as u32 * 4 + 3)));
 
 impl<T: Serialize> ProtocolVariantWritable<T> for Json {
     fn write_variant<W: ProtocolWriter>(object: &T, writer: &mut W) -> anyhow::Result<()> {
-        write_str_with_limit::<W, CHAT_LIMIT>(serde_json::to_string(object)?.as_str(), writer)
+        write_str_with_limit::<W, CHAT_LIMIT>(serde_json::to_string(object).map_err(anyhow::Error::msg)?.as_str(), writer)
     }
 }
 
 impl<'a, T: Deserialize<'a>> ProtocolVariantReadable<'a, T> for Json {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<T> {
         serde_json::from_str(read_str_with_limit::<C, CHAT_LIMIT>(cursor)?)
-            .map_err(|err| ProtocolError::Any(err.into()))
+            .map_err(|err| ProtocolError::Any(anyhow::Error::msg(err)))
     }
 }
 
@@ -637,7 +722,7 @@ impl<'a> ProtocolReadable<'a> for Uuid {
         let mut bytes = [0u8; 16];
         let took = cursor.take_bytes(16)?;
         unsafe {
-            std::ptr::copy_nonoverlapping(took.as_ptr(), bytes.as_mut_ptr(), 16);
+            core::ptr::copy_nonoverlapping(took.as_ptr(), bytes.as_mut_ptr(), 16);
         }
         Ok(Uuid::from_bytes(bytes))
     }
@@ -647,14 +732,14 @@ fixed_range_size!(Component<'_> = (1, (262144 * 4) + 3));
 
 impl<'a> ProtocolWritable for Component<'a> {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
-        write_str_with_limit::<_, CHAT_LIMIT>(serde_json::to_string(self)?.as_str(), writer)
+        write_str_with_limit::<_, CHAT_LIMIT>(serde_json::to_string(self).map_err(anyhow::Error::msg)?.as_str(), writer)
     }
 }
 
 impl<'a> ProtocolReadable<'a> for Component<'a> {
     fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
         read_str_with_limit::<_, CHAT_LIMIT>(cursor)
-            .and_then(|str| serde_json::from_str(str).map_err(|err| ProtocolError::Any(err.into())))
+            .and_then(|str| serde_json::from_str(str).map_err(|err| ProtocolError::Any(anyhow::Error::msg(err))))
     }
 }
 
@@ -676,6 +761,16 @@ impl<'a> ProtocolWritable for Identifier<'a> {
             }
         }
     }
+
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        match self.get_inner() {
+            IdentifierInner::Full(full) => str_exact_size_with_limit::<DEFAULT_LIMIT>(full.len()),
+            IdentifierInner::Partial(key, value) => match key.len() + value.len() <= DEFAULT_LIMIT - 1 {
+                true => str_exact_size_with_limit::<DEFAULT_LIMIT>(key.len() + value.len() + 1),
+                false => Err(too_long_string()),
+            }
+        }
+    }
 }
 
 impl<'a> ProtocolReadable<'a> for Identifier<'a> {
@@ -687,15 +782,17 @@ impl<'a> ProtocolReadable<'a> for Identifier<'a> {
 
 delegate_size!(Angle = u8);
 
+/// Degrees are wrapped into steps of 1/256 of a turn, so negative angles and angles above 360 are allowed
 impl ProtocolVariantWritable<f32> for Angle {
     fn write_variant<W: ProtocolWriter>(object: &f32, writer: &mut W) -> anyhow::Result<()> {
-        ((*object * 256.0 / std::f32::consts::PI) as u8).write(writer)
+        (((*object * 256.0 / 360.0) as i32 & 0xFF) as u8).write(writer)
     }
 }
 
+/// Read degrees are in `-180..180` like in vanilla
 impl<'a> ProtocolVariantReadable<'a, f32> for Angle {
     fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<f32> {
-        Ok((u8::read(cursor)? as f32) * std::f32::consts::PI / 256.0)
+        Ok((i8::read(cursor)? as f32) * 360.0 / 256.0)
     }
 }
 
@@ -898,12 +995,42 @@ impl<'a> ProtocolVariantReadable<'a, Cow<'a, [u8]>> for NbtBytes {
     }
 }
 
+impl ProtocolSize for OptionalNbt { const SIZE: Range<u32> = NbtBytes::SIZE; }
+
+impl<'a> ProtocolVariantWritable<Option<&'a [u8]>> for OptionalNbt {
+    fn write_variant<W: ProtocolWriter>(object: &Option<&'a [u8]>, writer: &mut W) -> anyhow::Result<()> {
+        match object {
+            Some(nbt) => NbtBytes::write_variant(nbt, writer),
+            None => Ok(writer.write_byte(0)),
+        }
+    }
+}
+
+impl<'a> ProtocolVariantReadable<'a, Option<&'a [u8]>> for OptionalNbt {
+    fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Option<&'a [u8]>> {
+        match cursor.take_cursor().take_byte()? {
+            0 => cursor.take_byte().map(|_| None),
+            _ => NbtBytes::read_variant(cursor).map(Some),
+        }
+    }
+}
+
 delegate_size!(BlockPosition = u64);
+delegate_size!(SectionPosition = u64);
 
 #[cfg(feature = "euclid")]
 mod euclid_impls {
+    use euclid::{Vector2D, Vector3D};
     use super::*;
 
+    unsafe impl<U> ProtocolRaw for Vector3D<u8, U> {}
+
+    unsafe impl<U> ProtocolRaw for Vector3D<i8, U> {}
+
+    unsafe impl<U> ProtocolRaw for Vector2D<u8, U> {}
+
+    unsafe impl<U> ProtocolRaw for Vector2D<i8, U> {}
+
     impl<T: ProtocolSize, U> ProtocolSize for Vector3D<T, U> {
         const SIZE: Range<u32> = (
             add_u32_without_overflow_array([T::SIZE.start; 3])..
@@ -942,6 +1069,28 @@ mod euclid_impls {
         }
     }
 
+    impl<U> ProtocolVariantWritable<Vector3D<i32, U>> for SectionPosition {
+        fn write_variant<W: ProtocolWriter>(object: &Vector3D<i32, U>, writer: &mut W) -> anyhow::Result<()> {
+            (((object.x as i64 & 0x3FFFFF) << 42) |
+                ((object.z as i64 & 0x3FFFFF) << 20) |
+                (object.y as i64 & 0xFFFFF)
+            ).write(writer)
+        }
+    }
+
+    impl<'a, U: 'a> ProtocolVariantReadable<'a, Vector3D<i32, U>> for SectionPosition {
+        fn read_variant<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Vector3D<i32, U>> {
+            // Arithmetic shifts extend the sign of every part
+            let value = i64::read(cursor)?;
+            Ok(Vector3D {
+                x: (value >> 42) as i32,
+                y: (value << 44 >> 44) as i32,
+                z: (value << 22 >> 42) as i32,
+                _unit: PhantomData,
+            })
+        }
+    }
+
     impl<'a, T: ProtocolReadable<'a>, U: 'a> ProtocolReadable<'a> for Vector3D<T, U> {
         fn read<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
             Ok(Self {
@@ -1097,6 +1246,14 @@ impl<T: ProtocolWritable, const LENGTH: usize> ProtocolVariantWritable<[T; LENGT
         }
         Ok(())
     }
+
+    fn variant_exact_size(object: &[T; LENGTH]) -> anyhow::Result<usize> {
+        let mut size = 0;
+        for obj in object {
+            size += obj.exact_size()?;
+        }
+        Ok(size)
+    }
 }
 
 impl<'a, T: ProtocolReadable<'a> + Clone, const LENGTH: usize> ProtocolVariantReadable<'a, [T; LENGTH]> for ConstLengthArray<T, LENGTH> {
@@ -1147,6 +1304,13 @@ impl<V, VV: ProtocolVariantWritable<V>> ProtocolVariantWritable<Option<V>> for P
             None => false.write(writer),
         }
     }
+
+    fn variant_exact_size(object: &Option<V>) -> anyhow::Result<usize> {
+        match object {
+            Some(val) => Ok(1 + VV::variant_exact_size(val)?),
+            None => Ok(1),
+        }
+    }
 }
 
 impl<'a, V, VV: ProtocolVariantReadable<'a, V>> ProtocolVariantReadable<'a, Option<V>> for ProtocolVariantOption<V, VV> {
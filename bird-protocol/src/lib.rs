@@ -1,18 +1,40 @@
 #![feature(generic_const_exprs)]
+// Without std the errors are implemented with the error trait of core, packet structs are defined in bird-packets
+#![cfg_attr(not(feature = "std"), no_std, feature(error_in_core))]
 
-use std::{ops::Range, marker::PhantomData};
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{ops::Range, marker::PhantomData};
+use core::fmt::{Display, Formatter};
 
 mod impls;
+#[cfg(feature = "std")]
 mod std_impls;
 mod pub_impls;
+#[cfg(feature = "std")]
+mod buffer;
+mod var_number;
 #[cfg(feature = "birdnbt")]
 pub mod nbt;
+#[cfg(feature = "sample")]
+pub mod sample;
+#[cfg(feature = "codec")]
+pub mod codec;
 
 pub use pub_impls::*;
+#[cfg(feature = "std")]
+pub use buffer::*;
+pub use var_number::*;
 
+#[cfg(feature = "std")]
 pub use crate::std_impls::StdIOReadProtocolCursor as ReadableProtocolCursor;
 
+#[cfg(feature = "std")]
 pub use anyhow;
+/// Used instead of the anyhow crate without the `std` feature
+#[cfg(not(feature = "std"))]
+pub mod anyhow;
 
 #[doc(hidden)]
 pub mod __private {
@@ -48,6 +70,8 @@ pub type LengthProvidedRawArray<L, LV, V, VV> = LengthFunctionRawArray<V, VV, Pr
 
 pub type LengthProvidedArray<L, LV, V, VV> = LengthFunctionArray<V, VV, ProtocolLengthProvidedDeterminer<L, LV>>;
 
+pub type LengthLimitedArray<L, LV, V, VV, const MAX: usize> = LengthFunctionArray<V, VV, ProtocolLengthLimitedDeterminer<L, LV, MAX>>;
+
 pub type LengthConstBytesArray<const SIZE: usize> = LengthConstRawArray<u8, u8, SIZE>;
 
 pub type LengthConstRawArray<V, VV, const SIZE: usize> = LengthFunctionRawArray<V, VV, ProtocolLengthConstDeterminer<SIZE>>;
@@ -66,10 +90,16 @@ pub struct Nbt;
 
 pub struct NbtBytes;
 
+/// Nbt compound or the single `TAG_End` byte if there is no nbt
+pub struct OptionalNbt;
+
 pub struct Angle;
 
 pub struct BlockPosition;
 
+/// Position of a chunk section packed into one long, `y` is in sections
+pub struct SectionPosition;
+
 pub struct FixedPointNumber<T, const N: u8>(PhantomData<T>,);
 
 pub trait ProtocolLengthDeterminer<'a>: ProtocolVariantReadable<'a, usize> + ProtocolVariantWritable<usize> + ProtocolSize {
@@ -78,6 +108,9 @@ pub trait ProtocolLengthDeterminer<'a>: ProtocolVariantReadable<'a, usize> + Pro
 
 pub struct ProtocolLengthProvidedDeterminer<L, LV>(PhantomData<(L, LV)>);
 
+/// Provided length which is rejected before elements are read if it is longer than `MAX`
+pub struct ProtocolLengthLimitedDeterminer<L, LV, const MAX: usize>(PhantomData<(L, LV)>);
+
 pub struct ProtocolLengthRemainingDeterminer;
 
 pub struct ProtocolLengthConstDeterminer<const N: usize>;
@@ -106,23 +139,46 @@ pub trait ProtocolLength {
     fn from_usize(size: usize) -> Self;
 }
 
-#[derive(thiserror::Error, Debug)]
+#[derive(Debug)]
 pub enum ProtocolError {
-    #[error("Tried to take too many bytes")]
     End,
-    #[error("Any: {0:?}")]
-    Any(#[from] anyhow::Error),
+    Any(anyhow::Error),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::End => write!(f, "Tried to take too many bytes"),
+            Self::Any(err) => write!(f, "Any: {:?}", err),
+        }
+    }
+}
+
+impl core::error::Error for ProtocolError {
+    #[cfg(feature = "std")]
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::End => None,
+            Self::Any(err) => Some(&**err),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ProtocolError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::Any(err)
+    }
 }
 
 pub type ProtocolResult<T> = Result<T, ProtocolError>;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum ProtocolPacketBound {
     Client,
     Server,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum ProtocolPacketState {
     Handshake,
     Status,
@@ -180,10 +236,34 @@ pub trait ProtocolWriter {
     fn write_vec_bytes(&mut self, bytes: Vec<u8>) {
         self.write_bytes(bytes.as_slice())
     }
+
+    /// Writes all slices one after another
+    fn write_vectored_bytes(&mut self, slices: &[&[u8]]) {
+        for bytes in slices {
+            self.write_bytes(bytes)
+        }
+    }
 }
 
 pub trait ProtocolWritable: ProtocolSize {
     fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()>;
+
+    /// Exact amount of bytes which the value writes, the derive sums sizes of the fields.
+    /// Types without their own implementation count the bytes of a write unless their size is fixed
+    fn exact_size(&self) -> anyhow::Result<usize> {
+        if Self::SIZE.start == Self::SIZE.end {
+            return Ok(Self::SIZE.start as usize);
+        }
+        let mut counter = ProtocolSizeCounter::default();
+        self.write(&mut counter)?;
+        Ok(counter.size)
+    }
+}
+
+/// Writer which only counts bytes
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ProtocolSizeCounter {
+    pub size: usize,
 }
 
 pub trait ProtocolVariantWritable<V: ?Sized>: ProtocolSize {
@@ -191,6 +271,16 @@ pub trait ProtocolVariantWritable<V: ?Sized>: ProtocolSize {
         object: &V,
         writer: &mut W,
     ) -> anyhow::Result<()>;
+
+    /// Exact amount of bytes which [`Self::write_variant`] writes
+    fn variant_exact_size(object: &V) -> anyhow::Result<usize> {
+        if Self::SIZE.start == Self::SIZE.end {
+            return Ok(Self::SIZE.start as usize);
+        }
+        let mut counter = ProtocolSizeCounter::default();
+        Self::write_variant(object, &mut counter)?;
+        Ok(counter.size)
+    }
 }
 
 pub trait ProtocolReadable<'a>: ProtocolSize + Sized + 'a {
@@ -1,7 +1,10 @@
-use std::borrow::Cow;
-use std::marker::PhantomData;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "euclid")]
 use euclid::Vector3D;
-use crate::{ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter, write_compound};
+use crate::{anyhow, ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter, write_compound};
 
 #[derive(Debug)]
 pub enum NbtBorrowedArray<'a, T, const SIZE: usize = 0> {
@@ -210,12 +213,63 @@ inherit_from_default_protocol!(
     f64 = NBT_TAG_DOUBLE,
 );
 
-pub fn write_nbt_str<W: ProtocolWriter>(str: &str, writer: &mut W) -> anyhow::Result<()> {
-    (str.len() as u16).write_nbt(writer)?;
-    match cesu8::to_java_cesu8(str) {
-        Cow::Owned(owned) => writer.write_bytes(&owned),
-        Cow::Borrowed(borrowed) => writer.write_bytes(borrowed),
+/// Java modified utf-8 of nbt strings, the null char and chars outside of the basic plane are encoded differently
+pub fn to_java_utf8(str: &str) -> Cow<[u8]> {
+    if !str.bytes().any(|byte| byte == 0 || byte >= 0xF0) {
+        return Cow::Borrowed(str.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(str.len() + str.len() / 2);
+    for char in str.chars() {
+        match char as u32 {
+            0 => bytes.extend_from_slice(&[0xC0, 0x80]),
+            0x10000.. => for unit in char.encode_utf16(&mut [0; 2]).iter() {
+                bytes.extend_from_slice(&[0xE0 | (unit >> 12) as u8, 0x80 | (unit >> 6 & 0x3F) as u8, 0x80 | (unit & 0x3F) as u8]);
+            },
+            _ => bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// Decodes java modified utf-8, plain utf-8 is borrowed
+pub fn from_java_utf8(bytes: &[u8]) -> anyhow::Result<Cow<str>> {
+    if let Ok(str) = core::str::from_utf8(bytes) {
+        return Ok(Cow::Borrowed(str));
+    }
+    let invalid = || anyhow::Error::msg("Invalid java utf-8 string");
+    let mut str = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        match rest {
+            [0xC0, 0x80, ..] => {
+                str.push('\0');
+                rest = &rest[2..];
+            }
+            [0xED, high @ 0xA0..=0xAF, high_low @ 0x80..=0xBF, 0xED, low @ 0xB0..=0xBF, low_low @ 0x80..=0xBF, ..] => {
+                let high = (*high as u32 & 0x0F) << 6 | (*high_low as u32 & 0x3F);
+                let low = (*low as u32 & 0x0F) << 6 | (*low_low as u32 & 0x3F);
+                str.push(char::from_u32(0x10000 + (high << 10 | low)).ok_or_else(invalid)?);
+                rest = &rest[6..];
+            }
+            _ => {
+                let valid = match core::str::from_utf8(rest) {
+                    Ok(valid) => valid,
+                    Err(err) if err.valid_up_to() > 0 => core::str::from_utf8(&rest[..err.valid_up_to()]).map_err(|_| invalid())?,
+                    Err(_) => return Err(invalid()),
+                };
+                str.push_str(valid);
+                rest = &rest[valid.len()..];
+            }
+        }
     }
+    Ok(Cow::Owned(str))
+}
+
+pub fn write_nbt_str<W: ProtocolWriter>(str: &str, writer: &mut W) -> anyhow::Result<()> {
+    let bytes = to_java_utf8(str);
+    let length: u16 = bytes.len().try_into().map_err(|_| anyhow::Error::msg("Nbt string is too long"))?;
+    length.write_nbt(writer)?;
+    writer.write_bytes(&bytes);
     Ok(())
 }
 
@@ -228,7 +282,7 @@ impl<'a> NbtTag<'a> for Cow<'a, str> {
 
     fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
         let len = u16::read_nbt(cursor)? as usize;
-        cesu8::from_java_cesu8(cursor.take_bytes(len)?).map_err(|err| ProtocolError::Any(err.into()))
+        Ok(from_java_utf8(cursor.take_bytes(len)?)?)
     }
 
     fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
@@ -572,7 +626,8 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Option<T> {
     const NBT_TAG: u8 = T::NBT_TAG;
 
     fn default_nbt_value() -> Option<Self> {
-        None
+        // Absent tag is read as None
+        Some(None)
     }
 
     fn should_write_nbt(&self) -> bool {
@@ -595,6 +650,7 @@ impl<'a, T: NbtTag<'a>> NbtTag<'a> for Option<T> {
     }
 }
 
+#[cfg(feature = "euclid")]
 impl<'a, T: NbtTag<'a>, U> NbtTag<'a> for Vector3D<T, U> {
     const NBT_TAG: u8 = NBT_TAG_COMPOUND;
 
@@ -649,4 +705,24 @@ impl<'a, T: NbtTag<'a>, U> NbtTag<'a> for Vector3D<T, U> {
         }
         Ok(result)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_utf8_test() {
+        for str in ["", "minecraft:stone", "null \0 char", "ключ", "emoji 🐦 and \0"] {
+            let bytes = to_java_utf8(str);
+            assert_eq!(bytes.as_ref(), cesu8::to_java_cesu8(str).as_ref());
+            assert_eq!(from_java_utf8(&bytes).unwrap(), str);
+            let mut written = Vec::new();
+            write_nbt_str(str, &mut written).unwrap();
+            assert_eq!(written[..2], (bytes.len() as u16).to_be_bytes());
+            assert_eq!(Cow::<str>::read_nbt(&mut written.as_slice()).unwrap(), str);
+        }
+        assert!(matches!(to_java_utf8("plain"), Cow::Borrowed(_)));
+        assert!(from_java_utf8(&[0xED, 0xA0, 0x80]).is_err());
+        assert!(from_java_utf8(&[0xFF]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter};
+use alloc::vec::Vec;
+use crate::{anyhow, ProtocolCursor, ProtocolError, ProtocolResult, ProtocolSizeCounter, ProtocolWritable, ProtocolWriter};
 
 impl<'a> ProtocolCursor<'a> for &'a [u8] {
     fn take_byte(&mut self) -> ProtocolResult<u8> {
@@ -37,11 +38,44 @@ impl ProtocolWriter for Vec<u8> {
         let old_len = self.len();
         self.resize(old_len + bytes.len(), 0);
         unsafe {
-            std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.as_mut_ptr().add(old_len), bytes.len());
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), self.as_mut_ptr().add(old_len), bytes.len());
         }
     }
 
     fn write_byte(&mut self, byte: u8) {
         self.push(byte)
     }
-}
\ No newline at end of file
+
+    fn write_vectored_bytes(&mut self, slices: &[&[u8]]) {
+        self.reserve(slices.iter().map(|bytes| bytes.len()).sum());
+        for bytes in slices {
+            self.extend_from_slice(bytes);
+        }
+    }
+}
+impl ProtocolWriter for ProtocolSizeCounter {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.size += bytes.len();
+    }
+
+    fn write_byte(&mut self, _byte: u8) {
+        self.size += 1;
+    }
+
+    fn write_fixed_bytes<const SIZE: usize>(&mut self, _bytes: [u8; SIZE]) {
+        self.size += SIZE;
+    }
+
+    fn write_vectored_bytes(&mut self, slices: &[&[u8]]) {
+        self.size += slices.iter().map(|bytes| bytes.len()).sum::<usize>();
+    }
+}
+
+/// Writes the value into a vector which is allocated once with the exact size of the value.
+/// Derived types compute the size from their fields, so only fields without their own size
+/// implementation are written twice
+pub fn write_exact_to_vec<T: ProtocolWritable + ?Sized>(value: &T) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.exact_size()?);
+    value.write(&mut bytes)?;
+    Ok(bytes)
+}
@@ -0,0 +1,448 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use uuid::Uuid;
+use crate::*;
+
+/// Strings and arrays are short, so samples pass length validations of packets
+pub const MAX_SAMPLE_LENGTH: usize = 4;
+pub const MAX_SAMPLE_STRING_LENGTH: usize = 16;
+const SAMPLE_CHARS: &'static [u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_";
+/// Compound without tags, the smallest value which is nbt
+const SAMPLE_NBT: &'static [u8] = &[10, 0, 0, 0];
+
+/// Xorshift generator, the same seed gives the same samples so failed round trips are reproducible
+#[derive(Clone, Debug)]
+pub struct SampleRandom {
+    state: u64,
+}
+
+impl SampleRandom {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves the zero state
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    pub fn next_length(&mut self) -> usize {
+        self.next_below(MAX_SAMPLE_LENGTH as u64 + 1) as usize
+    }
+
+    pub fn next_string(&mut self, min_length: usize) -> String {
+        let length = min_length + self.next_below((MAX_SAMPLE_STRING_LENGTH - min_length) as u64 + 1) as usize;
+        (0..length)
+            .map(|_| SAMPLE_CHARS[self.next_below(SAMPLE_CHARS.len() as u64) as usize] as char)
+            .collect()
+    }
+}
+
+/// Random value of the type which is written and read without losses
+pub trait ProtocolSample: Sized {
+    fn sample(random: &mut SampleRandom) -> Self;
+}
+
+/// Random value which is written and read without losses by the variant
+pub trait ProtocolVariantSample<V> {
+    fn sample_variant(random: &mut SampleRandom) -> V;
+}
+
+/// Number of the elements which the length determiner reads
+pub trait ProtocolSampleLength {
+    fn sample_length(random: &mut SampleRandom) -> usize;
+}
+
+impl<T: ProtocolSample> ProtocolVariantSample<T> for T {
+    fn sample_variant(random: &mut SampleRandom) -> T {
+        T::sample(random)
+    }
+}
+
+/// Samples of borrowing types are leaked, they are created only by tests
+fn leak<'a, T: 'a>(value: Vec<T>) -> &'a [T] {
+    Box::leak(value.into_boxed_slice())
+}
+
+fn sample_vec<V, VV: ProtocolVariantSample<V>>(random: &mut SampleRandom, length: usize) -> Vec<V> {
+    (0..length).map(|_| VV::sample_variant(random)).collect()
+}
+
+macro_rules! number_sample_impl {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            impl ProtocolSample for $ty {
+                fn sample(random: &mut SampleRandom) -> Self {
+                    random.next_u64() as $ty
+                }
+            }
+        )*
+    }
+}
+
+number_sample_impl!(i8 u8 i16 u16 i32 u32 i64 u64);
+
+impl ProtocolSample for i128 {
+    fn sample(random: &mut SampleRandom) -> Self {
+        ((random.next_u64() as i128) << 64) | random.next_u64() as i128
+    }
+}
+
+impl ProtocolSample for u128 {
+    fn sample(random: &mut SampleRandom) -> Self {
+        ((random.next_u64() as u128) << 64) | random.next_u64() as u128
+    }
+}
+
+// Floats are finite, because NaN is not equal to itself
+impl ProtocolSample for f32 {
+    fn sample(random: &mut SampleRandom) -> Self {
+        random.next_u64() as i32 as f32 / 256.0
+    }
+}
+
+impl ProtocolSample for f64 {
+    fn sample(random: &mut SampleRandom) -> Self {
+        random.next_u64() as i64 as f64 / 256.0
+    }
+}
+
+impl ProtocolSample for bool {
+    fn sample(random: &mut SampleRandom) -> Self {
+        random.next_bool()
+    }
+}
+
+impl ProtocolSample for Uuid {
+    fn sample(random: &mut SampleRandom) -> Self {
+        Uuid::from_u128(u128::sample(random))
+    }
+}
+
+impl ProtocolSample for String {
+    fn sample(random: &mut SampleRandom) -> Self {
+        random.next_string(0)
+    }
+}
+
+impl<'a> ProtocolSample for &'a str {
+    fn sample(random: &mut SampleRandom) -> Self {
+        Box::leak(random.next_string(0).into_boxed_str())
+    }
+}
+
+impl<'a> ProtocolSample for Cow<'a, str> {
+    fn sample(random: &mut SampleRandom) -> Self {
+        Cow::Owned(random.next_string(0))
+    }
+}
+
+impl<'a> ProtocolSample for Identifier<'a> {
+    fn sample(random: &mut SampleRandom) -> Self {
+        Identifier::new_partial(Cow::Borrowed("minecraft"), Cow::Owned(random.next_string(1))).unwrap()
+    }
+}
+
+impl<'a> ProtocolSample for Component<'a> {
+    fn sample(random: &mut SampleRandom) -> Self {
+        Component::text(random.next_string(0))
+    }
+}
+
+impl<T: ProtocolSample> ProtocolSample for Option<T> {
+    fn sample(random: &mut SampleRandom) -> Self {
+        match random.next_bool() {
+            true => Some(T::sample(random)),
+            false => None,
+        }
+    }
+}
+
+impl<A: ProtocolSample, B: ProtocolSample> ProtocolSample for (A, B) {
+    fn sample(random: &mut SampleRandom) -> Self {
+        (A::sample(random), B::sample(random))
+    }
+}
+
+impl<T: ProtocolSample, const LENGTH: usize> ProtocolSample for [T; LENGTH] {
+    fn sample(random: &mut SampleRandom) -> Self {
+        std::array::from_fn(|_| T::sample(random))
+    }
+}
+
+#[cfg(feature = "euclid")]
+mod euclid_impls {
+    use euclid::{Vector2D, Vector3D};
+    use super::*;
+
+    impl<T: ProtocolSample, U> ProtocolSample for Vector3D<T, U> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Vector3D::new(T::sample(random), T::sample(random), T::sample(random))
+        }
+    }
+
+    impl<T: ProtocolSample, U> ProtocolSample for Vector2D<T, U> {
+        fn sample(random: &mut SampleRandom) -> Self {
+            Vector2D::new(T::sample(random), T::sample(random))
+        }
+    }
+
+    fn signed(random: &mut SampleRandom, bits: u32) -> i32 {
+        (random.next_u64() as i32) << (32 - bits) >> (32 - bits)
+    }
+
+    impl<U> ProtocolVariantSample<Vector3D<i32, U>> for BlockPosition {
+        fn sample_variant(random: &mut SampleRandom) -> Vector3D<i32, U> {
+            Vector3D::new(signed(random, 26), signed(random, 12), signed(random, 26))
+        }
+    }
+
+    impl<U> ProtocolVariantSample<Vector3D<i32, U>> for SectionPosition {
+        fn sample_variant(random: &mut SampleRandom) -> Vector3D<i32, U> {
+            Vector3D::new(signed(random, 22), signed(random, 20), signed(random, 22))
+        }
+    }
+}
+
+macro_rules! var_number_sample_impl {
+    ($($ty: ty = ($($lower_ty: ty$(,)*)*)$(,)*)*) => {
+        $($(
+            impl ProtocolVariantSample<$lower_ty> for $ty {
+                fn sample_variant(random: &mut SampleRandom) -> $lower_ty {
+                    <$lower_ty>::sample(random)
+                }
+            }
+        )*)*
+    }
+}
+
+var_number_sample_impl!(
+    VarInt = (bool, i8, u8, i16, u16, i32, u32),
+    VarLong = (bool, i8, u8, i16, u16, i32, u32, i64, u64),
+);
+
+/// Any of 256 steps, steps above the half of the turn are read as negative degrees
+impl ProtocolVariantSample<f32> for Angle {
+    fn sample_variant(random: &mut SampleRandom) -> f32 {
+        random.next_u64() as i8 as f32 * 360.0 / 256.0
+    }
+}
+
+macro_rules! fixed_point_number_sample_impl {
+    ($($ty: ty$(,)*)*) => {
+        $(
+            // Small numbers are exact in floats
+            impl<const N: u8> ProtocolVariantSample<f32> for FixedPointNumber<$ty, N> {
+                fn sample_variant(random: &mut SampleRandom) -> f32 {
+                    (random.next_u64() as i16 as $ty) as f32 / (1 << N) as f32
+                }
+            }
+
+            impl<const N: u8> ProtocolVariantSample<f64> for FixedPointNumber<$ty, N> {
+                fn sample_variant(random: &mut SampleRandom) -> f64 {
+                    (random.next_u64() as i16 as $ty) as f64 / (1 << N) as f64
+                }
+            }
+        )*
+    }
+}
+
+fixed_point_number_sample_impl!(i16, i32, i64);
+
+impl<T: ProtocolSample> ProtocolVariantSample<T> for Json {
+    fn sample_variant(random: &mut SampleRandom) -> T {
+        T::sample(random)
+    }
+}
+
+impl<'a> ProtocolVariantSample<&'a [u8]> for NbtBytes {
+    fn sample_variant(_random: &mut SampleRandom) -> &'a [u8] {
+        SAMPLE_NBT
+    }
+}
+
+impl ProtocolVariantSample<Vec<u8>> for NbtBytes {
+    fn sample_variant(_random: &mut SampleRandom) -> Vec<u8> {
+        SAMPLE_NBT.to_vec()
+    }
+}
+
+impl<'a> ProtocolVariantSample<Cow<'a, [u8]>> for NbtBytes {
+    fn sample_variant(_random: &mut SampleRandom) -> Cow<'a, [u8]> {
+        Cow::Borrowed(SAMPLE_NBT)
+    }
+}
+
+impl<'a> ProtocolVariantSample<Option<&'a [u8]>> for OptionalNbt {
+    fn sample_variant(random: &mut SampleRandom) -> Option<&'a [u8]> {
+        match random.next_bool() {
+            true => Some(SAMPLE_NBT),
+            false => None,
+        }
+    }
+}
+
+impl<L, LV> ProtocolSampleLength for ProtocolLengthProvidedDeterminer<L, LV> {
+    fn sample_length(random: &mut SampleRandom) -> usize {
+        random.next_length()
+    }
+}
+
+impl<L, LV, const MAX: usize> ProtocolSampleLength for ProtocolLengthLimitedDeterminer<L, LV, MAX> {
+    fn sample_length(random: &mut SampleRandom) -> usize {
+        random.next_length().min(MAX)
+    }
+}
+
+impl ProtocolSampleLength for ProtocolLengthRemainingDeterminer {
+    fn sample_length(random: &mut SampleRandom) -> usize {
+        random.next_length()
+    }
+}
+
+impl<const N: usize> ProtocolSampleLength for ProtocolLengthConstDeterminer<N> {
+    fn sample_length(_random: &mut SampleRandom) -> usize {
+        N
+    }
+}
+
+impl<'a, V: ProtocolSample + 'a, VV, T: ProtocolSampleLength> ProtocolVariantSample<&'a [V]> for LengthFunctionRawArray<V, VV, T> {
+    fn sample_variant(random: &mut SampleRandom) -> &'a [V] {
+        leak(<Self as ProtocolVariantSample<Vec<V>>>::sample_variant(random))
+    }
+}
+
+impl<V: ProtocolSample, VV, T: ProtocolSampleLength> ProtocolVariantSample<Vec<V>> for LengthFunctionRawArray<V, VV, T> {
+    fn sample_variant(random: &mut SampleRandom) -> Vec<V> {
+        let length = T::sample_length(random);
+        sample_vec::<V, V>(random, length)
+    }
+}
+
+impl<'a, V: ProtocolSample + Clone, VV, T: ProtocolSampleLength> ProtocolVariantSample<Cow<'a, [V]>> for LengthFunctionRawArray<V, VV, T> {
+    fn sample_variant(random: &mut SampleRandom) -> Cow<'a, [V]> {
+        Cow::Owned(<Self as ProtocolVariantSample<Vec<V>>>::sample_variant(random))
+    }
+}
+
+impl<V, VV: ProtocolVariantSample<V>, T: ProtocolSampleLength> ProtocolVariantSample<Vec<V>> for LengthFunctionArray<V, VV, T> {
+    fn sample_variant(random: &mut SampleRandom) -> Vec<V> {
+        let length = T::sample_length(random);
+        sample_vec::<V, VV>(random, length)
+    }
+}
+
+impl<'a, V: Clone, VV: ProtocolVariantSample<V>, T: ProtocolSampleLength> ProtocolVariantSample<Cow<'a, [V]>> for LengthFunctionArray<V, VV, T> {
+    fn sample_variant(random: &mut SampleRandom) -> Cow<'a, [V]> {
+        Cow::Owned(<Self as ProtocolVariantSample<Vec<V>>>::sample_variant(random))
+    }
+}
+
+impl<T: ProtocolSample, const LENGTH: usize> ProtocolVariantSample<[T; LENGTH]> for ConstLengthArray<T, LENGTH> {
+    fn sample_variant(random: &mut SampleRandom) -> [T; LENGTH] {
+        <[T; LENGTH]>::sample(random)
+    }
+}
+
+impl<'a, T: ProtocolSample + 'a, const LENGTH: usize> ProtocolVariantSample<&'a [T; LENGTH]> for ConstLengthRawArray<T, LENGTH> {
+    fn sample_variant(random: &mut SampleRandom) -> &'a [T; LENGTH] {
+        Box::leak(Box::new(<[T; LENGTH]>::sample(random)))
+    }
+}
+
+impl<V, VV: ProtocolVariantSample<V>> ProtocolVariantSample<Option<V>> for ProtocolVariantOption<V, VV> {
+    fn sample_variant(random: &mut SampleRandom) -> Option<V> {
+        match random.next_bool() {
+            true => Some(VV::sample_variant(random)),
+            false => None,
+        }
+    }
+}
+
+impl<T: ProtocolSample, const S: usize> ProtocolVariantSample<Option<T>> for ProtocolSizeOption<T, S> {
+    fn sample_variant(random: &mut SampleRandom) -> Option<T> {
+        Option::sample(random)
+    }
+}
+
+/// Writes the value, reads it back and checks that the same value is read from all written bytes
+pub fn assert_round_trip<'a, T>(value: &T)
+    where T: ProtocolWritable + ProtocolReadable<'a> + PartialEq + Debug {
+    let mut bytes = Vec::new();
+    if let Err(error) = value.write(&mut bytes) {
+        panic!("Failed to write {:?}: {}", value, error);
+    }
+    assert_eq!(value.exact_size().unwrap(), bytes.len(), "Exact size of {:?} is wrong", value);
+    let length = bytes.len();
+    let mut cursor: &'a [u8] = leak(bytes);
+    let read = match T::read(&mut cursor) {
+        Ok(read) => read,
+        Err(error) => panic!("Failed to read {:?}: {:?}", value, error),
+    };
+    assert_eq!(&read, value);
+    assert!(cursor.is_empty(), "Only {} of {} bytes of {:?} were read", length - cursor.len(), length, value);
+}
+
+/// Round trips `count` samples of the type
+pub fn assert_samples_round_trip<'a, T>(seed: u64, count: usize)
+    where T: ProtocolSample + ProtocolWritable + ProtocolReadable<'a> + PartialEq + Debug {
+    let mut random = SampleRandom::new(seed);
+    for _ in 0..count {
+        assert_round_trip(&T::sample(&mut random));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_round_trip_test() {
+        assert_samples_round_trip::<i32>(1, 64);
+        assert_samples_round_trip::<f64>(2, 64);
+        assert_samples_round_trip::<Option<Uuid>>(3, 64);
+        assert_samples_round_trip::<&str>(4, 64);
+        assert_samples_round_trip::<Identifier>(5, 64);
+        assert_samples_round_trip::<Component>(6, 64);
+        let mut random = SampleRandom::new(7);
+        for _ in 0..64 {
+            let angle: f32 = <Angle as ProtocolVariantSample<f32>>::sample_variant(&mut random);
+            let mut bytes = Vec::new();
+            <Angle as ProtocolVariantWritable<f32>>::write_variant(&angle, &mut bytes).unwrap();
+            assert_eq!(<Angle as ProtocolVariantReadable<f32>>::read_variant(&mut bytes.as_slice()).unwrap(), angle);
+            // Whole turns are wrapped, so angles above 180 degrees are written like negative ones
+            for turned in [angle + 360.0, angle - 360.0, angle + 720.0] {
+                let mut turned_bytes = Vec::new();
+                <Angle as ProtocolVariantWritable<f32>>::write_variant(&turned, &mut turned_bytes).unwrap();
+                assert_eq!(turned_bytes, bytes);
+            }
+            let length: usize = ProtocolLengthConstDeterminer::<3>::sample_length(&mut random);
+            assert_eq!(length, 3);
+        }
+        for (angle, step) in [(90.0, 64), (-90.0, 192), (270.0, 192), (-1.0, 0)] {
+            let mut bytes = Vec::new();
+            <Angle as ProtocolVariantWritable<f32>>::write_variant(&angle, &mut bytes).unwrap();
+            assert_eq!(bytes, [step]);
+        }
+        for nbt in [None, Some(SAMPLE_NBT)] {
+            let mut bytes = Vec::new();
+            <OptionalNbt as ProtocolVariantWritable<Option<&[u8]>>>::write_variant(&nbt, &mut bytes).unwrap();
+            let mut cursor = bytes.as_slice();
+            assert_eq!(<OptionalNbt as ProtocolVariantReadable<Option<&[u8]>>>::read_variant(&mut cursor).unwrap(), nbt);
+            assert!(cursor.is_empty());
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{anyhow, ProtocolCursor, ProtocolError, ProtocolResult};
+
+pub const MAX_VAR_INT_SIZE: usize = 5;
+pub const MAX_VAR_LONG_SIZE: usize = 10;
+/// Bytes which are looked at once to find the end of a var number
+const PEEK_SIZE: usize = 16;
+
+/// Size of the encoded VarInt, computed without branches
+#[inline]
+pub const fn var_int_size(value: u32) -> usize {
+    let bits = 32 - (value | 1).leading_zeros() as usize;
+    (bits + 6) / 7
+}
+
+/// Size of the encoded VarLong, computed without branches
+#[inline]
+pub const fn var_long_size(value: u64) -> usize {
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    (bits + 6) / 7
+}
+
+/// Returns the encoded bytes and the amount of used bytes of them
+#[inline]
+pub fn encode_var_int(value: u32) -> ([u8; MAX_VAR_INT_SIZE], usize) {
+    let size = var_int_size(value);
+    let mut bytes = [
+        value as u8 | 0x80,
+        (value >> 7) as u8 | 0x80,
+        (value >> 14) as u8 | 0x80,
+        (value >> 21) as u8 | 0x80,
+        (value >> 28) as u8,
+    ];
+    bytes[size - 1] &= 0x7F;
+    (bytes, size)
+}
+
+/// Returns the encoded bytes and the amount of used bytes of them
+#[inline]
+pub fn encode_var_long(value: u64) -> ([u8; MAX_VAR_LONG_SIZE], usize) {
+    let size = var_long_size(value);
+    let mut bytes = [0; MAX_VAR_LONG_SIZE];
+    // The loop has the constant length, so it is unrolled
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (7 * index)) as u8 | 0x80;
+    }
+    bytes[size - 1] &= 0x7F;
+    (bytes, size)
+}
+
+/// Index of the first byte without the continuation bit
+#[inline]
+fn terminator_index(bytes: &[u8]) -> Option<usize> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64", target_feature = "sse2"))]
+    if bytes.len() >= PEEK_SIZE {
+        use core::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_movemask_epi8};
+        // Safety: sse2 is enabled and the slice has at least 16 bytes
+        let continuation = unsafe { _mm_movemask_epi8(_mm_loadu_si128(bytes.as_ptr() as *const __m128i)) };
+        let terminators = !continuation as u32 & 0xFFFF;
+        return match terminators {
+            0 => None,
+            _ => Some(terminators.trailing_zeros() as usize),
+        };
+    }
+    bytes.iter().position(|byte| byte & 0x80 == 0)
+}
+
+fn too_big() -> ProtocolError {
+    anyhow::Error::msg("Var number is too big").into()
+}
+
+/// Decodes the VarInt from the start of the slice, returns the value and the amount of read bytes
+#[inline]
+pub fn decode_var_int(bytes: &[u8]) -> ProtocolResult<(u32, usize)> {
+    if bytes.len() < MAX_VAR_INT_SIZE {
+        return decode_var_int_slow(bytes);
+    }
+    let size = match terminator_index(bytes) {
+        Some(index) if index < MAX_VAR_INT_SIZE => index + 1,
+        _ => return Err(too_big()),
+    };
+    // Bytes after the end are masked out instead of branching on each byte
+    let mask = |index: usize| ((index < size) as u32).wrapping_neg();
+    let value = (bytes[0] as u32 & 0x7F)
+        | ((bytes[1] as u32 & 0x7F) << 7) & mask(1)
+        | ((bytes[2] as u32 & 0x7F) << 14) & mask(2)
+        | ((bytes[3] as u32 & 0x7F) << 21) & mask(3)
+        | ((bytes[4] as u32 & 0x7F) << 28) & mask(4);
+    Ok((value, size))
+}
+
+/// Decodes the VarLong from the start of the slice, returns the value and the amount of read bytes
+#[inline]
+pub fn decode_var_long(bytes: &[u8]) -> ProtocolResult<(u64, usize)> {
+    if bytes.len() < MAX_VAR_LONG_SIZE {
+        return decode_var_long_slow(bytes);
+    }
+    let size = match terminator_index(bytes) {
+        Some(index) if index < MAX_VAR_LONG_SIZE => index + 1,
+        _ => return Err(too_big()),
+    };
+    let mut value = 0;
+    for (index, byte) in bytes[..MAX_VAR_LONG_SIZE].iter().enumerate() {
+        let mask = ((index < size) as u64).wrapping_neg();
+        value |= ((*byte as u64 & 0x7F) << (7 * index)) & mask;
+    }
+    Ok((value, size))
+}
+
+/// Used near the end of the input, where the whole number may not be available
+fn decode_var_int_slow(bytes: &[u8]) -> ProtocolResult<(u32, usize)> {
+    let mut value = 0;
+    for (index, byte) in bytes.iter().take(MAX_VAR_INT_SIZE).enumerate() {
+        value |= (*byte as u32 & 0x7F) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    match bytes.len() >= MAX_VAR_INT_SIZE {
+        true => Err(too_big()),
+        false => Err(ProtocolError::End),
+    }
+}
+
+fn decode_var_long_slow(bytes: &[u8]) -> ProtocolResult<(u64, usize)> {
+    let mut value = 0;
+    for (index, byte) in bytes.iter().take(MAX_VAR_LONG_SIZE).enumerate() {
+        value |= (*byte as u64 & 0x7F) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    match bytes.len() >= MAX_VAR_LONG_SIZE {
+        true => Err(too_big()),
+        false => Err(ProtocolError::End),
+    }
+}
+
+/// Looks at the next bytes of the cursor without taking them
+fn peek_bytes<'a, C: ProtocolCursor<'a>>(cursor: &C) -> ProtocolResult<&'a [u8]> {
+    let mut peek = cursor.take_cursor();
+    let length = peek.remaining_bytes().min(PEEK_SIZE);
+    peek.take_bytes(length)
+}
+
+#[inline]
+pub fn read_var_int<'a, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<u32> {
+    let (value, size) = decode_var_int(peek_bytes(cursor)?)?;
+    cursor.take_bytes(size)?;
+    Ok(value)
+}
+
+#[inline]
+pub fn read_var_long<'a, C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<u64> {
+    let (value, size) = decode_var_long(peek_bytes(cursor)?)?;
+    cursor.take_bytes(size)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use super::*;
+
+    /// Byte by byte codecs which were used before the branchless ones
+    fn loop_encode(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            if value & !0x7F == 0 {
+                bytes.push(value as u8);
+                return bytes;
+            }
+            bytes.push(value as u8 & 0x7F | 0x80);
+            value >>= 7;
+        }
+    }
+
+    fn loop_decode(bytes: &[u8], bits: u32) -> Option<(u64, usize)> {
+        let mut value = 0;
+        let mut position = 0;
+        for (index, byte) in bytes.iter().enumerate() {
+            value |= (*byte as u64 & 0x7F) << position;
+            if byte & 0x80 == 0 {
+                return Some((value, index + 1));
+            }
+            position += 7;
+            if position >= bits {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Inputs of 16 bytes and longer are decoded by the sse2 path when the `simd` feature is enabled
+    fn padded(bytes: &[u8]) -> Vec<u8> {
+        let mut padded = bytes.to_vec();
+        padded.resize(PEEK_SIZE + 4, 0xFF);
+        padded
+    }
+
+    const VALUES: [i32; 6] = [0, 127, 128, (1 << 21) - 1, i32::MIN, -1];
+
+    #[test]
+    fn var_int_test() {
+        for value in VALUES.map(|value| value as u32) {
+            let expected = loop_encode(value as u64);
+            let (bytes, size) = encode_var_int(value);
+            assert_eq!(&bytes[..size], expected.as_slice());
+            assert_eq!(var_int_size(value), expected.len());
+            for input in [expected.clone(), padded(&expected)] {
+                let reference = loop_decode(&input, 32).unwrap();
+                assert_eq!(decode_var_int(&input).unwrap(), (reference.0 as u32, reference.1));
+                let mut cursor = input.as_slice();
+                assert_eq!(read_var_int(&mut cursor).unwrap(), value);
+                assert_eq!(cursor.len(), input.len() - size);
+            }
+        }
+    }
+
+    #[test]
+    fn var_long_test() {
+        for value in VALUES.map(|value| value as i64 as u64) {
+            let expected = loop_encode(value);
+            let (bytes, size) = encode_var_long(value);
+            assert_eq!(&bytes[..size], expected.as_slice());
+            assert_eq!(var_long_size(value), expected.len());
+            for input in [expected.clone(), padded(&expected)] {
+                assert_eq!(decode_var_long(&input).unwrap(), loop_decode(&input, 64).unwrap());
+                let mut cursor = input.as_slice();
+                assert_eq!(read_var_long(&mut cursor).unwrap(), value);
+                assert_eq!(cursor.len(), input.len() - size);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_var_number_test() {
+        let invalid = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        for input in [invalid.to_vec(), padded(&invalid)] {
+            assert_eq!(loop_decode(&input, 32), None);
+            assert!(matches!(decode_var_int(&input), Err(ProtocolError::Any(_))));
+            assert!(matches!(read_var_int(&mut input.as_slice()), Err(ProtocolError::Any(_))));
+        }
+        let invalid = [0xFF; MAX_VAR_LONG_SIZE + 1];
+        for input in [invalid.to_vec(), padded(&invalid)] {
+            assert_eq!(loop_decode(&input, 64), None);
+            assert!(matches!(decode_var_long(&input), Err(ProtocolError::Any(_))));
+        }
+        // Unfinished numbers are not errors, the rest of them is not received yet
+        assert!(matches!(decode_var_int(&[0x80, 0x80]), Err(ProtocolError::End)));
+        assert!(matches!(decode_var_long(&[0x80; 9]), Err(ProtocolError::End)));
+    }
+}
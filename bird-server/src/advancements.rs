@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use bird_protocol::anyhow;
+use crate::protocol::*;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdvancementDisplayBuilder {
+    pub title: Component<'static>,
+    pub description: Component<'static>,
+    pub icon: Option<OwnedSlot>,
+    pub frame_type: AdvancementFrameType,
+    pub show_toast: bool,
+    pub hidden: bool,
+    /// Texture of the tab, it is used only by root advancements
+    pub background_texture: Option<String>,
+    /// Position in the tab, one is the size of the advancement
+    pub x: f32,
+    pub y: f32,
+}
+
+impl AdvancementDisplayBuilder {
+    pub fn new(title: Component<'static>, description: Component<'static>, icon: Option<OwnedSlot>, x: f32, y: f32) -> Self {
+        Self {
+            title,
+            description,
+            icon,
+            frame_type: AdvancementFrameType::Task,
+            show_toast: true,
+            hidden: false,
+            background_texture: None,
+            x,
+            y,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct AdvancementEntry {
+    key: String,
+    parent: Option<String>,
+    display: Option<AdvancementDisplayBuilder>,
+    criteria: Vec<String>,
+    /// Every criterion is a requirement if it is not set
+    requirements: Option<Vec<Vec<String>>>,
+}
+
+fn identifier(name: &str) -> anyhow::Result<Identifier<'_>> {
+    Identifier::new_full(Cow::Borrowed(name))
+        .ok_or_else(|| anyhow::Error::msg(format!("{} is not an identifier", name)))
+}
+
+/// Collects advancements and their progress for [`UpdateAdvancementsPS2C`].
+/// Parents are sent before their children, so they can be added in any order
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AdvancementsBuilder {
+    advancements: Vec<AdvancementEntry>,
+    removed: Vec<String>,
+    progress: Vec<(String, Vec<(String, Option<i64>)>)>,
+}
+
+impl AdvancementsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advancement without the parent is the root of a new tab. The advancement with the same key is replaced
+    pub fn advancement(
+        &mut self,
+        key: &str,
+        parent: Option<&str>,
+        display: Option<AdvancementDisplayBuilder>,
+        criteria: &[&str],
+    ) -> &mut Self {
+        let entry = AdvancementEntry {
+            key: key.into(),
+            parent: parent.map(Into::into),
+            display,
+            criteria: criteria.iter().map(|&criterion| criterion.into()).collect(),
+            requirements: None,
+        };
+        match self.advancements.iter_mut().find(|other| other.key == key) {
+            Some(other) => *other = entry,
+            None => self.advancements.push(entry),
+        }
+        self
+    }
+
+    /// The advancement is done when every requirement has at least one done criterion
+    pub fn requirements(&mut self, key: &str, requirements: &[&[&str]]) -> &mut Self {
+        if let Some(entry) = self.advancements.iter_mut().find(|entry| entry.key == key) {
+            entry.requirements = Some(requirements.iter()
+                .map(|requirement| requirement.iter().map(|&criterion| criterion.into()).collect())
+                .collect());
+        }
+        self
+    }
+
+    /// Removes the advancement from the client, it is also removed from the builder
+    pub fn remove(&mut self, key: &str) -> &mut Self {
+        self.advancements.retain(|entry| entry.key != key);
+        self.progress.retain(|(advancement, _)| advancement != key);
+        if !self.removed.iter().any(|removed| removed == key) {
+            self.removed.push(key.into());
+        }
+        self
+    }
+
+    /// `achieved_at` is milliseconds since the epoch, `None` revokes the criterion
+    pub fn progress(&mut self, key: &str, criterion: &str, achieved_at: Option<i64>) -> &mut Self {
+        let index = match self.progress.iter().position(|(advancement, _)| advancement == key) {
+            Some(index) => index,
+            None => {
+                self.progress.push((key.into(), Vec::new()));
+                self.progress.len() - 1
+            }
+        };
+        let criteria = &mut self.progress[index].1;
+        match criteria.iter_mut().find(|(other, _)| other == criterion) {
+            Some((_, other)) => *other = achieved_at,
+            None => criteria.push((criterion.into(), achieved_at)),
+        }
+        self
+    }
+
+    /// Advancements in the order of sending, an error is returned if parents are cyclic
+    fn sorted(&self) -> anyhow::Result<Vec<&AdvancementEntry>> {
+        let mut sorted = Vec::with_capacity(self.advancements.len());
+        let mut remaining: Vec<_> = self.advancements.iter().collect();
+        while !remaining.is_empty() {
+            let length = remaining.len();
+            remaining.retain(|entry| {
+                let ready = match entry.parent {
+                    // Parents which are not in the builder should be already sent
+                    Some(ref parent) => !self.advancements.iter().any(|other| &other.key == parent)
+                        || sorted.iter().any(|other: &&AdvancementEntry| &other.key == parent),
+                    None => true,
+                };
+                if ready {
+                    sorted.push(*entry);
+                }
+                !ready
+            });
+            if remaining.len() == length {
+                return Err(anyhow::Error::msg(format!("Parents of {} are cyclic", remaining[0].key)));
+            }
+        }
+        Ok(sorted)
+    }
+
+    /// With `reset` the client removes its advancements, so every parent must be in the builder
+    pub fn build(&self, reset: bool) -> anyhow::Result<UpdateAdvancementsPS2C<'_>> {
+        let mut advancements = Vec::with_capacity(self.advancements.len());
+        for entry in self.sorted()? {
+            if let Some(ref parent) = entry.parent {
+                if reset && !self.advancements.iter().any(|other| &other.key == parent) {
+                    return Err(anyhow::Error::msg(format!("Parent {} of {} is not added", parent, entry.key)));
+                }
+            }
+            let display = match entry.display {
+                Some(ref display) => Some(AdvancementDisplay {
+                    title: display.title.clone(),
+                    description: display.description.clone(),
+                    icon: display.icon.as_ref().map(OwnedSlot::as_slot),
+                    frame_type: display.frame_type,
+                    show_toast: display.show_toast,
+                    hidden: display.hidden,
+                    background_texture: display.background_texture.as_deref().map(identifier).transpose()?,
+                    x: display.x,
+                    y: display.y,
+                }),
+                None => None,
+            };
+            let requirements: Vec<_> = match entry.requirements {
+                Some(ref requirements) => requirements.iter()
+                    .map(|requirement| AdvancementRequirement {
+                        criteria: Cow::Owned(requirement.iter().map(String::as_str).collect()),
+                    })
+                    .collect(),
+                None => entry.criteria.iter()
+                    .map(|criterion| AdvancementRequirement { criteria: Cow::Owned(vec![criterion.as_str()]) })
+                    .collect(),
+            };
+            advancements.push(AdvancementMapping {
+                key: identifier(&entry.key)?,
+                value: Advancement {
+                    parent: entry.parent.as_deref().map(identifier).transpose()?,
+                    display,
+                    criteria: Cow::Owned(entry.criteria.iter().map(|criterion| identifier(criterion)).collect::<anyhow::Result<_>>()?),
+                    requirements: Cow::Owned(requirements),
+                },
+            });
+        }
+        let mut progress = Vec::with_capacity(self.progress.len());
+        for (key, criteria) in &self.progress {
+            if let Some(entry) = self.advancements.iter().find(|entry| &entry.key == key) {
+                if let Some((criterion, _)) = criteria.iter().find(|(criterion, _)| !entry.criteria.contains(criterion)) {
+                    return Err(anyhow::Error::msg(format!("{} doesn't have criterion {}", key, criterion)));
+                }
+            }
+            progress.push(AdvancementProgress {
+                advancement: identifier(key)?,
+                criteria: Cow::Owned(criteria.iter()
+                    .map(|(criterion, achieved_at)| Ok(CriterionProgress { criterion: identifier(criterion)?, achieved_at: *achieved_at }))
+                    .collect::<anyhow::Result<_>>()?),
+            });
+        }
+        Ok(UpdateAdvancementsPS2C {
+            reset,
+            advancements: Cow::Owned(advancements),
+            removed: Cow::Owned(self.removed.iter().map(|key| identifier(key)).collect::<anyhow::Result<_>>()?),
+            progress: Cow::Owned(progress),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::encode_packet;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn advancements_builder_test() {
+        let mut display = AdvancementDisplayBuilder::new(Component::text("Root"), Component::text("Tab"), Some(OwnedSlot::new(1, 1)), 0.0, 0.0);
+        display.background_texture = Some("minecraft:textures/gui/advancements/backgrounds/stone.png".into());
+        let mut builder = AdvancementsBuilder::new();
+        builder
+            .advancement("custom:child", Some("custom:root"), None, &["custom:a", "custom:b"])
+            .requirements("custom:child", &[&["custom:a", "custom:b"]])
+            .advancement("custom:root", None, Some(display), &["custom:tick"])
+            .progress("custom:root", "custom:tick", Some(1000))
+            .progress("custom:child", "custom:a", None)
+            .remove("custom:old");
+        let packet = builder.build(true).unwrap();
+        assert_eq!(packet.advancements[0].key.to_string(), "custom:root");
+        assert_eq!(packet.advancements[1].value.parent.as_ref().unwrap().to_string(), "custom:root");
+        assert_eq!(packet.advancements[1].value.requirements.len(), 1);
+        assert_eq!(packet.advancements[0].value.requirements[0].criteria.as_ref(), ["custom:tick"]);
+        assert_eq!(packet.progress[0].criteria[0].achieved_at, Some(1000));
+        assert_eq!(packet.removed.len(), 1);
+
+        let bytes = encode_packet(&packet).unwrap();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut bytes.as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::UpdateAdvancements(read))) => assert_eq!(read, packet),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+
+        builder.progress("custom:root", "custom:missing", None);
+        assert!(builder.build(false).is_err());
+        let mut cyclic = AdvancementsBuilder::new();
+        cyclic.advancement("custom:a", Some("custom:b"), None, &[]).advancement("custom:b", Some("custom:a"), None, &[]);
+        assert!(cyclic.build(false).is_err());
+        let mut orphan = AdvancementsBuilder::new();
+        orphan.advancement("custom:a", Some("custom:sent"), None, &[]);
+        assert!(orphan.build(false).is_ok());
+        assert!(orphan.build(true).is_err());
+    }
+}
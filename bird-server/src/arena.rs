@@ -0,0 +1,149 @@
+use bumpalo::Bump;
+use bird_protocol::{ProtocolPacketBound, ProtocolPacketState, ProtocolResult};
+use crate::ecs::InboundPacket;
+use crate::protocol::{OwnedSlot, Slot};
+use crate::registry::Packet;
+
+/// Initial capacity of the arena, most packets are smaller than this
+pub const DEFAULT_ARENA_CAPACITY: usize = 4096;
+
+/// Borrowed data of packets which is kept after the arena is reset
+pub trait IntoOwned {
+    type Owned;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl IntoOwned for &str {
+    type Owned = String;
+
+    fn into_owned(self) -> String {
+        self.to_owned()
+    }
+}
+
+impl IntoOwned for &[u8] {
+    type Owned = Vec<u8>;
+
+    fn into_owned(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl<'a> IntoOwned for Slot<'a> {
+    type Owned = OwnedSlot;
+
+    fn into_owned(self) -> OwnedSlot {
+        self.into()
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(IntoOwned::into_owned)
+    }
+}
+
+/// Bump arena of one connection. Packets are decoded from the copy of the frame in the arena, so the packet
+/// and data which is copied by handlers are freed together when the arena is reset after the packet is handled
+pub struct PacketArena {
+    bump: Bump,
+}
+
+impl PacketArena {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_ARENA_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { bump: Bump::with_capacity(capacity) }
+    }
+
+    pub fn alloc_bytes(&self, bytes: &[u8]) -> &[u8] {
+        self.bump.alloc_slice_copy(bytes)
+    }
+
+    pub fn alloc_str(&self, str: &str) -> &str {
+        self.bump.alloc_str(str)
+    }
+
+    /// Bytes of chunks which are kept by the arena
+    pub fn allocated(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    /// Reads the server bound packet from the id and body, it borrows the arena
+    pub fn decode(&self, state: ProtocolPacketState, frame: &[u8]) -> ProtocolResult<Option<Packet<'_>>> {
+        let frame = self.alloc_bytes(frame);
+        Packet::read(state, ProtocolPacketBound::Server, &mut &*frame)
+    }
+
+    /// Memory of the largest chunk is reused by the next packet
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// Decodes the packet, handles it and resets the arena. The result can't borrow the arena,
+    /// data which is needed later is converted by [`IntoOwned`]. Unknown packets are not handled
+    pub fn handle<R>(
+        &mut self,
+        packet: &InboundPacket,
+        handler: impl FnOnce(&PacketArena, Packet<'_>) -> R,
+    ) -> ProtocolResult<Option<R>> {
+        let arena = &*self;
+        let result = arena.decode(packet.state, &packet.frame).map(|decoded| decoded.map(|decoded| handler(arena, decoded)));
+        self.reset();
+        result
+    }
+}
+
+impl Default for PacketArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use crate::connection::encode_packet;
+    use crate::ecs::Ecs;
+    use crate::protocol::ChatCommandPC2S;
+    use crate::registry::PlayC2SPacket;
+    use super::*;
+
+    #[test]
+    fn packet_arena_test() {
+        let mut ecs = Ecs::new();
+        let handle = ecs.spawn();
+        let frame = encode_packet(&ChatCommandPC2S {
+            command: "pregen start 100",
+            timestamp: 0,
+            salt: 0,
+            argument_signatures: Cow::Borrowed(&[]),
+            message_count: 0,
+            acknowledged: &[0; 3],
+        }).unwrap();
+        let packet = InboundPacket { handle, state: ProtocolPacketState::Play, frame };
+        let mut arena = PacketArena::with_capacity(64);
+        let command = arena.handle(&packet, |arena, packet| match packet {
+            Packet::PlayC2S(PlayC2SPacket::ChatCommand(command)) => {
+                let name = arena.alloc_str(command.command.split(' ').next().unwrap());
+                assert_eq!(name, "pregen");
+                Some(command.command.into_owned())
+            }
+            _ => None,
+        });
+        assert_eq!(command.unwrap(), Some(Some("pregen start 100".to_owned())));
+        let allocated = arena.allocated();
+        // The chunk is reused after the reset
+        for _ in 0..16 {
+            arena.handle(&packet, |_, _| ()).unwrap();
+        }
+        assert_eq!(arena.allocated(), allocated);
+        assert!(arena.handle(&InboundPacket { handle, state: ProtocolPacketState::Play, frame: vec![0x7F] }, |_, _| ()).unwrap().is_none());
+        assert_eq!(Some(Slot { item_id: 1, item_count: 1, nbt: None }).into_owned(), Some(OwnedSlot::new(1, 1)));
+    }
+}
@@ -0,0 +1,314 @@
+use euclid::default::Vector3D;
+use bird_data::Sound;
+use bird_protocol::anyhow;
+use crate::block_state::BlockState;
+use crate::connection::PacketSender;
+use crate::entity::metadata::{ENTITY_FLAGS_INDEX, ENTITY_FLAG_GLOWING};
+use crate::entity::status::{RAVAGER_ENTITY_TYPE, WITCH_ENTITY_TYPE};
+use crate::entity::EntityKind;
+use crate::entity::tracker::EntityTracker;
+use crate::protocol::*;
+use crate::statistics::Statistics;
+
+pub const EVOKER_ENTITY_TYPE: i32 = 26;
+pub const ILLUSIONER_ENTITY_TYPE: i32 = 43;
+pub const PILLAGER_ENTITY_TYPE: i32 = 71;
+pub const VINDICATOR_ENTITY_TYPE: i32 = 104;
+pub const RAIDER_ENTITY_TYPES: &'static [i32] = &[
+    EVOKER_ENTITY_TYPE,
+    ILLUSIONER_ENTITY_TYPE,
+    PILLAGER_ENTITY_TYPE,
+    RAVAGER_ENTITY_TYPE,
+    VINDICATOR_ENTITY_TYPE,
+    WITCH_ENTITY_TYPE,
+];
+
+pub const BELL_RING_TICKS: u32 = 50;
+/// Raiders are searched after this amount of ringing ticks
+const BELL_RESONATE_DELAY: u32 = 5;
+/// Ticks between the resonating sound and the glowing of raiders
+const BELL_RESONATION_TICKS: u32 = 40;
+pub const BELL_GLOW_TICKS: u32 = 60;
+/// Bell resonates if a raider is closer than this distance
+pub const BELL_RAIDER_DETECTION_RANGE: f64 = 32.0;
+pub const BELL_RAIDER_GLOW_RANGE: f64 = 48.0;
+/// Hits above the bell body don't ring it
+const BELL_MAX_HIT_HEIGHT: f32 = 0.8124;
+
+pub fn is_raider(entity_type: i32) -> bool {
+    RAIDER_ENTITY_TYPES.contains(&entity_type)
+}
+
+/// Hook point of the raid system, the bell doesn't know about raids by itself
+pub trait RaidHook {
+    /// Called on every ring with raiders in the glowing range, `ringer` is `None` for redstone.
+    /// Returns true if the ring has triggered a raid
+    fn bell_rung(&mut self, bell: Vector3D<i32>, ringer: Option<i32>, raiders: &[i32]) -> bool;
+}
+
+/// Nothing is triggered without the raid system
+impl RaidHook for () {
+    fn bell_rung(&mut self, _bell: Vector3D<i32>, _ringer: Option<i32>, _raiders: &[i32]) -> bool {
+        false
+    }
+}
+
+fn direction_by_name(name: &str) -> Option<Direction> {
+    match name {
+        "north" => Some(Direction::North),
+        "south" => Some(Direction::South),
+        "west" => Some(Direction::West),
+        "east" => Some(Direction::East),
+        _ => None,
+    }
+}
+
+fn is_same_axis(first: Direction, second: Direction) -> bool {
+    first == second || first == second.opposite()
+}
+
+/// Checks the hit like vanilla, `cursor_y` is the height of the hit inside the block
+pub fn is_proper_hit(state: BlockState, face: Direction, cursor_y: f32) -> bool {
+    if matches!(face, Direction::Up | Direction::Down) || cursor_y > BELL_MAX_HIT_HEIGHT {
+        return false;
+    }
+    let facing = match state.get("facing").and_then(direction_by_name) {
+        Some(facing) => facing,
+        None => return false,
+    };
+    match state.get("attachment") {
+        Some("floor") => is_same_axis(facing, face),
+        Some("single_wall") | Some("double_wall") => !is_same_axis(facing, face),
+        Some("ceiling") => true,
+        _ => false,
+    }
+}
+
+pub const fn bell_direction(direction: Direction) -> BlockActionVariantBellDirection {
+    match direction {
+        Direction::Down => BlockActionVariantBellDirection::Down,
+        Direction::Up => BlockActionVariantBellDirection::Up,
+        Direction::North => BlockActionVariantBellDirection::North,
+        Direction::South => BlockActionVariantBellDirection::South,
+        Direction::West => BlockActionVariantBellDirection::West,
+        Direction::East => BlockActionVariantBellDirection::East,
+    }
+}
+
+/// Block entity of a bell. Ringing the bell with raiders nearby makes them glow
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bell {
+    pub position: Vector3D<i32>,
+    ringing: bool,
+    ticks: u32,
+    resonating: bool,
+    resonation_ticks: u32,
+    /// Raiders which are glowing because of the bell and their remaining ticks
+    glowing: Vec<(i32, u32)>,
+}
+
+impl Bell {
+    pub fn new(position: Vector3D<i32>) -> Self {
+        Self {
+            position,
+            ringing: false,
+            ticks: 0,
+            resonating: false,
+            resonation_ticks: 0,
+            glowing: Vec::new(),
+        }
+    }
+
+    pub fn is_ringing(&self) -> bool {
+        self.ringing
+    }
+
+    pub fn is_resonating(&self) -> bool {
+        self.resonating
+    }
+
+    /// Raiders in the range around the center of the bell
+    pub fn nearby_raiders(&self, tracker: &EntityTracker, range: f64) -> Vec<i32> {
+        let center = self.position.to_f64() + Vector3D::new(0.5, 0.5, 0.5);
+        tracker.entities()
+            .filter(|entity| match entity.kind {
+                EntityKind::Other { entity_type, .. } => is_raider(entity_type),
+                EntityKind::Player => false,
+            })
+            .filter(|entity| (entity.position - center).square_length() < range * range)
+            .map(|entity| entity.id)
+            .collect()
+    }
+
+    /// Swings the bell to the direction and plays the sound, the swing is restarted if the bell is ringing.
+    /// Returns true if the raid system has triggered a raid
+    pub fn ring<S: PacketSender, H: RaidHook>(
+        &mut self,
+        direction: Direction,
+        ringer: Option<i32>,
+        tracker: &EntityTracker,
+        raids: &mut H,
+        broadcast: &mut S,
+    ) -> anyhow::Result<bool> {
+        match self.ringing {
+            true => self.ticks = 0,
+            false => self.ringing = true,
+        }
+        if !self.resonating {
+            self.resonation_ticks = 0;
+        }
+        broadcast.send_packet(&BlockActionPS2C {
+            location: self.position,
+            variant: BlockActionVariant::Bell { direction: bell_direction(direction) },
+        })?;
+        self.send_sound(Sound::BlockBellUse, 2.0, broadcast)?;
+        let raiders = self.nearby_raiders(tracker, BELL_RAIDER_GLOW_RANGE);
+        Ok(!raiders.is_empty() && raids.bell_rung(self.position, ringer, &raiders))
+    }
+
+    /// Rings the bell by the player and awards the bell ring and raid trigger statistics
+    pub fn ring_by_player<S: PacketSender, H: RaidHook>(
+        &mut self,
+        direction: Direction,
+        player_id: i32,
+        statistics: &mut Statistics,
+        tracker: &EntityTracker,
+        raids: &mut H,
+        broadcast: &mut S,
+    ) -> anyhow::Result<bool> {
+        let triggered = self.ring(direction, Some(player_id), tracker, raids, broadcast)?;
+        statistics.increment_custom(AwardStatisticCustom::BellRing);
+        if triggered {
+            statistics.increment_custom(AwardStatisticCustom::RaidTrigger);
+        }
+        Ok(triggered)
+    }
+
+    pub fn tick<S: PacketSender>(&mut self, tracker: &mut EntityTracker, broadcast: &mut S) -> anyhow::Result<()> {
+        let mut expired = Vec::new();
+        self.glowing.retain_mut(|(id, remaining)| {
+            *remaining -= 1;
+            if *remaining == 0 {
+                expired.push(*id);
+            }
+            *remaining > 0
+        });
+        for id in expired {
+            tracker.update_metadata(id, |metadata| metadata.update_flags(ENTITY_FLAGS_INDEX, ENTITY_FLAG_GLOWING, false))?;
+        }
+        if self.ringing {
+            self.ticks += 1;
+        }
+        if self.ticks >= BELL_RING_TICKS {
+            self.ringing = false;
+            self.ticks = 0;
+        }
+        if self.ticks >= BELL_RESONATE_DELAY && self.resonation_ticks == 0
+            && !self.nearby_raiders(tracker, BELL_RAIDER_DETECTION_RANGE).is_empty() {
+            self.resonating = true;
+            self.send_sound(Sound::BlockBellResonate, 1.0, broadcast)?;
+        }
+        if self.resonating {
+            match self.resonation_ticks < BELL_RESONATION_TICKS {
+                true => self.resonation_ticks += 1,
+                false => {
+                    self.make_raiders_glow(tracker)?;
+                    // Resonation ticks are kept, so the bell resonates again only after the next ring
+                    self.resonating = false;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn make_raiders_glow(&mut self, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        for id in self.nearby_raiders(tracker, BELL_RAIDER_GLOW_RANGE) {
+            match self.glowing.iter_mut().find(|(other, _)| *other == id) {
+                Some((_, remaining)) => *remaining = BELL_GLOW_TICKS,
+                None => self.glowing.push((id, BELL_GLOW_TICKS)),
+            }
+            tracker.update_metadata(id, |metadata| metadata.update_flags(ENTITY_FLAGS_INDEX, ENTITY_FLAG_GLOWING, true))?;
+        }
+        Ok(())
+    }
+
+    fn send_sound<S: PacketSender>(&self, sound: Sound, volume: f32, broadcast: &mut S) -> anyhow::Result<()> {
+        broadcast.send_packet(&SoundEffectPS2C {
+            sound: SoundEvent::Registry(sound),
+            sound_category: CustomSoundCategory::Block,
+            effect_position_x: self.position.x as f32 + 0.5,
+            effect_position_y: self.position.y as f32 + 0.5,
+            effect_position_z: self.position.z as f32 + 0.5,
+            volume,
+            pitch: 1.0,
+            seed: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use crate::connection::PacketQueue;
+    use crate::entity::status::VILLAGER_ENTITY_TYPE;
+    use crate::entity::tracker::TrackedEntity;
+    use super::*;
+
+    struct RecordingRaids(Vec<Option<i32>>);
+
+    impl RaidHook for RecordingRaids {
+        fn bell_rung(&mut self, _bell: Vector3D<i32>, ringer: Option<i32>, _raiders: &[i32]) -> bool {
+            self.0.push(ringer);
+            true
+        }
+    }
+
+    #[test]
+    fn bell_test() {
+        let state = BlockState::from_name("bell").unwrap()
+            .with("attachment", "floor").unwrap()
+            .with("facing", "north").unwrap();
+        assert!(is_proper_hit(state, Direction::South, 0.5));
+        assert!(!is_proper_hit(state, Direction::East, 0.5));
+        assert!(!is_proper_hit(state, Direction::South, 0.9));
+        assert!(!is_proper_hit(state.with("attachment", "single_wall").unwrap(), Direction::South, 0.5));
+
+        let mut tracker = EntityTracker::new(64.0);
+        let pillager = EntityKind::Other { entity_type: PILLAGER_ENTITY_TYPE, data: 0 };
+        tracker.add_entity(TrackedEntity::new(1, Uuid::nil(), pillager, Vector3D::new(10.0, 0.0, 0.0))).unwrap();
+        tracker.add_entity(TrackedEntity::new(2, Uuid::nil(), pillager, Vector3D::new(40.0, 0.0, 0.0))).unwrap();
+        let villager = EntityKind::Other { entity_type: VILLAGER_ENTITY_TYPE, data: 0 };
+        tracker.add_entity(TrackedEntity::new(3, Uuid::nil(), villager, Vector3D::new(1.0, 0.0, 0.0))).unwrap();
+
+        let mut bell = Bell::new(Vector3D::zero());
+        let mut broadcast = PacketQueue::new();
+        let mut statistics = Statistics::new();
+        let mut raids = RecordingRaids(Vec::new());
+        assert!(bell.ring_by_player(Direction::North, 5, &mut statistics, &tracker, &mut raids, &mut broadcast).unwrap());
+        assert_eq!(raids.0, vec![Some(5)]);
+        assert_eq!(statistics.get(AwardStatistic::Custom(AwardStatisticCustom::BellRing)), 1);
+        assert_eq!(statistics.get(AwardStatistic::Custom(AwardStatisticCustom::RaidTrigger)), 1);
+        assert!(!bell.ring(Direction::North, None, &tracker, &mut (), &mut broadcast).unwrap());
+        // Block actions and sounds of both rings
+        assert_eq!(broadcast.take().len(), 4);
+
+        let is_glowing = |tracker: &EntityTracker, id| tracker.get_entity(id).unwrap().metadata
+            .get_byte(ENTITY_FLAGS_INDEX)
+            .map_or(false, |flags| flags & ENTITY_FLAG_GLOWING != 0);
+        // The resonation starts on the tick of the delay and it lasts for the resonation ticks with it
+        for _ in 1..BELL_RESONATE_DELAY + BELL_RESONATION_TICKS {
+            bell.tick(&mut tracker, &mut broadcast).unwrap();
+        }
+        assert!(bell.is_resonating());
+        bell.tick(&mut tracker, &mut broadcast).unwrap();
+        assert!(!bell.is_resonating());
+        assert!(is_glowing(&tracker, 1));
+        assert!(is_glowing(&tracker, 2));
+        assert!(!is_glowing(&tracker, 3));
+        for _ in 0..BELL_GLOW_TICKS {
+            bell.tick(&mut tracker, &mut broadcast).unwrap();
+        }
+        assert!(!is_glowing(&tracker, 1));
+        assert!(!bell.is_ringing());
+    }
+}
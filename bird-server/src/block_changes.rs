@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use euclid::default::Vector3D;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+/// Section of the block position
+pub fn section_position(position: Vector3D<i32>) -> Vector3D<i32> {
+    Vector3D::new(position.x >> 4, position.y >> 4, position.z >> 4)
+}
+
+/// Block changes which are sent together at the end of the tick.
+/// Only the last change of every position is kept, changes are grouped by sections
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct BlockChangeBatch {
+    sections: HashMap<Vector3D<i32>, HashMap<Vector3D<u8>, i32>>,
+}
+
+impl BlockChangeBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, position: Vector3D<i32>, state: i32) {
+        let local = Vector3D::new((position.x & 15) as u8, (position.y & 15) as u8, (position.z & 15) as u8);
+        self.sections.entry(section_position(position)).or_default().insert(local, state);
+    }
+
+    /// Amount of changed blocks
+    pub fn len(&self) -> usize {
+        self.sections.values().map(|changes| changes.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Sends one packet per section, a single change is sent as `BlockUpdate` which is shorter.
+    /// Returns the amount of sent packets
+    pub fn flush<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<usize> {
+        let mut sections: Vec<_> = self.sections.drain().collect();
+        // Sections are sent in the same order every time
+        sections.sort_by_key(|(section, _)| (section.x, section.z, section.y));
+        let count = sections.len();
+        for (section, changes) in sections {
+            if changes.len() == 1 {
+                let (local, state) = changes.into_iter().next().unwrap();
+                let location = section * 16 + local.to_i32();
+                sender.send_packet(&BlockUpdatePS2C { location, block_id: state })?;
+                continue;
+            }
+            let mut blocks: Vec<_> = changes.into_iter()
+                .map(|(local, state)| section_block_entry(state, local.x, local.y, local.z))
+                .collect();
+            blocks.sort_by_key(|entry| entry & 0xFFF);
+            sender.send_packet(&UpdateSectionBlocksPS2C {
+                section,
+                suppress_light_updates: false,
+                blocks: Cow::Owned(blocks),
+            })?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn block_change_batch_test() {
+        let mut batch = BlockChangeBatch::new();
+        batch.push(Vector3D::new(1, -60, 2), 1);
+        batch.push(Vector3D::new(1, -60, 2), 2);
+        batch.push(Vector3D::new(15, -49, 0), 3);
+        batch.push(Vector3D::new(-1, 70, 16), 4);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.section_count(), 2);
+        let mut queue = PacketQueue::new();
+        assert_eq!(batch.flush(&mut queue).unwrap(), 2);
+        assert!(batch.is_empty());
+        let packets = queue.take();
+        assert_eq!(packets.len(), 2);
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut packets[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::BlockUpdate(packet))) => {
+                assert_eq!(packet, BlockUpdatePS2C { location: Vector3D::new(-1, 70, 16), block_id: 4 });
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut packets[1].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::UpdateSectionBlocks(packet))) => {
+                assert_eq!(packet.section, Vector3D::new(0, -4, 0));
+                assert_eq!(packet.blocks.len(), 2);
+                assert_eq!(read_section_block_entry(packet.blocks[0]), (2, Vector3D::new(1, 4, 2)));
+                assert_eq!(read_section_block_entry(packet.blocks[1]), (3, Vector3D::new(15, 15, 0)));
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use euclid::default::Vector3D;
+use bird_data::{Block, BlockData};
+use bird_protocol::anyhow;
+use crate::protocol::BlockUpdatePS2C;
+use crate::world::World;
+
+const NAMESPACE: &'static str = "minecraft:";
+
+/// Block state which is addressed by property names, it is converted to the global state id of bird_data
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockState {
+    data: &'static BlockData<'static>,
+    id: u32,
+}
+
+impl BlockState {
+    /// State of the block with its properties
+    pub fn of(block: Block) -> Self {
+        let data = block.get_data();
+        Self { data, id: block.get_state().unwrap_or(data.default_state_id) }
+    }
+
+    pub fn default_of(data: &'static BlockData<'static>) -> Self {
+        Self { data, id: data.default_state_id }
+    }
+
+    /// Default state of the block, the name may have the namespace
+    pub fn from_name(name: &str) -> Option<Self> {
+        BlockData::from_name(name.strip_prefix(NAMESPACE).unwrap_or(name)).map(Self::default_of)
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        let data = BlockData::from_state(u32::try_from(id).ok()?)?;
+        Some(Self { data, id: id as u32 })
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id as i32
+    }
+
+    pub fn data(&self) -> &'static BlockData<'static> {
+        self.data
+    }
+
+    pub fn block(&self) -> Block {
+        // The id is always in the range of the block
+        Block::from_state(self.id).unwrap()
+    }
+
+    /// Amount of states which are skipped when the value of the property is changed by one
+    fn stride(&self, index: usize) -> u32 {
+        self.data.properties[index + 1..].iter().map(|property| property.values.len() as u32).product()
+    }
+
+    fn value_index(&self, index: usize) -> usize {
+        let values = self.data.properties[index].values.len() as u32;
+        ((self.id - self.data.min_state_id) / self.stride(index) % values) as usize
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'static str> {
+        let index = self.data.properties.iter().position(|property| property.name == name)?;
+        Some(self.data.properties[index].values[self.value_index(index)])
+    }
+
+    /// Returns an error if the block doesn't have the property or the value
+    pub fn with(self, name: &str, value: &str) -> anyhow::Result<Self> {
+        let index = self.data.properties.iter().position(|property| property.name == name)
+            .ok_or_else(|| anyhow::Error::msg(format!("{} doesn't have property {}", self.data.name, name)))?;
+        let new_index = self.data.properties[index].value_index(value)
+            .ok_or_else(|| anyhow::Error::msg(format!("{} is not a value of {} of {}", value, name, self.data.name)))?;
+        let stride = self.stride(index);
+        let id = self.id - self.value_index(index) as u32 * stride + new_index as u32 * stride;
+        Ok(Self { data: self.data, id })
+    }
+
+    /// Names and values of all properties in the order of bird_data
+    pub fn properties(&self) -> impl Iterator<Item=(&'static str, &'static str)> + '_ {
+        self.data.properties.iter()
+            .enumerate()
+            .map(|(index, property)| (property.name, property.values[self.value_index(index)]))
+    }
+
+    pub fn update_packet(&self, location: Vector3D<i32>) -> BlockUpdatePS2C {
+        BlockUpdatePS2C { location, block_id: self.id() }
+    }
+}
+
+impl From<Block> for BlockState {
+    fn from(block: Block) -> Self {
+        Self::of(block)
+    }
+}
+
+impl From<BlockState> for i32 {
+    fn from(state: BlockState) -> Self {
+        state.id()
+    }
+}
+
+/// Format of commands, `minecraft:oak_stairs[facing=north,half=bottom]`
+impl Display for BlockState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", NAMESPACE, self.data.name)?;
+        for (index, (name, value)) in self.properties().enumerate() {
+            write!(f, "{}{}={}", if index == 0 { '[' } else { ',' }, name, value)?;
+        }
+        match self.data.properties.is_empty() {
+            true => Ok(()),
+            false => write!(f, "]"),
+        }
+    }
+}
+
+/// Properties which are not given keep their default values
+impl FromStr for BlockState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, properties) = match s.split_once('[') {
+            Some((name, properties)) => (name, Some(properties.strip_suffix(']')
+                .ok_or_else(|| anyhow::Error::msg("Properties of the block state are not closed"))?)),
+            None => (s, None),
+        };
+        let mut state = Self::from_name(name)
+            .ok_or_else(|| anyhow::Error::msg(format!("Unknown block {}", name)))?;
+        for property in properties.into_iter().flat_map(|properties| properties.split(',')).filter(|property| !property.is_empty()) {
+            let (name, value) = property.split_once('=')
+                .ok_or_else(|| anyhow::Error::msg(format!("Property {} doesn't have a value", property)))?;
+            state = state.with(name.trim(), value.trim())?;
+        }
+        Ok(state)
+    }
+}
+
+impl World {
+    pub fn get_block(&self, position: Vector3D<i32>) -> Option<BlockState> {
+        self.get_block_state(position).and_then(BlockState::from_id)
+    }
+
+    /// Returns the previous state, `None` if the chunk is not loaded
+    pub fn set_block(&mut self, position: Vector3D<i32>, state: BlockState) -> Option<BlockState> {
+        self.set_block_state(position, state.id()).and_then(BlockState::from_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use super::*;
+
+    #[test]
+    fn block_state_properties_test() {
+        let stairs = BlockState::from_name("oak_stairs").unwrap();
+        assert_eq!(stairs.id(), stairs.data().default_state_id as i32);
+        let north = stairs.with("facing", "north").unwrap().with("half", "top").unwrap();
+        assert_eq!(north.get("facing"), Some("north"));
+        assert_eq!(north.get("half"), Some("top"));
+        assert_eq!(north.get("waterlogged"), stairs.get("waterlogged"));
+        assert!(stairs.with("facing", "up").is_err());
+        assert!(stairs.with("power", "1").is_err());
+        assert_eq!(BlockState::from_id(north.id()), Some(north));
+        assert_eq!(BlockState::of(north.block()), north);
+
+        let text = north.to_string();
+        assert!(text.starts_with("minecraft:oak_stairs[facing=north,half=top,"));
+        assert_eq!(text.parse::<BlockState>().unwrap(), north);
+        assert_eq!("stone".parse::<BlockState>().unwrap().to_string(), "minecraft:stone");
+        assert!("oak_stairs[facing=north".parse::<BlockState>().is_err());
+
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let position = Vector3D::new(1, 2, 3);
+        assert_eq!(world.set_block(position, north).map(|state| state.data().name), Some("air"));
+        assert_eq!(world.get_block(position), Some(north));
+        assert_eq!(north.update_packet(position).block_id, north.id());
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::inventory::{item_id, InventoryView, PLAYER_HOTBAR_START, PLAYER_OFFHAND_SLOT};
+use crate::protocol::*;
+
+pub const MAX_BOOK_PAGES: usize = 100;
+/// Vanilla client doesn't allow longer pages
+pub const MAX_PAGE_LENGTH: usize = 1024;
+pub const MAX_TITLE_LENGTH: usize = 16;
+/// Slot of [`EditBookPC2S`] which means the off hand
+pub const EDIT_BOOK_OFFHAND_SLOT: i32 = 40;
+
+/// Nbt of `writable_book`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct WritableBookNbt {
+    pub pages: Vec<String>,
+}
+
+/// Nbt of `written_book`, pages are json components
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WrittenBookNbt {
+    pub pages: Vec<String>,
+    pub title: String,
+    pub author: String,
+    /// 0 is the original, 1 is a copy of the original and so on
+    pub generation: i32,
+    pub resolved: bool,
+}
+
+/// Index of the player inventory which is edited by the packet
+pub fn edit_book_slot(slot: i32) -> anyhow::Result<usize> {
+    match slot {
+        0..=8 => Ok(PLAYER_HOTBAR_START + slot as usize),
+        EDIT_BOOK_OFFHAND_SLOT => Ok(PLAYER_OFFHAND_SLOT),
+        _ => Err(anyhow::Error::msg("Bad slot of the book")),
+    }
+}
+
+/// Checks limits of the vanilla client
+pub fn validate_edit_book(packet: &EditBookPC2S) -> anyhow::Result<()> {
+    if packet.pages.len() > MAX_BOOK_PAGES {
+        return Err(anyhow::Error::msg("Too many pages"));
+    }
+    if packet.pages.iter().any(|page| page.chars().count() > MAX_PAGE_LENGTH) {
+        return Err(anyhow::Error::msg("Too long page"));
+    }
+    match packet.title {
+        Some(title) if title.trim().is_empty() || title.chars().count() > MAX_TITLE_LENGTH =>
+            Err(anyhow::Error::msg("Bad title of the book")),
+        _ => Ok(()),
+    }
+}
+
+/// Writes pages into the writable book of the player, signed books become written books with the author.
+/// Returns the new item, nothing is changed if the slot doesn't contain a writable book
+pub fn handle_edit_book<S: PacketSender>(
+    packet: &EditBookPC2S,
+    author: &str,
+    view: &mut InventoryView,
+    sender: &mut S,
+) -> anyhow::Result<Option<OwnedSlot>> {
+    validate_edit_book(packet)?;
+    let index = edit_book_slot(packet.slot)?;
+    let item_count = match view.player.get(index) {
+        Some(item) if item.item_id == item_id("writable_book")? => item.item_count,
+        _ => return Ok(None),
+    };
+    let pages = packet.pages.iter().map(|page| page.to_string());
+    let item = match packet.title {
+        Some(title) => OwnedSlot {
+            item_id: item_id("written_book")?,
+            item_count,
+            nbt: fastnbt::to_bytes(&WrittenBookNbt {
+                pages: pages
+                    .map(|page| serde_json::to_string(&Component::text(page)))
+                    .collect::<Result<_, _>>()?,
+                title: title.into(),
+                author: author.into(),
+                generation: 0,
+                resolved: true,
+            })?,
+        },
+        None => OwnedSlot {
+            item_id: item_id("writable_book")?,
+            item_count,
+            nbt: fastnbt::to_bytes(&WritableBookNbt { pages: pages.collect() })?,
+        },
+    };
+    match view.container() {
+        // Slots of the player inventory have the same indexes as the window without a container
+        None => view.set_slot(index, Some(item.clone()), sender)?,
+        Some(_) => {
+            view.player.set(index, Some(item.clone()));
+        }
+    }
+    Ok(Some(item))
+}
+
+/// Opens the written book which is held in the hand, returns false if there is no written book
+pub fn open_book<S: PacketSender>(view: &InventoryView, selected_slot: usize, hand: Hand, sender: &mut S) -> anyhow::Result<bool> {
+    let index = match hand {
+        Hand::Main => PLAYER_HOTBAR_START + selected_slot,
+        Hand::Off => PLAYER_OFFHAND_SLOT,
+    };
+    match view.player.get(index) {
+        Some(item) if item.item_id == item_id("written_book")? => {
+            sender.send_packet(&OpenBookPS2C { hand })?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use crate::connection::PacketQueue;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn edit_book_test() {
+        let long_page = "a".repeat(MAX_PAGE_LENGTH + 1);
+        let pages = ["first", long_page.as_str()];
+        let mut packet = EditBookPC2S { slot: 0, pages: Cow::Borrowed(&pages), title: None };
+        assert!(validate_edit_book(&packet).is_err());
+        packet.pages = Cow::Borrowed(&pages[..1]);
+        packet.title = Some("A very long title of the book");
+        assert!(validate_edit_book(&packet).is_err());
+        packet.title = Some("Title");
+        let mut view = InventoryView::new();
+        view.player.set(PLAYER_HOTBAR_START, Some(OwnedSlot::new(item("writable_book"), 1)));
+        let mut sender = PacketQueue::new();
+        let book = handle_edit_book(&packet, "author", &mut view, &mut sender).unwrap().unwrap();
+        assert_eq!(book.item_id, item("written_book"));
+        assert_eq!(view.player.get(PLAYER_HOTBAR_START), Some(&book));
+        assert_eq!(edit_book_slot(41).ok(), None);
+    }
+}
@@ -0,0 +1,316 @@
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+/// Vanilla default and the maximum diameter
+pub const MAX_BORDER_DIAMETER: f64 = 59999968.0;
+/// Players can't be teleported by portals outside of this distance from the center
+pub const PORTAL_TELEPORT_BOUNDARY: i32 = 29999984;
+/// Milliseconds in one tick
+const TICK_MILLIS: i64 = 50;
+/// Positions are clamped a bit inside so the clamped position is contained
+const CLAMP_EPSILON: f64 = 1.0E-5;
+
+/// World border of one world, the client moves the border by itself so packets are sent only on changes
+#[derive(Clone, PartialEq, Debug)]
+pub struct WorldBorder {
+    center_x: f64,
+    center_z: f64,
+    old_diameter: f64,
+    target_diameter: f64,
+    /// Milliseconds of the current lerp
+    lerp_time: i64,
+    lerp_remaining: i64,
+    warning_blocks: i32,
+    warning_seconds: i32,
+    /// Blocks outside the border without the damage
+    damage_buffer: f64,
+    damage_per_block: f64,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorldBorder {
+    pub fn new() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            old_diameter: MAX_BORDER_DIAMETER,
+            target_diameter: MAX_BORDER_DIAMETER,
+            lerp_time: 0,
+            lerp_remaining: 0,
+            warning_blocks: 5,
+            warning_seconds: 15,
+            damage_buffer: 5.0,
+            damage_per_block: 0.2,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.center_x, self.center_z)
+    }
+
+    /// Current diameter, it changes linearly while the border is moving
+    pub fn diameter(&self) -> f64 {
+        match self.lerp_remaining > 0 {
+            true => {
+                let progress = 1.0 - self.lerp_remaining as f64 / self.lerp_time as f64;
+                self.old_diameter + (self.target_diameter - self.old_diameter) * progress
+            }
+            false => self.target_diameter,
+        }
+    }
+
+    pub fn target_diameter(&self) -> f64 {
+        self.target_diameter
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.lerp_remaining > 0
+    }
+
+    /// Milliseconds until the target diameter is reached
+    pub fn remaining_time(&self) -> i64 {
+        self.lerp_remaining
+    }
+
+    pub fn warning_blocks(&self) -> i32 {
+        self.warning_blocks
+    }
+
+    pub fn warning_seconds(&self) -> i32 {
+        self.warning_seconds
+    }
+
+    pub fn damage_buffer(&self) -> f64 {
+        self.damage_buffer
+    }
+
+    pub fn damage_per_block(&self) -> f64 {
+        self.damage_per_block
+    }
+
+    /// Damage is applied only by the server, so nothing is sent
+    pub fn set_damage(&mut self, damage_buffer: f64, damage_per_block: f64) {
+        self.damage_buffer = damage_buffer.max(0.0);
+        self.damage_per_block = damage_per_block.max(0.0);
+    }
+
+    /// Returns true if the position is inside the border
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        self.distance_to_edge(x, z) > 0.0
+    }
+
+    /// Distance to the closest edge, negative if the position is outside
+    pub fn distance_to_edge(&self, x: f64, z: f64) -> f64 {
+        let radius = self.diameter() / 2.0;
+        let distance_x = radius - (x - self.center_x).abs();
+        let distance_z = radius - (z - self.center_z).abs();
+        distance_x.min(distance_z)
+    }
+
+    /// Minimum and maximum coordinates inside the border
+    pub fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let radius = self.diameter() / 2.0;
+        let limit = PORTAL_TELEPORT_BOUNDARY as f64;
+        (
+            ((self.center_x - radius).max(-limit), (self.center_z - radius).max(-limit)),
+            ((self.center_x + radius).min(limit), (self.center_z + radius).min(limit)),
+        )
+    }
+
+    /// The closest position inside the border, teleports are clamped by it
+    pub fn clamp(&self, x: f64, z: f64) -> (f64, f64) {
+        let ((min_x, min_z), (max_x, max_z)) = self.bounds();
+        (x.clamp(min_x, max_x - CLAMP_EPSILON), z.clamp(min_z, max_z - CLAMP_EPSILON))
+    }
+
+    /// Validates movement of the player, it can't cross the border from the inside.
+    /// Players outside can move anywhere until they are inside, a shrinking border doesn't push them
+    pub fn validate_movement(&self, from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+        match self.contains(from.0, from.1) && !self.contains(to.0, to.1) {
+            true => self.clamp(to.0, to.1),
+            false => to,
+        }
+    }
+
+    /// Damage to the entity at the position, it is checked every tick like vanilla.
+    /// Returns `None` inside the border and the buffer
+    pub fn damage(&self, x: f64, z: f64) -> Option<f32> {
+        let distance = self.distance_to_edge(x, z) + self.damage_buffer;
+        match distance < 0.0 && self.damage_per_block > 0.0 {
+            true => Some((-distance * self.damage_per_block).floor().max(1.0) as f32),
+            false => None,
+        }
+    }
+
+    /// Distance where the client starts to tint the screen, the moving border warns earlier
+    pub fn warning_distance(&self) -> f64 {
+        let moving = match self.is_moving() {
+            true => {
+                let speed = (self.target_diameter - self.old_diameter).abs() / self.lerp_time as f64;
+                (speed * self.warning_seconds as f64 * 1000.0).min((self.target_diameter - self.diameter()).abs())
+            }
+            false => 0.0,
+        };
+        moving.max(self.warning_blocks as f64)
+    }
+
+    /// Tint of the screen at the position from zero to one like the client renders it
+    pub fn warning_intensity(&self, x: f64, z: f64) -> f64 {
+        let distance = self.distance_to_edge(x, z).max(0.0);
+        let warning_distance = self.warning_distance();
+        match distance < warning_distance {
+            true => 1.0 - distance / warning_distance,
+            false => 0.0,
+        }
+    }
+
+    /// Tints the screen of one player, the warning distance is changed only for them.
+    /// [`WorldBorder::clear_tint`] restores the warning distance of the border
+    pub fn send_tint<S: PacketSender>(&self, x: f64, z: f64, intensity: f64, sender: &mut S) -> anyhow::Result<()> {
+        let intensity = intensity.clamp(0.0, 0.99);
+        let distance = self.distance_to_edge(x, z).max(1.0);
+        let warning_blocks = (distance / (1.0 - intensity)).ceil().min(i32::MAX as f64) as i32;
+        sender.send_packet(&SetBorderWarningDistancePS2C { warning_blocks: warning_blocks.max(self.warning_blocks) })
+    }
+
+    pub fn clear_tint<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&SetBorderWarningDistancePS2C { warning_blocks: self.warning_blocks })
+    }
+
+    pub fn set_center<S: PacketSender>(&mut self, x: f64, z: f64, broadcast: &mut S) -> anyhow::Result<()> {
+        self.center_x = x;
+        self.center_z = z;
+        broadcast.send_packet(&SetBorderCenterPS2C { x, z })
+    }
+
+    /// Changes the diameter immediately stopping the movement
+    pub fn set_diameter<S: PacketSender>(&mut self, diameter: f64, broadcast: &mut S) -> anyhow::Result<()> {
+        let diameter = diameter.clamp(1.0, MAX_BORDER_DIAMETER);
+        self.old_diameter = diameter;
+        self.target_diameter = diameter;
+        self.lerp_time = 0;
+        self.lerp_remaining = 0;
+        broadcast.send_packet(&SetBorderSizePS2C { diameter })
+    }
+
+    /// Moves the border from the current diameter to the target during the time in milliseconds
+    pub fn lerp_diameter<S: PacketSender>(&mut self, target: f64, millis: i64, broadcast: &mut S) -> anyhow::Result<()> {
+        if millis <= 0 {
+            return self.set_diameter(target, broadcast);
+        }
+        self.old_diameter = self.diameter();
+        self.target_diameter = target.clamp(1.0, MAX_BORDER_DIAMETER);
+        self.lerp_time = millis;
+        self.lerp_remaining = millis;
+        broadcast.send_packet(&self.lerp_packet())
+    }
+
+    pub fn set_warning_blocks<S: PacketSender>(&mut self, warning_blocks: i32, broadcast: &mut S) -> anyhow::Result<()> {
+        self.warning_blocks = warning_blocks;
+        broadcast.send_packet(&SetBorderWarningDistancePS2C { warning_blocks })
+    }
+
+    pub fn set_warning_seconds<S: PacketSender>(&mut self, warning_seconds: i32, broadcast: &mut S) -> anyhow::Result<()> {
+        self.warning_seconds = warning_seconds;
+        broadcast.send_packet(&SetBorderWarningDelayPS2C { warning_time: warning_seconds })
+    }
+
+    /// Advances the movement by one tick, the client is moving the border by itself
+    pub fn tick(&mut self) {
+        if self.lerp_remaining <= 0 {
+            return;
+        }
+        self.lerp_remaining -= TICK_MILLIS;
+        if self.lerp_remaining <= 0 {
+            self.lerp_remaining = 0;
+            self.old_diameter = self.target_diameter;
+        }
+    }
+
+    fn lerp_packet(&self) -> SetBorderLerpSizePS2C {
+        SetBorderLerpSizePS2C {
+            old_diameter: self.diameter(),
+            new_diameter: self.target_diameter,
+            speed: self.lerp_remaining,
+        }
+    }
+
+    pub fn initialize_packet(&self) -> InitializeWorldBorderPS2C {
+        InitializeWorldBorderPS2C {
+            x: self.center_x,
+            y: self.center_z,
+            old_diameter: self.diameter(),
+            new_diameter: self.target_diameter,
+            speed: self.lerp_remaining,
+            portal_teleport_boundary: PORTAL_TELEPORT_BOUNDARY,
+            warning_blocks: self.warning_blocks,
+            warning_seconds: self.warning_seconds,
+        }
+    }
+
+    /// Sends the border to the player which has joined the world
+    pub fn send_state<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&self.initialize_packet())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn world_border_test() {
+        let mut border = WorldBorder::new();
+        let mut broadcast = PacketQueue::new();
+        border.set_center(100.0, -100.0, &mut broadcast).unwrap();
+        border.set_diameter(200.0, &mut broadcast).unwrap();
+        assert!(border.contains(150.0, -50.0));
+        assert!(!border.contains(250.0, -100.0));
+        assert_eq!(border.distance_to_edge(100.0, -190.0), 10.0);
+        border.lerp_diameter(100.0, 1000, &mut broadcast).unwrap();
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert_eq!(border.diameter(), 150.0);
+        assert_eq!(border.initialize_packet().speed, 500);
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert!(!border.is_moving());
+        assert_eq!(border.diameter(), 100.0);
+        assert_eq!(broadcast.take().len(), 3);
+    }
+
+    #[test]
+    fn world_border_damage_test() {
+        let mut border = WorldBorder::new();
+        let mut queue = PacketQueue::new();
+        border.set_diameter(20.0, &mut queue).unwrap();
+        assert_eq!(border.damage(14.0, 0.0), None);
+        assert_eq!(border.damage(16.0, 0.0), Some(1.0));
+        assert_eq!(border.damage(35.0, 0.0), Some(4.0));
+        border.set_damage(0.0, 1.0);
+        assert_eq!(border.damage(12.5, 0.0), Some(2.0));
+
+        assert_eq!(border.clamp(50.0, -3.0), (10.0 - CLAMP_EPSILON, -3.0));
+        assert_eq!(border.validate_movement((9.0, 0.0), (11.0, 0.0)), (10.0 - CLAMP_EPSILON, 0.0));
+        assert_eq!(border.validate_movement((12.0, 0.0), (13.0, 0.0)), (13.0, 0.0));
+
+        assert_eq!(border.warning_intensity(0.0, 0.0), 0.0);
+        assert_eq!(border.warning_intensity(9.0, 0.0), 0.8);
+        border.lerp_diameter(10.0, 1000, &mut queue).unwrap();
+        // 10 blocks per second are more than the warning blocks
+        assert_eq!(border.warning_distance(), 10.0);
+        queue.take();
+        border.send_tint(0.0, 0.0, 0.5, &mut queue).unwrap();
+        border.clear_tint(&mut queue).unwrap();
+        assert_eq!(queue.take().len(), 2);
+    }
+}
@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use bird_chat::identifier::Identifier;
+use bird_protocol::{anyhow, ProtocolReadable, ProtocolWritable};
+use crate::connection::{PacketQueue, PacketSender};
+use crate::protocol::*;
+
+pub const BRAND_CHANNEL: &'static str = "minecraft:brand";
+pub const REGISTER_CHANNEL: &'static str = "minecraft:register";
+pub const UNREGISTER_CHANNEL: &'static str = "minecraft:unregister";
+pub const BUNGEE_CHANNEL: &'static str = "bungeecord:main";
+pub const SERVER_BRAND: &'static str = "bird";
+
+type ChannelHandler<T> = Box<dyn Fn(&mut T, &[u8], &mut PacketQueue) -> anyhow::Result<()> + Send + Sync>;
+
+/// Channels of one player
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PlayerChannels {
+    /// Channels which the client registered with `minecraft:register`
+    pub registered: HashSet<String>,
+    pub brand: Option<String>,
+}
+
+impl PlayerChannels {
+    /// Vanilla channels are always accepted by the client
+    pub fn can_receive(&self, channel: &str) -> bool {
+        channel.starts_with("minecraft:") || self.registered.contains(channel)
+    }
+
+    /// Sends the message if the client listens to the channel, returns false otherwise
+    pub fn send<S: PacketSender>(&self, channel: &str, data: &[u8], sender: &mut S) -> anyhow::Result<bool> {
+        match self.can_receive(channel) {
+            true => send_message(sender, channel, data).map(|_| true),
+            false => Ok(false),
+        }
+    }
+}
+
+/// Routes plugin messages of players to the subscribers of their channels, `T` is the state of the connection
+pub struct PluginChannels<T> {
+    handlers: HashMap<String, Vec<ChannelHandler<T>>>,
+}
+
+impl<T> Default for PluginChannels<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PluginChannels<T> {
+    pub fn new() -> Self {
+        Self { handlers: HashMap::new() }
+    }
+
+    /// Panics if the channel is not a valid identifier
+    pub fn subscribe<F>(&mut self, channel: &str, handler: F) -> &mut Self
+        where F: Fn(&mut T, &[u8], &mut PacketQueue) -> anyhow::Result<()> + Send + Sync + 'static {
+        assert!(Identifier::new_full(Cow::Borrowed(channel)).is_some(), "Channel must be an identifier");
+        self.handlers.entry(channel.into()).or_default().push(Box::new(handler));
+        self
+    }
+
+    /// Custom channels which are announced to the client
+    pub fn channels(&self) -> impl Iterator<Item=&str> {
+        self.handlers.keys()
+            .map(|channel| channel.as_str())
+            .filter(|channel| !channel.starts_with("minecraft:"))
+    }
+
+    /// Sends the brand of the server and registers subscribed channels, must be sent after the login packet
+    pub fn send_join<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        send_message(sender, BRAND_CHANNEL, &brand_payload(SERVER_BRAND)?)?;
+        let mut channels: Vec<_> = self.channels().collect();
+        if channels.is_empty() {
+            return Ok(());
+        }
+        channels.sort();
+        send_message(sender, REGISTER_CHANNEL, &join_channels(channels))
+    }
+
+    pub fn handle_message(
+        &self,
+        player: &mut T,
+        channels: &mut PlayerChannels,
+        packet: &PluginMessagePC2S,
+        sender: &mut PacketQueue,
+    ) -> anyhow::Result<()> {
+        let channel = packet.channel.to_string();
+        match channel.as_str() {
+            REGISTER_CHANNEL => channels.registered.extend(split_channels(packet.data)),
+            UNREGISTER_CHANNEL => for channel in split_channels(packet.data) {
+                channels.registered.remove(&channel);
+            },
+            BRAND_CHANNEL => channels.brand = Some(read_brand(packet.data)?.into()),
+            _ => {}
+        }
+        if let Some(handlers) = self.handlers.get(&channel) {
+            for handler in handlers {
+                handler(player, packet.data, sender)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn send_message<S: PacketSender>(sender: &mut S, channel: &str, data: &[u8]) -> anyhow::Result<()> {
+    sender.send_packet(&PluginMessagePS2C {
+        channel: Identifier::new_full(Cow::Borrowed(channel))
+            .ok_or_else(|| anyhow::Error::msg("Channel must be an identifier"))?,
+        data,
+    })
+}
+
+/// Register and unregister payloads are channels separated by `\0`
+pub fn split_channels(data: &[u8]) -> impl Iterator<Item=String> + '_ {
+    data.split(|byte| *byte == 0)
+        .filter(|channel| !channel.is_empty())
+        .map(|channel| String::from_utf8_lossy(channel).into_owned())
+}
+
+pub fn join_channels<'a>(channels: impl IntoIterator<Item=&'a str>) -> Vec<u8> {
+    channels.into_iter().collect::<Vec<_>>().join("\0").into_bytes()
+}
+
+/// Brand is a string prefixed with the var int length
+pub fn read_brand(mut data: &[u8]) -> anyhow::Result<&str> {
+    Ok(<&str>::read(&mut data)?)
+}
+
+pub fn brand_payload(brand: &str) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    brand.write(&mut data)?;
+    Ok(data)
+}
+
+/// Reads the string of `DataOutput.writeUTF` which is used by the BungeeCord channel
+pub fn read_utf<'a>(data: &mut &'a [u8]) -> anyhow::Result<&'a str> {
+    if data.len() < 2 {
+        return Err(anyhow::Error::msg("Unexpected end of the message"));
+    }
+    let length = u16::from_be_bytes([data[0], data[1]]) as usize;
+    if data.len() < length + 2 {
+        return Err(anyhow::Error::msg("Unexpected end of the message"));
+    }
+    let value = std::str::from_utf8(&data[2..length + 2])?;
+    *data = &data[length + 2..];
+    Ok(value)
+}
+
+pub fn write_utf(output: &mut Vec<u8>, value: &str) {
+    output.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    output.extend_from_slice(value.as_bytes());
+}
+
+/// Login plugin requests which are waiting for the response
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LoginPluginRequests {
+    next_id: i32,
+    pending: HashMap<i32, String>,
+}
+
+impl LoginPluginRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the message id of the request
+    pub fn send<S: PacketSender>(&mut self, channel: &str, data: &[u8], sender: &mut S) -> anyhow::Result<i32> {
+        let message_id = self.next_id;
+        sender.send_packet(&LoginPluginRequestLS2C {
+            message_id,
+            channel: Identifier::new_full(Cow::Borrowed(channel))
+                .ok_or_else(|| anyhow::Error::msg("Channel must be an identifier"))?,
+            data,
+        })?;
+        self.next_id += 1;
+        self.pending.insert(message_id, channel.into());
+        Ok(message_id)
+    }
+
+    /// Returns the channel of the request and the data or `None` if the client doesn't understand the channel
+    pub fn handle_response<'a>(&mut self, packet: &LoginPluginResponseLC2S<'a>) -> anyhow::Result<(String, Option<&'a [u8]>)> {
+        let channel = self.pending.remove(&packet.message_id)
+            .ok_or_else(|| anyhow::Error::msg("Unexpected login plugin response"))?;
+        Ok((channel, packet.successful.then_some(packet.data)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_channels_test() {
+        let mut channels = PluginChannels::<Vec<String>>::new();
+        channels.subscribe(BUNGEE_CHANNEL, |log, mut data, sender| {
+            let subchannel = read_utf(&mut data)?;
+            log.push(subchannel.into());
+            let mut response = Vec::new();
+            write_utf(&mut response, "GetServer");
+            write_utf(&mut response, "lobby");
+            send_message(sender, BUNGEE_CHANNEL, &response)
+        });
+        let mut queue = PacketQueue::new();
+        channels.send_join(&mut queue).unwrap();
+        assert_eq!(queue.take().len(), 2);
+        let mut player = PlayerChannels::default();
+        let mut log = Vec::new();
+        let register = PluginMessagePC2S {
+            channel: Identifier::new_full(Cow::Borrowed(REGISTER_CHANNEL)).unwrap(),
+            data: b"bungeecord:main\0custom:channel",
+        };
+        channels.handle_message(&mut log, &mut player, &register, &mut queue).unwrap();
+        assert!(player.can_receive("custom:channel"));
+        assert!(!player.can_receive("other:channel"));
+        let brand = brand_payload("vanilla").unwrap();
+        let message = PluginMessagePC2S { channel: Identifier::new_full(Cow::Borrowed(BRAND_CHANNEL)).unwrap(), data: &brand };
+        channels.handle_message(&mut log, &mut player, &message, &mut queue).unwrap();
+        assert_eq!(player.brand.as_deref(), Some("vanilla"));
+        let mut request = Vec::new();
+        write_utf(&mut request, "GetServer");
+        let message = PluginMessagePC2S { channel: Identifier::new_full(Cow::Borrowed(BUNGEE_CHANNEL)).unwrap(), data: &request };
+        channels.handle_message(&mut log, &mut player, &message, &mut queue).unwrap();
+        assert_eq!(log, ["GetServer"]);
+        assert_eq!(queue.take().len(), 1);
+        let unregister = PluginMessagePC2S {
+            channel: Identifier::new_full(Cow::Borrowed(UNREGISTER_CHANNEL)).unwrap(),
+            data: b"custom:channel",
+        };
+        channels.handle_message(&mut log, &mut player, &unregister, &mut queue).unwrap();
+        assert!(!player.send("custom:channel", &[], &mut queue).unwrap());
+    }
+}
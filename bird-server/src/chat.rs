@@ -0,0 +1,422 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use rsa::{PaddingScheme, PublicKey, RsaPublicKey};
+use rsa::pkcs8::DecodePublicKey;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::{PacketQueue, PacketSender};
+use crate::protocol::*;
+use crate::viewers::{Viewer, Viewers};
+use crate::chat::pipeline::{ChatInput, ChatOutcome, ChatPipeline};
+
+pub mod pipeline;
+
+pub type MessageSignature = [u8; MESSAGE_SIGNATURE_LENGTH];
+
+/// Index of `minecraft:chat` in the chat type registry
+pub const CHAT_TYPE_CHAT: i32 = 0;
+const SIGNED_MESSAGE_VERSION: i32 = 1;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerPublicKey {
+    /// Milliseconds since the unix epoch
+    pub expires_at: i64,
+    /// X.509 encoded RSA key
+    pub encoded: Vec<u8>,
+    /// Signature of the key made by Mojang
+    pub key_signature: Vec<u8>,
+    key: RsaPublicKey,
+}
+
+impl PlayerPublicKey {
+    pub fn new(expires_at: i64, encoded: Vec<u8>, key_signature: Vec<u8>) -> anyhow::Result<Self> {
+        let key = RsaPublicKey::from_public_key_der(&encoded)
+            .map_err(|_| anyhow::Error::msg("Bad public key"))?;
+        Ok(Self { expires_at, encoded, key_signature, key })
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Verifies SHA256 with RSA signature of the data
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        let hashed = Sha256::digest(data);
+        self.key.verify(PaddingScheme::new_pkcs1v15_sign::<Sha256>(), &hashed, signature).is_ok()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChatSession {
+    pub session_id: Uuid,
+    pub public_key: PlayerPublicKey,
+}
+
+impl ChatSession {
+    pub fn from_packet(packet: &PlayerSessionPC2S) -> anyhow::Result<Self> {
+        Ok(Self {
+            session_id: packet.session_id,
+            public_key: PlayerPublicKey::new(packet.expires_at, packet.public_key.to_vec(), packet.key_signature.to_vec())?,
+        })
+    }
+
+    pub fn initialize_chat(&self) -> PlayerInfoUpdateInitializeChat {
+        PlayerInfoUpdateInitializeChat {
+            chat_session_id: self.session_id,
+            public_key_expire_time: self.public_key.expires_at,
+            encoded_public_key: &self.public_key.encoded,
+            public_key_signature: &self.public_key.key_signature,
+        }
+    }
+}
+
+/// Data which is covered by the message signature
+pub fn signed_message_data(
+    sender: Uuid,
+    session_id: Uuid,
+    index: i32,
+    salt: i64,
+    timestamp: i64,
+    message: &str,
+    last_seen: &[MessageSignature],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(64 + message.len() + last_seen.len() * MESSAGE_SIGNATURE_LENGTH);
+    data.extend_from_slice(&SIGNED_MESSAGE_VERSION.to_be_bytes());
+    data.extend_from_slice(sender.as_bytes());
+    data.extend_from_slice(session_id.as_bytes());
+    data.extend_from_slice(&index.to_be_bytes());
+    data.extend_from_slice(&salt.to_be_bytes());
+    // Signature contains seconds while the packet contains milliseconds
+    data.extend_from_slice(&(timestamp / 1000).to_be_bytes());
+    data.extend_from_slice(&(message.len() as i32).to_be_bytes());
+    data.extend_from_slice(message.as_bytes());
+    data.extend_from_slice(&(last_seen.len() as i32).to_be_bytes());
+    for signature in last_seen {
+        data.extend_from_slice(signature);
+    }
+    data
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct TrackedSignature {
+    signature: MessageSignature,
+    pending: bool,
+}
+
+/// Server side copy of the client last seen window, it is used to restore the signatures
+/// which are acknowledged by the client
+#[derive(Clone, PartialEq, Debug)]
+pub struct LastSeenMessages {
+    tracked: Vec<Option<TrackedSignature>>,
+    last_pending: Option<MessageSignature>,
+}
+
+impl Default for LastSeenMessages {
+    fn default() -> Self {
+        Self {
+            tracked: vec![None; LAST_SEEN_MESSAGES_LENGTH],
+            last_pending: None,
+        }
+    }
+}
+
+impl LastSeenMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers the signature of the message which is sent to the client
+    pub fn add_pending(&mut self, signature: MessageSignature) {
+        if self.last_pending != Some(signature) {
+            self.tracked.push(Some(TrackedSignature { signature, pending: true }));
+            self.last_pending = Some(signature);
+        }
+    }
+
+    /// Count of messages which are not acknowledged yet
+    pub fn pending_count(&self) -> usize {
+        self.tracked.len() - LAST_SEEN_MESSAGES_LENGTH
+    }
+
+    pub fn apply_offset(&mut self, offset: i32) -> anyhow::Result<()> {
+        if offset < 0 || offset as usize > self.pending_count() {
+            return Err(anyhow::Error::msg("Advanced last seen window by offset beyond pending messages"));
+        }
+        self.tracked.drain(..offset as usize);
+        Ok(())
+    }
+
+    /// Returns the signatures of the acknowledged messages
+    pub fn apply_update(&mut self, offset: i32, acknowledged: &[u8; 3]) -> anyhow::Result<Vec<MessageSignature>> {
+        self.apply_offset(offset)?;
+        let is_acknowledged = |index: usize| (acknowledged[index / 8] >> (index % 8)) & 1 == 1;
+        if (LAST_SEEN_MESSAGES_LENGTH..acknowledged.len() * 8).any(is_acknowledged) {
+            return Err(anyhow::Error::msg("Last seen update contains too many messages"));
+        }
+        let mut last_seen = Vec::new();
+        for (index, entry) in self.tracked.iter_mut().take(LAST_SEEN_MESSAGES_LENGTH).enumerate() {
+            match (is_acknowledged(index), entry.as_mut()) {
+                (true, Some(tracked)) => {
+                    tracked.pending = false;
+                    last_seen.push(tracked.signature);
+                }
+                (true, None) => return Err(anyhow::Error::msg("Last seen update acknowledged unknown message")),
+                (false, Some(TrackedSignature { pending: false, .. })) => {
+                    return Err(anyhow::Error::msg("Last seen update ignored previously acknowledged message"));
+                }
+                (false, _) => *entry = None,
+            }
+        }
+        Ok(last_seen)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatPlayer {
+    pub uuid: Uuid,
+    pub name: String,
+    pub packets: PacketQueue,
+    session: Option<ChatSession>,
+    next_index: i32,
+    last_timestamp: i64,
+    last_seen: LastSeenMessages,
+}
+
+impl ChatPlayer {
+    pub fn session(&self) -> Option<&ChatSession> {
+        self.session.as_ref()
+    }
+
+    /// Checks that messages are received in the order they were sent
+    fn update_timestamp(&mut self, timestamp: i64) -> anyhow::Result<()> {
+        if timestamp < self.last_timestamp {
+            return Err(anyhow::Error::msg("Out-of-order chat message"));
+        }
+        self.last_timestamp = timestamp;
+        Ok(())
+    }
+}
+
+impl Viewer for ChatPlayer {
+    fn packets(&mut self) -> &mut PacketQueue {
+        &mut self.packets
+    }
+}
+
+/// Broadcasts chat messages, messages are sent as signed player messages if secure chat is enabled,
+/// otherwise as system messages
+#[derive(Clone, Debug)]
+pub struct ChatManager {
+    pub secure_chat: bool,
+    players: HashMap<Uuid, ChatPlayer>,
+}
+
+impl ChatManager {
+    pub fn new(secure_chat: bool) -> Self {
+        Self {
+            secure_chat,
+            players: HashMap::new(),
+        }
+    }
+
+    pub fn get_player(&self, uuid: &Uuid) -> Option<&ChatPlayer> {
+        self.players.get(uuid)
+    }
+
+    pub fn get_player_mut(&mut self, uuid: &Uuid) -> Option<&mut ChatPlayer> {
+        self.players.get_mut(uuid)
+    }
+
+    pub fn players_mut(&mut self) -> impl Iterator<Item = &mut ChatPlayer> {
+        self.players.values_mut()
+    }
+
+    fn player_mut(&mut self, uuid: &Uuid) -> anyhow::Result<&mut ChatPlayer> {
+        self.players.get_mut(uuid).ok_or_else(|| anyhow::Error::msg("Unknown chat player"))
+    }
+
+    pub fn add_player(&mut self, uuid: Uuid, name: String) -> &mut ChatPlayer {
+        self.players.insert(uuid, ChatPlayer {
+            uuid,
+            name,
+            packets: PacketQueue::new(),
+            session: None,
+            next_index: 0,
+            last_timestamp: i64::MIN,
+            last_seen: LastSeenMessages::new(),
+        });
+        self.players.get_mut(&uuid).unwrap()
+    }
+
+    pub fn remove_player(&mut self, uuid: &Uuid) -> Option<ChatPlayer> {
+        self.players.remove(uuid)
+    }
+
+    /// Starts a new message chain, the session is announced to all players
+    pub fn handle_session(&mut self, uuid: Uuid, packet: &PlayerSessionPC2S, now: i64) -> anyhow::Result<()> {
+        let session = ChatSession::from_packet(packet)?;
+        if session.public_key.is_expired(now) {
+            return Err(anyhow::Error::msg("Expired profile public key"));
+        }
+        let packet = PlayerInfoUpdatePS2C {
+            actions: Cow::Owned(vec![(uuid, PlayerInfoUpdateAction {
+                add: None,
+                initialize_chat: Some(session.initialize_chat()),
+                update_game_mode: None,
+                update_listed: None,
+                update_latency: None,
+                update_display_name: None,
+            })]),
+        };
+        let player = self.player_mut(&uuid)?;
+        player.session = Some(session);
+        player.next_index = 0;
+        Viewers::all(self.players.values_mut()).send(&packet)
+    }
+
+    pub fn handle_acknowledgment(&mut self, uuid: Uuid, packet: &MessageAcknowledgmentPC2S) -> anyhow::Result<()> {
+        self.player_mut(&uuid)?.last_seen.apply_offset(packet.offset)
+    }
+
+    /// Validates the message order and the last seen messages of the command,
+    /// the command itself should be executed by the caller
+    pub fn handle_command(&mut self, uuid: Uuid, packet: &ChatCommandPC2S) -> anyhow::Result<()> {
+        let player = self.player_mut(&uuid)?;
+        player.update_timestamp(packet.timestamp)?;
+        player.last_seen.apply_update(packet.message_count, packet.acknowledged)?;
+        Ok(())
+    }
+
+    /// Result of the pipeline for the message which is typed by the player
+    pub fn preview(&self, uuid: &Uuid, message: &str, pipeline: &ChatPipeline) -> anyhow::Result<Option<Component<'static>>> {
+        let player = self.players.get(uuid).ok_or_else(|| anyhow::Error::msg("Unknown chat player"))?;
+        Ok(pipeline.preview(&ChatInput { sender: *uuid, name: &player.name, message }))
+    }
+
+    /// Errors mean that the player should be disconnected. The message goes through the pipeline,
+    /// rejected messages are not broadcast and the reason is sent to the player
+    pub fn handle_message(&mut self, uuid: Uuid, packet: &ChatMessagePC2S, now: i64, pipeline: &ChatPipeline) -> anyhow::Result<()> {
+        let secure_chat = self.secure_chat;
+        let player = self.player_mut(&uuid)?;
+        player.update_timestamp(packet.timestamp)?;
+        let last_seen = player.last_seen.apply_update(packet.message_count, packet.acknowledged)?;
+        let outcome = pipeline.process(&ChatInput { sender: uuid, name: &player.name, message: packet.message });
+        if !secure_chat {
+            let content = match outcome {
+                ChatOutcome::Broadcast { content, .. } => content,
+                ChatOutcome::Rejected(reason) => return player.packets.send_packet(&SystemChatMessagePS2C { content: reason, overlay: false }),
+            };
+            // The same layout as the chat type of signed messages, which adds the name of the sender
+            let content = Component::translation("chat.type.text", vec![Component::text(player.name.clone()), content]);
+            return self.broadcast_system(&content, false);
+        }
+        let (session, signature) = match (player.session.as_ref(), packet.signature) {
+            (Some(session), Some(signature)) => (session, signature),
+            _ => return Err(anyhow::Error::msg("Missing chat signature")),
+        };
+        if session.public_key.is_expired(now) {
+            return Err(anyhow::Error::msg("Expired profile public key"));
+        }
+        let index = player.next_index;
+        let data = signed_message_data(
+            uuid, session.session_id, index, packet.salt, packet.timestamp, packet.message, &last_seen,
+        );
+        if !session.public_key.verify(&data, signature) {
+            return Err(anyhow::Error::msg("Invalid chat signature"));
+        }
+        // The index is counted by the client for each signed message, rejected ones too
+        player.next_index += 1;
+        let unsigned_content = match outcome {
+            ChatOutcome::Broadcast { content, modified } => modified.then_some(content),
+            ChatOutcome::Rejected(reason) => return player.packets.send_packet(&SystemChatMessagePS2C { content: reason, overlay: false }),
+        };
+        let name = Component::text(player.name.clone());
+        // Signatures are always sent in full, so the client signature cache is not mirrored
+        Viewers::all(self.players.values_mut()).send(&PlayerChatMessagePS2C {
+            sender: uuid,
+            index,
+            message_signature_bytes: Some(signature),
+            message: packet.message,
+            timestamp: packet.timestamp,
+            salt: packet.salt,
+            previous_messages: Cow::Owned(last_seen.iter().map(PackedMessageSignature::Full).collect()),
+            unsigned_content,
+            filter: PlayerChatFilter::PassThrough,
+            chat_ty: CHAT_TYPE_CHAT,
+            network_name: name,
+            network_target_name: None,
+        })?;
+        for player in self.players.values_mut() {
+            player.last_seen.add_pending(*signature);
+        }
+        Ok(())
+    }
+
+    pub fn broadcast_system(&mut self, content: &Component, overlay: bool) -> anyhow::Result<()> {
+        Viewers::all(self.players.values_mut()).send(&SystemChatMessagePS2C { content: content.clone(), overlay })
+    }
+
+    pub fn send_system(&mut self, uuid: &Uuid, content: &Component, overlay: bool) -> anyhow::Result<()> {
+        self.player_mut(uuid)?.packets.send_packet(&SystemChatMessagePS2C { content: content.clone(), overlay })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chat::pipeline::ChatFilterResult;
+    use crate::connection::encode_packet;
+    use crate::events::EventPriority;
+    use super::*;
+
+    #[test]
+    fn last_seen_test() {
+        let mut last_seen = LastSeenMessages::new();
+        last_seen.add_pending([1; MESSAGE_SIGNATURE_LENGTH]);
+        last_seen.add_pending([2; MESSAGE_SIGNATURE_LENGTH]);
+        assert_eq!(last_seen.pending_count(), 2);
+        // Window is moved by 2, so new messages are at 18 and 19
+        let acknowledged = [0, 0, 0b1100];
+        assert_eq!(
+            last_seen.apply_update(2, &acknowledged).unwrap(),
+            vec![[1; MESSAGE_SIGNATURE_LENGTH], [2; MESSAGE_SIGNATURE_LENGTH]],
+        );
+        assert!(last_seen.apply_update(0, &[0, 0, 0b0100]).is_err());
+        assert!(last_seen.apply_offset(1).is_err());
+    }
+
+    #[test]
+    fn handle_message_test() {
+        let (sender, viewer) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut chat = ChatManager::new(false);
+        chat.add_player(sender, "Notch".to_owned());
+        chat.add_player(viewer, "jeb_".to_owned());
+        let mut pipeline = ChatPipeline::new();
+        pipeline
+            .on_filter(EventPriority::Normal, |_, message| match message.contains("spam") {
+                true => ChatFilterResult::Reject(Component::text("No spam")),
+                false => ChatFilterResult::Continue,
+            })
+            .set_formatter(|_, message| Component::text(message.to_uppercase()));
+        let packet = ChatMessagePC2S { message: "hello", timestamp: 0, salt: 0, signature: None, message_count: 0, acknowledged: &[0; 3] };
+        chat.handle_message(sender, &packet, 0, &pipeline).unwrap();
+        // The name is added only once
+        let expected = encode_packet(&SystemChatMessagePS2C {
+            content: Component::translation("chat.type.text", vec![Component::text("Notch"), Component::text("HELLO")]),
+            overlay: false,
+        }).unwrap();
+        for uuid in [sender, viewer] {
+            assert_eq!(chat.get_player_mut(&uuid).unwrap().packets.take(), vec![expected.clone()]);
+        }
+
+        let spam = ChatMessagePC2S { message: "spam", timestamp: 1, ..packet };
+        chat.handle_message(sender, &spam, 0, &pipeline).unwrap();
+        let reason = encode_packet(&SystemChatMessagePS2C { content: Component::text("No spam"), overlay: false }).unwrap();
+        assert_eq!(chat.get_player_mut(&sender).unwrap().packets.take(), vec![reason]);
+        assert!(chat.get_player_mut(&viewer).unwrap().packets.take().is_empty());
+        assert!(chat.handle_message(sender, &ChatMessagePC2S { timestamp: 0, ..packet }, 0, &pipeline).is_err());
+        chat.secure_chat = true;
+        assert!(chat.handle_message(sender, &ChatMessagePC2S { timestamp: 2, ..packet }, 0, &pipeline).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use uuid::Uuid;
+use bird_chat::component::Component;
+use crate::events::EventPriority;
+
+/// Chat message of a player before filters are applied
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ChatInput<'a> {
+    pub sender: Uuid,
+    pub name: &'a str,
+    pub message: &'a str,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChatFilterResult {
+    Continue,
+    /// Next filters receive the replaced text
+    Replace(String),
+    /// The message is not broadcast, the reason is sent to the sender
+    Reject(Component<'static>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChatOutcome {
+    /// `content` is the formatted message without the name of the sender, which is added by the chat type.
+    /// Signed messages are sent with the unsigned content if it is modified
+    Broadcast { content: Component<'static>, modified: bool },
+    Rejected(Component<'static>),
+}
+
+type ChatFilter = Box<dyn Fn(&ChatInput, &str) -> ChatFilterResult + Send + Sync>;
+type ChatFormatter = Box<dyn Fn(&ChatInput, &str) -> Component<'static> + Send + Sync>;
+
+/// Filters and the formatter of incoming chat messages. Previews are produced by the same pipeline,
+/// so the preview matches the broadcast message.
+/// 1.19.3 removed chat previews, there is no `RequestChatPreview` packet to answer
+#[derive(Default)]
+pub struct ChatPipeline {
+    /// Previews are produced only if it is enabled
+    pub previews_chat: bool,
+    filters: Vec<(EventPriority, ChatFilter)>,
+    formatter: Option<ChatFormatter>,
+}
+
+impl ChatPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filters with the higher priority are called first, a rejection skips remaining ones
+    pub fn on_filter<F>(&mut self, priority: EventPriority, filter: F) -> &mut Self
+        where F: Fn(&ChatInput, &str) -> ChatFilterResult + Send + Sync + 'static {
+        let index = self.filters.partition_point(|(other, _)| *other >= priority);
+        self.filters.insert(index, (priority, Box::new(filter)));
+        self
+    }
+
+    /// Formats the filtered text, the text is sent as is by default.
+    /// The name of the sender is added by the chat type, so the formatter must not add it
+    pub fn set_formatter<F>(&mut self, formatter: F) -> &mut Self
+        where F: Fn(&ChatInput, &str) -> Component<'static> + Send + Sync + 'static {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    pub fn process(&self, input: &ChatInput) -> ChatOutcome {
+        let mut message = input.message.to_owned();
+        for (_, filter) in &self.filters {
+            match filter(input, &message) {
+                ChatFilterResult::Continue => {}
+                ChatFilterResult::Replace(replaced) => message = replaced,
+                ChatFilterResult::Reject(reason) => return ChatOutcome::Rejected(reason),
+            }
+        }
+        let content = match self.formatter {
+            Some(ref formatter) => formatter(input, &message),
+            None => Component::text(message),
+        };
+        // Formatters which keep the text as is do not replace the signed content
+        ChatOutcome::Broadcast { modified: content != Component::text(input.message), content }
+    }
+
+    /// Content which the message would have, `None` if previews are disabled or the message is rejected
+    pub fn preview(&self, input: &ChatInput) -> Option<Component<'static>> {
+        match self.previews_chat {
+            true => match self.process(input) {
+                ChatOutcome::Broadcast { content, .. } => Some(content),
+                ChatOutcome::Rejected(_) => None,
+            },
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_pipeline_test() {
+        let mut pipeline = ChatPipeline::new();
+        let input = ChatInput { sender: Uuid::nil(), name: "player", message: "hello darn world" };
+        assert_eq!(pipeline.process(&input), ChatOutcome::Broadcast { content: Component::text("hello darn world"), modified: false });
+        pipeline
+            .on_filter(EventPriority::Normal, |_, message| match message.contains("spam") {
+                true => ChatFilterResult::Reject(Component::text("No spam")),
+                false => ChatFilterResult::Continue,
+            })
+            .on_filter(EventPriority::High, |_, message| ChatFilterResult::Replace(message.replace("darn", "****")));
+        assert_eq!(pipeline.process(&input), ChatOutcome::Broadcast { content: Component::text("hello **** world"), modified: true });
+        let spam = ChatInput { message: "spam", ..input };
+        assert_eq!(pipeline.process(&spam), ChatOutcome::Rejected(Component::text("No spam")));
+        assert_eq!(pipeline.preview(&input), None);
+        pipeline.previews_chat = true;
+        pipeline.set_formatter(|_, message| Component::text(message.to_uppercase()));
+        assert!(pipeline.preview(&spam).is_none());
+        assert_eq!(pipeline.preview(&input), Some(Component::text("HELLO **** WORLD")));
+        let loud = ChatInput { message: "HELLO", ..input };
+        assert_eq!(pipeline.process(&loud), ChatOutcome::Broadcast { content: Component::text("HELLO"), modified: false });
+    }
+}
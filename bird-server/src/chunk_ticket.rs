@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use euclid::default::Vector2D;
+use bird_protocol::anyhow;
+
+/// Chunks with this or a lower level are loaded, the level grows by one with every chunk from the ticket
+pub const FULL_CHUNK_LEVEL: u8 = 33;
+pub const BLOCK_TICKING_LEVEL: u8 = 32;
+pub const ENTITY_TICKING_LEVEL: u8 = 31;
+/// Level of the spawn ticket, so 11 chunks around spawn are entity ticking
+pub const START_TICKET_LEVEL: u8 = 22;
+pub const PORTAL_TICKET_LEVEL: u8 = 30;
+pub const TELEPORT_TICKET_LEVEL: u8 = 32;
+pub const PORTAL_TICKET_TICKS: u64 = 300;
+pub const TELEPORT_TICKET_TICKS: u64 = 5;
+/// Vanilla limit of `/forceload add` for one area
+pub const MAX_FORCE_LOAD_AREA: usize = 256;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TicketType {
+    Start,
+    /// Keeps chunks in the view distance of the player loaded
+    Player(i32),
+    /// Ticket of `/forceload`, never expires
+    Forced,
+    Portal,
+    PostTeleport(i32),
+    /// Ticket which is added by a plugin, plugins remove only their own tickets
+    Plugin(String),
+}
+
+impl TicketType {
+    /// Vanilla removes portal and teleport tickets after some ticks
+    pub fn expire_after(&self) -> Option<u64> {
+        match self {
+            TicketType::Portal => Some(PORTAL_TICKET_TICKS),
+            TicketType::PostTeleport(_) => Some(TELEPORT_TICKET_TICKS),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ChunkLoadStatus {
+    EntityTicking,
+    BlockTicking,
+    /// Loaded, but neither blocks nor entities are ticked
+    Border,
+    Unloaded,
+}
+
+impl ChunkLoadStatus {
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            level if level <= ENTITY_TICKING_LEVEL => ChunkLoadStatus::EntityTicking,
+            BLOCK_TICKING_LEVEL => ChunkLoadStatus::BlockTicking,
+            FULL_CHUNK_LEVEL => ChunkLoadStatus::Border,
+            _ => ChunkLoadStatus::Unloaded,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ticket {
+    pub ty: TicketType,
+    pub level: u8,
+    /// Tick when the ticket is removed
+    pub expires_at: Option<u64>,
+}
+
+/// Chunks which became loaded or unloaded since the last update
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ChunkLevelChanges {
+    pub loaded: Vec<Vector2D<i32>>,
+    pub unloaded: Vec<Vector2D<i32>>,
+}
+
+/// Tickets of one world, the level of a chunk is the lowest level which its tickets propagate to it.
+/// Loading chunks into [`crate::world::World`] is left to the caller of [`ChunkTickets::update`]
+#[derive(Clone, Debug, Default)]
+pub struct ChunkTickets {
+    tickets: HashMap<Vector2D<i32>, Vec<Ticket>>,
+    levels: HashMap<Vector2D<i32>, u8>,
+    player_chunks: HashMap<i32, Vector2D<i32>>,
+    tick: u64,
+    dirty: bool,
+}
+
+impl ChunkTickets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Ticket with the same type and level is not added twice, an expiring ticket is renewed instead
+    pub fn add_ticket(&mut self, position: Vector2D<i32>, ty: TicketType, level: u8) {
+        let expires_at = ty.expire_after().map(|ticks| self.tick + ticks);
+        let tickets = self.tickets.entry(position).or_default();
+        match tickets.iter_mut().find(|ticket| ticket.ty == ty && ticket.level == level) {
+            Some(ticket) => ticket.expires_at = expires_at,
+            None => {
+                tickets.push(Ticket { ty, level, expires_at });
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Removes tickets of the type from the chunk, returns `false` if there were none
+    pub fn remove_ticket(&mut self, position: Vector2D<i32>, ty: &TicketType) -> bool {
+        let tickets = match self.tickets.get_mut(&position) {
+            Some(tickets) => tickets,
+            None => return false,
+        };
+        let count = tickets.len();
+        tickets.retain(|ticket| ticket.ty != *ty);
+        let removed = tickets.len() != count;
+        if tickets.is_empty() {
+            self.tickets.remove(&position);
+        }
+        self.dirty |= removed;
+        removed
+    }
+
+    pub fn tickets(&self, position: Vector2D<i32>) -> &[Ticket] {
+        self.tickets.get(&position).map(|tickets| tickets.as_slice()).unwrap_or(&[])
+    }
+
+    /// Moves the player ticket, chunks in the view distance are loaded and
+    /// chunks two chunks closer are entity ticking like in vanilla
+    pub fn update_player(&mut self, entity_id: i32, chunk: Vector2D<i32>, view_distance: u8) {
+        let level = FULL_CHUNK_LEVEL.saturating_sub(view_distance);
+        if let Some(previous) = self.player_chunks.insert(entity_id, chunk) {
+            if previous == chunk && self.tickets(chunk).iter().any(|ticket| ticket.ty == TicketType::Player(entity_id) && ticket.level == level) {
+                return;
+            }
+            self.remove_ticket(previous, &TicketType::Player(entity_id));
+        }
+        self.add_ticket(chunk, TicketType::Player(entity_id), level);
+    }
+
+    pub fn remove_player(&mut self, entity_id: i32) {
+        if let Some(chunk) = self.player_chunks.remove(&entity_id) {
+            self.remove_ticket(chunk, &TicketType::Player(entity_id));
+        }
+    }
+
+    /// Keeps the chunk entity ticking, returns `false` if it is already forced
+    pub fn force_load(&mut self, position: Vector2D<i32>) -> bool {
+        if self.is_forced(position) {
+            return false;
+        }
+        self.add_ticket(position, TicketType::Forced, ENTITY_TICKING_LEVEL);
+        true
+    }
+
+    pub fn unforce(&mut self, position: Vector2D<i32>) -> bool {
+        self.remove_ticket(position, &TicketType::Forced)
+    }
+
+    pub fn is_forced(&self, position: Vector2D<i32>) -> bool {
+        self.tickets(position).iter().any(|ticket| ticket.ty == TicketType::Forced)
+    }
+
+    pub fn forced(&self) -> impl Iterator<Item=Vector2D<i32>> + '_ {
+        self.tickets.iter()
+            .filter(|(_, tickets)| tickets.iter().any(|ticket| ticket.ty == TicketType::Forced))
+            .map(|(position, _)| *position)
+    }
+
+    /// Forces or unforces all chunks between the corners like `/forceload add|remove`,
+    /// returns the number of changed chunks
+    pub fn force_load_area(&mut self, from: Vector2D<i32>, to: Vector2D<i32>, forced: bool) -> anyhow::Result<usize> {
+        let (min, max) = (from.min(to), from.max(to));
+        let count = (max.x - min.x + 1) as usize * (max.y - min.y + 1) as usize;
+        if forced && count > MAX_FORCE_LOAD_AREA {
+            return Err(anyhow::Error::msg(format!(
+                "Too many chunks in the specified area (maximum {}, specified {})", MAX_FORCE_LOAD_AREA, count,
+            )));
+        }
+        let mut changed = 0;
+        for x in min.x..=max.x {
+            for z in min.y..=max.y {
+                let position = Vector2D::new(x, z);
+                changed += match forced {
+                    true => self.force_load(position),
+                    false => self.unforce(position),
+                } as usize;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Advances the tick and removes expired tickets
+    pub fn tick(&mut self) {
+        self.tick += 1;
+        let tick = self.tick;
+        let mut removed = false;
+        self.tickets.retain(|_, tickets| {
+            let count = tickets.len();
+            tickets.retain(|ticket| ticket.expires_at.map(|expires_at| expires_at > tick).unwrap_or(true));
+            removed |= tickets.len() != count;
+            !tickets.is_empty()
+        });
+        self.dirty |= removed;
+    }
+
+    pub fn level(&self, position: Vector2D<i32>) -> Option<u8> {
+        self.levels.get(&position).copied()
+    }
+
+    pub fn status(&self, position: Vector2D<i32>) -> ChunkLoadStatus {
+        self.level(position).map(ChunkLoadStatus::from_level).unwrap_or(ChunkLoadStatus::Unloaded)
+    }
+
+    pub fn is_loaded(&self, position: Vector2D<i32>) -> bool {
+        self.levels.contains_key(&position)
+    }
+
+    pub fn loaded(&self) -> impl Iterator<Item=Vector2D<i32>> + '_ {
+        self.levels.keys().copied()
+    }
+
+    /// Propagates levels of the tickets if they were changed, the caller loads and unloads the chunks
+    pub fn update(&mut self) -> ChunkLevelChanges {
+        if !self.dirty {
+            return ChunkLevelChanges::default();
+        }
+        self.dirty = false;
+        let mut levels: HashMap<Vector2D<i32>, u8> = HashMap::new();
+        for (position, tickets) in &self.tickets {
+            let level = match tickets.iter().map(|ticket| ticket.level).min() {
+                Some(level) if level <= FULL_CHUNK_LEVEL => level,
+                _ => continue,
+            };
+            let radius = (FULL_CHUNK_LEVEL - level) as i32;
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    let chunk_level = level + x.abs().max(z.abs()) as u8;
+                    let entry = levels.entry(Vector2D::new(position.x + x, position.y + z)).or_insert(chunk_level);
+                    *entry = (*entry).min(chunk_level);
+                }
+            }
+        }
+        let previous: HashSet<Vector2D<i32>> = self.levels.keys().copied().collect();
+        let changes = ChunkLevelChanges {
+            loaded: levels.keys().filter(|position| !previous.contains(position)).copied().collect(),
+            unloaded: previous.into_iter().filter(|position| !levels.contains_key(position)).collect(),
+        };
+        self.levels = levels;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_tickets_test() {
+        let mut tickets = ChunkTickets::new();
+        assert!(tickets.force_load(Vector2D::new(0, 0)));
+        assert!(!tickets.force_load(Vector2D::new(0, 0)));
+        let changes = tickets.update();
+        // Forced chunk and two rings around it
+        assert_eq!(changes.loaded.len(), 25);
+        assert_eq!(tickets.status(Vector2D::new(0, 0)), ChunkLoadStatus::EntityTicking);
+        assert_eq!(tickets.status(Vector2D::new(1, -1)), ChunkLoadStatus::BlockTicking);
+        assert_eq!(tickets.status(Vector2D::new(2, 0)), ChunkLoadStatus::Border);
+        assert_eq!(tickets.status(Vector2D::new(3, 0)), ChunkLoadStatus::Unloaded);
+        assert_eq!(tickets.update(), ChunkLevelChanges::default());
+
+        tickets.add_ticket(Vector2D::new(10, 0), TicketType::PostTeleport(1), TELEPORT_TICKET_LEVEL);
+        assert_eq!(tickets.update().loaded.len(), 9);
+        for _ in 0..TELEPORT_TICKET_TICKS {
+            tickets.tick();
+        }
+        assert_eq!(tickets.update().unloaded.len(), 9);
+
+        tickets.update_player(1, Vector2D::new(0, 0), 10);
+        tickets.update();
+        assert_eq!(tickets.level(Vector2D::new(8, 8)), Some(31));
+        tickets.update_player(1, Vector2D::new(1, 0), 10);
+        let changes = tickets.update();
+        assert_eq!((changes.loaded.len(), changes.unloaded.len()), (21, 21));
+        tickets.remove_player(1);
+        assert!(tickets.unforce(Vector2D::new(0, 0)));
+        tickets.update();
+        assert_eq!(tickets.loaded().count(), 0);
+
+        assert!(tickets.force_load_area(Vector2D::new(0, 0), Vector2D::new(16, 16), true).is_err());
+        assert_eq!(tickets.force_load_area(Vector2D::new(2, 2), Vector2D::new(0, 0), true).unwrap(), 9);
+        assert_eq!(tickets.forced().count(), 9);
+        assert_eq!(tickets.force_load_area(Vector2D::new(0, 0), Vector2D::new(0, 1), false).unwrap(), 2);
+    }
+}
@@ -0,0 +1,353 @@
+use std::borrow::Cow;
+use bird_chat::identifier::Identifier;
+use bird_data::{BiomeData, BiomePrecipitation, Block, ItemData, WorldDimension, BIOME_COUNT};
+use bird_protocol::anyhow;
+use crate::protocol::*;
+
+pub const DIMENSION_TYPE_REGISTRY: &'static str = "minecraft:dimension_type";
+pub const BIOME_REGISTRY: &'static str = "minecraft:worldgen/biome";
+pub const CHAT_TYPE_REGISTRY: &'static str = "minecraft:chat_type";
+
+pub const BLOCK_TAGS: &'static str = "minecraft:block";
+pub const ITEM_TAGS: &'static str = "minecraft:item";
+pub const FLUID_TAGS: &'static str = "minecraft:fluid";
+pub const ENTITY_TYPE_TAGS: &'static str = "minecraft:entity_type";
+
+const DEFAULT_WATER_COLOR: i32 = 4159204;
+const DEFAULT_WATER_FOG_COLOR: i32 = 329011;
+const DEFAULT_FOG_COLOR: i32 = 12638463;
+const END_FOG_COLOR: i32 = 10518688;
+
+/// Network NBT of the codec which is sent in [`LoginPS2C::registry_codec`]
+pub fn registry_codec_nbt(codec: &LoginRegistryCodec) -> anyhow::Result<Vec<u8>> {
+    Ok(fastnbt::to_bytes(codec)?)
+}
+
+/// Vanilla dimension types, biomes of `bird_data` and vanilla chat types,
+/// ids of chat types match the constants of `chat.rs`
+pub fn default_registry_codec() -> LoginRegistryCodec<'static> {
+    let dimensions = [WorldDimension::Overworld, WorldDimension::Nether, WorldDimension::End];
+    LoginRegistryCodec {
+        dimension_type: LoginRegistryCodecRegistry {
+            ty: Cow::Borrowed(DIMENSION_TYPE_REGISTRY),
+            value: Cow::Owned(dimensions.into_iter().enumerate().map(|(id, dimension)| LoginRegistryCodecValue {
+                name: Cow::Borrowed(dimension_name(dimension)),
+                id: id as i32,
+                element: dimension_type(dimension),
+            }).collect()),
+        },
+        worldgen_biome: LoginRegistryCodecRegistry {
+            ty: Cow::Borrowed(BIOME_REGISTRY),
+            value: Cow::Owned((0..BIOME_COUNT as u32)
+                .filter_map(BiomeData::from_id)
+                .map(|data| LoginRegistryCodecValue {
+                    name: Cow::Owned(format!("minecraft:{}", data.name)),
+                    id: data.id as i32,
+                    element: biome(data),
+                })
+                .collect()),
+        },
+        chat_type: LoginRegistryCodecRegistry {
+            ty: Cow::Borrowed(CHAT_TYPE_REGISTRY),
+            value: Cow::Owned(chat_types()),
+        },
+    }
+}
+
+pub const fn dimension_name(dimension: WorldDimension) -> &'static str {
+    match dimension {
+        WorldDimension::Overworld => "minecraft:overworld",
+        WorldDimension::Nether => "minecraft:the_nether",
+        WorldDimension::End => "minecraft:the_end",
+    }
+}
+
+pub fn dimension_type(dimension: WorldDimension) -> LoginRegistryCodecDimension<'static> {
+    match dimension {
+        WorldDimension::Overworld => LoginRegistryCodecDimension {
+            piglin_safe: false,
+            has_raids: true,
+            monster_spawn_light_level: 0,
+            monster_spawn_block_light_limit: 0,
+            natural: true,
+            ambient_light: 0.0,
+            fixed_time: None,
+            infiniburn: Cow::Borrowed("#minecraft:infiniburn_overworld"),
+            respawn_anchor_works: false,
+            has_skylight: true,
+            bed_works: true,
+            effects: Cow::Borrowed("minecraft:overworld"),
+            min_y: -64,
+            height: 384,
+            logical_height: 384,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false,
+        },
+        WorldDimension::Nether => LoginRegistryCodecDimension {
+            piglin_safe: true,
+            has_raids: false,
+            monster_spawn_light_level: 7,
+            monster_spawn_block_light_limit: 15,
+            natural: false,
+            ambient_light: 0.1,
+            fixed_time: Some(18000),
+            infiniburn: Cow::Borrowed("#minecraft:infiniburn_nether"),
+            respawn_anchor_works: true,
+            has_skylight: false,
+            bed_works: false,
+            effects: Cow::Borrowed("minecraft:the_nether"),
+            min_y: 0,
+            height: 256,
+            logical_height: 128,
+            coordinate_scale: 8.0,
+            ultrawarm: true,
+            has_ceiling: true,
+        },
+        WorldDimension::End => LoginRegistryCodecDimension {
+            piglin_safe: false,
+            has_raids: true,
+            monster_spawn_light_level: 0,
+            monster_spawn_block_light_limit: 0,
+            natural: false,
+            ambient_light: 0.0,
+            fixed_time: Some(6000),
+            infiniburn: Cow::Borrowed("#minecraft:infiniburn_end"),
+            respawn_anchor_works: false,
+            has_skylight: false,
+            bed_works: false,
+            effects: Cow::Borrowed("minecraft:the_end"),
+            min_y: 0,
+            height: 256,
+            logical_height: 256,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false,
+        },
+    }
+}
+
+/// Colors which are not known by `bird_data` are vanilla defaults, the sky color is computed like in vanilla
+pub fn biome(data: &BiomeData) -> LoginRegistryCodecWorldgenBiome<'static> {
+    let precipitation = match data.precipitation {
+        BiomePrecipitation::Rain => "rain",
+        BiomePrecipitation::Snow => "snow",
+        _ => "none",
+    };
+    let (sky_color, fog_color) = match data.dimension {
+        WorldDimension::End => (0, END_FOG_COLOR),
+        _ => (sky_color(data.temperature), DEFAULT_FOG_COLOR),
+    };
+    LoginRegistryCodecWorldgenBiome {
+        precipitation: Cow::Borrowed(precipitation),
+        temperature: data.temperature,
+        downfall: data.rain_fall,
+        temperature_modifier: None,
+        effects: LoginRegistryCodecWorldgenBiomeEffects {
+            sky_color,
+            water_fog_color: DEFAULT_WATER_FOG_COLOR,
+            fog_color,
+            water_color: DEFAULT_WATER_COLOR,
+            foliage_color: None,
+            grass_color: None,
+            grass_color_modifier: None,
+            music: None,
+            ambient_sound: None,
+            additions_sound: None,
+            mood_sound: (data.dimension == WorldDimension::Overworld).then(|| LoginRegistryCodecWorldgenBiomeMoodSound {
+                sound: Cow::Borrowed("minecraft:ambient.cave"),
+                tick_delay: 6000,
+                offset: 2.0,
+                block_search_extent: 8,
+            }),
+            particle: None,
+        },
+    }
+}
+
+/// `OverworldBiomes.calculateSkyColor`
+pub fn sky_color(temperature: f32) -> i32 {
+    let temperature = (temperature / 3.0).clamp(-1.0, 1.0);
+    hsv_to_rgb(0.62222224 - temperature * 0.05, 0.5 + temperature * 0.1, 1.0)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> i32 {
+    let sector = (hue * 6.0) as i32 % 6;
+    let fraction = hue * 6.0 - sector as f32;
+    let p = value * (1.0 - saturation);
+    let q = value * (1.0 - fraction * saturation);
+    let t = value * (1.0 - (1.0 - fraction) * saturation);
+    let (red, green, blue) = match sector {
+        0 => (value, t, p),
+        1 => (q, value, p),
+        2 => (p, value, t),
+        3 => (p, q, value),
+        4 => (t, p, value),
+        _ => (value, p, q),
+    };
+    let channel = |channel: f32| ((channel * 255.0) as i32).clamp(0, 255);
+    channel(red) << 16 | channel(green) << 8 | channel(blue)
+}
+
+fn chat_type(
+    name: &'static str,
+    id: i32,
+    chat: LoginRegistryCodecChatDecoration<'static>,
+    narration: LoginRegistryCodecChatDecoration<'static>,
+) -> LoginRegistryCodecValue<'static, LoginRegistryCodecChatType<'static>> {
+    LoginRegistryCodecValue { name: Cow::Borrowed(name), id, element: LoginRegistryCodecChatType { chat, narration } }
+}
+
+fn decoration(translation_key: &'static str, parameters: &'static [Cow<'static, str>]) -> LoginRegistryCodecChatDecoration<'static> {
+    LoginRegistryCodecChatDecoration {
+        translation_key: Cow::Borrowed(translation_key),
+        parameters: Cow::Borrowed(parameters),
+        style: None,
+    }
+}
+
+const SENDER_CONTENT: &'static [Cow<'static, str>] = &[Cow::Borrowed("sender"), Cow::Borrowed("content")];
+const TARGET_CONTENT: &'static [Cow<'static, str>] = &[Cow::Borrowed("target"), Cow::Borrowed("content")];
+const TARGET_SENDER_CONTENT: &'static [Cow<'static, str>] = &[Cow::Borrowed("target"), Cow::Borrowed("sender"), Cow::Borrowed("content")];
+
+pub fn chat_types() -> Vec<LoginRegistryCodecValue<'static, LoginRegistryCodecChatType<'static>>> {
+    let narration = || decoration("chat.type.text.narrate", SENDER_CONTENT);
+    let whisper = |translation_key, parameters| LoginRegistryCodecChatDecoration {
+        style: Some(LoginRegistryCodecChatStyle { color: Some(Cow::Borrowed("gray")), bold: None, italic: Some(true) }),
+        ..decoration(translation_key, parameters)
+    };
+    vec![
+        chat_type("minecraft:chat", 0, decoration("chat.type.text", SENDER_CONTENT), narration()),
+        chat_type("minecraft:say_command", 1, decoration("chat.type.announcement", SENDER_CONTENT), narration()),
+        chat_type("minecraft:msg_command_incoming", 2, whisper("commands.message.display.incoming", SENDER_CONTENT), narration()),
+        chat_type("minecraft:msg_command_outgoing", 3, whisper("commands.message.display.outgoing", TARGET_CONTENT), narration()),
+        chat_type("minecraft:team_msg_command_incoming", 4, decoration("chat.type.team.text", TARGET_SENDER_CONTENT), narration()),
+        chat_type("minecraft:team_msg_command_outgoing", 5, decoration("chat.type.team.sent", TARGET_SENDER_CONTENT), narration()),
+        chat_type("minecraft:emote_command", 6, decoration("chat.type.emote", SENDER_CONTENT), decoration("chat.type.emote", SENDER_CONTENT)),
+    ]
+}
+
+/// Ids of the fluid registry
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(i32)]
+pub enum Fluid {
+    Empty,
+    FlowingWater,
+    Water,
+    FlowingLava,
+    Lava,
+}
+
+/// Collects tags for [`UpdateTagsPS2C`], entries of the same tag are merged
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TagsBuilder {
+    registries: Vec<(&'static str, Vec<(String, Vec<i32>)>)>,
+}
+
+impl TagsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tag(&mut self, registry: &'static str, name: &str, entries: impl IntoIterator<Item=i32>) -> &mut Self {
+        let index = match self.registries.iter().position(|(other, _)| *other == registry) {
+            Some(index) => index,
+            None => {
+                self.registries.push((registry, Vec::new()));
+                self.registries.len() - 1
+            }
+        };
+        let tags = &mut self.registries[index].1;
+        let index = match tags.iter().position(|(other, _)| other == name) {
+            Some(index) => index,
+            None => {
+                tags.push((name.into(), Vec::new()));
+                tags.len() - 1
+            }
+        };
+        let tag = &mut tags[index].1;
+        for entry in entries {
+            if !tag.contains(&entry) {
+                tag.push(entry);
+            }
+        }
+        self
+    }
+
+    pub fn block_tag(&mut self, name: &str, blocks: &[Block]) -> &mut Self {
+        self.tag(BLOCK_TAGS, name, blocks.iter().map(|block| block.get_data().id as i32))
+    }
+
+    pub fn item_tag(&mut self, name: &str, items: &[&ItemData]) -> &mut Self {
+        self.tag(ITEM_TAGS, name, items.iter().map(|item| item.id as i32))
+    }
+
+    pub fn fluid_tag(&mut self, name: &str, fluids: &[Fluid]) -> &mut Self {
+        self.tag(FLUID_TAGS, name, fluids.iter().map(|fluid| *fluid as i32))
+    }
+
+    /// `bird_data` has no entity types so ids are raw
+    pub fn entity_type_tag(&mut self, name: &str, entity_types: &[i32]) -> &mut Self {
+        self.tag(ENTITY_TYPE_TAGS, name, entity_types.iter().copied())
+    }
+
+    /// Returns an error if some tag name is not an identifier
+    pub fn build(&self) -> anyhow::Result<UpdateTagsPS2C<'_>> {
+        let mut registries = Vec::with_capacity(self.registries.len());
+        for (registry, tags) in &self.registries {
+            let mut built = Vec::with_capacity(tags.len());
+            for (name, entries) in tags {
+                built.push(UpdateTagsTag {
+                    name: Identifier::new_full(Cow::Borrowed(name.as_str()))
+                        .ok_or_else(|| anyhow::Error::msg("Tag name must be an identifier"))?,
+                    entries: Cow::Borrowed(entries.as_slice()),
+                });
+            }
+            registries.push(UpdateTagsRegistry {
+                registry: Identifier::new_full(Cow::Borrowed(*registry)).unwrap(),
+                tags: Cow::Owned(built),
+            });
+        }
+        Ok(UpdateTagsPS2C { registries: Cow::Owned(registries) })
+    }
+}
+
+/// Tags which the client needs for the movement
+pub fn default_tags() -> TagsBuilder {
+    let mut builder = TagsBuilder::new();
+    builder
+        .fluid_tag("minecraft:water", &[Fluid::FlowingWater, Fluid::Water])
+        .fluid_tag("minecraft:lava", &[Fluid::FlowingLava, Fluid::Lava])
+        .block_tag("minecraft:climbable", &[Block::Ladder, Block::Vine, Block::Scaffolding]);
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nbt::{NbtElement, read_compound_enter, read_named_nbt_tag};
+    use super::*;
+
+    #[test]
+    fn registry_codec_test() {
+        let codec = default_registry_codec();
+        assert_eq!(codec.chat_type.value[crate::chat::CHAT_TYPE_CHAT as usize].name, "minecraft:chat");
+        assert!(codec.worldgen_biome.value.iter().any(|biome| biome.name == "minecraft:plains"));
+        assert_eq!(sky_color(0.8), 7907327);
+        let nbt = registry_codec_nbt(&codec).unwrap();
+        let mut cursor = nbt.as_slice();
+        read_compound_enter(&mut cursor).unwrap();
+        assert!(matches!(read_named_nbt_tag("minecraft:chat_type", &mut cursor).unwrap(), Some(NbtElement::Compound(_))));
+    }
+
+    #[test]
+    fn tags_builder_test() {
+        let mut builder = default_tags();
+        builder.fluid_tag("minecraft:water", &[Fluid::Water]);
+        builder.entity_type_tag("minecraft:skeletons", &[1, 2]);
+        let packet = builder.build().unwrap();
+        assert_eq!(packet.registries.len(), 3);
+        assert_eq!(packet.registries[0].tags[0].entries.as_ref(), [1, 2]);
+        assert_eq!(packet.registries[2].registry.to_string(), ENTITY_TYPE_TAGS);
+        assert!(TagsBuilder::new().tag(ITEM_TAGS, "Not An Identifier", [1]).build().is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use bird_protocol::anyhow;
+use crate::gamemode::{GameMode, GameModeConfig};
+use crate::generator::{from_level_type, ChunkGenerator};
+use crate::login::{ForwardingMode, ProfileProvider, ServerKey};
+use crate::protocol::SetCompressionLS2C;
+use crate::query::QueryConfig;
+use crate::rcon::{RconConfig, RCON_DEFAULT_PORT};
+use crate::status::ServerStatus;
+use crate::view_distance::{ViewDistanceConfig, MAX_VIEW_DISTANCE, MIN_VIEW_DISTANCE};
+
+pub const SERVER_PROPERTIES_FILE: &'static str = "server.properties";
+pub const DEFAULT_SERVER_PORT: u16 = 25565;
+const PROPERTIES_HEADER: &'static str = "#Minecraft server properties";
+
+/// Parses the `.properties` format of Java with comments, escapes and line continuations
+pub fn parse_properties(text: &str) -> BTreeMap<String, String> {
+    let mut properties = BTreeMap::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let mut line = line.trim_start().to_string();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        // Line ending with an odd number of backslashes continues on the next line
+        while line.chars().rev().take_while(|&symbol| symbol == '\\').count() % 2 == 1 {
+            line.pop();
+            match lines.next() {
+                Some(next) => line.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        let mut key_end = line.len();
+        let mut escaped = false;
+        for (index, symbol) in line.char_indices() {
+            match symbol {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '=' | ':' | ' ' | '\t' | '\x0C' => {
+                    key_end = index;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let (key, rest) = line.split_at(key_end);
+        let rest = rest.trim_start_matches(|symbol| symbol == ' ' || symbol == '\t' || symbol == '\x0C');
+        let rest = rest.strip_prefix(|symbol| symbol == '=' || symbol == ':').unwrap_or(rest);
+        let value = rest.trim_start_matches(|symbol| symbol == ' ' || symbol == '\t' || symbol == '\x0C');
+        properties.insert(unescape(key), unescape(value));
+    }
+    properties
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut units = Vec::new();
+    let mut chars = text.chars();
+    while let Some(symbol) = chars.next() {
+        let symbol = match symbol {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\x0C',
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    match u16::from_str_radix(&code, 16) {
+                        // Surrogate pairs are collected until the next regular char
+                        Ok(unit) => {
+                            units.push(unit);
+                            continue;
+                        }
+                        Err(_) => '\u{FFFD}',
+                    }
+                }
+                Some(symbol) => symbol,
+                None => break,
+            },
+            symbol => symbol,
+        };
+        result.extend(char::decode_utf16(units.drain(..)).map(|unit| unit.unwrap_or('\u{FFFD}')));
+        result.push(symbol);
+    }
+    result.extend(char::decode_utf16(units).map(|unit| unit.unwrap_or('\u{FFFD}')));
+    result
+}
+
+/// Escapes like `Properties.store` of Java, chars outside of ASCII are written as `\uXXXX`
+fn escape(text: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (index, symbol) in text.chars().enumerate() {
+        match symbol {
+            ' ' if is_key || index == 0 => result.push_str("\\ "),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\x0C' => result.push_str("\\f"),
+            '\\' | '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(symbol);
+            }
+            ' '..='~' => result.push(symbol),
+            other => {
+                let mut units = [0; 2];
+                for unit in other.encode_utf16(&mut units) {
+                    result.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    result
+}
+
+fn take<T: FromStr>(properties: &mut BTreeMap<String, String>, key: &str, default: T) -> anyhow::Result<T> {
+    match properties.remove(key) {
+        // Vanilla treats empty values as missing
+        Some(value) if value.trim().is_empty() => Ok(default),
+        Some(value) => value.trim().parse()
+            .map_err(|_| anyhow::Error::msg(format!("Invalid value {} of the property {}", value, key))),
+        None => Ok(default),
+    }
+}
+
+/// Booleans are parsed like `Boolean.parseBoolean` of Java, everything except `true` is false
+fn take_bool(properties: &mut BTreeMap<String, String>, key: &str, default: bool) -> bool {
+    match properties.remove(key) {
+        Some(value) if !value.trim().is_empty() => value.trim().eq_ignore_ascii_case("true"),
+        _ => default,
+    }
+}
+
+fn take_string(properties: &mut BTreeMap<String, String>, key: &str, default: &str) -> String {
+    properties.remove(key).unwrap_or_else(|| default.into())
+}
+
+/// Settings of `server.properties`, properties which are not known are kept as they are
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ServerConfig {
+    /// Empty if the server listens on all addresses
+    pub server_ip: String,
+    pub server_port: u16,
+    pub motd: String,
+    pub max_players: i32,
+    pub online_mode: bool,
+    pub view_distance: u8,
+    pub simulation_distance: u8,
+    /// Packets of this size and bigger are compressed, negative value disables the compression
+    pub network_compression_threshold: i32,
+    pub level_name: String,
+    pub gamemode: GameMode,
+    pub force_gamemode: bool,
+    pub white_list: bool,
+    pub enforce_secure_profile: bool,
+    pub enable_query: bool,
+    pub query_port: u16,
+    pub enable_rcon: bool,
+    pub rcon_port: u16,
+    pub rcon_password: String,
+    pub other: BTreeMap<String, String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            server_ip: String::new(),
+            server_port: DEFAULT_SERVER_PORT,
+            motd: "A Minecraft Server".into(),
+            max_players: 20,
+            online_mode: true,
+            view_distance: 10,
+            simulation_distance: 10,
+            network_compression_threshold: 256,
+            level_name: "world".into(),
+            gamemode: GameMode::Survival,
+            force_gamemode: false,
+            white_list: false,
+            enforce_secure_profile: true,
+            enable_query: false,
+            query_port: DEFAULT_SERVER_PORT,
+            enable_rcon: false,
+            rcon_port: RCON_DEFAULT_PORT,
+            rcon_password: String::new(),
+            other: BTreeMap::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Missing properties have vanilla defaults
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut properties = parse_properties(text);
+        let default = Self::default();
+        let gamemode = match properties.remove("gamemode") {
+            Some(value) if !value.trim().is_empty() => GameMode::from_name(value.trim())
+                .or_else(|| value.trim().parse().ok().and_then(GameMode::from_id))
+                .ok_or_else(|| anyhow::Error::msg(format!("Invalid gamemode {}", value)))?,
+            _ => default.gamemode,
+        };
+        let properties = &mut properties;
+        Ok(Self {
+            server_ip: take_string(properties, "server-ip", &default.server_ip),
+            server_port: take(properties, "server-port", default.server_port)?,
+            motd: take_string(properties, "motd", &default.motd),
+            max_players: take(properties, "max-players", default.max_players)?,
+            online_mode: take_bool(properties, "online-mode", default.online_mode),
+            view_distance: take(properties, "view-distance", default.view_distance)?,
+            simulation_distance: take(properties, "simulation-distance", default.simulation_distance)?,
+            network_compression_threshold: take(properties, "network-compression-threshold", default.network_compression_threshold)?,
+            level_name: take_string(properties, "level-name", &default.level_name),
+            gamemode,
+            force_gamemode: take_bool(properties, "force-gamemode", default.force_gamemode),
+            white_list: take_bool(properties, "white-list", default.white_list),
+            enforce_secure_profile: take_bool(properties, "enforce-secure-profile", default.enforce_secure_profile),
+            enable_query: take_bool(properties, "enable-query", default.enable_query),
+            query_port: take(properties, "query.port", default.query_port)?,
+            enable_rcon: take_bool(properties, "enable-rcon", default.enable_rcon),
+            rcon_port: take(properties, "rcon.port", default.rcon_port)?,
+            rcon_password: take_string(properties, "rcon.password", &default.rcon_password),
+            other: std::mem::take(properties),
+        })
+    }
+
+    /// Default settings if the file doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        Ok(std::fs::write(path, self.to_properties())?)
+    }
+
+    /// Properties are written sorted by their keys
+    pub fn to_properties(&self) -> String {
+        let mut properties = self.other.clone();
+        let known = [
+            ("server-ip", self.server_ip.clone()),
+            ("server-port", self.server_port.to_string()),
+            ("motd", self.motd.clone()),
+            ("max-players", self.max_players.to_string()),
+            ("online-mode", self.online_mode.to_string()),
+            ("view-distance", self.view_distance.to_string()),
+            ("simulation-distance", self.simulation_distance.to_string()),
+            ("network-compression-threshold", self.network_compression_threshold.to_string()),
+            ("level-name", self.level_name.clone()),
+            ("gamemode", self.gamemode.name().into()),
+            ("force-gamemode", self.force_gamemode.to_string()),
+            ("white-list", self.white_list.to_string()),
+            ("enforce-secure-profile", self.enforce_secure_profile.to_string()),
+            ("enable-query", self.enable_query.to_string()),
+            ("query.port", self.query_port.to_string()),
+            ("enable-rcon", self.enable_rcon.to_string()),
+            ("rcon.port", self.rcon_port.to_string()),
+            ("rcon.password", self.rcon_password.clone()),
+        ];
+        properties.extend(known.into_iter().map(|(key, value)| (key.to_string(), value)));
+        let mut text = String::from(PROPERTIES_HEADER);
+        text.push('\n');
+        for (key, value) in properties {
+            text.push_str(&format!("{}={}\n", escape(&key, true), escape(&value, false)));
+        }
+        text
+    }
+
+    /// Address of the game listener
+    pub fn bind_address(&self) -> anyhow::Result<SocketAddr> {
+        let ip = match self.server_ip.trim() {
+            "" => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            ip => ip.parse().map_err(|_| anyhow::Error::msg(format!("Invalid server-ip {}", ip)))?,
+        };
+        Ok(SocketAddr::new(ip, self.server_port))
+    }
+
+    /// `None` if the compression is disabled
+    pub fn compression_threshold(&self) -> Option<i32> {
+        (self.network_compression_threshold >= 0).then_some(self.network_compression_threshold)
+    }
+
+    /// Packet which is sent before the login success if the compression is enabled
+    pub fn set_compression(&self) -> Option<SetCompressionLS2C> {
+        self.compression_threshold().map(|threshold| SetCompressionLS2C { threshold })
+    }
+
+    /// Status of the server without players
+    pub fn status(&self) -> ServerStatus {
+        ServerStatus {
+            motd: self.motd.clone(),
+            max_players: self.max_players,
+            map: self.level_name.clone(),
+            enforces_secure_chat: self.enforce_secure_profile,
+            ..ServerStatus::default()
+        }
+    }
+
+    /// Players are authenticated with the key only in online mode, proxies authenticate them by themselves
+    pub fn profile_provider(&self, forwarding: &ForwardingMode, server_key: Option<Arc<ServerKey>>) -> Arc<dyn ProfileProvider> {
+        forwarding.provider(server_key.filter(|_| self.online_mode))
+    }
+
+    pub fn view_distance_config(&self) -> ViewDistanceConfig {
+        ViewDistanceConfig {
+            view_distance: self.view_distance.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE),
+            simulation_distance: self.simulation_distance.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE),
+            ..ViewDistanceConfig::default()
+        }
+    }
+
+    /// Generator of the `level-type` property which is kept in the other properties
+    pub fn chunk_generator(&self, section_count: usize) -> Arc<dyn ChunkGenerator> {
+        from_level_type(self.other.get("level-type").map_or("", String::as_str), section_count)
+    }
+
+    pub fn game_mode_config(&self) -> GameModeConfig {
+        GameModeConfig { default: self.gamemode, force_gamemode: self.force_gamemode, ..GameModeConfig::default() }
+    }
+
+    pub fn query_config(&self) -> anyhow::Result<Option<QueryConfig>> {
+        if !self.enable_query {
+            return Ok(None);
+        }
+        let bind_address = self.bind_address()?;
+        Ok(Some(QueryConfig {
+            bind_address: SocketAddr::new(bind_address.ip(), self.query_port),
+            server_ip: bind_address.ip().to_string(),
+            server_port: self.server_port,
+        }))
+    }
+
+    /// Vanilla doesn't start rcon without the password
+    pub fn rcon_config(&self) -> anyhow::Result<Option<RconConfig>> {
+        if !self.enable_rcon || self.rcon_password.is_empty() {
+            return Ok(None);
+        }
+        let mut config = RconConfig::new(self.rcon_password.clone());
+        config.bind_address = SocketAddr::new(self.bind_address()?.ip(), self.rcon_port);
+        Ok(Some(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties_test() {
+        let properties = parse_properties("# comment\n  ! other comment\nkey = value\nspaced\\ key:a\\=b\nlong=first \\\n    second\nempty\nmotd=\\u00A7aHello \\uD83D\\uDE00\n");
+        assert_eq!(properties["key"], "value");
+        assert_eq!(properties["spaced key"], "a=b");
+        assert_eq!(properties["long"], "first second");
+        assert_eq!(properties["empty"], "");
+        assert_eq!(properties["motd"], "\u{A7}aHello \u{1F600}");
+        assert_eq!(properties.len(), 5);
+        let escaped = escape("\u{A7}a a=b\u{1F600}", false);
+        assert_eq!(escaped, "\\u00A7a a\\=b\\uD83D\\uDE00");
+        assert_eq!(unescape(&escaped), "\u{A7}a a=b\u{1F600}");
+    }
+
+    #[test]
+    fn server_config_test() {
+        let text = "#Minecraft server properties\nserver-port=25570\nmotd=\\u00A7bBird\nmax-players=50\nonline-mode=false\n\
+            view-distance=40\nnetwork-compression-threshold=-1\ngamemode=1\nenable-rcon=true\nrcon.password=secret\nspawn-protection=16\n";
+        let config = ServerConfig::parse(text).unwrap();
+        assert_eq!(config.server_port, 25570);
+        assert_eq!(config.motd, "\u{A7}bBird");
+        assert!(!config.online_mode);
+        assert_eq!(config.gamemode, GameMode::Creative);
+        assert_eq!(config.compression_threshold(), None);
+        assert_eq!(config.view_distance_config().view_distance, MAX_VIEW_DISTANCE);
+        assert_eq!(config.other["spawn-protection"], "16");
+        assert_eq!(config.bind_address().unwrap(), SocketAddr::from(([0, 0, 0, 0], 25570)));
+        assert_eq!(config.rcon_config().unwrap().unwrap().bind_address.port(), RCON_DEFAULT_PORT);
+        assert_eq!(config.query_config().unwrap(), None);
+        let status = config.status();
+        assert_eq!((status.motd.as_str(), status.max_players), ("\u{A7}bBird", 50));
+        assert_eq!(ServerConfig::parse(&config.to_properties()).unwrap(), config);
+
+        assert_eq!(ServerConfig::parse("").unwrap(), ServerConfig::default());
+        assert_eq!(ServerConfig::default().set_compression(), Some(SetCompressionLS2C { threshold: 256 }));
+        assert!(ServerConfig::parse("max-players=many").is_err());
+        assert!(ServerConfig::parse("gamemode=hardcore").is_err());
+        assert!(ServerConfig { server_ip: "localhost".into(), ..ServerConfig::default() }.bind_address().is_err());
+    }
+}
@@ -0,0 +1,600 @@
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use bird_protocol::{anyhow, PacketBuffer, ProtocolPacket, ProtocolPacketBound, ProtocolPacketState, ProtocolVariantReadable, ProtocolVariantWritable, ProtocolWritable, ProtocolWriter, VarInt};
+use crate::login::{ForwardedPlayer, ForwardingMode, parse_bungee_address};
+use crate::protocol::{Handshake, HandshakeNextState};
+use crate::instrument::{trace_span, warn};
+use crate::version::PacketIdTable;
+use crate::version_gate::VersionGate;
+
+pub const FML_MARKER: &'static str = "FML";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ModLoader {
+    /// Forge mod loader, the number is the version of the FML handshake (1 for `\0FML\0`)
+    Forge(u8),
+    /// Unknown marker appended to the address
+    Other(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ModdedClientPolicy {
+    Reject,
+    #[default]
+    Ignore,
+    Log,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HandshakeAddress<'a> {
+    pub host: &'a str,
+    pub marker: Option<&'a str>,
+}
+
+impl<'a> HandshakeAddress<'a> {
+    pub fn parse(address: &'a str) -> Self {
+        match address.split_once('\0') {
+            Some((host, marker)) => Self {
+                host,
+                marker: match marker.trim_end_matches('\0') {
+                    "" => None,
+                    marker => Some(marker),
+                },
+            },
+            None => Self { host: address, marker: None },
+        }
+    }
+
+    pub fn mod_loader(&self) -> Option<ModLoader> {
+        self.marker.map(|marker| match marker.strip_prefix(FML_MARKER) {
+            Some("") => ModLoader::Forge(1),
+            Some(version) => match version.parse() {
+                Ok(version) => ModLoader::Forge(version),
+                Err(_) => ModLoader::Other(marker.into()),
+            },
+            None => ModLoader::Other(marker.into()),
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConnectionInfo {
+    pub protocol_version: i32,
+    pub host: String,
+    pub port: u16,
+    pub next_state: HandshakeNextState,
+    pub mod_loader: Option<ModLoader>,
+    /// Player information which is appended to the address by BungeeCord
+    pub forwarded: Option<ForwardedPlayer>,
+}
+
+impl ConnectionInfo {
+    /// Returns `None` if the gate rejected the version of the login, the disconnect is already sent
+    pub fn from_handshake<S: PacketSender>(
+        handshake: &Handshake, policy: ModdedClientPolicy, forwarding: &ForwardingMode, gate: &VersionGate, sender: &mut S,
+    ) -> anyhow::Result<Option<Self>> {
+        let _span = trace_span!("handshake", protocol_version = handshake.protocol_version, next_state = ?handshake.next_state);
+        if gate.handle_handshake(handshake, sender)?.is_none() {
+            return Ok(None);
+        }
+        let (server_address, forwarded) = match forwarding {
+            // Status requests are not forwarded by BungeeCord
+            ForwardingMode::BungeeCord if handshake.next_state == HandshakeNextState::Login => {
+                let (host, forwarded) = parse_bungee_address(handshake.server_address)?;
+                (host, Some(forwarded))
+            }
+            _ => (handshake.server_address, None),
+        };
+        let address = HandshakeAddress::parse(server_address);
+        let mod_loader = address.mod_loader();
+        if let Some(ref mod_loader) = mod_loader {
+            match policy {
+                ModdedClientPolicy::Reject => Err(anyhow::Error::msg("Modded clients are not allowed"))?,
+                ModdedClientPolicy::Ignore => {}
+                ModdedClientPolicy::Log => warn!("Modded client connected to {}: {:?}", address.host, mod_loader),
+            }
+        }
+        Ok(Some(Self {
+            protocol_version: handshake.protocol_version,
+            host: address.host.into(),
+            port: handshake.server_port,
+            next_state: handshake.next_state,
+            mod_loader,
+            forwarded,
+        }))
+    }
+
+    pub fn is_modded(&self) -> bool {
+        self.mod_loader.is_some()
+    }
+}
+
+pub fn write_packet<P: ProtocolPacket + ProtocolWritable, W: ProtocolWriter>(packet: &P, writer: &mut W) -> anyhow::Result<()> {
+    let _span = trace_span!("encode_packet", id = P::ID);
+    VarInt::write_variant(&P::ID, writer)?;
+    packet.write(writer)
+}
+
+/// Maximum size of the packet id
+const MAX_PACKET_ID_SIZE: usize = 5;
+
+/// Buffer is allocated once with the exact size of the packet
+pub fn encode_packet<P: ProtocolPacket + ProtocolWritable>(packet: &P) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(MAX_PACKET_ID_SIZE + packet.exact_size()?);
+    write_packet(packet, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Writes the packet with the id of the given version
+pub fn write_versioned_packet<P: ProtocolPacket + ProtocolWritable, W: ProtocolWriter>(
+    packet: &P,
+    table: &PacketIdTable,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    let id = table.packet_id::<P>()
+        .ok_or_else(|| anyhow::Error::msg("Packet doesn't exist in the protocol version"))?;
+    let _span = trace_span!("encode_packet", id);
+    VarInt::write_variant(&id, writer)?;
+    packet.write(writer)
+}
+
+/// Replaces the id of the encoded packet of the latest version with the id of the given version
+pub fn remap_encoded(bytes: &[u8], state: ProtocolPacketState, bound: ProtocolPacketBound, table: &PacketIdTable) -> anyhow::Result<Vec<u8>> {
+    let mut cursor = bytes;
+    let id: i32 = VarInt::read_variant(&mut cursor)?;
+    let id = table.id(state, bound, id)
+        .ok_or_else(|| anyhow::Error::msg("Packet doesn't exist in the protocol version"))?;
+    let mut remapped = Vec::with_capacity(bytes.len());
+    VarInt::write_variant(&id, &mut remapped)?;
+    remapped.extend_from_slice(cursor);
+    Ok(remapped)
+}
+
+pub trait PacketSender {
+    fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()>;
+
+    /// Sends the packet which is encoded once for many senders, the frame is copied only if it can't be shared
+    fn send_shared(&mut self, packet: &SharedEncodedPacket) -> anyhow::Result<()>;
+
+    /// Sends a packet which isn't modelled, like packets of snapshots or modded ones. The id is the id in the
+    /// state and version of the receiver, so it is never remapped
+    fn send_raw(&mut self, state: ProtocolPacketState, id: i32, body: &[u8]) -> anyhow::Result<()>;
+}
+
+/// Frame of the packet with the id and body, which are written as is
+pub fn raw_frame(id: i32, body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut frame = Vec::with_capacity(body.len() + 5);
+    VarInt::write_variant(&id, &mut frame)?;
+    frame.extend_from_slice(body);
+    Ok(frame)
+}
+
+/// Frames of this size and bigger are compressed by the connection, `None` disables the compression
+pub fn should_compress(frame_length: usize, threshold: Option<i32>) -> bool {
+    threshold.map_or(false, |threshold| frame_length >= threshold.max(0) as usize)
+}
+
+/// Zlib data of the frame without the length of the uncompressed data
+pub fn compress_frame(frame: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::with_capacity(frame.len() / 2), Compression::default());
+    encoder.write_all(frame)?;
+    Ok(encoder.finish()?)
+}
+
+/// Writes the frame with the length of the uncompressed data if the compression is enabled,
+/// `compressed` is the zlib data of the frame which is used if the frame is big enough
+fn write_frame_with_threshold(
+    buffer: &mut PacketBuffer,
+    frame: &[u8],
+    compressed: impl FnOnce() -> anyhow::Result<Arc<[u8]>>,
+    threshold: Option<i32>,
+) -> anyhow::Result<()> {
+    match (threshold, should_compress(frame.len(), threshold)) {
+        (None, _) => buffer.write_frame(|buffer| {
+            buffer.write_bytes(frame);
+            Ok(())
+        }),
+        (Some(_), false) => buffer.write_frame(|buffer| {
+            VarInt::write_variant(&0i32, buffer)?;
+            buffer.write_bytes(frame);
+            Ok(())
+        }),
+        (Some(_), true) => {
+            let compressed = compressed()?;
+            buffer.write_frame(|buffer| {
+                VarInt::write_variant(&(frame.len() as i32), buffer)?;
+                buffer.write_bytes(&compressed);
+                Ok(())
+            })
+        }
+    }
+}
+
+/// Packet which is encoded once for many connections. Queues share the bytes instead of copying them,
+/// and the packet is compressed once for all connections which compress it
+#[derive(Clone, Debug)]
+pub struct SharedEncodedPacket {
+    /// Id and body of the packet with the id of the latest version
+    frame: Arc<[u8]>,
+    compressed: Arc<OnceLock<Arc<[u8]>>>,
+}
+
+impl SharedEncodedPacket {
+    pub fn new<P: ProtocolPacket + ProtocolWritable>(packet: &P) -> anyhow::Result<Self> {
+        Ok(Self::from_frame(encode_packet(packet)?))
+    }
+
+    pub fn from_frame(frame: Vec<u8>) -> Self {
+        Self { frame: frame.into(), compressed: Arc::default() }
+    }
+
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Zlib data of the frame, it is compressed by the first connection which needs it
+    pub fn compressed(&self) -> anyhow::Result<Arc<[u8]>> {
+        if let Some(compressed) = self.compressed.get() {
+            return Ok(compressed.clone());
+        }
+        let compressed: Arc<[u8]> = compress_frame(&self.frame)?.into();
+        Ok(self.compressed.get_or_init(|| compressed).clone())
+    }
+
+    /// Writes the frame for the compression threshold of the connection
+    pub fn write_frame(&self, buffer: &mut PacketBuffer, threshold: Option<i32>) -> anyhow::Result<()> {
+        write_frame_with_threshold(buffer, &self.frame, || self.compressed(), threshold)
+    }
+}
+
+/// Encoded packet of data which is sent to many connections, like a chunk to its viewers. It is encoded
+/// when it is sent first, the owner of the data calls [`CachedPacket::invalidate`] when the data is changed
+#[derive(Clone, Debug, Default)]
+pub struct CachedPacket {
+    packet: OnceLock<SharedEncodedPacket>,
+}
+
+impl CachedPacket {
+    pub fn new() -> Self {
+        Self { packet: OnceLock::new() }
+    }
+
+    pub fn get(&self) -> Option<&SharedEncodedPacket> {
+        self.packet.get()
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.packet.get().is_some()
+    }
+
+    /// Returns the cached packet or caches the encoded one
+    pub fn get_or_encode(&self, encode: impl FnOnce() -> anyhow::Result<SharedEncodedPacket>) -> anyhow::Result<&SharedEncodedPacket> {
+        if let Some(packet) = self.packet.get() {
+            return Ok(packet);
+        }
+        let packet = encode()?;
+        Ok(self.packet.get_or_init(|| packet))
+    }
+
+    pub fn send<S: PacketSender>(
+        &self,
+        sender: &mut S,
+        encode: impl FnOnce() -> anyhow::Result<SharedEncodedPacket>,
+    ) -> anyhow::Result<()> {
+        sender.send_shared(self.get_or_encode(encode)?)
+    }
+
+    /// Drops the packet, queues which already have it keep the old frame
+    pub fn invalidate(&mut self) {
+        self.packet = OnceLock::new();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum QueuedPacket {
+    Owned(Vec<u8>),
+    Shared(SharedEncodedPacket),
+}
+
+impl QueuedPacket {
+    pub fn frame(&self) -> &[u8] {
+        match self {
+            QueuedPacket::Owned(frame) => frame,
+            QueuedPacket::Shared(shared) => shared.frame(),
+        }
+    }
+
+    fn write_frame(&self, buffer: &mut PacketBuffer, threshold: Option<i32>) -> anyhow::Result<()> {
+        match self {
+            QueuedPacket::Owned(frame) => write_frame_with_threshold(buffer, frame, || Ok(compress_frame(frame)?.into()), threshold),
+            QueuedPacket::Shared(shared) => shared.write_frame(buffer, threshold),
+        }
+    }
+}
+
+/// Encoded packets (id and body without length) which are waiting to be sent
+#[derive(Clone, Debug, Default)]
+pub struct PacketQueue {
+    pub packets: Vec<QueuedPacket>,
+    /// Ids of the latest version are used if it is not set
+    pub ids: Option<&'static PacketIdTable>,
+}
+
+impl PacketQueue {
+    pub fn new() -> Self {
+        Self { packets: Vec::new(), ids: None }
+    }
+
+    pub fn for_version(protocol_version: i32) -> anyhow::Result<Self> {
+        let ids = PacketIdTable::for_version(protocol_version)
+            .ok_or_else(|| anyhow::Error::msg("Protocol version is not supported"))?;
+        Ok(Self { packets: Vec::new(), ids: Some(ids) })
+    }
+
+    fn remap(&self, bytes: &[u8]) -> Option<anyhow::Result<Vec<u8>>> {
+        match self.ids {
+            Some(ids) if ids != PacketIdTable::latest() =>
+                Some(remap_encoded(bytes, ProtocolPacketState::Play, ProtocolPacketBound::Client, ids)),
+            _ => None,
+        }
+    }
+
+    /// Encoded packets are shared between players in the play state, so their ids are remapped
+    /// for the version of the queue. Packets which don't exist in the version are dropped
+    pub fn push_encoded(&mut self, bytes: Vec<u8>) {
+        match self.remap(&bytes) {
+            Some(Ok(bytes)) => self.packets.push(QueuedPacket::Owned(bytes)),
+            Some(Err(_)) => {}
+            None => self.packets.push(QueuedPacket::Owned(bytes)),
+        }
+    }
+
+    /// Like [`PacketQueue::push_encoded`], the bytes are copied only if the ids are remapped
+    pub fn push_shared(&mut self, packet: &SharedEncodedPacket) {
+        match self.remap(packet.frame()) {
+            Some(Ok(bytes)) => self.packets.push(QueuedPacket::Owned(bytes)),
+            Some(Err(_)) => {}
+            None => self.packets.push(QueuedPacket::Shared(packet.clone())),
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<Vec<u8>> {
+        self.packets.drain(..)
+            .map(|packet| match packet {
+                QueuedPacket::Owned(frame) => frame,
+                QueuedPacket::Shared(shared) => shared.frame().to_vec(),
+            })
+            .collect()
+    }
+
+    /// Moves the queued packets into the buffer as frames
+    pub fn write_frames(&mut self, buffer: &mut PacketBuffer) -> anyhow::Result<()> {
+        self.write_frames_with_threshold(buffer, None)
+    }
+
+    /// Frames are compressed for the threshold of the connection, shared packets are compressed once
+    pub fn write_frames_with_threshold(&mut self, buffer: &mut PacketBuffer, threshold: Option<i32>) -> anyhow::Result<()> {
+        for packet in self.packets.drain(..) {
+            packet.write_frame(buffer, threshold)?;
+        }
+        Ok(())
+    }
+}
+
+impl PacketSender for PacketQueue {
+    fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()> {
+        let bytes = match self.ids {
+            Some(ids) => {
+                let mut bytes = Vec::new();
+                write_versioned_packet(packet, ids, &mut bytes)?;
+                bytes
+            }
+            None => encode_packet(packet)?,
+        };
+        self.packets.push(QueuedPacket::Owned(bytes));
+        Ok(())
+    }
+
+    fn send_shared(&mut self, packet: &SharedEncodedPacket) -> anyhow::Result<()> {
+        self.push_shared(packet);
+        Ok(())
+    }
+
+    fn send_raw(&mut self, _state: ProtocolPacketState, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        self.packets.push(QueuedPacket::Owned(raw_frame(id, body)?));
+        Ok(())
+    }
+}
+
+/// Packets are framed directly in the buffer with ids of the latest version
+impl PacketSender for PacketBuffer {
+    fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()> {
+        self.write_frame(|buffer| write_packet(packet, buffer))
+    }
+
+    fn send_shared(&mut self, packet: &SharedEncodedPacket) -> anyhow::Result<()> {
+        packet.write_frame(self, None)
+    }
+
+    fn send_raw(&mut self, _state: ProtocolPacketState, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        self.write_frame(|buffer| {
+            VarInt::write_variant(&id, buffer)?;
+            buffer.write_bytes(body);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::version::TEST_TABLE;
+    use super::*;
+
+    #[test]
+    fn handshake_address_test() {
+        let vanilla = HandshakeAddress::parse("localhost");
+        assert_eq!(vanilla, HandshakeAddress { host: "localhost", marker: None });
+        assert_eq!(vanilla.mod_loader(), None);
+        assert_eq!(HandshakeAddress::parse("localhost\0FML\0").mod_loader(), Some(ModLoader::Forge(1)));
+        assert_eq!(HandshakeAddress::parse("localhost\0FML3\0").mod_loader(), Some(ModLoader::Forge(3)));
+        assert_eq!(
+            HandshakeAddress::parse("localhost\0FABRIC\0").mod_loader(),
+            Some(ModLoader::Other("FABRIC".into()))
+        );
+    }
+
+    #[test]
+    fn from_handshake_test() {
+        use crate::status::PROTOCOL_VERSION;
+
+        let gate = VersionGate::default();
+        let forwarding = ForwardingMode::None;
+        let mut queue = PacketQueue::new();
+        let mut from_handshake = |protocol_version, next_state| {
+            let handshake = Handshake { protocol_version, server_address: "localhost\0FML\0", server_port: 25565, next_state };
+            ConnectionInfo::from_handshake(&handshake, ModdedClientPolicy::Ignore, &forwarding, &gate, &mut queue).unwrap()
+        };
+        let info = from_handshake(PROTOCOL_VERSION, HandshakeNextState::Login).unwrap();
+        assert_eq!((info.host.as_str(), info.mod_loader), ("localhost", Some(ModLoader::Forge(1))));
+        assert!(from_handshake(47, HandshakeNextState::Status).is_some());
+        // Unsupported logins receive the disconnect of the gate
+        assert!(from_handshake(47, HandshakeNextState::Login).is_none());
+        assert_eq!(queue.take().len(), 1);
+        let handshake = Handshake { protocol_version: PROTOCOL_VERSION, server_address: "localhost\0FML\0", server_port: 25565, next_state: HandshakeNextState::Login };
+        assert!(ConnectionInfo::from_handshake(&handshake, ModdedClientPolicy::Reject, &forwarding, &gate, &mut queue).is_err());
+    }
+
+    #[test]
+    fn versioned_queue_test() {
+        use crate::protocol::UpdateTimePS2C;
+
+        let packet = UpdateTimePS2C { world_age: 0, time_of_day: 6000 };
+        let mut queue = PacketQueue { packets: Vec::new(), ids: Some(&TEST_TABLE) };
+        queue.send_packet(&packet).unwrap();
+        queue.push_encoded(encode_packet(&packet).unwrap());
+        let packets = queue.take();
+        assert_eq!(packets[0][0], 0x5B);
+        assert_eq!(packets[0], packets[1]);
+        assert!(PacketQueue::for_version(760).is_err());
+    }
+
+    #[test]
+    fn exact_size_test() {
+        use crate::protocol::SystemChatMessagePS2C;
+        use bird_chat::component::Component;
+
+        let packet = SystemChatMessagePS2C { content: Component::text("exact"), overlay: false };
+        let bytes = bird_protocol::write_exact_to_vec(&packet).unwrap();
+        assert_eq!(packet.exact_size().unwrap(), bytes.len());
+        assert_eq!(6000i64.exact_size().unwrap(), 8);
+    }
+
+    #[test]
+    fn shared_packet_test() {
+        use std::io::Read;
+        use flate2::read::ZlibDecoder;
+        use bird_chat::component::Component;
+        use bird_protocol::PacketBufferPool;
+        use crate::protocol::SystemChatMessagePS2C;
+        use crate::viewers::Viewers;
+
+        let packet = SystemChatMessagePS2C { content: Component::text("shared ".repeat(64)), overlay: false };
+        let shared = SharedEncodedPacket::new(&packet).unwrap();
+        let (mut first, mut second) = (PacketQueue::new(), PacketQueue::new());
+        Viewers::new([&mut first, &mut second]).send_shared(&shared).unwrap();
+        match (&first.packets[0], &second.packets[0]) {
+            (QueuedPacket::Shared(first), QueuedPacket::Shared(second)) => assert!(Arc::ptr_eq(&first.frame, &second.frame)),
+            _ => panic!("Shared packet is copied"),
+        }
+
+        let pool = PacketBufferPool::new(1, 4096);
+        let mut buffer = pool.acquire();
+        first.write_frames_with_threshold(&mut buffer, Some(256)).unwrap();
+        second.write_frames_with_threshold(&mut buffer, Some(4096)).unwrap();
+        // Frames without the packet length
+        let frames: Vec<_> = buffer.frames()
+            .map(|mut frame| {
+                let _length: i32 = VarInt::read_variant(&mut frame).unwrap();
+                frame.to_vec()
+            })
+            .collect();
+        let mut compressed = &frames[0][..];
+        let data_length: i32 = VarInt::read_variant(&mut compressed).unwrap();
+        assert_eq!(data_length as usize, shared.frame().len());
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(compressed).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, shared.frame());
+        assert_eq!(frames[1], [&[0], shared.frame()].concat());
+        assert!(Arc::ptr_eq(&shared.compressed().unwrap(), &shared.compressed().unwrap()));
+    }
+
+    #[test]
+    fn cached_packet_test() {
+        use crate::protocol::UpdateTimePS2C;
+
+        let mut cached = CachedPacket::new();
+        let mut encoded = 0;
+        let mut queue = PacketQueue::new();
+        for _ in 0..2 {
+            cached.send(&mut queue, || {
+                encoded += 1;
+                SharedEncodedPacket::new(&UpdateTimePS2C { world_age: 0, time_of_day: 6000 })
+            }).unwrap();
+        }
+        assert_eq!(encoded, 1);
+        assert!(cached.get_or_encode(|| Err(anyhow::Error::msg("encoded twice"))).is_ok());
+        cached.invalidate();
+        assert!(!cached.is_cached());
+        assert!(cached.send(&mut queue, || Err(anyhow::Error::msg("failed"))).is_err());
+        cached.send(&mut queue, || SharedEncodedPacket::new(&UpdateTimePS2C { world_age: 0, time_of_day: 0 })).unwrap();
+        let frames = queue.take();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], frames[1]);
+        assert_ne!(frames[1], frames[2]);
+    }
+
+    #[test]
+    fn raw_packet_test() {
+        use crate::viewers::Viewers;
+
+        // The test version moves the id 0x10, raw packets keep it
+        let mut queue = PacketQueue { packets: Vec::new(), ids: Some(&TEST_TABLE) };
+        queue.send_raw(ProtocolPacketState::Play, 0x10, &[1, 2]).unwrap();
+        queue.send_shared(&SharedEncodedPacket::from_frame(raw_frame(0x10, &[]).unwrap())).unwrap();
+        assert_eq!(queue.take(), vec![vec![0x10, 1, 2], vec![0x11]]);
+        let mut other = PacketQueue { packets: Vec::new(), ids: Some(&TEST_TABLE) };
+        Viewers::new([&mut queue, &mut other]).send_raw(ProtocolPacketState::Play, 0x10, &[3]).unwrap();
+        assert_eq!(queue.take(), vec![vec![0x10, 3]]);
+        assert_eq!(other.take(), vec![vec![0x10, 3]]);
+        let mut shared = PacketQueue::new();
+        shared.send_shared(&SharedEncodedPacket::from_frame(raw_frame(0x80, &[]).unwrap())).unwrap();
+        assert_eq!(shared.take(), vec![vec![0x80, 0x01]]);
+    }
+
+    #[test]
+    fn packet_buffer_test() {
+        use bird_protocol::PacketBufferPool;
+        use crate::protocol::UpdateTimePS2C;
+
+        let pool = PacketBufferPool::new(1, 1024);
+        let packet = UpdateTimePS2C { world_age: 0, time_of_day: 6000 };
+        let encoded = encode_packet(&packet).unwrap();
+        let mut buffer = pool.acquire();
+        buffer.send_packet(&packet).unwrap();
+        let mut queue = PacketQueue::new();
+        queue.send_packet(&packet).unwrap();
+        queue.write_frames(&mut buffer).unwrap();
+        assert!(buffer.write_frame(|_| Err(anyhow::Error::msg("failed"))).is_err());
+        let mut frame = vec![encoded.len() as u8];
+        frame.extend_from_slice(&encoded);
+        assert_eq!(buffer.frame_count(), 2);
+        assert_eq!(buffer.to_vec(), [frame.as_slice(), frame.as_slice()].concat());
+        buffer.consume(frame.len() + 1);
+        assert_eq!(buffer.frames().next(), Some(&frame[1..]));
+        pool.release(buffer);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.acquire().is_empty());
+    }
+}
@@ -0,0 +1,528 @@
+use euclid::default::Vector3D;
+use bird_chat::component::Component;
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::block_state::BlockState;
+use crate::connection::PacketSender;
+use crate::inventory::{can_stack, max_stack_size, Container, ContainerType, Inventory, InventoryView};
+use crate::menu::Ingredient;
+use crate::protocol::*;
+use crate::random::LegacyRandom;
+use crate::world::World;
+
+pub const FURNACE_INPUT_SLOT: usize = 0;
+pub const FURNACE_FUEL_SLOT: usize = 1;
+pub const FURNACE_RESULT_SLOT: usize = 2;
+pub const CAMPFIRE_SLOTS: usize = 4;
+
+/// Woods which burn, nether woods don't burn
+const FUEL_WOODS: &'static [&'static str] = &["oak", "spruce", "birch", "jungle", "acacia", "dark_oak", "mangrove"];
+
+/// Vanilla burn times in ticks
+const FUEL_ITEMS: &'static [(&'static str, u32)] = &[
+    ("lava_bucket", 20000),
+    ("coal_block", 16000),
+    ("dried_kelp_block", 4001),
+    ("blaze_rod", 2400),
+    ("coal", 1600),
+    ("charcoal", 1600),
+    ("note_block", 300),
+    ("bookshelf", 300),
+    ("lectern", 300),
+    ("jukebox", 300),
+    ("chest", 300),
+    ("trapped_chest", 300),
+    ("crafting_table", 300),
+    ("daylight_detector", 300),
+    ("bow", 300),
+    ("crossbow", 300),
+    ("fishing_rod", 300),
+    ("ladder", 300),
+    ("loom", 300),
+    ("barrel", 300),
+    ("cartography_table", 300),
+    ("fletching_table", 300),
+    ("smithing_table", 300),
+    ("composter", 300),
+    ("mangrove_roots", 300),
+    ("wooden_shovel", 200),
+    ("wooden_sword", 200),
+    ("wooden_hoe", 200),
+    ("wooden_axe", 200),
+    ("wooden_pickaxe", 200),
+    ("stick", 100),
+    ("bowl", 100),
+    ("dead_bush", 100),
+    ("azalea", 100),
+    ("flowering_azalea", 100),
+    ("mangrove_propagule", 100),
+    ("bamboo", 50),
+    ("scaffolding", 50),
+];
+
+/// Burn times of wooden items by the suffix after the wood
+const FUEL_WOOD_SUFFIXES: &'static [(&'static str, u32)] = &[
+    ("_log", 300),
+    ("_wood", 300),
+    ("_planks", 300),
+    ("_stairs", 300),
+    ("_trapdoor", 300),
+    ("_pressure_plate", 300),
+    ("_fence", 300),
+    ("_fence_gate", 300),
+    ("_boat", 1200),
+    ("_chest_boat", 1200),
+    ("_sign", 200),
+    ("_door", 200),
+    ("_slab", 150),
+    ("_button", 100),
+    ("_sapling", 100),
+];
+
+/// Burn time of the fuel in a furnace in ticks, `None` if the item is not a fuel
+pub fn burn_time(item_id: i32) -> Option<u32> {
+    let name = ItemData::from_id(item_id as u32)?.name;
+    if let Some((_, time)) = FUEL_ITEMS.iter().find(|(fuel, _)| *fuel == name) {
+        return Some(*time);
+    }
+    if name.ends_with("_wool") || name.ends_with("_banner") {
+        return Some(if name.ends_with("_wool") { 100 } else { 300 });
+    }
+    if name.ends_with("_carpet") && name != "moss_carpet" {
+        return Some(67);
+    }
+    let name = name.strip_prefix("stripped_").unwrap_or(name);
+    FUEL_WOODS.iter()
+        .filter_map(|wood| name.strip_prefix(wood))
+        .find_map(|suffix| FUEL_WOOD_SUFFIXES.iter().find(|(fuel, _)| *fuel == suffix))
+        .map(|(_, time)| *time)
+}
+
+/// Item which stays in the fuel slot after burning
+fn fuel_remainder(item_id: i32) -> Option<OwnedSlot> {
+    match ItemData::from_id(item_id as u32)?.name {
+        "lava_bucket" => ItemData::from_name("bucket").map(|bucket| OwnedSlot::new(bucket.id as i32, 1)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CookingKind {
+    Smelting,
+    Blasting,
+    Smoking,
+    CampfireCooking,
+}
+
+impl CookingKind {
+    /// Vanilla cooking time of recipes in ticks
+    pub const fn default_cooking_time(&self) -> u32 {
+        match self {
+            CookingKind::Smelting => 200,
+            CookingKind::Blasting | CookingKind::Smoking => 100,
+            CookingKind::CampfireCooking => 600,
+        }
+    }
+
+    /// Blast furnaces and smokers burn fuel twice as fast
+    pub const fn fuel_divisor(&self) -> u32 {
+        match self {
+            CookingKind::Smelting | CookingKind::CampfireCooking => 1,
+            CookingKind::Blasting | CookingKind::Smoking => 2,
+        }
+    }
+
+    pub const fn container_type(&self) -> Option<ContainerType> {
+        match self {
+            CookingKind::Smelting => Some(ContainerType::Furnace),
+            CookingKind::Blasting => Some(ContainerType::BlastFurnace),
+            CookingKind::Smoking => Some(ContainerType::Smoker),
+            CookingKind::CampfireCooking => None,
+        }
+    }
+
+    const fn block_name(&self) -> &'static str {
+        match self {
+            CookingKind::Smelting => "furnace",
+            CookingKind::Blasting => "blast_furnace",
+            CookingKind::Smoking => "smoker",
+            CookingKind::CampfireCooking => "campfire",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct CookingRecipe {
+    pub id: String,
+    pub kind: CookingKind,
+    pub ingredient: Ingredient,
+    pub result: i32,
+    pub count: i8,
+    pub experience: f32,
+    /// Ticks to cook one item
+    pub cooking_time: u32,
+}
+
+impl CookingRecipe {
+    pub fn new(id: &str, kind: CookingKind, ingredient: Ingredient, result: i32, experience: f32) -> Self {
+        Self { id: id.into(), kind, ingredient, result, count: 1, experience, cooking_time: kind.default_cooking_time() }
+    }
+}
+
+/// Recipes of furnaces and campfires
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CookingRecipes {
+    recipes: Vec<CookingRecipe>,
+}
+
+impl CookingRecipes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, recipe: CookingRecipe) -> &mut Self {
+        self.recipes.push(recipe);
+        self
+    }
+
+    pub fn recipes(&self) -> &[CookingRecipe] {
+        &self.recipes
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CookingRecipe> {
+        self.recipes.iter().find(|recipe| recipe.id == id)
+    }
+
+    pub fn find(&self, kind: CookingKind, input: &OwnedSlot) -> Option<&CookingRecipe> {
+        self.recipes.iter().find(|recipe| recipe.kind == kind && recipe.ingredient.test(Some(input)))
+    }
+}
+
+/// Furnace, blast furnace or smoker. Items are stored in the block entity, viewers get copies of them
+#[derive(Clone, PartialEq, Debug)]
+pub struct Furnace {
+    pub kind: CookingKind,
+    pub position: Vector3D<i32>,
+    pub inventory: Inventory,
+    /// Remaining ticks of the burning fuel
+    burn_time: u32,
+    /// Ticks of the whole fuel
+    burn_duration: u32,
+    cook_time: u32,
+    cook_time_total: u32,
+    experience: f32,
+}
+
+impl Furnace {
+    pub fn new(kind: CookingKind, position: Vector3D<i32>) -> Self {
+        Self {
+            kind,
+            position,
+            inventory: Inventory::new(3),
+            burn_time: 0,
+            burn_duration: 0,
+            cook_time: 0,
+            cook_time_total: kind.default_cooking_time(),
+            experience: 0.0,
+        }
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.burn_time > 0
+    }
+
+    pub fn cook_time(&self) -> u32 {
+        self.cook_time
+    }
+
+    pub fn experience(&self) -> f32 {
+        self.experience
+    }
+
+    /// Values of the container properties, the client draws the fire and the arrow by them
+    pub fn properties(&self) -> [(FurnaceProperty, i16); 4] {
+        let clamp = |value: u32| value.min(i16::MAX as u32) as i16;
+        [
+            (FurnaceProperty::FireIcon, clamp(self.burn_time)),
+            (FurnaceProperty::MaximumFuelBurnTime, clamp(self.burn_duration)),
+            (FurnaceProperty::ProgressArrow, clamp(self.cook_time)),
+            (FurnaceProperty::MaximumProgress, clamp(self.cook_time_total)),
+        ]
+    }
+
+    fn can_burn(&self, recipe: Option<&CookingRecipe>) -> bool {
+        let recipe = match (recipe, self.inventory.get(FURNACE_INPUT_SLOT)) {
+            (Some(recipe), Some(_)) => recipe,
+            _ => return false,
+        };
+        match self.inventory.get(FURNACE_RESULT_SLOT) {
+            Some(result) => {
+                result.item_id == recipe.result
+                    && can_stack(result, &OwnedSlot::new(recipe.result, 1))
+                    && result.item_count as i16 + recipe.count as i16 <= max_stack_size(result.item_id) as i16
+            }
+            None => true,
+        }
+    }
+
+    fn burn(&mut self, recipe: &CookingRecipe) {
+        if let Some(input) = self.inventory.get_mut(FURNACE_INPUT_SLOT) {
+            if let Some(item) = input {
+                item.item_count -= 1;
+                if item.item_count <= 0 {
+                    *input = None;
+                }
+            }
+        }
+        match self.inventory.get_mut(FURNACE_RESULT_SLOT) {
+            Some(Some(result)) => result.item_count += recipe.count,
+            Some(result) => *result = Some(OwnedSlot::new(recipe.result, recipe.count)),
+            None => {}
+        }
+        self.experience += recipe.experience;
+    }
+
+    /// Advances the furnace by one tick like vanilla, returns true if something is changed.
+    /// The `lit` state of the block is updated when the fuel is started or burnt out
+    pub fn tick<S: PacketSender>(&mut self, recipes: &CookingRecipes, world: &mut World, broadcast: &mut S) -> anyhow::Result<bool> {
+        let was_lit = self.is_lit();
+        let mut changed = false;
+        if self.is_lit() {
+            self.burn_time -= 1;
+        }
+        let recipe = self.inventory.get(FURNACE_INPUT_SLOT).and_then(|input| recipes.find(self.kind, input)).cloned();
+        let fuel = self.inventory.get(FURNACE_FUEL_SLOT).and_then(|fuel| burn_time(fuel.item_id).map(|time| (fuel.item_id, time)));
+        if self.is_lit() || (fuel.is_some() && self.inventory.get(FURNACE_INPUT_SLOT).is_some()) {
+            if !self.is_lit() && self.can_burn(recipe.as_ref()) {
+                if let Some((fuel_id, time)) = fuel {
+                    self.burn_time = time / self.kind.fuel_divisor();
+                    self.burn_duration = self.burn_time;
+                    changed = true;
+                    let slot = self.inventory.get_mut(FURNACE_FUEL_SLOT).unwrap();
+                    let item = slot.as_mut().unwrap();
+                    item.item_count -= 1;
+                    if item.item_count <= 0 {
+                        *slot = fuel_remainder(fuel_id);
+                    }
+                }
+            }
+            match recipe {
+                Some(ref recipe) if self.is_lit() && self.can_burn(Some(recipe)) => {
+                    self.cook_time_total = recipe.cooking_time;
+                    self.cook_time += 1;
+                    if self.cook_time >= self.cook_time_total {
+                        self.cook_time = 0;
+                        self.burn(recipe);
+                        changed = true;
+                    }
+                }
+                _ => self.cook_time = 0,
+            }
+        } else if self.cook_time > 0 {
+            // The progress goes back while the furnace is not burning
+            self.cook_time = self.cook_time.saturating_sub(2).min(self.cook_time_total);
+        }
+        if was_lit != self.is_lit() {
+            changed = true;
+            self.set_lit(world, self.is_lit(), broadcast)?;
+        }
+        Ok(changed)
+    }
+
+    fn set_lit<S: PacketSender>(&self, world: &mut World, lit: bool, broadcast: &mut S) -> anyhow::Result<()> {
+        let state = match world.get_block(self.position) {
+            Some(state) if state.data().name == self.kind.block_name() => state,
+            _ => BlockState::from_name(self.kind.block_name())
+                .ok_or_else(|| anyhow::Error::msg("Furnace doesn't have a state"))?,
+        };
+        let state = state.with("lit", if lit { "true" } else { "false" })?;
+        world.set_block(self.position, state);
+        broadcast.send_packet(&state.update_packet(self.position))
+    }
+
+    /// Experience of cooked items is given when the result is taken, the fraction is a chance of one more point
+    pub fn take_experience(&mut self, random: &mut LegacyRandom) -> i32 {
+        let experience = std::mem::take(&mut self.experience);
+        let whole = experience.floor();
+        let fraction = experience - whole;
+        whole as i32 + (fraction > 0.0 && random.next_float() < fraction) as i32
+    }
+
+    pub fn open<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<()> {
+        // Campfires are not containers
+        let ty = self.kind.container_type().ok_or_else(|| anyhow::Error::msg("Campfire can't be opened"))?;
+        let title = Component::translation(format!("container.{}", self.kind.block_name()), Vec::new());
+        let mut container = Container::new(ty, title);
+        for (index, item) in self.inventory.slots().iter().enumerate() {
+            container.inventory.set(index, item.clone());
+        }
+        view.open(container, sender)?;
+        self.send_properties(view, sender)
+    }
+
+    pub fn send_properties<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<()> {
+        for (property, value) in self.properties() {
+            view.set_property(property, value, sender)?;
+        }
+        Ok(())
+    }
+
+    /// Takes items from the opened container after the click of the viewer
+    pub fn update_from_view(&mut self, view: &InventoryView) {
+        if let Some(container) = view.container() {
+            for (index, item) in container.inventory.slots().iter().take(self.inventory.len()).enumerate() {
+                self.inventory.set(index, item.clone());
+            }
+        }
+    }
+
+    /// Sends slots which are different in the opened container and the properties
+    pub fn update_view<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<()> {
+        let changed: Vec<usize> = match view.container() {
+            Some(container) => (0..self.inventory.len())
+                .filter(|&index| container.inventory.get(index) != self.inventory.get(index))
+                .collect(),
+            None => return Ok(()),
+        };
+        for index in changed {
+            view.set_slot(index, self.inventory.get(index).cloned(), sender)?;
+        }
+        self.send_properties(view, sender)
+    }
+}
+
+/// Campfire or soul campfire, cooked items are dropped by the caller
+#[derive(Clone, PartialEq, Debug)]
+pub struct Campfire {
+    pub position: Vector3D<i32>,
+    pub lit: bool,
+    items: [Option<OwnedSlot>; CAMPFIRE_SLOTS],
+    progress: [u32; CAMPFIRE_SLOTS],
+    cooking_time: [u32; CAMPFIRE_SLOTS],
+}
+
+impl Campfire {
+    pub fn new(position: Vector3D<i32>) -> Self {
+        Self {
+            position,
+            lit: true,
+            items: Default::default(),
+            progress: [0; CAMPFIRE_SLOTS],
+            cooking_time: [0; CAMPFIRE_SLOTS],
+        }
+    }
+
+    pub fn items(&self) -> &[Option<OwnedSlot>; CAMPFIRE_SLOTS] {
+        &self.items
+    }
+
+    /// Places one item of the stack in a free slot. Returns the rest of the item,
+    /// the whole item is returned if it can't be cooked or the campfire is full
+    pub fn place_item(&mut self, recipes: &CookingRecipes, mut item: OwnedSlot) -> Option<OwnedSlot> {
+        let recipe = match recipes.find(CookingKind::CampfireCooking, &item) {
+            Some(recipe) => recipe,
+            None => return Some(item),
+        };
+        let index = match self.items.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => return Some(item),
+        };
+        let mut placed = item.clone();
+        placed.item_count = 1;
+        item.item_count -= 1;
+        self.items[index] = Some(placed);
+        self.progress[index] = 0;
+        self.cooking_time[index] = recipe.cooking_time;
+        match item.item_count > 0 {
+            true => Some(item),
+            false => None,
+        }
+    }
+
+    /// Advances cooking by one tick and returns cooked items, the progress goes back while the campfire is not lit
+    pub fn tick(&mut self, recipes: &CookingRecipes) -> Vec<OwnedSlot> {
+        let mut cooked = Vec::new();
+        for index in 0..CAMPFIRE_SLOTS {
+            let item = match self.items[index] {
+                Some(ref item) => item,
+                None => continue,
+            };
+            if !self.lit {
+                self.progress[index] = self.progress[index].saturating_sub(2);
+                continue;
+            }
+            self.progress[index] += 1;
+            if self.progress[index] < self.cooking_time[index] {
+                continue;
+            }
+            if let Some(recipe) = recipes.find(CookingKind::CampfireCooking, item) {
+                cooked.push(OwnedSlot::new(recipe.result, recipe.count));
+            }
+            self.items[index] = None;
+            self.progress[index] = 0;
+        }
+        cooked
+    }
+
+    /// Items which are dropped when the campfire is broken or extinguished by a shovel
+    pub fn take_items(&mut self) -> Vec<OwnedSlot> {
+        self.progress = [0; CAMPFIRE_SLOTS];
+        self.items.iter_mut().filter_map(Option::take).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::connection::PacketQueue;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn cooking_test() {
+        assert_eq!(burn_time(item("coal")), Some(1600));
+        assert_eq!(burn_time(item("stripped_oak_log")), Some(300));
+        assert_eq!(burn_time(item("birch_slab")), Some(150));
+        assert_eq!(burn_time(item("crimson_planks")), None);
+        assert_eq!(burn_time(item("stone")), None);
+
+        let mut recipes = CookingRecipes::new();
+        recipes
+            .add(CookingRecipe::new("iron_ingot_from_blasting", CookingKind::Blasting, Ingredient::of(&[item("raw_iron")]), item("iron_ingot"), 0.7))
+            .add(CookingRecipe::new("cooked_beef_from_campfire", CookingKind::CampfireCooking, Ingredient::of(&[item("beef")]), item("cooked_beef"), 0.35));
+
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let mut broadcast = PacketQueue::new();
+        let mut furnace = Furnace::new(CookingKind::Blasting, Vector3D::new(0, 1, 0));
+        furnace.inventory.set(FURNACE_INPUT_SLOT, Some(OwnedSlot::new(item("raw_iron"), 2)));
+        furnace.inventory.set(FURNACE_FUEL_SLOT, Some(OwnedSlot::new(item("lava_bucket"), 1)));
+        furnace.tick(&recipes, &mut world, &mut broadcast).unwrap();
+        assert!(furnace.is_lit());
+        assert_eq!(world.get_block(furnace.position).unwrap().get("lit"), Some("true"));
+        assert_eq!(furnace.inventory.get(FURNACE_FUEL_SLOT).map(|fuel| fuel.item_id), Some(item("bucket")));
+        assert_eq!(furnace.properties()[1], (FurnaceProperty::MaximumFuelBurnTime, 10000));
+        for _ in 1..200 {
+            furnace.tick(&recipes, &mut world, &mut broadcast).unwrap();
+        }
+        assert_eq!(furnace.inventory.get(FURNACE_RESULT_SLOT).map(|result| result.item_count), Some(2));
+        assert!(furnace.inventory.get(FURNACE_INPUT_SLOT).is_none());
+        assert!((1..=2).contains(&furnace.take_experience(&mut LegacyRandom::new(0))));
+        assert_eq!(furnace.experience(), 0.0);
+
+        let mut view = InventoryView::new();
+        let mut sender = PacketQueue::new();
+        furnace.open(&mut view, &mut sender).unwrap();
+        assert_eq!(view.container().unwrap().get_property(FurnaceProperty::MaximumProgress), Some(100));
+        furnace.inventory.set(FURNACE_RESULT_SLOT, None);
+        furnace.update_view(&mut view, &mut sender).unwrap();
+        assert!(view.container().unwrap().inventory.get(FURNACE_RESULT_SLOT).is_none());
+
+        let mut campfire = Campfire::new(Vector3D::new(1, 1, 0));
+        assert_eq!(campfire.place_item(&recipes, OwnedSlot::new(item("raw_iron"), 1)).map(|item| item.item_count), Some(1));
+        assert_eq!(campfire.place_item(&recipes, OwnedSlot::new(item("beef"), 2)).map(|item| item.item_count), Some(1));
+        let cooked: Vec<_> = (0..600).flat_map(|_| campfire.tick(&recipes)).collect();
+        assert_eq!(cooked, vec![OwnedSlot::new(item("cooked_beef"), 1)]);
+        assert!(campfire.take_items().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+pub const ENDER_PEARL_COOLDOWN: i32 = 20;
+pub const CHORUS_FRUIT_COOLDOWN: i32 = 20;
+/// Cooldown of a shield which is disabled by an axe
+pub const SHIELD_DISABLE_COOLDOWN: i32 = 100;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CooldownGroup {
+    Item(i32),
+    /// Group which is defined by a plugin with [`CooldownRegistry::register_group`]
+    Custom(String),
+}
+
+/// Registered custom group, all items of the group are cooling down together
+#[derive(Clone, PartialEq, Debug)]
+struct CustomGroup {
+    items: Vec<i32>,
+    ticks: i32,
+}
+
+/// Cooldowns which are started on use of items, shared between all players
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CooldownRegistry {
+    items: HashMap<i32, i32>,
+    groups: HashMap<String, CustomGroup>,
+}
+
+impl CooldownRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cooldowns of vanilla items which are started by the server
+    pub fn vanilla() -> Self {
+        let mut registry = Self::new();
+        for (name, ticks) in [("ender_pearl", ENDER_PEARL_COOLDOWN), ("chorus_fruit", CHORUS_FRUIT_COOLDOWN)] {
+            if let Some(item) = ItemData::from_name(name) {
+                registry.set_item_cooldown(item.id as i32, ticks);
+            }
+        }
+        registry
+    }
+
+    /// Cooldown which is started when the item is used, zero removes it
+    pub fn set_item_cooldown(&mut self, item_id: i32, ticks: i32) -> &mut Self {
+        match ticks > 0 {
+            true => self.items.insert(item_id, ticks),
+            false => self.items.remove(&item_id),
+        };
+        self
+    }
+
+    pub fn item_cooldown(&self, item_id: i32) -> Option<i32> {
+        self.items.get(&item_id).copied()
+    }
+
+    /// Defines a group of items which are cooling down together for the given ticks when one of them is used
+    pub fn register_group(&mut self, name: impl Into<String>, items: &[i32], ticks: i32) -> &mut Self {
+        self.groups.insert(name.into(), CustomGroup { items: items.to_vec(), ticks });
+        self
+    }
+
+    pub fn unregister_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    fn group_items<'a>(&'a self, group: &'a CooldownGroup) -> &'a [i32] {
+        match group {
+            CooldownGroup::Item(item_id) => std::slice::from_ref(item_id),
+            CooldownGroup::Custom(name) => self.groups.get(name)
+                .map(|group| group.items.as_slice())
+                .unwrap_or(&[]),
+        }
+    }
+
+    /// Returns true if the item or one of its groups is cooling down for the player
+    pub fn is_cooling_down(&self, player: &PlayerCooldowns, item_id: i32) -> bool {
+        player.remaining(&CooldownGroup::Item(item_id)) > 0 || self.groups.iter()
+            .filter(|(_, group)| group.items.contains(&item_id))
+            .any(|(name, _)| player.remaining(&CooldownGroup::Custom(name.clone())) > 0)
+    }
+
+    /// Starts the cooldown and sends it for every affected item
+    pub fn start<S: PacketSender>(&self, player: &mut PlayerCooldowns, group: CooldownGroup, ticks: i32, sender: &mut S) -> anyhow::Result<()> {
+        for item_id in self.group_items(&group) {
+            sender.send_packet(&SetCooldownPS2C { item_id: *item_id, cooldown_ticks: ticks })?;
+        }
+        player.active.insert(group, player.tick + ticks.max(0) as u64);
+        Ok(())
+    }
+
+    pub fn clear<S: PacketSender>(&self, player: &mut PlayerCooldowns, group: &CooldownGroup, sender: &mut S) -> anyhow::Result<()> {
+        if player.active.remove(group).is_none() {
+            return Ok(());
+        }
+        for item_id in self.group_items(group) {
+            sender.send_packet(&SetCooldownPS2C { item_id: *item_id, cooldown_ticks: 0 })?;
+        }
+        Ok(())
+    }
+
+    /// Called when the shield of the player is hit by an axe
+    pub fn disable_shield<S: PacketSender>(&self, player: &mut PlayerCooldowns, sender: &mut S) -> anyhow::Result<()> {
+        match ItemData::from_name("shield") {
+            Some(shield) => self.start(player, CooldownGroup::Item(shield.id as i32), SHIELD_DISABLE_COOLDOWN, sender),
+            None => Ok(()),
+        }
+    }
+
+    /// Must be called before the item is used, returns false if the use is cancelled because of the cooldown.
+    /// Otherwise the cooldowns of the item and its groups are started
+    pub fn use_item<S: PacketSender>(&self, player: &mut PlayerCooldowns, packet: &UseItemPC2S, item: Option<&OwnedSlot>, sender: &mut S) -> anyhow::Result<bool> {
+        let item_id = match item {
+            Some(item) => item.item_id,
+            None => return Ok(true),
+        };
+        if self.is_cooling_down(player, item_id) {
+            sender.send_packet(&AcknowledgeBlockChangePS2C { sequence_id: packet.sequence })?;
+            return Ok(false);
+        }
+        if let Some(ticks) = self.item_cooldown(item_id) {
+            self.start(player, CooldownGroup::Item(item_id), ticks, sender)?;
+        }
+        let groups: Vec<(String, i32)> = self.groups.iter()
+            .filter(|(_, group)| group.items.contains(&item_id))
+            .map(|(name, group)| (name.clone(), group.ticks))
+            .collect();
+        for (name, ticks) in groups {
+            self.start(player, CooldownGroup::Custom(name), ticks, sender)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Active cooldowns of one player, the client counts them down by itself
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PlayerCooldowns {
+    tick: u64,
+    /// Tick when the cooldown ends
+    active: HashMap<CooldownGroup, u64>,
+}
+
+impl PlayerCooldowns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ticks until the group is available again
+    pub fn remaining(&self, group: &CooldownGroup) -> i32 {
+        self.active.get(group)
+            .map(|end| end.saturating_sub(self.tick) as i32)
+            .unwrap_or(0)
+    }
+
+    pub fn tick(&mut self) {
+        self.tick += 1;
+        let tick = self.tick;
+        self.active.retain(|_, end| *end > tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn cooldown_test() {
+        let mut registry = CooldownRegistry::new();
+        registry.set_item_cooldown(1, 20)
+            .register_group("wands", &[2, 3], 10);
+        let mut player = PlayerCooldowns::new();
+        let mut sender = PacketQueue::new();
+        let packet = UseItemPC2S { hand: Hand::Main, sequence: 1 };
+        let item = |item_id| OwnedSlot { item_id, item_count: 1, nbt: Vec::new() };
+        assert!(registry.use_item(&mut player, &packet, Some(&item(1)), &mut sender).unwrap());
+        assert!(!registry.use_item(&mut player, &packet, Some(&item(1)), &mut sender).unwrap());
+        assert!(registry.use_item(&mut player, &packet, Some(&item(2)), &mut sender).unwrap());
+        assert!(registry.is_cooling_down(&player, 3));
+        // Item cooldown, rejection acknowledgment and two items of the group
+        assert_eq!(sender.take().len(), 4);
+        for _ in 0..10 {
+            player.tick();
+        }
+        assert!(!registry.is_cooling_down(&player, 3));
+        assert_eq!(player.remaining(&CooldownGroup::Item(1)), 10);
+    }
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::inventory::{can_stack, max_stack_size, ContainerType, Inventory, InventoryView, PLAYER_MAIN_START, PLAYER_OFFHAND_SLOT};
+use crate::menu::{CraftingRecipe, Ingredient, RecipeBook};
+use crate::protocol::*;
+use crate::recipes::{recipe_identifier, UnlockedRecipes};
+
+/// Result slot of the crafting table and of the player inventory
+pub const CRAFTING_RESULT_SLOT: usize = 0;
+pub const CRAFTING_GRID_START: usize = 1;
+/// Ingredients are taken from the main inventory and the hotbar
+const INGREDIENT_SLOTS: Range<usize> = PLAYER_MAIN_START..PLAYER_OFFHAND_SLOT;
+
+/// Square crafting grid of the current window, the crafting table or the 2x2 grid of the player inventory
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CraftingGrid {
+    pub width: usize,
+}
+
+impl CraftingGrid {
+    /// `None` if the opened container doesn't have a crafting grid
+    pub fn of(view: &InventoryView) -> Option<Self> {
+        match view.container() {
+            Some(container) if container.ty == ContainerType::Crafting => Some(Self { width: 3 }),
+            Some(_) => None,
+            None => Some(Self { width: 2 }),
+        }
+    }
+
+    pub fn slots(&self) -> Range<usize> {
+        CRAFTING_GRID_START..CRAFTING_GRID_START + self.width * self.width
+    }
+
+    fn inventory<'a>(&self, view: &'a InventoryView) -> &'a Inventory {
+        match view.container() {
+            Some(container) => &container.inventory,
+            None => &view.player,
+        }
+    }
+
+    fn inventory_mut<'a>(&self, view: &'a mut InventoryView) -> &'a mut Inventory {
+        match view.container().is_some() {
+            true => &mut view.container_mut().unwrap().inventory,
+            false => &mut view.player,
+        }
+    }
+
+    /// Items of the grid row by row
+    pub fn items(&self, view: &InventoryView) -> Vec<Option<OwnedSlot>> {
+        self.inventory(view).slots()[self.slots()].to_vec()
+    }
+
+    /// Puts the result of the matching recipe into the result slot, the caller syncs the slot
+    pub fn update_result(&self, view: &mut InventoryView, book: &RecipeBook) {
+        let result = book.crafting_for(&self.items(view), self.width).map(CraftingRecipe::result);
+        self.inventory_mut(view).set(CRAFTING_RESULT_SLOT, result);
+    }
+}
+
+/// Like vanilla, named and enchanted items are not taken by the recipe book
+fn is_plain(item: &OwnedSlot) -> bool {
+    item.nbt == EMPTY_NBT
+}
+
+/// Takes `amount` items for every cell from the copy of the inventory, `None` if there are not enough items
+fn take_ingredients(player: &Inventory, cells: &[Ingredient], amount: i8) -> Option<(Inventory, Vec<Option<OwnedSlot>>)> {
+    let mut player = player.clone();
+    let mut placed = Vec::with_capacity(cells.len());
+    for ingredient in cells {
+        if ingredient.items().is_empty() {
+            placed.push(None);
+            continue;
+        }
+        let total = |player: &Inventory, item: &OwnedSlot| -> i32 {
+            INGREDIENT_SLOTS.filter_map(|slot| player.get(slot))
+                .filter(|other| can_stack(item, other))
+                .map(|other| other.item_count as i32)
+                .sum()
+        };
+        let item = INGREDIENT_SLOTS.filter_map(|slot| player.get(slot))
+            .filter(|item| is_plain(item) && ingredient.test(Some(item)))
+            .find(|item| amount <= max_stack_size(item.item_id) && total(&player, item) >= amount as i32)?
+            .clone();
+        let mut remaining = amount;
+        for slot in INGREDIENT_SLOTS {
+            let stack = match player.get_mut(slot) {
+                Some(stack) => stack,
+                None => break,
+            };
+            if let Some(other) = stack.as_mut().filter(|other| can_stack(&item, other)) {
+                let taken = remaining.min(other.item_count);
+                other.item_count -= taken;
+                remaining -= taken;
+                if other.item_count <= 0 {
+                    *stack = None;
+                }
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        placed.push(Some(OwnedSlot { item_count: amount, ..item }));
+    }
+    Some((player, placed))
+}
+
+/// Handles the click on a recipe in the recipe book like vanilla. Items of the grid are moved back to the inventory,
+/// then ingredients for one more craft or for as many crafts as possible are put into the grid.
+/// If the player doesn't have the ingredients, the ghost recipe is shown instead.
+/// Returns false if the click is ignored
+pub fn place_recipe<S: PacketSender>(
+    view: &mut InventoryView,
+    packet: &PlaceRecipePC2S,
+    book: &RecipeBook,
+    unlocked: &UnlockedRecipes,
+    sender: &mut S,
+) -> anyhow::Result<bool> {
+    let current_window = match view.container() {
+        Some(_) => view.window_id(),
+        None => PLAYER_INVENTORY_ID,
+    };
+    let grid = match CraftingGrid::of(view) {
+        Some(grid) if packet.window_id as u8 == current_window => grid,
+        _ => return Ok(false),
+    };
+    let recipe = book.crafting_recipes().iter()
+        .find(|recipe| recipe_identifier(recipe.id()).map_or(false, |id| id == packet.recipe));
+    let recipe = match recipe {
+        Some(recipe) if unlocked.is_unlocked(recipe.id()) && recipe.fits(grid.width) => recipe,
+        _ => return Ok(false),
+    };
+    let items = grid.items(view);
+    let crafted = match recipe.matches(&items, grid.width) {
+        true => items.iter().flatten().map(|item| item.item_count).min().unwrap_or(0),
+        false => 0,
+    };
+    // Nothing happens if the grid can't be cleared
+    let mut player = view.player.clone();
+    for item in items.into_iter().flatten() {
+        if player.add_item(item, INGREDIENT_SLOTS).is_some() {
+            view.sync(sender)?;
+            return Ok(false);
+        }
+    }
+    let cells = recipe.cells(grid.width);
+    let desired = match packet.make_all {
+        true => i8::MAX,
+        false => crafted.saturating_add(1),
+    };
+    let taken = (1..=desired).rev().find_map(|amount| take_ingredients(&player, &cells, amount));
+    let placed = match taken {
+        Some((taken_from, placed)) => {
+            player = taken_from;
+            placed
+        }
+        None => {
+            sender.send_packet(&PlaceGhostRecipePS2C { window_id: packet.window_id, recipe: packet.recipe.clone() })?;
+            vec![None; cells.len()]
+        }
+    };
+    view.player = player;
+    let inventory = grid.inventory_mut(view);
+    for (slot, item) in grid.slots().zip(placed) {
+        inventory.set(slot, item);
+    }
+    grid.update_result(view, book);
+    view.sync(sender)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_chat::component::Component;
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::inventory::Container;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn place_recipe_test() {
+        let planks = Ingredient::of(&[item("oak_planks"), item("birch_planks")]);
+        let mut book = RecipeBook::new();
+        book
+            .add_crafting(CraftingRecipe::Shaped {
+                id: "stick".into(),
+                width: 1,
+                height: 2,
+                ingredients: vec![planks.clone(), planks.clone()],
+                result: OwnedSlot::new(item("stick"), 4),
+            })
+            .add_crafting(CraftingRecipe::Shaped {
+                id: "custom:chest".into(),
+                width: 3,
+                height: 3,
+                ingredients: vec![planks.clone(), planks.clone(), planks.clone(), planks.clone(), Ingredient::empty(), planks.clone(), planks.clone(), planks.clone(), planks],
+                result: OwnedSlot::new(item("chest"), 1),
+            });
+        let mut unlocked = UnlockedRecipes::new();
+        let mut sender = PacketQueue::new();
+        unlocked.unlock(&["stick", "custom:chest"], &mut sender).unwrap();
+        let mut view = InventoryView::new();
+        view.player.set(PLAYER_MAIN_START, Some(OwnedSlot::new(item("oak_planks"), 3)));
+        view.player.set(PLAYER_MAIN_START + 1, Some(OwnedSlot::new(item("oak_planks"), 2)));
+        let packet = |recipe: &'static str, make_all| PlaceRecipePC2S {
+            window_id: 0,
+            recipe: recipe_identifier(recipe).unwrap(),
+            make_all,
+        };
+
+        // The chest doesn't fit into the inventory grid
+        assert!(!place_recipe(&mut view, &packet("custom:chest", false), &book, &unlocked, &mut sender).unwrap());
+        assert!(place_recipe(&mut view, &packet("minecraft:stick", false), &book, &unlocked, &mut sender).unwrap());
+        let grid = CraftingGrid::of(&view).unwrap();
+        assert_eq!(grid.items(&view)[0], Some(OwnedSlot::new(item("oak_planks"), 1)));
+        assert_eq!(grid.items(&view)[2], Some(OwnedSlot::new(item("oak_planks"), 1)));
+        assert_eq!(view.player.get(CRAFTING_RESULT_SLOT), Some(&OwnedSlot::new(item("stick"), 4)));
+        // Clicking again adds one more craft
+        assert!(place_recipe(&mut view, &packet("stick", false), &book, &unlocked, &mut sender).unwrap());
+        assert_eq!(grid.items(&view)[0].as_ref().map(|item| item.item_count), Some(2));
+        // Only 5 planks, so two crafts at most
+        assert!(place_recipe(&mut view, &packet("stick", true), &book, &unlocked, &mut sender).unwrap());
+        assert_eq!(grid.items(&view)[2].as_ref().map(|item| item.item_count), Some(2));
+        assert!(view.player.get(PLAYER_MAIN_START).is_none());
+        assert_eq!(view.player.get(PLAYER_MAIN_START + 1).map(|item| item.item_count), Some(1));
+        sender.take();
+
+        let mut table = Container::new(ContainerType::Crafting, Component::text("Crafting"));
+        table.inventory.set(CRAFTING_GRID_START + 4, Some(OwnedSlot::new(item("stone"), 1)));
+        view.open(table, &mut sender).unwrap();
+        let packet = PlaceRecipePC2S { window_id: view.window_id() as i8, ..packet("custom:chest", false) };
+        assert!(place_recipe(&mut view, &packet, &book, &unlocked, &mut sender).unwrap());
+        // Not enough planks, the stone is moved back and the ghost recipe is shown
+        let grid = CraftingGrid::of(&view).unwrap();
+        assert!(grid.items(&view).iter().all(Option::is_none));
+        assert_eq!(view.player.get(PLAYER_MAIN_START), Some(&OwnedSlot::new(item("stone"), 1)));
+        let ghost = sender.take().iter().any(|bytes| matches!(
+            Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut bytes.as_slice()),
+            Ok(Some(Packet::PlayS2C(PlayS2CPacket::PlaceGhostRecipe(_))))
+        ));
+        assert!(ghost);
+    }
+}
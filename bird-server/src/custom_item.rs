@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use fastnbt::Value;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use bird_protocol::anyhow;
+use crate::item_container::{read_nbt, write_nbt, Compound};
+use crate::protocol::*;
+
+/// The resource pack selects the model of the item by this number
+pub const CUSTOM_MODEL_DATA_TAG: &'static str = "CustomModelData";
+/// Compound with the name and the lore of the item
+pub const DISPLAY_TAG: &'static str = "display";
+/// Key of the custom item, the client ignores unknown tags
+pub const CUSTOM_ITEM_TAG: &'static str = "BirdCustomItem";
+const NAME_TAG: &'static str = "Name";
+const LORE_TAG: &'static str = "Lore";
+/// Components which are kept when the item is turned into another one
+const PRESERVED_TAGS: &'static [&'static str] = &[CUSTOM_MODEL_DATA_TAG, DISPLAY_TAG, CUSTOM_ITEM_TAG];
+
+pub fn custom_model_data(item: &OwnedSlot) -> anyhow::Result<Option<i32>> {
+    match read_nbt(&item.nbt)?.get(CUSTOM_MODEL_DATA_TAG) {
+        Some(Value::Int(data)) => Ok(Some(*data)),
+        _ => Ok(None),
+    }
+}
+
+/// Key of the custom item which was created by [`CustomItem::create`]
+pub fn custom_item_key(item: &OwnedSlot) -> Option<String> {
+    match read_nbt(&item.nbt).ok()?.remove(CUSTOM_ITEM_TAG) {
+        Some(Value::String(key)) => Some(key),
+        _ => None,
+    }
+}
+
+/// Copies the custom model data, the display and the custom item key of the source into the result,
+/// components which are already set in the result are not replaced.
+/// Used when the server creates a new item from another one, like repairs and loot functions
+pub fn carry_components(source: &OwnedSlot, result: &mut OwnedSlot) -> anyhow::Result<()> {
+    let source = read_nbt(&source.nbt)?;
+    let mut nbt = read_nbt(&result.nbt)?;
+    let mut changed = false;
+    for &tag in PRESERVED_TAGS {
+        if let Some(value) = source.get(tag).filter(|_| !nbt.contains_key(tag)) {
+            nbt.insert(tag.into(), value.clone());
+            changed = true;
+        }
+    }
+    if changed {
+        result.nbt = write_nbt(&nbt)?;
+    }
+    Ok(())
+}
+
+/// Item which is a vanilla base item with the nbt template, it is identified by the namespaced key
+#[derive(Clone, PartialEq, Debug)]
+pub struct CustomItem {
+    key: String,
+    base: i32,
+    custom_model_data: Option<i32>,
+    name: Option<Component>,
+    lore: Vec<Component>,
+    template: Compound,
+}
+
+impl CustomItem {
+    /// The key should contain the namespace, like `bird:ruby`
+    pub fn new(key: &str, base: i32) -> anyhow::Result<Self> {
+        Identifier::new_full(Cow::Borrowed(key))
+            .ok_or_else(|| anyhow::Error::msg(format!("{} is not a namespaced key", key)))?;
+        Ok(Self {
+            key: key.into(),
+            base,
+            custom_model_data: None,
+            name: None,
+            lore: Vec::new(),
+            template: Compound::new(),
+        })
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn base(&self) -> i32 {
+        self.base
+    }
+
+    pub fn custom_model_data(&mut self, data: i32) -> &mut Self {
+        self.custom_model_data = Some(data);
+        self
+    }
+
+    pub fn name(&mut self, name: Component) -> &mut Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn lore(&mut self, lore: Vec<Component>) -> &mut Self {
+        self.lore = lore;
+        self
+    }
+
+    /// Any other tag of the item, like enchantments or attribute modifiers
+    pub fn tag(&mut self, name: &str, value: Value) -> &mut Self {
+        self.template.insert(name.into(), value);
+        self
+    }
+
+    fn nbt(&self) -> anyhow::Result<Compound> {
+        let mut nbt = self.template.clone();
+        nbt.insert(CUSTOM_ITEM_TAG.into(), Value::String(self.key.clone()));
+        if let Some(data) = self.custom_model_data {
+            nbt.insert(CUSTOM_MODEL_DATA_TAG.into(), Value::Int(data));
+        }
+        let mut display = match nbt.remove(DISPLAY_TAG) {
+            Some(Value::Compound(display)) => display,
+            _ => Compound::new(),
+        };
+        if let Some(name) = &self.name {
+            display.insert(NAME_TAG.into(), Value::String(serde_json::to_string(name)?));
+        }
+        if !self.lore.is_empty() {
+            let lore = self.lore.iter()
+                .map(|line| serde_json::to_string(line).map(Value::String))
+                .collect::<Result<_, _>>()?;
+            display.insert(LORE_TAG.into(), Value::List(lore));
+        }
+        if !display.is_empty() {
+            nbt.insert(DISPLAY_TAG.into(), Value::Compound(display));
+        }
+        Ok(nbt)
+    }
+
+    pub fn create(&self, item_count: i8) -> anyhow::Result<OwnedSlot> {
+        Ok(OwnedSlot { item_id: self.base, item_count, nbt: write_nbt(&self.nbt()?)? })
+    }
+}
+
+/// Custom items of the server by their keys
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CustomItems {
+    items: HashMap<String, CustomItem>,
+}
+
+impl CustomItems {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, item: CustomItem) -> anyhow::Result<()> {
+        match self.items.contains_key(&item.key) {
+            true => Err(anyhow::Error::msg(format!("Custom item {} is already registered", item.key))),
+            false => {
+                self.items.insert(item.key.clone(), item);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CustomItem> {
+        self.items.get(key)
+    }
+
+    pub fn create(&self, key: &str, item_count: i8) -> anyhow::Result<OwnedSlot> {
+        self.get(key)
+            .ok_or_else(|| anyhow::Error::msg(format!("Custom item {} doesn't exist", key)))?
+            .create(item_count)
+    }
+
+    /// The custom item of the stack, `None` for vanilla items and items of unknown keys
+    pub fn identify(&self, item: &OwnedSlot) -> Option<&CustomItem> {
+        custom_item_key(item)
+            .and_then(|key| self.get(&key))
+            .filter(|custom| custom.base == item.item_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::menu::{CraftingRecipe, Ingredient, RecipeBook, SmithingRecipe};
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn custom_item_test() {
+        assert!(CustomItem::new("ruby", item("emerald")).is_err());
+        let mut ruby = CustomItem::new("bird:ruby", item("emerald")).unwrap();
+        ruby.custom_model_data(7).name(Component::text("Ruby"));
+        let mut items = CustomItems::new();
+        items.register(ruby.clone()).unwrap();
+        assert!(items.register(ruby).is_err());
+        let stack = items.create("bird:ruby", 3).unwrap();
+        assert_eq!(stack.item_count, 3);
+        assert_eq!(custom_model_data(&stack).unwrap(), Some(7));
+        assert_eq!(items.identify(&stack).map(CustomItem::key), Some("bird:ruby"));
+        assert!(items.identify(&OwnedSlot::new(item("emerald"), 1)).is_none());
+        assert!(items.identify(&OwnedSlot { item_id: item("diamond"), ..stack.clone() }).is_none());
+
+        // Crafted and transformed items keep the components
+        let mut book = RecipeBook::new();
+        book.add_crafting(CraftingRecipe::Shapeless {
+            id: "bird:ruby".into(),
+            ingredients: vec![Ingredient::of(&[item("redstone")]), Ingredient::of(&[item("emerald")])],
+            result: items.create("bird:ruby", 1).unwrap(),
+        });
+        let grid = [Some(OwnedSlot::new(item("emerald"), 1)), Some(OwnedSlot::new(item("redstone"), 1))];
+        let crafted = book.crafting_for(&grid, 2).unwrap().result();
+        assert_eq!(items.identify(&crafted).map(CustomItem::key), Some("bird:ruby"));
+        let upgrade = SmithingRecipe::Transform {
+            id: "bird:ruby_block".into(),
+            template: Ingredient::empty(),
+            base: Ingredient::of(&[item("emerald")]),
+            addition: Ingredient::of(&[item("redstone")]),
+            result: item("emerald_block"),
+        };
+        let upgraded = upgrade.assemble(None, &crafted, Some(&OwnedSlot::new(item("redstone"), 1))).unwrap().unwrap();
+        assert_eq!(custom_model_data(&upgraded).unwrap(), Some(7));
+
+        let mut result = OwnedSlot::new(item("diamond"), 1);
+        carry_components(&stack, &mut result).unwrap();
+        assert_eq!(custom_model_data(&result).unwrap(), Some(7));
+        assert_eq!(custom_item_key(&result).as_deref(), Some("bird:ruby"));
+        let mut named = CustomItem::new("bird:gem", item("diamond")).unwrap().custom_model_data(1).create(1).unwrap();
+        carry_components(&stack, &mut named).unwrap();
+        assert_eq!(custom_model_data(&named).unwrap(), Some(1));
+        assert_eq!(custom_item_key(&named).as_deref(), Some("bird:gem"));
+    }
+}
@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+use euclid::default::Vector3D;
+use fastnbt::{IntArray, Value};
+use bird_chat::identifier::Identifier;
+use bird_data::ItemData;
+use crate::gamemode::{GameMode, PlayerGameMode};
+use crate::item_container::Compound;
+use crate::protocol::*;
+
+/// Tags of the playerdata compound of vanilla
+pub const LAST_DEATH_LOCATION_TAG: &'static str = "LastDeathLocation";
+const DIMENSION_TAG: &'static str = "dimension";
+const POS_TAG: &'static str = "pos";
+pub const RECOVERY_COMPASS: &'static str = "recovery_compass";
+
+/// Last death location of the player, it is saved in the playerdata and sent in the login and respawn packets.
+/// The client uses it for the recovery compass, so the compass spins if it is not sent
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DeathLocation {
+    pub dimension: String,
+    pub position: Vector3D<i32>,
+}
+
+impl DeathLocation {
+    /// Location of the block where the player died
+    pub fn new(dimension: &str, position: Vector3D<f64>) -> Self {
+        Self { dimension: dimension.into(), position: position.floor().cast() }
+    }
+
+    /// `None` if the player has never died or the location is malformed
+    pub fn read(compound: &Compound) -> Option<Self> {
+        let location = match compound.get(LAST_DEATH_LOCATION_TAG) {
+            Some(Value::Compound(location)) => location,
+            _ => return None,
+        };
+        let dimension = match location.get(DIMENSION_TAG) {
+            Some(Value::String(dimension)) => dimension.clone(),
+            _ => return None,
+        };
+        match location.get(POS_TAG) {
+            Some(Value::IntArray(pos)) if pos.len() == 3 =>
+                Some(Self { dimension, position: Vector3D::new(pos[0], pos[1], pos[2]) }),
+            _ => None,
+        }
+    }
+
+    pub fn write(&self, compound: &mut Compound) {
+        let mut location = Compound::new();
+        location.insert(DIMENSION_TAG.into(), Value::String(self.dimension.clone()));
+        location.insert(POS_TAG.into(), Value::IntArray(IntArray::new(vec![self.position.x, self.position.y, self.position.z])));
+        compound.insert(LAST_DEATH_LOCATION_TAG.into(), Value::Compound(location));
+    }
+
+    /// Field of [`LoginPS2C`] and [`RespawnPS2C`], `None` if the dimension is not an identifier
+    pub fn login(&self) -> Option<LoginDeathLocation<'_>> {
+        Some(LoginDeathLocation {
+            dimension_name: Identifier::new_full(Cow::Borrowed(self.dimension.as_str()))?,
+            location: self.position,
+        })
+    }
+
+    /// Block where the recovery compass points, `None` if the item is not a recovery compass
+    /// or the player is in another dimension
+    pub fn recovery_compass_target(&self, item: &OwnedSlot, dimension: &str) -> Option<Vector3D<i32>> {
+        match is_recovery_compass(item) && self.dimension == dimension {
+            true => Some(self.position),
+            false => None,
+        }
+    }
+}
+
+pub fn is_recovery_compass(item: &OwnedSlot) -> bool {
+    ItemData::from_name(RECOVERY_COMPASS).map_or(false, |data| data.id as i32 == item.item_id)
+}
+
+/// Respawn packet which is sent after the death, the metadata of the player is reset
+pub fn respawn_after_death<'a>(
+    dimension_type: Identifier<'a>,
+    dimension_name: Identifier<'a>,
+    hashed_seed: i64,
+    game_mode: PlayerGameMode,
+    death: Option<&'a DeathLocation>,
+) -> RespawnPS2C<'a> {
+    RespawnPS2C {
+        dimension_type,
+        dimension_name,
+        hashed_seed,
+        game_mode: game_mode.current.login(),
+        previous_game_mode: GameMode::previous_login(game_mode.previous),
+        is_debug: false,
+        is_flat: false,
+        copy_metadata: false,
+        death_location: death.and_then(DeathLocation::login),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::encode_packet;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn death_location_test() {
+        let mut compound = Compound::new();
+        assert!(DeathLocation::read(&compound).is_none());
+        let death = DeathLocation::new("minecraft:the_nether", Vector3D::new(10.5, 64.0, -0.5));
+        assert_eq!(death.position, Vector3D::new(10, 64, -1));
+        death.write(&mut compound);
+        assert_eq!(DeathLocation::read(&compound), Some(death.clone()));
+        assert!(DeathLocation { dimension: "Bad Name".into(), position: Vector3D::zero() }.login().is_none());
+
+        let compass = OwnedSlot::new(item(RECOVERY_COMPASS), 1);
+        assert_eq!(death.recovery_compass_target(&compass, "minecraft:the_nether"), Some(death.position));
+        assert_eq!(death.recovery_compass_target(&compass, "minecraft:overworld"), None);
+        let compass_item = item("compass");
+        assert_eq!(death.recovery_compass_target(&OwnedSlot::new(compass_item, 1), "minecraft:the_nether"), None);
+
+        let overworld = || Identifier::new_full(Cow::Borrowed("minecraft:overworld")).unwrap();
+        let respawn = respawn_after_death(overworld(), overworld(), 0, PlayerGameMode::new(GameMode::Survival), Some(&death));
+        assert_eq!(respawn.death_location, death.login());
+        assert!(!respawn.copy_metadata);
+        assert!(encode_packet(&respawn).is_ok());
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use bird_protocol::{anyhow, ProtocolPacket, ProtocolPacketBound, ProtocolPacketState, ProtocolVariantReadable, ProtocolWritable, VarInt};
+use crate::connection::{encode_packet, PacketSender, raw_frame, SharedEncodedPacket};
+use crate::registry::Packet;
+
+/// Captures start with the magic and the version of the format
+pub const CAPTURE_MAGIC: &'static [u8; 8] = b"BIRDCAP\x01";
+pub const CAPTURE_EXTENSION: &'static str = "bcap";
+pub const LOG_EXTENSION: &'static str = "log";
+const HEX_DUMP_WIDTH: usize = 16;
+/// Time, state, bound and length of the frame
+const RECORD_HEADER_SIZE: usize = 10;
+/// Frames are checked before they are allocated while reading a capture
+const MAX_CAPTURED_FRAME: usize = 1 << 24;
+
+/// Offsets, hex bytes and printable chars like `hexdump -C`
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(HEX_DUMP_WIDTH).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * HEX_DUMP_WIDTH);
+        for index in 0..HEX_DUMP_WIDTH {
+            match chunk.get(index) {
+                Some(byte) => { let _ = write!(dump, " {:02x}", byte); }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(chunk.iter().map(|byte| match byte.is_ascii_graphic() || *byte == b' ' {
+            true => *byte as char,
+            false => '.',
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+fn state_id(state: ProtocolPacketState) -> u8 {
+    match state {
+        ProtocolPacketState::Handshake => 0,
+        ProtocolPacketState::Status => 1,
+        ProtocolPacketState::Login => 2,
+        ProtocolPacketState::Play => 3,
+    }
+}
+
+fn state_from_id(id: u8) -> anyhow::Result<ProtocolPacketState> {
+    Ok(match id {
+        0 => ProtocolPacketState::Handshake,
+        1 => ProtocolPacketState::Status,
+        2 => ProtocolPacketState::Login,
+        3 => ProtocolPacketState::Play,
+        _ => return Err(anyhow::Error::msg(format!("Bad state {} in the capture", id))),
+    })
+}
+
+/// Frame of the capture, it is the id and the body of the packet without the length and the compression
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CapturedFrame {
+    /// Time since the logger was created
+    pub time: Duration,
+    pub state: ProtocolPacketState,
+    pub bound: ProtocolPacketBound,
+    pub frame: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DecodeOutcome {
+    /// Debug output of the packet
+    Decoded(String),
+    /// The registry doesn't know the id
+    Unknown,
+    Failed(String),
+    /// The packet was read, but the frame is longer. Usually the packet layout differs from the client
+    TrailingBytes(String, usize),
+}
+
+impl DecodeOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, DecodeOutcome::Decoded(_))
+    }
+}
+
+/// Reads the frame like the connection does and checks that all of its bytes are read
+pub fn decode_frame(state: ProtocolPacketState, bound: ProtocolPacketBound, frame: &[u8]) -> DecodeOutcome {
+    let mut cursor = frame;
+    match Packet::read(state, bound, &mut cursor) {
+        Ok(Some(packet)) if cursor.is_empty() => DecodeOutcome::Decoded(format!("{:?}", packet)),
+        Ok(Some(packet)) => DecodeOutcome::TrailingBytes(format!("{:?}", packet), cursor.len()),
+        Ok(None) => DecodeOutcome::Unknown,
+        Err(error) => DecodeOutcome::Failed(format!("{:?}", error)),
+    }
+}
+
+fn frame_id(frame: &[u8]) -> Option<i32> {
+    let mut cursor = frame;
+    VarInt::read_variant(&mut cursor).ok()
+}
+
+/// Records raw frames into the capture and decoded packets into the text log
+pub struct PacketLogger<C: Write, L: Write> {
+    capture: C,
+    log: L,
+    opened: Instant,
+}
+
+impl PacketLogger<BufWriter<File>, BufWriter<File>> {
+    /// Creates `<name>.bcap` and `<name>.log` in the directory
+    pub fn create(directory: &Path, name: &str, now: Instant) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        let capture = File::create(directory.join(name).with_extension(CAPTURE_EXTENSION))?;
+        let log = File::create(directory.join(name).with_extension(LOG_EXTENSION))?;
+        Self::new(BufWriter::new(capture), BufWriter::new(log), now)
+    }
+}
+
+impl<C: Write, L: Write> PacketLogger<C, L> {
+    pub fn new(mut capture: C, log: L, now: Instant) -> anyhow::Result<Self> {
+        capture.write_all(CAPTURE_MAGIC)?;
+        Ok(Self { capture, log, opened: now })
+    }
+
+    /// Records the frame of a received or sent packet
+    pub fn record(&mut self, state: ProtocolPacketState, bound: ProtocolPacketBound, frame: &[u8], now: Instant) -> anyhow::Result<()> {
+        let time = now.saturating_duration_since(self.opened);
+        self.capture.write_all(&(time.as_millis() as u32).to_be_bytes())?;
+        self.capture.write_all(&[state_id(state), (bound == ProtocolPacketBound::Client) as u8])?;
+        self.capture.write_all(&(frame.len() as u32).to_be_bytes())?;
+        self.capture.write_all(frame)?;
+
+        let id = frame_id(frame).unwrap_or(-1);
+        write!(self.log, "[{:>10.3}s] {:?} {:?} 0x{:02X} ({} bytes) ", time.as_secs_f64(), state, bound, id, frame.len())?;
+        match decode_frame(state, bound, frame) {
+            DecodeOutcome::Decoded(packet) => writeln!(self.log, "{}", packet)?,
+            DecodeOutcome::Unknown => writeln!(self.log, "unknown\n{}", hex_dump(frame))?,
+            DecodeOutcome::Failed(error) => writeln!(self.log, "failed: {}\n{}", error, hex_dump(frame))?,
+            DecodeOutcome::TrailingBytes(packet, trailing) =>
+                writeln!(self.log, "{} with {} trailing bytes\n{}", packet, trailing, hex_dump(frame))?,
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.capture.flush()?;
+        self.log.flush()?;
+        Ok(())
+    }
+
+    /// Sender which records the sent packets before they are passed to `sender`
+    pub fn layer<'a, S: PacketSender>(&'a mut self, sender: &'a mut S, state: ProtocolPacketState, now: Instant) -> LoggedSender<'a, S, C, L> {
+        LoggedSender { sender, logger: self, state, now }
+    }
+
+    pub fn into_inner(self) -> (C, L) {
+        (self.capture, self.log)
+    }
+}
+
+pub struct LoggedSender<'a, S: PacketSender, C: Write, L: Write> {
+    sender: &'a mut S,
+    logger: &'a mut PacketLogger<C, L>,
+    state: ProtocolPacketState,
+    now: Instant,
+}
+
+impl<'a, S: PacketSender, C: Write, L: Write> PacketSender for LoggedSender<'a, S, C, L> {
+    fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()> {
+        self.logger.record(self.state, P::BOUND, &encode_packet(packet)?, self.now)?;
+        self.sender.send_packet(packet)
+    }
+
+    fn send_shared(&mut self, packet: &SharedEncodedPacket) -> anyhow::Result<()> {
+        self.logger.record(self.state, ProtocolPacketBound::Client, packet.frame(), self.now)?;
+        self.sender.send_shared(packet)
+    }
+
+    fn send_raw(&mut self, state: ProtocolPacketState, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        self.logger.record(state, ProtocolPacketBound::Client, &raw_frame(id, body)?, self.now)?;
+        self.sender.send_raw(state, id, body)
+    }
+}
+
+/// Reads all frames of the capture which is written by [`PacketLogger`]
+pub fn read_capture<R: Read>(mut reader: R) -> anyhow::Result<Vec<CapturedFrame>> {
+    let mut magic = [0; CAPTURE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if &magic != CAPTURE_MAGIC {
+        return Err(anyhow::Error::msg("File is not a packet capture"));
+    }
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut frames = Vec::new();
+    let mut cursor = bytes.as_slice();
+    while !cursor.is_empty() {
+        if cursor.len() < RECORD_HEADER_SIZE {
+            return Err(anyhow::Error::msg("Capture ends in the middle of a record"));
+        }
+        let (header, rest) = cursor.split_at(RECORD_HEADER_SIZE);
+        let time = Duration::from_millis(u32::from_be_bytes(header[0..4].try_into()?) as u64);
+        let state = state_from_id(header[4])?;
+        let bound = match header[5] {
+            0 => ProtocolPacketBound::Server,
+            _ => ProtocolPacketBound::Client,
+        };
+        let length = u32::from_be_bytes(header[6..10].try_into()?) as usize;
+        if length > MAX_CAPTURED_FRAME || length > rest.len() {
+            return Err(anyhow::Error::msg(format!("Frame of {} bytes doesn't fit into the capture", length)));
+        }
+        let (frame, rest) = rest.split_at(length);
+        frames.push(CapturedFrame { time, state, bound, frame: frame.to_vec() });
+        cursor = rest;
+    }
+    Ok(frames)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReplayedFrame {
+    pub index: usize,
+    pub time: Duration,
+    pub state: ProtocolPacketState,
+    pub bound: ProtocolPacketBound,
+    pub id: Option<i32>,
+    pub outcome: DecodeOutcome,
+}
+
+/// Feeds the captured frames back through the decoder
+pub fn replay(frames: &[CapturedFrame]) -> Vec<ReplayedFrame> {
+    frames.iter()
+        .enumerate()
+        .map(|(index, frame)| ReplayedFrame {
+            index,
+            time: frame.time,
+            state: frame.state,
+            bound: frame.bound,
+            id: frame_id(&frame.frame),
+            outcome: decode_frame(frame.state, frame.bound, &frame.frame),
+        })
+        .collect()
+}
+
+/// Frames which were not decoded cleanly with their hex dumps
+pub fn replay_report(frames: &[CapturedFrame]) -> String {
+    let mut report = String::new();
+    let replayed = replay(frames);
+    let failed = replayed.iter().filter(|frame| !frame.outcome.is_ok()).count();
+    let _ = writeln!(report, "{} frames, {} were not decoded cleanly", replayed.len(), failed);
+    for frame in replayed.iter().filter(|frame| !frame.outcome.is_ok()) {
+        let _ = writeln!(
+            report, "#{} [{:>10.3}s] {:?} {:?} {:?}: {:?}",
+            frame.index, frame.time.as_secs_f64(), frame.state, frame.bound, frame.id, frame.outcome,
+        );
+        report.push_str(&hex_dump(&frames[frame.index].frame));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use crate::protocol::{KeepAlivePS2C, UpdateTimePS2C};
+    use super::*;
+
+    #[test]
+    fn capture_replay_test() {
+        assert_eq!(
+            hex_dump(b"bird\x00"),
+            format!("00000000  62 69 72 64 00{}  |bird.|\n", " ".repeat(33)),
+        );
+        let now = Instant::now();
+        let mut logger = PacketLogger::new(Vec::new(), Vec::new(), now).unwrap();
+        let mut queue = PacketQueue::new();
+        logger.layer(&mut queue, ProtocolPacketState::Play, now + Duration::from_millis(1500))
+            .send_packet(&UpdateTimePS2C { world_age: 1, time_of_day: 6000 })
+            .unwrap();
+        assert_eq!(queue.take().len(), 1);
+        let mut trailing = encode_packet(&KeepAlivePS2C { keep_alive_id: 7 }).unwrap();
+        trailing.push(0);
+        logger.record(ProtocolPacketState::Play, ProtocolPacketBound::Client, &trailing, now).unwrap();
+        logger.record(ProtocolPacketState::Play, ProtocolPacketBound::Client, &[0x7F], now).unwrap();
+        let (capture, log) = logger.into_inner();
+        let log = String::from_utf8(log).unwrap();
+        assert!(log.contains("UpdateTime"));
+        assert!(log.contains("1 trailing bytes"));
+
+        let frames = read_capture(capture.as_slice()).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].time, Duration::from_millis(1500));
+        let replayed = replay(&frames);
+        assert!(replayed[0].outcome.is_ok());
+        assert!(matches!(replayed[1].outcome, DecodeOutcome::TrailingBytes(_, 1)));
+        assert_eq!(replayed[2].outcome, DecodeOutcome::Unknown);
+        assert!(replay_report(&frames).starts_with("3 frames, 2 were not decoded cleanly"));
+        assert!(read_capture(&capture[..capture.len() - 1]).is_err());
+        assert!(read_capture(&b"NOTACAPTURE"[..]).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, HashMap};
+use bird_protocol::{anyhow, ProtocolPacket, ProtocolPacketBound, ProtocolPacketState, ProtocolResult, ProtocolWritable};
+use crate::connection::{PacketQueue, PacketSender};
+use crate::registry::Packet;
+
+/// Entity of the ecs, it is not the protocol entity id. The generation is increased when the index
+/// is reused, so handles of despawned entities are not valid anymore
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+trait AnyStorage {
+    fn remove_handle(&mut self, handle: Handle);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Components are ordered by handles, so systems see entities in the same order on each run
+struct Storage<T>(BTreeMap<Handle, T>);
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_handle(&mut self, handle: Handle) {
+        self.0.remove(&handle);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Packet which is received by the connection of the entity, it is decoded by systems which handle it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InboundPacket {
+    pub handle: Handle,
+    pub state: ProtocolPacketState,
+    /// Id and body of the packet
+    pub frame: Vec<u8>,
+}
+
+impl InboundPacket {
+    pub fn decode(&self) -> ProtocolResult<Option<Packet<'_>>> {
+        Packet::read(self.state, ProtocolPacketBound::Server, &mut self.frame.as_slice())
+    }
+}
+
+/// Entities with components, resources which are shared by systems and queues of events.
+/// Outbound packets are queued into the [`PacketQueue`] component of the entity
+#[derive(Default)]
+pub struct Ecs {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    components: HashMap<TypeId, Box<dyn AnyStorage>>,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+    events: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Ecs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self) -> Handle {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Handle { index, generation: self.generations[index as usize] }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Handle { index: self.generations.len() as u32 - 1, generation: 0 }
+            }
+        }
+    }
+
+    pub fn is_alive(&self, handle: Handle) -> bool {
+        let index = handle.index as usize;
+        self.alive.get(index).copied().unwrap_or(false) && self.generations[index] == handle.generation
+    }
+
+    /// Removes the entity with all of its components
+    pub fn despawn(&mut self, handle: Handle) -> bool {
+        if !self.is_alive(handle) {
+            return false;
+        }
+        for storage in self.components.values_mut() {
+            storage.remove_handle(handle);
+        }
+        let index = handle.index as usize;
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(handle.index);
+        true
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item=Handle> + '_ {
+        self.generations.iter()
+            .enumerate()
+            .filter(|(index, _)| self.alive[*index])
+            .map(|(index, generation)| Handle { index: index as u32, generation: *generation })
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&BTreeMap<Handle, T>> {
+        self.components.get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref::<Storage<T>>())
+            .map(|storage| &storage.0)
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> Option<&mut BTreeMap<Handle, T>> {
+        self.components.get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Storage<T>>())
+            .map(|storage| &mut storage.0)
+    }
+
+    /// Returns the previous component, components of entities which are not alive are not inserted
+    pub fn insert<T: 'static>(&mut self, handle: Handle, component: T) -> anyhow::Result<Option<T>> {
+        if !self.is_alive(handle) {
+            return Err(anyhow::Error::msg("Entity is not alive"));
+        }
+        let storage = self.components.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>(BTreeMap::new())))
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap();
+        Ok(storage.0.insert(handle, component))
+    }
+
+    pub fn remove<T: 'static>(&mut self, handle: Handle) -> Option<T> {
+        self.storage_mut::<T>()?.remove(&handle)
+    }
+
+    pub fn get<T: 'static>(&self, handle: Handle) -> Option<&T> {
+        self.storage::<T>()?.get(&handle)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, handle: Handle) -> Option<&mut T> {
+        self.storage_mut::<T>()?.get_mut(&handle)
+    }
+
+    pub fn has<T: 'static>(&self, handle: Handle) -> bool {
+        self.get::<T>(handle).is_some()
+    }
+
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item=(Handle, &T)> {
+        self.storage::<T>().into_iter().flat_map(|storage| storage.iter().map(|(handle, component)| (*handle, component)))
+    }
+
+    pub fn query_mut<T: 'static>(&mut self) -> impl Iterator<Item=(Handle, &mut T)> {
+        self.storage_mut::<T>().into_iter().flat_map(|storage| storage.iter_mut().map(|(handle, component)| (*handle, component)))
+    }
+
+    /// Entities which have both components
+    pub fn query_pair<A: 'static, B: 'static>(&self) -> impl Iterator<Item=(Handle, &A, &B)> {
+        self.query::<A>().filter_map(|(handle, first)| self.get::<B>(handle).map(|second| (handle, first, second)))
+    }
+
+    /// Handles of entities with the component, it is used to mutate other components in the loop
+    pub fn with<T: 'static>(&self) -> Vec<Handle> {
+        self.query::<T>().map(|(handle, _)| handle).collect()
+    }
+
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.resources.insert(TypeId::of::<T>(), Box::new(resource))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    pub fn resource<T: 'static>(&self) -> Option<&T> {
+        self.resources.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn resource_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.resources.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    pub fn send_event<E: 'static>(&mut self, event: E) {
+        self.events.entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Vec::<E>::new()))
+            .downcast_mut::<Vec<E>>()
+            .unwrap()
+            .push(event)
+    }
+
+    pub fn read_events<E: 'static>(&self) -> &[E] {
+        self.events.get(&TypeId::of::<E>())
+            .and_then(|events| events.downcast_ref::<Vec<E>>())
+            .map_or(&[], |events| events.as_slice())
+    }
+
+    /// Takes the events, so the next system doesn't see them
+    pub fn drain_events<E: 'static>(&mut self) -> Vec<E> {
+        self.events.get_mut(&TypeId::of::<E>())
+            .and_then(|events| events.downcast_mut::<Vec<E>>())
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Queues the packet into the [`PacketQueue`] of the entity
+    pub fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, handle: Handle, packet: &P) -> anyhow::Result<()> {
+        self.get_mut::<PacketQueue>(handle)
+            .ok_or_else(|| anyhow::Error::msg("Entity doesn't have a connection"))?
+            .send_packet(packet)
+    }
+}
+
+type System = Box<dyn FnMut(&mut Ecs) -> anyhow::Result<()>>;
+
+/// Systems which are run in the order they were added, usually once per tick
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(&'static str, System)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(&mut self, name: &'static str, system: impl FnMut(&mut Ecs) -> anyhow::Result<()> + 'static) -> &mut Self {
+        self.systems.push((name, Box::new(system)));
+        self
+    }
+
+    pub fn systems(&self) -> impl Iterator<Item=&'static str> + '_ {
+        self.systems.iter().map(|(name, _)| *name)
+    }
+
+    /// Stops at the first failed system
+    pub fn run(&mut self, ecs: &mut Ecs) -> anyhow::Result<()> {
+        for (name, system) in &mut self.systems {
+            system(ecs).map_err(|err| anyhow::Error::msg(format!("System {} failed: {}", name, err)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::encode_packet;
+    use crate::protocol::{Hand, UpdateTimePS2C, UseItemPC2S};
+    use crate::registry::PlayC2SPacket;
+    use super::*;
+
+    struct Name(&'static str);
+
+    struct UsedItems(u32);
+
+    #[test]
+    fn ecs_test() {
+        let mut ecs = Ecs::new();
+        let first = ecs.spawn();
+        let second = ecs.spawn();
+        ecs.insert(first, Name("first")).unwrap();
+        ecs.insert(first, PacketQueue::new()).unwrap();
+        ecs.insert(second, Name("second")).unwrap();
+        ecs.insert_resource(UsedItems(0));
+        assert_eq!(ecs.query::<Name>().map(|(_, name)| name.0).collect::<Vec<_>>(), ["first", "second"]);
+        assert_eq!(ecs.query_pair::<Name, PacketQueue>().count(), 1);
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system("use_item", |ecs| {
+                for packet in ecs.drain_events::<InboundPacket>() {
+                    if let Some(Packet::PlayC2S(PlayC2SPacket::UseItem(_))) = packet.decode()? {
+                        ecs.resource_mut::<UsedItems>().unwrap().0 += 1;
+                        ecs.send_packet(packet.handle, &UpdateTimePS2C { world_age: 0, time_of_day: 0 })?;
+                    }
+                }
+                Ok(())
+            })
+            .add_system("names", |ecs| {
+                for handle in ecs.with::<Name>() {
+                    ecs.get_mut::<Name>(handle).unwrap().0 = "renamed";
+                }
+                Ok(())
+            });
+        let frame = encode_packet(&UseItemPC2S { hand: Hand::Main, sequence: 1 }).unwrap();
+        ecs.send_event(InboundPacket { handle: first, state: ProtocolPacketState::Play, frame: frame.clone() });
+        schedule.run(&mut ecs).unwrap();
+        assert_eq!(ecs.resource::<UsedItems>().unwrap().0, 1);
+        assert_eq!(ecs.get_mut::<PacketQueue>(first).unwrap().take().len(), 1);
+        assert!(ecs.read_events::<InboundPacket>().is_empty());
+        assert_eq!(ecs.get::<Name>(second).unwrap().0, "renamed");
+
+        // Second entity doesn't have a connection
+        ecs.send_event(InboundPacket { handle: second, state: ProtocolPacketState::Play, frame });
+        assert!(schedule.run(&mut ecs).is_err());
+
+        assert!(ecs.despawn(first));
+        assert!(!ecs.despawn(first));
+        assert!(ecs.get::<Name>(first).is_none());
+        let third = ecs.spawn();
+        assert_ne!(third, first);
+        assert!(!ecs.is_alive(first));
+        assert!(ecs.insert(first, Name("dead")).is_err());
+        assert_eq!(ecs.entities().count(), 2);
+    }
+}
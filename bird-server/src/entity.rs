@@ -0,0 +1,54 @@
+use euclid::default::Vector3D;
+
+pub mod tracker;
+pub mod metadata;
+pub mod display;
+pub mod armor_stand;
+pub mod link;
+pub mod minecart;
+pub mod status;
+pub mod wandering_trader;
+
+pub const PLAYER_ENTITY_TYPE: i32 = 117;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EntityKind {
+    Player,
+    Other {
+        entity_type: i32,
+        /// `data` field of `SpawnEntity`, meaning depends on the entity type
+        data: i32,
+    },
+}
+
+impl EntityKind {
+    pub const fn entity_type(&self) -> i32 {
+        match self {
+            EntityKind::Player => PLAYER_ENTITY_TYPE,
+            EntityKind::Other { entity_type, .. } => *entity_type,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EntityIdAllocator {
+    next: i32,
+}
+
+impl EntityIdAllocator {
+    pub const fn new() -> Self {
+        Self { next: 1 }
+    }
+
+    pub fn allocate(&mut self) -> i32 {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        id
+    }
+}
+
+/// Converts velocity in blocks per tick to the protocol units (1/8000 of block per tick)
+pub fn protocol_velocity(velocity: Vector3D<f64>) -> Vector3D<i16> {
+    let convert = |value: f64| (value.clamp(-3.9, 3.9) * 8000.0) as i16;
+    Vector3D::new(convert(velocity.x), convert(velocity.y), convert(velocity.z))
+}
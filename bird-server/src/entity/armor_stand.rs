@@ -0,0 +1,206 @@
+use euclid::default::Vector3D;
+use uuid::Uuid;
+use bird_protocol::anyhow;
+use crate::entity::EntityKind;
+use crate::entity::metadata::*;
+use crate::entity::tracker::{EntityTracker, TrackedEntity};
+use crate::protocol::{EquipmentSlot, InteractAction, InteractPC2S, OwnedSlot};
+
+pub const ARMOR_STAND_ENTITY_TYPE: i32 = 2;
+
+pub const ARMOR_STAND_FLAGS_INDEX: u8 = 15;
+pub const ARMOR_STAND_FLAG_SMALL: i8 = 0x01;
+pub const ARMOR_STAND_FLAG_ARMS: i8 = 0x04;
+pub const ARMOR_STAND_FLAG_NO_BASE_PLATE: i8 = 0x08;
+pub const ARMOR_STAND_FLAG_MARKER: i8 = 0x10;
+pub const ARMOR_STAND_HEAD_INDEX: u8 = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ArmorStandPart {
+    Head,
+    Body,
+    LeftArm,
+    RightArm,
+    LeftLeg,
+    RightLeg,
+}
+
+/// Rotations of parts in degrees
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ArmorStandPose {
+    pub parts: [Vector3D<f32>; 6],
+}
+
+impl Default for ArmorStandPose {
+    fn default() -> Self {
+        Self {
+            parts: [
+                Vector3D::new(0.0, 0.0, 0.0),
+                Vector3D::new(0.0, 0.0, 0.0),
+                Vector3D::new(-10.0, 0.0, -10.0),
+                Vector3D::new(-15.0, 0.0, 10.0),
+                Vector3D::new(-1.0, 0.0, -1.0),
+                Vector3D::new(1.0, 0.0, 1.0),
+            ],
+        }
+    }
+}
+
+impl ArmorStandPose {
+    pub fn get(&self, part: ArmorStandPart) -> Vector3D<f32> {
+        self.parts[part as usize]
+    }
+
+    pub fn set(&mut self, part: ArmorStandPart, rotation: Vector3D<f32>) {
+        self.parts[part as usize] = rotation
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ArmorStandInteraction {
+    Ignored,
+    /// The held item was put into the slot, the previous item of the slot should be given to the player
+    Equipped {
+        slot: EquipmentSlot,
+        previous: Option<OwnedSlot>,
+    },
+    /// The item was taken from the slot
+    Taken {
+        slot: EquipmentSlot,
+        item: OwnedSlot,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArmorStand {
+    pub small: bool,
+    pub arms: bool,
+    pub no_base_plate: bool,
+    pub marker: bool,
+    pub invisible: bool,
+    /// Players can't take or put items
+    pub locked: bool,
+    pub pose: ArmorStandPose,
+    /// Indexed by [`EquipmentSlot`]
+    pub equipment: [Option<OwnedSlot>; 6],
+}
+
+impl Default for ArmorStand {
+    fn default() -> Self {
+        Self {
+            small: false,
+            arms: false,
+            no_base_plate: false,
+            marker: false,
+            invisible: false,
+            locked: false,
+            pose: ArmorStandPose::default(),
+            equipment: Default::default(),
+        }
+    }
+}
+
+impl ArmorStand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn flags(&self) -> i8 {
+        let mut flags = 0;
+        if self.small { flags |= ARMOR_STAND_FLAG_SMALL }
+        if self.arms { flags |= ARMOR_STAND_FLAG_ARMS }
+        if self.no_base_plate { flags |= ARMOR_STAND_FLAG_NO_BASE_PLATE }
+        if self.marker { flags |= ARMOR_STAND_FLAG_MARKER }
+        flags
+    }
+
+    pub fn write_metadata(&self, metadata: &mut EntityMetadata) -> anyhow::Result<()> {
+        metadata.update_flags(ENTITY_FLAGS_INDEX, ENTITY_FLAG_INVISIBLE, self.invisible)?;
+        metadata.set(ARMOR_STAND_FLAGS_INDEX, MetadataValue::Byte(self.flags()))?;
+        for (index, rotation) in self.pose.parts.iter().enumerate() {
+            metadata.set(ARMOR_STAND_HEAD_INDEX + index as u8, MetadataValue::Rotation(*rotation))?;
+        }
+        Ok(())
+    }
+
+    pub fn to_tracked(&self, id: i32, uuid: Uuid, position: Vector3D<f64>) -> anyhow::Result<TrackedEntity> {
+        let mut entity = TrackedEntity::new(id, uuid, EntityKind::Other {
+            entity_type: ARMOR_STAND_ENTITY_TYPE,
+            data: 0,
+        }, position);
+        self.write_metadata(&mut entity.metadata)?;
+        entity.equipment = self.equipment.clone();
+        Ok(entity)
+    }
+
+    /// Sends changed flags, pose and equipment to viewers
+    pub fn update(&self, id: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        tracker.update_metadata(id, |metadata| self.write_metadata(metadata))?;
+        for slot in EquipmentSlot::ALL {
+            tracker.set_equipment(id, slot, self.equipment[slot as usize].clone())?;
+        }
+        Ok(())
+    }
+
+    pub fn set_equipment(&mut self, slot: EquipmentSlot, item: Option<OwnedSlot>) -> Option<OwnedSlot> {
+        std::mem::replace(&mut self.equipment[slot as usize], item)
+    }
+
+    /// Returns the slot which is clicked at the given height relative to the armor stand position
+    pub fn clicked_slot(&self, y: f32) -> EquipmentSlot {
+        let has = |slot: EquipmentSlot| self.equipment[slot as usize].is_some();
+        let (y, small) = match self.small {
+            true => (y * 2.0, true),
+            false => (y, false),
+        };
+        if y >= 0.1 && y < 0.1 + if small { 0.8 } else { 0.45 } && has(EquipmentSlot::Boots) {
+            EquipmentSlot::Boots
+        } else if y >= 0.9 + if small { 0.3 } else { 0.0 } && y < 0.9 + if small { 1.0 } else { 0.7 } && has(EquipmentSlot::Chestplate) {
+            EquipmentSlot::Chestplate
+        } else if y >= 0.4 && y < 0.4 + if small { 1.0 } else { 0.8 } && has(EquipmentSlot::Leggings) {
+            EquipmentSlot::Leggings
+        } else if y >= 1.6 && has(EquipmentSlot::Helmet) {
+            EquipmentSlot::Helmet
+        } else if !has(EquipmentSlot::MainHand) && has(EquipmentSlot::OffHand) {
+            EquipmentSlot::OffHand
+        } else {
+            EquipmentSlot::MainHand
+        }
+    }
+
+    /// Handles interaction of player with the armor stand.
+    /// `held_slot` is the equipment slot of the held item (e.g. [`EquipmentSlot::Helmet`] for helmets),
+    /// the caller must update the player inventory using the returned interaction
+    pub fn handle_interact(
+        &mut self,
+        packet: &InteractPC2S,
+        held: Option<&OwnedSlot>,
+        held_slot: EquipmentSlot,
+    ) -> ArmorStandInteraction {
+        // Vanilla client sends both Interact and InteractAt, only the last one contains the position
+        let target = match packet.action {
+            InteractAction::InteractAt { target, .. } => target,
+            _ => return ArmorStandInteraction::Ignored,
+        };
+        if self.marker || self.locked {
+            return ArmorStandInteraction::Ignored;
+        }
+        match held {
+            Some(held) => {
+                let slot = match held_slot {
+                    EquipmentSlot::MainHand | EquipmentSlot::OffHand if !self.arms => return ArmorStandInteraction::Ignored,
+                    slot => slot,
+                };
+                let previous = self.set_equipment(slot, Some(held.clone()));
+                ArmorStandInteraction::Equipped { slot, previous }
+            }
+            None => {
+                let slot = self.clicked_slot(target.y);
+                match self.set_equipment(slot, None) {
+                    Some(item) => ArmorStandInteraction::Taken { slot, item },
+                    None => ArmorStandInteraction::Ignored,
+                }
+            }
+        }
+    }
+}
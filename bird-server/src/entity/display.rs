@@ -0,0 +1,267 @@
+use euclid::default::Vector3D;
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::*;
+use crate::entity::EntityKind;
+use crate::entity::metadata::*;
+use crate::entity::tracker::{EntityTracker, TrackedEntity};
+use crate::protocol::{Direction, OwnedSlot};
+
+pub const ITEM_FRAME_ENTITY_TYPE: i32 = 46;
+pub const GLOW_ITEM_FRAME_ENTITY_TYPE: i32 = 36;
+pub const PAINTING_ENTITY_TYPE: i32 = 64;
+
+pub const ITEM_FRAME_ITEM_INDEX: u8 = 8;
+pub const ITEM_FRAME_ROTATION_INDEX: u8 = 9;
+pub const PAINTING_VARIANT_INDEX: u8 = 8;
+
+// Display entities are added only in 1.19.4 so their ids and metadata types are from that version
+
+pub const BLOCK_DISPLAY_ENTITY_TYPE: i32 = 8;
+pub const ITEM_DISPLAY_ENTITY_TYPE: i32 = 55;
+pub const TEXT_DISPLAY_ENTITY_TYPE: i32 = 100;
+
+pub const DISPLAY_INTERPOLATION_START_INDEX: u8 = 8;
+pub const DISPLAY_INTERPOLATION_DURATION_INDEX: u8 = 9;
+pub const DISPLAY_TRANSLATION_INDEX: u8 = 10;
+pub const DISPLAY_SCALE_INDEX: u8 = 11;
+pub const DISPLAY_LEFT_ROTATION_INDEX: u8 = 12;
+pub const DISPLAY_RIGHT_ROTATION_INDEX: u8 = 13;
+pub const DISPLAY_BILLBOARD_INDEX: u8 = 14;
+pub const DISPLAY_VIEW_RANGE_INDEX: u8 = 16;
+pub const DISPLAY_SHADOW_RADIUS_INDEX: u8 = 17;
+pub const DISPLAY_SHADOW_STRENGTH_INDEX: u8 = 18;
+pub const DISPLAY_GLOW_COLOR_INDEX: u8 = 21;
+pub const DISPLAY_CONTENT_INDEX: u8 = 22;
+pub const ITEM_DISPLAY_TYPE_INDEX: u8 = 23;
+pub const TEXT_DISPLAY_LINE_WIDTH_INDEX: u8 = 23;
+pub const TEXT_DISPLAY_BACKGROUND_INDEX: u8 = 24;
+
+const DISPLAY_BLOCK_STATE_TYPE: i32 = 14;
+const DISPLAY_VECTOR3_TYPE: i32 = 26;
+const DISPLAY_QUATERNION_TYPE: i32 = 27;
+
+pub fn block_center(position: Vector3D<i32>) -> Vector3D<f64> {
+    position.cast::<f64>() + Vector3D::new(0.5, 0.5, 0.5)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ItemFrame {
+    pub glowing: bool,
+    pub invisible: bool,
+    pub facing: Direction,
+    pub item: Option<OwnedSlot>,
+    /// From 0 to 7, each step is 45 degrees
+    pub rotation: u8,
+}
+
+impl ItemFrame {
+    pub fn new(facing: Direction) -> Self {
+        Self { glowing: false, invisible: false, facing, item: None, rotation: 0 }
+    }
+
+    pub fn write_metadata(&self, metadata: &mut EntityMetadata) -> anyhow::Result<()> {
+        metadata.update_flags(ENTITY_FLAGS_INDEX, ENTITY_FLAG_INVISIBLE, self.invisible)?;
+        metadata.set(ITEM_FRAME_ITEM_INDEX, MetadataValue::Slot(self.item.as_ref().map(|item| item.as_slot())))?;
+        metadata.set(ITEM_FRAME_ROTATION_INDEX, MetadataValue::VarInt((self.rotation % 8) as i32))
+    }
+
+    pub fn to_tracked(&self, id: i32, uuid: Uuid, block: Vector3D<i32>) -> anyhow::Result<TrackedEntity> {
+        let mut entity = TrackedEntity::new(id, uuid, EntityKind::Other {
+            entity_type: match self.glowing {
+                true => GLOW_ITEM_FRAME_ENTITY_TYPE,
+                false => ITEM_FRAME_ENTITY_TYPE,
+            },
+            data: self.facing as i32,
+        }, block_center(block));
+        self.write_metadata(&mut entity.metadata)?;
+        Ok(entity)
+    }
+
+    /// Sends changed item and rotation to viewers
+    pub fn update(&self, id: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        tracker.update_metadata(id, |metadata| self.write_metadata(metadata))
+    }
+}
+
+/// Since 1.19 the variant of painting is sent in metadata, `data` of `SpawnEntity` is the facing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Painting {
+    pub facing: Direction,
+    pub variant: i32,
+}
+
+impl Painting {
+    pub fn write_metadata(&self, metadata: &mut EntityMetadata) -> anyhow::Result<()> {
+        metadata.set(PAINTING_VARIANT_INDEX, MetadataValue::PaintingVariant(self.variant))
+    }
+
+    pub fn to_tracked(&self, id: i32, uuid: Uuid, block: Vector3D<i32>) -> anyhow::Result<TrackedEntity> {
+        let mut entity = TrackedEntity::new(id, uuid, EntityKind::Other {
+            entity_type: PAINTING_ENTITY_TYPE,
+            data: self.facing as i32,
+        }, block_center(block));
+        self.write_metadata(&mut entity.metadata)?;
+        Ok(entity)
+    }
+
+    pub fn update(&self, id: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        tracker.update_metadata(id, |metadata| self.write_metadata(metadata))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DisplayTransform {
+    pub translation: Vector3D<f32>,
+    pub scale: Vector3D<f32>,
+    /// Quaternion in x, y, z, w order
+    pub left_rotation: [f32; 4],
+    pub right_rotation: [f32; 4],
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3D::zero(),
+            scale: Vector3D::new(1.0, 1.0, 1.0),
+            left_rotation: [0.0, 0.0, 0.0, 1.0],
+            right_rotation: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[repr(i8)]
+pub enum DisplayBillboard {
+    #[default]
+    Fixed = 0,
+    Vertical,
+    Horizontal,
+    Center,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DisplayContent<'a> {
+    Block {
+        block_state: i32,
+    },
+    Item {
+        item: Option<OwnedSlot>,
+        display_type: i8,
+    },
+    Text {
+        text: Component<'a>,
+        line_width: i32,
+        background_color: i32,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisplayEntity<'a> {
+    pub transform: DisplayTransform,
+    pub billboard: DisplayBillboard,
+    /// Ticks of interpolation between the old and the new transform
+    pub interpolation_duration: i32,
+    pub view_range: f32,
+    pub shadow_radius: f32,
+    pub shadow_strength: f32,
+    /// -1 means no override
+    pub glow_color: i32,
+    pub content: DisplayContent<'a>,
+}
+
+fn write_floats<W: ProtocolWriter>(floats: &[f32], writer: &mut W) -> anyhow::Result<()> {
+    for float in floats {
+        float.write(writer)?;
+    }
+    Ok(())
+}
+
+fn raw_entry(ty: i32, write: impl FnOnce(&mut Vec<u8>) -> anyhow::Result<()>) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    VarInt::write_variant(&ty, &mut bytes)?;
+    write(&mut bytes)?;
+    Ok(bytes)
+}
+
+impl<'a> DisplayEntity<'a> {
+    pub fn new(content: DisplayContent<'a>) -> Self {
+        Self {
+            transform: DisplayTransform::default(),
+            billboard: DisplayBillboard::Fixed,
+            interpolation_duration: 0,
+            view_range: 1.0,
+            shadow_radius: 0.0,
+            shadow_strength: 1.0,
+            glow_color: -1,
+            content,
+        }
+    }
+
+    pub const fn entity_type(&self) -> i32 {
+        match self.content {
+            DisplayContent::Block { .. } => BLOCK_DISPLAY_ENTITY_TYPE,
+            DisplayContent::Item { .. } => ITEM_DISPLAY_ENTITY_TYPE,
+            DisplayContent::Text { .. } => TEXT_DISPLAY_ENTITY_TYPE,
+        }
+    }
+
+    pub fn write_transform(&self, metadata: &mut EntityMetadata) -> anyhow::Result<()> {
+        let transform = &self.transform;
+        metadata.set_raw(DISPLAY_TRANSLATION_INDEX, raw_entry(DISPLAY_VECTOR3_TYPE, |bytes| {
+            write_floats(&transform.translation.to_array(), bytes)
+        })?);
+        metadata.set_raw(DISPLAY_SCALE_INDEX, raw_entry(DISPLAY_VECTOR3_TYPE, |bytes| {
+            write_floats(&transform.scale.to_array(), bytes)
+        })?);
+        metadata.set_raw(DISPLAY_LEFT_ROTATION_INDEX, raw_entry(DISPLAY_QUATERNION_TYPE, |bytes| {
+            write_floats(&transform.left_rotation, bytes)
+        })?);
+        metadata.set_raw(DISPLAY_RIGHT_ROTATION_INDEX, raw_entry(DISPLAY_QUATERNION_TYPE, |bytes| {
+            write_floats(&transform.right_rotation, bytes)
+        })?);
+        Ok(())
+    }
+
+    pub fn write_metadata(&self, metadata: &mut EntityMetadata) -> anyhow::Result<()> {
+        // Interpolation starts from the next tick after the change
+        metadata.set(DISPLAY_INTERPOLATION_START_INDEX, MetadataValue::VarInt(0))?;
+        metadata.set(DISPLAY_INTERPOLATION_DURATION_INDEX, MetadataValue::VarInt(self.interpolation_duration))?;
+        self.write_transform(metadata)?;
+        metadata.set(DISPLAY_BILLBOARD_INDEX, MetadataValue::Byte(self.billboard as i8))?;
+        metadata.set(DISPLAY_VIEW_RANGE_INDEX, MetadataValue::Float(self.view_range))?;
+        metadata.set(DISPLAY_SHADOW_RADIUS_INDEX, MetadataValue::Float(self.shadow_radius))?;
+        metadata.set(DISPLAY_SHADOW_STRENGTH_INDEX, MetadataValue::Float(self.shadow_strength))?;
+        metadata.set(DISPLAY_GLOW_COLOR_INDEX, MetadataValue::VarInt(self.glow_color))?;
+        match &self.content {
+            DisplayContent::Block { block_state } => metadata.set_raw(
+                DISPLAY_CONTENT_INDEX,
+                raw_entry(DISPLAY_BLOCK_STATE_TYPE, |bytes| VarInt::write_variant(block_state, bytes))?,
+            ),
+            DisplayContent::Item { item, display_type } => {
+                metadata.set(DISPLAY_CONTENT_INDEX, MetadataValue::Slot(item.as_ref().map(|item| item.as_slot())))?;
+                metadata.set(ITEM_DISPLAY_TYPE_INDEX, MetadataValue::Byte(*display_type))?;
+            }
+            DisplayContent::Text { text, line_width, background_color } => {
+                metadata.set(DISPLAY_CONTENT_INDEX, MetadataValue::Chat(text))?;
+                metadata.set(TEXT_DISPLAY_LINE_WIDTH_INDEX, MetadataValue::VarInt(*line_width))?;
+                metadata.set(TEXT_DISPLAY_BACKGROUND_INDEX, MetadataValue::VarInt(*background_color))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_tracked(&self, id: i32, uuid: Uuid, position: Vector3D<f64>) -> anyhow::Result<TrackedEntity> {
+        let mut entity = TrackedEntity::new(id, uuid, EntityKind::Other {
+            entity_type: self.entity_type(),
+            data: 0,
+        }, position);
+        self.write_metadata(&mut entity.metadata)?;
+        Ok(entity)
+    }
+
+    /// Sends changed fields to viewers, transform changes are interpolated by the client
+    pub fn update(&self, id: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        tracker.update_metadata(id, |metadata| self.write_metadata(metadata))
+    }
+}
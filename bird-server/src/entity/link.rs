@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+use bird_protocol::anyhow;
+use bird_protocol::derive::BirdNbt;
+use crate::entity::tracker::EntityTracker;
+use crate::nbt::NbtUuid;
+
+#[derive(BirdNbt, Clone, PartialEq, Debug)]
+pub struct LeashNbt {
+    #[bnbt(name = "UUID")]
+    pub uuid: NbtUuid,
+}
+
+#[derive(BirdNbt, Clone, PartialEq, Debug)]
+pub struct PassengerNbt {
+    #[bnbt(name = "UUID")]
+    pub uuid: NbtUuid,
+}
+
+/// Leash and passengers of the entity, entities are referenced by their uuids
+/// because entity ids are not persisted
+#[derive(BirdNbt, Clone, PartialEq, Debug)]
+pub struct EntityLinksNbt {
+    #[bnbt(name = "Leash")]
+    pub leash: Option<LeashNbt>,
+    #[bnbt(name = "Passengers")]
+    pub passengers: Option<Vec<PassengerNbt>>,
+}
+
+impl EntityLinksNbt {
+    pub fn from_tracker(tracker: &EntityTracker, id: i32) -> Option<Self> {
+        let entity = tracker.get_entity(id)?;
+        let uuid_of = |id: &i32| tracker.get_entity(*id).map(|entity| NbtUuid(entity.uuid));
+        let passengers: Vec<_> = entity.passengers().iter()
+            .filter_map(uuid_of)
+            .map(|uuid| PassengerNbt { uuid })
+            .collect();
+        Some(Self {
+            leash: entity.leash_holder().as_ref().and_then(uuid_of).map(|uuid| LeashNbt { uuid }),
+            passengers: match passengers.is_empty() {
+                true => None,
+                false => Some(passengers),
+            },
+        })
+    }
+
+    /// Restores links to entities which are already tracked, links to unknown entities are skipped
+    pub fn apply(&self, tracker: &mut EntityTracker, id: i32) -> anyhow::Result<()> {
+        let id_of = |tracker: &EntityTracker, uuid: &NbtUuid| tracker.entities()
+            .find(|entity| entity.uuid == uuid.0)
+            .map(|entity| entity.id);
+        if let Some(ref leash) = self.leash {
+            let holder = id_of(tracker, &leash.uuid);
+            tracker.set_leash_holder(id, holder)?;
+        }
+        if let Some(ref passengers) = self.passengers {
+            let passengers = passengers.iter()
+                .filter_map(|passenger| id_of(tracker, &passenger.uuid))
+                .collect();
+            tracker.set_passengers(id, passengers)?;
+        }
+        Ok(())
+    }
+}
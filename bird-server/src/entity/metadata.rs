@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet};
+use euclid::default::Vector3D;
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::*;
+use crate::protocol::{Direction, Slot};
+
+pub const ENTITY_FLAGS_INDEX: u8 = 0;
+pub const ENTITY_FLAG_ON_FIRE: i8 = 0x01;
+pub const ENTITY_FLAG_CROUCHING: i8 = 0x02;
+pub const ENTITY_FLAG_SPRINTING: i8 = 0x08;
+pub const ENTITY_FLAG_SWIMMING: i8 = 0x10;
+pub const ENTITY_FLAG_INVISIBLE: i8 = 0x20;
+pub const ENTITY_FLAG_GLOWING: i8 = 0x40;
+pub const ENTITY_FLAG_ELYTRA_FLYING: i8 = -0x80;
+pub const ENTITY_CUSTOM_NAME_INDEX: u8 = 2;
+pub const ENTITY_CUSTOM_NAME_VISIBLE_INDEX: u8 = 3;
+pub const ENTITY_SILENT_INDEX: u8 = 4;
+pub const ENTITY_NO_GRAVITY_INDEX: u8 = 5;
+
+const METADATA_END: u8 = 0xFF;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MetadataValue<'a> {
+    Byte(i8),
+    VarInt(i32),
+    VarLong(i64),
+    Float(f32),
+    String(&'a str),
+    Chat(&'a Component<'a>),
+    OptionalChat(Option<&'a Component<'a>>),
+    Slot(Option<Slot<'a>>),
+    Boolean(bool),
+    Rotation(Vector3D<f32>),
+    Position(Vector3D<i32>),
+    OptionalPosition(Option<Vector3D<i32>>),
+    Direction(Direction),
+    OptionalUuid(Option<Uuid>),
+    /// 0 means absent
+    OptionalBlockState(i32),
+    Nbt(&'a [u8]),
+    OptionalVarInt(Option<i32>),
+    Pose(i32),
+    PaintingVariant(i32),
+}
+
+impl<'a> MetadataValue<'a> {
+    pub const fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::VarLong(_) => 2,
+            MetadataValue::Float(_) => 3,
+            MetadataValue::String(_) => 4,
+            MetadataValue::Chat(_) => 5,
+            MetadataValue::OptionalChat(_) => 6,
+            MetadataValue::Slot(_) => 7,
+            MetadataValue::Boolean(_) => 8,
+            MetadataValue::Rotation(_) => 9,
+            MetadataValue::Position(_) => 10,
+            MetadataValue::OptionalPosition(_) => 11,
+            MetadataValue::Direction(_) => 12,
+            MetadataValue::OptionalUuid(_) => 13,
+            MetadataValue::OptionalBlockState(_) => 14,
+            MetadataValue::Nbt(_) => 15,
+            MetadataValue::OptionalVarInt(_) => 18,
+            MetadataValue::Pose(_) => 19,
+            MetadataValue::PaintingVariant(_) => 23,
+        }
+    }
+
+    pub fn write_value<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        match self {
+            MetadataValue::Byte(value) => value.write(writer),
+            MetadataValue::VarInt(value) | MetadataValue::OptionalBlockState(value) |
+            MetadataValue::Pose(value) | MetadataValue::PaintingVariant(value) => VarInt::write_variant(value, writer),
+            MetadataValue::VarLong(value) => VarLong::write_variant(value, writer),
+            MetadataValue::Float(value) => value.write(writer),
+            MetadataValue::String(value) => value.write(writer),
+            MetadataValue::Chat(value) => Json::write_variant(value, writer),
+            MetadataValue::OptionalChat(value) => match value {
+                Some(value) => {
+                    true.write(writer)?;
+                    Json::write_variant(value, writer)
+                }
+                None => false.write(writer),
+            },
+            MetadataValue::Slot(value) => value.write(writer),
+            MetadataValue::Boolean(value) => value.write(writer),
+            MetadataValue::Rotation(value) => value.write(writer),
+            MetadataValue::Position(value) => BlockPosition::write_variant(value, writer),
+            MetadataValue::OptionalPosition(value) => match value {
+                Some(value) => {
+                    true.write(writer)?;
+                    BlockPosition::write_variant(value, writer)
+                }
+                None => false.write(writer),
+            },
+            MetadataValue::Direction(value) => value.write(writer),
+            MetadataValue::OptionalUuid(value) => value.write(writer),
+            MetadataValue::Nbt(value) => NbtBytes::write_variant(value, writer),
+            // 0 is absent, otherwise 1 + value
+            MetadataValue::OptionalVarInt(value) => VarInt::write_variant(&value.map(|value| value + 1).unwrap_or(0), writer),
+        }
+    }
+}
+
+/// Encoded entity metadata, changed entries are remembered until they are written with [`EntityMetadata::write_dirty`]
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct EntityMetadata {
+    entries: BTreeMap<u8, Vec<u8>>,
+    dirty: BTreeSet<u8>,
+}
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, index: u8, value: MetadataValue) -> anyhow::Result<()> {
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&value.type_id(), &mut bytes)?;
+        value.write_value(&mut bytes)?;
+        self.set_raw(index, bytes);
+        Ok(())
+    }
+
+    /// Bytes must contain the type id and the value
+    pub fn set_raw(&mut self, index: u8, bytes: Vec<u8>) {
+        debug_assert!(index != METADATA_END);
+        if self.entries.get(&index) != Some(&bytes) {
+            self.entries.insert(index, bytes);
+            self.dirty.insert(index);
+        }
+    }
+
+    pub fn update_flags(&mut self, index: u8, flag: i8, enabled: bool) -> anyhow::Result<()> {
+        let mut flags = self.get_byte(index).unwrap_or(0);
+        match enabled {
+            true => flags |= flag,
+            false => flags &= !flag,
+        }
+        self.set(index, MetadataValue::Byte(flags))
+    }
+
+    pub fn get_byte(&self, index: u8) -> Option<i8> {
+        match self.entries.get(&index)?.as_slice() {
+            [0, value] => Some(*value as i8),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty.clear()
+    }
+
+    pub fn write_all<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for (index, bytes) in &self.entries {
+            writer.write_byte(*index);
+            writer.write_bytes(bytes);
+        }
+        writer.write_byte(METADATA_END);
+        Ok(())
+    }
+
+    pub fn write_dirty<W: ProtocolWriter>(&mut self, writer: &mut W) -> anyhow::Result<()> {
+        for index in std::mem::take(&mut self.dirty) {
+            writer.write_byte(index);
+            writer.write_bytes(&self.entries[&index]);
+        }
+        writer.write_byte(METADATA_END);
+        Ok(())
+    }
+}
@@ -0,0 +1,519 @@
+use euclid::default::Vector3D;
+use uuid::Uuid;
+use bird_data::{Block, Shape10, Shape6};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::entity::{EntityKind, protocol_velocity};
+use crate::entity::tracker::{EntityTracker, TrackedEntity};
+use crate::protocol::{MoveVehiclePS2C, PlayerInputPC2S};
+use crate::world::{block_position, is_air, World};
+
+pub const MINECART_ENTITY_TYPE: i32 = 54;
+
+pub const MINECART_WIDTH: f64 = 0.98;
+pub const MINECART_HEIGHT: f64 = 0.7;
+/// Height of the seat above the minecart position
+pub const MINECART_PASSENGER_OFFSET: f64 = 0.0;
+/// Players are sitting a bit lower than other entities
+pub const PLAYER_RIDING_OFFSET: f64 = -0.35;
+
+const MAX_SPEED: f64 = 0.4;
+const GRAVITY: f64 = 0.04;
+const SLOPE_ACCELERATION: f64 = 0.0078125;
+const POWERED_ACCELERATION: f64 = 0.06;
+const POWERED_START_SPEED: f64 = 0.02;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RailShape {
+    NorthSouth,
+    EastWest,
+    AscendingEast,
+    AscendingWest,
+    AscendingNorth,
+    AscendingSouth,
+    SouthEast,
+    SouthWest,
+    NorthWest,
+    NorthEast,
+}
+
+impl RailShape {
+    pub const fn is_ascending(&self) -> bool {
+        matches!(self, RailShape::AscendingEast | RailShape::AscendingWest | RailShape::AscendingNorth | RailShape::AscendingSouth)
+    }
+
+    /// Directions to both ends of the rail, `y` is the height of the end
+    pub fn exits(&self) -> [Vector3D<i32>; 2] {
+        let vector = Vector3D::new;
+        match self {
+            RailShape::NorthSouth => [vector(0, 0, -1), vector(0, 0, 1)],
+            RailShape::EastWest => [vector(-1, 0, 0), vector(1, 0, 0)],
+            RailShape::AscendingEast => [vector(-1, -1, 0), vector(1, 0, 0)],
+            RailShape::AscendingWest => [vector(-1, 0, 0), vector(1, -1, 0)],
+            RailShape::AscendingNorth => [vector(0, 0, -1), vector(0, -1, 1)],
+            RailShape::AscendingSouth => [vector(0, -1, -1), vector(0, 0, 1)],
+            RailShape::SouthEast => [vector(0, 0, 1), vector(1, 0, 0)],
+            RailShape::SouthWest => [vector(0, 0, 1), vector(-1, 0, 0)],
+            RailShape::NorthWest => [vector(0, 0, -1), vector(-1, 0, 0)],
+            RailShape::NorthEast => [vector(0, 0, -1), vector(1, 0, 0)],
+        }
+    }
+}
+
+impl From<Shape10> for RailShape {
+    fn from(shape: Shape10) -> Self {
+        match shape {
+            Shape10::NorthSouth => RailShape::NorthSouth,
+            Shape10::EastWest => RailShape::EastWest,
+            Shape10::AscendingEast => RailShape::AscendingEast,
+            Shape10::AscendingWest => RailShape::AscendingWest,
+            Shape10::AscendingNorth => RailShape::AscendingNorth,
+            Shape10::AscendingSouth => RailShape::AscendingSouth,
+            Shape10::SouthEast => RailShape::SouthEast,
+            Shape10::SouthWest => RailShape::SouthWest,
+            Shape10::NorthWest => RailShape::NorthWest,
+            Shape10::NorthEast => RailShape::NorthEast,
+        }
+    }
+}
+
+impl From<Shape6> for RailShape {
+    fn from(shape: Shape6) -> Self {
+        match shape {
+            Shape6::NorthSouth => RailShape::NorthSouth,
+            Shape6::EastWest => RailShape::EastWest,
+            Shape6::AscendingEast => RailShape::AscendingEast,
+            Shape6::AscendingWest => RailShape::AscendingWest,
+            Shape6::AscendingNorth => RailShape::AscendingNorth,
+            Shape6::AscendingSouth => RailShape::AscendingSouth,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RailKind {
+    Normal,
+    /// Accelerates minecarts when powered and stops them otherwise
+    Powered {
+        powered: bool,
+    },
+    Detector,
+    Activator,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rail {
+    pub kind: RailKind,
+    pub shape: RailShape,
+}
+
+impl Rail {
+    pub fn from_state(state: i32) -> Option<Self> {
+        match Block::from_state(state as u32)? {
+            Block::Rail { shape, .. } => Some(Self { kind: RailKind::Normal, shape: shape.into() }),
+            Block::PoweredRail { powered, shape, .. } => Some(Self { kind: RailKind::Powered { powered }, shape: shape.into() }),
+            Block::DetectorRail { shape, .. } => Some(Self { kind: RailKind::Detector, shape: shape.into() }),
+            Block::ActivatorRail { shape, .. } => Some(Self { kind: RailKind::Activator, shape: shape.into() }),
+            _ => None,
+        }
+    }
+}
+
+pub fn rail_at(world: &World, position: Vector3D<i32>) -> Option<Rail> {
+    world.get_block_state(position).and_then(Rail::from_state)
+}
+
+/// Blocks are treated as full cubes, unloaded chunks are solid to stop minecarts at their borders
+fn is_solid(world: &World, position: Vector3D<i32>) -> bool {
+    match world.get_block_state(position) {
+        Some(state) => !is_air(state) && Rail::from_state(state).is_none(),
+        None => true,
+    }
+}
+
+/// Finds the rail under the position, the minecart stays a bit above the rail when it leaves a slope
+fn find_rail(world: &World, position: Vector3D<f64>) -> Option<(Vector3D<i32>, Rail)> {
+    let block = block_position(position);
+    let below = block - Vector3D::new(0, 1, 0);
+    match rail_at(world, below) {
+        Some(rail) => Some((below, rail)),
+        None => rail_at(world, block).map(|rail| (block, rail)),
+    }
+}
+
+/// Projects the position on the rail which is under it
+pub fn rail_position(world: &World, position: Vector3D<f64>) -> Option<Vector3D<f64>> {
+    let (block, rail) = find_rail(world, position)?;
+    let block = block.to_f64();
+    let [first, second] = rail.shape.exits().map(|exit| exit.to_f64());
+    let start = Vector3D::new(block.x + 0.5 + first.x * 0.5, block.y + 0.0625 + first.y * 0.5, block.z + 0.5 + first.z * 0.5);
+    let end = Vector3D::new(block.x + 0.5 + second.x * 0.5, block.y + 0.0625 + second.y * 0.5, block.z + 0.5 + second.z * 0.5);
+    let direction = Vector3D::new(end.x - start.x, (end.y - start.y) * 2.0, end.z - start.z);
+    let progress = if direction.x == 0.0 {
+        position.z - block.z
+    } else if direction.z == 0.0 {
+        position.x - block.x
+    } else {
+        ((position.x - start.x) * direction.x + (position.z - start.z) * direction.z) * 2.0
+    };
+    let mut result = start + direction * progress;
+    if direction.y < 0.0 {
+        result.y += 1.0;
+    } else if direction.y > 0.0 {
+        result.y += 0.5;
+    }
+    Some(result)
+}
+
+/// Position of the passenger which is riding the minecart
+pub fn seat_position(position: Vector3D<f64>, passenger: EntityKind) -> Vector3D<f64> {
+    let riding_offset = match passenger {
+        EntityKind::Player => PLAYER_RIDING_OFFSET,
+        EntityKind::Other { .. } => 0.0,
+    };
+    position + Vector3D::new(0.0, MINECART_PASSENGER_OFFSET + riding_offset, 0.0)
+}
+
+fn horizontal_length(velocity: Vector3D<f64>) -> f64 {
+    velocity.x.hypot(velocity.z)
+}
+
+fn wrap_degrees(degrees: f32) -> f32 {
+    let degrees = degrees % 360.0;
+    if degrees >= 180.0 {
+        degrees - 360.0
+    } else if degrees < -180.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    }
+}
+
+/// Server side physics of the minecart, [`Minecart::relay`] sends the result to viewers
+#[derive(Clone, PartialEq, Debug)]
+pub struct Minecart {
+    pub position: Vector3D<f64>,
+    /// Blocks per tick
+    pub velocity: Vector3D<f64>,
+    /// Degrees in `-180..180` like in vanilla, it is 0 when the minecart is moving to negative X
+    /// (not positive Z like other entities)
+    pub yaw: f32,
+    pub on_ground: bool,
+    /// Minecart is rotated by 180 degrees relative to its movement direction
+    flipped: bool,
+    /// Push of the riding player, used in the next tick
+    input: Vector3D<f64>,
+}
+
+impl Minecart {
+    pub fn new(position: Vector3D<f64>) -> Self {
+        Self {
+            position,
+            velocity: Vector3D::zero(),
+            yaw: 0.0,
+            on_ground: false,
+            flipped: false,
+            input: Vector3D::zero(),
+        }
+    }
+
+    pub fn to_tracked(&self, id: i32, uuid: Uuid) -> TrackedEntity {
+        let mut entity = TrackedEntity::new(id, uuid, EntityKind::Other {
+            entity_type: MINECART_ENTITY_TYPE,
+            data: 0,
+        }, self.position);
+        entity.yaw = self.yaw;
+        entity.velocity = protocol_velocity(self.velocity);
+        entity
+    }
+
+    /// `yaw` is the rotation of the riding player, only forward input pushes the minecart
+    pub fn handle_input(&mut self, packet: &PlayerInputPC2S, yaw: f32) {
+        if packet.forward <= 0.0 {
+            return;
+        }
+        let yaw = (yaw as f64).to_radians();
+        self.input = Vector3D::new(-yaw.sin(), 0.0, yaw.cos()) * (packet.forward as f64 * 0.1);
+    }
+
+    /// Minecarts are not controlled by passengers, the client is moved back to the server position
+    pub fn handle_move_vehicle<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&MoveVehiclePS2C {
+            pos: self.position,
+            yaw: self.yaw,
+            pitch: 0.0,
+        })
+    }
+
+    /// `occupied` is true if the minecart has passengers, they slow down the minecart
+    pub fn tick(&mut self, world: &World, occupied: bool) {
+        let previous = self.position;
+        let previous_yaw = self.yaw;
+        self.velocity.y -= GRAVITY;
+        match find_rail(world, self.position) {
+            Some((block, rail)) => self.move_along_track(world, block, rail, occupied),
+            None => self.come_off_track(world),
+        }
+        self.input = Vector3D::zero();
+        let delta = previous - self.position;
+        if delta.x * delta.x + delta.z * delta.z > 0.001 {
+            self.yaw = delta.z.atan2(delta.x).to_degrees() as f32;
+            if self.flipped {
+                self.yaw += 180.0;
+            }
+        }
+        let rotation = wrap_degrees(self.yaw - previous_yaw);
+        if rotation < -170.0 || rotation >= 170.0 {
+            self.yaw += 180.0;
+            self.flipped = !self.flipped;
+        }
+        self.yaw = wrap_degrees(self.yaw);
+    }
+
+    fn move_along_track(&mut self, world: &World, block: Vector3D<i32>, rail: Rail, occupied: bool) {
+        let start = rail_position(world, self.position);
+        let mut position = Vector3D::new(self.position.x, block.y as f64, self.position.z);
+        let (powered, mut braking) = match rail.kind {
+            RailKind::Powered { powered } => (powered, !powered),
+            _ => (false, false),
+        };
+        let slope = match rail.shape {
+            RailShape::AscendingEast => Some(Vector3D::new(-SLOPE_ACCELERATION, 0.0, 0.0)),
+            RailShape::AscendingWest => Some(Vector3D::new(SLOPE_ACCELERATION, 0.0, 0.0)),
+            RailShape::AscendingNorth => Some(Vector3D::new(0.0, 0.0, SLOPE_ACCELERATION)),
+            RailShape::AscendingSouth => Some(Vector3D::new(0.0, 0.0, -SLOPE_ACCELERATION)),
+            _ => None,
+        };
+        if let Some(slope) = slope {
+            self.velocity += slope;
+            position.y += 1.0;
+        }
+
+        let [first, second] = rail.shape.exits();
+        let mut direction = Vector3D::new((second.x - first.x) as f64, 0.0, (second.z - first.z) as f64);
+        if self.velocity.x * direction.x + self.velocity.z * direction.z < 0.0 {
+            direction = -direction;
+        }
+        let speed = horizontal_length(self.velocity).min(2.0);
+        let direction = direction / direction.length();
+        self.velocity = Vector3D::new(speed * direction.x, self.velocity.y, speed * direction.z);
+
+        let input = self.input;
+        if input.square_length() > 1.0E-4 && horizontal_length(self.velocity).powi(2) < 0.01 {
+            self.velocity += Vector3D::new(input.x * 0.1, 0.0, input.z * 0.1);
+            braking = false;
+        }
+        if braking {
+            self.velocity = match horizontal_length(self.velocity) < 0.03 {
+                true => Vector3D::zero(),
+                false => Vector3D::new(self.velocity.x * 0.5, 0.0, self.velocity.z * 0.5),
+            };
+        }
+
+        let start_x = block.x as f64 + 0.5 + first.x as f64 * 0.5;
+        let start_z = block.z as f64 + 0.5 + first.z as f64 * 0.5;
+        let rail_x = block.x as f64 + 0.5 + second.x as f64 * 0.5 - start_x;
+        let rail_z = block.z as f64 + 0.5 + second.z as f64 * 0.5 - start_z;
+        let progress = if rail_x == 0.0 {
+            position.z - block.z as f64
+        } else if rail_z == 0.0 {
+            position.x - block.x as f64
+        } else {
+            ((position.x - start_x) * rail_x + (position.z - start_z) * rail_z) * 2.0
+        };
+        position.x = start_x + rail_x * progress;
+        position.z = start_z + rail_z * progress;
+        self.position = position;
+
+        let scale = if occupied { 0.75 } else { 1.0 };
+        self.move_by(world, Vector3D::new(
+            (scale * self.velocity.x).clamp(-MAX_SPEED, MAX_SPEED),
+            0.0,
+            (scale * self.velocity.z).clamp(-MAX_SPEED, MAX_SPEED),
+        ));
+        let moved_to = block_position(self.position) - block;
+        if let Some(exit) = [first, second].into_iter().find(|exit| exit.y != 0 && moved_to.x == exit.x && moved_to.z == exit.z) {
+            self.position.y += exit.y as f64;
+        }
+
+        let slowdown = if occupied { 0.997 } else { 0.96 };
+        self.velocity = Vector3D::new(self.velocity.x * slowdown, 0.0, self.velocity.z * slowdown);
+
+        // Minecart gains speed while going down and loses it while going up
+        if let (Some(start), Some(end)) = (start, rail_position(world, self.position)) {
+            let energy = (start.y - end.y) * 0.05;
+            let speed = horizontal_length(self.velocity);
+            if speed > 0.0 {
+                self.velocity.x *= (speed + energy) / speed;
+                self.velocity.z *= (speed + energy) / speed;
+            }
+            self.position.y = end.y;
+        }
+
+        let moved_to = block_position(self.position) - block;
+        if moved_to.x != 0 || moved_to.z != 0 {
+            let speed = horizontal_length(self.velocity);
+            self.velocity = Vector3D::new(speed * moved_to.x as f64, self.velocity.y, speed * moved_to.z as f64);
+        }
+
+        if powered {
+            let speed = horizontal_length(self.velocity);
+            if speed > 0.01 {
+                self.velocity.x += self.velocity.x / speed * POWERED_ACCELERATION;
+                self.velocity.z += self.velocity.z / speed * POWERED_ACCELERATION;
+            } else {
+                // Powered rail next to a solid block pushes the minecart away from it
+                let solid = |x: i32, z: i32| is_solid(world, block + Vector3D::new(x, 0, z));
+                match rail.shape {
+                    RailShape::EastWest if solid(-1, 0) => self.velocity.x = POWERED_START_SPEED,
+                    RailShape::EastWest if solid(1, 0) => self.velocity.x = -POWERED_START_SPEED,
+                    RailShape::NorthSouth if solid(0, -1) => self.velocity.z = POWERED_START_SPEED,
+                    RailShape::NorthSouth if solid(0, 1) => self.velocity.z = -POWERED_START_SPEED,
+                    _ => {}
+                }
+            }
+        }
+        self.on_ground = true;
+    }
+
+    fn come_off_track(&mut self, world: &World) {
+        self.velocity.x = self.velocity.x.clamp(-MAX_SPEED, MAX_SPEED);
+        self.velocity.z = self.velocity.z.clamp(-MAX_SPEED, MAX_SPEED);
+        if self.on_ground {
+            self.velocity *= 0.5;
+        }
+        self.move_by(world, self.velocity);
+        if !self.on_ground {
+            self.velocity *= 0.95;
+        }
+    }
+
+    /// Moves the minecart stopping it at solid blocks, the minecart is checked as a point at its bottom
+    fn move_by(&mut self, world: &World, delta: Vector3D<f64>) {
+        let mut target = self.position + delta;
+        if (delta.x != 0.0 || delta.z != 0.0)
+            && is_solid(world, block_position(Vector3D::new(target.x, self.position.y, target.z))) {
+            target.x = self.position.x;
+            target.z = self.position.z;
+            self.velocity.x = 0.0;
+            self.velocity.z = 0.0;
+        }
+        self.on_ground = false;
+        if delta.y < 0.0 && is_solid(world, block_position(target)) {
+            target.y = target.y.floor() + 1.0;
+            self.velocity.y = 0.0;
+            self.on_ground = true;
+        }
+        self.position = target;
+    }
+
+    pub fn intersects(&self, other: &Minecart) -> bool {
+        let delta = other.position - self.position;
+        delta.x.abs() < MINECART_WIDTH && delta.z.abs() < MINECART_WIDTH && delta.y.abs() < MINECART_HEIGHT
+    }
+
+    /// Pushes intersecting minecarts apart, minecarts which are moving side by side are not pushed
+    pub fn push(&mut self, other: &mut Minecart) {
+        if !self.intersects(other) {
+            return;
+        }
+        let delta = Vector3D::new(other.position.x - self.position.x, 0.0, other.position.z - self.position.z);
+        let distance = delta.length();
+        if distance < 0.01 {
+            return;
+        }
+        let yaw = (self.yaw as f64).to_radians();
+        let facing = Vector3D::new(yaw.cos(), 0.0, yaw.sin());
+        if (delta / distance).dot(facing).abs() < 0.8 {
+            return;
+        }
+        let push = delta / distance * ((1.0 / distance).min(1.0) * 0.05);
+        let velocity = (self.velocity + other.velocity) * 0.5;
+        self.velocity = velocity - push;
+        other.velocity = velocity + push;
+    }
+
+    /// Sends the movement of the minecart to viewers and moves passengers to their seats
+    pub fn relay(&self, id: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        tracker.move_entity(id, self.position, self.yaw, 0.0, self.on_ground)?;
+        tracker.set_velocity(id, protocol_velocity(self.velocity))?;
+        let passengers = match tracker.get_entity(id) {
+            Some(entity) => entity.passengers().to_vec(),
+            None => return Ok(()),
+        };
+        for passenger_id in passengers {
+            let (kind, yaw, pitch) = match tracker.get_entity(passenger_id) {
+                Some(passenger) => (passenger.kind, passenger.yaw, passenger.pitch),
+                None => continue,
+            };
+            tracker.move_entity(passenger_id, seat_position(self.position, kind), yaw, pitch, false)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::world::{OVERWORLD_MIN_Y, OVERWORLD_SECTION_COUNT};
+    use super::*;
+
+    fn rail_world(rail: Block) -> World {
+        let mut world = World::new(OVERWORLD_SECTION_COUNT, OVERWORLD_MIN_Y);
+        world.get_or_create_chunk(Vector2D::new(0, 0));
+        let state = rail.get_state().unwrap() as i32;
+        for x in 0..16 {
+            world.set_block_state(Vector3D::new(x, -1, 0), 1);
+            world.set_block_state(Vector3D::new(x, 0, 0), state);
+        }
+        world
+    }
+
+    #[test]
+    fn rail_movement_test() {
+        let world = rail_world(Block::Rail { shape: Shape10::EastWest, waterlogged: false });
+        let mut minecart = Minecart::new(Vector3D::new(2.5, 0.0, 0.7));
+        minecart.velocity = Vector3D::new(0.3, 0.0, 0.1);
+        for _ in 0..10 {
+            minecart.tick(&world, false);
+        }
+        assert!(minecart.position.x > 3.5);
+        assert!((minecart.position.y - 0.0625).abs() < 1.0E-6);
+        assert!((minecart.position.z - 0.5).abs() < 1.0E-6);
+        assert!(minecart.velocity.x > 0.0 && minecart.velocity.x < 0.3);
+    }
+
+    #[test]
+    fn powered_rail_test() {
+        let world = rail_world(Block::PoweredRail { powered: true, shape: Shape6::EastWest, waterlogged: false });
+        let mut minecart = Minecart::new(Vector3D::new(4.5, 0.0625, 0.5));
+        minecart.velocity = Vector3D::new(0.1, 0.0, 0.0);
+        minecart.tick(&world, false);
+        assert!(minecart.velocity.x > 0.1);
+        let world = rail_world(Block::PoweredRail { powered: false, shape: Shape6::EastWest, waterlogged: false });
+        minecart.tick(&world, false);
+        assert!(minecart.velocity.x < 0.1);
+    }
+
+    #[test]
+    fn yaw_test() {
+        use bird_protocol::ProtocolPacket;
+        use crate::protocol::UpdateEntityPositionAndRotationPS2C;
+
+        let world = rail_world(Block::Rail { shape: Shape10::EastWest, waterlogged: false });
+        let mut minecart = Minecart::new(Vector3D::new(4.5, 0.0625, 0.5));
+        minecart.yaw = 90.0;
+        let mut tracker = EntityTracker::new(64.0);
+        tracker.add_viewer(1, Vector3D::zero()).unwrap();
+        tracker.add_entity(minecart.to_tracked(2, Uuid::nil())).unwrap();
+        tracker.get_viewer_mut(1).unwrap().packets.take();
+        // Moving to positive X is the half turn from the zero yaw
+        minecart.velocity = Vector3D::new(0.2, 0.0, 0.0);
+        minecart.tick(&world, false);
+        assert_eq!(minecart.yaw, -180.0);
+        minecart.relay(2, &mut tracker).unwrap();
+        let frames = tracker.get_viewer_mut(1).unwrap().packets.take();
+        let frame = frames.iter().find(|frame| frame[0] == UpdateEntityPositionAndRotationPS2C::ID as u8).unwrap();
+        assert_eq!(frame[8], 128);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::entity::PLAYER_ENTITY_TYPE;
+use crate::entity::armor_stand::ARMOR_STAND_ENTITY_TYPE;
+use crate::entity::minecart::MINECART_ENTITY_TYPE;
+use crate::protocol::EntityEventPS2C;
+
+// Entity type ids of 1.19.3 which are handling entity events
+
+pub const ALLAY_ENTITY_TYPE: i32 = 0;
+pub const ARROW_ENTITY_TYPE: i32 = 3;
+pub const AXOLOTL_ENTITY_TYPE: i32 = 4;
+pub const BEE_ENTITY_TYPE: i32 = 6;
+pub const CAT_ENTITY_TYPE: i32 = 10;
+pub const CAMEL_ENTITY_TYPE: i32 = 11;
+pub const CHICKEN_ENTITY_TYPE: i32 = 13;
+pub const COW_ENTITY_TYPE: i32 = 15;
+pub const DOLPHIN_ENTITY_TYPE: i32 = 17;
+pub const DONKEY_ENTITY_TYPE: i32 = 18;
+pub const ELDER_GUARDIAN_ENTITY_TYPE: i32 = 21;
+pub const EVOKER_FANGS_ENTITY_TYPE: i32 = 27;
+pub const FIREWORK_ROCKET_ENTITY_TYPE: i32 = 31;
+pub const FOX_ENTITY_TYPE: i32 = 32;
+pub const FROG_ENTITY_TYPE: i32 = 33;
+pub const GLOW_SQUID_ENTITY_TYPE: i32 = 37;
+pub const GOAT_ENTITY_TYPE: i32 = 38;
+pub const GUARDIAN_ENTITY_TYPE: i32 = 39;
+pub const HOGLIN_ENTITY_TYPE: i32 = 40;
+pub const HORSE_ENTITY_TYPE: i32 = 41;
+pub const IRON_GOLEM_ENTITY_TYPE: i32 = 44;
+pub const LLAMA_ENTITY_TYPE: i32 = 50;
+pub const MULE_ENTITY_TYPE: i32 = 61;
+pub const MOOSHROOM_ENTITY_TYPE: i32 = 62;
+pub const OCELOT_ENTITY_TYPE: i32 = 63;
+pub const PANDA_ENTITY_TYPE: i32 = 65;
+pub const PARROT_ENTITY_TYPE: i32 = 66;
+pub const PIG_ENTITY_TYPE: i32 = 68;
+pub const POLAR_BEAR_ENTITY_TYPE: i32 = 72;
+pub const RABBIT_ENTITY_TYPE: i32 = 75;
+pub const RAVAGER_ENTITY_TYPE: i32 = 76;
+pub const SHEEP_ENTITY_TYPE: i32 = 78;
+pub const SKELETON_HORSE_ENTITY_TYPE: i32 = 83;
+pub const SNOWBALL_ENTITY_TYPE: i32 = 87;
+pub const SPAWNER_MINECART_ENTITY_TYPE: i32 = 59;
+pub const SQUID_ENTITY_TYPE: i32 = 90;
+pub const STRIDER_ENTITY_TYPE: i32 = 92;
+pub const EGG_ENTITY_TYPE: i32 = 94;
+pub const TNT_MINECART_ENTITY_TYPE: i32 = 60;
+pub const TRADER_LLAMA_ENTITY_TYPE: i32 = 98;
+pub const TURTLE_ENTITY_TYPE: i32 = 101;
+pub const VILLAGER_ENTITY_TYPE: i32 = 103;
+pub const WARDEN_ENTITY_TYPE: i32 = 106;
+pub const WITCH_ENTITY_TYPE: i32 = 107;
+pub const WOLF_ENTITY_TYPE: i32 = 111;
+pub const ZOGLIN_ENTITY_TYPE: i32 = 112;
+pub const ZOMBIE_HORSE_ENTITY_TYPE: i32 = 114;
+pub const ZOMBIE_VILLAGER_ENTITY_TYPE: i32 = 115;
+pub const FISHING_BOBBER_ENTITY_TYPE: i32 = 118;
+
+/// Entity types which are not living, so they ignore statuses of living entities
+const NOT_LIVING_ENTITY_TYPES: &'static [i32] = &[
+    1, ARROW_ENTITY_TYPE, 8, 9, 19, 22, EVOKER_FANGS_ENTITY_TYPE, 28, 29, 30, FIREWORK_ROCKET_ENTITY_TYPE,
+    36, 45, 46, 47, 48, 49, 51, 53, MINECART_ENTITY_TYPE, 55, 56, 57, 58, SPAWNER_MINECART_ENTITY_TYPE,
+    TNT_MINECART_ENTITY_TYPE, 64, 73, 80, 85, SNOWBALL_ENTITY_TYPE, 88, EGG_ENTITY_TYPE, 95, 96, 97, 99, 110,
+    FISHING_BOBBER_ENTITY_TYPE,
+];
+
+const ANIMAL_ENTITY_TYPES: &'static [i32] = &[
+    ALLAY_ENTITY_TYPE, AXOLOTL_ENTITY_TYPE, BEE_ENTITY_TYPE, CAT_ENTITY_TYPE, CAMEL_ENTITY_TYPE, CHICKEN_ENTITY_TYPE,
+    COW_ENTITY_TYPE, DONKEY_ENTITY_TYPE, FOX_ENTITY_TYPE, FROG_ENTITY_TYPE, GOAT_ENTITY_TYPE,
+    HOGLIN_ENTITY_TYPE, HORSE_ENTITY_TYPE, LLAMA_ENTITY_TYPE, MULE_ENTITY_TYPE, MOOSHROOM_ENTITY_TYPE,
+    OCELOT_ENTITY_TYPE, PANDA_ENTITY_TYPE, PARROT_ENTITY_TYPE, PIG_ENTITY_TYPE, POLAR_BEAR_ENTITY_TYPE,
+    RABBIT_ENTITY_TYPE, SHEEP_ENTITY_TYPE, SKELETON_HORSE_ENTITY_TYPE, STRIDER_ENTITY_TYPE,
+    TRADER_LLAMA_ENTITY_TYPE, TURTLE_ENTITY_TYPE, WOLF_ENTITY_TYPE, ZOMBIE_HORSE_ENTITY_TYPE,
+];
+
+/// Wolves, cats, parrots and horses
+const TAMEABLE_ENTITY_TYPES: &'static [i32] = &[
+    CAT_ENTITY_TYPE, CAMEL_ENTITY_TYPE, DONKEY_ENTITY_TYPE, HORSE_ENTITY_TYPE, LLAMA_ENTITY_TYPE, MULE_ENTITY_TYPE,
+    PARROT_ENTITY_TYPE, SKELETON_HORSE_ENTITY_TYPE, TRADER_LLAMA_ENTITY_TYPE, WOLF_ENTITY_TYPE,
+    ZOMBIE_HORSE_ENTITY_TYPE,
+];
+
+/// Entity types which are handling the status, the client ignores the status for other entities
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntityStatusTarget {
+    Any,
+    Living,
+    /// Living entities except players and armor stands
+    Mob,
+    Player,
+    Animal,
+    Tameable,
+    Types(&'static [i32]),
+}
+
+impl EntityStatusTarget {
+    pub fn contains(&self, entity_type: i32) -> bool {
+        let living = !NOT_LIVING_ENTITY_TYPES.contains(&entity_type);
+        match self {
+            EntityStatusTarget::Any => true,
+            EntityStatusTarget::Living => living,
+            EntityStatusTarget::Mob => living &&
+                entity_type != PLAYER_ENTITY_TYPE && entity_type != ARMOR_STAND_ENTITY_TYPE,
+            EntityStatusTarget::Player => entity_type == PLAYER_ENTITY_TYPE,
+            EntityStatusTarget::Animal => ANIMAL_ENTITY_TYPES.contains(&entity_type),
+            EntityStatusTarget::Tameable => TAMEABLE_ENTITY_TYPES.contains(&entity_type),
+            EntityStatusTarget::Types(types) => types.contains(&entity_type),
+        }
+    }
+}
+
+macro_rules! entity_statuses {
+    ($($(#[$meta:meta])* $name:ident = $id:literal => $target:expr),* $(,)?) => {
+        /// Status of [`EntityEventPS2C`], ids are not unique because they are handled by each entity separately
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        pub enum EntityEventStatus {
+            $($(#[$meta])* $name,)*
+        }
+
+        impl EntityEventStatus {
+            pub const ALL: &'static [EntityEventStatus] = &[$(EntityEventStatus::$name,)*];
+
+            pub const fn id(&self) -> i8 {
+                match self {
+                    $(EntityEventStatus::$name => $id,)*
+                }
+            }
+
+            pub const fn target(&self) -> EntityStatusTarget {
+                match self {
+                    $(EntityEventStatus::$name => $target,)*
+                }
+            }
+        }
+    }
+}
+
+entity_statuses! {
+    TippedArrowParticles = 0 => EntityStatusTarget::Types(&[ARROW_ENTITY_TYPE]),
+    RabbitJump = 1 => EntityStatusTarget::Types(&[RABBIT_ENTITY_TYPE]),
+    SpawnerMinecartResetDelay = 1 => EntityStatusTarget::Types(&[SPAWNER_MINECART_ENTITY_TYPE]),
+    Hurt = 2 => EntityStatusTarget::Living,
+    Death = 3 => EntityStatusTarget::Living,
+    /// Particles of snowballs and eggs hitting something
+    ProjectileHit = 3 => EntityStatusTarget::Types(&[SNOWBALL_ENTITY_TYPE, EGG_ENTITY_TYPE]),
+    Attack = 4 => EntityStatusTarget::Types(&[
+        IRON_GOLEM_ENTITY_TYPE, EVOKER_FANGS_ENTITY_TYPE, RAVAGER_ENTITY_TYPE,
+        HOGLIN_ENTITY_TYPE, ZOGLIN_ENTITY_TYPE, WARDEN_ENTITY_TYPE,
+    ]),
+    TamingFailed = 6 => EntityStatusTarget::Tameable,
+    TamingSucceeded = 7 => EntityStatusTarget::Tameable,
+    WolfShake = 8 => EntityStatusTarget::Types(&[WOLF_ENTITY_TYPE]),
+    /// Finishes eating or drinking of the player itself
+    ItemUseFinished = 9 => EntityStatusTarget::Player,
+    SheepEatGrass = 10 => EntityStatusTarget::Types(&[SHEEP_ENTITY_TYPE]),
+    TntMinecartIgnite = 10 => EntityStatusTarget::Types(&[TNT_MINECART_ENTITY_TYPE]),
+    IronGolemOfferFlower = 11 => EntityStatusTarget::Types(&[IRON_GOLEM_ENTITY_TYPE]),
+    VillagerMating = 12 => EntityStatusTarget::Types(&[VILLAGER_ENTITY_TYPE]),
+    VillagerAngry = 13 => EntityStatusTarget::Types(&[VILLAGER_ENTITY_TYPE]),
+    VillagerHappy = 14 => EntityStatusTarget::Types(&[VILLAGER_ENTITY_TYPE]),
+    WitchMagic = 15 => EntityStatusTarget::Types(&[WITCH_ENTITY_TYPE]),
+    ZombieVillagerCure = 16 => EntityStatusTarget::Types(&[ZOMBIE_VILLAGER_ENTITY_TYPE]),
+    FireworkExplode = 17 => EntityStatusTarget::Types(&[FIREWORK_ROCKET_ENTITY_TYPE]),
+    LoveHearts = 18 => EntityStatusTarget::Animal,
+    SquidResetRotation = 19 => EntityStatusTarget::Types(&[SQUID_ENTITY_TYPE, GLOW_SQUID_ENTITY_TYPE]),
+    SpawnExplosion = 20 => EntityStatusTarget::Mob,
+    GuardianAttack = 21 => EntityStatusTarget::Types(&[GUARDIAN_ENTITY_TYPE, ELDER_GUARDIAN_ENTITY_TYPE]),
+    EnableReducedDebugInfo = 22 => EntityStatusTarget::Player,
+    DisableReducedDebugInfo = 23 => EntityStatusTarget::Player,
+    OpPermissionLevel0 = 24 => EntityStatusTarget::Player,
+    OpPermissionLevel1 = 25 => EntityStatusTarget::Player,
+    OpPermissionLevel2 = 26 => EntityStatusTarget::Player,
+    OpPermissionLevel3 = 27 => EntityStatusTarget::Player,
+    OpPermissionLevel4 = 28 => EntityStatusTarget::Player,
+    ShieldBlock = 29 => EntityStatusTarget::Living,
+    ShieldBreak = 30 => EntityStatusTarget::Living,
+    FishingRodPull = 31 => EntityStatusTarget::Types(&[FISHING_BOBBER_ENTITY_TYPE]),
+    ArmorStandHit = 32 => EntityStatusTarget::Types(&[ARMOR_STAND_ENTITY_TYPE]),
+    ThornsHurt = 33 => EntityStatusTarget::Living,
+    IronGolemRemoveFlower = 34 => EntityStatusTarget::Types(&[IRON_GOLEM_ENTITY_TYPE]),
+    TotemOfUndying = 35 => EntityStatusTarget::Living,
+    DrownHurt = 36 => EntityStatusTarget::Living,
+    BurnHurt = 37 => EntityStatusTarget::Living,
+    DolphinHappy = 38 => EntityStatusTarget::Types(&[DOLPHIN_ENTITY_TYPE]),
+    RavagerStunned = 39 => EntityStatusTarget::Types(&[RAVAGER_ENTITY_TYPE]),
+    OcelotTamingFailed = 40 => EntityStatusTarget::Types(&[OCELOT_ENTITY_TYPE]),
+    OcelotTamingSucceeded = 41 => EntityStatusTarget::Types(&[OCELOT_ENTITY_TYPE]),
+    VillagerSplash = 42 => EntityStatusTarget::Types(&[VILLAGER_ENTITY_TYPE]),
+    BadOmenClouds = 43 => EntityStatusTarget::Player,
+    SweetBerryBushHurt = 44 => EntityStatusTarget::Living,
+    FoxChew = 45 => EntityStatusTarget::Types(&[FOX_ENTITY_TYPE]),
+    TeleportParticles = 46 => EntityStatusTarget::Living,
+    MainHandBreak = 47 => EntityStatusTarget::Living,
+    OffHandBreak = 48 => EntityStatusTarget::Living,
+    HeadBreak = 49 => EntityStatusTarget::Living,
+    ChestBreak = 50 => EntityStatusTarget::Living,
+    LegsBreak = 51 => EntityStatusTarget::Living,
+    FeetBreak = 52 => EntityStatusTarget::Living,
+    HoneyBlockSlide = 53 => EntityStatusTarget::Any,
+    HoneyBlockJump = 54 => EntityStatusTarget::Living,
+    SwapHands = 55 => EntityStatusTarget::Living,
+    WolfStopShaking = 56 => EntityStatusTarget::Types(&[WOLF_ENTITY_TYPE]),
+    FreezeHurt = 57 => EntityStatusTarget::Living,
+    GoatLowerHead = 58 => EntityStatusTarget::Types(&[GOAT_ENTITY_TYPE]),
+    GoatRaiseHead = 59 => EntityStatusTarget::Types(&[GOAT_ENTITY_TYPE]),
+    DeathSmoke = 60 => EntityStatusTarget::Living,
+    WardenTendrilShake = 61 => EntityStatusTarget::Types(&[WARDEN_ENTITY_TYPE]),
+    WardenSonicBoom = 62 => EntityStatusTarget::Types(&[WARDEN_ENTITY_TYPE]),
+}
+
+impl EntityEventStatus {
+    pub fn applies_to(&self, entity_type: i32) -> bool {
+        self.target().contains(entity_type)
+    }
+
+    /// Finds the status which the entity type handles by the id
+    pub fn from_id(entity_type: i32, id: i8) -> Option<Self> {
+        Self::ALL.iter()
+            .find(|status| status.id() == id && status.applies_to(entity_type))
+            .copied()
+    }
+
+    /// Status which sets the op permission level of the player, it changes available commands
+    /// and the gamemode switcher on the client
+    pub const fn op_permission_level(level: u8) -> Self {
+        match level {
+            0 => EntityEventStatus::OpPermissionLevel0,
+            1 => EntityEventStatus::OpPermissionLevel1,
+            2 => EntityEventStatus::OpPermissionLevel2,
+            3 => EntityEventStatus::OpPermissionLevel3,
+            _ => EntityEventStatus::OpPermissionLevel4,
+        }
+    }
+
+    /// Debug builds panic if the entity type doesn't handle the status
+    pub fn packet(self, entity_id: i32, entity_type: i32) -> EntityEventPS2C {
+        debug_assert!(
+            self.applies_to(entity_type),
+            "Entity type {} doesn't handle the status {:?}", entity_type, self,
+        );
+        EntityEventPS2C { entity_id, status: self.id() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entity_status_test() {
+        assert_eq!(EntityEventStatus::from_id(SHEEP_ENTITY_TYPE, 10), Some(EntityEventStatus::SheepEatGrass));
+        assert_eq!(EntityEventStatus::from_id(TNT_MINECART_ENTITY_TYPE, 10), Some(EntityEventStatus::TntMinecartIgnite));
+        assert_eq!(EntityEventStatus::from_id(MINECART_ENTITY_TYPE, 10), None);
+        assert!(EntityEventStatus::TotemOfUndying.applies_to(PLAYER_ENTITY_TYPE));
+        assert!(!EntityEventStatus::Death.applies_to(MINECART_ENTITY_TYPE));
+        assert!(!EntityEventStatus::SpawnExplosion.applies_to(PLAYER_ENTITY_TYPE));
+        assert_eq!(EntityEventStatus::WolfShake.packet(1, WOLF_ENTITY_TYPE).status, 8);
+        assert_eq!(EntityEventStatus::op_permission_level(4).id(), 28);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn invalid_entity_status_test() {
+        EntityEventStatus::WolfShake.packet(1, PLAYER_ENTITY_TYPE);
+    }
+}
@@ -0,0 +1,712 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use euclid::default::{Vector2D, Vector3D};
+use uuid::Uuid;
+use bird_protocol::{anyhow, ProtocolPacket, ProtocolWritable};
+use crate::connection::{PacketQueue, PacketSender};
+use crate::entity::EntityKind;
+use crate::entity::metadata::EntityMetadata;
+use crate::protocol::*;
+use crate::viewers::{Viewer, Viewers};
+use crate::world::chunk_position;
+
+/// Relative move packets are using 1/4096 of block as unit
+const DELTA_SCALE: f64 = 4096.0;
+
+/// State of the entity which was sent to its viewers, updates are sent as the difference from it
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct SentState {
+    position: Vector3D<f64>,
+    yaw: f32,
+    pitch: f32,
+    head_yaw: f32,
+    velocity: Vector3D<i16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackedEntity {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub kind: EntityKind,
+    pub position: Vector3D<f64>,
+    /// Rotation is in degrees like in vanilla packets, it is wrapped when it is sent
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    pub velocity: Vector3D<i16>,
+    pub on_ground: bool,
+    pub metadata: EntityMetadata,
+    /// Indexed by [`EquipmentSlot`]
+    pub equipment: [Option<OwnedSlot>; 6],
+    /// Entity which is holding the lead, changed with [`EntityTracker::set_leash_holder`]
+    leash_holder: Option<i32>,
+    passengers: Vec<i32>,
+    vehicle: Option<i32>,
+    viewers: HashSet<i32>,
+    /// Viewers which can't see the entity even in range
+    hidden_from: HashSet<i32>,
+    sent: SentState,
+}
+
+impl TrackedEntity {
+    pub fn new(id: i32, uuid: Uuid, kind: EntityKind, position: Vector3D<f64>) -> Self {
+        Self {
+            id,
+            uuid,
+            kind,
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            velocity: Vector3D::zero(),
+            on_ground: false,
+            metadata: EntityMetadata::new(),
+            equipment: Default::default(),
+            leash_holder: None,
+            passengers: Vec::new(),
+            vehicle: None,
+            viewers: HashSet::new(),
+            hidden_from: HashSet::new(),
+            sent: SentState { position, yaw: 0.0, pitch: 0.0, head_yaw: 0.0, velocity: Vector3D::zero() },
+        }
+    }
+
+    pub fn viewers(&self) -> &HashSet<i32> {
+        &self.viewers
+    }
+
+    pub fn is_hidden_from(&self, viewer_id: i32) -> bool {
+        self.hidden_from.contains(&viewer_id)
+    }
+
+    pub fn leash_holder(&self) -> Option<i32> {
+        self.leash_holder
+    }
+
+    pub fn passengers(&self) -> &[i32] {
+        &self.passengers
+    }
+
+    pub fn vehicle(&self) -> Option<i32> {
+        self.vehicle
+    }
+
+    fn passengers_packet(&self) -> SetPassengersPS2C {
+        SetPassengersPS2C { entity_id: self.id, passengers: Cow::Borrowed(&self.passengers) }
+    }
+
+    fn current_state(&self) -> SentState {
+        SentState {
+            position: self.position,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            head_yaw: self.head_yaw,
+            velocity: self.velocity,
+        }
+    }
+
+    fn write_spawn<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        self.write_spawn_entity(sender)?;
+        if !self.metadata.is_empty() {
+            let mut metadata = Vec::new();
+            self.metadata.write_all(&mut metadata)?;
+            sender.send_packet(&SetEntityMetadataPS2C { entity_id: self.id, metadata: &metadata })?;
+        }
+        let equipment: Vec<_> = EquipmentSlot::ALL.iter()
+            .zip(self.equipment.iter())
+            .filter_map(|(slot, item)| item.as_ref().map(|item| (*slot, Some(item.as_slot()))))
+            .collect();
+        if !equipment.is_empty() {
+            sender.send_packet(&SetEquipmentPS2C { entity_id: self.id, equipment: Cow::Owned(equipment) })?;
+        }
+        if !self.passengers.is_empty() {
+            sender.send_packet(&self.passengers_packet())?;
+        }
+        if let Some(holding_entity_id) = self.leash_holder {
+            sender.send_packet(&LinkEntitiesPS2C { attached_entity_id: self.id, holding_entity_id })?;
+        }
+        Ok(())
+    }
+
+    /// The entity is spawned in the sent state, so coalesced updates are applied by the flush like for other viewers
+    fn write_spawn_entity<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        let sent = &self.sent;
+        match self.kind {
+            EntityKind::Player => {
+                sender.send_packet(&SpawnPlayerPS2C {
+                    entity_id: self.id,
+                    player_uuid: self.uuid,
+                    position: sent.position,
+                    yaw: sent.yaw,
+                    pitch: sent.pitch,
+                })?;
+                sender.send_packet(&SetHeadRotationPS2C { entity_id: self.id, head_yaw: sent.head_yaw })
+            }
+            EntityKind::Other { entity_type, data } => sender.send_packet(&SpawnEntityPS2C {
+                entity_id: self.id,
+                entity_uuid: self.uuid,
+                entity_type,
+                position: sent.position,
+                pitch: sent.pitch,
+                yaw: sent.yaw,
+                head_yaw: sent.head_yaw,
+                data,
+                velocity: sent.velocity,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EntityViewer {
+    pub position: Vector3D<f64>,
+    pub packets: PacketQueue,
+    visible: HashSet<i32>,
+}
+
+impl EntityViewer {
+    pub fn visible(&self) -> &HashSet<i32> {
+        &self.visible
+    }
+}
+
+impl Viewer for EntityViewer {
+    fn packets(&mut self) -> &mut PacketQueue {
+        &mut self.packets
+    }
+
+    fn position(&self) -> Option<Vector3D<f64>> {
+        Some(self.position)
+    }
+}
+
+/// Keeps track of entities and players which are seeing them.
+/// Viewers are identified by the entity id of their player, so the player is never spawned for himself
+#[derive(Clone, Debug)]
+pub struct EntityTracker {
+    view_distance: f64,
+    entities: HashMap<i32, TrackedEntity>,
+    index: HashMap<Vector2D<i32>, HashSet<i32>>,
+    viewers: HashMap<i32, EntityViewer>,
+    /// Movement, head rotation, velocity and metadata are sent by [`EntityTracker::flush`]
+    coalescing: bool,
+    /// Entities with updates which are not flushed yet
+    pending: HashSet<i32>,
+}
+
+fn position_delta(from: Vector3D<f64>, to: Vector3D<f64>) -> Option<Vector3D<i16>> {
+    let delta = (to * DELTA_SCALE).round() - (from * DELTA_SCALE).round();
+    let range = i16::MIN as f64..=i16::MAX as f64;
+    match range.contains(&delta.x) && range.contains(&delta.y) && range.contains(&delta.z) {
+        true => Some(Vector3D::new(delta.x as i16, delta.y as i16, delta.z as i16)),
+        false => None,
+    }
+}
+
+fn send_to<P: ProtocolPacket + ProtocolWritable>(
+    viewers: &mut HashMap<i32, EntityViewer>,
+    ids: &HashSet<i32>,
+    packet: &P,
+) -> anyhow::Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    Viewers::all(viewers.iter_mut().filter(|(id, _)| ids.contains(id)).map(|(_, viewer)| viewer)).send(packet)
+}
+
+/// Sends relative move packets from the sent position if the delta fits, teleport otherwise
+fn send_movement(viewers: &mut HashMap<i32, EntityViewer>, entity: &mut TrackedEntity) -> anyhow::Result<()> {
+    let (id, position, yaw, pitch, on_ground) = (entity.id, entity.position, entity.yaw, entity.pitch, entity.on_ground);
+    let rotated = entity.sent.yaw != yaw || entity.sent.pitch != pitch;
+    match (position_delta(entity.sent.position, position), rotated) {
+        (Some(delta), _) if delta == Vector3D::zero() && !rotated => {}
+        (Some(delta), false) => send_to(viewers, &entity.viewers, &UpdateEntityPositionPS2C {
+            entity_id: id,
+            delta,
+            on_ground,
+        })?,
+        (Some(delta), true) if delta == Vector3D::zero() => send_to(viewers, &entity.viewers, &UpdateEntityRotationPS2C {
+            entity_id: id,
+            yaw,
+            pitch,
+            on_ground,
+        })?,
+        (Some(delta), true) => send_to(viewers, &entity.viewers, &UpdateEntityPositionAndRotationPS2C {
+            entity_id: id,
+            delta,
+            yaw,
+            pitch,
+            on_ground,
+        })?,
+        (None, _) => send_to(viewers, &entity.viewers, &TeleportEntityPS2C {
+            entity_id: id,
+            position,
+            yaw,
+            pitch,
+            on_ground,
+        })?,
+    }
+    entity.sent.position = position;
+    entity.sent.yaw = yaw;
+    entity.sent.pitch = pitch;
+    Ok(())
+}
+
+/// Sends head rotation, velocity and metadata which were changed since they were sent
+fn send_changes(viewers: &mut HashMap<i32, EntityViewer>, entity: &mut TrackedEntity) -> anyhow::Result<()> {
+    let id = entity.id;
+    if entity.sent.head_yaw != entity.head_yaw {
+        entity.sent.head_yaw = entity.head_yaw;
+        send_to(viewers, &entity.viewers, &SetHeadRotationPS2C { entity_id: id, head_yaw: entity.head_yaw })?;
+    }
+    if entity.sent.velocity != entity.velocity {
+        entity.sent.velocity = entity.velocity;
+        send_to(viewers, &entity.viewers, &SetEntityVelocityPS2C { entity_id: id, velocity: entity.velocity })?;
+    }
+    if entity.metadata.is_dirty() {
+        let mut metadata = Vec::new();
+        entity.metadata.write_dirty(&mut metadata)?;
+        send_to(viewers, &entity.viewers, &SetEntityMetadataPS2C { entity_id: id, metadata: &metadata })?;
+    }
+    Ok(())
+}
+
+fn remove_entities_packet(ids: Vec<i32>) -> RemoveEntitiesPS2C<'static> {
+    RemoveEntitiesPS2C { entity_ids: Cow::Owned(ids) }
+}
+
+/// Spawns the entity for the viewer. The client ignores passengers which are not spawned yet,
+/// so passengers of the vehicle are resent if the viewer is seeing it
+fn spawn_for(
+    entities: &mut HashMap<i32, TrackedEntity>,
+    id: i32,
+    viewer_id: i32,
+    viewer: &mut EntityViewer,
+) -> anyhow::Result<()> {
+    let entity = entities.get_mut(&id).unwrap();
+    entity.write_spawn(&mut viewer.packets)?;
+    entity.viewers.insert(viewer_id);
+    viewer.visible.insert(id);
+    if let Some(vehicle) = entity.vehicle.and_then(|vehicle| entities.get(&vehicle)) {
+        if vehicle.viewers.contains(&viewer_id) {
+            viewer.packets.send_packet(&vehicle.passengers_packet())?;
+        }
+    }
+    Ok(())
+}
+
+impl EntityTracker {
+    /// View distance is in blocks
+    pub fn new(view_distance: f64) -> Self {
+        Self {
+            view_distance,
+            entities: HashMap::new(),
+            index: HashMap::new(),
+            viewers: HashMap::new(),
+            coalescing: false,
+            pending: HashSet::new(),
+        }
+    }
+
+    pub fn is_coalescing(&self) -> bool {
+        self.coalescing
+    }
+
+    /// Updates of the entity in one tick are sent as one packet of each kind by [`EntityTracker::flush`],
+    /// the last position wins if the entity is teleported. Pending updates are sent by the next flush
+    pub fn set_coalescing(&mut self, coalescing: bool) {
+        self.coalescing = coalescing;
+    }
+
+    /// Sends coalesced updates to viewers, it is called at the end of the tick
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        for id in std::mem::take(&mut self.pending) {
+            if let Some(entity) = self.entities.get_mut(&id) {
+                send_movement(&mut self.viewers, entity)?;
+                send_changes(&mut self.viewers, entity)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the updates of the entity now or leaves them to the flush
+    fn send_updates(&mut self, id: i32) -> anyhow::Result<()> {
+        if self.coalescing {
+            self.pending.insert(id);
+            return Ok(());
+        }
+        match self.entities.get_mut(&id) {
+            Some(entity) => {
+                send_movement(&mut self.viewers, entity)?;
+                send_changes(&mut self.viewers, entity)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_entity(&self, id: i32) -> Option<&TrackedEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item=&TrackedEntity> {
+        self.entities.values()
+    }
+
+    pub fn get_viewer_mut(&mut self, id: i32) -> Option<&mut EntityViewer> {
+        self.viewers.get_mut(&id)
+    }
+
+    fn is_in_range(&self, viewer: Vector3D<f64>, entity: Vector3D<f64>) -> bool {
+        let dx = viewer.x - entity.x;
+        let dz = viewer.z - entity.z;
+        dx * dx + dz * dz <= self.view_distance * self.view_distance
+    }
+
+    fn entities_in_range(&self, viewer_id: i32, position: Vector3D<f64>) -> HashSet<i32> {
+        let center = chunk_position(position.x, position.z);
+        let radius = (self.view_distance / 16.0).ceil() as i32;
+        let mut result = HashSet::new();
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                if let Some(ids) = self.index.get(&Vector2D::new(center.x + x, center.y + z)) {
+                    result.extend(ids.iter().copied().filter(|id| {
+                        let entity = &self.entities[id];
+                        *id != viewer_id && !entity.hidden_from.contains(&viewer_id) && self.is_in_range(position, entity.position)
+                    }));
+                }
+            }
+        }
+        result
+    }
+
+    /// Spawns the entity for viewers which came into range and removes it for viewers which went out of range
+    fn refresh_entity(&mut self, id: i32) -> anyhow::Result<()> {
+        let entity = match self.entities.get(&id) {
+            Some(entity) => entity,
+            None => return Ok(()),
+        };
+        let mut spawn = Vec::new();
+        let mut remove = Vec::new();
+        for (viewer_id, viewer) in &self.viewers {
+            if *viewer_id == id { continue; }
+            let can_see = !entity.hidden_from.contains(viewer_id) && self.is_in_range(viewer.position, entity.position);
+            match (can_see, viewer.visible.contains(&id)) {
+                (true, false) => spawn.push(*viewer_id),
+                (false, true) => remove.push(*viewer_id),
+                _ => {}
+            }
+        }
+        for viewer_id in spawn {
+            spawn_for(&mut self.entities, id, viewer_id, self.viewers.get_mut(&viewer_id).unwrap())?;
+        }
+        let entity = self.entities.get_mut(&id).unwrap();
+        for viewer_id in remove {
+            let viewer = self.viewers.get_mut(&viewer_id).unwrap();
+            viewer.packets.send_packet(&remove_entities_packet(vec![id]))?;
+            viewer.visible.remove(&id);
+            entity.viewers.remove(&viewer_id);
+        }
+        Ok(())
+    }
+
+    pub fn add_entity(&mut self, mut entity: TrackedEntity) -> anyhow::Result<()> {
+        // Entity ids are unique, so the old entity is removed for its viewers
+        self.remove_entity(entity.id)?;
+        entity.metadata.clear_dirty();
+        entity.sent = entity.current_state();
+        let id = entity.id;
+        self.index.entry(chunk_position(entity.position.x, entity.position.z)).or_default().insert(id);
+        self.entities.insert(id, entity);
+        self.refresh_entity(id)
+    }
+
+    pub fn remove_entity(&mut self, id: i32) -> anyhow::Result<Option<TrackedEntity>> {
+        let entity = match self.entities.remove(&id) {
+            Some(entity) => entity,
+            None => return Ok(None),
+        };
+        self.pending.remove(&id);
+        let chunk = chunk_position(entity.position.x, entity.position.z);
+        if let Some(ids) = self.index.get_mut(&chunk) {
+            ids.remove(&id);
+            if ids.is_empty() { self.index.remove(&chunk); }
+        }
+        send_to(&mut self.viewers, &entity.viewers, &remove_entities_packet(vec![id]))?;
+        for viewer_id in &entity.viewers {
+            self.viewers.get_mut(viewer_id).map(|viewer| viewer.visible.remove(&id));
+        }
+        // Viewers are dismounting passengers of removed entities by themselves
+        for passenger in &entity.passengers {
+            self.entities.get_mut(passenger).map(|passenger| passenger.vehicle = None);
+        }
+        if let Some(vehicle) = entity.vehicle.and_then(|vehicle| self.entities.get_mut(&vehicle)) {
+            vehicle.passengers.retain(|passenger| *passenger != id);
+            send_to(&mut self.viewers, &vehicle.viewers, &vehicle.passengers_packet())?;
+        }
+        let leashed: Vec<_> = self.entities.values()
+            .filter(|leashed| leashed.leash_holder == Some(id))
+            .map(|leashed| leashed.id)
+            .collect();
+        for leashed in leashed {
+            self.set_leash_holder(leashed, None)?;
+        }
+        Ok(Some(entity))
+    }
+
+    /// Removes the entity for the viewer until [`EntityTracker::show_entity`] is called
+    pub fn hide_entity(&mut self, id: i32, viewer_id: i32) -> anyhow::Result<()> {
+        let entity = match self.entities.get_mut(&id) {
+            Some(entity) => entity,
+            None => return Ok(()),
+        };
+        entity.hidden_from.insert(viewer_id);
+        if entity.viewers.remove(&viewer_id) {
+            let viewer = self.viewers.get_mut(&viewer_id).unwrap();
+            viewer.visible.remove(&id);
+            viewer.packets.send_packet(&remove_entities_packet(vec![id]))?;
+        }
+        Ok(())
+    }
+
+    /// Spawns the entity for the viewer again if it is in range
+    pub fn show_entity(&mut self, id: i32, viewer_id: i32) -> anyhow::Result<()> {
+        match self.entities.get_mut(&id) {
+            Some(entity) if entity.hidden_from.remove(&viewer_id) => self.refresh_entity(id),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends relative move packets if the delta fits, teleport otherwise. Yaw and pitch are in degrees
+    pub fn move_entity(&mut self, id: i32, position: Vector3D<f64>, yaw: f32, pitch: f32, on_ground: bool) -> anyhow::Result<()> {
+        let entity = match self.entities.get_mut(&id) {
+            Some(entity) => entity,
+            None => return Ok(()),
+        };
+        let old_position = entity.position;
+        entity.position = position;
+        entity.yaw = yaw;
+        entity.pitch = pitch;
+        entity.on_ground = on_ground;
+        self.send_updates(id)?;
+        let old_chunk = chunk_position(old_position.x, old_position.z);
+        let new_chunk = chunk_position(position.x, position.z);
+        if old_chunk != new_chunk {
+            if let Some(ids) = self.index.get_mut(&old_chunk) {
+                ids.remove(&id);
+                if ids.is_empty() { self.index.remove(&old_chunk); }
+            }
+            self.index.entry(new_chunk).or_default().insert(id);
+        }
+        self.refresh_entity(id)
+    }
+
+    pub fn set_head_yaw(&mut self, id: i32, head_yaw: f32) -> anyhow::Result<()> {
+        match self.entities.get_mut(&id) {
+            Some(entity) if entity.head_yaw != head_yaw => {
+                entity.head_yaw = head_yaw;
+                self.send_updates(id)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_velocity(&mut self, id: i32, velocity: Vector3D<i16>) -> anyhow::Result<()> {
+        match self.entities.get_mut(&id) {
+            Some(entity) if entity.velocity != velocity => {
+                entity.velocity = velocity;
+                self.send_updates(id)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends changed metadata entries to viewers of the entity
+    pub fn update_metadata<F>(&mut self, id: i32, update: F) -> anyhow::Result<()>
+        where F: FnOnce(&mut EntityMetadata) -> anyhow::Result<()> {
+        let entity = match self.entities.get_mut(&id) {
+            Some(entity) => entity,
+            None => return Ok(()),
+        };
+        update(&mut entity.metadata)?;
+        if !entity.metadata.is_dirty() {
+            return Ok(());
+        }
+        self.send_updates(id)
+    }
+
+    pub fn set_equipment(&mut self, id: i32, slot: EquipmentSlot, item: Option<OwnedSlot>) -> anyhow::Result<()> {
+        match self.entities.get_mut(&id) {
+            Some(entity) if entity.equipment[slot as usize] != item => {
+                send_to(&mut self.viewers, &entity.viewers, &SetEquipmentPS2C {
+                    entity_id: id,
+                    equipment: Cow::Borrowed(&[(slot, item.as_ref().map(|item| item.as_slot()))]),
+                })?;
+                entity.equipment[slot as usize] = item;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Attaches the lead of the entity to the holder, `None` detaches it
+    pub fn set_leash_holder(&mut self, id: i32, holder: Option<i32>) -> anyhow::Result<()> {
+        match self.entities.get_mut(&id) {
+            Some(entity) if entity.leash_holder != holder => {
+                entity.leash_holder = holder;
+                send_to(&mut self.viewers, &entity.viewers, &LinkEntitiesPS2C {
+                    attached_entity_id: id,
+                    holding_entity_id: holder.unwrap_or(0),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Replaces passengers of the vehicle, passengers which are riding another vehicle are dismounted from it
+    pub fn set_passengers(&mut self, vehicle_id: i32, passengers: Vec<i32>) -> anyhow::Result<()> {
+        if !self.entities.contains_key(&vehicle_id) {
+            return Ok(());
+        }
+        let mut passengers: Vec<_> = passengers.into_iter()
+            .filter(|passenger| *passenger != vehicle_id && self.entities.contains_key(passenger))
+            .collect();
+        passengers.dedup();
+        let vehicle = self.entities.get_mut(&vehicle_id).unwrap();
+        let old_passengers = std::mem::replace(&mut vehicle.passengers, passengers.clone());
+        for passenger in old_passengers {
+            self.entities.get_mut(&passenger).map(|passenger| passenger.vehicle = None);
+        }
+        for passenger_id in &passengers {
+            let passenger = self.entities.get_mut(passenger_id).unwrap();
+            let previous = passenger.vehicle.replace(vehicle_id);
+            if let Some(previous) = previous.filter(|previous| *previous != vehicle_id) {
+                if let Some(previous) = self.entities.get_mut(&previous) {
+                    previous.passengers.retain(|passenger| passenger != passenger_id);
+                    send_to(&mut self.viewers, &previous.viewers, &previous.passengers_packet())?;
+                }
+            }
+        }
+        let vehicle = &self.entities[&vehicle_id];
+        send_to(&mut self.viewers, &vehicle.viewers, &vehicle.passengers_packet())
+    }
+
+    pub fn dismount(&mut self, passenger_id: i32) -> anyhow::Result<()> {
+        let vehicle_id = match self.entities.get(&passenger_id).and_then(|passenger| passenger.vehicle) {
+            Some(vehicle_id) => vehicle_id,
+            None => return Ok(()),
+        };
+        let mut passengers = self.entities[&vehicle_id].passengers.clone();
+        passengers.retain(|passenger| *passenger != passenger_id);
+        self.set_passengers(vehicle_id, passengers)
+    }
+
+    /// Relays `MoveVehicle` of the controlling (first) passenger to viewers of the vehicle,
+    /// returns the vehicle id if it was moved
+    pub fn move_vehicle(&mut self, passenger_id: i32, packet: &MoveVehiclePC2S) -> anyhow::Result<Option<i32>> {
+        let vehicle = match self.entities.get(&passenger_id).and_then(|passenger| passenger.vehicle) {
+            Some(vehicle_id) => &self.entities[&vehicle_id],
+            None => return Ok(None),
+        };
+        if vehicle.passengers.first() != Some(&passenger_id) {
+            return Ok(None);
+        }
+        let (vehicle_id, on_ground) = (vehicle.id, vehicle.on_ground);
+        self.move_entity(vehicle_id, packet.pos, packet.yaw, packet.pitch, on_ground)?;
+        Ok(Some(vehicle_id))
+    }
+
+    pub fn add_viewer(&mut self, id: i32, position: Vector3D<f64>) -> anyhow::Result<()> {
+        self.viewers.insert(id, EntityViewer {
+            position,
+            packets: PacketQueue::new(),
+            visible: HashSet::new(),
+        });
+        self.move_viewer(id, position)
+    }
+
+    /// Recomputes visible entities of the viewer, removed entities are sent in one packet
+    pub fn move_viewer(&mut self, id: i32, position: Vector3D<f64>) -> anyhow::Result<()> {
+        let visible = self.entities_in_range(id, position);
+        let viewer = match self.viewers.get_mut(&id) {
+            Some(viewer) => viewer,
+            None => return Ok(()),
+        };
+        viewer.position = position;
+        let removed: Vec<_> = viewer.visible.difference(&visible).copied().collect();
+        if !removed.is_empty() {
+            for entity_id in &removed {
+                self.entities.get_mut(entity_id).map(|entity| entity.viewers.remove(&id));
+            }
+            viewer.packets.send_packet(&remove_entities_packet(removed))?;
+        }
+        viewer.visible.retain(|entity_id| visible.contains(entity_id));
+        for entity_id in visible {
+            if !viewer.visible.contains(&entity_id) {
+                spawn_for(&mut self.entities, entity_id, id, viewer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The viewer is removed without sending packets, because it is expected that the player is disconnected
+    pub fn remove_viewer(&mut self, id: i32) -> Option<EntityViewer> {
+        let viewer = self.viewers.remove(&id)?;
+        for entity in self.entities.values_mut() {
+            entity.viewers.remove(&id);
+            entity.hidden_from.remove(&id);
+        }
+        Some(viewer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::metadata::MetadataValue;
+    use super::*;
+
+    fn packet_ids(tracker: &mut EntityTracker, viewer_id: i32) -> Vec<u8> {
+        tracker.get_viewer_mut(viewer_id).unwrap().packets.take().iter().map(|frame| frame[0]).collect()
+    }
+
+    #[test]
+    fn coalescing_test() {
+        let mut tracker = EntityTracker::new(64.0);
+        tracker.set_coalescing(true);
+        tracker.add_viewer(1, Vector3D::zero()).unwrap();
+        let kind = EntityKind::Other { entity_type: 0, data: 0 };
+        tracker.add_entity(TrackedEntity::new(2, Uuid::nil(), kind, Vector3D::new(1.0, 0.0, 0.0))).unwrap();
+        assert_eq!(packet_ids(&mut tracker, 1), [SpawnEntityPS2C::ID as u8]);
+        for step in 1..=4u8 {
+            tracker.move_entity(2, Vector3D::new(1.0 + step as f64 * 0.25, 0.0, 0.0), 0.0, 0.0, true).unwrap();
+            tracker.update_metadata(2, |metadata| metadata.set(step, MetadataValue::Byte(step as i8))).unwrap();
+        }
+        assert!(packet_ids(&mut tracker, 1).is_empty());
+        tracker.flush().unwrap();
+        assert_eq!(packet_ids(&mut tracker, 1), [UpdateEntityPositionPS2C::ID as u8, SetEntityMetadataPS2C::ID as u8]);
+        // The viewer which is added in the middle of the tick sees the entity at the sent position
+        tracker.move_entity(2, Vector3D::new(40.0, 0.0, 0.0), 90.0, 0.0, true).unwrap();
+        tracker.move_entity(2, Vector3D::new(20.0, 0.0, 0.0), 90.0, 0.0, true).unwrap();
+        tracker.add_viewer(3, Vector3D::new(10.0, 0.0, 0.0)).unwrap();
+        tracker.flush().unwrap();
+        assert_eq!(packet_ids(&mut tracker, 1), [TeleportEntityPS2C::ID as u8]);
+        assert_eq!(packet_ids(&mut tracker, 3), [SpawnEntityPS2C::ID as u8, SetEntityMetadataPS2C::ID as u8, TeleportEntityPS2C::ID as u8]);
+        tracker.set_coalescing(false);
+        tracker.set_head_yaw(2, 45.0).unwrap();
+        assert_eq!(packet_ids(&mut tracker, 1), [SetHeadRotationPS2C::ID as u8]);
+    }
+
+    #[test]
+    fn rotation_test() {
+        let mut tracker = EntityTracker::new(64.0);
+        tracker.add_viewer(1, Vector3D::zero()).unwrap();
+        let kind = EntityKind::Other { entity_type: 0, data: 0 };
+        tracker.add_entity(TrackedEntity::new(2, Uuid::nil(), kind, Vector3D::zero())).unwrap();
+        tracker.get_viewer_mut(1).unwrap().packets.take();
+        // Angles are sent in steps of 1/256 of a turn
+        tracker.move_entity(2, Vector3D::zero(), -90.0, 45.0, true).unwrap();
+        tracker.set_head_yaw(2, 450.0).unwrap();
+        let frames = tracker.get_viewer_mut(1).unwrap().packets.take();
+        assert_eq!(frames[0], [UpdateEntityRotationPS2C::ID as u8, 2, 192, 32, 1]);
+        assert_eq!(frames[1], [SetHeadRotationPS2C::ID as u8, 2, 64]);
+    }
+}
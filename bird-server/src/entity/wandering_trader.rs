@@ -0,0 +1,434 @@
+use std::borrow::Cow;
+use euclid::default::Vector3D;
+use uuid::{Builder, Uuid};
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::entity::{EntityIdAllocator, EntityKind};
+use crate::entity::status::TRADER_LLAMA_ENTITY_TYPE;
+use crate::entity::tracker::{EntityTracker, TrackedEntity};
+use crate::protocol::{MerchantOffersPS2C, MerchantOffersTrades, OwnedSlot};
+use crate::random::LegacyRandom;
+use crate::world::World;
+use crate::world::query::{block_shape, is_passable};
+
+pub const WANDERING_TRADER_ENTITY_TYPE: i32 = 105;
+/// Ticks between spawn attempts
+pub const SPAWN_DELAY: i32 = 24000;
+/// Ticks until the trader and its llamas disappear
+pub const DESPAWN_DELAY: i32 = 48000;
+pub const MIN_SPAWN_CHANCE: i32 = 25;
+pub const MAX_SPAWN_CHANCE: i32 = 75;
+
+/// The spawn delay is counted down once per minute
+const TICK_DELAY: i32 = 1200;
+const SPAWN_CHANCE_STEP: i32 = 25;
+/// Chance of the spawn after the spawn chance has succeeded is one to this
+const PLAYER_SPAWN_CHANCE: i32 = 10;
+const SPAWN_RADIUS: i32 = 48;
+const SPAWN_ATTEMPTS: usize = 10;
+const LLAMA_COUNT: usize = 2;
+const LLAMA_RADIUS: i32 = 4;
+const GENERIC_TRADE_COUNT: usize = 5;
+const RARE_TRADE_COUNT: usize = 1;
+const TRADE_XP: i32 = 1;
+const TRADE_PRICE_MULTIPLIER: f32 = 0.05;
+
+/// Item which is sold for emeralds
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WanderingTrade {
+    pub item: &'static str,
+    pub price: i8,
+    pub count: i8,
+    pub max_uses: i32,
+}
+
+const fn trade(item: &'static str, price: i8, count: i8, max_uses: i32) -> WanderingTrade {
+    WanderingTrade { item, price, count, max_uses }
+}
+
+pub const GENERIC_TRADES: &'static [WanderingTrade] = &[
+    trade("sea_pickle", 2, 1, 5),
+    trade("slime_ball", 4, 1, 5),
+    trade("glowstone", 2, 1, 5),
+    trade("nautilus_shell", 5, 1, 5),
+    trade("fern", 1, 1, 12),
+    trade("sugar_cane", 1, 1, 8),
+    trade("pumpkin", 1, 1, 4),
+    trade("kelp", 3, 1, 12),
+    trade("cactus", 3, 1, 8),
+    trade("dandelion", 1, 1, 12),
+    trade("poppy", 1, 1, 12),
+    trade("blue_orchid", 1, 1, 8),
+    trade("allium", 1, 1, 12),
+    trade("azure_bluet", 1, 1, 12),
+    trade("red_tulip", 1, 1, 12),
+    trade("orange_tulip", 1, 1, 12),
+    trade("white_tulip", 1, 1, 12),
+    trade("pink_tulip", 1, 1, 12),
+    trade("oxeye_daisy", 1, 1, 12),
+    trade("cornflower", 1, 1, 12),
+    trade("lily_of_the_valley", 1, 1, 7),
+    trade("wheat_seeds", 1, 1, 12),
+    trade("beetroot_seeds", 1, 1, 12),
+    trade("pumpkin_seeds", 1, 1, 12),
+    trade("melon_seeds", 1, 1, 12),
+    trade("acacia_sapling", 5, 1, 8),
+    trade("birch_sapling", 5, 1, 8),
+    trade("dark_oak_sapling", 5, 1, 8),
+    trade("jungle_sapling", 5, 1, 8),
+    trade("oak_sapling", 5, 1, 8),
+    trade("spruce_sapling", 5, 1, 8),
+    trade("mangrove_propagule", 5, 1, 8),
+    trade("red_dye", 1, 3, 12),
+    trade("white_dye", 1, 3, 12),
+    trade("blue_dye", 1, 3, 12),
+    trade("pink_dye", 1, 3, 12),
+    trade("black_dye", 1, 3, 12),
+    trade("green_dye", 1, 3, 12),
+    trade("light_gray_dye", 1, 3, 12),
+    trade("magenta_dye", 1, 3, 12),
+    trade("yellow_dye", 1, 3, 12),
+    trade("gray_dye", 1, 3, 12),
+    trade("purple_dye", 1, 3, 12),
+    trade("light_blue_dye", 1, 3, 12),
+    trade("lime_dye", 1, 3, 12),
+    trade("orange_dye", 1, 3, 12),
+    trade("brown_dye", 1, 3, 12),
+    trade("cyan_dye", 1, 3, 12),
+    trade("brain_coral_block", 3, 1, 8),
+    trade("bubble_coral_block", 3, 1, 8),
+    trade("fire_coral_block", 3, 1, 8),
+    trade("horn_coral_block", 3, 1, 8),
+    trade("tube_coral_block", 3, 1, 8),
+    trade("vine", 1, 1, 12),
+    trade("brown_mushroom", 1, 1, 12),
+    trade("red_mushroom", 1, 1, 12),
+    trade("lily_pad", 1, 2, 5),
+    trade("small_dripleaf", 1, 2, 5),
+    trade("sand", 1, 8, 8),
+    trade("red_sand", 1, 4, 6),
+    trade("pointed_dripstone", 1, 2, 5),
+    trade("rooted_dirt", 1, 2, 5),
+    trade("moss_block", 1, 2, 5),
+];
+
+pub const RARE_TRADES: &'static [WanderingTrade] = &[
+    trade("tropical_fish_bucket", 5, 1, 4),
+    trade("pufferfish_bucket", 5, 1, 4),
+    trade("packed_ice", 3, 1, 6),
+    trade("blue_ice", 6, 1, 6),
+    trade("gunpowder", 1, 1, 8),
+    trade("podzol", 3, 3, 6),
+];
+
+/// Trade of one trader, uses are counted separately for every offer
+#[derive(Clone, PartialEq, Debug)]
+pub struct MerchantOffer {
+    pub input: OwnedSlot,
+    pub output: OwnedSlot,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub xp: i32,
+}
+
+impl MerchantOffer {
+    /// Returns `None` if bird_data doesn't know the item of the trade
+    pub fn from_trade(trade: &WanderingTrade) -> Option<Self> {
+        let emerald = ItemData::from_name("emerald")?.id as i32;
+        let item = ItemData::from_name(trade.item)?.id as i32;
+        Some(Self {
+            input: OwnedSlot::new(emerald, trade.price),
+            output: OwnedSlot::new(item, trade.count),
+            uses: 0,
+            max_uses: trade.max_uses,
+            xp: TRADE_XP,
+        })
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.uses >= self.max_uses
+    }
+
+    pub fn as_trade(&self) -> MerchantOffersTrades {
+        MerchantOffersTrades {
+            input_item1: Some(self.input.as_slot()),
+            output_item: Some(self.output.as_slot()),
+            input_item2: None,
+            disabled: self.is_disabled(),
+            already_used: self.uses,
+            maximum_uses: self.max_uses,
+            xp: self.xp,
+            special_price: 0,
+            price_multiplier: TRADE_PRICE_MULTIPLIER,
+            demand: 0,
+        }
+    }
+}
+
+/// Picks the given amount of different trades, trades with unknown items are skipped
+fn pick_trades(trades: &[WanderingTrade], count: usize, random: &mut LegacyRandom, offers: &mut Vec<MerchantOffer>) {
+    let mut available: Vec<_> = trades.iter().filter_map(MerchantOffer::from_trade).collect();
+    for _ in 0..count.min(available.len()) {
+        let index = random.next_int(available.len() as i32) as usize;
+        offers.push(available.swap_remove(index));
+    }
+}
+
+/// Generic trades followed by rare trades
+pub fn generate_offers(random: &mut LegacyRandom) -> Vec<MerchantOffer> {
+    let mut offers = Vec::with_capacity(GENERIC_TRADE_COUNT + RARE_TRADE_COUNT);
+    pick_trades(GENERIC_TRADES, GENERIC_TRADE_COUNT, random, &mut offers);
+    pick_trades(RARE_TRADES, RARE_TRADE_COUNT, random, &mut offers);
+    offers
+}
+
+fn random_uuid(random: &mut LegacyRandom) -> Uuid {
+    let mut bytes = [0; 16];
+    for chunk in bytes.chunks_mut(4) {
+        chunk.copy_from_slice(&random.next_i32().to_be_bytes());
+    }
+    Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// Mobs can spawn on the block below and the trader fits above it
+fn can_spawn_at(world: &World, position: Vector3D<i32>) -> bool {
+    let state = |offset: i32| world.get_block_state(position + Vector3D::new(0, offset, 0));
+    state(-1).and_then(block_shape).map_or(false, |shape| shape.is_spawnable())
+        && state(0).map_or(false, is_passable)
+        && state(1).map_or(false, is_passable)
+}
+
+/// Random position on the surface around the center
+fn find_spawn_position(world: &World, center: Vector3D<i32>, radius: i32, random: &mut LegacyRandom) -> Option<Vector3D<i32>> {
+    (0..SPAWN_ATTEMPTS).find_map(|_| {
+        let x = center.x + random.next_int(radius * 2) - radius;
+        let z = center.z + random.next_int(radius * 2) - radius;
+        let position = Vector3D::new(x, world.top_block_y(x, z)? + 1, z);
+        match can_spawn_at(world, position) {
+            true => Some(position),
+            false => None,
+        }
+    })
+}
+
+fn entity_position(position: Vector3D<i32>) -> Vector3D<f64> {
+    Vector3D::new(position.x as f64 + 0.5, position.y as f64, position.z as f64 + 0.5)
+}
+
+/// Spawned trader with llamas which are leashed to it
+#[derive(Clone, PartialEq, Debug)]
+pub struct WanderingTrader {
+    pub entity_id: i32,
+    pub llamas: Vec<i32>,
+    pub offers: Vec<MerchantOffer>,
+    /// Player who is trading with the trader, the trader doesn't despawn while trading
+    pub customer: Option<i32>,
+    despawn_delay: i32,
+}
+
+impl WanderingTrader {
+    pub fn despawn_delay(&self) -> i32 {
+        self.despawn_delay
+    }
+
+    /// Returns true when the trader has despawned, he is removed from the tracker with its llamas
+    pub fn tick(&mut self, tracker: &mut EntityTracker) -> anyhow::Result<bool> {
+        if self.customer.is_some() || self.despawn_delay <= 0 {
+            return Ok(false);
+        }
+        self.despawn_delay -= 1;
+        if self.despawn_delay > 0 {
+            return Ok(false);
+        }
+        for llama in self.llamas.drain(..) {
+            tracker.remove_entity(llama)?;
+        }
+        tracker.remove_entity(self.entity_id)?;
+        Ok(true)
+    }
+
+    /// Counts the use of the offer, returns the output or `None` if the offer is disabled
+    pub fn trade(&mut self, index: usize) -> Option<OwnedSlot> {
+        let offer = self.offers.get_mut(index).filter(|offer| !offer.is_disabled())?;
+        offer.uses += 1;
+        Some(offer.output.clone())
+    }
+
+    pub fn send_offers<S: PacketSender>(&self, window_id: i32, sender: &mut S) -> anyhow::Result<()> {
+        let trades: Vec<_> = self.offers.iter().map(MerchantOffer::as_trade).collect();
+        sender.send_packet(&MerchantOffersPS2C {
+            window_id,
+            trades: Cow::Owned(trades),
+            villager_level: 1,
+            experience: 0,
+            is_regular_villager: false,
+            can_restock: false,
+        })
+    }
+}
+
+/// Schedules wandering traders of one world like vanilla does
+#[derive(Clone, PartialEq, Debug)]
+pub struct WanderingTraderSpawner {
+    /// `doTraderSpawning`, the schedule is paused while it is disabled
+    pub enabled: bool,
+    random: LegacyRandom,
+    tick_delay: i32,
+    spawn_delay: i32,
+    spawn_chance: i32,
+}
+
+impl WanderingTraderSpawner {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            enabled: true,
+            random: LegacyRandom::new(seed),
+            tick_delay: TICK_DELAY,
+            spawn_delay: SPAWN_DELAY,
+            spawn_chance: MIN_SPAWN_CHANCE,
+        }
+    }
+
+    pub fn spawn_delay(&self) -> i32 {
+        self.spawn_delay
+    }
+
+    pub fn spawn_chance(&self) -> i32 {
+        self.spawn_chance
+    }
+
+    /// Tries to spawn the trader near one of the players once per spawn delay.
+    /// The chance grows after every failed attempt and resets after the spawn
+    pub fn tick(
+        &mut self,
+        world: &World,
+        players: &[Vector3D<f64>],
+        entity_ids: &mut EntityIdAllocator,
+        tracker: &mut EntityTracker,
+    ) -> anyhow::Result<Option<WanderingTrader>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        self.tick_delay -= 1;
+        if self.tick_delay > 0 {
+            return Ok(None);
+        }
+        self.tick_delay = TICK_DELAY;
+        self.spawn_delay -= TICK_DELAY;
+        if self.spawn_delay > 0 {
+            return Ok(None);
+        }
+        self.spawn_delay = SPAWN_DELAY;
+        let chance = self.spawn_chance;
+        self.spawn_chance = (self.spawn_chance + SPAWN_CHANCE_STEP).clamp(MIN_SPAWN_CHANCE, MAX_SPAWN_CHANCE);
+        if self.random.next_int(100) > chance {
+            return Ok(None);
+        }
+        let trader = self.spawn(world, players, entity_ids, tracker)?;
+        if trader.is_some() {
+            self.spawn_chance = MIN_SPAWN_CHANCE;
+        }
+        Ok(trader)
+    }
+
+    /// One spawn attempt near a random player, it fails mostly
+    pub fn spawn(
+        &mut self,
+        world: &World,
+        players: &[Vector3D<f64>],
+        entity_ids: &mut EntityIdAllocator,
+        tracker: &mut EntityTracker,
+    ) -> anyhow::Result<Option<WanderingTrader>> {
+        if players.is_empty() || self.random.next_int(PLAYER_SPAWN_CHANCE) != 0 {
+            return Ok(None);
+        }
+        let player = players[self.random.next_int(players.len() as i32) as usize];
+        let center = Vector3D::new(player.x.floor() as i32, player.y.floor() as i32, player.z.floor() as i32);
+        let position = match find_spawn_position(world, center, SPAWN_RADIUS, &mut self.random) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let entity_id = entity_ids.allocate();
+        let kind = EntityKind::Other { entity_type: WANDERING_TRADER_ENTITY_TYPE, data: 0 };
+        let uuid = random_uuid(&mut self.random);
+        tracker.add_entity(TrackedEntity::new(entity_id, uuid, kind, entity_position(position)))?;
+        let mut llamas = Vec::with_capacity(LLAMA_COUNT);
+        for _ in 0..LLAMA_COUNT {
+            let llama_position = match find_spawn_position(world, position, LLAMA_RADIUS, &mut self.random) {
+                Some(llama_position) => llama_position,
+                None => continue,
+            };
+            let llama_id = entity_ids.allocate();
+            let kind = EntityKind::Other { entity_type: TRADER_LLAMA_ENTITY_TYPE, data: 0 };
+            let uuid = random_uuid(&mut self.random);
+            tracker.add_entity(TrackedEntity::new(llama_id, uuid, kind, entity_position(llama_position)))?;
+            tracker.set_leash_holder(llama_id, Some(entity_id))?;
+            llamas.push(llama_id);
+        }
+        Ok(Some(WanderingTrader {
+            entity_id,
+            llamas,
+            offers: generate_offers(&mut self.random),
+            customer: None,
+            despawn_delay: DESPAWN_DELAY,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use bird_data::Block;
+    use super::*;
+
+    #[test]
+    fn wandering_trader_test() {
+        let mut world = World::new(1, 0);
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        for chunk_x in -4..4 {
+            for chunk_z in -4..4 {
+                let column = world.get_or_create_chunk(Vector2D::new(chunk_x, chunk_z));
+                for x in 0..16 {
+                    for z in 0..16 {
+                        column.set_block_state(x, 0, z, stone);
+                    }
+                }
+            }
+        }
+        let players = [Vector3D::new(0.5, 1.0, 0.5)];
+        let mut entity_ids = EntityIdAllocator::new();
+        let mut tracker = EntityTracker::new(128.0);
+        let mut spawner = WanderingTraderSpawner::new(0);
+        for _ in 0..SPAWN_DELAY - 1 {
+            assert!(spawner.tick(&world, &players, &mut entity_ids, &mut tracker).unwrap().is_none());
+        }
+        assert_eq!(spawner.spawn_chance(), MIN_SPAWN_CHANCE);
+        let trader = spawner.tick(&world, &players, &mut entity_ids, &mut tracker).unwrap();
+        assert_eq!(spawner.spawn_delay(), SPAWN_DELAY);
+        assert_eq!(trader.is_some(), spawner.spawn_chance() == MIN_SPAWN_CHANCE);
+        let mut trader = match trader {
+            Some(trader) => trader,
+            None => (0..1000)
+                .find_map(|_| spawner.spawn(&world, &players, &mut entity_ids, &mut tracker).unwrap())
+                .unwrap(),
+        };
+        let entity = tracker.get_entity(trader.entity_id).unwrap();
+        assert_eq!(entity.position.y, 1.0);
+        assert_eq!(trader.llamas.len(), LLAMA_COUNT);
+        assert_eq!(tracker.get_entity(trader.llamas[0]).unwrap().leash_holder(), Some(trader.entity_id));
+        assert_eq!(trader.offers.len(), GENERIC_TRADE_COUNT + RARE_TRADE_COUNT);
+        let output = trader.offers[0].output.clone();
+        assert_eq!(trader.trade(0), Some(output));
+        trader.customer = Some(1);
+        assert!(!trader.tick(&mut tracker).unwrap());
+        assert_eq!(trader.despawn_delay(), DESPAWN_DELAY);
+        trader.customer = None;
+        for _ in 0..DESPAWN_DELAY - 1 {
+            assert!(!trader.tick(&mut tracker).unwrap());
+        }
+        assert!(trader.tick(&mut tracker).unwrap());
+        assert!(tracker.get_entity(trader.entity_id).is_none());
+        assert_eq!(tracker.entities().count(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use bird_protocol::{anyhow, ProtocolCursor, ProtocolPacketBound, ProtocolPacketState};
+use crate::registry::{Packet, RegistryPacket};
+use crate::version::PacketIdTable;
+
+/// Handlers with the higher priority are called first
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum EventPriority {
+    Lowest,
+    Low,
+    #[default]
+    Normal,
+    High,
+    Highest,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EventResult {
+    #[default]
+    Continue,
+    /// Remaining handlers and the default handling are skipped
+    Cancel,
+}
+
+type Handler<T> = Box<dyn for<'p, 'a> Fn(&mut T, &'p Packet<'a>) -> EventResult + Send + Sync>;
+
+struct RegisteredHandler<T> {
+    priority: EventPriority,
+    handler: Handler<T>,
+}
+
+/// Routes decoded packets to handlers which are registered for their types,
+/// `T` is the state of the connection which is given to handlers
+pub struct EventBus<T> {
+    handlers: HashMap<(ProtocolPacketState, ProtocolPacketBound, i32), Vec<RegisteredHandler<T>>>,
+}
+
+impl<T> Default for EventBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EventBus<T> {
+    pub fn new() -> Self {
+        Self { handlers: HashMap::new() }
+    }
+
+    /// Handlers with the same priority are called in the order of registration
+    pub fn on<P, F>(&mut self, priority: EventPriority, handler: F) -> &mut Self
+        where P: RegistryPacket, F: for<'a> Fn(&mut T, &P::Packet<'a>) -> EventResult + Send + Sync + 'static {
+        let handler: Handler<T> = Box::new(move |player, packet| match P::from_packet(packet) {
+            Some(packet) => handler(player, packet),
+            None => EventResult::Continue,
+        });
+        let handlers = self.handlers.entry((P::STATE, P::BOUND, P::ID)).or_default();
+        let index = handlers.partition_point(|registered| registered.priority >= priority);
+        handlers.insert(index, RegisteredHandler { priority, handler });
+        self
+    }
+
+    pub fn has_handlers(&self, state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32) -> bool {
+        self.handlers.get(&(state, bound, id)).map_or(false, |handlers| !handlers.is_empty())
+    }
+
+    pub fn dispatch(&self, player: &mut T, packet: &Packet) -> EventResult {
+        if let Some(handlers) = self.handlers.get(&packet.key()) {
+            for registered in handlers {
+                if (registered.handler)(player, packet) == EventResult::Cancel {
+                    return EventResult::Cancel;
+                }
+            }
+        }
+        EventResult::Continue
+    }
+
+    /// Reads the frame without the length and dispatches the packet,
+    /// `default` is called if no handler cancelled it. Unknown packets are given to `default` as [`Packet::Unknown`]
+    pub fn handle_frame<'a, C, F>(
+        &self,
+        player: &mut T,
+        state: ProtocolPacketState,
+        bound: ProtocolPacketBound,
+        cursor: &mut C,
+        default: F,
+    ) -> anyhow::Result<()>
+        where C: ProtocolCursor<'a>, F: FnOnce(&mut T, Packet<'a>) -> anyhow::Result<()> {
+        let packet = Packet::read_or_unknown(state, bound, PacketIdTable::latest(), cursor)?;
+        match self.dispatch(player, &packet) {
+            EventResult::Continue => default(player, packet),
+            EventResult::Cancel => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector3D;
+    use crate::connection::encode_packet;
+    use crate::protocol::*;
+    use super::*;
+
+    #[test]
+    fn event_bus_test() {
+        let mut bus = EventBus::<Vec<&'static str>>::new();
+        bus.on::<MoveVehiclePC2S, _>(EventPriority::Low, |log, _| {
+            log.push("low");
+            EventResult::Continue
+        });
+        bus.on::<MoveVehiclePC2S, _>(EventPriority::High, |log, packet| {
+            log.push("high");
+            match packet.pos.y < -64.0 {
+                true => EventResult::Cancel,
+                false => EventResult::Continue,
+            }
+        });
+        bus.on::<PlayerInputPC2S, _>(EventPriority::Highest, |log, _| {
+            log.push("input");
+            EventResult::Continue
+        });
+        let handle = |log: &mut Vec<&'static str>, y: f64| {
+            let bytes = encode_packet(&MoveVehiclePC2S { pos: Vector3D::new(0.0, y, 0.0), yaw: 0.0, pitch: 0.0 }).unwrap();
+            bus.handle_frame(log, ProtocolPacketState::Play, ProtocolPacketBound::Server, &mut bytes.as_slice(), |log, _| {
+                log.push("default");
+                Ok(())
+            }).unwrap();
+        };
+        let mut log = Vec::new();
+        handle(&mut log, 64.0);
+        assert_eq!(log, ["high", "low", "default"]);
+        log.clear();
+        handle(&mut log, -100.0);
+        assert_eq!(log, ["high"]);
+        bus.handle_frame(&mut log, ProtocolPacketState::Play, ProtocolPacketBound::Server, &mut [0x7F].as_slice(), |log, packet| {
+            assert!(matches!(packet, Packet::Unknown(_)));
+            log.push("unknown");
+            Ok(())
+        }).unwrap();
+        assert_eq!(log, ["high", "unknown"]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use bird_protocol::anyhow;
+use crate::trace::crc32;
+
+/// The client shows only icons of this size
+pub const FAVICON_SIZE: u32 = 64;
+pub const FAVICON_PREFIX: &'static str = "data:image/png;base64,";
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// 8 bits per channel, RGBA
+const PNG_BIT_DEPTH: u8 = 8;
+const PNG_COLOR_TYPE_RGBA: u8 = 6;
+const MAX_STORED_BLOCK: usize = u16::MAX as usize;
+
+/// Width and height of the png from its header
+pub fn png_size(png: &[u8]) -> anyhow::Result<(u32, u32)> {
+    let not_png = || anyhow::Error::msg("Favicon is not a png image");
+    match png.get(..PNG_SIGNATURE.len()) {
+        Some(signature) if signature == PNG_SIGNATURE => {}
+        _ => return Err(not_png()),
+    }
+    let header = png.get(8..24).ok_or_else(not_png)?;
+    if &header[4..8] != b"IHDR" {
+        return Err(not_png());
+    }
+    let width = u32::from_be_bytes(header[8..12].try_into()?);
+    let height = u32::from_be_bytes(header[12..16].try_into()?);
+    Ok((width, height))
+}
+
+/// Like vanilla, the png must be 64x64
+pub fn favicon_from_png(png: &[u8]) -> anyhow::Result<String> {
+    match png_size(png)? {
+        (FAVICON_SIZE, FAVICON_SIZE) => Ok(format!("{}{}", FAVICON_PREFIX, base64::encode(png))),
+        (width, height) => Err(anyhow::Error::msg(format!("Favicon must be {0}x{0} pixels, not {1}x{2}", FAVICON_SIZE, width, height))),
+    }
+}
+
+/// Scales RGBA pixels to 64x64, every pixel is the average of the source pixels it covers
+pub fn resize_rgba(rgba: &[u8], width: u32, height: u32) -> anyhow::Result<Vec<u8>> {
+    if width == 0 || height == 0 || rgba.len() != width as usize * height as usize * 4 {
+        return Err(anyhow::Error::msg(format!("{} bytes are not {}x{} RGBA pixels", rgba.len(), width, height)));
+    }
+    let size = FAVICON_SIZE as usize;
+    let (width, height) = (width as usize, height as usize);
+    let mut resized = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        let rows = y * height / size..((y + 1) * height / size).max(y * height / size + 1);
+        for x in 0..size {
+            let columns = x * width / size..((x + 1) * width / size).max(x * width / size + 1);
+            let mut sum = [0u32; 4];
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let pixel = (row * width + column) * 4;
+                    for (channel, value) in sum.iter_mut().zip(&rgba[pixel..pixel + 4]) {
+                        *channel += *value as u32;
+                    }
+                }
+            }
+            let count = (rows.len() * columns.len()) as u32;
+            resized.extend(sum.iter().map(|channel| (channel / count) as u8));
+        }
+    }
+    Ok(resized)
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+fn write_chunk(png: &mut Vec<u8>, ty: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(ty);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Png with the image data in stored deflate blocks, favicons are small enough to skip the compression
+pub fn encode_png(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(rgba.len() + height as usize);
+    for row in rgba.chunks(width as usize * 4) {
+        // No filter
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(MAX_STORED_BLOCK).count();
+    for (index, block) in raw.chunks(MAX_STORED_BLOCK).enumerate() {
+        zlib.push((index + 1 == blocks) as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Deflate compression, adaptive filtering and no interlace
+    header.extend_from_slice(&[PNG_BIT_DEPTH, PNG_COLOR_TYPE_RGBA, 0, 0, 0]);
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Resizes the pixels to 64x64 and encodes them as the png favicon
+pub fn favicon_from_rgba(rgba: &[u8], width: u32, height: u32) -> anyhow::Result<String> {
+    favicon_from_png(&encode_png(&resize_rgba(rgba, width, height)?, FAVICON_SIZE, FAVICON_SIZE))
+}
+
+/// Favicon which is loaded from the png file, it is loaded again when the file is modified
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FaviconCache {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    favicon: Option<String>,
+}
+
+impl FaviconCache {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, modified: None, favicon: None }
+    }
+
+    /// Favicon for [`crate::status::ServerStatus`], `None` if the file doesn't exist
+    pub fn get(&mut self) -> anyhow::Result<Option<&str>> {
+        let modified = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.modified()?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                self.modified = None;
+                self.favicon = None;
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        };
+        if self.favicon.is_none() || self.modified != Some(modified) {
+            self.favicon = Some(favicon_from_png(&std::fs::read(&self.path)?)?);
+            self.modified = Some(modified);
+        }
+        Ok(self.favicon.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favicon_test() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+        let mut rgba = Vec::new();
+        for _ in 0..128 {
+            for x in 0..128 {
+                rgba.extend_from_slice(match x < 64 {
+                    true => &[255, 0, 0, 255],
+                    false => &[0, 0, 255, 255],
+                });
+            }
+        }
+        let resized = resize_rgba(&rgba, 128, 128).unwrap();
+        assert_eq!(resized.len(), 64 * 64 * 4);
+        assert_eq!(&resized[..4], &[255, 0, 0, 255]);
+        assert_eq!(&resized[63 * 4..64 * 4], &[0, 0, 255, 255]);
+        // Small images are scaled up
+        assert_eq!(&resize_rgba(&[1, 2, 3, 4], 1, 1).unwrap()[252..256], &[1, 2, 3, 4]);
+        assert!(resize_rgba(&[0; 3], 1, 1).is_err());
+
+        let favicon = favicon_from_rgba(&rgba, 128, 128).unwrap();
+        let png = base64::decode(favicon.strip_prefix(FAVICON_PREFIX).unwrap()).unwrap();
+        assert_eq!(png_size(&png).unwrap(), (64, 64));
+        assert!(favicon_from_png(&encode_png(&[0; 16], 2, 2)).is_err());
+        assert!(png_size(b"GIF89a").is_err());
+
+        let path = std::env::temp_dir().join(format!("bird-favicon-{}.png", std::process::id()));
+        let mut cache = FaviconCache::new(path.clone());
+        assert_eq!(cache.get().unwrap(), None);
+        std::fs::write(&path, &png).unwrap();
+        assert_eq!(cache.get().unwrap(), Some(favicon.as_str()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use fastnbt::Value;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::item_container::Compound;
+use crate::protocol::*;
+
+/// Tags of the playerdata compound of vanilla
+pub const PLAYER_GAME_TYPE_TAG: &'static str = "playerGameType";
+pub const PREVIOUS_PLAYER_GAME_TYPE_TAG: &'static str = "previousPlayerGameType";
+pub const DEFAULT_FLYING_SPEED: f32 = 0.05;
+pub const DEFAULT_WALKING_SPEED: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        Self::Survival
+    }
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [GameMode::Survival, GameMode::Creative, GameMode::Adventure, GameMode::Spectator];
+
+    pub fn id(self) -> i32 {
+        match self {
+            GameMode::Survival => 0,
+            GameMode::Creative => 1,
+            GameMode::Adventure => 2,
+            GameMode::Spectator => 3,
+        }
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(id).ok()?).copied()
+    }
+
+    /// Name in `server.properties` and commands
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn login(self) -> LoginGameMode {
+        match self {
+            GameMode::Survival => LoginGameMode::Survival,
+            GameMode::Creative => LoginGameMode::Creative,
+            GameMode::Adventure => LoginGameMode::Adventure,
+            GameMode::Spectator => LoginGameMode::Spectator,
+        }
+    }
+
+    pub fn previous_login(previous: Option<Self>) -> PreviousLoginGameMode {
+        match previous {
+            None => PreviousLoginGameMode::None,
+            Some(GameMode::Survival) => PreviousLoginGameMode::Survival,
+            Some(GameMode::Creative) => PreviousLoginGameMode::Creative,
+            Some(GameMode::Adventure) => PreviousLoginGameMode::Adventure,
+            Some(GameMode::Spectator) => PreviousLoginGameMode::Spectator,
+        }
+    }
+
+    pub fn game_event(self) -> GameEventGameMode {
+        match self {
+            GameMode::Survival => GameEventGameMode::Survival,
+            GameMode::Creative => GameEventGameMode::Creative,
+            GameMode::Adventure => GameEventGameMode::Adventure,
+            GameMode::Spectator => GameEventGameMode::Spectator,
+        }
+    }
+
+    pub fn player_info(self) -> PlayerInfoUpdateGameMode {
+        match self {
+            GameMode::Survival => PlayerInfoUpdateGameMode::Survival,
+            GameMode::Creative => PlayerInfoUpdateGameMode::Creative,
+            GameMode::Adventure => PlayerInfoUpdateGameMode::Adventure,
+            GameMode::Spectator => PlayerInfoUpdateGameMode::Spectator,
+        }
+    }
+
+    /// Abilities of the gamemode like vanilla, spectators always fly.
+    /// `flying` is kept in creative and dropped in survival and adventure
+    pub fn abilities(self, flying: bool) -> PlayerAbilitiesPS2C {
+        let flags = PlayerAbilitiesFlags::new();
+        let flags = match self {
+            GameMode::Survival | GameMode::Adventure => flags,
+            GameMode::Creative => flags.with_invulnerable(true).with_allow_flying(true).with_creative_mode(true).with_flying(flying),
+            GameMode::Spectator => flags.with_invulnerable(true).with_allow_flying(true).with_flying(true),
+        };
+        PlayerAbilitiesPS2C { flags, flying_speed: DEFAULT_FLYING_SPEED, view_modifier: DEFAULT_WALKING_SPEED }
+    }
+}
+
+/// Gamemode of the player, it is saved in the playerdata
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PlayerGameMode {
+    pub current: GameMode,
+    /// Gamemode before the last change, the F3+F4 switcher starts from it
+    pub previous: Option<GameMode>,
+}
+
+impl PlayerGameMode {
+    pub fn new(current: GameMode) -> Self {
+        Self { current, previous: None }
+    }
+
+    /// `None` if the playerdata doesn't have the gamemode
+    pub fn read(compound: &Compound) -> Option<Self> {
+        let current = match compound.get(PLAYER_GAME_TYPE_TAG) {
+            Some(Value::Int(id)) => GameMode::from_id(*id)?,
+            _ => return None,
+        };
+        let previous = match compound.get(PREVIOUS_PLAYER_GAME_TYPE_TAG) {
+            Some(Value::Int(id)) => GameMode::from_id(*id),
+            _ => None,
+        };
+        Some(Self { current, previous })
+    }
+
+    pub fn write(&self, compound: &mut Compound) {
+        compound.insert(PLAYER_GAME_TYPE_TAG.into(), Value::Int(self.current.id()));
+        compound.insert(PREVIOUS_PLAYER_GAME_TYPE_TAG.into(), Value::Int(self.previous.map_or(-1, GameMode::id)));
+    }
+
+    /// Changes the gamemode of the joined player, the tab list is updated by [`crate::player_list::PlayerList::set_game_mode`].
+    /// Returns false if the player already has the gamemode
+    pub fn change<S: PacketSender>(&mut self, mode: GameMode, flying: bool, sender: &mut S) -> anyhow::Result<bool> {
+        if self.current == mode {
+            return Ok(false);
+        }
+        self.previous = Some(self.current);
+        self.current = mode;
+        sender.send_packet(&GameEventPS2C::ChangeGameMode(mode.game_event()))?;
+        sender.send_packet(&self.current.abilities(flying))?;
+        Ok(true)
+    }
+}
+
+/// Gamemodes of new players and of players which join the world when the gamemode is forced
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GameModeConfig {
+    /// `gamemode` property of vanilla
+    pub default: GameMode,
+    /// Worlds which don't use the default gamemode
+    pub worlds: HashMap<String, GameMode>,
+    /// `force-gamemode` property of vanilla, players get the gamemode of the world on join and on the world change
+    pub force_gamemode: bool,
+}
+
+impl GameModeConfig {
+    pub fn world_default(&self, world: &str) -> GameMode {
+        self.worlds.get(world).copied().unwrap_or(self.default)
+    }
+
+    /// Gamemode which is sent in [`LoginPS2C`], `saved` is read from the playerdata
+    pub fn join(&self, world: &str, saved: Option<PlayerGameMode>) -> PlayerGameMode {
+        let default = self.world_default(world);
+        match saved {
+            Some(saved) if self.force_gamemode && saved.current != default =>
+                PlayerGameMode { current: default, previous: Some(saved.current) },
+            Some(saved) => saved,
+            None => PlayerGameMode::new(default),
+        }
+    }
+
+    /// The player is moved to another world, returns true if the gamemode is changed
+    pub fn change_world<S: PacketSender>(
+        &self,
+        world: &str,
+        player: &mut PlayerGameMode,
+        flying: bool,
+        sender: &mut S,
+    ) -> anyhow::Result<bool> {
+        match self.force_gamemode {
+            true => player.change(self.world_default(world), flying, sender),
+            false => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn game_mode_test() {
+        let mut config = GameModeConfig { default: GameMode::Survival, ..Default::default() };
+        config.worlds.insert("creative".into(), GameMode::Creative);
+        assert_eq!(config.join("world", None), PlayerGameMode::new(GameMode::Survival));
+        let saved = PlayerGameMode { current: GameMode::Adventure, previous: Some(GameMode::Survival) };
+        assert_eq!(config.join("creative", Some(saved)), saved);
+        config.force_gamemode = true;
+        let mut player = config.join("creative", Some(saved));
+        assert_eq!(player, PlayerGameMode { current: GameMode::Creative, previous: Some(GameMode::Adventure) });
+
+        let mut compound = Compound::new();
+        player.write(&mut compound);
+        assert_eq!(compound.get(PLAYER_GAME_TYPE_TAG), Some(&Value::Int(1)));
+        assert_eq!(PlayerGameMode::read(&compound), Some(player));
+        assert_eq!(PlayerGameMode::read(&Compound::new()), None);
+
+        let mut sender = PacketQueue::new();
+        assert!(!config.change_world("creative", &mut player, true, &mut sender).unwrap());
+        assert!(config.change_world("world", &mut player, true, &mut sender).unwrap());
+        assert_eq!(player, PlayerGameMode { current: GameMode::Survival, previous: Some(GameMode::Creative) });
+        let packets = sender.take();
+        assert_eq!(packets.len(), 2);
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut packets[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::GameEvent(event))) => assert_eq!(event, GameEventPS2C::ChangeGameMode(GameEventGameMode::Survival)),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut packets[1].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::PlayerAbilities(abilities))) => {
+                assert!(!abilities.flags.allow_flying());
+                assert!(!abilities.flags.flying());
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        let spectator = GameMode::Spectator.abilities(false);
+        assert!(spectator.flags.flying() && spectator.flags.invulnerable() && !spectator.flags.creative_mode());
+        assert_eq!(GameMode::from_name("adventure"), Some(GameMode::Adventure));
+        assert_eq!(GameMode::from_id(4), None);
+    }
+}
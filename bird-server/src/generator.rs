@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use euclid::default::Vector2D;
+use bird_data::Block;
+use crate::chunk_ticket::ChunkLevelChanges;
+use crate::world::{ChunkColumn, World};
+
+/// Values of the `level-type` property for the void world
+pub const VOID_LEVEL_TYPES: &'static [&'static str] = &["bird:void", "void"];
+
+pub trait ChunkGenerator: Send + Sync {
+    fn generate(&self, x: i32, z: i32) -> ChunkColumn;
+}
+
+/// Superflat world, layers are placed from the bottom of the world. All chunks are the same,
+/// so the column is built once and cloned with its height maps and light
+#[derive(Clone, Debug)]
+pub struct FlatGenerator {
+    section_count: usize,
+    /// Block state and the amount of layers
+    layers: Vec<(i32, usize)>,
+    template: ChunkColumn,
+}
+
+impl FlatGenerator {
+    pub fn new(section_count: usize) -> Self {
+        let mut template = ChunkColumn::new(section_count);
+        template.compute_light();
+        Self { section_count, layers: Vec::new(), template }
+    }
+
+    /// Bedrock, two dirt layers and grass like the default preset of vanilla
+    pub fn classic(section_count: usize) -> Self {
+        let state = |block: Block| block.get_state().unwrap() as i32;
+        let mut generator = Self::new(section_count);
+        generator
+            .layer(state(Block::Bedrock), 1)
+            .layer(state(Block::Dirt), 2)
+            .layer(state(Block::GrassBlock), 1);
+        generator
+    }
+
+    /// Layers above the world height are cut
+    pub fn layer(&mut self, state: i32, count: usize) -> &mut Self {
+        let start: usize = self.layers.iter().map(|(_, count)| count).sum();
+        let end = (start + count).min(self.section_count * 16);
+        for y in start..end {
+            for x in 0..16 {
+                for z in 0..16 {
+                    self.template.set_block_state(x, y, z, state);
+                }
+            }
+        }
+        self.layers.push((state, count));
+        self.template.compute_light();
+        self
+    }
+
+    pub fn layers(&self) -> &[(i32, usize)] {
+        &self.layers
+    }
+
+    /// `y` of the block where players spawn on the top of the layers
+    pub fn surface_y(&self, min_y: i32) -> i32 {
+        min_y + self.layers.iter().map(|(_, count)| *count as i32).sum::<i32>()
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, _x: i32, _z: i32) -> ChunkColumn {
+        self.template.clone()
+    }
+}
+
+/// Empty world, players spawn on blocks which are placed by the server
+#[derive(Clone, Debug)]
+pub struct VoidGenerator {
+    template: ChunkColumn,
+}
+
+impl VoidGenerator {
+    pub fn new(section_count: usize) -> Self {
+        let mut template = ChunkColumn::new(section_count);
+        template.compute_light();
+        Self { template }
+    }
+}
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate(&self, _x: i32, _z: i32) -> ChunkColumn {
+        self.template.clone()
+    }
+}
+
+/// Generator of the `level-type` property. There is no terrain generator yet,
+/// so the default type creates the classic superflat world
+pub fn from_level_type(level_type: &str, section_count: usize) -> Arc<dyn ChunkGenerator> {
+    let level_type = level_type.trim().to_lowercase();
+    match VOID_LEVEL_TYPES.contains(&level_type.as_str()) {
+        true => Arc::new(VoidGenerator::new(section_count)),
+        false => Arc::new(FlatGenerator::classic(section_count)),
+    }
+}
+
+/// Generates chunks on worker threads, finished chunks are inserted into the world by [`GenerationPool::poll`]
+pub struct GenerationPool {
+    requests: Option<Sender<Vector2D<i32>>>,
+    results: Receiver<(Vector2D<i32>, ChunkColumn)>,
+    /// Chunks which are requested and not inserted yet
+    pending: HashSet<Vector2D<i32>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl GenerationPool {
+    pub fn new(generator: Arc<dyn ChunkGenerator>, workers: usize) -> Self {
+        let (requests, receiver) = mpsc::channel::<Vector2D<i32>>();
+        let (sender, results) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let (generator, receiver, sender) = (generator.clone(), receiver.clone(), sender.clone());
+                thread::spawn(move || loop {
+                    // The lock is released before the chunk is generated
+                    let position = match receiver.lock().unwrap().recv() {
+                        Ok(position) => position,
+                        Err(_) => break,
+                    };
+                    if sender.send((position, generator.generate(position.x, position.y))).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        Self { requests: Some(requests), results, pending: HashSet::new(), workers }
+    }
+
+    /// Returns false if the chunk is already requested
+    pub fn request(&mut self, position: Vector2D<i32>) -> bool {
+        if !self.pending.insert(position) {
+            return false;
+        }
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(position);
+        }
+        true
+    }
+
+    /// The chunk is not inserted when it is generated
+    pub fn cancel(&mut self, position: Vector2D<i32>) -> bool {
+        self.pending.remove(&position)
+    }
+
+    pub fn is_pending(&self, position: Vector2D<i32>) -> bool {
+        self.pending.contains(&position)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Requests chunks which were loaded by tickets and are not in the world, unloaded chunks are removed from the world
+    pub fn apply_changes(&mut self, world: &mut World, changes: &ChunkLevelChanges) {
+        for position in &changes.unloaded {
+            self.cancel(*position);
+            world.remove_chunk(*position);
+        }
+        for position in &changes.loaded {
+            if world.get_chunk(*position).is_none() {
+                self.request(*position);
+            }
+        }
+    }
+
+    fn insert(&mut self, world: &mut World, position: Vector2D<i32>, column: ChunkColumn) -> bool {
+        match self.pending.remove(&position) && world.get_chunk(position).is_none() {
+            true => {
+                world.insert_chunk(position, column);
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Inserts generated chunks, returns them so they are sent to players by [`crate::world::ChunkSender::on_chunk_loaded`]
+    pub fn poll(&mut self, world: &mut World) -> Vec<Vector2D<i32>> {
+        let mut inserted = Vec::new();
+        while let Ok((position, column)) = self.results.try_recv() {
+            if self.insert(world, position, column) {
+                inserted.push(position);
+            }
+        }
+        inserted
+    }
+
+    /// Waits until all requested chunks are inserted, it is used before the server accepts players
+    pub fn wait(&mut self, world: &mut World, timeout: Duration) -> Vec<Vector2D<i32>> {
+        let mut inserted = Vec::new();
+        while !self.pending.is_empty() {
+            let (position, column) = match self.results.recv_timeout(timeout) {
+                Ok(result) => result,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            };
+            if self.insert(world, position, column) {
+                inserted.push(position);
+            }
+        }
+        inserted
+    }
+}
+
+impl Drop for GenerationPool {
+    fn drop(&mut self) {
+        self.requests.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_ticket::{ChunkTickets, TicketType, FULL_CHUNK_LEVEL};
+    use super::*;
+
+    #[test]
+    fn flat_generator_test() {
+        let generator = FlatGenerator::classic(2);
+        assert_eq!(generator.surface_y(-64), -60);
+        let column = generator.generate(5, -3);
+        assert_eq!(column.get_block_state(3, 0, 3), Block::Bedrock.get_state().map(|state| state as i32));
+        assert_eq!(column.get_block_state(3, 3, 3), Block::GrassBlock.get_state().map(|state| state as i32));
+        assert_eq!(column.top_block(0, 0), Some(3));
+        assert!(column.light().is_some());
+        assert_eq!(VoidGenerator::new(2).generate(0, 0).top_block(0, 0), None);
+    }
+
+    #[test]
+    fn generation_pool_test() {
+        let mut world = World::new(2, 0);
+        let mut pool = GenerationPool::new(from_level_type("minecraft:flat", 2), 2);
+        let mut tickets = ChunkTickets::new();
+        tickets.add_ticket(Vector2D::zero(), TicketType::Forced, FULL_CHUNK_LEVEL);
+        let changes = tickets.update();
+        assert_eq!(changes.loaded.len(), 1);
+        pool.apply_changes(&mut world, &changes);
+        assert!(pool.request(Vector2D::new(1, 0)));
+        assert!(!pool.request(Vector2D::new(1, 0)));
+        assert!(pool.cancel(Vector2D::new(1, 0)));
+        assert_eq!(pool.wait(&mut world, Duration::from_secs(10)), [Vector2D::zero()]);
+        assert_eq!(world.chunks().count(), 1);
+        assert_eq!(world.top_block_y(0, 0), Some(3));
+        assert!(pool.poll(&mut world).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+use std::ops::Range;
+use euclid::default::Vector3D;
+use bird_chat::component::Component;
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::block_state::BlockState;
+use crate::connection::PacketSender;
+use crate::cooking::{burn_time, Furnace, FURNACE_FUEL_SLOT, FURNACE_INPUT_SLOT, FURNACE_RESULT_SLOT};
+use crate::inventory::{max_stack_size, merge_item, Container, ContainerType, Inventory, InventoryView};
+use crate::item_container::{can_fit_inside, ShulkerBox};
+use crate::protocol::*;
+use crate::world::World;
+
+pub const HOPPER_SLOTS: usize = 5;
+/// Ticks between two moves of a hopper
+pub const HOPPER_COOLDOWN: u32 = 8;
+/// Item entities are taken from the box above the hopper bowl, heights are relative to the hopper position
+pub const HOPPER_PICKUP_MIN_Y: f64 = 11.0 / 16.0;
+pub const HOPPER_PICKUP_MAX_Y: f64 = 2.0;
+
+/// Block entity which hoppers put items into and take items from.
+/// Faces are faces of this block, the hopper below takes items through `Down`
+pub trait ItemTransport {
+    fn inventory(&self) -> &Inventory;
+
+    fn inventory_mut(&mut self) -> &mut Inventory;
+
+    /// Slots which are reachable through the face
+    fn slots(&self, _face: Direction) -> Range<usize> {
+        0..self.inventory().len()
+    }
+
+    fn can_insert(&self, slot: usize, item: &OwnedSlot, face: Direction) -> bool;
+
+    fn can_extract(&self, slot: usize, item: &OwnedSlot, face: Direction) -> bool;
+
+    /// Called after a hopper has put an item into the empty block entity
+    fn on_filled(&mut self) {}
+}
+
+/// Result slots can be only taken from, shulker boxes don't accept other shulker boxes
+impl ItemTransport for Container {
+    fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    fn can_insert(&self, slot: usize, item: &OwnedSlot, _face: Direction) -> bool {
+        self.ty.result_slot() != Some(slot) && (self.ty != ContainerType::ShulkerBox || can_fit_inside(item))
+    }
+
+    fn can_extract(&self, _slot: usize, _item: &OwnedSlot, _face: Direction) -> bool {
+        true
+    }
+}
+
+impl ItemTransport for ShulkerBox {
+    fn inventory(&self) -> &Inventory {
+        &self.container.inventory
+    }
+
+    fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.container.inventory
+    }
+
+    fn can_insert(&self, slot: usize, item: &OwnedSlot, face: Direction) -> bool {
+        self.container.can_insert(slot, item, face)
+    }
+
+    fn can_extract(&self, slot: usize, item: &OwnedSlot, face: Direction) -> bool {
+        self.container.can_extract(slot, item, face)
+    }
+}
+
+/// Like vanilla, the input is put from above and the fuel from the sides.
+/// The result and empty buckets of the fuel are taken from below
+impl ItemTransport for Furnace {
+    fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    fn slots(&self, face: Direction) -> Range<usize> {
+        match face {
+            Direction::Down => FURNACE_FUEL_SLOT..FURNACE_RESULT_SLOT + 1,
+            Direction::Up => FURNACE_INPUT_SLOT..FURNACE_INPUT_SLOT + 1,
+            _ => FURNACE_FUEL_SLOT..FURNACE_FUEL_SLOT + 1,
+        }
+    }
+
+    fn can_insert(&self, slot: usize, item: &OwnedSlot, _face: Direction) -> bool {
+        match slot {
+            FURNACE_RESULT_SLOT => false,
+            FURNACE_FUEL_SLOT => burn_time(item.item_id).is_some(),
+            _ => true,
+        }
+    }
+
+    fn can_extract(&self, slot: usize, item: &OwnedSlot, face: Direction) -> bool {
+        face != Direction::Down || slot != FURNACE_FUEL_SLOT || ItemData::from_id(item.item_id as u32)
+            .map_or(false, |data| data.name == "bucket" || data.name == "water_bucket")
+    }
+}
+
+/// Item entity which can be taken by a hopper, the caller removes entities whose item is taken
+#[derive(Clone, PartialEq, Debug)]
+pub struct DroppedItem {
+    pub entity_id: i32,
+    pub position: Vector3D<f64>,
+    pub item: Option<OwnedSlot>,
+}
+
+fn is_empty(transport: &dyn ItemTransport, face: Direction) -> bool {
+    transport.slots(face).all(|slot| transport.inventory().get(slot).is_none())
+}
+
+fn is_full(transport: &dyn ItemTransport, face: Direction) -> bool {
+    transport.slots(face).all(|slot| match transport.inventory().get(slot) {
+        Some(item) => item.item_count >= max_stack_size(item.item_id),
+        None => false,
+    })
+}
+
+/// Puts as much of the item as possible into the slots of the face, returns the amount of moved items
+pub fn insert_item(target: &mut dyn ItemTransport, item: &mut Option<OwnedSlot>, face: Direction) -> i8 {
+    let was_empty = target.inventory().slots().iter().all(Option::is_none);
+    let mut moved = 0;
+    for slot in target.slots(face) {
+        let allowed = match item.as_ref() {
+            Some(item) => target.can_insert(slot, item, face),
+            None => break,
+        };
+        if allowed {
+            if let Some(target_slot) = target.inventory_mut().get_mut(slot) {
+                moved += merge_item(item, target_slot, i8::MAX);
+            }
+        }
+    }
+    if was_empty && moved > 0 {
+        target.on_filled();
+    }
+    moved
+}
+
+fn insert_one(target: &mut dyn ItemTransport, item: &OwnedSlot, face: Direction) -> bool {
+    let mut single = Some(OwnedSlot { item_count: 1, ..item.clone() });
+    insert_item(target, &mut single, face) == 1
+}
+
+fn take_one(inventory: &mut Inventory, slot: usize) {
+    if let Some(item) = inventory.get_mut(slot) {
+        if let Some(stack) = item {
+            stack.item_count -= 1;
+            if stack.item_count <= 0 {
+                *item = None;
+            }
+        }
+    }
+}
+
+/// Hopper block entity, `facing` and `enabled` are taken from the block state
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hopper {
+    pub position: Vector3D<i32>,
+    pub inventory: Inventory,
+    cooldown: u32,
+}
+
+impl Hopper {
+    pub fn new(position: Vector3D<i32>) -> Self {
+        Self {
+            position,
+            inventory: Inventory::new(HOPPER_SLOTS),
+            cooldown: 0,
+        }
+    }
+
+    pub fn cooldown(&self) -> u32 {
+        self.cooldown
+    }
+
+    /// Direction where items are pushed, `None` if the block is not a hopper
+    pub fn facing(state: BlockState) -> Option<Direction> {
+        match state.data().name {
+            "hopper" => match state.get("facing")? {
+                "down" => Some(Direction::Down),
+                "north" => Some(Direction::North),
+                "south" => Some(Direction::South),
+                "west" => Some(Direction::West),
+                "east" => Some(Direction::East),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Position of the block entity where items are pushed
+    pub fn target_position(&self, world: &World) -> Option<Vector3D<i32>> {
+        world.get_block(self.position)
+            .and_then(Self::facing)
+            .map(|facing| self.position + facing.offset())
+    }
+
+    /// Position of the block entity where items are pulled from
+    pub fn source_position(&self) -> Vector3D<i32> {
+        self.position + Direction::Up.offset()
+    }
+
+    pub fn can_pickup(&self, position: Vector3D<f64>) -> bool {
+        let relative = position - self.position.to_f64();
+        (0.0..1.0).contains(&relative.x) && (0.0..1.0).contains(&relative.z)
+            && (HOPPER_PICKUP_MIN_Y..HOPPER_PICKUP_MAX_Y).contains(&relative.y)
+    }
+
+    /// Advances the hopper by one tick like vanilla, returns true if items were moved.
+    /// `source` and `target` are block entities at [`Hopper::source_position`] and [`Hopper::target_position`],
+    /// items are taken from `items` only when there is no block entity above.
+    /// Powered hoppers are disabled in their state and do nothing
+    pub fn tick(
+        &mut self,
+        world: &World,
+        source: Option<&mut dyn ItemTransport>,
+        target: Option<&mut dyn ItemTransport>,
+        items: &mut [DroppedItem],
+    ) -> bool {
+        self.cooldown = self.cooldown.saturating_sub(1);
+        if self.cooldown > 0 {
+            return false;
+        }
+        let (facing, enabled) = match world.get_block(self.position) {
+            Some(state) => (Self::facing(state), state.get("enabled") != Some("false")),
+            None => return false,
+        };
+        let facing = match facing {
+            Some(facing) if enabled => facing,
+            _ => return false,
+        };
+        let mut moved = false;
+        if let Some(target) = target {
+            moved = self.push(target, facing.opposite());
+        }
+        if !is_full(&*self, Direction::Up) {
+            moved |= match source {
+                Some(source) => self.pull(source),
+                None => self.pickup(items),
+            };
+        }
+        if moved {
+            self.cooldown = HOPPER_COOLDOWN;
+        }
+        moved
+    }
+
+    fn push(&mut self, target: &mut dyn ItemTransport, face: Direction) -> bool {
+        if is_full(&*target, face) {
+            return false;
+        }
+        for slot in 0..self.inventory.len() {
+            let moved = match self.inventory.get(slot) {
+                Some(item) => insert_one(target, item, face),
+                None => false,
+            };
+            if moved {
+                take_one(&mut self.inventory, slot);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn pull(&mut self, source: &mut dyn ItemTransport) -> bool {
+        if is_empty(&*source, Direction::Down) {
+            return false;
+        }
+        for slot in source.slots(Direction::Down) {
+            let moved = match source.inventory().get(slot) {
+                Some(item) if source.can_extract(slot, item, Direction::Down) => insert_one(self, item, Direction::Up),
+                _ => false,
+            };
+            if moved {
+                take_one(source.inventory_mut(), slot);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn pickup(&mut self, items: &mut [DroppedItem]) -> bool {
+        let mut moved = false;
+        for dropped in items.iter_mut() {
+            if self.can_pickup(dropped.position) {
+                moved |= insert_item(self, &mut dropped.item, Direction::Up) > 0;
+            }
+        }
+        moved
+    }
+
+    pub fn open<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<()> {
+        let mut container = Container::new(ContainerType::Hopper, Component::translation("container.hopper", Vec::new()));
+        for (index, item) in self.inventory.slots().iter().enumerate() {
+            container.inventory.set(index, item.clone());
+        }
+        view.open(container, sender).map(|_| ())
+    }
+
+    /// Takes items from the opened container after the click of the viewer
+    pub fn update_from_view(&mut self, view: &InventoryView) {
+        if let Some(container) = view.container() {
+            for (index, item) in container.inventory.slots().iter().take(self.inventory.len()).enumerate() {
+                self.inventory.set(index, item.clone());
+            }
+        }
+    }
+
+    /// Sends slots which are different in the opened container
+    pub fn update_view<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<()> {
+        let changed: Vec<usize> = match view.container() {
+            Some(container) => (0..self.inventory.len())
+                .filter(|&index| container.inventory.get(index) != self.inventory.get(index))
+                .collect(),
+            None => return Ok(()),
+        };
+        for index in changed {
+            view.set_slot(index, self.inventory.get(index).cloned(), sender)?;
+        }
+        Ok(())
+    }
+}
+
+/// Items pushed into an empty hopper wait for the whole cooldown before they move on
+impl ItemTransport for Hopper {
+    fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    fn can_insert(&self, _slot: usize, _item: &OwnedSlot, _face: Direction) -> bool {
+        true
+    }
+
+    fn can_extract(&self, _slot: usize, _item: &OwnedSlot, _face: Direction) -> bool {
+        true
+    }
+
+    fn on_filled(&mut self) {
+        self.cooldown = HOPPER_COOLDOWN;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::cooking::CookingKind;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn hopper_test() {
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let position = Vector3D::new(0, 1, 0);
+        world.set_block(position, BlockState::from_name("hopper").unwrap().with("facing", "down").unwrap());
+        let mut hopper = Hopper::new(position);
+        assert_eq!(hopper.target_position(&world), Some(Vector3D::new(0, 0, 0)));
+
+        let mut chest = Container::new(ContainerType::Generic { rows: 3 }, Component::text("Chest"));
+        chest.inventory.set(4, Some(OwnedSlot::new(item("raw_iron"), 2)));
+        let mut furnace = Furnace::new(CookingKind::Smelting, Vector3D::new(0, 0, 0));
+        assert!(hopper.tick(&world, Some(&mut chest), Some(&mut furnace), &mut []));
+        assert_eq!(hopper.inventory.get(0).map(|item| item.item_count), Some(1));
+        // The hopper waits for the cooldown before the next move
+        for _ in 1..HOPPER_COOLDOWN {
+            assert!(!hopper.tick(&world, Some(&mut chest), Some(&mut furnace), &mut []));
+        }
+        assert!(hopper.tick(&world, Some(&mut chest), Some(&mut furnace), &mut []));
+        assert_eq!(furnace.inventory.get(FURNACE_INPUT_SLOT).map(|item| item.item_count), Some(1));
+        assert!(chest.inventory.slots().iter().all(Option::is_none));
+
+        // Fuel is put into the furnace from the side, other items are not accepted
+        let mut coal = Some(OwnedSlot::new(item("coal"), 3));
+        assert_eq!(insert_item(&mut furnace, &mut Some(OwnedSlot::new(item("stone"), 1)), Direction::East), 0);
+        assert_eq!(insert_item(&mut furnace, &mut coal, Direction::East), 3);
+        assert!(coal.is_none());
+        assert!(!furnace.can_extract(FURNACE_FUEL_SLOT, furnace.inventory.get(FURNACE_FUEL_SLOT).unwrap(), Direction::Down));
+
+        let mut shulker = Container::new(ContainerType::ShulkerBox, Component::text("Box"));
+        assert_eq!(insert_item(&mut shulker, &mut Some(OwnedSlot::new(item("shulker_box"), 1)), Direction::Up), 0);
+
+        let mut target = Hopper::new(Vector3D::new(0, 0, 0));
+        let mut items = [
+            DroppedItem { entity_id: 1, position: Vector3D::new(0.5, 1.8, 0.5), item: Some(OwnedSlot::new(item("stick"), 64)) },
+            DroppedItem { entity_id: 2, position: Vector3D::new(0.5, 3.5, 0.5), item: Some(OwnedSlot::new(item("stick"), 1)) },
+        ];
+        for _ in 0..HOPPER_COOLDOWN {
+            hopper.tick(&world, None, Some(&mut target), &mut items);
+        }
+        assert!(items[0].item.is_none());
+        assert!(items[1].item.is_some());
+        assert_eq!(target.inventory.get(0).map(|item| item.item_count), Some(1));
+        assert_eq!(target.cooldown(), HOPPER_COOLDOWN);
+
+        world.set_block(position, world.get_block(position).unwrap().with("enabled", "false").unwrap());
+        for _ in 0..HOPPER_COOLDOWN * 2 {
+            assert!(!hopper.tick(&world, None, Some(&mut target), &mut items));
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use bird_protocol::ProtocolPacketState;
+
+/// Enters a trace span until the end of the scope. Nothing is done without the `tracing` feature,
+/// so fields must not have side effects
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ($($arguments: tt)*) => {
+        tracing::trace_span!($($arguments)*).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ($($arguments: tt)*) => {
+        $crate::instrument::DisabledSpan
+    };
+}
+
+pub(crate) use trace_span;
+
+/// Warning about the server state, arguments are formatted like `format!`.
+/// It is printed to stderr without the `tracing` feature
+#[cfg(feature = "tracing")]
+macro_rules! warn {
+    ($($arguments: tt)*) => {
+        tracing::warn!($($arguments)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! warn {
+    ($($arguments: tt)*) => {
+        eprintln!($($arguments)*)
+    };
+}
+
+pub(crate) use warn;
+
+/// Guard which is returned by `trace_span` without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+pub struct DisabledSpan;
+
+/// Span of the connection from the handshake until it is closed. It is stored with the connection
+/// and entered while its packets are handled, so packet and login spans are its children
+#[derive(Clone, Debug)]
+pub struct ConnectionSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl ConnectionSpan {
+    #[allow(unused_variables)]
+    pub fn new(connection: u64, address: &str) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("connection", connection, address, state = tracing::field::Empty),
+        }
+    }
+
+    #[allow(unused_variables)]
+    pub fn record_state(&self, state: ProtocolPacketState) {
+        #[cfg(feature = "tracing")]
+        self.span.record("state", tracing::field::debug(state));
+    }
+
+    pub fn in_scope<T>(&self, function: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(function);
+        #[cfg(not(feature = "tracing"))]
+        function()
+    }
+
+    /// Called when the connection is closed, the reason is recorded as an event of the span
+    #[allow(unused_variables)]
+    pub fn close(self, reason: &str) {
+        #[cfg(feature = "tracing")]
+        self.span.in_scope(|| tracing::info!(reason, "connection closed"));
+    }
+}
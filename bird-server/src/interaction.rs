@@ -0,0 +1,277 @@
+use euclid::default::Vector3D;
+use bird_data::{Block, BlockData, ItemData};
+use bird_protocol::anyhow;
+use crate::block_changes::BlockChangeBatch;
+use crate::connection::PacketSender;
+use crate::gamemode::GameMode;
+use crate::protocol::*;
+use crate::world::{is_air, World};
+use crate::world::query::is_replaceable;
+
+/// Stages from 0 to 9 are shown, other values remove the crack
+pub const MAX_DESTROY_STAGE: u8 = 9;
+const CLEAR_DESTROY_STAGE: u8 = u8::MAX;
+/// Vanilla accepts digging which is finished a bit earlier than the server expects
+const FINISH_TOLERANCE: f32 = 0.7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlockChangeCause {
+    Break,
+    Place,
+}
+
+/// Change of a block by a player, the world applies it if it is not cancelled.
+/// Cancelled changes don't need packets, the acknowledgment reverts the prediction of the client
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockChangeEvent {
+    pub entity_id: i32,
+    pub position: Vector3D<i32>,
+    pub previous: i32,
+    pub state: i32,
+    pub cause: BlockChangeCause,
+}
+
+impl BlockChangeEvent {
+    /// Changes the block if it wasn't changed after the event, viewers receive the change with the batch
+    pub fn apply(&self, world: &mut World, batch: &mut BlockChangeBatch) -> bool {
+        if world.get_block_state(self.position) != Some(self.previous) {
+            return false;
+        }
+        world.set_block_state(self.position, self.state);
+        batch.push(self.position, self.state);
+        true
+    }
+}
+
+/// Part of the block which is broken in one tick, 1 breaks it instantly and 0 never breaks it.
+/// Enchantments, effects and being in water are not applied
+pub fn break_progress_per_tick(state: i32, item_id: Option<i32>) -> f32 {
+    let block = match BlockData::from_state(state as u32) {
+        Some(block) if block.diggable && block.hardness >= 0.0 => block,
+        _ => return 0.0,
+    };
+    if block.hardness == 0.0 {
+        return 1.0;
+    }
+    let speed = item_id.and_then(|item_id| block.get_material()?.get_value(item_id)).unwrap_or(1.0);
+    // Harvest tools are not in the data, blocks which are mined by pickaxes need the tool for drops
+    let harvestable = speed > 1.0 || !block.material.contains("mineable/pickaxe");
+    speed / block.hardness / if harvestable { 30.0 } else { 100.0 }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Digging {
+    position: Vector3D<i32>,
+    state: i32,
+    started: u64,
+    per_tick: f32,
+    stage: Option<u8>,
+}
+
+impl Digging {
+    fn progress(&self, tick: u64) -> f32 {
+        (tick.saturating_sub(self.started) + 1) as f32 * self.per_tick
+    }
+}
+
+/// Block breaking and placing of one player. `viewers` of destroy stages are other players,
+/// the player renders its own crack. Sequences of handled packets are acknowledged by [`BlockInteraction::flush`]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BlockInteraction {
+    digging: Option<Digging>,
+    sequence: Option<i32>,
+}
+
+impl BlockInteraction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Position of the block which is being broken
+    pub fn digging(&self) -> Option<Vector3D<i32>> {
+        self.digging.map(|digging| digging.position)
+    }
+
+    fn acknowledge(&mut self, sequence: i32) {
+        self.sequence = Some(self.sequence.map_or(sequence, |acknowledged| acknowledged.max(sequence)));
+    }
+
+    fn break_event(entity_id: i32, position: Vector3D<i32>, previous: i32) -> BlockChangeEvent {
+        let state = Block::Air.get_data().default_state_id as i32;
+        BlockChangeEvent { entity_id, position, previous, state, cause: BlockChangeCause::Break }
+    }
+
+    /// Stops breaking and removes the crack from viewers
+    pub fn stop_digging<S: PacketSender>(&mut self, entity_id: i32, viewers: &mut S) -> anyhow::Result<()> {
+        match self.digging.take() {
+            Some(Digging { position, stage: Some(_), .. }) => viewers.send_packet(&SetBlockDestroyStagePS2C {
+                entity_id,
+                location: position,
+                destroy_stage: CLEAR_DESTROY_STAGE,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Handles digging actions, `tick` is the current server tick. Returns the change if the block is broken
+    pub fn handle_action<S: PacketSender>(
+        &mut self,
+        entity_id: i32,
+        packet: &PlayerActionPC2S,
+        game_mode: GameMode,
+        item_id: Option<i32>,
+        world: &World,
+        tick: u64,
+        viewers: &mut S,
+    ) -> anyhow::Result<Option<BlockChangeEvent>> {
+        if !matches!(
+            packet.status,
+            PlayerActionStatus::StartedDigging | PlayerActionStatus::CancelledDigging | PlayerActionStatus::FinishedDigging,
+        ) {
+            return Ok(None);
+        }
+        self.acknowledge(packet.sequence);
+        let state = match world.get_block_state(packet.location) {
+            Some(state) if !is_air(state) => state,
+            _ => return self.stop_digging(entity_id, viewers).map(|_| None),
+        };
+        match (packet.status, game_mode) {
+            (_, GameMode::Adventure | GameMode::Spectator) => Ok(None),
+            (PlayerActionStatus::StartedDigging, GameMode::Creative) => Ok(Some(Self::break_event(entity_id, packet.location, state))),
+            (PlayerActionStatus::StartedDigging, _) => {
+                self.stop_digging(entity_id, viewers)?;
+                let per_tick = break_progress_per_tick(state, item_id);
+                if per_tick >= 1.0 {
+                    return Ok(Some(Self::break_event(entity_id, packet.location, state)));
+                }
+                if per_tick > 0.0 {
+                    self.digging = Some(Digging { position: packet.location, state, started: tick, per_tick, stage: None });
+                }
+                Ok(None)
+            }
+            (PlayerActionStatus::FinishedDigging, _) => {
+                let finished = self.digging.map_or(false, |digging| {
+                    digging.position == packet.location && digging.state == state && digging.progress(tick) >= FINISH_TOLERANCE
+                });
+                self.stop_digging(entity_id, viewers)?;
+                Ok(finished.then(|| Self::break_event(entity_id, packet.location, state)))
+            }
+            _ => self.stop_digging(entity_id, viewers).map(|_| None),
+        }
+    }
+
+    /// Sends the destroy stage to viewers when it changes
+    pub fn tick<S: PacketSender>(&mut self, entity_id: i32, tick: u64, viewers: &mut S) -> anyhow::Result<()> {
+        let digging = match self.digging {
+            Some(ref mut digging) => digging,
+            None => return Ok(()),
+        };
+        let stage = ((digging.progress(tick) * 10.0) as u8).min(MAX_DESTROY_STAGE);
+        if digging.stage == Some(stage) {
+            return Ok(());
+        }
+        digging.stage = Some(stage);
+        viewers.send_packet(&SetBlockDestroyStagePS2C { entity_id, location: digging.position, destroy_stage: stage })
+    }
+
+    /// Places the block of the held item against the clicked face, the clicked block is replaced if it is air or a fluid.
+    /// Interactions with the clicked block must be handled before. Entities inside the block are not checked
+    pub fn handle_use_item_on(
+        &mut self,
+        entity_id: i32,
+        packet: &UseItemOnPC2S,
+        game_mode: GameMode,
+        item: Option<&OwnedSlot>,
+        world: &World,
+    ) -> Option<BlockChangeEvent> {
+        self.acknowledge(packet.sequence);
+        if matches!(game_mode, GameMode::Adventure | GameMode::Spectator) {
+            return None;
+        }
+        let item = ItemData::from_id(item?.item_id as u32)?;
+        let state = Block::from_name(item.name)?.get_data().default_state_id as i32;
+        let position = match is_replaceable(world.get_block_state(packet.location)?) {
+            true => packet.location,
+            false => packet.location + packet.face.offset(),
+        };
+        let previous = world.get_block_state(position)?;
+        match is_replaceable(previous) {
+            true => Some(BlockChangeEvent { entity_id, position, previous, state, cause: BlockChangeCause::Place }),
+            false => None,
+        }
+    }
+
+    /// Acknowledges the last sequence of the tick, the client stops predicting changes up to it
+    pub fn flush<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<()> {
+        match self.sequence.take() {
+            Some(sequence_id) => sender.send_packet(&AcknowledgeBlockChangePS2C { sequence_id }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::connection::PacketQueue;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn block_interaction_test() {
+        let mut world = World::new(2, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let stone = Block::Stone.get_data().default_state_id as i32;
+        let position = Vector3D::new(1, 1, 1);
+        world.set_block_state(position, stone);
+        let action = |status, sequence| PlayerActionPC2S { status, location: position, face: BlockFace::Up, sequence };
+        let mut interaction = BlockInteraction::new();
+        let mut viewers = PacketQueue::new();
+
+        // Stone is broken slowly without a pickaxe
+        let per_tick = break_progress_per_tick(stone, None);
+        assert!(per_tick > 0.0 && per_tick < 0.01);
+        let started = action(PlayerActionStatus::StartedDigging, 1);
+        assert_eq!(interaction.handle_action(1, &started, GameMode::Survival, None, &world, 0, &mut viewers).unwrap(), None);
+        assert_eq!(interaction.digging(), Some(position));
+        interaction.tick(1, 20, &mut viewers).unwrap();
+        interaction.tick(1, 21, &mut viewers).unwrap();
+        assert_eq!(viewers.take().len(), 1);
+        let finished = action(PlayerActionStatus::FinishedDigging, 2);
+        assert_eq!(interaction.handle_action(1, &finished, GameMode::Survival, None, &world, 30, &mut viewers).unwrap(), None);
+        // The crack is removed
+        assert_eq!(viewers.take().len(), 1);
+        interaction.handle_action(1, &started, GameMode::Survival, None, &world, 100, &mut viewers).unwrap();
+        let ticks = (FINISH_TOLERANCE / per_tick).ceil() as u64;
+        let event = interaction.handle_action(1, &finished, GameMode::Survival, None, &world, 100 + ticks, &mut viewers).unwrap().unwrap();
+        assert_eq!((event.previous, event.cause), (stone, BlockChangeCause::Break));
+        let mut queue = PacketQueue::new();
+        interaction.flush(&mut queue).unwrap();
+        interaction.flush(&mut queue).unwrap();
+        assert_eq!(queue.take().len(), 1);
+
+        let mut batch = BlockChangeBatch::new();
+        assert!(event.apply(&mut world, &mut batch));
+        assert!(!event.apply(&mut world, &mut batch));
+        assert_eq!(batch.len(), 1);
+        assert!(interaction.handle_action(1, &started, GameMode::Creative, None, &world, 200, &mut viewers).unwrap().is_none());
+
+        world.set_block_state(position, stone);
+        let event = interaction.handle_action(1, &started, GameMode::Creative, None, &world, 200, &mut viewers).unwrap();
+        assert_eq!(event.map(|event| event.state), Some(Block::Air.get_data().default_state_id as i32));
+        assert!(interaction.handle_action(1, &started, GameMode::Adventure, None, &world, 200, &mut viewers).unwrap().is_none());
+
+        let stone_item = OwnedSlot::new(item("stone"), 1);
+        let use_on = UseItemOnPC2S {
+            hand: Hand::Main,
+            location: position,
+            face: Direction::Up,
+            cursor: Vector3D::new(0.5, 1.0, 0.5),
+            inside_block: false,
+            sequence: 3,
+        };
+        let event = interaction.handle_use_item_on(1, &use_on, GameMode::Survival, Some(&stone_item), &world).unwrap();
+        assert_eq!((event.position, event.state, event.cause), (position + Vector3D::new(0, 1, 0), stone, BlockChangeCause::Place));
+        assert!(interaction.handle_use_item_on(1, &use_on, GameMode::Survival, None, &world).is_none());
+    }
+}
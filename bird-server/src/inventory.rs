@@ -0,0 +1,706 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::item_container::bundle_click;
+use crate::protocol::*;
+
+pub const PLAYER_INVENTORY_SIZE: usize = 46;
+pub const PLAYER_CRAFTING_RESULT_SLOT: usize = 0;
+pub const PLAYER_ARMOR_START: usize = 5;
+pub const PLAYER_MAIN_START: usize = 9;
+pub const PLAYER_HOTBAR_START: usize = 36;
+pub const PLAYER_OFFHAND_SLOT: usize = 45;
+/// Main inventory and hotbar are shown in the bottom of every opened container
+pub const PLAYER_STORAGE_SIZE: usize = 36;
+
+pub fn item_id(name: &str) -> anyhow::Result<i32> {
+    bird_data::ItemData::from_name(name)
+        .map(|item| item.id as i32)
+        .ok_or_else(|| anyhow::Error::msg("Unknown item"))
+}
+
+pub fn max_stack_size(item_id: i32) -> i8 {
+    bird_data::ItemData::from_id(item_id as u32)
+        .map(|data| data.stack_size as i8)
+        .unwrap_or(64)
+}
+
+pub fn can_stack(first: &OwnedSlot, second: &OwnedSlot) -> bool {
+    first.item_id == second.item_id && first.nbt == second.nbt
+}
+
+fn slot_eq(owned: &Option<OwnedSlot>, borrowed: &Option<Slot>) -> bool {
+    match (owned, borrowed) {
+        (Some(owned), Some(borrowed)) => owned.as_slot() == *borrowed,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn split_item(item: &mut Option<OwnedSlot>, count: i8) -> Option<OwnedSlot> {
+    let current = item.as_mut()?;
+    let count = count.min(current.item_count);
+    if count <= 0 {
+        return None;
+    }
+    let mut taken = current.clone();
+    taken.item_count = count;
+    current.item_count -= count;
+    if current.item_count <= 0 {
+        *item = None;
+    }
+    Some(taken)
+}
+
+/// Moves items from source to target as long as they are stackable, returns the amount of moved items
+pub fn merge_item(source: &mut Option<OwnedSlot>, target: &mut Option<OwnedSlot>, limit: i8) -> i8 {
+    let moved = match (source.as_ref(), target.as_mut()) {
+        (Some(source_item), Some(target_item)) if can_stack(source_item, target_item) => {
+            let free = max_stack_size(target_item.item_id) - target_item.item_count;
+            let moved = free.min(limit).min(source_item.item_count).max(0);
+            target_item.item_count += moved;
+            moved
+        }
+        (Some(source_item), None) => {
+            let moved = max_stack_size(source_item.item_id).min(limit).min(source_item.item_count);
+            let mut target_item = source_item.clone();
+            target_item.item_count = moved;
+            *target = Some(target_item);
+            moved
+        }
+        _ => 0,
+    };
+    split_item(source, moved);
+    moved
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Inventory {
+    slots: Vec<Option<OwnedSlot>>,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self { slots: vec![None; size] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&OwnedSlot> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Option<OwnedSlot>> {
+        self.slots.get_mut(index)
+    }
+
+    pub fn set(&mut self, index: usize, item: Option<OwnedSlot>) -> Option<OwnedSlot> {
+        std::mem::replace(&mut self.slots[index], item)
+    }
+
+    pub fn slots(&self) -> &[Option<OwnedSlot>] {
+        &self.slots
+    }
+
+    /// Puts the item into the given range of slots, similar stacks are filled first. Returns the items which are not fit
+    pub fn add_item(&mut self, item: OwnedSlot, range: std::ops::Range<usize>) -> Option<OwnedSlot> {
+        let mut item = Some(item);
+        for only_similar in [true, false] {
+            for index in range.clone() {
+                if item.is_none() { return None; }
+                let target = &mut self.slots[index];
+                if only_similar == target.is_some() {
+                    merge_item(&mut item, target, i8::MAX);
+                }
+            }
+        }
+        item
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ContainerType {
+    Generic {
+        rows: u8,
+    },
+    Generic3x3,
+    Anvil,
+    Beacon,
+    BlastFurnace,
+    BrewingStand,
+    Crafting,
+    Enchantment,
+    Furnace,
+    Grindstone,
+    Hopper,
+    Lectern,
+    Loom,
+    Merchant,
+    ShulkerBox,
+    Smithing,
+    Smoker,
+    Cartography,
+    Stonecutter,
+}
+
+impl ContainerType {
+    pub const fn size(&self) -> usize {
+        match self {
+            ContainerType::Generic { rows } => *rows as usize * 9,
+            ContainerType::Generic3x3 => 9,
+            ContainerType::Anvil | ContainerType::BlastFurnace | ContainerType::Furnace |
+            ContainerType::Smoker | ContainerType::Grindstone | ContainerType::Merchant |
+            ContainerType::Smithing | ContainerType::Cartography => 3,
+            ContainerType::Beacon | ContainerType::Lectern => 1,
+            ContainerType::BrewingStand | ContainerType::Hopper => 5,
+            ContainerType::Crafting => 10,
+            ContainerType::Enchantment | ContainerType::Stonecutter => 2,
+            ContainerType::Loom => 4,
+            ContainerType::ShulkerBox => 27,
+        }
+    }
+
+    /// Slot where items can be only taken from
+    pub const fn result_slot(&self) -> Option<usize> {
+        match self {
+            ContainerType::Anvil | ContainerType::BlastFurnace | ContainerType::Furnace |
+            ContainerType::Smoker | ContainerType::Grindstone | ContainerType::Merchant |
+            ContainerType::Smithing | ContainerType::Cartography => Some(2),
+            ContainerType::Crafting => Some(0),
+            ContainerType::Stonecutter => Some(1),
+            ContainerType::Loom => Some(3),
+            _ => None,
+        }
+    }
+
+    pub const fn window_type(&self) -> InventorySizeType {
+        match self {
+            ContainerType::Generic { rows: 1 } => InventorySizeType::Inventory1,
+            ContainerType::Generic { rows: 2 } => InventorySizeType::Inventory2,
+            ContainerType::Generic { rows: 3 } => InventorySizeType::Inventory3,
+            ContainerType::Generic { rows: 4 } => InventorySizeType::Inventory4,
+            ContainerType::Generic { rows: 5 } => InventorySizeType::Inventory5,
+            ContainerType::Generic { .. } => InventorySizeType::Inventory6,
+            ContainerType::Generic3x3 => InventorySizeType::Inventory3x3,
+            ContainerType::Anvil => InventorySizeType::Anvil,
+            ContainerType::Beacon => InventorySizeType::Beacon,
+            ContainerType::BlastFurnace => InventorySizeType::BlastFurnace,
+            ContainerType::BrewingStand => InventorySizeType::BrewingStand,
+            ContainerType::Crafting => InventorySizeType::Crafting,
+            ContainerType::Enchantment => InventorySizeType::Enchantment,
+            ContainerType::Furnace => InventorySizeType::Furnace,
+            ContainerType::Grindstone => InventorySizeType::Grindstone,
+            ContainerType::Hopper => InventorySizeType::Hopper,
+            ContainerType::Lectern => InventorySizeType::Lectern,
+            ContainerType::Loom => InventorySizeType::Loom,
+            ContainerType::Merchant => InventorySizeType::Merchant,
+            ContainerType::ShulkerBox => InventorySizeType::ShulkerBox,
+            ContainerType::Smithing => InventorySizeType::Smithing,
+            ContainerType::Smoker => InventorySizeType::Smoker,
+            ContainerType::Cartography => InventorySizeType::Cartography,
+            ContainerType::Stonecutter => InventorySizeType::Stonecutter,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Container {
+    pub ty: ContainerType,
+    pub title: Component<'static>,
+    pub inventory: Inventory,
+    properties: BTreeMap<i16, i16>,
+}
+
+impl Container {
+    pub fn new(ty: ContainerType, title: Component<'static>) -> Self {
+        Self {
+            ty,
+            title,
+            inventory: Inventory::new(ty.size()),
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn get_property(&self, property: impl Into<ContainerProperty>) -> Option<i16> {
+        self.properties.get(&property.into().index()).copied()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DragKind {
+    Split,
+    One,
+    Clone,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Drag {
+    kind: DragKind,
+    slots: Vec<usize>,
+}
+
+/// State of the player inventory and of the opened container
+#[derive(Clone, PartialEq, Debug)]
+pub struct InventoryView {
+    pub player: Inventory,
+    pub cursor: Option<OwnedSlot>,
+    pub creative: bool,
+    state_id: i32,
+    window_id: u8,
+    container: Option<Container>,
+    drag: Option<Drag>,
+}
+
+impl Default for InventoryView {
+    fn default() -> Self {
+        Self {
+            player: Inventory::new(PLAYER_INVENTORY_SIZE),
+            cursor: None,
+            creative: false,
+            state_id: 0,
+            window_id: PLAYER_INVENTORY_ID,
+            container: None,
+            drag: None,
+        }
+    }
+}
+
+impl InventoryView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state_id(&self) -> i32 {
+        self.state_id
+    }
+
+    pub fn window_id(&self) -> u8 {
+        self.window_id
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        self.container.as_ref()
+    }
+
+    /// Changed slots are not sent, the caller syncs the window
+    pub fn container_mut(&mut self) -> Option<&mut Container> {
+        self.container.as_mut()
+    }
+
+    fn next_state_id(&mut self) -> i32 {
+        self.state_id = (self.state_id + 1) & 0x7FFF;
+        self.state_id
+    }
+
+    pub fn window_size(&self) -> usize {
+        match self.container {
+            Some(ref container) => container.ty.size() + PLAYER_STORAGE_SIZE,
+            None => PLAYER_INVENTORY_SIZE,
+        }
+    }
+
+    fn slot_mut(&mut self, slot: usize) -> Option<&mut Option<OwnedSlot>> {
+        match self.container {
+            Some(ref mut container) => {
+                let size = container.ty.size();
+                match slot < size {
+                    true => container.inventory.get_mut(slot),
+                    false if slot < size + PLAYER_STORAGE_SIZE => self.player.get_mut(slot - size + PLAYER_MAIN_START),
+                    false => None,
+                }
+            }
+            None => self.player.get_mut(slot),
+        }
+    }
+
+    fn slot(&mut self, slot: usize) -> Option<OwnedSlot> {
+        self.slot_mut(slot).and_then(|item| item.clone())
+    }
+
+    fn is_result_slot(&self, slot: usize) -> bool {
+        match self.container {
+            Some(ref container) => container.ty.result_slot() == Some(slot),
+            None => slot == PLAYER_CRAFTING_RESULT_SLOT,
+        }
+    }
+
+    fn window_slots(&mut self) -> Vec<Option<OwnedSlot>> {
+        (0..self.window_size()).map(|slot| self.slot(slot)).collect()
+    }
+
+    /// Sends the whole content of the current window and the cursor
+    pub fn sync<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<()> {
+        let slots = self.window_slots();
+        let state_id = self.next_state_id();
+        sender.send_packet(&SetContainerContentPS2C {
+            window_id: self.window_id,
+            state_id,
+            slot_data: Cow::Owned(slots.iter().map(|item| item.as_ref().map(|item| item.as_slot())).collect()),
+            carried_item: self.cursor.as_ref().map(|item| item.as_slot()),
+        })
+    }
+
+    pub fn set_slot<S: PacketSender>(&mut self, slot: usize, item: Option<OwnedSlot>, sender: &mut S) -> anyhow::Result<()> {
+        let target = self.slot_mut(slot).ok_or_else(|| anyhow::Error::msg("Slot is out of window"))?;
+        *target = item;
+        self.send_slot(slot, sender)
+    }
+
+    fn send_slot<S: PacketSender>(&mut self, slot: usize, sender: &mut S) -> anyhow::Result<()> {
+        let item = self.slot(slot);
+        let state_id = self.next_state_id();
+        sender.send_packet(&SetContainerSlotPS2C {
+            window_id: self.window_id as i8,
+            state_id,
+            slot: slot as i16,
+            slot_data: item.as_ref().map(|item| item.as_slot()),
+        })
+    }
+
+    pub fn send_cursor<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<()> {
+        let state_id = self.next_state_id();
+        sender.send_packet(&SetContainerSlotPS2C {
+            window_id: CURSOR_WINDOW_ID,
+            state_id,
+            slot: CURSOR_SLOT_ID,
+            slot_data: self.cursor.as_ref().map(|item| item.as_slot()),
+        })
+    }
+
+    /// Properties of other window types are not sent
+    pub fn set_property<S: PacketSender>(&mut self, property: impl Into<ContainerProperty>, value: i16, sender: &mut S) -> anyhow::Result<()> {
+        let container = self.container.as_mut().ok_or_else(|| anyhow::Error::msg("There is no opened container"))?;
+        let property = property.into();
+        if !property.is_supported_by(container.ty.window_type()) {
+            return Err(anyhow::Error::msg("The opened container doesn't have the property"));
+        }
+        container.properties.insert(property.index(), value);
+        sender.send_packet(&SetContainerPropertyPS2C::new(self.window_id, property, value))
+    }
+
+    /// Opens the container, the previously opened container is closed and returned
+    pub fn open<S: PacketSender>(&mut self, container: Container, sender: &mut S) -> anyhow::Result<Option<Container>> {
+        let previous = self.container.take();
+        // Window ids are from 1 to 100 like in vanilla
+        self.window_id = self.window_id % 100 + 1;
+        sender.send_packet(&OpenScreenPS2C {
+            window_id: self.window_id as i32,
+            window_type: container.ty.window_type(),
+            window_title: container.title.clone(),
+        })?;
+        for (property, value) in &container.properties {
+            sender.send_packet(&SetContainerPropertyPS2C { window_id: self.window_id, property: *property, value: *value })?;
+        }
+        self.container = Some(container);
+        self.drag = None;
+        self.sync(sender)?;
+        Ok(previous)
+    }
+
+    pub fn close<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<Option<Container>> {
+        if self.container.is_some() {
+            sender.send_packet(&CloseContainerPS2C { window_id: self.window_id })?;
+        }
+        Ok(self.handle_close())
+    }
+
+    /// Called when the client has closed the window, the cursor item is put back to the inventory.
+    /// Returns the container which was opened
+    pub fn handle_close(&mut self) -> Option<Container> {
+        self.drag = None;
+        if let Some(cursor) = self.cursor.take() {
+            self.cursor = self.player.add_item(cursor, PLAYER_MAIN_START..PLAYER_OFFHAND_SLOT);
+        }
+        self.container.take()
+    }
+
+    /// Applies the click, items which should be dropped are returned.
+    /// If the client prediction is different from the server result, the difference is sent back
+    pub fn handle_click<S: PacketSender>(&mut self, packet: &ClickContainerPC2S, sender: &mut S) -> anyhow::Result<Vec<OwnedSlot>> {
+        let current_window = match self.container {
+            Some(_) => self.window_id,
+            None => PLAYER_INVENTORY_ID,
+        };
+        if packet.window_id != current_window {
+            return Ok(Vec::new());
+        }
+        let dropped = self.apply_click(packet)?;
+        if packet.state_id != self.state_id {
+            self.sync(sender)?;
+            return Ok(dropped);
+        }
+        for changed in packet.changed_slots.iter() {
+            let slot = changed.slot as usize;
+            let differs = match self.slot_mut(slot) {
+                Some(item) => !slot_eq(item, &changed.slot_data),
+                None => false,
+            };
+            if differs {
+                self.send_slot(slot, sender)?;
+            }
+        }
+        if !slot_eq(&self.cursor, &packet.carried_item) {
+            self.send_cursor(sender)?;
+        }
+        Ok(dropped)
+    }
+
+    fn apply_click(&mut self, packet: &ClickContainerPC2S) -> anyhow::Result<Vec<OwnedSlot>> {
+        let mut dropped = Vec::new();
+        if packet.mode != ClickContainerMode::QuickCraft {
+            self.drag = None;
+        }
+        match packet.mode {
+            ClickContainerMode::Pickup if packet.slot == OUTSIDE_SLOT_ID => {
+                let count = match packet.button {
+                    0 => i8::MAX,
+                    _ => 1,
+                };
+                dropped.extend(split_item(&mut self.cursor, count));
+            }
+            ClickContainerMode::Pickup => {
+                let slot = packet.slot as usize;
+                if packet.button != 1 || !self.bundle_pickup(slot)? {
+                    self.pickup(slot, packet.button == 0);
+                }
+            }
+            ClickContainerMode::QuickMove => self.quick_move(packet.slot as usize),
+            ClickContainerMode::Swap => self.swap(packet.slot as usize, packet.button as usize),
+            ClickContainerMode::Clone => {
+                if self.creative && self.cursor.is_none() {
+                    self.cursor = self.slot(packet.slot as usize).map(|mut item| {
+                        item.item_count = max_stack_size(item.item_id);
+                        item
+                    });
+                }
+            }
+            ClickContainerMode::Throw if packet.slot != OUTSIDE_SLOT_ID => {
+                let count = match packet.button {
+                    0 => 1,
+                    _ => i8::MAX,
+                };
+                if let Some(item) = self.slot_mut(packet.slot as usize) {
+                    dropped.extend(split_item(item, count));
+                }
+            }
+            ClickContainerMode::Throw => {}
+            ClickContainerMode::QuickCraft => self.quick_craft(packet.slot, packet.button),
+            ClickContainerMode::PickupAll => self.pickup_all(),
+        }
+        Ok(dropped)
+    }
+
+    /// Right click which inserts into a bundle or takes out of it, returns false if there is no bundle
+    fn bundle_pickup(&mut self, slot: usize) -> anyhow::Result<bool> {
+        if self.is_result_slot(slot) {
+            return Ok(false);
+        }
+        let mut cursor = self.cursor.take();
+        let result = match self.slot_mut(slot) {
+            Some(item) => bundle_click(&mut cursor, item),
+            None => Ok(false),
+        };
+        self.cursor = cursor;
+        result
+    }
+
+    fn pickup(&mut self, slot: usize, left: bool) {
+        let is_result = self.is_result_slot(slot);
+        let mut cursor = self.cursor.take();
+        if let Some(item) = self.slot_mut(slot) {
+            match (cursor.is_some(), item.is_some()) {
+                (false, true) => {
+                    let count = match left || is_result {
+                        true => i8::MAX,
+                        false => (item.as_ref().unwrap().item_count + 1) / 2,
+                    };
+                    cursor = split_item(item, count);
+                }
+                (true, _) if is_result => {
+                    // Result can be only taken if it can be stacked with the cursor
+                    if item.as_ref().map_or(false, |item| can_stack(item, cursor.as_ref().unwrap())) {
+                        merge_item(item, &mut cursor, i8::MAX);
+                    }
+                }
+                (true, false) => {
+                    merge_item(&mut cursor, item, if left { i8::MAX } else { 1 });
+                }
+                (true, true) => {
+                    match can_stack(item.as_ref().unwrap(), cursor.as_ref().unwrap()) {
+                        true => {
+                            merge_item(&mut cursor, item, if left { i8::MAX } else { 1 });
+                        }
+                        false => std::mem::swap(item, &mut cursor),
+                    }
+                }
+                (false, false) => {}
+            }
+        }
+        self.cursor = cursor;
+    }
+
+    fn quick_move(&mut self, slot: usize) {
+        let size = self.window_size();
+        let targets: Vec<usize> = match self.container {
+            Some(ref container) => {
+                let container_size = container.ty.size();
+                match slot < container_size {
+                    true => (container_size..size).rev().collect(),
+                    false => (0..container_size).filter(|target| Some(*target) != container.ty.result_slot()).collect(),
+                }
+            }
+            None => match slot {
+                PLAYER_MAIN_START..=35 => (PLAYER_HOTBAR_START..PLAYER_OFFHAND_SLOT).collect(),
+                PLAYER_HOTBAR_START..=44 => (PLAYER_MAIN_START..PLAYER_HOTBAR_START).collect(),
+                _ => (PLAYER_MAIN_START..PLAYER_OFFHAND_SLOT).collect(),
+            },
+        };
+        let mut item = match self.slot_mut(slot) {
+            Some(item) => item.take(),
+            None => return,
+        };
+        for only_similar in [true, false] {
+            for target in &targets {
+                if item.is_none() { break; }
+                if let Some(target) = self.slot_mut(*target) {
+                    if only_similar == target.is_some() {
+                        merge_item(&mut item, target, i8::MAX);
+                    }
+                }
+            }
+        }
+        if let Some(source) = self.slot_mut(slot) {
+            *source = item;
+        }
+    }
+
+    fn swap(&mut self, slot: usize, button: usize) {
+        let player_slot = match button {
+            0..=8 => PLAYER_HOTBAR_START + button,
+            40 => PLAYER_OFFHAND_SLOT,
+            _ => return,
+        };
+        if self.is_result_slot(slot) && self.player.get(player_slot).is_some() {
+            return;
+        }
+        let mut hotbar = self.player.set(player_slot, None);
+        if let Some(item) = self.slot_mut(slot) {
+            std::mem::swap(item, &mut hotbar);
+        }
+        self.player.set(player_slot, hotbar);
+    }
+
+    fn quick_craft(&mut self, slot: i16, button: i8) {
+        let kind = match button >> 2 {
+            0 => DragKind::Split,
+            1 => DragKind::One,
+            _ => DragKind::Clone,
+        };
+        match button & 0b11 {
+            0 => self.drag = Some(Drag { kind, slots: Vec::new() }),
+            1 => {
+                let slot = slot as usize;
+                if self.is_result_slot(slot) { return; }
+                if let Some(ref mut drag) = self.drag {
+                    if drag.kind == kind && !drag.slots.contains(&slot) {
+                        drag.slots.push(slot);
+                    }
+                }
+            }
+            2 => {
+                let drag = match self.drag.take() {
+                    Some(drag) if drag.kind == kind && !drag.slots.is_empty() => drag,
+                    _ => return,
+                };
+                let mut cursor = self.cursor.take();
+                let per_slot = match (drag.kind, cursor.as_ref()) {
+                    (DragKind::Split, Some(item)) => (item.item_count as usize / drag.slots.len()).max(1) as i8,
+                    (DragKind::Clone, Some(item)) if self.creative => max_stack_size(item.item_id),
+                    (DragKind::One, _) => 1,
+                    _ => 0,
+                };
+                for slot in drag.slots {
+                    if let Some(target) = self.slot_mut(slot) {
+                        match drag.kind {
+                            DragKind::Clone if per_slot > 0 => {
+                                if let Some(mut item) = cursor.clone() {
+                                    item.item_count = per_slot;
+                                    *target = Some(item);
+                                }
+                            }
+                            DragKind::Clone => {}
+                            _ => {
+                                merge_item(&mut cursor, target, per_slot);
+                            }
+                        }
+                    }
+                }
+                self.cursor = cursor;
+            }
+            _ => self.drag = None,
+        }
+    }
+
+    fn pickup_all(&mut self) {
+        let mut cursor = match self.cursor.take() {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let max = max_stack_size(cursor.item_id);
+        for slot in 0..self.window_size() {
+            if cursor.item_count >= max { break; }
+            if self.is_result_slot(slot) { continue; }
+            if let Some(item) = self.slot_mut(slot) {
+                if item.as_ref().map_or(false, |item| can_stack(item, &cursor)) {
+                    let mut cursor_slot = Some(cursor);
+                    merge_item(item, &mut cursor_slot, i8::MAX);
+                    cursor = cursor_slot.unwrap();
+                }
+            }
+        }
+        self.cursor = Some(cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use crate::connection::PacketQueue;
+    use crate::protocol::*;
+    use super::*;
+
+    fn click(view: &InventoryView, slot: i16, button: i8, mode: ClickContainerMode) -> ClickContainerPC2S<'static> {
+        ClickContainerPC2S {
+            window_id: view.window_id(),
+            state_id: view.state_id(),
+            slot,
+            button,
+            mode,
+            changed_slots: Cow::Owned(Vec::new()),
+            carried_item: None,
+        }
+    }
+
+    #[test]
+    fn click_test() {
+        let mut view = InventoryView::new();
+        let mut queue = PacketQueue::new();
+        view.player.set(PLAYER_MAIN_START, Some(OwnedSlot::new(1, 10)));
+        view.handle_click(&click(&view, PLAYER_MAIN_START as i16, 1, ClickContainerMode::Pickup), &mut queue).unwrap();
+        assert_eq!(view.cursor, Some(OwnedSlot::new(1, 5)));
+        assert_eq!(view.player.get(PLAYER_MAIN_START), Some(&OwnedSlot::new(1, 5)));
+        view.handle_click(&click(&view, PLAYER_HOTBAR_START as i16, 0, ClickContainerMode::Pickup), &mut queue).unwrap();
+        assert_eq!(view.cursor, None);
+        assert_eq!(view.player.get(PLAYER_HOTBAR_START), Some(&OwnedSlot::new(1, 5)));
+        view.handle_click(&click(&view, PLAYER_HOTBAR_START as i16, 0, ClickContainerMode::QuickMove), &mut queue).unwrap();
+        assert_eq!(view.player.get(PLAYER_HOTBAR_START), None);
+        assert_eq!(view.player.get(PLAYER_MAIN_START), Some(&OwnedSlot::new(1, 10)));
+        let dropped = view.handle_click(&click(&view, PLAYER_MAIN_START as i16, 1, ClickContainerMode::Throw), &mut queue).unwrap();
+        assert_eq!(dropped, vec![OwnedSlot::new(1, 10)]);
+        assert_eq!(view.player.get(PLAYER_MAIN_START), None);
+    }
+}
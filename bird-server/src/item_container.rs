@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use euclid::default::Vector3D;
+use fastnbt::Value;
+use bird_chat::component::Component;
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::inventory::{can_stack, max_stack_size, Container, ContainerType};
+use crate::protocol::*;
+
+/// Bundle is full when the sum of weights of stored items reaches this value
+pub const BUNDLE_MAX_WEIGHT: i32 = 64;
+/// Weight of an empty bundle which is stored inside of another bundle
+const BUNDLE_WEIGHT: i32 = 4;
+const ITEMS_TAG: &'static str = "Items";
+const BLOCK_ENTITY_TAG: &'static str = "BlockEntityTag";
+
+pub type Compound = HashMap<String, Value>;
+
+/// Reads the nbt of an item, the empty nbt is an empty compound
+pub fn read_nbt(nbt: &[u8]) -> anyhow::Result<Compound> {
+    match nbt {
+        [] | [0] => Ok(Compound::new()),
+        _ => Ok(fastnbt::from_bytes(nbt)?),
+    }
+}
+
+pub fn write_nbt(compound: &Compound) -> anyhow::Result<Vec<u8>> {
+    match compound.is_empty() {
+        true => Ok(EMPTY_NBT.to_vec()),
+        false => Ok(fastnbt::to_bytes(compound)?),
+    }
+}
+
+fn item_name(item_id: i32) -> Option<&'static str> {
+    ItemData::from_id(item_id as u32).map(|item| item.name)
+}
+
+pub fn is_bundle(item_id: i32) -> bool {
+    item_name(item_id) == Some("bundle")
+}
+
+pub fn is_shulker_box(item_id: i32) -> bool {
+    item_name(item_id).map_or(false, |name| name.ends_with("shulker_box"))
+}
+
+/// Shulker boxes keep their contents, so they can't be stored inside of other items
+pub fn can_fit_inside(item: &OwnedSlot) -> bool {
+    !is_shulker_box(item.item_id)
+}
+
+/// Item compound of vanilla, the slot is written if it is given
+pub fn item_to_value(item: &OwnedSlot, slot: Option<i8>) -> anyhow::Result<Value> {
+    let name = item_name(item.item_id).ok_or_else(|| anyhow::Error::msg("Unknown item"))?;
+    let mut compound = Compound::new();
+    if let Some(slot) = slot {
+        compound.insert("Slot".into(), Value::Byte(slot));
+    }
+    compound.insert("id".into(), Value::String(format!("minecraft:{}", name)));
+    compound.insert("Count".into(), Value::Byte(item.item_count));
+    let tag = read_nbt(&item.nbt)?;
+    if !tag.is_empty() {
+        compound.insert("tag".into(), Value::Compound(tag));
+    }
+    Ok(Value::Compound(compound))
+}
+
+pub fn item_from_value(value: &Value) -> anyhow::Result<(Option<i8>, OwnedSlot)> {
+    let compound = match value {
+        Value::Compound(compound) => compound,
+        _ => return Err(anyhow::Error::msg("Stored item is not a compound")),
+    };
+    let slot = match compound.get("Slot") {
+        Some(Value::Byte(slot)) => Some(*slot),
+        _ => None,
+    };
+    let item_id = match compound.get("id") {
+        Some(Value::String(id)) => ItemData::from_name(id.strip_prefix("minecraft:").unwrap_or(id))
+            .map(|item| item.id as i32)
+            .ok_or_else(|| anyhow::Error::msg("Unknown stored item"))?,
+        _ => return Err(anyhow::Error::msg("Stored item doesn't have an id")),
+    };
+    let item_count = match compound.get("Count") {
+        Some(Value::Byte(count)) => *count,
+        _ => 1,
+    };
+    let nbt = match compound.get("tag") {
+        Some(Value::Compound(tag)) => write_nbt(tag)?,
+        _ => EMPTY_NBT.to_vec(),
+    };
+    Ok((slot, OwnedSlot { item_id, item_count, nbt }))
+}
+
+fn read_items(compound: &Compound) -> anyhow::Result<Vec<(Option<i8>, OwnedSlot)>> {
+    match compound.get(ITEMS_TAG) {
+        Some(Value::List(items)) => items.iter().map(item_from_value).collect(),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replaces the items of the compound, the tag is removed if there are no items
+fn write_items<'a>(compound: &mut Compound, items: impl Iterator<Item = (Option<i8>, &'a OwnedSlot)>) -> anyhow::Result<()> {
+    let items = items
+        .map(|(slot, item)| item_to_value(item, slot))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    match items.is_empty() {
+        true => compound.remove(ITEMS_TAG),
+        false => compound.insert(ITEMS_TAG.into(), Value::List(items)),
+    };
+    Ok(())
+}
+
+/// Items which are stored in the shulker box or in the bundle in the order of the client tooltip.
+/// Shulker box items are sorted by the slot, bundle items are ordered from the last inserted
+pub fn stored_items(item: &OwnedSlot) -> anyhow::Result<Vec<OwnedSlot>> {
+    let nbt = read_nbt(&item.nbt)?;
+    match is_shulker_box(item.item_id) {
+        true => {
+            let mut items = match nbt.get(BLOCK_ENTITY_TAG) {
+                Some(Value::Compound(block_entity)) => read_items(block_entity)?,
+                _ => Vec::new(),
+            };
+            items.sort_by_key(|(slot, _)| *slot);
+            Ok(items.into_iter().map(|(_, item)| item).collect())
+        }
+        false => Ok(read_items(&nbt)?.into_iter().map(|(_, item)| item).collect()),
+    }
+}
+
+/// Weight of one item inside of a bundle
+pub fn item_weight(item: &OwnedSlot) -> anyhow::Result<i32> {
+    match is_bundle(item.item_id) {
+        true => Ok(BUNDLE_WEIGHT + bundle_weight(item)?),
+        false => Ok(BUNDLE_MAX_WEIGHT / (max_stack_size(item.item_id) as i32).max(1)),
+    }
+}
+
+/// Sum of weights of the stored items
+pub fn bundle_weight(bundle: &OwnedSlot) -> anyhow::Result<i32> {
+    stored_items(bundle)?.iter()
+        .map(|item| item_weight(item).map(|weight| weight * item.item_count as i32))
+        .sum()
+}
+
+/// Puts as many items into the bundle as its weight allows, the inserted stack is moved to the front.
+/// Returns the amount of moved items
+pub fn bundle_insert(bundle: &mut OwnedSlot, item: &mut Option<OwnedSlot>) -> anyhow::Result<i8> {
+    let source = match item.as_mut() {
+        Some(source) if can_fit_inside(source) => source,
+        _ => return Ok(0),
+    };
+    let mut nbt = read_nbt(&bundle.nbt)?;
+    let mut items: Vec<OwnedSlot> = read_items(&nbt)?.into_iter().map(|(_, item)| item).collect();
+    let free = BUNDLE_MAX_WEIGHT - bundle_weight(bundle)?;
+    let moved = (free / item_weight(source)?).min(source.item_count as i32).max(0) as i8;
+    if moved == 0 {
+        return Ok(0);
+    }
+    let similar = items.iter().position(|stored| {
+        can_stack(stored, source) && stored.item_count < max_stack_size(stored.item_id)
+    });
+    let stack = match similar {
+        Some(index) => {
+            let mut stack = items.remove(index);
+            stack.item_count += moved;
+            stack
+        }
+        None => {
+            let mut stack = source.clone();
+            stack.item_count = moved;
+            stack
+        }
+    };
+    items.insert(0, stack);
+    write_items(&mut nbt, items.iter().map(|item| (None, item)))?;
+    bundle.nbt = write_nbt(&nbt)?;
+    source.item_count -= moved;
+    if source.item_count <= 0 {
+        *item = None;
+    }
+    Ok(moved)
+}
+
+/// Takes the last inserted stack out of the bundle
+pub fn bundle_remove(bundle: &mut OwnedSlot) -> anyhow::Result<Option<OwnedSlot>> {
+    let mut nbt = read_nbt(&bundle.nbt)?;
+    let mut items: Vec<OwnedSlot> = read_items(&nbt)?.into_iter().map(|(_, item)| item).collect();
+    if items.is_empty() {
+        return Ok(None);
+    }
+    let removed = items.remove(0);
+    write_items(&mut nbt, items.iter().map(|item| (None, item)))?;
+    bundle.nbt = write_nbt(&nbt)?;
+    Ok(Some(removed))
+}
+
+/// Secondary click with a bundle on a slot or with an item on a bundle.
+/// Items are inserted into the bundle or the last inserted stack is taken out, returns false if there is no bundle
+pub fn bundle_click(cursor: &mut Option<OwnedSlot>, slot: &mut Option<OwnedSlot>) -> anyhow::Result<bool> {
+    let cursor_bundle = cursor.as_ref().map_or(false, |item| is_bundle(item.item_id));
+    let slot_bundle = slot.as_ref().map_or(false, |item| is_bundle(item.item_id));
+    let (bundle, other) = match (cursor_bundle, slot_bundle) {
+        (true, _) => (cursor, slot),
+        (false, true) => (slot, cursor),
+        (false, false) => return Ok(false),
+    };
+    let bundle = bundle.as_mut().unwrap();
+    match other.is_some() {
+        true => {
+            bundle_insert(bundle, other)?;
+        }
+        false => *other = bundle_remove(bundle)?,
+    }
+    Ok(true)
+}
+
+/// Block entity of a placed shulker box, the contents are moved between the item and the block
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShulkerBox {
+    pub position: Vector3D<i32>,
+    /// Item of the box which defines its color
+    pub item_id: i32,
+    pub container: Container,
+    /// Nbt of the placed item without the contents, it is given back to the item when the box is broken
+    item_nbt: Compound,
+}
+
+impl ShulkerBox {
+    pub fn new(position: Vector3D<i32>, item_id: i32) -> Self {
+        Self {
+            position,
+            item_id,
+            container: Container::new(
+                ContainerType::ShulkerBox,
+                Component::translation("container.shulkerBox", Vec::new()),
+            ),
+            item_nbt: Compound::new(),
+        }
+    }
+
+    /// Creates the block entity of the placed item with all its contents
+    pub fn place(position: Vector3D<i32>, item: &OwnedSlot) -> anyhow::Result<Self> {
+        let mut shulker_box = Self::new(position, item.item_id);
+        let mut nbt = read_nbt(&item.nbt)?;
+        if let Some(Value::Compound(block_entity)) = nbt.get_mut(BLOCK_ENTITY_TAG) {
+            for (slot, item) in read_items(block_entity)? {
+                if let Some(slot) = slot.filter(|slot| *slot >= 0) {
+                    shulker_box.container.inventory.set(slot as usize, Some(item));
+                }
+            }
+            block_entity.remove(ITEMS_TAG);
+            if block_entity.is_empty() {
+                nbt.remove(BLOCK_ENTITY_TAG);
+            }
+        }
+        shulker_box.item_nbt = nbt;
+        Ok(shulker_box)
+    }
+
+    /// Item which is dropped when the box is broken.
+    /// An empty box doesn't have the contents tag, so it is stacked with new boxes
+    pub fn break_item(&self) -> anyhow::Result<OwnedSlot> {
+        let mut nbt = self.item_nbt.clone();
+        let items = self.container.inventory.slots().iter()
+            .enumerate()
+            .filter_map(|(slot, item)| item.as_ref().map(|item| (Some(slot as i8), item)));
+        let mut block_entity = match nbt.remove(BLOCK_ENTITY_TAG) {
+            Some(Value::Compound(block_entity)) => block_entity,
+            _ => Compound::new(),
+        };
+        write_items(&mut block_entity, items)?;
+        if !block_entity.is_empty() {
+            nbt.insert(BLOCK_ENTITY_TAG.into(), Value::Compound(block_entity));
+        }
+        Ok(OwnedSlot { item_id: self.item_id, item_count: 1, nbt: write_nbt(&nbt)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn item_container_test() {
+        let mut bundle = OwnedSlot::new(item("bundle"), 1);
+        let mut pearls = Some(OwnedSlot::new(item("ender_pearl"), 16));
+        let mut sticks = Some(OwnedSlot::new(item("stick"), 40));
+        let mut shulker_item = Some(OwnedSlot::new(item("shulker_box"), 1));
+        assert_eq!(bundle_insert(&mut bundle, &mut pearls).unwrap(), 16);
+        assert_eq!(pearls, None);
+        assert_eq!(bundle_insert(&mut bundle, &mut shulker_item).unwrap(), 0);
+        // Ender pearls take the whole bundle
+        assert_eq!(bundle_insert(&mut bundle, &mut sticks).unwrap(), 0);
+        let mut cursor = bundle_remove(&mut bundle).unwrap();
+        assert_eq!(cursor, Some(OwnedSlot::new(item("ender_pearl"), 16)));
+        assert_eq!(bundle.nbt, EMPTY_NBT);
+        assert_eq!(bundle_insert(&mut bundle, &mut sticks).unwrap(), 40);
+        let mut slot = Some(bundle);
+        assert!(bundle_click(&mut cursor, &mut slot).unwrap());
+        // Only 6 pearls fit next to the sticks and they are shown first
+        let stored = stored_items(slot.as_ref().unwrap()).unwrap();
+        assert_eq!(stored, vec![OwnedSlot::new(item("ender_pearl"), 6), OwnedSlot::new(item("stick"), 40)]);
+        assert_eq!(cursor.as_ref().map(|item| item.item_count), Some(10));
+
+        let mut shulker_box = ShulkerBox::new(Vector3D::zero(), item("shulker_box"));
+        shulker_box.container.inventory.set(5, cursor.clone());
+        shulker_box.container.inventory.set(1, slot.clone());
+        let broken = shulker_box.break_item().unwrap();
+        assert_eq!(stored_items(&broken).unwrap(), vec![slot.unwrap(), cursor.unwrap()]);
+        let placed = ShulkerBox::place(Vector3D::zero(), &broken).unwrap();
+        assert_eq!(placed, shulker_box);
+        assert_eq!(ShulkerBox::new(Vector3D::zero(), item("shulker_box")).break_item().unwrap().nbt, EMPTY_NBT);
+    }
+}
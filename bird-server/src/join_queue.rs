@@ -0,0 +1,316 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use bird_chat::color::Color;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use crate::events::{EventPriority, EventResult};
+use crate::login::LoginProfile;
+use crate::protocol::*;
+
+/// Login plugin channel which keeps queued connections alive, the client answers that it doesn't understand it
+pub const QUEUE_CHANNEL: &'static str = "bird:queue";
+/// Message ids of keep alive requests start from this value so they don't collide with other login plugin requests
+pub const QUEUE_MESSAGE_ID_START: i32 = 1 << 24;
+/// Players with this permission join even if the server is full
+pub const FULL_BYPASS_PERMISSION: &'static str = "bird.join.full";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ServerFullPolicy {
+    /// Players are disconnected with the hint when to retry
+    Disconnect {
+        retry_after: Duration,
+    },
+    /// Players wait on the logging in screen until a slot is free
+    Queue {
+        max_size: usize,
+        keep_alive_interval: Duration,
+        /// Players are disconnected with their position after this time
+        max_wait: Duration,
+        retry_after: Duration,
+    },
+}
+
+impl Default for ServerFullPolicy {
+    fn default() -> Self {
+        Self::Disconnect { retry_after: Duration::from_secs(60) }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JoinDecision {
+    Join,
+    Queued {
+        /// Starts from 1
+        position: usize,
+    },
+    Disconnect(Component<'static>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum QueueAction {
+    /// The login plugin request from [`keep_alive_request`] must be sent
+    KeepAlive {
+        connection: u64,
+        message_id: i32,
+    },
+    /// The login can be continued
+    Admit {
+        connection: u64,
+    },
+    Disconnect {
+        connection: u64,
+        reason: Component<'static>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct QueuedConnection {
+    connection: u64,
+    joined: Instant,
+    last_keep_alive: Instant,
+}
+
+/// Decides what to do with players when the server is full
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct JoinQueue {
+    pub policy: ServerFullPolicy,
+    queue: VecDeque<QueuedConnection>,
+    next_message_id: i32,
+}
+
+impl JoinQueue {
+    pub fn new(policy: ServerFullPolicy) -> Self {
+        Self {
+            policy,
+            queue: VecDeque::new(),
+            next_message_id: QUEUE_MESSAGE_ID_START,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn position(&self, connection: u64) -> Option<usize> {
+        self.queue.iter().position(|queued| queued.connection == connection).map(|index| index + 1)
+    }
+
+    /// Queued players join before the new ones
+    pub fn try_join(&mut self, connection: u64, online: i32, max_players: i32, now: Instant) -> JoinDecision {
+        if online < max_players && self.queue.is_empty() {
+            return JoinDecision::Join;
+        }
+        match self.policy {
+            ServerFullPolicy::Disconnect { retry_after } => JoinDecision::Disconnect(full_message(None, retry_after)),
+            ServerFullPolicy::Queue { max_size, retry_after, .. } if self.queue.len() >= max_size => {
+                JoinDecision::Disconnect(full_message(None, retry_after))
+            }
+            ServerFullPolicy::Queue { .. } => JoinDecision::Queued { position: self.push(connection, now) },
+        }
+    }
+
+    /// Adds the connection to the end of the queue ignoring the policy, returns its position
+    pub fn push(&mut self, connection: u64, now: Instant) -> usize {
+        if let Some(position) = self.position(connection) {
+            return position;
+        }
+        self.queue.push_back(QueuedConnection { connection, joined: now, last_keep_alive: now });
+        self.queue.len()
+    }
+
+    /// Must be called when the queued connection is closed
+    pub fn remove(&mut self, connection: u64) {
+        self.queue.retain(|queued| queued.connection != connection);
+    }
+
+    /// Admits players to free slots and keeps others alive
+    pub fn tick(&mut self, online: i32, max_players: i32, now: Instant) -> Vec<QueueAction> {
+        let mut actions = Vec::new();
+        let mut free = (max_players - online).max(0);
+        while free > 0 {
+            match self.queue.pop_front() {
+                Some(queued) => actions.push(QueueAction::Admit { connection: queued.connection }),
+                None => break,
+            }
+            free -= 1;
+        }
+        let (keep_alive_interval, max_wait, retry_after) = match self.policy {
+            ServerFullPolicy::Queue { keep_alive_interval, max_wait, retry_after, .. } => (keep_alive_interval, max_wait, retry_after),
+            ServerFullPolicy::Disconnect { .. } => return actions,
+        };
+        let mut position = 0;
+        let mut timed_out = Vec::new();
+        for queued in self.queue.iter_mut() {
+            position += 1;
+            if now.duration_since(queued.joined) >= max_wait {
+                timed_out.push(queued.connection);
+                actions.push(QueueAction::Disconnect {
+                    connection: queued.connection,
+                    reason: full_message(Some(position), retry_after),
+                });
+            } else if now.duration_since(queued.last_keep_alive) >= keep_alive_interval {
+                queued.last_keep_alive = now;
+                actions.push(QueueAction::KeepAlive { connection: queued.connection, message_id: self.next_message_id });
+                self.next_message_id = self.next_message_id.checked_add(1).unwrap_or(QUEUE_MESSAGE_ID_START);
+            }
+        }
+        self.queue.retain(|queued| !timed_out.contains(&queued.connection));
+        actions
+    }
+}
+
+pub fn keep_alive_request(message_id: i32) -> LoginPluginRequestLS2C<'static> {
+    LoginPluginRequestLS2C {
+        message_id,
+        channel: Identifier::new_full(Cow::Borrowed(QUEUE_CHANNEL)).unwrap(),
+        data: &[],
+    }
+}
+
+/// Permissions which are checked at login, ops have all of them
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LoginPermissions {
+    pub ops: HashSet<Uuid>,
+    pub granted: HashMap<Uuid, HashSet<String>>,
+}
+
+impl LoginPermissions {
+    pub fn has(&self, uuid: Uuid, permission: &str) -> bool {
+        self.ops.contains(&uuid) || self.granted.get(&uuid).map_or(false, |granted| granted.contains(permission))
+    }
+
+    pub fn grant(&mut self, uuid: Uuid, permission: &str) {
+        self.granted.entry(uuid).or_default().insert(permission.into());
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct JoinAttempt<'a> {
+    pub connection: u64,
+    pub profile: &'a LoginProfile,
+    pub online: i32,
+    pub max_players: i32,
+    /// The player has [`FULL_BYPASS_PERMISSION`]
+    pub bypass: bool,
+}
+
+type JoinHandler = Box<dyn Fn(&JoinAttempt, &mut JoinDecision) -> EventResult + Send + Sync>;
+
+/// Enforces max players at login, handlers may change the decision for each player
+pub struct JoinGate {
+    pub permissions: LoginPermissions,
+    pub queue: JoinQueue,
+    handlers: Vec<(EventPriority, JoinHandler)>,
+}
+
+impl JoinGate {
+    pub fn new(policy: ServerFullPolicy, permissions: LoginPermissions) -> Self {
+        Self { permissions, queue: JoinQueue::new(policy), handlers: Vec::new() }
+    }
+
+    /// Handlers with the higher priority are called first, [`EventResult::Cancel`] skips the remaining ones
+    pub fn on_join<F>(&mut self, priority: EventPriority, handler: F) -> &mut Self
+        where F: Fn(&JoinAttempt, &mut JoinDecision) -> EventResult + Send + Sync + 'static {
+        let index = self.handlers.partition_point(|(other, _)| *other >= priority);
+        self.handlers.insert(index, (priority, Box::new(handler)));
+        self
+    }
+
+    pub fn decide(&mut self, connection: u64, profile: &LoginProfile, online: i32, max_players: i32, now: Instant) -> JoinDecision {
+        let bypass = self.permissions.has(profile.uuid, FULL_BYPASS_PERMISSION);
+        let mut decision = match bypass {
+            true => JoinDecision::Join,
+            false => self.queue.try_join(connection, online, max_players, now),
+        };
+        let attempt = JoinAttempt { connection, profile, online, max_players, bypass };
+        for (_, handler) in &self.handlers {
+            if handler(&attempt, &mut decision) == EventResult::Cancel {
+                break;
+            }
+        }
+        match decision {
+            JoinDecision::Queued { .. } => JoinDecision::Queued { position: self.queue.push(connection, now) },
+            decision => {
+                self.queue.remove(connection);
+                decision
+            }
+        }
+    }
+}
+
+/// Vanilla server full message with the position in the queue and the retry time
+pub fn full_message(position: Option<usize>, retry_after: Duration) -> Component<'static> {
+    let mut message = Component::translation("multiplayer.disconnect.server_full", vec![]);
+    let mut extra = Vec::new();
+    if let Some(position) = position {
+        let mut line = Component::text(format!("\n\nPosition in queue: {}", position));
+        line.color = Some(Color::Gold);
+        extra.push(line);
+    }
+    let mut line = Component::text(format!("\nTry again in {} seconds", retry_after.as_secs().max(1)));
+    line.color = Some(Color::Gray);
+    extra.push(line);
+    message.extra = Cow::Owned(extra);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::login::offline_uuid;
+    use super::*;
+
+    #[test]
+    fn join_queue_test() {
+        let now = Instant::now();
+        let mut disconnect = JoinQueue::new(ServerFullPolicy::default());
+        assert_eq!(disconnect.try_join(1, 0, 1, now), JoinDecision::Join);
+        assert!(matches!(disconnect.try_join(1, 1, 1, now), JoinDecision::Disconnect(_)));
+        let mut queue = JoinQueue::new(ServerFullPolicy::Queue {
+            max_size: 2,
+            keep_alive_interval: Duration::from_secs(10),
+            max_wait: Duration::from_secs(60),
+            retry_after: Duration::from_secs(30),
+        });
+        assert_eq!(queue.try_join(1, 1, 1, now), JoinDecision::Queued { position: 1 });
+        assert_eq!(queue.try_join(2, 1, 1, now), JoinDecision::Queued { position: 2 });
+        assert!(matches!(queue.try_join(3, 1, 1, now), JoinDecision::Disconnect(_)));
+        // Queued players are first even if there is a free slot
+        assert!(matches!(queue.try_join(3, 0, 1, now), JoinDecision::Disconnect(_)));
+        let later = now + Duration::from_secs(10);
+        assert_eq!(queue.tick(1, 1, later), vec![
+            QueueAction::KeepAlive { connection: 1, message_id: QUEUE_MESSAGE_ID_START },
+            QueueAction::KeepAlive { connection: 2, message_id: QUEUE_MESSAGE_ID_START + 1 },
+        ]);
+        assert_eq!(queue.tick(0, 1, later), vec![QueueAction::Admit { connection: 1 }]);
+        assert_eq!(queue.position(2), Some(1));
+        let actions = queue.tick(1, 1, now + Duration::from_secs(60));
+        assert_eq!(actions, vec![QueueAction::Disconnect { connection: 2, reason: full_message(Some(1), Duration::from_secs(30)) }]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn join_gate_test() {
+        let now = Instant::now();
+        let profile = |name: &str| LoginProfile { uuid: offline_uuid(name), name: name.into(), properties: Vec::new(), address: None };
+        let mut permissions = LoginPermissions::default();
+        permissions.ops.insert(offline_uuid("op"));
+        permissions.grant(offline_uuid("vip"), FULL_BYPASS_PERMISSION);
+        let mut gate = JoinGate::new(ServerFullPolicy::default(), permissions);
+        gate.on_join(EventPriority::Normal, |attempt, decision| {
+            if attempt.profile.name == "banned" {
+                *decision = JoinDecision::Disconnect(Component::text("Banned"));
+            }
+            EventResult::Continue
+        });
+        assert!(matches!(gate.decide(1, &profile("player"), 20, 20, now), JoinDecision::Disconnect(_)));
+        assert_eq!(gate.decide(2, &profile("op"), 20, 20, now), JoinDecision::Join);
+        assert_eq!(gate.decide(3, &profile("vip"), 21, 20, now), JoinDecision::Join);
+        assert_eq!(gate.decide(4, &profile("banned"), 0, 20, now), JoinDecision::Disconnect(Component::text("Banned")));
+    }
+}
@@ -0,0 +1,152 @@
+use euclid::default::Vector3D;
+use bird_data::{Block, ItemData};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+use crate::world::World;
+
+/// Music disc with the length of the song in seconds and the comparator output of the jukebox
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Record {
+    pub name: &'static str,
+    pub length: u32,
+    pub comparator_output: u8,
+}
+
+pub const RECORDS: &'static [Record] = &[
+    Record { name: "music_disc_13", length: 178, comparator_output: 1 },
+    Record { name: "music_disc_cat", length: 185, comparator_output: 2 },
+    Record { name: "music_disc_blocks", length: 345, comparator_output: 3 },
+    Record { name: "music_disc_chirp", length: 185, comparator_output: 4 },
+    Record { name: "music_disc_far", length: 174, comparator_output: 5 },
+    Record { name: "music_disc_mall", length: 197, comparator_output: 6 },
+    Record { name: "music_disc_mellohi", length: 96, comparator_output: 7 },
+    Record { name: "music_disc_stal", length: 150, comparator_output: 8 },
+    Record { name: "music_disc_strad", length: 188, comparator_output: 9 },
+    Record { name: "music_disc_ward", length: 251, comparator_output: 10 },
+    Record { name: "music_disc_11", length: 71, comparator_output: 11 },
+    Record { name: "music_disc_wait", length: 238, comparator_output: 12 },
+    Record { name: "music_disc_pigstep", length: 149, comparator_output: 13 },
+    Record { name: "music_disc_otherside", length: 195, comparator_output: 14 },
+    Record { name: "music_disc_5", length: 178, comparator_output: 15 },
+];
+
+const TICKS_PER_SECOND: u64 = 20;
+
+pub fn record_by_item(item_id: i32) -> Option<&'static Record> {
+    let name = ItemData::from_id(item_id as u32)?.name;
+    RECORDS.iter().find(|record| record.name == name)
+}
+
+/// Block entity of a jukebox, the client plays the record by the world event
+#[derive(Clone, PartialEq, Debug)]
+pub struct Jukebox {
+    pub position: Vector3D<i32>,
+    record: Option<OwnedSlot>,
+    /// Ticks since the record was inserted
+    tick_count: u64,
+}
+
+impl Jukebox {
+    pub fn new(position: Vector3D<i32>) -> Self {
+        Self { position, record: None, tick_count: 0 }
+    }
+
+    pub fn record(&self) -> Option<&OwnedSlot> {
+        self.record.as_ref()
+    }
+
+    /// Returns true while the song of the inserted record is not finished
+    pub fn is_playing(&self) -> bool {
+        self.record.as_ref()
+            .and_then(|item| record_by_item(item.item_id))
+            .map(|record| self.tick_count < record.length as u64 * TICKS_PER_SECOND)
+            .unwrap_or(false)
+    }
+
+    pub fn comparator_output(&self) -> u8 {
+        self.record.as_ref()
+            .and_then(|item| record_by_item(item.item_id))
+            .map(|record| record.comparator_output)
+            .unwrap_or(0)
+    }
+
+    /// Inserts one disc from the item and starts playing it.
+    /// Returns the rest of the item, the whole item is returned if it is not a disc or the jukebox is not empty
+    pub fn insert_record<S: PacketSender>(&mut self, world: &mut World, mut item: OwnedSlot, broadcast: &mut S) -> anyhow::Result<Option<OwnedSlot>> {
+        if self.record.is_some() || record_by_item(item.item_id).is_none() {
+            return Ok(Some(item));
+        }
+        let mut record = item.clone();
+        record.item_count = 1;
+        item.item_count -= 1;
+        broadcast.send_packet(&WorldEventPS2C::new(WorldEvent::PlayRecord { record_id: record.item_id }, self.position))?;
+        self.record = Some(record);
+        self.tick_count = 0;
+        self.set_has_record(world, true, broadcast)?;
+        Ok(match item.item_count > 0 {
+            true => Some(item),
+            false => None,
+        })
+    }
+
+    /// Stops the song and returns the disc, the caller drops it above the jukebox
+    pub fn eject_record<S: PacketSender>(&mut self, world: &mut World, broadcast: &mut S) -> anyhow::Result<Option<OwnedSlot>> {
+        let record = match self.record.take() {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        // Record id which is not a disc stops the music
+        broadcast.send_packet(&WorldEventPS2C::new(WorldEvent::PlayRecord { record_id: 0 }, self.position))?;
+        self.tick_count = 0;
+        self.set_has_record(world, false, broadcast)?;
+        Ok(Some(record))
+    }
+
+    /// The client stops the song by itself, the server only counts ticks for [`Jukebox::is_playing`]
+    pub fn tick(&mut self) {
+        if self.record.is_some() {
+            self.tick_count += 1;
+        }
+    }
+
+    fn set_has_record<S: PacketSender>(&self, world: &mut World, has_record: bool, broadcast: &mut S) -> anyhow::Result<()> {
+        let state = Block::Jukebox { has_record }.get_state()
+            .ok_or_else(|| anyhow::Error::msg("Jukebox doesn't have a state"))? as i32;
+        world.set_block_state(self.position, state);
+        broadcast.send_packet(&BlockUpdatePS2C { location: self.position, block_id: state })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::connection::PacketQueue;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn jukebox_test() {
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let mut broadcast = PacketQueue::new();
+        let mut jukebox = Jukebox::new(Vector3D::new(0, 1, 0));
+        let disc = item("music_disc_11");
+        let stick = item("stick");
+        assert!(jukebox.insert_record(&mut world, OwnedSlot::new(stick, 1), &mut broadcast).unwrap().is_some());
+        assert_eq!(jukebox.insert_record(&mut world, OwnedSlot::new(disc, 2), &mut broadcast).unwrap().map(|item| item.item_count), Some(1));
+        assert!(jukebox.is_playing());
+        assert_eq!(jukebox.comparator_output(), 11);
+        for _ in 0..71 * TICKS_PER_SECOND {
+            jukebox.tick();
+        }
+        assert!(!jukebox.is_playing());
+        assert_eq!(jukebox.eject_record(&mut world, &mut broadcast).unwrap().map(|item| item.item_count), Some(1));
+        assert_eq!(
+            world.get_block_state(jukebox.position),
+            Block::Jukebox { has_record: false }.get_state().map(|state| state as i32),
+        );
+        // World events and block updates of the insertion and the ejection
+        assert_eq!(broadcast.take().len(), 4);
+    }
+}
@@ -0,0 +1,238 @@
+use euclid::default::Vector3D;
+use serde::Deserialize;
+use bird_chat::component::Component;
+use bird_data::ItemData;
+use bird_protocol::anyhow;
+use crate::block_state::BlockState;
+use crate::connection::PacketSender;
+use crate::inventory::{Container, ContainerType, InventoryView, PLAYER_HOTBAR_START};
+use crate::protocol::*;
+use crate::world::World;
+
+pub const LECTERN_BOOK_SLOT: usize = 0;
+/// Property of the lectern with the opened page
+pub const LECTERN_PAGE_PROPERTY: ContainerProperty = ContainerProperty::LecternPage;
+pub const LECTERN_PREVIOUS_PAGE_BUTTON: i8 = 1;
+pub const LECTERN_NEXT_PAGE_BUTTON: i8 = 2;
+pub const LECTERN_TAKE_BOOK_BUTTON: i8 = 3;
+/// Buttons from this one jump to the page `button - LECTERN_JUMP_TO_PAGE_BUTTON`
+pub const LECTERN_JUMP_TO_PAGE_BUTTON: i8 = 100;
+
+/// Pages of both writable and written books
+#[derive(Deserialize, Default)]
+struct BookPages {
+    #[serde(default)]
+    pages: Vec<String>,
+}
+
+fn is_book(item_id: i32) -> bool {
+    ItemData::from_id(item_id as u32)
+        .map_or(false, |item| item.name == "writable_book" || item.name == "written_book")
+}
+
+/// Block entity of a lectern, the book is shown to every viewer on the same page
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lectern {
+    pub position: Vector3D<i32>,
+    book: Option<OwnedSlot>,
+    page: i32,
+    page_count: i32,
+}
+
+impl Lectern {
+    pub fn new(position: Vector3D<i32>) -> Self {
+        Self { position, book: None, page: 0, page_count: 0 }
+    }
+
+    pub fn book(&self) -> Option<&OwnedSlot> {
+        self.book.as_ref()
+    }
+
+    pub fn page(&self) -> i32 {
+        self.page
+    }
+
+    pub fn page_count(&self) -> i32 {
+        self.page_count
+    }
+
+    /// Vanilla output, from 1 on the first page to 15 on the last page
+    pub fn comparator_output(&self) -> u8 {
+        match self.book {
+            Some(_) if self.page_count > 1 => (self.page as f32 / (self.page_count - 1) as f32 * 14.0) as u8 + 1,
+            Some(_) => 15,
+            None => 0,
+        }
+    }
+
+    /// Places one book from the item on the lectern. Returns the rest of the item,
+    /// the whole item is returned if it is not a book or the lectern already has a book
+    pub fn place_book<S: PacketSender>(&mut self, world: &mut World, mut item: OwnedSlot, broadcast: &mut S) -> anyhow::Result<Option<OwnedSlot>> {
+        if self.book.is_some() || !is_book(item.item_id) {
+            return Ok(Some(item));
+        }
+        let mut book = item.clone();
+        book.item_count = 1;
+        item.item_count -= 1;
+        self.page_count = fastnbt::from_bytes::<BookPages>(&book.nbt)
+            .unwrap_or_default()
+            .pages
+            .len() as i32;
+        self.page = 0;
+        self.book = Some(book);
+        self.set_has_book(world, true, broadcast)?;
+        Ok(match item.item_count > 0 {
+            true => Some(item),
+            false => None,
+        })
+    }
+
+    /// Removes the book, the caller gives it to the player or drops it
+    pub fn take_book<S: PacketSender>(&mut self, world: &mut World, broadcast: &mut S) -> anyhow::Result<Option<OwnedSlot>> {
+        let book = match self.book.take() {
+            Some(book) => book,
+            None => return Ok(None),
+        };
+        self.page = 0;
+        self.page_count = 0;
+        self.set_has_book(world, false, broadcast)?;
+        Ok(Some(book))
+    }
+
+    /// Returns false if the page is out of the book
+    pub fn set_page(&mut self, page: i32) -> bool {
+        match self.book.is_some() && (0..self.page_count.max(1)).contains(&page) {
+            true => {
+                self.page = page;
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Opens the lectern screen with the book on the current page
+    pub fn open<S: PacketSender>(&self, view: &mut InventoryView, sender: &mut S) -> anyhow::Result<bool> {
+        if self.book.is_none() {
+            return Ok(false);
+        }
+        let mut container = Container::new(ContainerType::Lectern, Component::translation("container.lectern", Vec::new()));
+        container.inventory.set(LECTERN_BOOK_SLOT, self.book.clone());
+        view.open(container, sender)?;
+        view.set_property(LECTERN_PAGE_PROPERTY, self.page as i16, sender)?;
+        Ok(true)
+    }
+
+    /// Opens the book like it is held in the main hand. The client reads the book from the selected slot,
+    /// so the slot is replaced for the moment and sent back after the `OpenBook`
+    pub fn open_book<S: PacketSender>(&self, view: &InventoryView, selected_slot: usize, sender: &mut S) -> anyhow::Result<bool> {
+        let book = match self.book {
+            Some(ref book) => book,
+            None => return Ok(false),
+        };
+        let slot = (PLAYER_HOTBAR_START + selected_slot) as i16;
+        sender.send_packet(&SetContainerSlotPS2C {
+            window_id: PLAYER_INVENTORY_ID as i8,
+            state_id: view.state_id(),
+            slot,
+            slot_data: Some(book.as_slot()),
+        })?;
+        sender.send_packet(&OpenBookPS2C { hand: Hand::Main })?;
+        sender.send_packet(&SetContainerSlotPS2C {
+            window_id: PLAYER_INVENTORY_ID as i8,
+            state_id: view.state_id(),
+            slot,
+            slot_data: view.player.get(PLAYER_HOTBAR_START + selected_slot).map(OwnedSlot::as_slot),
+        })?;
+        Ok(true)
+    }
+
+    /// Turns pages for every viewer, `viewers` are views and senders of players which have opened the lectern.
+    /// Returns the book if the player has taken it, viewers' screens are closed then
+    pub fn handle_button<'a, S: PacketSender + 'a>(
+        &mut self,
+        world: &mut World,
+        packet: &ClickContainerButtonPC2S,
+        broadcast: &mut S,
+        viewers: impl IntoIterator<Item=(&'a mut InventoryView, &'a mut S)>,
+    ) -> anyhow::Result<Option<OwnedSlot>> {
+        let page = match packet.button_id {
+            LECTERN_PREVIOUS_PAGE_BUTTON => self.page - 1,
+            LECTERN_NEXT_PAGE_BUTTON => self.page + 1,
+            LECTERN_TAKE_BOOK_BUTTON => {
+                let book = self.take_book(world, broadcast)?;
+                for (view, sender) in viewers {
+                    view.close(sender)?;
+                }
+                return Ok(book);
+            }
+            button if button >= LECTERN_JUMP_TO_PAGE_BUTTON => (button - LECTERN_JUMP_TO_PAGE_BUTTON) as i32,
+            _ => return Ok(None),
+        };
+        if self.set_page(page) {
+            for (view, sender) in viewers {
+                view.set_property(LECTERN_PAGE_PROPERTY, self.page as i16, sender)?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn set_has_book<S: PacketSender>(&self, world: &mut World, has_book: bool, broadcast: &mut S) -> anyhow::Result<()> {
+        let state = match world.get_block(self.position) {
+            Some(state) if state.data().name == "lectern" => state,
+            _ => BlockState::from_name("lectern").ok_or_else(|| anyhow::Error::msg("Lectern doesn't have a state"))?,
+        };
+        let state = state.with("has_book", if has_book { "true" } else { "false" })?;
+        world.set_block(self.position, state);
+        broadcast.send_packet(&state.update_packet(self.position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::book::WritableBookNbt;
+    use crate::connection::PacketQueue;
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn lectern_test() {
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let mut broadcast = PacketQueue::new();
+        let mut lectern = Lectern::new(Vector3D::new(0, 1, 0));
+        let book_id = item("writable_book");
+        let stick = item("stick");
+        let pages = WritableBookNbt { pages: vec!["1".into(), "2".into(), "3".into()] };
+        let book = OwnedSlot { item_id: book_id, item_count: 2, nbt: fastnbt::to_bytes(&pages).unwrap() };
+        assert!(lectern.place_book(&mut world, OwnedSlot::new(stick, 1), &mut broadcast).unwrap().is_some());
+        assert_eq!(lectern.place_book(&mut world, book, &mut broadcast).unwrap().map(|item| item.item_count), Some(1));
+        assert_eq!(lectern.page_count(), 3);
+        assert_eq!(world.get_block(lectern.position).unwrap().get("has_book"), Some("true"));
+        assert_eq!(lectern.comparator_output(), 1);
+
+        let mut view = InventoryView::new();
+        let mut sender = PacketQueue::new();
+        assert!(lectern.open(&mut view, &mut sender).unwrap());
+        assert_eq!(view.container().unwrap().get_property(LECTERN_PAGE_PROPERTY), Some(0));
+        let button = |button_id| ClickContainerButtonPC2S { window_id: view.window_id() as i8, button_id };
+        let next = button(LECTERN_NEXT_PAGE_BUTTON);
+        let jump = button(LECTERN_JUMP_TO_PAGE_BUTTON + 5);
+        let take = button(LECTERN_TAKE_BOOK_BUTTON);
+        lectern.handle_button(&mut world, &next, &mut broadcast, [(&mut view, &mut sender)]).unwrap();
+        assert_eq!(view.container().unwrap().get_property(LECTERN_PAGE_PROPERTY), Some(1));
+        lectern.handle_button(&mut world, &jump, &mut broadcast, [(&mut view, &mut sender)]).unwrap();
+        assert_eq!(lectern.page(), 1);
+        assert!(lectern.set_page(2));
+        assert_eq!(lectern.comparator_output(), 15);
+
+        sender.take();
+        assert!(lectern.open_book(&view, 0, &mut sender).unwrap());
+        assert_eq!(sender.take().len(), 3);
+        let taken = lectern.handle_button(&mut world, &take, &mut broadcast, [(&mut view, &mut sender)]).unwrap();
+        assert_eq!(taken.map(|item| item.item_count), Some(1));
+        assert!(view.container().is_none());
+        assert_eq!(world.get_block(lectern.position).unwrap().get("has_book"), Some("false"));
+        assert!(!lectern.open(&mut view, &mut sender).unwrap());
+    }
+}
@@ -0,0 +1,310 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use bird_data::Block;
+use bird_protocol::{anyhow, ProtocolCursor, ProtocolError, ProtocolResult, ProtocolWriter};
+use bird_protocol::derive::BirdNbt;
+use bird_protocol::nbt::{NBT_TAG_BYTE_ARRAY, NbtTag};
+use crate::protocol::{BitSet, LightArray, LightData};
+use crate::world::ChunkColumn;
+
+/// Two levels are packed in a byte, the even block is in the low nibble
+pub const LIGHT_ARRAY_SIZE: usize = 2048;
+pub const MAX_LIGHT: u8 = 15;
+const SECTION_BLOCKS: usize = 4096;
+/// Sky light of the section above the world
+static FULL_LIGHT: [u8; LIGHT_ARRAY_SIZE] = [0xFF; LIGHT_ARRAY_SIZE];
+
+/// Light levels of a 16x16x16 section indexed like block states
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NibbleArray(Box<[u8; LIGHT_ARRAY_SIZE]>);
+
+impl NibbleArray {
+    pub fn new() -> Self {
+        Self(Box::new([0; LIGHT_ARRAY_SIZE]))
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        match index & 1 == 0 {
+            true => self.0[index >> 1] & 0xF,
+            false => self.0[index >> 1] >> 4,
+        }
+    }
+
+    pub fn set(&mut self, index: usize, level: u8) {
+        let byte = &mut self.0[index >> 1];
+        *byte = match index & 1 == 0 {
+            true => (*byte & 0xF0) | (level & 0xF),
+            false => (*byte & 0xF) | (level << 4),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; LIGHT_ARRAY_SIZE] {
+        &self.0
+    }
+}
+
+impl<'a> NbtTag<'a> for NibbleArray {
+    const NBT_TAG: u8 = NBT_TAG_BYTE_ARRAY;
+
+    fn write_nbt<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        (LIGHT_ARRAY_SIZE as i32).write_nbt(writer)?;
+        writer.write_bytes(self.0.as_slice());
+        Ok(())
+    }
+
+    fn read_nbt<C: ProtocolCursor<'a>>(cursor: &mut C) -> ProtocolResult<Self> {
+        if i32::read_nbt(cursor)? != LIGHT_ARRAY_SIZE as i32 {
+            return Err(ProtocolError::Any(anyhow::Error::msg("Light array must contain 2048 bytes")));
+        }
+        Ok(Self(Box::new(*cursor.take_fixed_bytes::<LIGHT_ARRAY_SIZE>()?)))
+    }
+
+    fn skip_nbt<C: ProtocolCursor<'a>>(cursor: &mut C, amount: usize) -> ProtocolResult<usize> {
+        let mut result = 0;
+        for _ in 0..amount {
+            let len = i32::read_nbt(cursor)?.max(0) as usize;
+            cursor.take_bytes(len)?;
+            result += 4 + len;
+        }
+        Ok(result)
+    }
+}
+
+/// Light part of a section in the chunk nbt, `Y` is the section y of the world
+#[derive(BirdNbt, Clone, PartialEq, Debug)]
+pub struct LightSectionNbt {
+    #[bnbt(name = "Y")]
+    pub y: i8,
+    #[bnbt(name = "BlockLight")]
+    pub block_light: Option<NibbleArray>,
+    #[bnbt(name = "SkyLight")]
+    pub sky_light: Option<NibbleArray>,
+}
+
+/// Light which is stored in the chunk nbt like in vanilla. `isLightOn` is false when the light
+/// must be computed again
+#[derive(BirdNbt, Clone, PartialEq, Debug)]
+pub struct ChunkLightNbt {
+    #[bnbt(name = "isLightOn")]
+    pub is_light_on: bool,
+    pub sections: Vec<LightSectionNbt>,
+}
+
+/// Sky and block light of each section of a column, `None` means that the section is dark
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChunkLight {
+    sky: Vec<Option<NibbleArray>>,
+    block: Vec<Option<NibbleArray>>,
+}
+
+/// Emitted light and opacity. The emitted light of bird_data is the same for all states of the block
+fn light_properties(state: i32) -> (u8, u8) {
+    match Block::from_state(state as u32) {
+        Some(block) => {
+            let data = block.get_data();
+            (data.emit_light, data.filter_light)
+        }
+        None => (0, 0),
+    }
+}
+
+fn propagate(levels: &mut [u8], opacity: &[u8], mut queue: VecDeque<usize>) {
+    let height = levels.len() / 256;
+    while let Some(index) = queue.pop_front() {
+        let level = levels[index];
+        let (x, y, z) = (index & 15, index >> 8, (index >> 4) & 15);
+        let neighbours = [
+            (x > 0).then(|| index - 1),
+            (x < 15).then(|| index + 1),
+            (z > 0).then(|| index - 16),
+            (z < 15).then(|| index + 16),
+            (y > 0).then(|| index - 256),
+            (y + 1 < height).then(|| index + 256),
+        ];
+        for neighbour in neighbours.into_iter().flatten() {
+            let next = level.saturating_sub(opacity[neighbour].max(1));
+            if next > levels[neighbour] {
+                levels[neighbour] = next;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+}
+
+fn pack_sections(levels: &[u8]) -> Vec<Option<NibbleArray>> {
+    levels.chunks(SECTION_BLOCKS)
+        .map(|section| {
+            let mut array = NibbleArray::new();
+            for (index, level) in section.iter().enumerate() {
+                array.set(index, *level);
+            }
+            (!array.is_empty()).then(|| array)
+        })
+        .collect()
+}
+
+impl ChunkLight {
+    /// Light of the column, light is not spread to the neighbour columns
+    pub fn compute(column: &ChunkColumn) -> Self {
+        let height = column.sections.len() * 16;
+        let mut sky = vec![0; height * 256];
+        let mut block = vec![0; height * 256];
+        let mut opacity = vec![0; height * 256];
+        let mut sky_queue = VecDeque::new();
+        let mut block_queue = VecDeque::new();
+        for y in 0..height {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let index = y << 8 | z << 4 | x;
+                    let (emit, filter) = column.get_block_state(x, y, z).map_or((0, 0), light_properties);
+                    opacity[index] = filter;
+                    if emit > 0 {
+                        block[index] = emit;
+                        block_queue.push_back(index);
+                    }
+                }
+            }
+        }
+        for z in 0..16 {
+            for x in 0..16 {
+                for y in (0..height).rev() {
+                    let index = y << 8 | z << 4 | x;
+                    if opacity[index] > 0 {
+                        break;
+                    }
+                    sky[index] = MAX_LIGHT;
+                    sky_queue.push_back(index);
+                }
+            }
+        }
+        propagate(&mut sky, &opacity, sky_queue);
+        propagate(&mut block, &opacity, block_queue);
+        Self { sky: pack_sections(&sky), block: pack_sections(&block) }
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.sky.len()
+    }
+
+    /// Coordinates are relative to the column like in [`ChunkColumn::get_block_state`]
+    pub fn sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        self.sky.get(y >> 4).and_then(Option::as_ref).map_or(0, |array| array.get(((y & 15) << 8) | (z << 4) | x))
+    }
+
+    pub fn block_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        self.block.get(y >> 4).and_then(Option::as_ref).map_or(0, |array| array.get(((y & 15) << 8) | (z << 4) | x))
+    }
+
+    /// Sections are stored with `Y` starting from `min_section_y`
+    pub fn to_nbt(&self, min_section_y: i8) -> ChunkLightNbt {
+        ChunkLightNbt {
+            is_light_on: true,
+            sections: self.sky.iter()
+                .zip(self.block.iter())
+                .enumerate()
+                .filter(|(_, (sky, block))| sky.is_some() || block.is_some())
+                .map(|(index, (sky, block))| LightSectionNbt {
+                    y: min_section_y + index as i8,
+                    block_light: block.clone(),
+                    sky_light: sky.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `None` if the stored light is not valid for the column and must be computed
+    pub fn from_nbt(nbt: &ChunkLightNbt, min_section_y: i8, section_count: usize) -> Option<Self> {
+        if !nbt.is_light_on {
+            return None;
+        }
+        let mut light = Self { sky: vec![None; section_count], block: vec![None; section_count] };
+        for section in &nbt.sections {
+            let index = section.y.checked_sub(min_section_y).filter(|index| *index >= 0)? as usize;
+            if index >= section_count {
+                return None;
+            }
+            light.sky[index] = section.sky_light.clone();
+            light.block[index] = section.block_light.clone();
+        }
+        Some(light)
+    }
+
+    /// Masks of the sky, block, empty sky and empty block light. The first bit is the section below
+    /// the world and the last bit is the section above it
+    pub fn masks(&self) -> [Vec<u64>; 4] {
+        let bits = self.section_count() + 2;
+        let mut masks = [(); 4].map(|_| vec![0u64; (bits + 63) / 64]);
+        let mut set = |mask: usize, bit: usize| masks[mask][bit >> 6] |= 1 << (bit & 63);
+        set(2, 0);
+        set(3, 0);
+        for index in 0..self.section_count() {
+            set(match self.sky[index].is_some() { true => 0, false => 2 }, index + 1);
+            set(match self.block[index].is_some() { true => 1, false => 3 }, index + 1);
+        }
+        set(0, bits - 1);
+        set(3, bits - 1);
+        masks
+    }
+
+    /// Light of the packet, `masks` must be returned by [`ChunkLight::masks`]
+    pub fn light_data<'a>(&'a self, masks: &'a [Vec<u64>; 4]) -> LightData<'a> {
+        // Safety: the length of all arrays is 2048
+        let array = |bytes: &'a [u8]| unsafe { LightArray::new(bytes) };
+        let mut sky_arrays: Vec<_> = self.sky.iter().flatten().map(|sky| array(sky.as_bytes())).collect();
+        sky_arrays.push(array(&FULL_LIGHT));
+        let block_arrays: Vec<_> = self.block.iter().flatten().map(|block| array(block.as_bytes())).collect();
+        let [sky, block, empty_sky, empty_block] = masks;
+        LightData {
+            trust_edges: true,
+            sky_light_mask: BitSet::new_words(sky),
+            block_light_mask: BitSet::new_words(block),
+            empty_sky_light_mask: BitSet::new_words(empty_sky),
+            empty_block_light_mask: BitSet::new_words(empty_block),
+            sky_light_arrays: Cow::Owned(sky_arrays),
+            block_light_arrays: Cow::Owned(block_arrays),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_test() {
+        let mut column = ChunkColumn::new(2);
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        let torch = Block::Torch.get_state().unwrap() as i32;
+        for x in 0..16 {
+            for z in 0..16 {
+                column.set_block_state(x, 20, z, stone);
+            }
+        }
+        column.set_block_state(4, 10, 4, torch);
+        let light = column.compute_light().clone();
+        assert_eq!(light.sky_light(0, 31, 0), MAX_LIGHT);
+        assert_eq!(light.sky_light(0, 19, 0), 0);
+        assert_eq!(light.block_light(4, 10, 4), 14);
+        assert_eq!(light.block_light(4, 11, 5), 12);
+        assert_eq!(light.block_light(4, 20, 4), 0);
+
+        let nbt = light.to_nbt(-4);
+        let mut bytes = Vec::new();
+        nbt.write_nbt(&mut bytes).unwrap();
+        let read = ChunkLightNbt::read_nbt(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, nbt);
+        assert_eq!(ChunkLight::from_nbt(&read, -4, 2), Some(light.clone()));
+        assert_eq!(ChunkLight::from_nbt(&read, -3, 2), None);
+        assert!(!column.load_light(&ChunkLightNbt { is_light_on: false, sections: Vec::new() }, -4));
+        assert!(column.load_light(&read, -4));
+
+        column.set_block_state(0, 25, 0, stone);
+        assert!(column.light().is_none());
+        assert!(!column.light_nbt(-4).is_light_on);
+        assert_eq!(column.compute_light().sky_light(0, 24, 0), 14);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use bird_chat::component::Component;
+use bird_protocol::{anyhow, ProtocolPacketState, MAX_FRAME_LENGTH};
+use crate::connection::PacketSender;
+use crate::events::{EventPriority, EventResult};
+use crate::protocol::*;
+
+/// At most `max` events in the `window`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RateLimit {
+    pub max: u32,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    pub const fn per_second(max: u32) -> Self {
+        Self { max, window: Duration::from_secs(1) }
+    }
+}
+
+/// Limits of incoming connections, `None` disables the limit
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConnectionLimits {
+    pub max_connections_per_ip: Option<usize>,
+    /// Handshakes of one address, status pings are counted too
+    pub handshake_rate: Option<RateLimit>,
+    /// Length of the frame without its header, bigger frames are not read at all
+    pub max_packet_size: usize,
+    pub handshake_packet_rate: Option<RateLimit>,
+    pub status_packet_rate: Option<RateLimit>,
+    pub login_packet_rate: Option<RateLimit>,
+    pub play_packet_rate: Option<RateLimit>,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: Some(3),
+            handshake_rate: Some(RateLimit { max: 10, window: Duration::from_secs(10) }),
+            max_packet_size: MAX_FRAME_LENGTH,
+            handshake_packet_rate: Some(RateLimit::per_second(2)),
+            status_packet_rate: Some(RateLimit::per_second(10)),
+            login_packet_rate: Some(RateLimit::per_second(50)),
+            play_packet_rate: Some(RateLimit::per_second(500)),
+        }
+    }
+}
+
+impl ConnectionLimits {
+    pub fn packet_rate(&self, state: ProtocolPacketState) -> Option<RateLimit> {
+        match state {
+            ProtocolPacketState::Handshake => self.handshake_packet_rate,
+            ProtocolPacketState::Status => self.status_packet_rate,
+            ProtocolPacketState::Login => self.login_packet_rate,
+            ProtocolPacketState::Play => self.play_packet_rate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LimitViolation {
+    TooManyConnections {
+        limit: usize,
+    },
+    HandshakeRate {
+        limit: RateLimit,
+    },
+    PacketTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    PacketFlood {
+        state: ProtocolPacketState,
+        limit: RateLimit,
+    },
+}
+
+impl LimitViolation {
+    pub fn message(&self) -> Component<'static> {
+        match self {
+            LimitViolation::TooManyConnections { limit } => Component::text(format!("Too many connections from your address, the limit is {}", limit)),
+            LimitViolation::HandshakeRate { .. } => Component::text("Connection throttled! Please wait before reconnecting."),
+            LimitViolation::PacketTooLarge { .. } => Component::text("Packet is too large"),
+            LimitViolation::PacketFlood { .. } => Component::text("You are sending too many packets!"),
+        }
+    }
+
+    /// Sends the message in states which have the disconnect packet, the caller closes the connection after it
+    pub fn disconnect<S: PacketSender>(&self, state: ProtocolPacketState, sender: &mut S) -> anyhow::Result<()> {
+        match state {
+            ProtocolPacketState::Login => sender.send_packet(&LoginDisconnectLS2C { reason: self.message() }),
+            ProtocolPacketState::Play => sender.send_packet(&DisconnectPS2C { reason: self.message() }),
+            ProtocolPacketState::Handshake | ProtocolPacketState::Status => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LimitEvent {
+    pub connection: u64,
+    pub address: IpAddr,
+    pub state: ProtocolPacketState,
+    pub violation: LimitViolation,
+}
+
+type LimitHandler = Box<dyn Fn(&LimitEvent) -> EventResult + Send + Sync>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct LimitedConnection {
+    address: IpAddr,
+    state: ProtocolPacketState,
+    window_start: Instant,
+    packets: u32,
+}
+
+/// Checks connections against [`ConnectionLimits`]. A violation means that the connection must be closed,
+/// handlers are told about every violation and may let the connection stay with [`EventResult::Cancel`]
+pub struct ConnectionLimiter {
+    pub limits: ConnectionLimits,
+    connections: HashMap<u64, LimitedConnection>,
+    per_address: HashMap<IpAddr, usize>,
+    handshakes: HashMap<IpAddr, VecDeque<Instant>>,
+    handlers: Vec<(EventPriority, LimitHandler)>,
+    tripped: u64,
+}
+
+impl ConnectionLimiter {
+    pub fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            connections: HashMap::new(),
+            per_address: HashMap::new(),
+            handshakes: HashMap::new(),
+            handlers: Vec::new(),
+            tripped: 0,
+        }
+    }
+
+    /// Handlers with the higher priority are called first, [`EventResult::Cancel`] forgives the violation
+    pub fn on_limit<F>(&mut self, priority: EventPriority, handler: F) -> &mut Self
+        where F: Fn(&LimitEvent) -> EventResult + Send + Sync + 'static {
+        let index = self.handlers.partition_point(|(other, _)| *other >= priority);
+        self.handlers.insert(index, (priority, Box::new(handler)));
+        self
+    }
+
+    /// Amount of violations which were not forgiven
+    pub fn tripped(&self) -> u64 {
+        self.tripped
+    }
+
+    pub fn connections_of(&self, address: IpAddr) -> usize {
+        self.per_address.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Registers the accepted connection, it is not registered if the violation is returned
+    pub fn accept(&mut self, connection: u64, address: IpAddr, now: Instant) -> Result<(), LimitViolation> {
+        if let Some(limit) = self.limits.max_connections_per_ip {
+            if self.connections_of(address) >= limit {
+                self.trip(connection, address, ProtocolPacketState::Handshake, LimitViolation::TooManyConnections { limit })?;
+            }
+        }
+        self.connections.insert(connection, LimitedConnection {
+            address,
+            state: ProtocolPacketState::Handshake,
+            window_start: now,
+            packets: 0,
+        });
+        *self.per_address.entry(address).or_default() += 1;
+        Ok(())
+    }
+
+    /// Must be called when the connection is closed
+    pub fn remove(&mut self, connection: u64) {
+        let address = match self.connections.remove(&connection) {
+            Some(removed) => removed.address,
+            None => return,
+        };
+        if let Some(count) = self.per_address.get_mut(&address) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_address.remove(&address);
+            }
+        }
+    }
+
+    /// Counts the handshake of the connection and switches it to the next state
+    pub fn handshake(&mut self, connection: u64, next_state: ProtocolPacketState, now: Instant) -> Result<(), LimitViolation> {
+        let address = match self.connections.get_mut(&connection) {
+            Some(limited) => {
+                limited.state = next_state;
+                limited.packets = 0;
+                limited.window_start = now;
+                limited.address
+            }
+            None => return Ok(()),
+        };
+        let limit = match self.limits.handshake_rate {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let handshakes = self.handshakes.entry(address).or_default();
+        while handshakes.front().map_or(false, |&time| now.duration_since(time) >= limit.window) {
+            handshakes.pop_front();
+        }
+        handshakes.push_back(now);
+        match handshakes.len() > limit.max as usize {
+            true => self.trip(connection, address, next_state, LimitViolation::HandshakeRate { limit }),
+            false => Ok(()),
+        }
+    }
+
+    /// Login switches the connection to play
+    pub fn set_state(&mut self, connection: u64, state: ProtocolPacketState) {
+        if let Some(limited) = self.connections.get_mut(&connection) {
+            limited.state = state;
+        }
+    }
+
+    /// Checks the length of the frame before it is read
+    pub fn check_frame_length(&mut self, connection: u64, length: usize) -> Result<(), LimitViolation> {
+        let limit = self.limits.max_packet_size;
+        match (length > limit, self.connections.get(&connection)) {
+            (true, Some(limited)) => {
+                let (address, state) = (limited.address, limited.state);
+                self.trip(connection, address, state, LimitViolation::PacketTooLarge { size: length, limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts the received packet in the rate window of the connection state
+    pub fn packet(&mut self, connection: u64, now: Instant) -> Result<(), LimitViolation> {
+        let limited = match self.connections.get_mut(&connection) {
+            Some(limited) => limited,
+            None => return Ok(()),
+        };
+        let limit = match self.limits.packet_rate(limited.state) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        if now.duration_since(limited.window_start) >= limit.window {
+            limited.window_start = now;
+            limited.packets = 0;
+        }
+        limited.packets += 1;
+        match limited.packets > limit.max {
+            true => {
+                let (address, state) = (limited.address, limited.state);
+                self.trip(connection, address, state, LimitViolation::PacketFlood { state, limit })
+            }
+            false => Ok(()),
+        }
+    }
+
+    /// Forgets handshakes which are out of the window, so idle addresses don't take memory
+    pub fn cleanup(&mut self, now: Instant) {
+        let window = match self.limits.handshake_rate {
+            Some(limit) => limit.window,
+            None => Duration::ZERO,
+        };
+        self.handshakes.retain(|_, handshakes| {
+            handshakes.retain(|&time| now.duration_since(time) < window);
+            !handshakes.is_empty()
+        });
+    }
+
+    fn trip(&mut self, connection: u64, address: IpAddr, state: ProtocolPacketState, violation: LimitViolation) -> Result<(), LimitViolation> {
+        let event = LimitEvent { connection, address, state, violation };
+        for (_, handler) in &self.handlers {
+            if handler(&event) == EventResult::Cancel {
+                return Ok(());
+            }
+        }
+        self.tripped += 1;
+        Err(violation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn connection_limiter_test() {
+        let now = Instant::now();
+        let address = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let trusted = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let mut limiter = ConnectionLimiter::new(ConnectionLimits {
+            max_connections_per_ip: Some(2),
+            handshake_rate: Some(RateLimit { max: 2, window: Duration::from_secs(10) }),
+            max_packet_size: 1024,
+            ..Default::default()
+        });
+        limiter.on_limit(EventPriority::Normal, move |event| match event.address == trusted {
+            true => EventResult::Cancel,
+            false => EventResult::Continue,
+        });
+        assert_eq!(limiter.accept(1, address, now), Ok(()));
+        assert_eq!(limiter.accept(2, address, now), Ok(()));
+        assert_eq!(limiter.accept(3, address, now), Err(LimitViolation::TooManyConnections { limit: 2 }));
+        assert_eq!(limiter.accept(3, trusted, now), Ok(()));
+        assert_eq!(limiter.accept(4, trusted, now), Ok(()));
+        assert_eq!(limiter.accept(5, trusted, now), Ok(()));
+        limiter.remove(2);
+        assert_eq!(limiter.connections_of(address), 1);
+
+        assert_eq!(limiter.handshake(1, ProtocolPacketState::Status, now), Ok(()));
+        assert_eq!(limiter.accept(2, address, now), Ok(()));
+        assert_eq!(limiter.handshake(2, ProtocolPacketState::Login, now), Ok(()));
+        limiter.remove(1);
+        assert_eq!(limiter.accept(1, address, now), Ok(()));
+        assert!(matches!(limiter.handshake(1, ProtocolPacketState::Login, now), Err(LimitViolation::HandshakeRate { .. })));
+        limiter.cleanup(now + Duration::from_secs(10));
+        assert_eq!(limiter.handshake(1, ProtocolPacketState::Login, now + Duration::from_secs(10)), Ok(()));
+
+        assert!(matches!(limiter.check_frame_length(2, 2048), Err(LimitViolation::PacketTooLarge { size: 2048, limit: 1024 })));
+        assert_eq!(limiter.check_frame_length(2, 1024), Ok(()));
+        for _ in 0..50 {
+            assert_eq!(limiter.packet(2, now), Ok(()));
+        }
+        let violation = limiter.packet(2, now).unwrap_err();
+        assert!(matches!(violation, LimitViolation::PacketFlood { state: ProtocolPacketState::Login, .. }));
+        assert_eq!(limiter.packet(2, now + Duration::from_secs(1)), Ok(()));
+        assert_eq!(limiter.tripped(), 4);
+
+        let mut sender = PacketQueue::new();
+        violation.disconnect(ProtocolPacketState::Login, &mut sender).unwrap();
+        violation.disconnect(ProtocolPacketState::Status, &mut sender).unwrap();
+        assert_eq!(sender.take().len(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use bird_protocol::{anyhow, ProtocolPacketState};
+use crate::limits::{ConnectionLimiter, LimitViolation};
+
+/// Listener of the game port, accepted connections are checked by the [`ConnectionLimiter`]
+pub struct GameListener {
+    listener: TcpListener,
+    limiter: Arc<Mutex<ConnectionLimiter>>,
+    next_connection: u64,
+}
+
+fn lock(limiter: &Mutex<ConnectionLimiter>) -> MutexGuard<'_, ConnectionLimiter> {
+    limiter.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+impl GameListener {
+    pub fn bind(address: SocketAddr, limiter: ConnectionLimiter) -> anyhow::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            limiter: Arc::new(Mutex::new(limiter)),
+            next_connection: 0,
+        })
+    }
+
+    pub fn local_address(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Shared with the connections, so limit handlers can be added after the bind
+    pub fn limiter(&self) -> MutexGuard<'_, ConnectionLimiter> {
+        lock(&self.limiter)
+    }
+
+    /// Waits for the next client, `None` if it broke the limits and its stream is already closed
+    pub fn accept(&mut self) -> anyhow::Result<Option<AcceptedConnection>> {
+        let (stream, address) = self.listener.accept()?;
+        self.next_connection += 1;
+        let id = self.next_connection;
+        match lock(&self.limiter).accept(id, address.ip(), Instant::now()) {
+            // There is no disconnect packet before the handshake, so the stream is just dropped
+            Err(_) => Ok(None),
+            Ok(()) => Ok(Some(AcceptedConnection { id, stream, address, limiter: self.limiter.clone() })),
+        }
+    }
+}
+
+/// Connection which is counted by the limiter until it is dropped
+pub struct AcceptedConnection {
+    pub id: u64,
+    pub stream: TcpStream,
+    pub address: SocketAddr,
+    limiter: Arc<Mutex<ConnectionLimiter>>,
+}
+
+impl AcceptedConnection {
+    pub fn handshake(&self, next_state: ProtocolPacketState) -> Result<(), LimitViolation> {
+        lock(&self.limiter).handshake(self.id, next_state, Instant::now())
+    }
+
+    pub fn set_state(&self, state: ProtocolPacketState) {
+        lock(&self.limiter).set_state(self.id, state)
+    }
+
+    pub fn check_frame_length(&self, length: usize) -> Result<(), LimitViolation> {
+        lock(&self.limiter).check_frame_length(self.id, length)
+    }
+
+    pub fn packet(&self) -> Result<(), LimitViolation> {
+        lock(&self.limiter).packet(self.id, Instant::now())
+    }
+}
+
+impl Drop for AcceptedConnection {
+    fn drop(&mut self) {
+        lock(&self.limiter).remove(self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::Ipv4Addr;
+    use crate::limits::ConnectionLimits;
+    use super::*;
+
+    #[test]
+    fn limited_accept_test() {
+        let localhost = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let mut listener = GameListener::bind(localhost, ConnectionLimiter::new(ConnectionLimits {
+            max_connections_per_ip: Some(1),
+            ..Default::default()
+        })).unwrap();
+        let address = listener.local_address().unwrap();
+
+        let _first_client = TcpStream::connect(address).unwrap();
+        let first = listener.accept().unwrap().unwrap();
+        assert_eq!(first.handshake(ProtocolPacketState::Status), Ok(()));
+        let mut rejected_client = TcpStream::connect(address).unwrap();
+        assert!(listener.accept().unwrap().is_none());
+        // The server closed the stream without writing anything
+        assert_eq!(rejected_client.read(&mut [0; 1]).unwrap(), 0);
+        assert_eq!(listener.limiter().tripped(), 1);
+
+        drop(first);
+        assert_eq!(listener.limiter().connections_of(Ipv4Addr::LOCALHOST.into()), 0);
+        let _second_client = TcpStream::connect(address).unwrap();
+        let second = listener.accept().unwrap().unwrap();
+        assert_eq!(second.id, 3);
+        assert_eq!(listener.limiter().connections_of(Ipv4Addr::LOCALHOST.into()), 1);
+    }
+}
@@ -0,0 +1,841 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rsa::{BigUint, PaddingScheme, PublicKey, RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use bird_chat::identifier::Identifier;
+use bird_protocol::*;
+use bird_protocol::derive::ProtocolAll;
+use crate::connection::{ConnectionInfo, PacketSender};
+use crate::instrument::trace_span;
+use crate::protocol::*;
+
+pub const VELOCITY_CHANNEL: &'static str = "velocity:player_info";
+/// Version of the Velocity forwarding without the player public key
+pub const VELOCITY_FORWARDING_VERSION: i32 = 1;
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+pub const UNVERIFIED_USERNAME_MESSAGE: &'static str = "Failed to verify username!";
+/// Vanilla sends 4 random bytes
+pub const VERIFY_TOKEN_LENGTH: usize = 4;
+pub const SHARED_SECRET_LENGTH: usize = 16;
+/// Mojang key which signs profile properties, it is shipped in the authlib jar
+pub const YGGDRASIL_KEY_FILE: &'static str = "yggdrasil_session_pubkey.der";
+pub const SESSION_OUTAGE_MESSAGE: &'static str = "Authentication servers are down. Please try again later, sorry!";
+
+/// How the player information is received from the proxy
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum ForwardingMode {
+    /// Players are connecting directly in offline mode
+    #[default]
+    None,
+    /// Address, uuid and properties are appended to the handshake address
+    BungeeCord,
+    /// Player information is sent in the login plugin response signed with the shared secret
+    Velocity {
+        secret: Vec<u8>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ForwardedPlayer {
+    pub address: String,
+    pub uuid: Uuid,
+    pub properties: Vec<OwnedProperty>,
+}
+
+/// Parses `host\0address\0uuid\0properties` format of BungeeCord, returns the host and the player
+pub fn parse_bungee_address(address: &str) -> anyhow::Result<(&str, ForwardedPlayer)> {
+    let mut parts = address.split('\0');
+    let bad_address = || anyhow::Error::msg("If you wish to use IP forwarding, please enable it in your BungeeCord config as well!");
+    let host = parts.next().ok_or_else(bad_address)?;
+    let player_address = parts.next().ok_or_else(bad_address)?;
+    let uuid = parts.next().and_then(|uuid| Uuid::parse_str(uuid).ok()).ok_or_else(bad_address)?;
+    let properties = match parts.next() {
+        Some(properties) if !properties.is_empty() => serde_json::from_str(properties)?,
+        _ => Vec::new(),
+    };
+    Ok((host, ForwardedPlayer { address: player_address.into(), uuid, properties }))
+}
+
+/// Uuid which vanilla gives to players in offline mode
+pub fn offline_uuid(name: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{}", name).as_bytes());
+    uuid::Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+#[derive(ProtocolAll, Clone, PartialEq, Debug)]
+pub struct VelocityForwardingData<'a> {
+    #[bp(variant = VarInt)]
+    pub version: i32,
+    pub address: &'a str,
+    pub uuid: Uuid,
+    pub name: &'a str,
+    #[bp(variant = "LengthProvidedArray<i32, VarInt, Property<'a>, Property<'a>>")]
+    pub properties: Cow<'a, [Property<'a>]>,
+}
+
+/// Checks the HMAC-SHA256 signature which is placed before the forwarding data
+pub fn verify_velocity_data<'a>(secret: &[u8], data: &'a [u8]) -> anyhow::Result<VelocityForwardingData<'a>> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        return Err(anyhow::Error::msg("Unable to verify player details"));
+    }
+    let (signature, mut payload) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).map_err(|_| anyhow::Error::msg("Bad forwarding secret"))?;
+    mac.update(payload);
+    mac.verify_slice(signature).map_err(|_| anyhow::Error::msg("Unable to verify player details"))?;
+    Ok(VelocityForwardingData::read(&mut payload)?)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LoginProfile {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<OwnedProperty>,
+    /// Real address of the player if it is forwarded by the proxy
+    pub address: Option<String>,
+}
+
+/// Verifies signatures of profile properties (like textures) which are made by the session server
+#[derive(Clone, PartialEq, Debug)]
+pub struct PropertyVerifier {
+    key: RsaPublicKey,
+}
+
+impl PropertyVerifier {
+    pub fn new(key: RsaPublicKey) -> Self {
+        Self { key }
+    }
+
+    /// Key in the X.509 DER format like `yggdrasil_session_pubkey.der`
+    pub fn from_der(der: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self::new(RsaPublicKey::from_public_key_der(der).map_err(|_| anyhow::Error::msg("Bad yggdrasil public key"))?))
+    }
+
+    /// Verifies SHA1 with RSA signature of the base64 value, properties without the signature are not verified
+    pub fn verify(&self, property: &OwnedProperty) -> bool {
+        let signature = match property.signature.as_deref().map(base64::decode) {
+            Some(Ok(signature)) => signature,
+            _ => return false,
+        };
+        let hashed = Sha1::digest(property.value.as_bytes());
+        self.key.verify(PaddingScheme::new_pkcs1v15_sign::<Sha1>(), &hashed, &signature).is_ok()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum UnverifiedPropertyPolicy {
+    #[default]
+    Keep,
+    /// Properties are removed from the profile before the login success is sent
+    Strip,
+    /// Player is disconnected
+    Reject,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PropertyVerification {
+    pub verifier: PropertyVerifier,
+    pub policy: UnverifiedPropertyPolicy,
+}
+
+impl PropertyVerification {
+    pub fn apply(&self, properties: Vec<OwnedProperty>) -> anyhow::Result<Vec<OwnedProperty>> {
+        match self.policy {
+            UnverifiedPropertyPolicy::Keep => Ok(properties),
+            UnverifiedPropertyPolicy::Strip => Ok(properties.into_iter()
+                .filter(|property| self.verifier.verify(property))
+                .collect()),
+            UnverifiedPropertyPolicy::Reject => match properties.iter().all(|property| self.verifier.verify(property)) {
+                true => Ok(properties),
+                false => Err(anyhow::Error::msg("Invalid profile properties")),
+            },
+        }
+    }
+}
+
+/// RSA key of the server which is used to encrypt the shared secret
+#[derive(Clone, PartialEq, Debug)]
+pub struct ServerKey {
+    pub private_key: RsaPrivateKey,
+    /// X.509 DER encoded public key which is sent in the encryption request
+    pub public_key: Vec<u8>,
+}
+
+impl ServerKey {
+    pub fn new(private_key: RsaPrivateKey) -> anyhow::Result<Self> {
+        let public_key = RsaPublicKey::from(&private_key).to_public_key_der()
+            .map_err(|_| anyhow::Error::msg("Unable to encode the public key"))?;
+        Ok(Self { private_key, public_key: public_key.as_ref().to_vec() })
+    }
+}
+
+/// Compares the bytes in the time which doesn't depend on their content
+pub fn constant_time_eq(first: &[u8], second: &[u8]) -> bool {
+    first.len() == second.len() && first.iter().zip(second).fold(0, |result, (first, second)| result | (first ^ second)) == 0
+}
+
+/// Verify token of one login, the token is consumed by the first response
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncryptionChallenge {
+    verify_token: [u8; VERIFY_TOKEN_LENGTH],
+    used: bool,
+}
+
+impl EncryptionChallenge {
+    pub fn new() -> anyhow::Result<Self> {
+        let mut verify_token = [0; VERIFY_TOKEN_LENGTH];
+        getrandom::getrandom(&mut verify_token).map_err(|_| anyhow::Error::msg("Unable to generate the verify token"))?;
+        Ok(Self { verify_token, used: false })
+    }
+
+    pub fn verify_token(&self) -> &[u8] {
+        &self.verify_token
+    }
+
+    pub fn request<'a>(&'a self, key: &'a ServerKey) -> EncryptionRequestLS2C<'a> {
+        EncryptionRequestLS2C {
+            server_id: "",
+            public_key: &key.public_key,
+            verify_token: &self.verify_token,
+        }
+    }
+
+    /// Decrypts and checks the verify token, returns the shared secret
+    pub fn verify(&mut self, packet: &EncryptionResponseLC2S, key: &ServerKey) -> anyhow::Result<[u8; SHARED_SECRET_LENGTH]> {
+        if self.used {
+            return Err(anyhow::Error::msg("Verify token was already used"));
+        }
+        self.used = true;
+        let verify_token = key.private_key.decrypt(PaddingScheme::new_pkcs1v15_encrypt(), packet.verify_token)
+            .map_err(|_| anyhow::Error::msg("Invalid verify token"))?;
+        if !constant_time_eq(&verify_token, &self.verify_token) {
+            return Err(anyhow::Error::msg("Invalid verify token"));
+        }
+        let shared_secret = key.private_key.decrypt(PaddingScheme::new_pkcs1v15_encrypt(), packet.shared_secret)
+            .map_err(|_| anyhow::Error::msg("Invalid shared secret"))?;
+        shared_secret.try_into().map_err(|_| anyhow::Error::msg("Invalid shared secret"))
+    }
+}
+
+/// Hash which is sent to the session server in `hasJoined`, SHA1 as the signed number in the hex format
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    let mut hash = hasher.finalize();
+    let negative = hash[0] & 0x80 != 0;
+    if negative {
+        // Two's complement
+        let mut carry = true;
+        for byte in hash.iter_mut().rev() {
+            let (value, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = value;
+            carry = overflow;
+        }
+    }
+    let hex = BigUint::from_bytes_be(&hash).to_str_radix(16);
+    match negative {
+        true => format!("-{}", hex),
+        false => hex,
+    }
+}
+
+/// Result of the encryption which is used to enable the cipher and to authenticate the player
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncryptionResult {
+    pub name: String,
+    pub shared_secret: [u8; SHARED_SECRET_LENGTH],
+    pub server_hash: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum LoginStage {
+    Start,
+    Forwarding {
+        message_id: i32,
+    },
+    Encryption {
+        name: String,
+        challenge: EncryptionChallenge,
+    },
+    /// Waiting for the session server
+    Authenticating,
+    Finished,
+}
+
+/// What the login does after the login start
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProfileStep {
+    /// The profile is known, the login is finished right away
+    Resolved(LoginProfile),
+    /// The encryption is started, the profile is resolved by [`SessionAuthenticator`]
+    /// and given to [`LoginHandler::finish_authentication`]
+    Encrypt(Arc<ServerKey>),
+    /// The login plugin request is sent, the response is given to [`ProfileProvider::plugin_response`]
+    PluginRequest {
+        channel: Cow<'static, str>,
+        data: Vec<u8>,
+    },
+}
+
+/// Authentication strategy of the server, like online mode, offline mode or forwarding by a proxy
+pub trait ProfileProvider: std::fmt::Debug + Send + Sync {
+    /// `forwarded` is the player information from the handshake address
+    fn login_start(&self, packet: &LoginStartLC2S, forwarded: Option<&ForwardedPlayer>) -> anyhow::Result<ProfileStep>;
+
+    fn plugin_response(&self, _packet: &LoginPluginResponseLC2S) -> anyhow::Result<LoginProfile> {
+        Err(anyhow::Error::msg("Unexpected login plugin response"))
+    }
+}
+
+/// Players join without the encryption with the uuid derived from their name
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct OfflineProfiles;
+
+impl ProfileProvider for OfflineProfiles {
+    fn login_start(&self, packet: &LoginStartLC2S, _forwarded: Option<&ForwardedPlayer>) -> anyhow::Result<ProfileStep> {
+        Ok(ProfileStep::Resolved(LoginProfile {
+            uuid: offline_uuid(packet.name),
+            name: packet.name.into(),
+            properties: Vec::new(),
+            address: None,
+        }))
+    }
+}
+
+/// Players are authenticated by the session server
+#[derive(Clone, PartialEq, Debug)]
+pub struct OnlineProfiles {
+    pub key: Arc<ServerKey>,
+}
+
+impl ProfileProvider for OnlineProfiles {
+    fn login_start(&self, _packet: &LoginStartLC2S, _forwarded: Option<&ForwardedPlayer>) -> anyhow::Result<ProfileStep> {
+        Ok(ProfileStep::Encrypt(self.key.clone()))
+    }
+}
+
+/// Profiles are taken from the handshake address, see [`ConnectionInfo::from_handshake`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct BungeeCordProfiles;
+
+impl ProfileProvider for BungeeCordProfiles {
+    fn login_start(&self, packet: &LoginStartLC2S, forwarded: Option<&ForwardedPlayer>) -> anyhow::Result<ProfileStep> {
+        let forwarded = forwarded
+            .ok_or_else(|| anyhow::Error::msg("If you wish to use IP forwarding, please enable it in your BungeeCord config as well!"))?;
+        Ok(ProfileStep::Resolved(LoginProfile {
+            uuid: forwarded.uuid,
+            name: packet.name.into(),
+            properties: forwarded.properties.clone(),
+            address: Some(forwarded.address.clone()),
+        }))
+    }
+}
+
+/// Profiles are requested from Velocity with the login plugin request
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VelocityProfiles {
+    pub secret: Vec<u8>,
+}
+
+impl ProfileProvider for VelocityProfiles {
+    fn login_start(&self, _packet: &LoginStartLC2S, _forwarded: Option<&ForwardedPlayer>) -> anyhow::Result<ProfileStep> {
+        let mut data = Vec::new();
+        VarInt::write_variant(&VELOCITY_FORWARDING_VERSION, &mut data)?;
+        Ok(ProfileStep::PluginRequest { channel: Cow::Borrowed(VELOCITY_CHANNEL), data })
+    }
+
+    fn plugin_response(&self, packet: &LoginPluginResponseLC2S) -> anyhow::Result<LoginProfile> {
+        if !packet.successful {
+            return Err(anyhow::Error::msg("This server requires you to connect with Velocity"));
+        }
+        let data = verify_velocity_data(&self.secret, packet.data)?;
+        if data.version > VELOCITY_FORWARDING_VERSION {
+            return Err(anyhow::Error::msg("Unsupported forwarding version"));
+        }
+        Ok(LoginProfile {
+            uuid: data.uuid,
+            name: data.name.into(),
+            properties: data.properties.iter().map(|property| OwnedProperty::from(*property)).collect(),
+            address: Some(data.address.into()),
+        })
+    }
+}
+
+impl ForwardingMode {
+    /// Provider of the forwarding mode, players which are not forwarded are authenticated if the key is given
+    pub fn provider(&self, server_key: Option<Arc<ServerKey>>) -> Arc<dyn ProfileProvider> {
+        match (self, server_key) {
+            (ForwardingMode::None, Some(key)) => Arc::new(OnlineProfiles { key }),
+            (ForwardingMode::None, None) => Arc::new(OfflineProfiles),
+            (ForwardingMode::BungeeCord, _) => Arc::new(BungeeCordProfiles),
+            (ForwardingMode::Velocity { secret }, _) => Arc::new(VelocityProfiles { secret: secret.clone() }),
+        }
+    }
+}
+
+/// Login state machine, returns the profile of the player when the login is finished
+#[derive(Clone, Debug)]
+pub struct LoginHandler {
+    provider: Arc<dyn ProfileProvider>,
+    forwarded: Option<ForwardedPlayer>,
+    stage: LoginStage,
+    /// Key of the started encryption
+    server_key: Option<Arc<ServerKey>>,
+    /// Verification of properties which are received from the proxy
+    pub property_verification: Option<PropertyVerification>,
+}
+
+impl LoginHandler {
+    pub fn new(provider: Arc<dyn ProfileProvider>, info: &ConnectionInfo) -> Self {
+        Self {
+            provider,
+            forwarded: info.forwarded.clone(),
+            stage: LoginStage::Start,
+            server_key: None,
+            property_verification: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stage == LoginStage::Finished
+    }
+
+    pub fn handle_start<S: PacketSender>(&mut self, packet: &LoginStartLC2S, sender: &mut S) -> anyhow::Result<Option<LoginProfile>> {
+        let _span = trace_span!("login_start", name = packet.name);
+        if self.stage != LoginStage::Start {
+            return Err(anyhow::Error::msg("Unexpected login start"));
+        }
+        match self.provider.login_start(packet, self.forwarded.as_ref())? {
+            ProfileStep::Resolved(profile) => self.finish(profile, sender),
+            ProfileStep::Encrypt(key) => {
+                let challenge = EncryptionChallenge::new()?;
+                sender.send_packet(&challenge.request(&key))?;
+                self.server_key = Some(key);
+                self.stage = LoginStage::Encryption { name: packet.name.into(), challenge };
+                Ok(None)
+            }
+            ProfileStep::PluginRequest { channel, data } => {
+                let message_id = 0;
+                sender.send_packet(&LoginPluginRequestLS2C {
+                    message_id,
+                    channel: Identifier::new_full(channel)
+                        .ok_or_else(|| anyhow::Error::msg("Invalid login plugin channel"))?,
+                    data: &data,
+                })?;
+                self.stage = LoginStage::Forwarding { message_id };
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn handle_plugin_response<S: PacketSender>(
+        &mut self,
+        packet: &LoginPluginResponseLC2S,
+        sender: &mut S,
+    ) -> anyhow::Result<Option<LoginProfile>> {
+        let _span = trace_span!("login_forwarding", message_id = packet.message_id);
+        match self.stage {
+            LoginStage::Forwarding { message_id } if message_id == packet.message_id => {}
+            _ => return Ok(None),
+        }
+        let profile = self.provider.plugin_response(packet)?;
+        self.finish(profile, sender)
+    }
+
+    /// Cipher must be enabled with the shared secret before the next packet is sent
+    pub fn handle_encryption_response(&mut self, packet: &EncryptionResponseLC2S) -> anyhow::Result<EncryptionResult> {
+        let _span = trace_span!("login_encryption");
+        let key = self.server_key.clone().ok_or_else(|| anyhow::Error::msg("Unexpected encryption response"))?;
+        let (name, challenge) = match self.stage {
+            LoginStage::Encryption { ref name, ref mut challenge } => (name.clone(), challenge),
+            _ => return Err(anyhow::Error::msg("Unexpected encryption response")),
+        };
+        let shared_secret = challenge.verify(packet, &key)?;
+        self.stage = LoginStage::Authenticating;
+        Ok(EncryptionResult {
+            server_hash: server_hash("", &shared_secret, &key.public_key),
+            name,
+            shared_secret,
+        })
+    }
+
+    /// Finishes the login with the profile which is resolved by [`SessionAuthenticator`]
+    pub fn finish_authentication<S: PacketSender>(&mut self, profile: LoginProfile, sender: &mut S) -> anyhow::Result<Option<LoginProfile>> {
+        if self.stage != LoginStage::Authenticating {
+            return Err(anyhow::Error::msg("Player is not authenticating"));
+        }
+        self.finish(profile, sender)
+    }
+
+    fn finish<S: PacketSender>(&mut self, mut profile: LoginProfile, sender: &mut S) -> anyhow::Result<Option<LoginProfile>> {
+        let _span = trace_span!("login_finish", name = %profile.name, uuid = %profile.uuid);
+        if let Some(ref verification) = self.property_verification {
+            profile.properties = verification.apply(profile.properties)?;
+        }
+        sender.send_packet(&LoginSuccessLS2C {
+            uuid: profile.uuid,
+            username: &profile.name,
+            properties: Cow::Owned(profile.properties.iter().map(|property| property.as_property()).collect()),
+        })?;
+        self.stage = LoginStage::Finished;
+        Ok(Some(profile))
+    }
+}
+
+/// Result of the `hasJoined` request to the session server
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SessionAuthResult {
+    Authenticated(LoginProfile),
+    /// The session server answered, but the player didn't join
+    Unverified,
+    /// The request failed or timed out
+    Unreachable,
+}
+
+/// Fallbacks which are tried in order when the session server is unreachable
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SessionFallback {
+    /// Profiles of players who were authenticated during the cache lifetime are accepted
+    CachedProfile,
+    /// Players from the list (lowercase names) join with the offline uuid
+    OfflineAllowList(HashSet<String>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SessionOutageConfig {
+    pub fallbacks: Vec<SessionFallback>,
+    pub cache_lifetime: Duration,
+    /// Disconnect reason if no fallback accepted the player
+    pub message: String,
+}
+
+impl Default for SessionOutageConfig {
+    fn default() -> Self {
+        Self {
+            fallbacks: Vec::new(),
+            cache_lifetime: Duration::from_secs(24 * 60 * 60),
+            message: SESSION_OUTAGE_MESSAGE.into(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct CachedSession {
+    profile: LoginProfile,
+    authenticated: Instant,
+}
+
+/// Caches successful authentications and applies the fallback policy during session server outages
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SessionAuthenticator {
+    pub config: SessionOutageConfig,
+    /// Sessions by the uuid of the profile
+    sessions: HashMap<Uuid, CachedSession>,
+}
+
+impl SessionAuthenticator {
+    pub fn new(config: SessionOutageConfig) -> Self {
+        Self {
+            config,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Returns the profile of the player or the disconnect reason.
+    /// `uuid` is the uuid which is sent by the client in the login start packet,
+    /// cached profiles are not accepted without it
+    pub fn resolve(&mut self, name: &str, uuid: Option<Uuid>, result: SessionAuthResult, now: Instant) -> Result<LoginProfile, String> {
+        match result {
+            SessionAuthResult::Authenticated(profile) => {
+                self.sessions.insert(profile.uuid, CachedSession { profile: profile.clone(), authenticated: now });
+                Ok(profile)
+            }
+            SessionAuthResult::Unverified => Err(UNVERIFIED_USERNAME_MESSAGE.into()),
+            SessionAuthResult::Unreachable => {
+                let key = name.to_lowercase();
+                for fallback in &self.config.fallbacks {
+                    match fallback {
+                        SessionFallback::CachedProfile => match uuid.and_then(|uuid| self.sessions.get(&uuid)) {
+                            Some(cached) if now.duration_since(cached.authenticated) < self.config.cache_lifetime
+                                && cached.profile.name.eq_ignore_ascii_case(name) => {
+                                return Ok(cached.profile.clone());
+                            }
+                            _ => {}
+                        },
+                        SessionFallback::OfflineAllowList(players) if players.contains(&key) => {
+                            return Ok(LoginProfile {
+                                uuid: offline_uuid(name),
+                                name: name.into(),
+                                properties: Vec::new(),
+                                address: None,
+                            });
+                        }
+                        SessionFallback::OfflineAllowList(_) => {}
+                    }
+                }
+                Err(self.config.message.clone())
+            }
+        }
+    }
+
+    /// Removes sessions which are older than the cache lifetime
+    pub fn clear_expired(&mut self, now: Instant) {
+        let lifetime = self.config.cache_lifetime;
+        self.sessions.retain(|_, cached| now.duration_since(cached.authenticated) < lifetime);
+    }
+
+    pub fn cached_sessions(&self) -> usize {
+        self.sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::PublicKeyParts;
+    use crate::connection::PacketQueue;
+    use crate::registry::{LoginC2SPacket, Packet};
+    use crate::status::PROTOCOL_VERSION;
+    use super::*;
+
+    fn connection_info() -> ConnectionInfo {
+        ConnectionInfo {
+            protocol_version: PROTOCOL_VERSION,
+            host: "localhost".into(),
+            port: 25565,
+            next_state: HandshakeNextState::Login,
+            mod_loader: None,
+            forwarded: None,
+        }
+    }
+
+    #[test]
+    fn bungee_address_test() {
+        let (host, player) = parse_bungee_address(
+            "localhost\x00127.0.0.1\x00069a79f444e94726a5befca90e38aaf5\x00[{\"name\":\"textures\",\"value\":\"abc\"}]"
+        ).unwrap();
+        assert_eq!(host, "localhost");
+        assert_eq!(player.address, "127.0.0.1");
+        assert_eq!(player.uuid, Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap());
+        assert_eq!(player.properties, vec![OwnedProperty { name: "textures".into(), value: "abc".into(), signature: None }]);
+        assert!(parse_bungee_address("localhost").is_err());
+    }
+
+    #[test]
+    fn velocity_test() {
+        let secret = b"secret";
+        let mut payload = Vec::new();
+        VelocityForwardingData {
+            version: VELOCITY_FORWARDING_VERSION,
+            address: "127.0.0.1",
+            uuid: offline_uuid("Notch"),
+            name: "Notch",
+            properties: Cow::Borrowed(&[]),
+        }.write(&mut payload).unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(&payload);
+        let info = connection_info();
+        let provider = ForwardingMode::Velocity { secret: secret.to_vec() }.provider(None);
+        let mut handler = LoginHandler::new(provider.clone(), &info);
+        let mut queue = PacketQueue::new();
+        let start = LoginStartLC2S { name: "Notch", uuid: None };
+        assert_eq!(handler.handle_start(&start, &mut queue).unwrap(), None);
+        let response = LoginPluginResponseLC2S { message_id: 0, successful: true, data: &data };
+        let profile = handler.handle_plugin_response(&response, &mut queue).unwrap().unwrap();
+        assert_eq!(profile.address.as_deref(), Some("127.0.0.1"));
+        assert!(handler.is_finished());
+        data[0] ^= 1;
+        let response = LoginPluginResponseLC2S { message_id: 0, successful: true, data: &data };
+        let mut handler = LoginHandler::new(provider, &info);
+        handler.handle_start(&start, &mut queue).unwrap();
+        assert!(handler.handle_plugin_response(&response, &mut queue).is_err());
+    }
+
+    #[test]
+    fn offline_profile_test() {
+        let uuid = offline_uuid("Notch");
+        assert_eq!(uuid.get_version_num(), 3);
+        assert_ne!(uuid, offline_uuid("notch"));
+        let info = connection_info();
+        let start = LoginStartLC2S { name: "Notch", uuid: None };
+        let mut queue = PacketQueue::new();
+        let mut handler = LoginHandler::new(ForwardingMode::None.provider(None), &info);
+        assert_eq!(handler.handle_start(&start, &mut queue).unwrap().map(|profile| profile.uuid), Some(uuid));
+        assert!(handler.is_finished());
+        // BungeeCord didn't forward the player
+        let mut handler = LoginHandler::new(ForwardingMode::BungeeCord.provider(None), &info);
+        assert!(handler.handle_start(&start, &mut queue).is_err());
+    }
+
+    #[test]
+    fn session_outage_test() {
+        let profile = LoginProfile {
+            uuid: Uuid::from_u128(1),
+            name: "Notch".into(),
+            properties: Vec::new(),
+            address: None,
+        };
+        let mut authenticator = SessionAuthenticator::new(SessionOutageConfig {
+            fallbacks: vec![
+                SessionFallback::CachedProfile,
+                SessionFallback::OfflineAllowList(HashSet::from(["jeb_".to_string()])),
+            ],
+            cache_lifetime: Duration::from_secs(60),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let unreachable = SessionAuthResult::Unreachable;
+        assert_eq!(authenticator.resolve("Notch", None, unreachable.clone(), now), Err(SESSION_OUTAGE_MESSAGE.into()));
+        assert_eq!(authenticator.resolve("Notch", None, SessionAuthResult::Authenticated(profile.clone()), now), Ok(profile.clone()));
+        assert_eq!(authenticator.resolve("notch", Some(profile.uuid), unreachable.clone(), now), Ok(profile.clone()));
+        assert!(authenticator.resolve("Notch", Some(Uuid::from_u128(2)), unreachable.clone(), now).is_err());
+        // The cached profile is not found without the uuid of the client
+        assert_eq!(authenticator.resolve("Notch", None, unreachable.clone(), now), Err(SESSION_OUTAGE_MESSAGE.into()));
+        // The name must be the name of the cached profile
+        assert_eq!(authenticator.resolve("jeb_", Some(profile.uuid), unreachable.clone(), now).unwrap().uuid, offline_uuid("jeb_"));
+        assert_eq!(authenticator.resolve("jeb_", None, unreachable.clone(), now).unwrap().uuid, offline_uuid("jeb_"));
+        assert_eq!(authenticator.resolve("Notch", None, SessionAuthResult::Unverified, now), Err(UNVERIFIED_USERNAME_MESSAGE.into()));
+        let later = now + Duration::from_secs(60);
+        assert!(authenticator.resolve("Notch", Some(profile.uuid), unreachable, later).is_err());
+        authenticator.clear_expired(later);
+        assert_eq!(authenticator.cached_sessions(), 0);
+    }
+
+    #[test]
+    fn property_verification_test() {
+        let key = RsaPublicKey::new(rsa::BigUint::from_bytes_be(&[0xff; 128]), rsa::BigUint::from(65537u32)).unwrap();
+        let verification = PropertyVerification {
+            verifier: PropertyVerifier::new(key),
+            policy: UnverifiedPropertyPolicy::Strip,
+        };
+        let properties = vec![
+            OwnedProperty { name: "textures".into(), value: "e30=".into(), signature: None },
+            OwnedProperty { name: "textures".into(), value: "e30=".into(), signature: Some(base64::encode([1; 128])) },
+        ];
+        assert!(!verification.verifier.verify(&properties[1]));
+        assert_eq!(verification.apply(properties.clone()).unwrap(), vec![]);
+        let reject = PropertyVerification { policy: UnverifiedPropertyPolicy::Reject, ..verification.clone() };
+        assert!(reject.apply(properties.clone()).is_err());
+        assert!(reject.apply(Vec::new()).is_ok());
+        let keep = PropertyVerification { policy: UnverifiedPropertyPolicy::Keep, ..verification };
+        assert_eq!(keep.apply(properties.clone()).unwrap(), properties);
+    }
+
+    const TEST_KEY_N: &'static str = "d2304e45a6caea22b31970674335e758515a082b4e2347f69106c956ac876b84172010f49017c3b79cc72358d03080fead5da70a655c17591b673c4e7f6d6b2e485546c77643a3061a1c2ef9baead9395f1e4518a5b188a5687d0d4c683879031c57d6b7f4e2ac772e479c97eb2883f87a4fdad49d59441b785057acf9f5ebc3";
+    const TEST_KEY_D: &'static str = "cc95220a5f6f4ff79855a177910ead6fe436fa8e940e7924004317c39a487118ef9d518389a0e7ea16da29886f93566b0cf983f5f5746e7157dfe2ee10cfc2aa54b0f060422fcc36b94daac5a53c31070610cbd76aac9bd102dad57b8f348f665a7039e1941d62a406d4d5513722f4768e4b5ca910eeaa4219784bb0b0af04c1";
+    const TEST_KEY_P: &'static str = "dc9bef5f9f81d73ac9b7f1bbed89e362556a1dfed526c9f7a8d6ecd01e9e24a88b0888f1a8da9c4da222a5910c05f4fc22363de704672e2dc290290f2f9d1223";
+    const TEST_KEY_Q: &'static str = "f3e86b9d8ec893aac4c717c3bd640470738990904d9e4469c3054a9217b7c8c0fe7387ab95bf25d0e7c24d96891317d8cdcef68bbd8e3002f0e87d87519f49e1";
+
+    fn hex(value: &str) -> BigUint {
+        BigUint::parse_bytes(value.as_bytes(), 16).unwrap()
+    }
+
+    /// PKCS#1 v1.5 encryption with the constant padding
+    fn encrypt(key: &ServerKey, data: &[u8]) -> Vec<u8> {
+        let public_key = RsaPublicKey::from(&key.private_key);
+        let length = (public_key.n().bits() + 7) / 8;
+        let mut message = vec![0, 2];
+        message.resize(length - data.len() - 1, 0x42);
+        message.push(0);
+        message.extend_from_slice(data);
+        let encrypted = BigUint::from_bytes_be(&message).modpow(public_key.e(), public_key.n()).to_bytes_be();
+        let mut result = vec![0; length - encrypted.len()];
+        result.extend_from_slice(&encrypted);
+        result
+    }
+
+    #[test]
+    fn server_hash_test() {
+        // Hashes of names from the protocol documentation
+        let hash = |name: &str| server_hash(name, &[], &[]);
+        assert_eq!(hash("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(hash("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(hash("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"tok"));
+    }
+
+    fn read_login(mut frame: &[u8]) -> LoginC2SPacket {
+        match Packet::read(ProtocolPacketState::Login, ProtocolPacketBound::Server, &mut frame).unwrap() {
+            Some(Packet::LoginC2S(packet)) => {
+                assert!(frame.is_empty());
+                packet
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encryption_test() {
+        let private_key = RsaPrivateKey::from_components(
+            hex(TEST_KEY_N), BigUint::from(65537u32), hex(TEST_KEY_D), vec![hex(TEST_KEY_P), hex(TEST_KEY_Q)],
+        );
+        let key = Arc::new(ServerKey::new(private_key).unwrap());
+        let info = connection_info();
+        // Frame of the vanilla client: the name and the uuid of the profile
+        let mut start_frame = vec![0x00, 0x05];
+        start_frame.extend_from_slice(b"Notch");
+        start_frame.push(0x01);
+        start_frame.extend_from_slice(Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap().as_bytes());
+        let start = match read_login(&start_frame) {
+            LoginC2SPacket::LoginStart(start) => start,
+            other => panic!("Unexpected packet {:?}", other),
+        };
+        assert_eq!(start.name, "Notch");
+        let secret = [7; SHARED_SECRET_LENGTH];
+        let login = |queue: &mut PacketQueue| {
+            let mut handler = LoginHandler::new(Arc::new(OnlineProfiles { key: key.clone() }), &info);
+            assert_eq!(handler.handle_start(&start, queue).unwrap(), None);
+            handler
+        };
+        let mut queue = PacketQueue::new();
+        let mut handler = login(&mut queue);
+        // Tokens are random for every login
+        let token = match &handler.stage {
+            LoginStage::Encryption { challenge, .. } => challenge.verify_token().to_vec(),
+            _ => panic!("Expected encryption stage"),
+        };
+        let wrong_token = token.iter().map(|byte| byte ^ 1).collect::<Vec<_>>();
+        let shared_secret = encrypt(&key, &secret);
+        let wrong = EncryptionResponseLC2S { shared_secret: &shared_secret, verify_token: &encrypt(&key, &wrong_token) };
+        assert!(handler.handle_encryption_response(&wrong).is_err());
+        // The token can't be retried after the failure
+        let verify_token = encrypt(&key, &token);
+        let response = EncryptionResponseLC2S { shared_secret: &shared_secret, verify_token: &verify_token };
+        assert!(handler.handle_encryption_response(&response).is_err());
+        let mut handler = login(&mut queue);
+        let token = match &handler.stage {
+            LoginStage::Encryption { challenge, .. } => challenge.verify_token().to_vec(),
+            _ => panic!("Expected encryption stage"),
+        };
+        let verify_token = encrypt(&key, &token);
+        let mut response_frame = vec![0x01];
+        for bytes in [&shared_secret, &verify_token] {
+            let (length, size) = encode_var_int(bytes.len() as u32);
+            response_frame.extend_from_slice(&length[..size]);
+            response_frame.extend_from_slice(bytes);
+        }
+        let response = match read_login(&response_frame) {
+            LoginC2SPacket::EncryptionResponse(response) => response,
+            other => panic!("Unexpected packet {:?}", other),
+        };
+        let result = handler.handle_encryption_response(&response).unwrap();
+        assert_eq!(result.shared_secret, secret);
+        assert_eq!(result.server_hash, server_hash("", &secret, &key.public_key));
+        // Replayed response
+        assert!(handler.handle_encryption_response(&response).is_err());
+        let profile = LoginProfile { uuid: Uuid::from_u128(1), name: result.name, properties: Vec::new(), address: None };
+        assert_eq!(handler.finish_authentication(profile.clone(), &mut queue).unwrap(), Some(profile));
+        assert!(handler.is_finished());
+    }
+}
@@ -1,7 +1,77 @@
 #![feature(generic_const_exprs)]
 
-pub mod protocol;
-pub mod nbt;
+pub use bird_packets as protocol;
+pub use bird_packets::nbt;
+pub mod connection;
+pub mod world;
+pub mod entity;
+pub mod inventory;
+pub mod chat;
+pub mod login;
+pub mod rcon;
+pub mod random;
+pub mod weather;
+pub mod timings;
+pub mod status;
+pub mod query;
+pub mod version;
+pub mod registry;
+pub mod events;
+pub mod channels;
+pub mod resource_pack;
+pub mod join_queue;
+pub mod codec;
+pub mod player_list;
+pub mod border;
+pub mod cooldown;
+pub mod book;
+pub mod jukebox;
+pub mod item_container;
+pub mod menu;
+pub mod trace;
+pub mod block_state;
+pub mod metrics;
+pub mod view_distance;
+pub mod block_changes;
+pub mod advancements;
+pub mod lectern;
+pub mod map;
+pub mod cooking;
+pub mod trade;
+pub mod statistics;
+pub mod bell;
+pub mod recipes;
+pub mod limits;
+pub mod listener;
+pub mod hopper;
+pub mod crafting;
+pub mod custom_item;
+pub mod storage;
+pub mod gamemode;
+pub mod config;
+pub mod favicon;
+pub mod teleport_request;
+pub mod chunk_ticket;
+pub mod debug;
+pub mod light;
+pub mod instrument;
+pub mod preload;
+pub mod tick;
+pub mod ecs;
+pub mod pregen;
+pub mod death_location;
+pub mod movement;
+pub mod generator;
+pub mod arena;
+pub mod playerdata;
+pub mod viewers;
+pub mod synced;
+pub mod interaction;
+pub mod version_gate;
+#[cfg(feature = "metrics")]
+pub mod metrics_registry;
+#[cfg(test)]
+pub mod test_util;
 
 fn main() {
     println!("Hello, world!");
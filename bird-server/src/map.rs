@@ -0,0 +1,242 @@
+use std::borrow::Cow;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+pub const MAP_SIZE: usize = 128;
+
+/// Rgb of vanilla map colors, the color id is `base * 4 + shade`. The first color is transparent
+pub const MAP_BASE_COLORS: &'static [u32] = &[
+    0x000000, 0x7FB238, 0xF7E9A3, 0xC7C7C7, 0xFF0000, 0xA0A0FF, 0xA7A7A7, 0x007C00,
+    0xFFFFFF, 0xA4A8B8, 0x976D4D, 0x707070, 0x4040FF, 0x8F7748, 0xFFFCF5, 0xD87F33,
+    0xB24CD8, 0x6699D8, 0xE5E533, 0x7FCC19, 0xF27FA5, 0x4C4C4C, 0x999999, 0x4C7F99,
+    0x7F3FB2, 0x334CB2, 0x664C33, 0x667F33, 0x993333, 0x191919, 0xFAEE4D, 0x5CDBD5,
+    0x4A80FF, 0x00D93A, 0x815631, 0x700200, 0xD1B1A1, 0x9F5224, 0x95576C, 0x706C8A,
+    0xBA8524, 0x677535, 0xA04D4E, 0x392923, 0x876B62, 0x575C5C, 0x7A4958, 0x4C3E5C,
+    0x4C3223, 0x4C522A, 0x8E3C2E, 0x251610, 0xBD3031, 0x943F61, 0x5C191D, 0x167E86,
+    0x3A8E8C, 0x562C3E, 0x14B485, 0x646464, 0xD8AF93, 0x7FA796,
+];
+
+/// Multipliers of shades out of 255
+const MAP_SHADES: [u32; 4] = [180, 220, 255, 135];
+
+/// Rgb of the map color as the client renders it, `None` for transparent and unknown colors
+pub fn map_color_rgb(color: u8) -> Option<u32> {
+    let base = *MAP_BASE_COLORS.get(color as usize / 4)?;
+    if color < 4 {
+        return None;
+    }
+    let shade = MAP_SHADES[color as usize % 4];
+    let channel = |offset: u32| (base >> offset & 0xFF) * shade / 255 << offset;
+    Some(channel(16) | channel(8) | channel(0))
+}
+
+/// The closest map color to the rgb
+pub fn nearest_map_color(rgb: u32) -> u8 {
+    let distance = |other: u32| -> i32 {
+        let channel = |offset: u32| (rgb >> offset & 0xFF) as i32 - (other >> offset & 0xFF) as i32;
+        channel(16).pow(2) + channel(8).pow(2) + channel(0).pow(2)
+    };
+    (4..MAP_BASE_COLORS.len() as u32 * 4)
+        .map(|color| color as u8)
+        .min_by_key(|&color| distance(map_color_rgb(color).unwrap()))
+        .unwrap()
+}
+
+/// Rectangle of changed pixels, maximums are inclusive
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct DirtyRect {
+    min_x: usize,
+    min_z: usize,
+    max_x: usize,
+    max_z: usize,
+}
+
+/// Pixels of a map which are drawn by the server, only changed rectangles are sent like vanilla does
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapCanvas {
+    pub map_id: i32,
+    pub scale: i8,
+    pub locked: bool,
+    pixels: Box<[u8; MAP_SIZE * MAP_SIZE]>,
+    dirty: Option<DirtyRect>,
+    icons: Vec<MapDataIcon<'static>>,
+    icons_dirty: bool,
+}
+
+impl MapCanvas {
+    pub fn new(map_id: i32) -> Self {
+        Self {
+            map_id,
+            scale: 0,
+            locked: true,
+            pixels: Box::new([0; MAP_SIZE * MAP_SIZE]),
+            dirty: None,
+            icons: Vec::new(),
+            icons_dirty: false,
+        }
+    }
+
+    pub fn pixels(&self) -> &[u8; MAP_SIZE * MAP_SIZE] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, z: usize) -> Option<u8> {
+        match x < MAP_SIZE && z < MAP_SIZE {
+            true => Some(self.pixels[z * MAP_SIZE + x]),
+            false => None,
+        }
+    }
+
+    /// Pixels outside of the canvas are ignored
+    pub fn set(&mut self, x: usize, z: usize, color: u8) {
+        if x >= MAP_SIZE || z >= MAP_SIZE || self.pixels[z * MAP_SIZE + x] == color {
+            return;
+        }
+        self.pixels[z * MAP_SIZE + x] = color;
+        self.dirty = Some(match self.dirty {
+            Some(rect) => DirtyRect {
+                min_x: rect.min_x.min(x),
+                min_z: rect.min_z.min(z),
+                max_x: rect.max_x.max(x),
+                max_z: rect.max_z.max(z),
+            },
+            None => DirtyRect { min_x: x, min_z: z, max_x: x, max_z: z },
+        });
+    }
+
+    pub fn fill(&mut self, x: usize, z: usize, width: usize, height: usize, color: u8) {
+        for pixel_z in z..(z + height).min(MAP_SIZE) {
+            for pixel_x in x..(x + width).min(MAP_SIZE) {
+                self.set(pixel_x, pixel_z, color);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(0, 0, MAP_SIZE, MAP_SIZE, 0);
+    }
+
+    /// Draws argb pixels of the image row by row with the closest map colors, pixels with alpha below half are skipped
+    pub fn draw_image(&mut self, x: usize, z: usize, width: usize, argb: &[u32]) {
+        if width == 0 {
+            return;
+        }
+        for (index, &pixel) in argb.iter().enumerate() {
+            if pixel >> 24 >= 0x80 {
+                self.set(x + index % width, z + index / width, nearest_map_color(pixel & 0xFFFFFF));
+            }
+        }
+    }
+
+    /// Icons are sent with the next update
+    pub fn set_icons(&mut self, icons: Vec<MapDataIcon<'static>>) {
+        self.icons = icons;
+        self.icons_dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.is_some() || self.icons_dirty
+    }
+
+    /// Marks the whole canvas as changed, it is sent to players which started to see the map
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = Some(DirtyRect { min_x: 0, min_z: 0, max_x: MAP_SIZE - 1, max_z: MAP_SIZE - 1 });
+        self.icons_dirty = true;
+    }
+
+    fn patch(&self, rect: DirtyRect) -> Vec<u8> {
+        let mut data = Vec::with_capacity((rect.max_x - rect.min_x + 1) * (rect.max_z - rect.min_z + 1));
+        for z in rect.min_z..=rect.max_z {
+            data.extend_from_slice(&self.pixels[z * MAP_SIZE + rect.min_x..=z * MAP_SIZE + rect.max_x]);
+        }
+        data
+    }
+
+    /// Sends the whole canvas without changing the dirty state
+    pub fn send_full<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&MapDataPS2C {
+            map_id: self.map_id,
+            scale: self.scale,
+            locked: self.locked,
+            icons: Some(Cow::Borrowed(&self.icons)),
+            col_rows: Some(MapDataColRows { columns: MAP_SIZE as u8, rows: MAP_SIZE as u8, x: 0, z: 0, data: self.pixels.as_slice() }),
+        })
+    }
+
+    /// Sends the changed rectangle and icons, returns false if nothing is changed
+    pub fn flush<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<bool> {
+        if !self.is_dirty() {
+            return Ok(false);
+        }
+        let data = self.dirty.map(|rect| (rect, self.patch(rect)));
+        sender.send_packet(&MapDataPS2C {
+            map_id: self.map_id,
+            scale: self.scale,
+            locked: self.locked,
+            icons: match self.icons_dirty {
+                true => Some(Cow::Borrowed(&self.icons)),
+                false => None,
+            },
+            col_rows: data.as_ref().map(|(rect, data)| MapDataColRows {
+                columns: (rect.max_x - rect.min_x + 1) as u8,
+                rows: (rect.max_z - rect.min_z + 1) as u8,
+                x: rect.min_x as i8,
+                z: rect.min_z as i8,
+                data,
+            }),
+        })?;
+        self.dirty = None;
+        self.icons_dirty = false;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn map_canvas_test() {
+        assert_eq!(map_color_rgb(2), None);
+        assert_eq!(map_color_rgb(8 * 4 + 2), Some(0xFFFFFF));
+        assert_eq!(nearest_map_color(0xFEFEFE), 8 * 4 + 2);
+        assert_eq!(nearest_map_color(0xFF0000), 4 * 4 + 2);
+
+        let mut canvas = MapCanvas::new(3);
+        let mut queue = PacketQueue::new();
+        assert!(!canvas.flush(&mut queue).unwrap());
+        canvas.set(10, 20, 34);
+        canvas.fill(12, 21, 2, 2, 35);
+        canvas.draw_image(0, 0, 2, &[0xFFFF0000, 0x00FFFFFF]);
+        assert_eq!(canvas.get(0, 0), Some(4 * 4 + 2));
+        assert_eq!(canvas.get(1, 0), Some(0));
+        canvas.set(0, 0, 0);
+        assert!(canvas.flush(&mut queue).unwrap());
+        let packets = queue.take();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut packets[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::MapData(packet))) => {
+                assert_eq!(packet.icons, None);
+                let patch = packet.col_rows.unwrap();
+                assert_eq!((patch.columns, patch.rows, patch.x, patch.z), (14, 23, 0, 0));
+                assert_eq!(patch.data[20 * 14 + 10], 34);
+                assert_eq!(patch.data[22 * 14 + 13], 35);
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+
+        canvas.set_icons(Vec::new());
+        assert!(canvas.flush(&mut queue).unwrap());
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut queue.take()[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::MapData(packet))) => {
+                assert_eq!(packet.icons.map(|icons| icons.len()), Some(0));
+                assert_eq!(packet.col_rows, None);
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        assert!(!canvas.is_dirty());
+    }
+}
@@ -0,0 +1,599 @@
+use fastnbt::Value;
+use bird_data::{BlockData, ItemData};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::inventory::{ContainerType, InventoryView};
+use crate::item_container::{read_nbt, write_nbt, Compound};
+use crate::protocol::*;
+
+pub const STONECUTTER_INPUT_SLOT: usize = 0;
+pub const STONECUTTER_RESULT_SLOT: usize = 1;
+/// Property of the stonecutter with the index of the selected recipe
+pub const STONECUTTER_SELECTED_PROPERTY: ContainerProperty = ContainerProperty::StonecutterSelectedRecipe;
+const TRIM_TAG: &'static str = "Trim";
+const TRIM_TEMPLATE_SUFFIX: &'static str = "_armor_trim_smithing_template";
+
+/// Items which are used as trim materials and names of the materials
+pub const TRIM_MATERIALS: &'static [(&'static str, &'static str)] = &[
+    ("quartz", "quartz"),
+    ("iron_ingot", "iron"),
+    ("netherite_ingot", "netherite"),
+    ("redstone", "redstone"),
+    ("copper_ingot", "copper"),
+    ("gold_ingot", "gold"),
+    ("emerald", "emerald"),
+    ("diamond", "diamond"),
+    ("lapis_lazuli", "lapis"),
+    ("amethyst_shard", "amethyst"),
+];
+
+fn item_name(item_id: i32) -> anyhow::Result<&'static str> {
+    ItemData::from_id(item_id as u32)
+        .map(|item| item.name)
+        .ok_or_else(|| anyhow::Error::msg("Unknown item"))
+}
+
+/// Description id of the item, by which the client sorts recipes
+fn description_id(item_id: i32) -> String {
+    match ItemData::from_id(item_id as u32) {
+        Some(item) if BlockData::from_name(item.name).is_some() => format!("block.minecraft.{}", item.name),
+        Some(item) => format!("item.minecraft.{}", item.name),
+        None => String::new(),
+    }
+}
+
+fn container_slot(view: &InventoryView, slot: usize) -> Option<OwnedSlot> {
+    view.container()
+        .and_then(|container| container.inventory.get(slot))
+        .cloned()
+}
+
+fn is_opened(view: &InventoryView, ty: ContainerType) -> bool {
+    view.container().map_or(false, |container| container.ty == ty)
+}
+
+/// Items which are accepted by a slot of the recipe
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Ingredient {
+    items: Vec<i32>,
+}
+
+impl Ingredient {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn of(items: &[i32]) -> Self {
+        Self { items: items.to_vec() }
+    }
+
+    pub fn items(&self) -> &[i32] {
+        &self.items
+    }
+
+    /// The empty ingredient is matched only by an empty slot
+    pub fn test(&self, item: Option<&OwnedSlot>) -> bool {
+        match item {
+            Some(item) => self.items.contains(&item.item_id),
+            None => self.items.is_empty(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct StonecuttingRecipe {
+    pub id: String,
+    pub ingredient: Ingredient,
+    pub result: i32,
+    pub count: i8,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SmithingRecipe {
+    /// Replaces the base with the result keeping its nbt, like the netherite upgrade
+    Transform {
+        id: String,
+        template: Ingredient,
+        base: Ingredient,
+        addition: Ingredient,
+        result: i32,
+    },
+    /// Adds the trim with the pattern of the template and the material of the addition to the armor
+    Trim {
+        id: String,
+        template: Ingredient,
+        base: Ingredient,
+        addition: Ingredient,
+    },
+}
+
+impl SmithingRecipe {
+    pub fn id(&self) -> &str {
+        match self {
+            SmithingRecipe::Transform { id, .. } | SmithingRecipe::Trim { id, .. } => id,
+        }
+    }
+
+    /// Legacy smithing tables don't have the template slot, so only recipes with the empty template match there
+    pub fn matches(&self, template: Option<&OwnedSlot>, base: Option<&OwnedSlot>, addition: Option<&OwnedSlot>) -> bool {
+        let (recipe_template, recipe_base, recipe_addition) = match self {
+            SmithingRecipe::Transform { template, base, addition, .. } |
+            SmithingRecipe::Trim { template, base, addition, .. } => (template, base, addition),
+        };
+        recipe_template.test(template) && recipe_base.test(base) && recipe_addition.test(addition)
+    }
+
+    /// Result of the recipe, the trim is not applied again if the armor already has the same trim
+    pub fn assemble(&self, template: Option<&OwnedSlot>, base: &OwnedSlot, addition: Option<&OwnedSlot>) -> anyhow::Result<Option<OwnedSlot>> {
+        match self {
+            SmithingRecipe::Transform { result, .. } => Ok(Some(OwnedSlot { item_id: *result, item_count: 1, nbt: base.nbt.clone() })),
+            SmithingRecipe::Trim { .. } => {
+                let (template, addition) = match (template, addition) {
+                    (Some(template), Some(addition)) => (template, addition),
+                    _ => return Ok(None),
+                };
+                let template_name = item_name(template.item_id)?;
+                let addition_name = item_name(addition.item_id)?;
+                let pattern = template_name.strip_suffix(TRIM_TEMPLATE_SUFFIX).unwrap_or(template_name);
+                let material = TRIM_MATERIALS.iter()
+                    .find(|(item, _)| *item == addition_name)
+                    .map_or(addition_name, |(_, material)| *material);
+                let mut trim = Compound::new();
+                trim.insert("material".into(), Value::String(format!("minecraft:{}", material)));
+                trim.insert("pattern".into(), Value::String(format!("minecraft:{}", pattern)));
+                let mut nbt = read_nbt(&base.nbt)?;
+                let trim = Value::Compound(trim);
+                if nbt.get(TRIM_TAG) == Some(&trim) {
+                    return Ok(None);
+                }
+                nbt.insert(TRIM_TAG.into(), trim);
+                Ok(Some(OwnedSlot { item_id: base.item_id, item_count: 1, nbt: write_nbt(&nbt)? }))
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum CraftingRecipe {
+    /// Ingredients are given row by row, empty ingredients are empty cells.
+    /// The pattern matches anywhere in the grid and mirrored
+    Shaped {
+        id: String,
+        width: usize,
+        height: usize,
+        ingredients: Vec<Ingredient>,
+        /// The result keeps its nbt, so custom items can be crafted
+        result: OwnedSlot,
+    },
+    Shapeless {
+        id: String,
+        ingredients: Vec<Ingredient>,
+        result: OwnedSlot,
+    },
+}
+
+/// Assigns every item to a different ingredient
+fn matches_shapeless(ingredients: &[Ingredient], items: &[&OwnedSlot], used: &mut [bool]) -> bool {
+    let (item, rest) = match items.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for (index, ingredient) in ingredients.iter().enumerate() {
+        if !used[index] && ingredient.test(Some(item)) {
+            used[index] = true;
+            if matches_shapeless(ingredients, rest, used) {
+                return true;
+            }
+            used[index] = false;
+        }
+    }
+    false
+}
+
+impl CraftingRecipe {
+    pub fn id(&self) -> &str {
+        match self {
+            CraftingRecipe::Shaped { id, .. } | CraftingRecipe::Shapeless { id, .. } => id,
+        }
+    }
+
+    pub fn result(&self) -> OwnedSlot {
+        match self {
+            CraftingRecipe::Shaped { result, .. } | CraftingRecipe::Shapeless { result, .. } => result.clone(),
+        }
+    }
+
+    /// Whether the recipe can be crafted in the square grid with the width
+    pub fn fits(&self, grid_width: usize) -> bool {
+        match self {
+            CraftingRecipe::Shaped { width, height, .. } => *width <= grid_width && *height <= grid_width,
+            CraftingRecipe::Shapeless { ingredients, .. } => ingredients.len() <= grid_width * grid_width,
+        }
+    }
+
+    /// Ingredients of every cell of the grid when the recipe is placed by the recipe book, shaped recipes are put in the corner
+    pub fn cells(&self, grid_width: usize) -> Vec<Ingredient> {
+        let mut cells = vec![Ingredient::empty(); grid_width * grid_width];
+        match self {
+            CraftingRecipe::Shaped { width, height, ingredients, .. } => {
+                for y in 0..(*height).min(grid_width) {
+                    for x in 0..(*width).min(grid_width) {
+                        cells[y * grid_width + x] = ingredients[y * width + x].clone();
+                    }
+                }
+            }
+            CraftingRecipe::Shapeless { ingredients, .. } => {
+                for (cell, ingredient) in cells.iter_mut().zip(ingredients) {
+                    *cell = ingredient.clone();
+                }
+            }
+        }
+        cells
+    }
+
+    /// `grid` is the square crafting grid row by row
+    pub fn matches(&self, grid: &[Option<OwnedSlot>], grid_width: usize) -> bool {
+        let items: Vec<(usize, &OwnedSlot)> = grid.iter()
+            .enumerate()
+            .filter_map(|(index, item)| item.as_ref().map(|item| (index, item)))
+            .collect();
+        match self {
+            CraftingRecipe::Shaped { width, height, ingredients, .. } => {
+                let (min_x, min_y) = match (
+                    items.iter().map(|(index, _)| index % grid_width).min(),
+                    items.iter().map(|(index, _)| index / grid_width).min(),
+                ) {
+                    (Some(min_x), Some(min_y)) => (min_x, min_y),
+                    _ => return false,
+                };
+                if items.iter().any(|(index, _)| index % grid_width >= min_x + width || index / grid_width >= min_y + height) {
+                    return false;
+                }
+                if min_x + width > grid_width || min_y + height > grid_width {
+                    return false;
+                }
+                [false, true].into_iter().any(|mirrored| (0..*height).all(|y| (0..*width).all(|x| {
+                    let pattern_x = if mirrored { width - 1 - x } else { x };
+                    ingredients[y * width + pattern_x].test(grid[(min_y + y) * grid_width + min_x + x].as_ref())
+                })))
+            }
+            CraftingRecipe::Shapeless { ingredients, .. } => {
+                let items: Vec<&OwnedSlot> = items.into_iter().map(|(_, item)| item).collect();
+                items.len() == ingredients.len() && matches_shapeless(ingredients, &items, &mut vec![false; ingredients.len()])
+            }
+        }
+    }
+}
+
+/// Recipes of the crafting table, the stonecutter and the smithing table
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RecipeBook {
+    crafting: Vec<CraftingRecipe>,
+    stonecutting: Vec<StonecuttingRecipe>,
+    smithing: Vec<SmithingRecipe>,
+}
+
+impl RecipeBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_crafting(&mut self, recipe: CraftingRecipe) -> &mut Self {
+        self.crafting.push(recipe);
+        self
+    }
+
+    pub fn add_stonecutting(&mut self, recipe: StonecuttingRecipe) -> &mut Self {
+        self.stonecutting.push(recipe);
+        self
+    }
+
+    pub fn add_smithing(&mut self, recipe: SmithingRecipe) -> &mut Self {
+        self.smithing.push(recipe);
+        self
+    }
+
+    pub fn crafting_recipes(&self) -> &[CraftingRecipe] {
+        &self.crafting
+    }
+
+    pub fn stonecutting_recipes(&self) -> &[StonecuttingRecipe] {
+        &self.stonecutting
+    }
+
+    pub fn smithing_recipes(&self) -> &[SmithingRecipe] {
+        &self.smithing
+    }
+
+    pub fn crafting_for(&self, grid: &[Option<OwnedSlot>], grid_width: usize) -> Option<&CraftingRecipe> {
+        self.crafting.iter().find(|recipe| recipe.matches(grid, grid_width))
+    }
+
+    pub fn stonecutting(&self, id: &str) -> Option<&StonecuttingRecipe> {
+        self.stonecutting.iter().find(|recipe| recipe.id == id)
+    }
+
+    /// Recipes of the input in the order of the client list, which is sorted by the result item
+    pub fn stonecutting_for(&self, input: &OwnedSlot) -> Vec<&StonecuttingRecipe> {
+        let mut recipes: Vec<&StonecuttingRecipe> = self.stonecutting.iter()
+            .filter(|recipe| recipe.ingredient.test(Some(input)))
+            .collect();
+        recipes.sort_by_cached_key(|recipe| description_id(recipe.result));
+        recipes
+    }
+
+    pub fn smithing_for(&self, template: Option<&OwnedSlot>, base: Option<&OwnedSlot>, addition: Option<&OwnedSlot>) -> Option<&SmithingRecipe> {
+        self.smithing.iter().find(|recipe| recipe.matches(template, base, addition))
+    }
+}
+
+/// State of the opened stonecutter, the client builds the same recipe list by itself
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct StonecutterMenu {
+    /// Item which the recipes were found for
+    input: Option<i32>,
+    /// Ids of available recipes in the order of the client list
+    recipes: Vec<String>,
+    selected: Option<usize>,
+}
+
+impl StonecutterMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn recipes(&self) -> &[String] {
+        &self.recipes
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Selects the recipe by its index in the list, returns false if the button is not valid
+    pub fn handle_button<S: PacketSender>(&mut self, view: &mut InventoryView, packet: &ClickContainerButtonPC2S, book: &RecipeBook, sender: &mut S) -> anyhow::Result<bool> {
+        if !is_opened(view, ContainerType::Stonecutter) || packet.window_id as u8 != view.window_id() {
+            return Ok(false);
+        }
+        let index = match usize::try_from(packet.button_id) {
+            Ok(index) if index < self.recipes.len() => index,
+            _ => return Ok(false),
+        };
+        self.selected = Some(index);
+        view.set_property(STONECUTTER_SELECTED_PROPERTY, index as i16, sender)?;
+        self.update_result(view, book, sender)?;
+        Ok(true)
+    }
+
+    /// Applies the click, one input item is consumed when the result is taken
+    pub fn handle_click<S: PacketSender>(&mut self, view: &mut InventoryView, packet: &ClickContainerPC2S, book: &RecipeBook, sender: &mut S) -> anyhow::Result<Vec<OwnedSlot>> {
+        if !is_opened(view, ContainerType::Stonecutter) {
+            return view.handle_click(packet, sender);
+        }
+        let result = container_slot(view, STONECUTTER_RESULT_SLOT);
+        let dropped = view.handle_click(packet, sender)?;
+        if result.is_some() && container_slot(view, STONECUTTER_RESULT_SLOT) != result {
+            let input = container_slot(view, STONECUTTER_INPUT_SLOT).and_then(|mut input| {
+                input.item_count -= 1;
+                (input.item_count > 0).then_some(input)
+            });
+            view.set_slot(STONECUTTER_INPUT_SLOT, input, sender)?;
+        }
+        self.update(view, book, sender)?;
+        Ok(dropped)
+    }
+
+    /// Rebuilds the recipe list if the input item is changed and updates the result
+    pub fn update<S: PacketSender>(&mut self, view: &mut InventoryView, book: &RecipeBook, sender: &mut S) -> anyhow::Result<()> {
+        let input = container_slot(view, STONECUTTER_INPUT_SLOT);
+        let input_id = input.as_ref().map(|input| input.item_id);
+        if input_id != self.input {
+            self.input = input_id;
+            self.recipes = input
+                .map(|input| book.stonecutting_for(&input).iter().map(|recipe| recipe.id.clone()).collect())
+                .unwrap_or_default();
+            self.selected = None;
+        }
+        self.update_result(view, book, sender)
+    }
+
+    fn update_result<S: PacketSender>(&self, view: &mut InventoryView, book: &RecipeBook, sender: &mut S) -> anyhow::Result<()> {
+        let result = match (self.selected, self.input) {
+            (Some(index), Some(_)) => book.stonecutting(&self.recipes[index])
+                .map(|recipe| OwnedSlot::new(recipe.result, recipe.count)),
+            _ => None,
+        };
+        match container_slot(view, STONECUTTER_RESULT_SLOT) == result {
+            true => Ok(()),
+            false => view.set_slot(STONECUTTER_RESULT_SLOT, result, sender),
+        }
+    }
+}
+
+/// Slots of the smithing table
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SmithingLayout {
+    pub template: Option<usize>,
+    pub base: usize,
+    pub addition: usize,
+    pub result: usize,
+}
+
+/// Smithing table of the supported protocol, it doesn't have the template slot
+pub const LEGACY_SMITHING_LAYOUT: SmithingLayout = SmithingLayout { template: None, base: 0, addition: 1, result: 2 };
+/// Smithing table of 1.20 with the template slot for trims and the netherite upgrade
+pub const TEMPLATE_SMITHING_LAYOUT: SmithingLayout = SmithingLayout { template: Some(0), base: 1, addition: 2, result: 3 };
+
+impl SmithingLayout {
+    /// Layout of the smithing window with the given amount of slots
+    pub const fn for_size(size: usize) -> Option<Self> {
+        match size {
+            3 => Some(LEGACY_SMITHING_LAYOUT),
+            4 => Some(TEMPLATE_SMITHING_LAYOUT),
+            _ => None,
+        }
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = usize> {
+        self.template.into_iter().chain([self.base, self.addition])
+    }
+}
+
+/// State of the opened smithing table
+#[derive(Clone, PartialEq, Debug)]
+pub struct SmithingMenu {
+    layout: SmithingLayout,
+    recipe: Option<String>,
+}
+
+impl Default for SmithingMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SmithingMenu {
+    pub fn new() -> Self {
+        Self {
+            layout: SmithingLayout::for_size(ContainerType::Smithing.size()).unwrap_or(LEGACY_SMITHING_LAYOUT),
+            recipe: None,
+        }
+    }
+
+    pub fn layout(&self) -> SmithingLayout {
+        self.layout
+    }
+
+    /// Id of the recipe which matches the inputs
+    pub fn recipe(&self) -> Option<&str> {
+        self.recipe.as_deref()
+    }
+
+    /// Applies the click, one item of every input is consumed when the result is taken
+    pub fn handle_click<S: PacketSender>(&mut self, view: &mut InventoryView, packet: &ClickContainerPC2S, book: &RecipeBook, sender: &mut S) -> anyhow::Result<Vec<OwnedSlot>> {
+        if !is_opened(view, ContainerType::Smithing) {
+            return view.handle_click(packet, sender);
+        }
+        let result = container_slot(view, self.layout.result);
+        let dropped = view.handle_click(packet, sender)?;
+        if result.is_some() && container_slot(view, self.layout.result) != result {
+            for slot in self.layout.inputs() {
+                let input = container_slot(view, slot).and_then(|mut input| {
+                    input.item_count -= 1;
+                    (input.item_count > 0).then_some(input)
+                });
+                view.set_slot(slot, input, sender)?;
+            }
+        }
+        self.update(view, book, sender)?;
+        Ok(dropped)
+    }
+
+    /// Finds the recipe of the inputs and updates the result
+    pub fn update<S: PacketSender>(&mut self, view: &mut InventoryView, book: &RecipeBook, sender: &mut S) -> anyhow::Result<()> {
+        let template = self.layout.template.and_then(|slot| container_slot(view, slot));
+        let base = container_slot(view, self.layout.base);
+        let addition = container_slot(view, self.layout.addition);
+        let recipe = book.smithing_for(template.as_ref(), base.as_ref(), addition.as_ref());
+        self.recipe = recipe.map(|recipe| recipe.id().into());
+        let result = match (recipe, base.as_ref()) {
+            (Some(recipe), Some(base)) => recipe.assemble(template.as_ref(), base, addition.as_ref())?,
+            _ => None,
+        };
+        match container_slot(view, self.layout.result) == result {
+            true => Ok(()),
+            false => view.set_slot(self.layout.result, result, sender),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use bird_chat::component::Component;
+    use crate::connection::PacketQueue;
+    use crate::inventory::{Container, PLAYER_HOTBAR_START};
+    use crate::test_util::item;
+    use super::*;
+
+    fn click(view: &InventoryView, slot: i16) -> ClickContainerPC2S<'static> {
+        ClickContainerPC2S {
+            window_id: view.window_id(),
+            state_id: view.state_id(),
+            slot,
+            button: 0,
+            mode: ClickContainerMode::QuickMove,
+            changed_slots: Cow::Owned(Vec::new()),
+            carried_item: None,
+        }
+    }
+
+    #[test]
+    fn menu_test() {
+        let stone = item("stone");
+        let mut book = RecipeBook::new();
+        book.add_stonecutting(StonecuttingRecipe {
+            id: "stone_slab_from_stonecutting".into(),
+            ingredient: Ingredient::of(&[stone]),
+            result: item("stone_slab"),
+            count: 2,
+        })
+            .add_stonecutting(StonecuttingRecipe {
+                id: "stone_bricks_from_stonecutting".into(),
+                ingredient: Ingredient::of(&[stone]),
+                result: item("stone_bricks"),
+                count: 1,
+            })
+            .add_smithing(SmithingRecipe::Transform {
+                id: "netherite_sword_smithing".into(),
+                template: Ingredient::empty(),
+                base: Ingredient::of(&[item("diamond_sword")]),
+                addition: Ingredient::of(&[item("netherite_ingot")]),
+                result: item("netherite_sword"),
+            });
+        let mut sender = PacketQueue::new();
+        let mut view = InventoryView::new();
+        let mut container = Container::new(ContainerType::Stonecutter, Component::text("Stonecutter"));
+        container.inventory.set(STONECUTTER_INPUT_SLOT, Some(OwnedSlot::new(stone, 2)));
+        view.open(container, &mut sender).unwrap();
+        let mut stonecutter = StonecutterMenu::new();
+        stonecutter.update(&mut view, &book, &mut sender).unwrap();
+        assert_eq!(stonecutter.recipes(), ["stone_bricks_from_stonecutting", "stone_slab_from_stonecutting"]);
+        let button = ClickContainerButtonPC2S { window_id: view.window_id() as i8, button_id: 1 };
+        assert!(stonecutter.handle_button(&mut view, &button, &book, &mut sender).unwrap());
+        stonecutter.handle_click(&mut view, &click(&view, STONECUTTER_RESULT_SLOT as i16), &book, &mut sender).unwrap();
+        // Quick move fills the hotbar from its end
+        assert_eq!(view.player.get(PLAYER_HOTBAR_START + 8), Some(&OwnedSlot::new(item("stone_slab"), 2)));
+        assert_eq!(container_slot(&view, STONECUTTER_INPUT_SLOT), Some(OwnedSlot::new(stone, 1)));
+        assert_eq!(stonecutter.selected(), Some(1));
+
+        view.open(Container::new(ContainerType::Smithing, Component::text("Smithing")), &mut sender).unwrap();
+        let mut smithing = SmithingMenu::new();
+        let layout = smithing.layout();
+        view.set_slot(layout.base, Some(OwnedSlot::new(item("diamond_sword"), 1)), &mut sender).unwrap();
+        view.set_slot(layout.addition, Some(OwnedSlot::new(item("netherite_ingot"), 1)), &mut sender).unwrap();
+        smithing.update(&mut view, &book, &mut sender).unwrap();
+        assert_eq!(smithing.recipe(), Some("netherite_sword_smithing"));
+        assert_eq!(container_slot(&view, layout.result), Some(OwnedSlot::new(item("netherite_sword"), 1)));
+
+        let trim = SmithingRecipe::Trim {
+            id: "trim".into(),
+            template: Ingredient::of(&[item("paper")]),
+            base: Ingredient::of(&[item("iron_chestplate")]),
+            addition: Ingredient::of(&[item("lapis_lazuli")]),
+        };
+        let template = OwnedSlot::new(item("paper"), 1);
+        let addition = OwnedSlot::new(item("lapis_lazuli"), 1);
+        let armor = OwnedSlot::new(item("iron_chestplate"), 1);
+        assert!(!trim.matches(None, Some(&armor), Some(&addition)));
+        let trimmed = trim.assemble(Some(&template), &armor, Some(&addition)).unwrap().unwrap();
+        assert_eq!(
+            read_nbt(&trimmed.nbt).unwrap().get(TRIM_TAG).and_then(|trim| match trim {
+                Value::Compound(trim) => trim.get("material").cloned(),
+                _ => None,
+            }),
+            Some(Value::String("minecraft:lapis".into())),
+        );
+        assert_eq!(trim.assemble(Some(&template), &trimmed, Some(&addition)).unwrap(), None);
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::entity::tracker::EntityTracker;
+#[cfg(feature = "metrics")]
+use crate::metrics_registry::MetricsRegistry;
+use crate::world::World;
+
+pub const TARGET_TPS: f64 = 20.0;
+
+/// Counters of all connections, they are updated from network threads without locking
+#[derive(Debug, Default)]
+pub struct NetworkMetrics {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    packets_sent: AtomicU64,
+    packets_received: AtomicU64,
+    connections_opened: AtomicU64,
+    connections_closed: AtomicU64,
+}
+
+impl NetworkMetrics {
+    pub fn record_sent(&self, packets: u64, bytes: u64) {
+        self.packets_sent.fetch_add(packets, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_received(&self, packets: u64, bytes: u64) {
+        self.packets_received.fetch_add(packets, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_connection_closed(&self) {
+        self.connections_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn totals(&self) -> NetworkTotals {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let opened = load(&self.connections_opened);
+        let closed = load(&self.connections_closed);
+        NetworkTotals {
+            bytes_sent: load(&self.bytes_sent),
+            bytes_received: load(&self.bytes_received),
+            packets_sent: load(&self.packets_sent),
+            packets_received: load(&self.packets_received),
+            connections_opened: opened,
+            open_connections: opened.saturating_sub(closed),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct NetworkTotals {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub connections_opened: u64,
+    pub open_connections: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WorldMetrics {
+    pub name: String,
+    pub loaded_chunks: usize,
+    pub entities: usize,
+}
+
+/// Metrics at the moment of [`ServerMetrics::snapshot`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct MetricsSnapshot {
+    pub uptime: Duration,
+    pub players: usize,
+    pub tps: f64,
+    pub mspt: f64,
+    pub worlds: Vec<WorldMetrics>,
+    pub network: NetworkTotals,
+}
+
+impl MetricsSnapshot {
+    pub fn loaded_chunks(&self) -> usize {
+        self.worlds.iter().map(|world| world.loaded_chunks).sum()
+    }
+
+    pub fn entities(&self) -> usize {
+        self.worlds.iter().map(|world| world.entities).sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct GameMetrics {
+    players: usize,
+    mspt: f64,
+    worlds: Vec<WorldMetrics>,
+}
+
+/// Metrics which embedders poll with [`ServerMetrics::snapshot`], it is shared between threads.
+/// The tick loop updates game metrics and connections update network counters
+#[derive(Debug)]
+pub struct ServerMetrics {
+    started: Instant,
+    network: NetworkMetrics,
+    game: Mutex<GameMetrics>,
+    #[cfg(feature = "metrics")]
+    registry: MetricsRegistry,
+}
+
+/// Ticks per second can't be higher than the target even if ticks are faster
+pub fn tps(mspt: f64) -> f64 {
+    match mspt > 1000.0 / TARGET_TPS {
+        true => 1000.0 / mspt,
+        false => TARGET_TPS,
+    }
+}
+
+impl ServerMetrics {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            network: NetworkMetrics::default(),
+            game: Mutex::new(GameMetrics::default()),
+            #[cfg(feature = "metrics")]
+            registry: MetricsRegistry::new(),
+        }
+    }
+
+    pub fn network(&self) -> &NetworkMetrics {
+        &self.network
+    }
+
+    /// Per packet counters and the tick duration histogram which are exported to Prometheus
+    #[cfg(feature = "metrics")]
+    pub fn registry(&self) -> &MetricsRegistry {
+        &self.registry
+    }
+
+    fn game(&self) -> std::sync::MutexGuard<'_, GameMetrics> {
+        self.game.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Called at the end of the tick, `mspt` is the average like [`crate::timings::BlockEntityTimings::mspt`]
+    pub fn update_tick(&self, players: usize, mspt: f64) {
+        let mut game = self.game();
+        game.players = players;
+        game.mspt = mspt;
+    }
+
+    pub fn update_world(&self, name: &str, world: &World, tracker: &EntityTracker) {
+        let metrics = WorldMetrics {
+            name: name.into(),
+            loaded_chunks: world.chunks().count(),
+            entities: tracker.entities().count(),
+        };
+        let mut game = self.game();
+        match game.worlds.iter_mut().find(|world| world.name == name) {
+            Some(world) => *world = metrics,
+            None => game.worlds.push(metrics),
+        }
+    }
+
+    pub fn remove_world(&self, name: &str) {
+        self.game().worlds.retain(|world| world.name != name);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    pub fn snapshot_at(&self, now: Instant) -> MetricsSnapshot {
+        let game = self.game();
+        MetricsSnapshot {
+            uptime: now.saturating_duration_since(self.started),
+            players: game.players,
+            tps: tps(game.mspt),
+            mspt: game.mspt,
+            worlds: game.worlds.clone(),
+            network: self.network.totals(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use super::*;
+
+    #[test]
+    fn metrics_snapshot_test() {
+        let now = Instant::now();
+        let metrics = ServerMetrics::new(now);
+        let mut world = World::new(1, 0);
+        world.get_or_create_chunk(Vector2D::new(0, 0));
+        world.get_or_create_chunk(Vector2D::new(1, 0));
+        metrics.update_world("overworld", &world, &EntityTracker::new(64.0));
+        metrics.update_world("nether", &World::new(1, 0), &EntityTracker::new(64.0));
+        metrics.update_tick(3, 100.0);
+        metrics.network().record_connection_opened();
+        metrics.network().record_connection_opened();
+        metrics.network().record_connection_closed();
+        metrics.network().record_sent(2, 100);
+        metrics.network().record_received(1, 10);
+
+        let snapshot = metrics.snapshot_at(now + Duration::from_secs(5));
+        assert_eq!(snapshot.uptime, Duration::from_secs(5));
+        assert_eq!(snapshot.players, 3);
+        assert_eq!(snapshot.tps, 10.0);
+        assert_eq!(snapshot.loaded_chunks(), 2);
+        assert_eq!(snapshot.worlds.len(), 2);
+        assert_eq!(snapshot.network, NetworkTotals {
+            bytes_sent: 100,
+            bytes_received: 10,
+            packets_sent: 2,
+            packets_received: 1,
+            connections_opened: 2,
+            open_connections: 1,
+        });
+
+        metrics.remove_world("nether");
+        metrics.update_tick(3, 10.0);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.worlds.len(), 1);
+        assert_eq!(snapshot.tps, TARGET_TPS);
+    }
+}
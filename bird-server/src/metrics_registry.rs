@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+use bird_protocol::ProtocolPacketState;
+
+pub const METRICS_PREFIX: &'static str = "bird";
+/// Upper bounds of the tick duration buckets in milliseconds
+pub const TICK_BUCKETS: &'static [f64] = &[5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+fn state_label(state: ProtocolPacketState) -> &'static str {
+    match state {
+        ProtocolPacketState::Handshake => "handshake",
+        ProtocolPacketState::Status => "status",
+        ProtocolPacketState::Login => "login",
+        ProtocolPacketState::Play => "play",
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PacketCounter {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Histogram {
+    /// Counts of each bucket of [`TICK_BUCKETS`] and the last is `+Inf`
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    /// Sum in milliseconds
+    pub sum: f64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self { buckets: vec![0; TICK_BUCKETS.len() + 1], count: 0, sum: 0.0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = TICK_BUCKETS.iter().position(|bound| value <= *bound).unwrap_or(TICK_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Counters which are rendered in the Prometheus text format, they are shared between the network
+/// threads and the tick loop
+#[derive(Debug)]
+pub struct MetricsRegistry {
+    received: Mutex<HashMap<(ProtocolPacketState, i32), PacketCounter>>,
+    sent: Mutex<HashMap<(ProtocolPacketState, i32), PacketCounter>>,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    active_connections: AtomicI64,
+    tick_duration: Mutex<Histogram>,
+    rejected_logins: Mutex<HashMap<i32, u64>>,
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        Self {
+            received: Mutex::new(HashMap::new()),
+            sent: Mutex::new(HashMap::new()),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            active_connections: AtomicI64::new(0),
+            tick_duration: Mutex::new(Histogram::new()),
+            rejected_logins: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn count(counters: &Mutex<HashMap<(ProtocolPacketState, i32), PacketCounter>>, state: ProtocolPacketState, id: i32, bytes: usize) {
+    let mut counters = lock(counters);
+    let counter = counters.entry((state, id)).or_default();
+    counter.packets += 1;
+    counter.bytes += bytes as u64;
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `bytes` is the length of the frame including the length prefix
+    pub fn record_received(&self, state: ProtocolPacketState, id: i32, bytes: usize) {
+        count(&self.received, state, id, bytes);
+        self.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self, state: ProtocolPacketState, id: i32, bytes: usize) {
+        count(&self.sent, state, id, bytes);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn connection_opened(&self) {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_closed(&self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn record_tick(&self, duration: Duration) {
+        lock(&self.tick_duration).observe(duration.as_secs_f64() * 1000.0);
+    }
+
+    /// Logins of unsupported protocol versions, see [`crate::version_gate::VersionGate`]
+    pub fn record_rejected_login(&self, protocol_version: i32) {
+        *lock(&self.rejected_logins).entry(protocol_version).or_default() += 1;
+    }
+
+    pub fn received(&self, state: ProtocolPacketState, id: i32) -> PacketCounter {
+        lock(&self.received).get(&(state, id)).copied().unwrap_or_default()
+    }
+
+    pub fn sent(&self, state: ProtocolPacketState, id: i32) -> PacketCounter {
+        lock(&self.sent).get(&(state, id)).copied().unwrap_or_default()
+    }
+
+    pub fn active_connections(&self) -> i64 {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    pub fn tick_duration(&self) -> Histogram {
+        lock(&self.tick_duration).clone()
+    }
+
+    /// Counts of rejected logins sorted by the protocol version
+    pub fn rejected_logins(&self) -> Vec<(i32, u64)> {
+        let mut rejected: Vec<_> = lock(&self.rejected_logins).iter().map(|(version, count)| (*version, *count)).collect();
+        rejected.sort();
+        rejected
+    }
+
+    /// Metrics in the Prometheus text exposition format, packets are sorted by state and id
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        // Writing into a string doesn't fail
+        for (name, help, counters) in [
+            ("packets_received_total", "Packets received by state and id", &self.received),
+            ("packets_sent_total", "Packets sent by state and id", &self.sent),
+        ] {
+            let mut counters: Vec<_> = lock(counters).iter().map(|(key, counter)| (*key, *counter)).collect();
+            counters.sort_by_key(|((state, id), _)| (*state as u8, *id));
+            let _ = writeln!(text, "# HELP {}_{} {}", METRICS_PREFIX, name, help);
+            let _ = writeln!(text, "# TYPE {}_{} counter", METRICS_PREFIX, name);
+            for ((state, id), counter) in counters {
+                let _ = writeln!(
+                    text, "{}_{}{{state=\"{}\",id=\"0x{:02X}\"}} {}",
+                    METRICS_PREFIX, name, state_label(state), id, counter.packets,
+                );
+            }
+        }
+        for (name, help, value) in [
+            ("bytes_received_total", "Bytes received", self.bytes_received.load(Ordering::Relaxed)),
+            ("bytes_sent_total", "Bytes sent", self.bytes_sent.load(Ordering::Relaxed)),
+        ] {
+            let _ = writeln!(text, "# HELP {}_{} {}", METRICS_PREFIX, name, help);
+            let _ = writeln!(text, "# TYPE {}_{} counter", METRICS_PREFIX, name);
+            let _ = writeln!(text, "{}_{} {}", METRICS_PREFIX, name, value);
+        }
+        let _ = writeln!(text, "# HELP {}_active_connections Open connections", METRICS_PREFIX);
+        let _ = writeln!(text, "# TYPE {}_active_connections gauge", METRICS_PREFIX);
+        let _ = writeln!(text, "{}_active_connections {}", METRICS_PREFIX, self.active_connections());
+        let _ = writeln!(text, "# HELP {}_rejected_logins_total Logins of unsupported protocol versions", METRICS_PREFIX);
+        let _ = writeln!(text, "# TYPE {}_rejected_logins_total counter", METRICS_PREFIX);
+        for (version, count) in self.rejected_logins() {
+            let _ = writeln!(text, "{}_rejected_logins_total{{protocol_version=\"{}\"}} {}", METRICS_PREFIX, version, count);
+        }
+
+        let histogram = self.tick_duration();
+        let _ = writeln!(text, "# HELP {}_tick_duration_milliseconds Duration of game ticks", METRICS_PREFIX);
+        let _ = writeln!(text, "# TYPE {}_tick_duration_milliseconds histogram", METRICS_PREFIX);
+        let mut cumulative = 0;
+        for (index, count) in histogram.buckets.iter().enumerate() {
+            cumulative += count;
+            let bound = TICK_BUCKETS.get(index).map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(text, "{}_tick_duration_milliseconds_bucket{{le=\"{}\"}} {}", METRICS_PREFIX, bound, cumulative);
+        }
+        let _ = writeln!(text, "{}_tick_duration_milliseconds_sum {}", METRICS_PREFIX, histogram.sum);
+        let _ = writeln!(text, "{}_tick_duration_milliseconds_count {}", METRICS_PREFIX, histogram.count);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_registry_test() {
+        let registry = MetricsRegistry::new();
+        registry.connection_opened();
+        registry.connection_opened();
+        registry.connection_closed();
+        registry.record_received(ProtocolPacketState::Play, 0x12, 10);
+        registry.record_received(ProtocolPacketState::Play, 0x12, 6);
+        registry.record_sent(ProtocolPacketState::Login, 0x02, 30);
+        registry.record_tick(Duration::from_millis(7));
+        registry.record_tick(Duration::from_secs(2));
+        registry.record_rejected_login(47);
+
+        assert_eq!(registry.received(ProtocolPacketState::Play, 0x12), PacketCounter { packets: 2, bytes: 16 });
+        assert_eq!(registry.sent(ProtocolPacketState::Play, 0x02), PacketCounter::default());
+        assert_eq!(registry.active_connections(), 1);
+        let histogram = registry.tick_duration();
+        assert_eq!(histogram.buckets[1], 1);
+        assert_eq!(histogram.buckets[TICK_BUCKETS.len()], 1);
+
+        let text = registry.render();
+        assert!(text.contains("bird_packets_received_total{state=\"play\",id=\"0x12\"} 2\n"));
+        assert!(text.contains("bird_packets_sent_total{state=\"login\",id=\"0x02\"} 1\n"));
+        assert!(text.contains("bird_bytes_received_total 16\n"));
+        assert!(text.contains("bird_active_connections 1\n"));
+        assert!(text.contains("bird_rejected_logins_total{protocol_version=\"47\"} 1\n"));
+        assert!(text.contains("bird_tick_duration_milliseconds_bucket{le=\"5\"} 0\n"));
+        assert!(text.contains("bird_tick_duration_milliseconds_bucket{le=\"+Inf\"} 2\n"));
+    }
+}
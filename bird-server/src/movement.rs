@@ -0,0 +1,254 @@
+use euclid::default::{Box3D, Point3D, Vector3D};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::gamemode::GameMode;
+use crate::protocol::*;
+use crate::registry::PlayC2SPacket;
+use crate::world::World;
+use crate::world::query::intersects_blocks;
+
+pub const PLAYER_WIDTH: f64 = 0.6;
+pub const PLAYER_HEIGHT: f64 = 1.8;
+/// Squared distance of one movement packet like in vanilla, elytra movement is not checked separately
+pub const MAX_MOVEMENT_SQUARED: f64 = 100.0;
+/// Coordinates are invalid outside of this like in vanilla, the player is kicked
+pub const MAX_COORDINATE: f64 = 3.0e7;
+/// Teleport is sent again if the client has not confirmed it
+pub const TELEPORT_RESEND_TICKS: u64 = 20;
+/// Collision boxes are inset by this, so players standing on a block are not colliding with it
+const COLLISION_EPSILON: f64 = 1.0e-5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementOutcome {
+    Accepted,
+    /// The client has not confirmed the teleport yet, the packet is ignored
+    AwaitingTeleport,
+    /// The player is teleported back by [`SynchronizePlayerPositionPS2C`]
+    MovedTooQuickly,
+    MovedIntoBlock,
+}
+
+/// Bounding box of the player whose feet are at the position
+pub fn player_box(position: Vector3D<f64>) -> Box3D<f64> {
+    let half = PLAYER_WIDTH / 2.0;
+    Box3D::new(
+        Point3D::new(position.x - half, position.y, position.z - half),
+        Point3D::new(position.x + half, position.y + PLAYER_HEIGHT, position.z + half),
+    )
+}
+
+/// Checks if the box intersects colliding blocks, unloaded blocks are air
+pub fn collides(world: &World, bounds: Box3D<f64>) -> bool {
+    let inset = Vector3D::splat(COLLISION_EPSILON);
+    intersects_blocks(world, Box3D::new(bounds.min + inset, bounds.max - inset))
+}
+
+/// Position of the player which is validated on the server. Movement packets are applied if they are valid,
+/// the player is teleported back to the last valid position otherwise
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerMovement {
+    position: Vector3D<f64>,
+    yaw: f32,
+    pitch: f32,
+    on_ground: bool,
+    next_teleport_id: i32,
+    /// Teleport id and the tick when it was sent
+    pending_teleport: Option<(i32, u64)>,
+    /// Movement packets since the last tick, vanilla allows the distance of each of them
+    packets: u32,
+}
+
+impl PlayerMovement {
+    pub fn new(position: Vector3D<f64>, yaw: f32, pitch: f32) -> Self {
+        Self { position, yaw, pitch, on_ground: false, next_teleport_id: 0, pending_teleport: None, packets: 0 }
+    }
+
+    pub fn position(&self) -> Vector3D<f64> {
+        self.position
+    }
+
+    pub fn rotation(&self) -> (f32, f32) {
+        (self.yaw, self.pitch)
+    }
+
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    pub fn is_awaiting_teleport(&self) -> bool {
+        self.pending_teleport.is_some()
+    }
+
+    fn send_teleport<S: PacketSender>(&self, teleport_id: i32, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&SynchronizePlayerPositionPS2C {
+            pos: self.position,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            flags: SynchronizePlayerPositionFlags::new(),
+            teleport_id,
+            dismount_vehicle: false,
+        })
+    }
+
+    /// Movement packets are ignored until the client confirms the teleport
+    pub fn teleport<S: PacketSender>(&mut self, position: Vector3D<f64>, yaw: f32, pitch: f32, tick: u64, sender: &mut S) -> anyhow::Result<i32> {
+        self.position = position;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        let teleport_id = self.next_teleport_id;
+        self.next_teleport_id = self.next_teleport_id.wrapping_add(1);
+        self.pending_teleport = Some((teleport_id, tick));
+        self.send_teleport(teleport_id, sender)?;
+        Ok(teleport_id)
+    }
+
+    /// Returns false if the id is not the id of the last teleport
+    pub fn confirm_teleport(&mut self, teleport_id: i32) -> bool {
+        match self.pending_teleport {
+            Some((pending, _)) if pending == teleport_id => {
+                self.pending_teleport = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Sends the teleport again if it is not confirmed for too long
+    pub fn tick<S: PacketSender>(&mut self, tick: u64, sender: &mut S) -> anyhow::Result<()> {
+        self.packets = 0;
+        if let Some((teleport_id, sent)) = self.pending_teleport {
+            if tick >= sent + TELEPORT_RESEND_TICKS {
+                self.pending_teleport = Some((teleport_id, tick));
+                self.send_teleport(teleport_id, sender)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the movement packet, `None` if it is not one. Invalid coordinates are errors, the player must be kicked
+    pub fn handle_packet<S: PacketSender>(
+        &mut self,
+        packet: &PlayC2SPacket,
+        world: &World,
+        game_mode: GameMode,
+        tick: u64,
+        sender: &mut S,
+    ) -> anyhow::Result<Option<MovementOutcome>> {
+        let (position, rotation, on_ground) = match packet {
+            PlayC2SPacket::ConfirmTeleportation(packet) => {
+                self.confirm_teleport(packet.teleport_id);
+                return Ok(None);
+            }
+            PlayC2SPacket::SetPlayerPosition(packet) => (Some(packet.pos), None, packet.on_ground),
+            PlayC2SPacket::SetPlayerPositionAndRotation(packet) =>
+                (Some(packet.pos), Some((packet.yaw, packet.pitch)), packet.on_ground),
+            PlayC2SPacket::SetPlayerRotation(packet) => (None, Some((packet.yaw, packet.pitch)), packet.on_ground),
+            PlayC2SPacket::SetPlayerOnGround(packet) => (None, None, packet.on_ground),
+            _ => return Ok(None),
+        };
+        self.handle_move(position, rotation, on_ground, world, game_mode, tick, sender).map(Some)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn handle_move<S: PacketSender>(
+        &mut self,
+        position: Option<Vector3D<f64>>,
+        rotation: Option<(f32, f32)>,
+        on_ground: bool,
+        world: &World,
+        game_mode: GameMode,
+        tick: u64,
+        sender: &mut S,
+    ) -> anyhow::Result<MovementOutcome> {
+        let valid_position = position.map_or(true, |position| {
+            [position.x, position.y, position.z].iter().all(|value| value.is_finite())
+                && position.x.abs() < MAX_COORDINATE && position.z.abs() < MAX_COORDINATE && position.y.abs() < MAX_COORDINATE
+        });
+        let valid_rotation = rotation.map_or(true, |(yaw, pitch)| yaw.is_finite() && pitch.is_finite());
+        if !valid_position || !valid_rotation {
+            return Err(anyhow::Error::msg("Invalid move player packet received"));
+        }
+        if self.pending_teleport.is_some() {
+            return Ok(MovementOutcome::AwaitingTeleport);
+        }
+        self.packets += 1;
+        let (yaw, pitch) = rotation.unwrap_or((self.yaw, self.pitch));
+        let (yaw, pitch) = (yaw.rem_euclid(360.0), pitch.clamp(-90.0, 90.0));
+        let position = match position {
+            Some(position) => position,
+            None => {
+                self.yaw = yaw;
+                self.pitch = pitch;
+                self.on_ground = on_ground;
+                return Ok(MovementOutcome::Accepted);
+            }
+        };
+        let outcome = match game_mode {
+            // Spectators fly through blocks faster than the limit
+            GameMode::Spectator => MovementOutcome::Accepted,
+            _ if (position - self.position).square_length() > MAX_MOVEMENT_SQUARED * self.packets as f64 =>
+                MovementOutcome::MovedTooQuickly,
+            // Players which are already stuck in blocks can move out of them
+            _ if collides(world, player_box(position)) && !collides(world, player_box(self.position)) =>
+                MovementOutcome::MovedIntoBlock,
+            _ => MovementOutcome::Accepted,
+        };
+        match outcome {
+            MovementOutcome::Accepted => {
+                self.position = position;
+                self.yaw = yaw;
+                self.pitch = pitch;
+                self.on_ground = on_ground;
+            }
+            _ => { self.teleport(self.position, yaw, pitch, tick, sender)?; }
+        }
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use bird_data::Block;
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn movement_test() {
+        let mut world = World::new(2, 0);
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        world.get_or_create_chunk(Vector2D::zero());
+        world.set_block_state(Vector3D::new(2, 1, 0), stone);
+        let mut queue = PacketQueue::new();
+        let mut movement = PlayerMovement::new(Vector3D::new(0.5, 1.0, 0.5), 0.0, 0.0);
+        let handle = |movement: &mut PlayerMovement, packet: PlayC2SPacket, queue: &mut PacketQueue|
+            movement.handle_packet(&packet, &world, GameMode::Survival, 0, queue).unwrap();
+
+        let step = SetPlayerPositionAndRotationPC2S { pos: Vector3D::new(1.5, 1.0, 0.5), yaw: 370.0, pitch: 95.0, on_ground: true };
+        let packet = PlayC2SPacket::SetPlayerPositionAndRotation(step);
+        assert_eq!(handle(&mut movement, packet, &mut queue), Some(MovementOutcome::Accepted));
+        assert_eq!(movement.rotation(), (10.0, 90.0));
+        // Standing next to the stone
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(1.69, 1.0, 0.5), on_ground: true });
+        assert_eq!(handle(&mut movement, packet, &mut queue), Some(MovementOutcome::Accepted));
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(2.5, 1.0, 0.5), on_ground: true });
+        assert_eq!(handle(&mut movement, packet, &mut queue), Some(MovementOutcome::MovedIntoBlock));
+        assert_eq!(queue.take().len(), 1);
+        assert!(movement.is_awaiting_teleport());
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(1.0, 1.0, 0.5), on_ground: true });
+        assert_eq!(handle(&mut movement, packet, &mut queue), Some(MovementOutcome::AwaitingTeleport));
+        movement.tick(TELEPORT_RESEND_TICKS, &mut queue).unwrap();
+        assert_eq!(queue.take().len(), 1);
+        assert_eq!(handle(&mut movement, PlayC2SPacket::ConfirmTeleportation(ConfirmTeleportationPC2S { teleport_id: 0 }), &mut queue), None);
+        assert!(!movement.is_awaiting_teleport());
+        assert_eq!(movement.position(), Vector3D::new(1.69, 1.0, 0.5));
+
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(1.69, 1.0, 20.0), on_ground: true });
+        assert_eq!(handle(&mut movement, packet, &mut queue), Some(MovementOutcome::MovedTooQuickly));
+        assert!(movement.confirm_teleport(1));
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(f64::NAN, 1.0, 0.5), on_ground: true });
+        assert!(movement.handle_packet(&packet, &world, GameMode::Survival, 0, &mut queue).is_err());
+        let packet = PlayC2SPacket::SetPlayerPosition(SetPlayerPositionPC2S { pos: Vector3D::new(2.5, 1.0, 20.0), on_ground: false });
+        assert_eq!(movement.handle_packet(&packet, &world, GameMode::Spectator, 0, &mut queue).unwrap(), Some(MovementOutcome::Accepted));
+    }
+}
@@ -0,0 +1,209 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::entity::tracker::EntityTracker;
+use crate::protocol::*;
+
+/// Entry of the tab list, players are identified by their entity id like viewers of [`EntityTracker`]
+#[derive(Clone, Debug)]
+pub struct ListedPlayer {
+    pub entity_id: i32,
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<OwnedProperty>,
+    pub game_mode: PlayerInfoUpdateGameMode,
+    pub latency: i32,
+    pub display_name: Option<Component<'static>>,
+}
+
+impl ListedPlayer {
+    fn add_packet(&self) -> PlayerInfoUpdatePS2C<'_> {
+        PlayerInfoUpdatePS2C {
+            actions: Cow::Owned(vec![(self.uuid, PlayerInfoUpdateAction {
+                add: Some(PlayerInfoUpdateAddAction {
+                    name: &self.name,
+                    properties: Cow::Owned(self.properties.iter().map(|property| property.as_property()).collect()),
+                }),
+                initialize_chat: None,
+                update_game_mode: Some(self.game_mode),
+                update_listed: Some(true),
+                update_latency: Some(self.latency),
+                update_display_name: Some(self.display_name.clone()),
+            })]),
+        }
+    }
+}
+
+/// Tab list of online players which keeps hidden players out of entities,
+/// the tab list and suggestions of their viewers
+#[derive(Clone, Debug, Default)]
+pub struct PlayerList {
+    players: HashMap<i32, ListedPlayer>,
+    /// Pairs of the hidden player and the viewer
+    hidden: HashSet<(i32, i32)>,
+}
+
+impl PlayerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, entity_id: i32) -> Option<&ListedPlayer> {
+        self.players.get(&entity_id)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item=&ListedPlayer> {
+        self.players.values()
+    }
+
+    pub fn can_see(&self, viewer: i32, player: i32) -> bool {
+        !self.hidden.contains(&(player, viewer))
+    }
+
+    /// Must be called before the player and its entity are added to the tracker,
+    /// because the client doesn't spawn players which are not in the tab list.
+    /// The tab list is sent to `sender` of the joined player
+    pub fn add_player<S: PacketSender>(&mut self, player: ListedPlayer, sender: &mut S, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        let entity_id = player.entity_id;
+        self.players.insert(entity_id, player);
+        let player = &self.players[&entity_id];
+        for other in self.players.values() {
+            sender.send_packet(&other.add_packet())?;
+            if other.entity_id == entity_id {
+                continue;
+            }
+            if let Some(viewer) = tracker.get_viewer_mut(other.entity_id) {
+                viewer.packets.send_packet(&player.add_packet())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the player from the tab list of viewers, the entity must be removed from the tracker by the caller
+    pub fn remove_player(&mut self, entity_id: i32, tracker: &mut EntityTracker) -> anyhow::Result<Option<ListedPlayer>> {
+        let player = match self.players.remove(&entity_id) {
+            Some(player) => player,
+            None => return Ok(None),
+        };
+        for viewer_id in self.players.keys() {
+            if !self.hidden.contains(&(entity_id, *viewer_id)) {
+                if let Some(viewer) = tracker.get_viewer_mut(*viewer_id) {
+                    viewer.packets.send_packet(&PlayerInfoRemovePS2C { players: Cow::Borrowed(&[player.uuid]) })?;
+                }
+            }
+        }
+        self.hidden.retain(|(hidden, viewer)| *hidden != entity_id && *viewer != entity_id);
+        Ok(Some(player))
+    }
+
+    /// Updates the gamemode in the tab list of the player and of its viewers
+    pub fn set_game_mode(&mut self, entity_id: i32, game_mode: PlayerInfoUpdateGameMode, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        let player = match self.players.get_mut(&entity_id) {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+        player.game_mode = game_mode;
+        let packet = PlayerInfoUpdatePS2C {
+            actions: Cow::Owned(vec![(player.uuid, PlayerInfoUpdateAction {
+                add: None,
+                initialize_chat: None,
+                update_game_mode: Some(game_mode),
+                update_listed: None,
+                update_latency: None,
+                update_display_name: None,
+            })]),
+        };
+        for viewer_id in self.players.keys() {
+            if !self.hidden.contains(&(entity_id, *viewer_id)) {
+                if let Some(viewer) = tracker.get_viewer_mut(*viewer_id) {
+                    viewer.packets.send_packet(&packet)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Despawns the player for the viewer and removes it from the tab list and so from the chat completions
+    pub fn hide_from(&mut self, player: i32, viewer: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        if player == viewer || !self.players.contains_key(&player) || !self.hidden.insert((player, viewer)) {
+            return Ok(());
+        }
+        tracker.hide_entity(player, viewer)?;
+        if let Some(viewer) = tracker.get_viewer_mut(viewer) {
+            viewer.packets.send_packet(&PlayerInfoRemovePS2C { players: Cow::Borrowed(&[self.players[&player].uuid]) })?;
+        }
+        Ok(())
+    }
+
+    pub fn show_to(&mut self, player: i32, viewer: i32, tracker: &mut EntityTracker) -> anyhow::Result<()> {
+        if !self.hidden.remove(&(player, viewer)) {
+            return Ok(());
+        }
+        if let (Some(listed), Some(viewer)) = (self.players.get(&player), tracker.get_viewer_mut(viewer)) {
+            viewer.packets.send_packet(&listed.add_packet())?;
+        }
+        tracker.show_entity(player, viewer)
+    }
+
+    /// Removes names of hidden players from suggestions which are sent to the viewer
+    pub fn filter_suggestions<'a>(&self, viewer: i32, matches: &mut Vec<CommandSuggestionsMatch<'a>>) {
+        let hidden: HashSet<&str> = self.hidden.iter()
+            .filter(|(_, hidden_viewer)| *hidden_viewer == viewer)
+            .filter_map(|(player, _)| self.players.get(player))
+            .map(|player| player.name.as_str())
+            .collect();
+        matches.retain(|suggestion| !hidden.contains(suggestion.insert));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector3D;
+    use crate::connection::PacketQueue;
+    use crate::entity::EntityKind;
+    use crate::entity::tracker::TrackedEntity;
+    use super::*;
+
+    fn player(entity_id: i32, name: &str) -> ListedPlayer {
+        ListedPlayer {
+            entity_id,
+            uuid: Uuid::from_u128(entity_id as u128),
+            name: name.into(),
+            properties: Vec::new(),
+            game_mode: PlayerInfoUpdateGameMode::Survival,
+            latency: 0,
+            display_name: None,
+        }
+    }
+
+    #[test]
+    fn hide_player_test() {
+        let mut tracker = EntityTracker::new(64.0);
+        let mut list = PlayerList::new();
+        for (id, name) in [(1, "vanished"), (2, "viewer")] {
+            list.add_player(player(id, name), &mut PacketQueue::new(), &mut tracker).unwrap();
+            tracker.add_viewer(id, Vector3D::zero()).unwrap();
+            tracker.add_entity(TrackedEntity::new(id, Uuid::from_u128(id as u128), EntityKind::Player, Vector3D::zero())).unwrap();
+        }
+        assert!(tracker.get_viewer_mut(2).unwrap().visible().contains(&1));
+        list.hide_from(1, 2, &mut tracker).unwrap();
+        assert!(!list.can_see(2, 1));
+        assert!(!tracker.get_viewer_mut(2).unwrap().visible().contains(&1));
+        // The hidden player still sees the viewer
+        assert!(tracker.get_viewer_mut(1).unwrap().visible().contains(&2));
+        let mut matches = vec![
+            CommandSuggestionsMatch { insert: "vanished", tooltip: None },
+            CommandSuggestionsMatch { insert: "viewer", tooltip: None },
+        ];
+        list.filter_suggestions(2, &mut matches);
+        assert_eq!(matches.len(), 1);
+        tracker.get_viewer_mut(2).unwrap().packets.take();
+        list.show_to(1, 2, &mut tracker).unwrap();
+        assert!(tracker.get_viewer_mut(2).unwrap().visible().contains(&1));
+        // Player info, spawn and head rotation
+        assert_eq!(tracker.get_viewer_mut(2).unwrap().packets.take().len(), 3);
+    }
+}
@@ -0,0 +1,337 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use euclid::default::Vector3D;
+use fastnbt::Value;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use uuid::Uuid;
+use bird_protocol::anyhow;
+use crate::death_location::{DeathLocation, LAST_DEATH_LOCATION_TAG};
+use crate::gamemode::{PlayerGameMode, PLAYER_GAME_TYPE_TAG, PREVIOUS_PLAYER_GAME_TYPE_TAG};
+use crate::instrument::warn;
+use crate::inventory::{Inventory, PLAYER_ARMOR_START, PLAYER_HOTBAR_START, PLAYER_INVENTORY_SIZE, PLAYER_MAIN_START, PLAYER_OFFHAND_SLOT};
+use crate::item_container::{item_from_value, item_to_value, Compound};
+
+pub const PLAYERDATA_DIRECTORY: &'static str = "playerdata";
+/// Data version of 1.19.3, vanilla upgrades files with older versions
+pub const PLAYERDATA_VERSION: i32 = 3218;
+pub const ENDER_CHEST_SIZE: usize = 27;
+/// Ticks between autosaves, vanilla saves players every 6000 ticks
+pub const DEFAULT_AUTOSAVE_TICKS: u64 = 6000;
+const DATA_VERSION_TAG: &'static str = "DataVersion";
+const POS_TAG: &'static str = "Pos";
+const ROTATION_TAG: &'static str = "Rotation";
+const DIMENSION_TAG: &'static str = "Dimension";
+const INVENTORY_TAG: &'static str = "Inventory";
+const ENDER_ITEMS_TAG: &'static str = "EnderItems";
+const XP_LEVEL_TAG: &'static str = "XpLevel";
+const XP_PROGRESS_TAG: &'static str = "XpP";
+const XP_TOTAL_TAG: &'static str = "XpTotal";
+/// Tags which are read into the fields of [`PlayerData`]
+const KNOWN_TAGS: &'static [&'static str] = &[
+    DATA_VERSION_TAG, POS_TAG, ROTATION_TAG, DIMENSION_TAG, INVENTORY_TAG, ENDER_ITEMS_TAG, XP_LEVEL_TAG,
+    XP_PROGRESS_TAG, XP_TOTAL_TAG, PLAYER_GAME_TYPE_TAG, PREVIOUS_PLAYER_GAME_TYPE_TAG, LAST_DEATH_LOCATION_TAG,
+];
+/// Armor slots of the vanilla playerdata from the feet to the head
+const VANILLA_ARMOR_START: i8 = 100;
+const VANILLA_OFFHAND_SLOT: i8 = -106;
+const VANILLA_HOTBAR_SIZE: i8 = 9;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Experience {
+    pub level: i32,
+    /// Progress to the next level from 0 to 1
+    pub progress: f32,
+    pub total: i32,
+}
+
+/// Data of the player which is saved in `playerdata/<uuid>.dat`. Tags which are not read by the server
+/// are kept, so files of vanilla are not broken by the server
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerData {
+    pub position: Vector3D<f64>,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub dimension: String,
+    pub game_mode: PlayerGameMode,
+    /// Slots of the player inventory window
+    pub inventory: Inventory,
+    pub ender_chest: Inventory,
+    pub experience: Experience,
+    pub death_location: Option<DeathLocation>,
+    other: Compound,
+}
+
+/// Slot of the player inventory window for the slot of the vanilla playerdata
+pub fn window_slot(slot: i8) -> Option<usize> {
+    match slot {
+        0..=8 => Some(PLAYER_HOTBAR_START + slot as usize),
+        9..=35 => Some(PLAYER_MAIN_START + (slot - VANILLA_HOTBAR_SIZE) as usize),
+        100..=103 => Some(PLAYER_ARMOR_START + (103 - slot) as usize),
+        VANILLA_OFFHAND_SLOT => Some(PLAYER_OFFHAND_SLOT),
+        _ => None,
+    }
+}
+
+/// Slot of the vanilla playerdata, crafting slots are not saved
+pub fn vanilla_slot(window_slot: usize) -> Option<i8> {
+    match window_slot {
+        slot if slot == PLAYER_OFFHAND_SLOT => Some(VANILLA_OFFHAND_SLOT),
+        slot if slot >= PLAYER_HOTBAR_START => Some((slot - PLAYER_HOTBAR_START) as i8),
+        slot if slot >= PLAYER_MAIN_START => Some((slot - PLAYER_MAIN_START) as i8 + VANILLA_HOTBAR_SIZE),
+        slot if slot >= PLAYER_ARMOR_START => Some(VANILLA_ARMOR_START + 3 - (slot - PLAYER_ARMOR_START) as i8),
+        _ => None,
+    }
+}
+
+fn read_items(compound: &Compound, tag: &str, size: usize, slot: impl Fn(i8) -> Option<usize>) -> anyhow::Result<Inventory> {
+    let mut inventory = Inventory::new(size);
+    if let Some(Value::List(items)) = compound.get(tag) {
+        for item in items {
+            // Items in unknown slots are dropped like in vanilla
+            if let (Some(index), item) = item_from_value(item)? {
+                if let Some(index) = slot(index).filter(|index| *index < size) {
+                    inventory.set(index, Some(item));
+                }
+            }
+        }
+    }
+    Ok(inventory)
+}
+
+fn write_items(compound: &mut Compound, tag: &str, inventory: &Inventory, slot: impl Fn(usize) -> Option<i8>) -> anyhow::Result<()> {
+    let items = inventory.slots().iter()
+        .enumerate()
+        .filter_map(|(index, item)| Some((slot(index)?, item.as_ref()?)))
+        .map(|(slot, item)| item_to_value(item, Some(slot)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    compound.insert(tag.into(), Value::List(items));
+    Ok(())
+}
+
+impl PlayerData {
+    pub fn new(position: Vector3D<f64>, dimension: &str, game_mode: PlayerGameMode) -> Self {
+        Self {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            dimension: dimension.into(),
+            game_mode,
+            inventory: Inventory::new(PLAYER_INVENTORY_SIZE),
+            ender_chest: Inventory::new(ENDER_CHEST_SIZE),
+            experience: Experience::default(),
+            death_location: None,
+            other: Compound::new(),
+        }
+    }
+
+    /// Missing tags have the values of the new player
+    pub fn read(compound: Compound, default: &PlayerData) -> anyhow::Result<Self> {
+        let position = match compound.get(POS_TAG) {
+            Some(Value::List(pos)) => match pos.as_slice() {
+                [Value::Double(x), Value::Double(y), Value::Double(z)] => Vector3D::new(*x, *y, *z),
+                _ => return Err(anyhow::Error::msg("Bad position of the player")),
+            },
+            _ => default.position,
+        };
+        let (yaw, pitch) = match compound.get(ROTATION_TAG) {
+            Some(Value::List(rotation)) => match rotation.as_slice() {
+                [Value::Float(yaw), Value::Float(pitch)] => (*yaw, *pitch),
+                _ => return Err(anyhow::Error::msg("Bad rotation of the player")),
+            },
+            _ => (default.yaw, default.pitch),
+        };
+        let dimension = match compound.get(DIMENSION_TAG) {
+            Some(Value::String(dimension)) => dimension.clone(),
+            _ => default.dimension.clone(),
+        };
+        let int = |tag: &str| match compound.get(tag) {
+            Some(Value::Int(value)) => Some(*value),
+            _ => None,
+        };
+        let experience = Experience {
+            level: int(XP_LEVEL_TAG).unwrap_or(default.experience.level),
+            progress: match compound.get(XP_PROGRESS_TAG) {
+                Some(Value::Float(progress)) => *progress,
+                _ => default.experience.progress,
+            },
+            total: int(XP_TOTAL_TAG).unwrap_or(default.experience.total),
+        };
+        let data = Self {
+            position,
+            yaw,
+            pitch,
+            dimension,
+            game_mode: PlayerGameMode::read(&compound).unwrap_or(default.game_mode),
+            inventory: read_items(&compound, INVENTORY_TAG, PLAYER_INVENTORY_SIZE, window_slot)?,
+            ender_chest: read_items(&compound, ENDER_ITEMS_TAG, ENDER_CHEST_SIZE, |slot| usize::try_from(slot).ok())?,
+            experience,
+            death_location: DeathLocation::read(&compound),
+            other: Compound::new(),
+        };
+        let mut other = compound;
+        for tag in KNOWN_TAGS {
+            other.remove(*tag);
+        }
+        Ok(Self { other, ..data })
+    }
+
+    pub fn write(&self) -> anyhow::Result<Compound> {
+        let mut compound = self.other.clone();
+        compound.insert(DATA_VERSION_TAG.into(), Value::Int(PLAYERDATA_VERSION));
+        compound.insert(POS_TAG.into(), Value::List(vec![
+            Value::Double(self.position.x), Value::Double(self.position.y), Value::Double(self.position.z),
+        ]));
+        compound.insert(ROTATION_TAG.into(), Value::List(vec![Value::Float(self.yaw), Value::Float(self.pitch)]));
+        compound.insert(DIMENSION_TAG.into(), Value::String(self.dimension.clone()));
+        compound.insert(XP_LEVEL_TAG.into(), Value::Int(self.experience.level));
+        compound.insert(XP_PROGRESS_TAG.into(), Value::Float(self.experience.progress));
+        compound.insert(XP_TOTAL_TAG.into(), Value::Int(self.experience.total));
+        self.game_mode.write(&mut compound);
+        write_items(&mut compound, INVENTORY_TAG, &self.inventory, vanilla_slot)?;
+        write_items(&mut compound, ENDER_ITEMS_TAG, &self.ender_chest, |slot| i8::try_from(slot).ok())?;
+        if let Some(death_location) = &self.death_location {
+            death_location.write(&mut compound);
+        }
+        Ok(compound)
+    }
+}
+
+pub fn playerdata_path(directory: &Path, uuid: Uuid) -> PathBuf {
+    directory.join(format!("{}.dat", uuid.as_hyphenated()))
+}
+
+/// Reads the gzipped nbt file, `None` if the player has not joined before
+pub fn read_playerdata_file(path: &Path) -> anyhow::Result<Option<Compound>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut bytes = Vec::new();
+    GzDecoder::new(file).read_to_end(&mut bytes)?;
+    Ok(Some(fastnbt::from_bytes(&bytes)?))
+}
+
+/// The file is written next to the old one and renamed, so the data is not lost if the server stops while saving
+pub fn write_playerdata_file(path: &Path, compound: &Compound) -> anyhow::Result<()> {
+    let temporary = path.with_extension("dat_tmp");
+    let mut encoder = GzEncoder::new(File::create(&temporary)?, Compression::default());
+    encoder.write_all(&fastnbt::to_bytes(compound)?)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Playerdata of the world, files are written on a thread, so saves don't block the tick
+pub struct PlayerDataStorage {
+    directory: PathBuf,
+    requests: Option<Sender<(PathBuf, Compound)>>,
+    worker: Option<JoinHandle<()>>,
+    autosave_ticks: u64,
+    last_autosave: u64,
+}
+
+impl PlayerDataStorage {
+    pub fn new(world_directory: &Path, autosave_ticks: u64) -> anyhow::Result<Self> {
+        let directory = world_directory.join(PLAYERDATA_DIRECTORY);
+        fs::create_dir_all(&directory)?;
+        let (requests, receiver) = mpsc::channel::<(PathBuf, Compound)>();
+        let worker = thread::spawn(move || {
+            for (path, compound) in receiver {
+                if let Err(err) = write_playerdata_file(&path, &compound) {
+                    warn!("Failed to save {}: {}", path.display(), err);
+                }
+            }
+        });
+        Ok(Self { directory, requests: Some(requests), worker: Some(worker), autosave_ticks, last_autosave: 0 })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Data of the new player is returned if the player has not joined before
+    pub fn load(&self, uuid: Uuid, default: &PlayerData) -> anyhow::Result<PlayerData> {
+        match read_playerdata_file(&playerdata_path(&self.directory, uuid))? {
+            Some(compound) => PlayerData::read(compound, default),
+            None => Ok(default.clone()),
+        }
+    }
+
+    /// Queues the save, the compound is built on the tick thread
+    pub fn save(&self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()> {
+        let requests = self.requests.as_ref().ok_or_else(|| anyhow::Error::msg("Playerdata storage is closed"))?;
+        requests.send((playerdata_path(&self.directory, uuid), data.write()?))
+            .map_err(|_| anyhow::Error::msg("Playerdata saving thread has stopped"))
+    }
+
+    /// Returns true once in the autosave interval, online players are saved then
+    pub fn should_autosave(&mut self, tick: u64) -> bool {
+        match self.autosave_ticks != 0 && tick >= self.last_autosave + self.autosave_ticks {
+            true => {
+                self.last_autosave = tick;
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Waits until all queued saves are written, it is called when the server stops
+    pub fn flush(&mut self) {
+        self.requests.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for PlayerDataStorage {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gamemode::GameMode;
+    use crate::protocol::OwnedSlot;
+    use super::*;
+
+    #[test]
+    fn playerdata_test() {
+        for slot in [0, 8, 9, 35, 100, 103, VANILLA_OFFHAND_SLOT] {
+            assert_eq!(window_slot(slot).and_then(vanilla_slot), Some(slot));
+        }
+        assert_eq!(window_slot(103), Some(PLAYER_ARMOR_START));
+        assert_eq!(vanilla_slot(0), None);
+
+        let directory = std::env::temp_dir().join(format!("bird-playerdata-{}", std::process::id()));
+        let default = PlayerData::new(Vector3D::new(0.5, 64.0, 0.5), "minecraft:overworld", PlayerGameMode::new(GameMode::Survival));
+        let uuid = Uuid::from_u128(1);
+        let mut storage = PlayerDataStorage::new(&directory, 20).unwrap();
+        assert_eq!(storage.load(uuid, &default).unwrap(), default);
+
+        let mut data = default.clone();
+        data.position = Vector3D::new(10.0, -20.5, 30.25);
+        data.yaw = 90.0;
+        data.experience = Experience { level: 5, progress: 0.5, total: 60 };
+        data.inventory.set(PLAYER_HOTBAR_START, Some(OwnedSlot::new(1, 32)));
+        data.inventory.set(PLAYER_OFFHAND_SLOT, Some(OwnedSlot::new(2, 1)));
+        data.ender_chest.set(26, Some(OwnedSlot::new(3, 64)));
+        data.death_location = Some(DeathLocation::new("minecraft:overworld", Vector3D::new(1.0, 2.0, 3.0)));
+        data.other.insert("foodLevel".into(), Value::Int(20));
+        storage.save(uuid, &data).unwrap();
+        storage.flush();
+        assert_eq!(storage.load(uuid, &default).unwrap(), data);
+
+        assert!(!storage.should_autosave(10));
+        assert!(storage.should_autosave(20));
+        assert!(!storage.should_autosave(30));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use std::path::Path;
+use euclid::default::Vector2D;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+use crate::synced::{BossBar, Synced};
+
+/// File in the world directory, the task is resumed from it after a restart
+pub const PREGEN_FILE: &'static str = "pregen.json";
+pub const PREGEN_COMMAND: &'static str = "pregen";
+/// Radius in blocks
+pub const MAX_PREGEN_RADIUS: i32 = 30_000;
+/// Console line is printed each time the progress crosses this step
+pub const REPORT_PERCENT_STEP: u8 = 10;
+pub const PREGEN_BAR_UUID: Uuid = Uuid::from_u128(0x62697264_7072_6567_656e_000000000001);
+
+/// Storage of chunks which the task fills, chunks are saved by the implementation
+pub trait ChunkGeneration {
+    fn is_generated(&self, position: Vector2D<i32>) -> bool;
+
+    fn generate_and_save(&mut self, position: Vector2D<i32>) -> anyhow::Result<()>;
+}
+
+/// Chunks are generated in a square spiral around the center, `next` is the index in the spiral
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct PregenState {
+    pub world: String,
+    pub center_x: i32,
+    pub center_z: i32,
+    /// Radius in chunks
+    pub radius: i32,
+    pub next: u64,
+}
+
+impl PregenState {
+    pub fn total(&self) -> u64 {
+        let side = self.radius as u64 * 2 + 1;
+        side * side
+    }
+
+    pub fn percent(&self) -> u8 {
+        (self.next.min(self.total()) * 100 / self.total()) as u8
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.total()
+    }
+}
+
+fn isqrt(value: u64) -> u64 {
+    let mut root = (value as f64).sqrt() as u64;
+    while root * root > value {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= value {
+        root += 1;
+    }
+    root
+}
+
+/// Offset of the chunk with the index in the spiral, ring `k` contains `8k` chunks
+pub fn spiral_offset(index: u64) -> Vector2D<i32> {
+    if index == 0 {
+        return Vector2D::zero();
+    }
+    let ring = ((isqrt(index) + 1) / 2) as i32;
+    let inner = (2 * ring as u64 - 1).pow(2);
+    let side = ((index - inner) / (2 * ring as u64)) as i32;
+    let step = ((index - inner) % (2 * ring as u64)) as i32;
+    match side {
+        0 => Vector2D::new(ring, -ring + 1 + step),
+        1 => Vector2D::new(ring - 1 - step, ring),
+        2 => Vector2D::new(-ring, ring - 1 - step),
+        _ => Vector2D::new(-ring + 1 + step, -ring),
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PregenConfig {
+    pub chunks_per_tick: usize,
+    /// Generation is paused while the server is slower than this
+    pub max_mspt: f64,
+}
+
+impl Default for PregenConfig {
+    fn default() -> Self {
+        Self { chunks_per_tick: 4, max_mspt: 40.0 }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PregenTick {
+    pub generated: usize,
+    /// Chunks which were generated before the task
+    pub existing: usize,
+    pub finished: bool,
+    /// Progress line for the console
+    pub report: Option<String>,
+}
+
+/// Long running `/pregen` task which generates chunks in a radius a few chunks per tick
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Pregenerator {
+    pub config: PregenConfig,
+    state: Option<PregenState>,
+    paused: bool,
+    reported: u8,
+    dirty: bool,
+    /// Progress of the task, it is removed from viewers when the task ends
+    bar: Synced<Option<BossBar>>,
+}
+
+impl Pregenerator {
+    pub fn new(config: PregenConfig) -> Self {
+        Self { config, ..Self::default() }
+    }
+
+    /// Resumes the task which was saved before the restart
+    pub fn load(config: PregenConfig, path: &Path) -> anyhow::Result<Self> {
+        let state: Option<PregenState> = match std::fs::read_to_string(path) {
+            Ok(text) => Some(serde_json::from_str(&text)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error.into()),
+        };
+        let reported = state.as_ref().map_or(0, |state| state.percent() / REPORT_PERCENT_STEP * REPORT_PERCENT_STEP);
+        Ok(Self { config, state, reported, ..Self::default() })
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// The file is removed when there is no task
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        match self.state {
+            Some(ref state) => std::fs::write(path, serde_json::to_string_pretty(state)?)?,
+            None => match std::fs::remove_file(path) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+                _ => {}
+            },
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn state(&self) -> Option<&PregenState> {
+        self.state.as_ref()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// `radius` is in blocks around the block position of the center
+    pub fn start(&mut self, world: &str, center: Vector2D<i32>, radius: i32) -> anyhow::Result<()> {
+        if self.state.is_some() {
+            return Err(anyhow::Error::msg("Pregeneration is already running"));
+        }
+        if !(1..=MAX_PREGEN_RADIUS).contains(&radius) {
+            return Err(anyhow::Error::msg(format!("Radius must be between 1 and {}", MAX_PREGEN_RADIUS)));
+        }
+        self.state = Some(PregenState {
+            world: world.into(),
+            center_x: center.x >> 4,
+            center_z: center.y >> 4,
+            radius: (radius + 15) / 16,
+            next: 0,
+        });
+        self.paused = false;
+        self.reported = 0;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn cancel<S: PacketSender>(&mut self, viewers: &mut S) -> anyhow::Result<bool> {
+        if self.state.take().is_none() {
+            return Ok(false);
+        }
+        self.dirty = true;
+        self.sync_bar(viewers)?;
+        Ok(true)
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn bar(state: &PregenState) -> BossBar {
+        BossBar {
+            health: state.percent() as f32 / 100.0,
+            color: BossBarColor::Green,
+            division: BossBarDivision::Ten,
+            ..BossBar::new(PREGEN_BAR_UUID, Component::text(format!("Pregenerating {}: {}%", state.world, state.percent())))
+        }
+    }
+
+    /// Sends changes of the progress to viewers of the boss bar
+    fn sync_bar<S: PacketSender>(&mut self, viewers: &mut S) -> anyhow::Result<()> {
+        self.bar.set(self.state.as_ref().map(Self::bar));
+        self.bar.sync(viewers)?;
+        Ok(())
+    }
+
+    /// Adds the boss bar of the progress to the player
+    pub fn show_bar<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        self.bar.show(sender)
+    }
+
+    /// Generates the next chunks of the task, `viewers` are players who see the boss bar
+    pub fn tick<G: ChunkGeneration, S: PacketSender>(&mut self, generation: &mut G, mspt: f64, viewers: &mut S) -> anyhow::Result<PregenTick> {
+        let mut result = PregenTick::default();
+        let state = match self.state {
+            Some(ref mut state) if !self.paused && mspt <= self.config.max_mspt => state,
+            _ => return Ok(result),
+        };
+        let center = Vector2D::new(state.center_x, state.center_z);
+        while result.generated < self.config.chunks_per_tick && !state.is_finished() {
+            let position = center + spiral_offset(state.next);
+            match generation.is_generated(position) {
+                true => result.existing += 1,
+                false => {
+                    generation.generate_and_save(position)?;
+                    result.generated += 1;
+                }
+            }
+            state.next += 1;
+        }
+        self.dirty = true;
+        let percent = state.percent();
+        if percent >= self.reported + REPORT_PERCENT_STEP || state.is_finished() {
+            self.reported = percent / REPORT_PERCENT_STEP * REPORT_PERCENT_STEP;
+            result.report = Some(format!(
+                "Pregenerating {}: {}/{} chunks ({}%)", state.world, state.next.min(state.total()), state.total(), percent,
+            ));
+        }
+        if state.is_finished() {
+            result.finished = true;
+            self.state = None;
+        }
+        self.sync_bar(viewers)?;
+        Ok(result)
+    }
+
+    /// Handles `/pregen <radius>|pause|resume|cancel|status`, `center` is the block position of the sender
+    pub fn handle_command<S: PacketSender>(
+        &mut self,
+        arguments: &str,
+        world: &str,
+        center: Vector2D<i32>,
+        sender: &mut S,
+    ) -> anyhow::Result<Component<'static>> {
+        Ok(match arguments.trim() {
+            "pause" => {
+                self.paused = true;
+                Component::text("Pregeneration is paused")
+            }
+            "resume" => {
+                self.paused = false;
+                Component::text("Pregeneration is resumed")
+            }
+            "cancel" => match self.cancel(sender)? {
+                true => Component::text("Pregeneration is cancelled"),
+                false => Component::text("Pregeneration is not running"),
+            },
+            "status" | "" => match self.state {
+                Some(ref state) => Component::text(format!(
+                    "Pregenerating {}: {}/{} chunks ({}%){}",
+                    state.world, state.next, state.total(), state.percent(), if self.paused { ", paused" } else { "" },
+                )),
+                None => Component::text("Pregeneration is not running"),
+            },
+            radius => match radius.parse::<i32>() {
+                Ok(radius) => match self.start(world, center, radius) {
+                    Ok(()) => {
+                        self.sync_bar(sender)?;
+                        Component::text(format!("Pregenerating {} chunks", self.state.as_ref().unwrap().total()))
+                    }
+                    Err(error) => Component::text(error.to_string()),
+                },
+                Err(_) => Component::text("Usage: /pregen <radius>|pause|resume|cancel|status"),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[derive(Default)]
+    struct Generated(HashSet<Vector2D<i32>>);
+
+    impl ChunkGeneration for Generated {
+        fn is_generated(&self, position: Vector2D<i32>) -> bool {
+            self.0.contains(&position)
+        }
+
+        fn generate_and_save(&mut self, position: Vector2D<i32>) -> anyhow::Result<()> {
+            assert!(self.0.insert(position));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn spiral_test() {
+        let offsets: HashSet<_> = (0..81).map(spiral_offset).collect();
+        assert_eq!(offsets.len(), 81);
+        assert!(offsets.iter().all(|offset| offset.x.abs() <= 4 && offset.y.abs() <= 4));
+        assert_eq!(spiral_offset(1), Vector2D::new(1, 0));
+        assert_eq!(spiral_offset(8), Vector2D::new(1, -1));
+    }
+
+    #[test]
+    fn pregen_test() {
+        let path = std::env::temp_dir().join(format!("bird-pregen-{}.json", std::process::id()));
+        let mut queue = PacketQueue::new();
+        let mut generated = Generated::default();
+        generated.0.insert(Vector2D::new(10, 10));
+        let mut pregen = Pregenerator::new(PregenConfig { chunks_per_tick: 10, max_mspt: 40.0 });
+        pregen.handle_command("40", "world", Vector2D::new(160, 170), &mut queue).unwrap();
+        assert_eq!(queue.take().len(), 1);
+        // 3 chunks in each direction
+        assert_eq!(pregen.state().unwrap().total(), 49);
+        assert!(pregen.start("world", Vector2D::zero(), 10).is_err());
+        assert_eq!(pregen.tick(&mut generated, 45.0, &mut queue).unwrap(), PregenTick::default());
+        let tick = pregen.tick(&mut generated, 20.0, &mut queue).unwrap();
+        assert_eq!((tick.generated, tick.existing), (10, 1));
+        assert_eq!(tick.report.as_deref(), Some("Pregenerating world: 11/49 chunks (22%)"));
+        assert_eq!(queue.take().len(), 2);
+
+        pregen.save(&path).unwrap();
+        let mut pregen = Pregenerator::load(PregenConfig { chunks_per_tick: 10, max_mspt: 40.0 }, &path).unwrap();
+        assert_eq!(pregen.state().unwrap().next, 11);
+        let mut finished = false;
+        while !finished {
+            finished = pregen.tick(&mut generated, 20.0, &mut queue).unwrap().finished;
+        }
+        assert_eq!(generated.0.len(), 49);
+        assert!(pregen.state().is_none());
+        pregen.save(&path).unwrap();
+        assert!(!path.exists());
+        assert!(Pregenerator::load(PregenConfig::default(), &path).unwrap().state().is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use euclid::default::Vector2D;
+use bird_protocol::{anyhow, ProtocolReadable};
+use crate::connection::PacketQueue;
+use crate::protocol::ChunkSectionData;
+use crate::status::ServerStatus;
+use crate::world::{ChunkColumn, World};
+
+/// Vanilla prepares 21x21 chunks around the spawn
+pub const SPAWN_CHUNK_RADIUS: i32 = 10;
+pub const STARTING_MOTD: &'static str = "Starting...";
+
+/// Chunk which is read from the storage, sections are stored in the format of the chunk data packet
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StoredChunk {
+    pub position: Vector2D<i32>,
+    pub sections: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PreloadedChunk {
+    pub position: Vector2D<i32>,
+    pub column: ChunkColumn,
+    /// Chunk packets which are sent to joining players without encoding them again
+    pub encoded: Vec<Vec<u8>>,
+}
+
+/// Progress of the world pre-load, it is shared between the workers, the console and the status
+#[derive(Clone, Debug, Default)]
+pub struct PreloadProgress {
+    done: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+}
+
+impl PreloadProgress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            done: Arc::new(AtomicUsize::new(0)),
+            total: Arc::new(AtomicUsize::new(total)),
+        }
+    }
+
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done() >= self.total()
+    }
+
+    pub fn percent(&self) -> u8 {
+        match self.total() {
+            0 => 100,
+            total => (self.done().min(total) * 100 / total) as u8,
+        }
+    }
+
+    /// Line which is printed to the console like the vanilla one
+    pub fn console_line(&self) -> String {
+        format!("Preparing spawn area: {}%", self.percent())
+    }
+
+    /// Motd of the status while the server is starting, the configured motd after it
+    pub fn motd(&self, status: &ServerStatus) -> String {
+        match self.is_finished() {
+            true => status.motd.clone(),
+            false => format!("{} {}%", STARTING_MOTD, self.percent()),
+        }
+    }
+}
+
+/// Chunks around the spawn, the nearest chunks are the first
+pub fn spawn_chunks(spawn: Vector2D<i32>, radius: i32) -> Vec<Vector2D<i32>> {
+    let mut chunks: Vec<_> = (-radius..=radius)
+        .flat_map(|x| (-radius..=radius).map(move |z| Vector2D::new(x, z)))
+        .collect();
+    chunks.sort_by_key(|offset| offset.x.abs().max(offset.y.abs()));
+    chunks.into_iter().map(|offset| spawn + offset).collect()
+}
+
+/// Reads the sections and builds their palettes, height maps and light
+pub fn decode_chunk(chunk: &StoredChunk, section_count: usize) -> anyhow::Result<ChunkColumn> {
+    let mut cursor = chunk.sections.as_slice();
+    let mut sections = Vec::with_capacity(section_count);
+    for _ in 0..section_count {
+        sections.push(ChunkSectionData::read(&mut cursor)
+            .map_err(|err| anyhow::Error::msg(format!("Chunk {:?} is corrupted: {:?}", chunk.position, err)))?);
+    }
+    if !cursor.is_empty() {
+        return Err(anyhow::Error::msg(format!("Chunk {:?} has {} bytes after the sections", chunk.position, cursor.len())));
+    }
+    let mut column = ChunkColumn::from_sections(sections);
+    column.compute_light();
+    Ok(column)
+}
+
+pub fn encode_chunk(position: Vector2D<i32>, column: &ChunkColumn) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut queue = PacketQueue::new();
+    column.send(position, &mut queue)?;
+    Ok(queue.take())
+}
+
+fn preload_chunk(chunk: &StoredChunk, section_count: usize) -> anyhow::Result<PreloadedChunk> {
+    let column = decode_chunk(chunk, section_count)?;
+    let encoded = encode_chunk(chunk.position, &column)?;
+    Ok(PreloadedChunk { position: chunk.position, column, encoded })
+}
+
+/// Decodes and encodes the chunks on `workers` threads, results are in the order of the chunks
+pub fn preload(
+    chunks: &[StoredChunk],
+    section_count: usize,
+    workers: usize,
+    progress: &PreloadProgress,
+) -> Vec<anyhow::Result<PreloadedChunk>> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let chunk = match chunks.get(index) {
+                        Some(chunk) => chunk,
+                        None => break results,
+                    };
+                    results.push((index, preload_chunk(chunk, section_count)));
+                    progress.advance();
+                }
+            }))
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Inserts preloaded chunks into the world, returns errors of chunks which were not loaded
+pub fn insert_preloaded(world: &mut World, results: Vec<anyhow::Result<PreloadedChunk>>) -> Vec<anyhow::Error> {
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(chunk) => { world.insert_chunk(chunk.position, chunk.column); }
+            Err(err) => errors.push(err),
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_data::Block;
+    use super::*;
+
+    #[test]
+    fn spawn_chunks_order_test() {
+        let spawn = Vector2D::new(5, -3);
+        let chunks = spawn_chunks(spawn, 2);
+        assert_eq!(chunks.len(), 25);
+        let rings: Vec<_> = chunks.iter()
+            .map(|chunk| (chunk.x - spawn.x).abs().max((chunk.y - spawn.y).abs()))
+            .collect();
+        assert!(rings.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(rings.iter().filter(|ring| **ring == 1).count(), 8);
+        // Chunks which are far only on the z axis are loaded after the nearest ring
+        let far_z = chunks.iter().position(|chunk| *chunk == spawn + Vector2D::new(0, 2)).unwrap();
+        assert!(far_z >= 9);
+    }
+
+    #[test]
+    fn preload_test() {
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        let positions = spawn_chunks(Vector2D::new(2, -1), 1);
+        assert_eq!(positions.len(), 9);
+        assert_eq!(positions[0], Vector2D::new(2, -1));
+        let chunks: Vec<_> = positions.iter()
+            .map(|position| {
+                let mut column = ChunkColumn::new(2);
+                column.set_block_state(position.x.rem_euclid(16) as usize, 5, 0, stone);
+                let mut sections = Vec::new();
+                column.write_sections(&mut sections).unwrap();
+                StoredChunk { position: *position, sections }
+            })
+            .chain(std::iter::once(StoredChunk { position: Vector2D::new(10, 10), sections: vec![0, 1] }))
+            .collect();
+
+        let progress = PreloadProgress::new(chunks.len());
+        let mut status = ServerStatus::default();
+        assert_eq!(progress.motd(&status), "Starting... 0%");
+        let results = preload(&chunks, 2, 3, &progress);
+        assert!(progress.is_finished());
+        assert_eq!(progress.console_line(), "Preparing spawn area: 100%");
+        status.motd = "Bird".into();
+        assert_eq!(progress.motd(&status), "Bird");
+        assert_eq!(results.len(), 10);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.position, Vector2D::new(2, -1));
+        assert_eq!(first.column.get_block_state(2, 5, 0), Some(stone));
+        assert!(first.column.light().is_some());
+        assert_eq!(first.encoded.len(), 1);
+
+        let mut world = World::new(2, 0);
+        let errors = insert_preloaded(&mut world, results);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(world.chunks().count(), 9);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use bird_protocol::anyhow;
+use crate::random::LegacyRandom;
+use crate::status::{ServerStatus, VERSION_NAME};
+
+pub const QUERY_MAGIC: [u8; 2] = [0xFE, 0xFD];
+pub const QUERY_TYPE_STAT: u8 = 0;
+pub const QUERY_TYPE_HANDSHAKE: u8 = 9;
+
+/// Challenge tokens are valid for this amount of time
+pub const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+const GAME_TYPE: &'static str = "SMP";
+const GAME_ID: &'static str = "MINECRAFT";
+/// Magic, type, session id and challenge token
+const BASIC_STAT_LENGTH: usize = 11;
+/// Full stat request has 4 padding bytes after the challenge token
+const FULL_STAT_LENGTH: usize = 15;
+const MAX_REQUEST_LENGTH: usize = 1460;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct QueryConfig {
+    pub bind_address: SocketAddr,
+    /// Address of the game port which is reported in stat responses
+    pub server_ip: String,
+    pub server_port: u16,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], 25565)),
+            server_ip: "0.0.0.0".into(),
+            server_port: 25565,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Challenge {
+    token: i32,
+    created: Instant,
+}
+
+/// Sans-IO Query (GS4) responder, every client must request a challenge token before requesting stats
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueryResponder {
+    config: QueryConfig,
+    random: LegacyRandom,
+    challenges: HashMap<SocketAddr, Challenge>,
+}
+
+impl QueryResponder {
+    pub fn new(config: QueryConfig, seed: i64) -> Self {
+        Self {
+            config,
+            random: LegacyRandom::new(seed),
+            challenges: HashMap::new(),
+        }
+    }
+
+    /// Returns the response datagram, invalid requests are ignored like in vanilla
+    pub fn handle(&mut self, from: SocketAddr, data: &[u8], now: Instant, status: &ServerStatus) -> Option<Vec<u8>> {
+        self.challenges.retain(|_, challenge| now.duration_since(challenge.created) < CHALLENGE_LIFETIME);
+        if data.len() < 7 || data[..2] != QUERY_MAGIC {
+            return None;
+        }
+        let session_id = &data[3..7];
+        match data[2] {
+            QUERY_TYPE_HANDSHAKE => {
+                let token = self.random.next_int(16777216);
+                self.challenges.insert(from, Challenge { token, created: now });
+                let mut response = vec![QUERY_TYPE_HANDSHAKE];
+                response.extend_from_slice(session_id);
+                write_string(&mut response, &token.to_string());
+                Some(response)
+            }
+            QUERY_TYPE_STAT if data.len() >= BASIC_STAT_LENGTH => {
+                let token = i32::from_be_bytes(data[7..11].try_into().unwrap());
+                if self.challenges.get(&from).map(|challenge| challenge.token) != Some(token) {
+                    return None;
+                }
+                let mut response = vec![QUERY_TYPE_STAT];
+                response.extend_from_slice(session_id);
+                match data.len() >= FULL_STAT_LENGTH {
+                    true => self.write_full_stat(&mut response, status),
+                    false => self.write_basic_stat(&mut response, status),
+                }
+                Some(response)
+            }
+            _ => None,
+        }
+    }
+
+    fn write_basic_stat(&self, output: &mut Vec<u8>, status: &ServerStatus) {
+        write_string(output, &status.motd);
+        write_string(output, GAME_TYPE);
+        write_string(output, &status.map);
+        write_string(output, &status.online().to_string());
+        write_string(output, &status.displayed_max_players().to_string());
+        output.extend_from_slice(&self.config.server_port.to_le_bytes());
+        write_string(output, &self.config.server_ip);
+    }
+
+    fn write_full_stat(&self, output: &mut Vec<u8>, status: &ServerStatus) {
+        output.extend_from_slice(b"splitnum\x00\x80\x00");
+        let values = [
+            ("hostname", status.motd.clone()),
+            ("gametype", GAME_TYPE.into()),
+            ("game_id", GAME_ID.into()),
+            ("version", VERSION_NAME.into()),
+            ("plugins", String::new()),
+            ("map", status.map.clone()),
+            ("numplayers", status.online().to_string()),
+            ("maxplayers", status.displayed_max_players().to_string()),
+            ("hostport", self.config.server_port.to_string()),
+            ("hostip", self.config.server_ip.clone()),
+        ];
+        for (key, value) in values {
+            write_string(output, key);
+            write_string(output, &value);
+        }
+        output.push(0);
+        output.extend_from_slice(b"\x01player_\x00\x00");
+        for player in &status.players {
+            write_string(output, &player.name);
+        }
+        output.push(0);
+    }
+}
+
+fn write_string(output: &mut Vec<u8>, value: &str) {
+    output.extend_from_slice(value.as_bytes());
+    output.push(0);
+}
+
+/// Query listener, the status is shared with the status handler of the game port
+#[derive(Debug)]
+pub struct QueryServer {
+    socket: UdpSocket,
+    responder: QueryResponder,
+}
+
+impl QueryServer {
+    pub fn bind(config: QueryConfig) -> anyhow::Result<Self> {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as i64;
+        Ok(Self {
+            socket: UdpSocket::bind(config.bind_address)?,
+            responder: QueryResponder::new(config, seed),
+        })
+    }
+
+    pub fn local_address(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Answers requests until the socket fails
+    pub fn run(mut self, status: Arc<RwLock<ServerStatus>>) -> anyhow::Result<()> {
+        let mut buffer = [0; MAX_REQUEST_LENGTH];
+        loop {
+            let (length, from) = self.socket.recv_from(&mut buffer)?;
+            let response = {
+                let status = status.read().map_err(|_| anyhow::Error::msg("Server status lock is poisoned"))?;
+                self.responder.handle(from, &buffer[..length], Instant::now(), &status)
+            };
+            if let Some(response) = response {
+                self.socket.send_to(&response, from)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use crate::status::StatusPlayer;
+    use super::*;
+
+    fn request(ty: u8, token: Option<i32>, padding: bool) -> Vec<u8> {
+        let mut request = QUERY_MAGIC.to_vec();
+        request.push(ty);
+        request.extend_from_slice(&1i32.to_be_bytes());
+        if let Some(token) = token {
+            request.extend_from_slice(&token.to_be_bytes());
+        }
+        if padding {
+            request.extend_from_slice(&[0; 4]);
+        }
+        request
+    }
+
+    #[test]
+    fn query_test() {
+        let mut responder = QueryResponder::new(QueryConfig::default(), 0);
+        let status = ServerStatus {
+            players: vec![StatusPlayer { name: "Notch".into(), uuid: Uuid::nil() }],
+            ..Default::default()
+        };
+        let from = SocketAddr::from(([127, 0, 0, 1], 40000));
+        let now = Instant::now();
+        assert_eq!(responder.handle(from, &request(QUERY_TYPE_STAT, Some(0), false), now, &status), None);
+        let handshake = responder.handle(from, &request(QUERY_TYPE_HANDSHAKE, None, false), now, &status).unwrap();
+        assert_eq!(&handshake[..5], &[QUERY_TYPE_HANDSHAKE, 0, 0, 0, 1]);
+        let token: i32 = std::str::from_utf8(&handshake[5..handshake.len() - 1]).unwrap().parse().unwrap();
+        let basic = responder.handle(from, &request(QUERY_TYPE_STAT, Some(token), false), now, &status).unwrap();
+        let mut expected = vec![QUERY_TYPE_STAT, 0, 0, 0, 1];
+        expected.extend_from_slice(b"A Minecraft Server\x00SMP\x00world\x001\x0020\x00\xdd\x630.0.0.0\x00");
+        assert_eq!(basic, expected);
+        let full = responder.handle(from, &request(QUERY_TYPE_STAT, Some(token), true), now, &status).unwrap();
+        assert!(full.ends_with(b"\x00\x01player_\x00\x00Notch\x00\x00"));
+        let later = now + CHALLENGE_LIFETIME;
+        assert_eq!(responder.handle(from, &request(QUERY_TYPE_STAT, Some(token), false), later, &status), None);
+    }
+}
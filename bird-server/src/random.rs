@@ -0,0 +1,65 @@
+const MULTIPLIER: i64 = 0x5DEECE66D;
+const ADDEND: i64 = 0xB;
+const MASK: i64 = (1 << 48) - 1;
+
+/// Linear congruential generator of `java.util.Random` which vanilla uses for gameplay randomness
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LegacyRandom {
+    seed: i64,
+}
+
+impl LegacyRandom {
+    pub const fn new(seed: i64) -> Self {
+        Self { seed: (seed ^ MULTIPLIER) & MASK }
+    }
+
+    pub fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(MULTIPLIER).wrapping_add(ADDEND) & MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    pub fn next_i32(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    /// Returns value in `0..bound`, panics if the bound is not positive
+    pub fn next_int(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0);
+        if bound & (bound - 1) == 0 {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+        }
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            if bits.checked_sub(value).and_then(|bits| bits.checked_add(bound - 1)).is_some() {
+                return value;
+            }
+        }
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next(1) != 0
+    }
+
+    pub fn next_float(&mut self) -> f32 {
+        self.next(24) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns value in `min..=max`
+    pub fn next_between(&mut self, min: i32, max: i32) -> i32 {
+        min + self.next_int(max - min + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_random_test() {
+        let mut random = LegacyRandom::new(0);
+        assert_eq!(random.next_i32(), -1155484576);
+        let mut random = LegacyRandom::new(42);
+        assert_eq!(random.next_int(10), 0);
+    }
+}
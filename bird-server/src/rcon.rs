@@ -0,0 +1,261 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use bird_protocol::anyhow;
+
+pub const RCON_DEFAULT_PORT: u16 = 25575;
+
+pub const RCON_TYPE_RESPONSE: i32 = 0;
+pub const RCON_TYPE_COMMAND: i32 = 2;
+pub const RCON_TYPE_AUTH_RESPONSE: i32 = 2;
+pub const RCON_TYPE_AUTH: i32 = 3;
+/// Request id of the auth response when the password is wrong
+pub const RCON_AUTH_FAILURE: i32 = -1;
+
+/// Request id, type and two null terminators
+const MIN_PACKET_LENGTH: usize = 10;
+/// Vanilla reads requests with a buffer of this size
+const MAX_REQUEST_LENGTH: usize = 1460;
+/// Longer responses are split into several packets
+const MAX_RESPONSE_BODY: usize = 4096;
+
+/// Source RCON packet, all integers are little endian
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RconPacket {
+    pub request_id: i32,
+    pub ty: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub fn write(&self, output: &mut Vec<u8>) {
+        let body = self.body.as_bytes();
+        output.extend_from_slice(&((MIN_PACKET_LENGTH + body.len()) as i32).to_le_bytes());
+        output.extend_from_slice(&self.request_id.to_le_bytes());
+        output.extend_from_slice(&self.ty.to_le_bytes());
+        output.extend_from_slice(body);
+        output.extend_from_slice(&[0, 0]);
+    }
+
+    /// Returns the packet and the amount of consumed bytes or `None` if the packet is not fully received
+    pub fn read(input: &[u8]) -> anyhow::Result<Option<(Self, usize)>> {
+        let int = |offset: usize| i32::from_le_bytes(input[offset..offset + 4].try_into().unwrap());
+        if input.len() < 4 {
+            return Ok(None);
+        }
+        let length = int(0);
+        if length < MIN_PACKET_LENGTH as i32 || length > MAX_REQUEST_LENGTH as i32 {
+            return Err(anyhow::Error::msg("Bad rcon packet length"));
+        }
+        let end = 4 + length as usize;
+        if input.len() < end {
+            return Ok(None);
+        }
+        let body = &input[12..end - 2];
+        let body = match body.iter().position(|byte| *byte == 0) {
+            Some(position) => &body[..position],
+            None => body,
+        };
+        Ok(Some((Self {
+            request_id: int(4),
+            ty: int(8),
+            body: String::from_utf8_lossy(body).into_owned(),
+        }, end)))
+    }
+}
+
+/// Sans-IO state of one rcon client, `execute` runs the command and returns its output
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RconConnection {
+    password: String,
+    authenticated: bool,
+    buffer: Vec<u8>,
+}
+
+impl RconConnection {
+    pub fn new(password: String) -> Self {
+        Self { password, authenticated: false, buffer: Vec::new() }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// Handles received bytes and writes responses to the output, an error means that the connection should be closed
+    pub fn receive<F>(&mut self, data: &[u8], output: &mut Vec<u8>, execute: &mut F) -> anyhow::Result<()>
+        where F: FnMut(&str) -> String {
+        self.buffer.extend_from_slice(data);
+        let mut consumed = 0;
+        while let Some((packet, length)) = RconPacket::read(&self.buffer[consumed..])? {
+            consumed += length;
+            self.handle(packet, output, execute);
+        }
+        self.buffer.drain(..consumed);
+        Ok(())
+    }
+
+    fn handle<F>(&mut self, packet: RconPacket, output: &mut Vec<u8>, execute: &mut F)
+        where F: FnMut(&str) -> String {
+        match packet.ty {
+            RCON_TYPE_AUTH => {
+                self.authenticated = packet.body == self.password;
+                RconPacket {
+                    request_id: match self.authenticated {
+                        true => packet.request_id,
+                        false => RCON_AUTH_FAILURE,
+                    },
+                    ty: RCON_TYPE_AUTH_RESPONSE,
+                    body: String::new(),
+                }.write(output);
+            }
+            _ if !self.authenticated => RconPacket {
+                request_id: RCON_AUTH_FAILURE,
+                ty: RCON_TYPE_AUTH_RESPONSE,
+                body: String::new(),
+            }.write(output),
+            RCON_TYPE_COMMAND => write_response(packet.request_id, &execute(&packet.body), output),
+            ty => write_response(packet.request_id, &format!("Unknown request {:x}", ty), output),
+        }
+    }
+}
+
+/// Writes the response splitting it into packets of at most 4096 bytes
+pub fn write_response(request_id: i32, response: &str, output: &mut Vec<u8>) {
+    let mut rest = response;
+    loop {
+        let mut split = rest.len().min(MAX_RESPONSE_BODY);
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        let (body, next) = rest.split_at(split);
+        RconPacket { request_id, ty: RCON_TYPE_RESPONSE, body: body.into() }.write(output);
+        if next.is_empty() {
+            break;
+        }
+        rest = next;
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RconConfig {
+    pub bind_address: SocketAddr,
+    pub password: String,
+}
+
+impl RconConfig {
+    pub fn new(password: String) -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], RCON_DEFAULT_PORT)),
+            password,
+        }
+    }
+}
+
+/// Rcon listener which serves every client in its own thread
+#[derive(Debug)]
+pub struct RconServer {
+    listener: TcpListener,
+    password: String,
+}
+
+impl RconServer {
+    pub fn bind(config: RconConfig) -> anyhow::Result<Self> {
+        if config.password.is_empty() {
+            return Err(anyhow::Error::msg("No rcon password set"));
+        }
+        Ok(Self {
+            listener: TcpListener::bind(config.bind_address)?,
+            password: config.password,
+        })
+    }
+
+    pub fn local_address(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accepts clients until the listener fails, `execute` is called from client threads
+    pub fn run<F>(self, execute: F) -> anyhow::Result<()>
+        where F: Fn(&str) -> String + Send + Sync + 'static {
+        let execute = Arc::new(execute);
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            let execute = execute.clone();
+            let connection = RconConnection::new(self.password.clone());
+            std::thread::spawn(move || {
+                // The client is disconnected on any error
+                let _ = serve(stream, connection, |command| execute(command));
+            });
+        }
+    }
+}
+
+fn serve<F>(mut stream: TcpStream, mut connection: RconConnection, mut execute: F) -> anyhow::Result<()>
+    where F: FnMut(&str) -> String {
+    let mut buffer = [0; MAX_REQUEST_LENGTH];
+    let mut output = Vec::new();
+    loop {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        connection.receive(&buffer[..read], &mut output, &mut execute)?;
+        stream.write_all(&output)?;
+        output.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(request_id: i32, ty: i32, body: &str) -> Vec<u8> {
+        let mut output = Vec::new();
+        RconPacket { request_id, ty, body: body.into() }.write(&mut output);
+        output
+    }
+
+    fn read_all(mut input: &[u8]) -> Vec<RconPacket> {
+        let mut packets = Vec::new();
+        while let Some((packet, length)) = RconPacket::read(input).unwrap() {
+            packets.push(packet);
+            input = &input[length..];
+        }
+        packets
+    }
+
+    /// Responses are longer than requests so they can't be read with `RconPacket::read`
+    fn body_lengths(mut input: &[u8]) -> Vec<usize> {
+        let mut lengths = Vec::new();
+        while input.len() >= 4 {
+            let length = i32::from_le_bytes(input[..4].try_into().unwrap()) as usize;
+            lengths.push(length - MIN_PACKET_LENGTH);
+            input = &input[4 + length..];
+        }
+        lengths
+    }
+
+    #[test]
+    fn rcon_test() {
+        let mut connection = RconConnection::new("secret".into());
+        let mut execute = |command: &str| format!("executed {}", command);
+        let mut output = Vec::new();
+        connection.receive(&packet(1, RCON_TYPE_COMMAND, "list"), &mut output, &mut execute).unwrap();
+        connection.receive(&packet(2, RCON_TYPE_AUTH, "wrong"), &mut output, &mut execute).unwrap();
+        let auth = packet(3, RCON_TYPE_AUTH, "secret");
+        connection.receive(&auth[..5], &mut output, &mut execute).unwrap();
+        connection.receive(&auth[5..], &mut output, &mut execute).unwrap();
+        assert!(connection.is_authenticated());
+        connection.receive(&packet(4, RCON_TYPE_COMMAND, "list"), &mut output, &mut execute).unwrap();
+        let packets = read_all(&output);
+        assert_eq!(packets.iter().map(|packet| packet.request_id).collect::<Vec<_>>(), vec![-1, -1, 3, 4]);
+        assert_eq!(packets[3], RconPacket { request_id: 4, ty: RCON_TYPE_RESPONSE, body: "executed list".into() });
+        assert!(connection.receive(&[0, 0, 0, 0], &mut output, &mut execute).is_err());
+    }
+
+    #[test]
+    fn split_response_test() {
+        let mut output = Vec::new();
+        write_response(1, &"a".repeat(5000), &mut output);
+        assert_eq!(body_lengths(&output), vec![4096, 904]);
+    }
+}
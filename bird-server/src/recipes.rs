@@ -0,0 +1,255 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use bird_chat::identifier::Identifier;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::cooking::{CookingKind, CookingRecipes};
+use crate::menu::{CraftingRecipe, Ingredient, RecipeBook, SmithingRecipe};
+use crate::protocol::*;
+
+/// Recipe ids without the namespace are in the `minecraft` namespace
+pub fn recipe_identifier(id: &str) -> anyhow::Result<Identifier<'_>> {
+    match id.contains(':') {
+        true => Identifier::new_full(Cow::Borrowed(id)),
+        false => Identifier::new_partial(Cow::Borrowed("minecraft"), Cow::Borrowed(id)),
+    }.ok_or_else(|| anyhow::Error::msg(format!("{} is not a recipe id", id)))
+}
+
+fn item_slot(item_id: i32, item_count: i8) -> Slot<'static> {
+    Slot { item_id, item_count, nbt: None }
+}
+
+fn ingredient_data(ingredient: &Ingredient) -> RecipeIngredient<'static> {
+    RecipeIngredient {
+        items: Cow::Owned(ingredient.items().iter().map(|&item_id| Some(item_slot(item_id, 1))).collect()),
+    }
+}
+
+/// Recipes of the server for [`UpdateRecipesPS2C`]. Smithing recipes with templates and trims
+/// don't exist in this version of the client, so they are not declared
+pub fn declare_recipes<'a>(book: &'a RecipeBook, cooking: &'a CookingRecipes) -> anyhow::Result<UpdateRecipesPS2C<'a>> {
+    let mut recipes = Vec::new();
+    for recipe in book.crafting_recipes() {
+        let variant = match recipe {
+            CraftingRecipe::Shaped { width, height, ingredients, result, .. } => DeclaredRecipeVariant::Shaped(ShapedRecipeData {
+                width: *width as i32,
+                height: *height as i32,
+                group: "",
+                category: CraftingRecipeCategory::Misc,
+                ingredients: Cow::Owned(ingredients.iter().map(ingredient_data).collect()),
+                result: Some(result.as_slot()),
+            }),
+            CraftingRecipe::Shapeless { ingredients, result, .. } => DeclaredRecipeVariant::Shapeless(ShapelessRecipeData {
+                group: "",
+                category: CraftingRecipeCategory::Misc,
+                ingredients: Cow::Owned(ingredients.iter().map(ingredient_data).collect()),
+                result: Some(result.as_slot()),
+            }),
+        };
+        recipes.push(DeclaredRecipe { id: recipe_identifier(recipe.id())?, variant });
+    }
+    for recipe in cooking.recipes() {
+        let data = CookingRecipeData {
+            group: "",
+            category: match recipe.kind {
+                CookingKind::Smoking | CookingKind::CampfireCooking => CookingRecipeCategory::Food,
+                CookingKind::Smelting | CookingKind::Blasting => CookingRecipeCategory::Misc,
+            },
+            ingredient: ingredient_data(&recipe.ingredient),
+            result: Some(item_slot(recipe.result, recipe.count)),
+            experience: recipe.experience,
+            cooking_time: recipe.cooking_time as i32,
+        };
+        recipes.push(DeclaredRecipe {
+            id: recipe_identifier(&recipe.id)?,
+            variant: match recipe.kind {
+                CookingKind::Smelting => DeclaredRecipeVariant::Smelting(data),
+                CookingKind::Blasting => DeclaredRecipeVariant::Blasting(data),
+                CookingKind::Smoking => DeclaredRecipeVariant::Smoking(data),
+                CookingKind::CampfireCooking => DeclaredRecipeVariant::CampfireCooking(data),
+            },
+        });
+    }
+    for recipe in book.stonecutting_recipes() {
+        recipes.push(DeclaredRecipe {
+            id: recipe_identifier(&recipe.id)?,
+            variant: DeclaredRecipeVariant::Stonecutting(StonecuttingRecipeData {
+                group: "",
+                ingredient: ingredient_data(&recipe.ingredient),
+                result: Some(item_slot(recipe.result, recipe.count)),
+            }),
+        });
+    }
+    for recipe in book.smithing_recipes() {
+        if let SmithingRecipe::Transform { id, template, base, addition, result } = recipe {
+            if template.items().is_empty() {
+                recipes.push(DeclaredRecipe {
+                    id: recipe_identifier(id)?,
+                    variant: DeclaredRecipeVariant::Smithing(SmithingRecipeData {
+                        base: ingredient_data(base),
+                        addition: ingredient_data(addition),
+                        result: Some(item_slot(*result, 1)),
+                    }),
+                });
+            }
+        }
+    }
+    Ok(UpdateRecipesPS2C { recipes: Cow::Owned(recipes) })
+}
+
+/// Full id of the recipe, unlocked recipes are stored by it
+fn full_id(id: &str) -> anyhow::Result<String> {
+    recipe_identifier(id).map(|id| id.to_string())
+}
+
+/// Recipes which are unlocked by the player, the client shows only them in the recipe book
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct UnlockedRecipes {
+    unlocked: BTreeSet<String>,
+    /// Unlocked recipes which the player hasn't seen yet
+    highlighted: BTreeSet<String>,
+    pub settings: RecipeBookSettings,
+}
+
+impl UnlockedRecipes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        full_id(id).map_or(false, |id| self.unlocked.contains(&id))
+    }
+
+    pub fn is_highlighted(&self, id: &str) -> bool {
+        full_id(id).map_or(false, |id| self.highlighted.contains(&id))
+    }
+
+    /// The player has seen the recipe in the book
+    pub fn mark_seen(&mut self, id: &str) {
+        if let Ok(id) = full_id(id) {
+            self.highlighted.remove(&id);
+        }
+    }
+
+    /// Sends every unlocked recipe, it is sent after the recipes are declared on join
+    pub fn send_init<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&UpdateRecipeBookPS2C {
+            action: RecipeBookAction::Init,
+            settings: self.settings,
+            recipes: Cow::Owned(self.unlocked.iter().map(|id| recipe_identifier(id)).collect::<anyhow::Result<_>>()?),
+            highlighted: Cow::Owned(self.highlighted.iter().map(|id| recipe_identifier(id)).collect::<anyhow::Result<_>>()?),
+        })
+    }
+
+    /// Unlocks and highlights the recipes, the client shows the toast with them.
+    /// Returns the amount of newly unlocked recipes
+    pub fn unlock<S: PacketSender>(&mut self, ids: &[&str], sender: &mut S) -> anyhow::Result<usize> {
+        let mut recipes = Vec::new();
+        for &id in ids {
+            let id = full_id(id)?;
+            if !self.unlocked.contains(&id) && !recipes.contains(&id) {
+                recipes.push(id);
+            }
+        }
+        self.send_update(RecipeBookAction::Add, &recipes, sender)?;
+        self.highlighted.extend(recipes.iter().cloned());
+        self.unlocked.extend(recipes.iter().cloned());
+        Ok(recipes.len())
+    }
+
+    /// Returns the amount of recipes which were unlocked before
+    pub fn lock<S: PacketSender>(&mut self, ids: &[&str], sender: &mut S) -> anyhow::Result<usize> {
+        let mut recipes = Vec::new();
+        for &id in ids {
+            let id = full_id(id)?;
+            if self.unlocked.remove(&id) {
+                self.highlighted.remove(&id);
+                recipes.push(id);
+            }
+        }
+        self.send_update(RecipeBookAction::Remove, &recipes, sender)?;
+        Ok(recipes.len())
+    }
+
+    fn send_update<S: PacketSender>(&self, action: RecipeBookAction, recipes: &[String], sender: &mut S) -> anyhow::Result<()> {
+        if recipes.is_empty() {
+            return Ok(());
+        }
+        sender.send_packet(&UpdateRecipeBookPS2C {
+            action,
+            settings: self.settings,
+            recipes: Cow::Owned(recipes.iter().map(|id| recipe_identifier(id)).collect::<anyhow::Result<_>>()?),
+            highlighted: Cow::Borrowed(&[]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::{encode_packet, PacketQueue};
+    use crate::cooking::CookingRecipe;
+    use crate::menu::StonecuttingRecipe;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use crate::test_util::item;
+    use super::*;
+
+    #[test]
+    fn recipes_test() {
+        let mut cooking = CookingRecipes::new();
+        cooking.add(CookingRecipe::new("cooked_beef_from_smoking", CookingKind::Smoking, Ingredient::of(&[item("beef")]), item("cooked_beef"), 0.35));
+        let mut book = RecipeBook::new();
+        book
+            .add_stonecutting(StonecuttingRecipe { id: "custom:stone_slab".into(), ingredient: Ingredient::of(&[item("stone")]), result: item("stone_slab"), count: 2 })
+            .add_smithing(SmithingRecipe::Transform {
+                id: "netherite_sword_smithing".into(),
+                template: Ingredient::empty(),
+                base: Ingredient::of(&[item("diamond_sword")]),
+                addition: Ingredient::of(&[item("netherite_ingot")]),
+                result: item("netherite_sword"),
+            });
+        let mut packet = declare_recipes(&book, &cooking).unwrap();
+        let shaped = ShapedRecipeData {
+            width: 2,
+            height: 1,
+            group: "planks",
+            category: CraftingRecipeCategory::Building,
+            ingredients: Cow::Owned(vec![ingredient_data(&Ingredient::of(&[item("stick")])), ingredient_data(&Ingredient::empty())]),
+            result: Some(item_slot(item("oak_planks"), 4)),
+        };
+        packet.recipes.to_mut().push(DeclaredRecipe { id: recipe_identifier("custom:shaped").unwrap(), variant: DeclaredRecipeVariant::Shaped(shaped) });
+        packet.recipes.to_mut().push(DeclaredRecipe {
+            id: recipe_identifier("armor_dye").unwrap(),
+            variant: DeclaredRecipeVariant::Special {
+                ty: recipe_identifier("crafting_special_armordye").unwrap(),
+                category: CraftingRecipeCategory::Misc,
+            },
+        });
+        assert_eq!(packet.recipes.len(), 5);
+        assert_eq!(packet.recipes[0].variant.ty(), RECIPE_SMOKING);
+        assert_eq!(packet.recipes[0].id.to_string(), "minecraft:cooked_beef_from_smoking");
+        let bytes = encode_packet(&packet).unwrap();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut bytes.as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::UpdateRecipes(read))) => assert_eq!(read, packet),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+
+        let mut unlocked = UnlockedRecipes::new();
+        let mut sender = PacketQueue::new();
+        assert_eq!(unlocked.unlock(&["custom:shaped", "armor_dye", "minecraft:armor_dye"], &mut sender).unwrap(), 2);
+        assert_eq!(unlocked.unlock(&["custom:shaped"], &mut sender).unwrap(), 0);
+        assert!(unlocked.is_highlighted("minecraft:armor_dye"));
+        unlocked.mark_seen("armor_dye");
+        assert_eq!(unlocked.lock(&["custom:shaped", "custom:missing"], &mut sender).unwrap(), 1);
+        assert_eq!(sender.take().len(), 2);
+        unlocked.send_init(&mut sender).unwrap();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut sender.take()[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::UpdateRecipeBook(packet))) => {
+                assert_eq!(packet.action, RecipeBookAction::Init);
+                assert_eq!(packet.recipes.iter().map(ToString::to_string).collect::<Vec<_>>(), ["minecraft:armor_dye"]);
+                assert!(packet.highlighted.is_empty());
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use bird_protocol::{ProtocolCursor, ProtocolPacket, ProtocolPacketBound, ProtocolPacketState, ProtocolReadable, ProtocolResult, ProtocolVariantReadable, ProtocolVariantWritable, ProtocolWritable, ProtocolWriter, RemainingBytesArray, VarInt, anyhow};
+use crate::instrument::trace_span;
+use crate::protocol::*;
+use crate::version::PacketIdTable;
+
+/// Generates an enum of all packets of one state and bound, `$top` is the variant of [`Packet`]
+macro_rules! packets {
+    ($ident: ident<$lt: lifetime> => $top: ident, $state: ident, $bound: ident, { $($variant: ident($packet: ident $(<$plt: lifetime>)?)),* $(,)? }) => {
+        #[derive(Clone, Debug)]
+        pub enum $ident<$lt> {
+            $($variant($packet $(<$plt>)?),)*
+        }
+
+        impl<$lt> $ident<$lt> {
+            packets!(@methods $lt, $state, $bound, { $($variant($packet $(<$plt>)?)),* });
+        }
+
+        $(registry_packet!($top, $ident, $variant, $packet $(<$plt>)?);)*
+    };
+    ($ident: ident => $top: ident, $state: ident, $bound: ident, { $($variant: ident($packet: ident)),* $(,)? }) => {
+        #[derive(Clone, Debug)]
+        pub enum $ident {
+            $($variant($packet),)*
+        }
+
+        impl<'a> $ident {
+            packets!(@methods 'a, $state, $bound, { $($variant($packet)),* });
+        }
+
+        $(registry_packet!($top, $ident, $variant, $packet);)*
+    };
+    (@methods $lt: lifetime, $state: ident, $bound: ident, { $($variant: ident($packet: ty)),* }) => {
+        pub const STATE: ProtocolPacketState = ProtocolPacketState::$state;
+        pub const BOUND: ProtocolPacketBound = ProtocolPacketBound::$bound;
+
+        /// Reads the body of the packet, returns `None` if the id is unknown
+        pub fn read<C: ProtocolCursor<$lt>>(id: i32, cursor: &mut C) -> ProtocolResult<Option<Self>> {
+            $(
+                if id == <$packet as ProtocolPacket>::ID {
+                    return Ok(Some(Self::$variant(<$packet as ProtocolReadable<$lt>>::read(cursor)?)));
+                }
+            )*
+            Ok(None)
+        }
+
+        pub fn id(&self) -> i32 {
+            match self {
+                $(Self::$variant(_) => <$packet as ProtocolPacket>::ID,)*
+            }
+        }
+
+        /// Writes the id and the body of the packet
+        pub fn write<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+            VarInt::write_variant(&self.id(), writer)?;
+            match self {
+                $(Self::$variant(packet) => packet.write(writer),)*
+            }
+        }
+
+        /// Round trips samples of every packet, so all packets of the registry must have samples
+        #[cfg(test)]
+        pub fn assert_samples_round_trip(seed: u64, count: usize) {
+            let mut seed = seed;
+            $(
+                seed += 1;
+                bird_protocol::sample::assert_samples_round_trip::<$packet>(seed, count);
+            )*
+        }
+    };
+}
+
+macro_rules! registry_packet {
+    ($top: ident, $ident: ident, $variant: ident, $packet: ident<$lt: lifetime>) => {
+        impl RegistryPacket for $packet<'static> {
+            type Packet<'a> = $packet<'a>;
+
+            fn from_packet<'p, 'a>(packet: &'p Packet<'a>) -> Option<&'p $packet<'a>> {
+                match packet {
+                    Packet::$top($ident::$variant(packet)) => Some(packet),
+                    _ => None,
+                }
+            }
+        }
+    };
+    ($top: ident, $ident: ident, $variant: ident, $packet: ident) => {
+        impl RegistryPacket for $packet {
+            type Packet<'a> = $packet;
+
+            fn from_packet<'p, 'a>(packet: &'p Packet<'a>) -> Option<&'p $packet> {
+                match packet {
+                    Packet::$top($ident::$variant(packet)) => Some(packet),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/// Packet which can be taken out of [`Packet`], implemented for the `'static` version of the packet
+pub trait RegistryPacket: ProtocolPacket + 'static {
+    type Packet<'a>;
+
+    fn from_packet<'p, 'a>(packet: &'p Packet<'a>) -> Option<&'p Self::Packet<'a>>;
+}
+
+packets!(HandshakeC2SPacket<'a> => HandshakeC2S, Handshake, Server, {
+    Handshake(Handshake<'a>),
+});
+
+packets!(StatusS2CPacket<'a> => StatusS2C, Status, Client, {
+    StatusResponse(StatusResponseSS2C<'a>),
+    PingResponse(PingResponseSS2C),
+});
+
+packets!(StatusC2SPacket => StatusC2S, Status, Server, {
+    StatusRequest(StatusRequest),
+    PingRequest(PingRequestSC2S),
+});
+
+packets!(LoginS2CPacket<'a> => LoginS2C, Login, Client, {
+    LoginDisconnect(LoginDisconnectLS2C<'a>),
+    EncryptionRequest(EncryptionRequestLS2C<'a>),
+    LoginSuccess(LoginSuccessLS2C<'a>),
+    SetCompression(SetCompressionLS2C),
+    LoginPluginRequest(LoginPluginRequestLS2C<'a>),
+});
+
+packets!(LoginC2SPacket<'a> => LoginC2S, Login, Server, {
+    LoginStart(LoginStartLC2S<'a>),
+    EncryptionResponse(EncryptionResponseLC2S<'a>),
+    LoginPluginResponse(LoginPluginResponseLC2S<'a>),
+});
+
+packets!(PlayS2CPacket<'a> => PlayS2C, Play, Client, {
+    SpawnEntity(SpawnEntityPS2C),
+    SpawnExperienceOrb(SpawnExperienceOrbPS2C),
+    SpawnPlayer(SpawnPlayerPS2C),
+    EntityAnimation(EntityAnimationPS2C),
+    AwardStatistics(AwardStatisticsPS2C<'a>),
+    AcknowledgeBlockChange(AcknowledgeBlockChangePS2C),
+    SetBlockDestroyStage(SetBlockDestroyStagePS2C),
+    BlockEntityData(BlockEntityDataPS2C<'a>),
+    BlockAction(BlockActionPS2C),
+    BlockUpdate(BlockUpdatePS2C),
+    BossBar(BossBarPS2C<'a>),
+    ChangeDifficulty(ChangeDifficultyPS2C),
+    ClearTitles(ClearTitles),
+    CommandSuggestionsResponse(CommandSuggestionsResponsePS2C<'a>),
+    Commands(CommandsPS2C<'a>),
+    CloseContainer(CloseContainerPS2C),
+    SetContainerContent(SetContainerContentPS2C<'a>),
+    SetContainerProperty(SetContainerPropertyPS2C),
+    SetContainerSlot(SetContainerSlotPS2C<'a>),
+    SetCooldown(SetCooldownPS2C),
+    ChatSuggestions(ChatSuggestionsPS2C<'a>),
+    PluginMessage(PluginMessagePS2C<'a>),
+    DeleteMessage(DeleteMessagePS2C<'a>),
+    Disconnect(DisconnectPS2C<'a>),
+    DisguisedChatMessage(DisguisedChatMessagePS2C<'a>),
+    EntityEvent(EntityEventPS2C),
+    Explosion(ExplosionPS2C<'a>),
+    UnloadChunk(UnloadChunkPS2C),
+    GameEvent(GameEventPS2C),
+    OpenHorseScreen(OpenHorseScreenPS2C),
+    InitializeWorldBorder(InitializeWorldBorderPS2C),
+    KeepAlive(KeepAlivePS2C),
+    ChunkDataAndUpdateLight(ChunkDataAndUpdateLightPS2C<'a>),
+    WorldEvent(WorldEventPS2C),
+    Particle(ParticlePS2C<'a>),
+    UpdateLight(UpdateLightPS2C<'a>),
+    Login(LoginPS2C<'a>),
+    MapData(MapDataPS2C<'a>),
+    MerchantOffers(MerchantOffersPS2C<'a>),
+    UpdateEntityPosition(UpdateEntityPositionPS2C),
+    UpdateEntityPositionAndRotation(UpdateEntityPositionAndRotationPS2C),
+    UpdateEntityRotation(UpdateEntityRotationPS2C),
+    MoveVehicle(MoveVehiclePS2C),
+    OpenBook(OpenBookPS2C),
+    OpenScreen(OpenScreenPS2C<'a>),
+    OpenSignEditor(OpenSignEditorPS2C),
+    Ping(PingPS2C),
+    PlaceGhostRecipe(PlaceGhostRecipePS2C<'a>),
+    PlayerAbilities(PlayerAbilitiesPS2C),
+    PlayerChatMessage(PlayerChatMessagePS2C<'a>),
+    EndCombat(EndCombatPS2C),
+    EnterCombat(EnterCombatPS2C),
+    CombatDeath(CombatDeathPS2C<'a>),
+    PlayerInfoRemove(PlayerInfoRemovePS2C<'a>),
+    PlayerInfoUpdate(PlayerInfoUpdatePS2C<'a>),
+    SynchronizePlayerPosition(SynchronizePlayerPositionPS2C),
+    UpdateRecipeBook(UpdateRecipeBookPS2C<'a>),
+    RemoveEntities(RemoveEntitiesPS2C<'a>),
+    ResourcePack(ResourcePackPS2C<'a>),
+    Respawn(RespawnPS2C<'a>),
+    SetHeadRotation(SetHeadRotationPS2C),
+    UpdateSectionBlocks(UpdateSectionBlocksPS2C<'a>),
+    SetBorderCenter(SetBorderCenterPS2C),
+    SetBorderLerpSize(SetBorderLerpSizePS2C),
+    SetBorderSize(SetBorderSizePS2C),
+    SetBorderWarningDelay(SetBorderWarningDelayPS2C),
+    SetBorderWarningDistance(SetBorderWarningDistancePS2C),
+    SetCenterChunk(SetCenterChunkPS2C),
+    SetRenderDistance(SetRenderDistancePS2C),
+    SetEntityMetadata(SetEntityMetadataPS2C<'a>),
+    LinkEntities(LinkEntitiesPS2C),
+    SetEntityVelocity(SetEntityVelocityPS2C),
+    SetEquipment(SetEquipmentPS2C<'a>),
+    SetPassengers(SetPassengersPS2C<'a>),
+    SetSimulationDistance(SetSimulationDistancePS2C),
+    UpdateTime(UpdateTimePS2C),
+    EntitySoundEffect(EntitySoundEffectPS2C<'a>),
+    SoundEffect(SoundEffectPS2C<'a>),
+    StopSound(StopSoundPS2C<'a>),
+    SystemChatMessage(SystemChatMessagePS2C<'a>),
+    TeleportEntity(TeleportEntityPS2C),
+    UpdateAdvancements(UpdateAdvancementsPS2C<'a>),
+    UpdateRecipes(UpdateRecipesPS2C<'a>),
+    UpdateTags(UpdateTagsPS2C<'a>),
+});
+
+packets!(PlayC2SPacket<'a> => PlayC2S, Play, Server, {
+    ConfirmTeleportation(ConfirmTeleportationPC2S),
+    MessageAcknowledgment(MessageAcknowledgmentPC2S),
+    ChatCommand(ChatCommandPC2S<'a>),
+    ChatMessage(ChatMessagePC2S<'a>),
+    ClickContainerButton(ClickContainerButtonPC2S),
+    ClickContainer(ClickContainerPC2S<'a>),
+    CloseContainer(CloseContainerPC2S),
+    PluginMessage(PluginMessagePC2S<'a>),
+    EditBook(EditBookPC2S<'a>),
+    Interact(InteractPC2S),
+    SetPlayerPosition(SetPlayerPositionPC2S),
+    SetPlayerPositionAndRotation(SetPlayerPositionAndRotationPC2S),
+    SetPlayerRotation(SetPlayerRotationPC2S),
+    SetPlayerOnGround(SetPlayerOnGroundPC2S),
+    MoveVehicle(MoveVehiclePC2S),
+    PlaceRecipe(PlaceRecipePC2S<'a>),
+    PlayerAction(PlayerActionPC2S),
+    PlayerInput(PlayerInputPC2S),
+    PlayerSession(PlayerSessionPC2S<'a>),
+    ResourcePack(ResourcePackPC2S),
+    UseItemOn(UseItemOnPC2S),
+    UseItem(UseItemPC2S),
+});
+/// Packet which is not modelled by the crate, like packets of snapshots and mods
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UnknownPacket<'a> {
+    pub state: ProtocolPacketState,
+    pub bound: ProtocolPacketBound,
+    /// Id in the version of the client
+    pub id: i32,
+    pub body: &'a [u8],
+}
+
+/// Any packet, the variant is chosen by the state and the bound of the connection
+#[derive(Clone, Debug)]
+pub enum Packet<'a> {
+    HandshakeC2S(HandshakeC2SPacket<'a>),
+    StatusS2C(StatusS2CPacket<'a>),
+    StatusC2S(StatusC2SPacket),
+    LoginS2C(LoginS2CPacket<'a>),
+    LoginC2S(LoginC2SPacket<'a>),
+    PlayS2C(PlayS2CPacket<'a>),
+    PlayC2S(PlayC2SPacket<'a>),
+    /// Returned only by [`Packet::read_or_unknown`]
+    Unknown(UnknownPacket<'a>),
+}
+
+impl<'a> Packet<'a> {
+    /// Reads the packet from the frame without the length, returns `None` if the id is unknown
+    pub fn read<C: ProtocolCursor<'a>>(state: ProtocolPacketState, bound: ProtocolPacketBound, cursor: &mut C) -> ProtocolResult<Option<Self>> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        let _span = trace_span!("decode_packet", ?state, ?bound, id);
+        Self::read_body(state, bound, id, cursor)
+    }
+
+    /// Reads the packet of the other protocol version, the id is mapped to the id of the latest version
+    pub fn read_versioned<C: ProtocolCursor<'a>>(
+        state: ProtocolPacketState,
+        bound: ProtocolPacketBound,
+        table: &PacketIdTable,
+        cursor: &mut C,
+    ) -> ProtocolResult<Option<Self>> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        let _span = trace_span!("decode_packet", ?state, ?bound, id);
+        match table.latest_id(state, bound, id) {
+            Some(id) => Self::read_body(state, bound, id, cursor),
+            None => Ok(None),
+        }
+    }
+
+    /// Like [`Packet::read_versioned`], but packets which are unknown in the latest version are returned
+    /// with their raw body, so they can be ignored or forwarded instead of closing the connection
+    pub fn read_or_unknown<C: ProtocolCursor<'a>>(
+        state: ProtocolPacketState,
+        bound: ProtocolPacketBound,
+        table: &PacketIdTable,
+        cursor: &mut C,
+    ) -> ProtocolResult<Self> {
+        let id: i32 = VarInt::read_variant(cursor)?;
+        let _span = trace_span!("decode_packet", ?state, ?bound, id);
+        if let Some(latest) = table.latest_id(state, bound, id) {
+            if let Some(packet) = Self::read_body(state, bound, latest, cursor)? {
+                return Ok(packet);
+            }
+        }
+        let body: &'a [u8] = RemainingBytesArray::read_variant(cursor)?;
+        Ok(Self::Unknown(UnknownPacket { state, bound, id, body }))
+    }
+
+    /// State, bound and id of the packet
+    pub fn key(&self) -> (ProtocolPacketState, ProtocolPacketBound, i32) {
+        match self {
+            Self::HandshakeC2S(packet) => (HandshakeC2SPacket::STATE, HandshakeC2SPacket::BOUND, packet.id()),
+            Self::StatusS2C(packet) => (StatusS2CPacket::STATE, StatusS2CPacket::BOUND, packet.id()),
+            Self::StatusC2S(packet) => (StatusC2SPacket::STATE, StatusC2SPacket::BOUND, packet.id()),
+            Self::LoginS2C(packet) => (LoginS2CPacket::STATE, LoginS2CPacket::BOUND, packet.id()),
+            Self::LoginC2S(packet) => (LoginC2SPacket::STATE, LoginC2SPacket::BOUND, packet.id()),
+            Self::PlayS2C(packet) => (PlayS2CPacket::STATE, PlayS2CPacket::BOUND, packet.id()),
+            Self::PlayC2S(packet) => (PlayC2SPacket::STATE, PlayC2SPacket::BOUND, packet.id()),
+            Self::Unknown(packet) => (packet.state, packet.bound, packet.id),
+        }
+    }
+
+    pub fn read_body<C: ProtocolCursor<'a>>(state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32, cursor: &mut C) -> ProtocolResult<Option<Self>> {
+        use ProtocolPacketBound::*;
+        use ProtocolPacketState::*;
+
+        Ok(match (state, bound) {
+            (Handshake, Server) => HandshakeC2SPacket::read(id, cursor)?.map(Self::HandshakeC2S),
+            (Handshake, Client) => None,
+            (Status, Client) => StatusS2CPacket::read(id, cursor)?.map(Self::StatusS2C),
+            (Status, Server) => StatusC2SPacket::read(id, cursor)?.map(Self::StatusC2S),
+            (Login, Client) => LoginS2CPacket::read(id, cursor)?.map(Self::LoginS2C),
+            (Login, Server) => LoginC2SPacket::read(id, cursor)?.map(Self::LoginC2S),
+            (Play, Client) => PlayS2CPacket::read(id, cursor)?.map(Self::PlayS2C),
+            (Play, Server) => PlayC2SPacket::read(id, cursor)?.map(Self::PlayC2S),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::encode_packet;
+    use crate::version::TEST_TABLE;
+    use super::*;
+
+    #[test]
+    fn packet_registry_test() {
+        let packet = UpdateTimePS2C { world_age: 20, time_of_day: 6000 };
+        let bytes = encode_packet(&packet).unwrap();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut bytes.as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::UpdateTime(read))) => assert_eq!(read, packet),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        let mut written = Vec::new();
+        PlayS2CPacket::UpdateTime(packet).write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+        let packet = Packet::PlayS2C(PlayS2CPacket::UpdateTime(packet));
+        assert_eq!(UpdateTimePS2C::from_packet(&packet), Some(&UpdateTimePS2C { world_age: 20, time_of_day: 6000 }));
+        assert_eq!(KeepAlivePS2C::from_packet(&packet), None);
+        assert!(Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Server, &mut [0x7F].as_slice()).unwrap().is_none());
+        // Move vehicle is 0x18 in the test version
+        let mut bytes = vec![0x18];
+        bytes.extend_from_slice(&[0; 32]);
+        let table = &TEST_TABLE;
+        let packet = Packet::read_versioned(ProtocolPacketState::Play, ProtocolPacketBound::Server, table, &mut bytes.as_slice()).unwrap();
+        assert!(matches!(packet, Some(Packet::PlayC2S(PlayC2SPacket::MoveVehicle(_)))));
+
+        let latest = PacketIdTable::latest();
+        let packet = Packet::read_or_unknown(ProtocolPacketState::Play, ProtocolPacketBound::Server, latest, &mut [0x7F, 1, 2].as_slice()).unwrap();
+        match packet {
+            Packet::Unknown(unknown) => assert_eq!((unknown.id, unknown.body), (0x7F, &[1, 2][..])),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        assert_eq!(packet.key(), (ProtocolPacketState::Play, ProtocolPacketBound::Server, 0x7F));
+        // The first id of the test version doesn't exist in the latest version
+        let packet = Packet::read_or_unknown(ProtocolPacketState::Play, ProtocolPacketBound::Server, table, &mut [0x00].as_slice()).unwrap();
+        assert!(matches!(packet, Packet::Unknown(UnknownPacket { id: 0x00, body: &[], .. })));
+    }
+
+    #[test]
+    fn packets_round_trip_test() {
+        const SAMPLES: usize = 32;
+        HandshakeC2SPacket::assert_samples_round_trip(0, SAMPLES);
+        StatusS2CPacket::assert_samples_round_trip(100, SAMPLES);
+        StatusC2SPacket::assert_samples_round_trip(200, SAMPLES);
+        LoginS2CPacket::assert_samples_round_trip(300, SAMPLES);
+        LoginC2SPacket::assert_samples_round_trip(400, SAMPLES);
+        PlayS2CPacket::assert_samples_round_trip(500, SAMPLES);
+        PlayC2SPacket::assert_samples_round_trip(1000, SAMPLES);
+    }
+}
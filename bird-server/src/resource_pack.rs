@@ -0,0 +1,318 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ResourcePack {
+    /// Identifies the pack in the pack set of the player
+    pub uuid: Uuid,
+    pub url: String,
+    /// Lowercase hex SHA1 of the pack
+    pub hash: String,
+    /// The player is disconnected if the pack is declined or failed
+    pub forced: bool,
+    pub prompt_message: Option<Component<'static>>,
+    /// The pack is removed when the player leaves this world, `None` for packs of the whole server
+    pub world: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResourcePackOutcome {
+    Loaded,
+    Declined,
+    Failed,
+    /// Another pack was sent before the client answered
+    Replaced,
+}
+
+#[derive(Debug, Default)]
+struct PromptState {
+    outcome: Option<ResourcePackOutcome>,
+    waker: Option<Waker>,
+}
+
+impl PromptState {
+    fn resolve(&mut self, outcome: ResourcePackOutcome) {
+        self.outcome = Some(outcome);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves when the client loaded, declined or failed to download the pack
+#[derive(Clone, Debug)]
+pub struct ResourcePackFuture {
+    state: Arc<Mutex<PromptState>>,
+}
+
+impl ResourcePackFuture {
+    pub fn outcome(&self) -> Option<ResourcePackOutcome> {
+        self.state.lock().unwrap().outcome
+    }
+}
+
+impl Future for ResourcePackFuture {
+    type Output = ResourcePackOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.outcome {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingPack {
+    uuid: Uuid,
+    forced: bool,
+    accepted: bool,
+    state: Arc<Mutex<PromptState>>,
+}
+
+/// Resource pack prompts of one player with the pack set of newer versions emulated by the single
+/// server pack of this protocol. The last pack of the set is the applied one, removing it or
+/// declining it sends the previous pack again
+#[derive(Debug, Default)]
+pub struct ResourcePackManager {
+    pending: Option<PendingPack>,
+    packs: Vec<ResourcePack>,
+}
+
+impl ResourcePackManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send<S: PacketSender>(&mut self, pack: &ResourcePack, sender: &mut S) -> anyhow::Result<ResourcePackFuture> {
+        sender.send_packet(&ResourcePackPS2C {
+            url: &pack.url,
+            hash: &pack.hash,
+            forced: pack.forced,
+            prompt_message: pack.prompt_message.clone(),
+        })?;
+        if let Some(pending) = self.pending.take() {
+            pending.state.lock().unwrap().resolve(ResourcePackOutcome::Replaced);
+        }
+        let state = Arc::new(Mutex::new(PromptState::default()));
+        self.pending = Some(PendingPack { uuid: pack.uuid, forced: pack.forced, accepted: false, state: state.clone() });
+        Ok(ResourcePackFuture { state })
+    }
+
+    /// Packs of the player in the order they were pushed
+    pub fn packs(&self) -> &[ResourcePack] {
+        &self.packs
+    }
+
+    pub fn applied(&self) -> Option<&ResourcePack> {
+        self.packs.last()
+    }
+
+    /// Adds the pack to the set and sends it, a pack with the same uuid is replaced
+    pub fn push<S: PacketSender>(&mut self, pack: ResourcePack, sender: &mut S) -> anyhow::Result<ResourcePackFuture> {
+        self.packs.retain(|other| other.uuid != pack.uuid);
+        let future = self.send(&pack, sender)?;
+        self.packs.push(pack);
+        Ok(future)
+    }
+
+    /// Removes the pack from the set. The client keeps the last pack until it reconnects,
+    /// because this protocol has no packet to remove it
+    pub fn remove<S: PacketSender>(&mut self, uuid: Uuid, sender: &mut S) -> anyhow::Result<Option<ResourcePack>> {
+        let applied = self.applied().map(|pack| pack.uuid);
+        let index = match self.packs.iter().position(|pack| pack.uuid == uuid) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let pack = self.packs.remove(index);
+        self.resend_applied(applied, sender)?;
+        Ok(Some(pack))
+    }
+
+    /// Removes packs of other worlds when the player is moved to the world
+    pub fn switch_world<S: PacketSender>(&mut self, world: &str, sender: &mut S) -> anyhow::Result<Vec<ResourcePack>> {
+        let applied = self.applied().map(|pack| pack.uuid);
+        let (removed, packs): (Vec<_>, Vec<_>) = std::mem::take(&mut self.packs).into_iter()
+            .partition(|pack| pack.world.as_deref().map_or(false, |pack_world| pack_world != world));
+        self.packs = packs;
+        self.resend_applied(applied, sender)?;
+        Ok(removed)
+    }
+
+    fn resend_applied<S: PacketSender>(&mut self, previous: Option<Uuid>, sender: &mut S) -> anyhow::Result<()> {
+        match self.packs.last() {
+            Some(pack) if Some(pack.uuid) != previous => {
+                let pack = pack.clone();
+                self.send(&pack, sender)?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true if the client accepted the prompt and is downloading the pack
+    pub fn is_downloading(&self) -> bool {
+        self.pending.as_ref().map_or(false, |pending| pending.accepted)
+    }
+
+    /// Returns an error if the forced pack was declined or failed, the disconnect packet is already sent.
+    /// Otherwise the declined or failed pack is removed from the set and the previous pack is sent again
+    pub fn handle_status<S: PacketSender>(&mut self, packet: &ResourcePackPC2S, sender: &mut S) -> anyhow::Result<Option<ResourcePackOutcome>> {
+        let pending = match self.pending {
+            Some(ref mut pending) => pending,
+            None => return Ok(None),
+        };
+        let outcome = match packet.status {
+            ResourcePackStatus::Accepted => {
+                pending.accepted = true;
+                return Ok(None);
+            }
+            ResourcePackStatus::SuccessfullyLoaded => ResourcePackOutcome::Loaded,
+            ResourcePackStatus::Declined => ResourcePackOutcome::Declined,
+            ResourcePackStatus::FailedDownload => ResourcePackOutcome::Failed,
+        };
+        let pending = self.pending.take().unwrap();
+        pending.state.lock().unwrap().resolve(outcome);
+        if outcome != ResourcePackOutcome::Loaded {
+            self.packs.retain(|pack| pack.uuid != pending.uuid);
+        }
+        if pending.forced && outcome != ResourcePackOutcome::Loaded {
+            sender.send_packet(&DisconnectPS2C {
+                reason: Component::translation("multiplayer.requiredTexturePrompt.disconnect", vec![]),
+            })?;
+            return Err(anyhow::Error::msg("Required resource pack was not loaded"));
+        }
+        if outcome != ResourcePackOutcome::Loaded {
+            self.resend_applied(Some(pending.uuid), sender)?;
+        }
+        Ok(Some(outcome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Wake;
+    use crate::connection::{encode_packet, PacketQueue};
+    use super::*;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll(future: &mut ResourcePackFuture) -> Poll<ResourcePackOutcome> {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn resource_pack_test() {
+        let mut pack = ResourcePack {
+            uuid: Uuid::from_u128(1),
+            url: "https://example.com/pack.zip".into(),
+            hash: "".into(),
+            forced: false,
+            prompt_message: None,
+            world: None,
+        };
+        let mut manager = ResourcePackManager::new();
+        let mut queue = PacketQueue::new();
+        let mut first = manager.send(&pack, &mut queue).unwrap();
+        assert_eq!(poll(&mut first), Poll::Pending);
+        let mut second = manager.send(&pack, &mut queue).unwrap();
+        assert_eq!(poll(&mut first), Poll::Ready(ResourcePackOutcome::Replaced));
+        let accepted = ResourcePackPC2S { status: ResourcePackStatus::Accepted };
+        assert_eq!(manager.handle_status(&accepted, &mut queue).unwrap(), None);
+        assert!(manager.is_downloading());
+        assert_eq!(poll(&mut second), Poll::Pending);
+        let loaded = ResourcePackPC2S { status: ResourcePackStatus::SuccessfullyLoaded };
+        assert_eq!(manager.handle_status(&loaded, &mut queue).unwrap(), Some(ResourcePackOutcome::Loaded));
+        assert_eq!(poll(&mut second), Poll::Ready(ResourcePackOutcome::Loaded));
+        assert_eq!(queue.take().len(), 2);
+        pack.forced = true;
+        let forced = manager.send(&pack, &mut queue).unwrap();
+        let declined = ResourcePackPC2S { status: ResourcePackStatus::Declined };
+        assert!(manager.handle_status(&declined, &mut queue).is_err());
+        assert_eq!(forced.outcome(), Some(ResourcePackOutcome::Declined));
+        // The pack and the disconnect
+        assert_eq!(queue.take().len(), 2);
+    }
+
+    #[test]
+    fn pack_set_test() {
+        let pack = |id: u128, world: Option<&str>| ResourcePack {
+            uuid: Uuid::from_u128(id),
+            url: format!("https://example.com/{}.zip", id),
+            hash: "".into(),
+            forced: false,
+            prompt_message: None,
+            world: world.map(Into::into),
+        };
+        let mut manager = ResourcePackManager::new();
+        let mut queue = PacketQueue::new();
+        manager.push(pack(1, None), &mut queue).unwrap();
+        manager.push(pack(2, Some("lobby")), &mut queue).unwrap();
+        manager.push(pack(3, None), &mut queue).unwrap();
+        assert_eq!(queue.take().len(), 3);
+        // The applied pack is not changed
+        assert_eq!(manager.remove(Uuid::from_u128(1), &mut queue).unwrap(), Some(pack(1, None)));
+        assert!(queue.take().is_empty());
+        manager.push(pack(2, Some("lobby")), &mut queue).unwrap();
+        assert_eq!(manager.packs().len(), 2);
+        queue.take();
+        assert_eq!(manager.switch_world("world", &mut queue).unwrap(), vec![pack(2, Some("lobby"))]);
+        assert_eq!(manager.applied(), Some(&pack(3, None)));
+        assert_eq!(queue.take().len(), 1);
+        let declined = ResourcePackPC2S { status: ResourcePackStatus::Declined };
+        assert_eq!(manager.handle_status(&declined, &mut queue).unwrap(), Some(ResourcePackOutcome::Declined));
+        assert!(manager.packs().is_empty());
+    }
+
+    #[test]
+    fn declined_pack_resend_test() {
+        let pack = |id: u128| ResourcePack {
+            uuid: Uuid::from_u128(id),
+            url: format!("https://example.com/{}.zip", id),
+            hash: "".into(),
+            forced: false,
+            prompt_message: None,
+            world: None,
+        };
+        let packet = |pack: &ResourcePack| encode_packet(&ResourcePackPS2C {
+            url: &pack.url,
+            hash: &pack.hash,
+            forced: pack.forced,
+            prompt_message: None,
+        }).unwrap();
+        let mut manager = ResourcePackManager::new();
+        let mut queue = PacketQueue::new();
+        let loaded = ResourcePackPC2S { status: ResourcePackStatus::SuccessfullyLoaded };
+        manager.push(pack(1), &mut queue).unwrap();
+        manager.handle_status(&loaded, &mut queue).unwrap();
+        manager.push(pack(2), &mut queue).unwrap();
+        queue.take();
+        let declined = ResourcePackPC2S { status: ResourcePackStatus::Declined };
+        assert_eq!(manager.handle_status(&declined, &mut queue).unwrap(), Some(ResourcePackOutcome::Declined));
+        assert_eq!(manager.applied(), Some(&pack(1)));
+        assert_eq!(queue.take(), vec![packet(&pack(1))]);
+        manager.handle_status(&loaded, &mut queue).unwrap();
+        manager.push(pack(3), &mut queue).unwrap();
+        queue.take();
+        let failed = ResourcePackPC2S { status: ResourcePackStatus::FailedDownload };
+        assert_eq!(manager.handle_status(&failed, &mut queue).unwrap(), Some(ResourcePackOutcome::Failed));
+        assert_eq!(manager.packs(), &[pack(1)]);
+        assert_eq!(queue.take(), vec![packet(&pack(1))]);
+    }
+}
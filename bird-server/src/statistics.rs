@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+/// Statistics of a player. Like vanilla, only changed statistics are sent when the client requests them
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Statistics {
+    values: HashMap<AwardStatistic, i32>,
+    dirty: HashSet<AwardStatistic>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, statistic: AwardStatistic) -> i32 {
+        self.values.get(&statistic).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, statistic: AwardStatistic, value: i32) {
+        if self.get(statistic) != value {
+            self.values.insert(statistic, value);
+            self.dirty.insert(statistic);
+        }
+    }
+
+    /// Values don't overflow, returns the new value
+    pub fn increment(&mut self, statistic: AwardStatistic, amount: i32) -> i32 {
+        let value = self.get(statistic).saturating_add(amount);
+        self.set(statistic, value);
+        value
+    }
+
+    pub fn increment_custom(&mut self, statistic: AwardStatisticCustom) -> i32 {
+        self.increment(AwardStatistic::Custom(statistic), 1)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Every statistic is sent with the next [`Statistics::send`], it is used for loaded statistics
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.extend(self.values.keys().copied());
+    }
+
+    /// Response to the statistics request of the client
+    pub fn send<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<()> {
+        let statistics: Vec<_> = self.dirty.drain()
+            .map(|statistic| AwardStatisticsEntry { statistic, value: self.values.get(&statistic).copied().unwrap_or(0) })
+            .collect();
+        sender.send_packet(&AwardStatisticsPS2C { statistics: Cow::Owned(statistics) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn statistics_test() {
+        let mut statistics = Statistics::new();
+        assert_eq!(statistics.increment_custom(AwardStatisticCustom::BellRing), 1);
+        assert_eq!(statistics.increment(AwardStatistic::Used(5), i32::MAX), i32::MAX);
+        assert_eq!(statistics.increment(AwardStatistic::Used(5), 1), i32::MAX);
+        let mut queue = PacketQueue::new();
+        statistics.send(&mut queue).unwrap();
+        assert!(!statistics.is_dirty());
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut queue.take()[0].as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::AwardStatistics(packet))) => {
+                assert_eq!(packet.statistics.len(), 2);
+                assert!(packet.statistics.contains(&AwardStatisticsEntry {
+                    statistic: AwardStatistic::Custom(AwardStatisticCustom::BellRing),
+                    value: 1,
+                }));
+            }
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        statistics.set(AwardStatistic::Used(5), i32::MAX);
+        assert!(!statistics.is_dirty());
+        statistics.mark_all_dirty();
+        assert!(statistics.is_dirty());
+    }
+}
@@ -0,0 +1,385 @@
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::{encode_packet, PacketQueue, PacketSender};
+use crate::events::EventPriority;
+use crate::protocol::*;
+
+pub const VERSION_NAME: &'static str = "1.19.3";
+pub const PROTOCOL_VERSION: i32 = 761;
+/// Vanilla shows at most 12 players in the server list
+pub const STATUS_SAMPLE_SIZE: usize = 12;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StatusPlayer {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+/// Information about the server which is shared by the status handler and the query responder
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ServerStatus {
+    pub motd: String,
+    pub max_players: i32,
+    pub players: Vec<StatusPlayer>,
+    /// `data:image/png;base64,` url of 64x64 icon
+    pub favicon: Option<String>,
+    /// Name of the main world
+    pub map: String,
+    pub enforces_secure_chat: bool,
+}
+
+impl Default for ServerStatus {
+    fn default() -> Self {
+        Self {
+            motd: "A Minecraft Server".into(),
+            max_players: 20,
+            players: Vec::new(),
+            favicon: None,
+            map: "world".into(),
+            enforces_secure_chat: false,
+        }
+    }
+}
+
+impl ServerStatus {
+    pub fn online(&self) -> i32 {
+        self.players.len() as i32
+    }
+
+    /// Players who bypassed the limit don't make the server look overfilled
+    pub fn displayed_max_players(&self) -> i32 {
+        self.max_players.max(self.online())
+    }
+
+    pub fn response(&self) -> StatusResponseObject<'_> {
+        StatusResponseObject {
+            version: StatusResponseVersion { name: VERSION_NAME, protocol: PROTOCOL_VERSION },
+            players: StatusResponsePlayers {
+                max: self.displayed_max_players(),
+                sample: Cow::Owned(self.players.iter()
+                    .take(STATUS_SAMPLE_SIZE)
+                    .map(|player| StatusResponsePlayersSample { name: &player.name, id: player.uuid })
+                    .collect()),
+                online: self.online(),
+            },
+            description: either::Either::Left(&self.motd),
+            favicon: self.favicon.as_deref(),
+            previews_chat: false,
+            enforces_secure_chat: self.enforces_secure_chat,
+        }
+    }
+
+    pub fn handle_status_request<S: PacketSender>(&self, _: &StatusRequest, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&StatusResponseSS2C(self.response()))
+    }
+
+    /// Response without the favicon which is the biggest part of it
+    pub fn compact_response(&self) -> StatusResponseObject<'_> {
+        StatusResponseObject { favicon: None, ..self.response() }
+    }
+
+    pub fn handle_ping_request<S: PacketSender>(&self, packet: &PingRequestSC2S, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&PingResponseSS2C { payload: packet.payload })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StatusCacheConfig {
+    /// The favicon is skipped to make the response compact
+    pub include_favicon: bool,
+    /// Response is rebuilt only when the online count moves to another bucket of this size
+    pub player_count_bucket: i32,
+}
+
+impl Default for StatusCacheConfig {
+    fn default() -> Self {
+        Self {
+            include_favicon: true,
+            player_count_bucket: 1,
+        }
+    }
+}
+
+/// Fields of the status which the cached response depends on
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct StatusCacheKey {
+    motd: String,
+    favicon: Option<String>,
+    max_players: i32,
+    player_bucket: i32,
+    enforces_secure_chat: bool,
+}
+
+/// Encoded status response which is reused between requests.
+/// The player sample and the online count are updated only with the rebuild of the response
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StatusCache {
+    pub config: StatusCacheConfig,
+    pub hits: u64,
+    pub misses: u64,
+    key: Option<StatusCacheKey>,
+    bytes: Vec<u8>,
+}
+
+impl StatusCache {
+    pub fn new(config: StatusCacheConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn key(&self, status: &ServerStatus) -> StatusCacheKey {
+        StatusCacheKey {
+            motd: status.motd.clone(),
+            favicon: match self.config.include_favicon {
+                true => status.favicon.clone(),
+                false => None,
+            },
+            max_players: status.displayed_max_players(),
+            player_bucket: status.online() / self.config.player_count_bucket.max(1),
+            enforces_secure_chat: status.enforces_secure_chat,
+        }
+    }
+
+    fn is_valid(&self, status: &ServerStatus) -> bool {
+        let key = match self.key {
+            Some(ref key) => key,
+            None => return false,
+        };
+        key.motd == status.motd
+            && key.max_players == status.displayed_max_players()
+            && key.player_bucket == status.online() / self.config.player_count_bucket.max(1)
+            && key.enforces_secure_chat == status.enforces_secure_chat
+            && (!self.config.include_favicon || key.favicon == status.favicon)
+    }
+
+    /// Invalidates the cached response, so the next request rebuilds it
+    pub fn invalidate(&mut self) {
+        self.key = None;
+    }
+
+    /// Encoded `StatusResponseSS2C` packet
+    pub fn get(&mut self, status: &ServerStatus) -> anyhow::Result<&[u8]> {
+        match self.is_valid(status) {
+            true => self.hits += 1,
+            false => {
+                self.misses += 1;
+                self.bytes = match self.config.include_favicon {
+                    true => encode_packet(&StatusResponseSS2C(status.response()))?,
+                    false => encode_packet(&StatusResponseSS2C(status.compact_response()))?,
+                };
+                self.key = Some(self.key(status));
+            }
+        }
+        Ok(&self.bytes)
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+
+    pub fn handle_status_request(&mut self, _: &StatusRequest, status: &ServerStatus, queue: &mut PacketQueue) -> anyhow::Result<()> {
+        // Status packets have the same ids in all versions, so they are not remapped
+        queue.packets.push(self.get(status)?.to_vec());
+        Ok(())
+    }
+}
+
+/// Owned status response which handlers of [`StatusEvents`] may rewrite
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatusResponse {
+    pub version_name: String,
+    pub protocol: i32,
+    pub max_players: i32,
+    pub online: i32,
+    pub sample: Vec<StatusPlayer>,
+    pub description: either::Either<String, Component<'static>>,
+    /// `data:image/png;base64,` url of 64x64 icon
+    pub favicon: Option<String>,
+    pub enforces_secure_chat: bool,
+}
+
+impl StatusResponse {
+    pub fn new(status: &ServerStatus) -> Self {
+        Self {
+            version_name: VERSION_NAME.into(),
+            protocol: PROTOCOL_VERSION,
+            max_players: status.displayed_max_players(),
+            online: status.online(),
+            sample: status.players.iter().take(STATUS_SAMPLE_SIZE).cloned().collect(),
+            description: either::Either::Left(status.motd.clone()),
+            favicon: status.favicon.clone(),
+            enforces_secure_chat: status.enforces_secure_chat,
+        }
+    }
+
+    pub fn as_object(&self) -> StatusResponseObject<'_> {
+        StatusResponseObject {
+            version: StatusResponseVersion { name: &self.version_name, protocol: self.protocol },
+            players: StatusResponsePlayers {
+                max: self.max_players,
+                sample: Cow::Owned(self.sample.iter()
+                    .map(|player| StatusResponsePlayersSample { name: &player.name, id: player.uuid })
+                    .collect()),
+                online: self.online,
+            },
+            description: self.description.as_ref().map_left(String::as_str).map_right(Clone::clone),
+            favicon: self.favicon.as_deref(),
+            previews_chat: false,
+            enforces_secure_chat: self.enforces_secure_chat,
+        }
+    }
+}
+
+/// Status request with the address which the client used to connect, so the response may differ per virtual host
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatusRequestEvent {
+    pub server_address: String,
+    pub server_port: u16,
+    pub protocol_version: i32,
+    pub response: StatusResponse,
+}
+
+impl StatusRequestEvent {
+    pub fn new(handshake: &Handshake, response: StatusResponse) -> Self {
+        Self {
+            server_address: handshake.server_address.into(),
+            server_port: handshake.server_port,
+            protocol_version: handshake.protocol_version,
+            response,
+        }
+    }
+}
+
+type StatusFuture = Pin<Box<dyn Future<Output=StatusRequestEvent> + Send>>;
+type StatusHandler = Box<dyn Fn(StatusRequestEvent) -> StatusFuture + Send + Sync>;
+
+/// Asynchronous handlers which rewrite the status response before it is encoded
+#[derive(Default)]
+pub struct StatusEvents {
+    handlers: Vec<(EventPriority, StatusHandler)>,
+}
+
+impl StatusEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_handlers(&self) -> bool {
+        !self.handlers.is_empty()
+    }
+
+    /// Handlers with the higher priority are called first, each one receives the event which was returned by the previous one
+    pub fn on_request<F, R>(&mut self, priority: EventPriority, handler: F) -> &mut Self
+        where F: Fn(StatusRequestEvent) -> R + Send + Sync + 'static, R: Future<Output=StatusRequestEvent> + Send + 'static {
+        let index = self.handlers.partition_point(|(other, _)| *other >= priority);
+        self.handlers.insert(index, (priority, Box::new(move |event| Box::pin(handler(event)))));
+        self
+    }
+
+    pub async fn handle(&self, mut event: StatusRequestEvent) -> StatusRequestEvent {
+        for (_, handler) in &self.handlers {
+            event = handler(event).await;
+        }
+        event
+    }
+
+    /// Encoded `StatusResponseSS2C` packet, the cached response is used if there are no handlers
+    pub async fn respond(&self, handshake: &Handshake<'_>, status: &ServerStatus, cache: &mut StatusCache) -> anyhow::Result<Vec<u8>> {
+        if !self.has_handlers() {
+            return Ok(cache.get(status)?.to_vec());
+        }
+        let mut response = StatusResponse::new(status);
+        if !cache.config.include_favicon {
+            response.favicon = None;
+        }
+        let event = self.handle(StatusRequestEvent::new(handshake, response)).await;
+        encode_packet(&StatusResponseSS2C(event.response.as_object()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str) -> StatusPlayer {
+        StatusPlayer { name: name.into(), uuid: Uuid::nil() }
+    }
+
+    #[test]
+    fn status_cache_test() {
+        let mut cache = StatusCache::new(StatusCacheConfig { include_favicon: false, player_count_bucket: 5 });
+        let mut status = ServerStatus { favicon: Some("data:image/png;base64,".into()), ..Default::default() };
+        let mut queue = PacketQueue::new();
+        cache.handle_status_request(&StatusRequest, &status, &mut queue).unwrap();
+        assert_eq!(queue.take(), vec![encode_packet(&StatusResponseSS2C(status.compact_response())).unwrap()]);
+        status.players.push(player("Notch"));
+        status.favicon = None;
+        cache.get(&status).unwrap();
+        assert_eq!((cache.hits, cache.misses), (1, 1));
+        status.players.extend(["jeb_", "Dinnerbone", "Grumm", "Alex"].map(player));
+        cache.get(&status).unwrap();
+        assert_eq!(cache.misses, 2);
+        status.motd = "Another server".into();
+        assert_eq!(cache.get(&status).unwrap(), encode_packet(&StatusResponseSS2C(status.compact_response())).unwrap());
+        assert_eq!(cache.misses, 3);
+        assert_eq!(cache.hit_rate(), 0.25);
+    }
+
+    /// Handlers of the test are never pending
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct NoopWaker;
+
+        impl std::task::Wake for NoopWaker {
+            fn wake(self: std::sync::Arc<Self>) {}
+        }
+
+        let waker = std::task::Waker::from(std::sync::Arc::new(NoopWaker));
+        let mut future = Box::pin(future);
+        match future.as_mut().poll(&mut std::task::Context::from_waker(&waker)) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("The future is pending"),
+        }
+    }
+
+    #[test]
+    fn status_events_test() {
+        let status = ServerStatus { players: vec![player("Notch")], ..Default::default() };
+        let mut cache = StatusCache::new(StatusCacheConfig::default());
+        let mut events = StatusEvents::new();
+        let handshake = |server_address| Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            server_address,
+            server_port: 25565,
+            next_state: HandshakeNextState::Status,
+        };
+        let default = block_on(events.respond(&handshake("play.example.com"), &status, &mut cache)).unwrap();
+        assert_eq!(default, encode_packet(&StatusResponseSS2C(status.response())).unwrap());
+        assert_eq!(StatusResponse::new(&status).as_object(), status.response());
+        events
+            .on_request(EventPriority::Low, |mut event| async move {
+                event.response.sample.clear();
+                event
+            })
+            .on_request(EventPriority::High, |mut event| async move {
+                if event.server_address == "minigames.example.com" {
+                    event.response.description = either::Either::Right(Component::text("Minigames"));
+                }
+                event
+            });
+        let response = block_on(events.handle(StatusRequestEvent::new(&handshake("minigames.example.com"), StatusResponse::new(&status))));
+        assert_eq!(response.response.description, either::Either::Right(Component::text("Minigames")));
+        assert!(response.response.sample.is_empty());
+        let bytes = block_on(events.respond(&handshake("play.example.com"), &status, &mut cache)).unwrap();
+        let expected = StatusResponse { sample: Vec::new(), ..StatusResponse::new(&status) };
+        assert_eq!(bytes, encode_packet(&StatusResponseSS2C(expected.as_object())).unwrap());
+    }
+}
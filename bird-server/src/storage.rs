@@ -0,0 +1,3 @@
+pub mod json_lists;
+pub mod locations;
+pub mod region;
@@ -0,0 +1,431 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::join_queue::{LoginPermissions, FULL_BYPASS_PERMISSION};
+use crate::login::LoginProfile;
+
+pub const WHITELIST_FILE: &'static str = "whitelist.json";
+pub const OPS_FILE: &'static str = "ops.json";
+pub const BANNED_PLAYERS_FILE: &'static str = "banned-players.json";
+pub const BANNED_IPS_FILE: &'static str = "banned-ips.json";
+/// Expiration of permanent bans
+pub const BAN_FOREVER: &'static str = "forever";
+pub const DEFAULT_BAN_SOURCE: &'static str = "Server";
+pub const DEFAULT_BAN_REASON: &'static str = "Banned by an operator.";
+/// Level of `/op`, the `op-permission-level` property of vanilla
+pub const DEFAULT_OP_LEVEL: i32 = 4;
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Days since the unix epoch of the date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = (if days >= 0 { days } else { days - 146096 }) / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (if month <= 2 { year_of_era + era * 400 + 1 } else { year_of_era + era * 400 }, month, day)
+}
+
+/// Vanilla `yyyy-MM-dd HH:mm:ss Z` format, times are written in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs() as i64),
+    };
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let time = seconds.rem_euclid(SECONDS_PER_DAY);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} +0000", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+pub fn parse_date(text: &str) -> anyhow::Result<SystemTime> {
+    let bad_date = || anyhow::Error::msg(format!("{} is not a date", text));
+    let numbers = |part: Option<&str>, separator: char| -> anyhow::Result<Vec<i64>> {
+        part.ok_or_else(bad_date)?
+            .split(separator)
+            .map(|number| number.parse().map_err(|_| bad_date()))
+            .collect()
+    };
+    let mut parts = text.split(' ');
+    let (date, time) = (numbers(parts.next(), '-')?, numbers(parts.next(), ':')?);
+    let zone = parts.next().ok_or_else(bad_date)?;
+    let (year, month, day, hour, minute, second) = match (date.as_slice(), time.as_slice()) {
+        (&[year, month, day], &[hour, minute, second]) if (1..=12).contains(&month) && (1..=31).contains(&day) =>
+            (year, month, day, hour, minute, second),
+        _ => return Err(bad_date()),
+    };
+    let offset = match (zone.get(..1), zone.get(1..3).map(str::parse::<i64>), zone.get(3..).map(str::parse::<i64>)) {
+        (Some(sign), Some(Ok(hours)), Some(Ok(minutes))) if zone.len() == 5 && (sign == "+" || sign == "-") =>
+            (hours * 3600 + minutes * 60) * if sign == "-" { -1 } else { 1 },
+        _ => return Err(bad_date()),
+    };
+    let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second - offset;
+    Ok(match seconds >= 0 {
+        true => UNIX_EPOCH + Duration::from_secs(seconds as u64),
+        false => UNIX_EPOCH - Duration::from_secs(-seconds as u64),
+    })
+}
+
+mod date {
+    use std::time::SystemTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_date(*time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        super::parse_date(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+mod expiry {
+    use std::time::SystemTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => super::date::serialize(time, serializer),
+            None => serializer.serialize_str(super::BAN_FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SystemTime>, D::Error> {
+        match String::deserialize(deserializer)? {
+            text if text == super::BAN_FOREVER => Ok(None),
+            text => super::parse_date(&text).map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+fn default_ban_source() -> String {
+    DEFAULT_BAN_SOURCE.into()
+}
+
+fn default_ban_reason() -> String {
+    DEFAULT_BAN_REASON.into()
+}
+
+fn default_ban_expiry() -> Option<SystemTime> {
+    None
+}
+
+/// Entry of the whitelist
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ProfileEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub level: i32,
+    /// The op joins even if the server is full
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BanInfo {
+    #[serde(with = "date")]
+    pub created: SystemTime,
+    #[serde(default = "default_ban_source")]
+    pub source: String,
+    /// `None` if the ban is permanent
+    #[serde(with = "expiry", default = "default_ban_expiry")]
+    pub expires: Option<SystemTime>,
+    #[serde(default = "default_ban_reason")]
+    pub reason: String,
+}
+
+impl BanInfo {
+    /// Permanent ban by the server with the default reason
+    pub fn new(now: SystemTime) -> Self {
+        Self { created: now, source: default_ban_source(), expires: None, reason: default_ban_reason() }
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+
+    fn message(&self, reason_key: &'static str, expiration_key: &'static str) -> Component<'static> {
+        let mut message = Component::translation(reason_key, vec![Component::text(self.reason.clone())]);
+        if let Some(expires) = self.expires {
+            message.extra = Cow::Owned(vec![Component::translation(expiration_key, vec![Component::text(format_date(expires))])]);
+        }
+        message
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct PlayerBan {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct IpBan {
+    pub ip: String,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+/// Result of [`JsonLists::check_login`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum LoginCheck {
+    /// Ops have their permission level
+    Allow {
+        op_level: Option<i32>,
+    },
+    /// The player is disconnected with the vanilla message
+    Deny(Component<'static>),
+}
+
+fn read_list<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) if text.trim().is_empty() => Ok(Vec::new()),
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|error| anyhow::Error::msg(format!("Failed to read {}: {}", path.display(), error))),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn write_list<T: Serialize>(path: &Path, list: &[T]) -> anyhow::Result<()> {
+    Ok(std::fs::write(path, serde_json::to_string_pretty(list)?)?)
+}
+
+/// Whitelist, ops and bans in the files of vanilla, missing files are empty lists
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct JsonLists {
+    /// The `white-list` property of vanilla, ops are always whitelisted
+    pub whitelist_enabled: bool,
+    whitelist: Vec<ProfileEntry>,
+    ops: Vec<OpEntry>,
+    banned_players: Vec<PlayerBan>,
+    banned_ips: Vec<IpBan>,
+}
+
+impl JsonLists {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(directory: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            whitelist_enabled: false,
+            whitelist: read_list(&directory.join(WHITELIST_FILE))?,
+            ops: read_list(&directory.join(OPS_FILE))?,
+            banned_players: read_list(&directory.join(BANNED_PLAYERS_FILE))?,
+            banned_ips: read_list(&directory.join(BANNED_IPS_FILE))?,
+        })
+    }
+
+    pub fn save(&self, directory: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(directory)?;
+        write_list(&directory.join(WHITELIST_FILE), &self.whitelist)?;
+        write_list(&directory.join(OPS_FILE), &self.ops)?;
+        write_list(&directory.join(BANNED_PLAYERS_FILE), &self.banned_players)?;
+        write_list(&directory.join(BANNED_IPS_FILE), &self.banned_ips)
+    }
+
+    pub fn whitelist(&self) -> &[ProfileEntry] {
+        &self.whitelist
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.whitelist.iter().any(|entry| entry.uuid == uuid)
+    }
+
+    /// Returns false if the player is already whitelisted
+    pub fn add_to_whitelist(&mut self, uuid: Uuid, name: &str) -> bool {
+        match self.is_whitelisted(uuid) {
+            true => false,
+            false => {
+                self.whitelist.push(ProfileEntry { uuid, name: name.into() });
+                true
+            }
+        }
+    }
+
+    pub fn remove_from_whitelist(&mut self, uuid: Uuid) -> bool {
+        let len = self.whitelist.len();
+        self.whitelist.retain(|entry| entry.uuid != uuid);
+        self.whitelist.len() != len
+    }
+
+    pub fn ops(&self) -> &[OpEntry] {
+        &self.ops
+    }
+
+    pub fn op_level(&self, uuid: Uuid) -> Option<i32> {
+        self.ops.iter().find(|entry| entry.uuid == uuid).map(|entry| entry.level)
+    }
+
+    /// Makes the player an op or changes the level of the op
+    pub fn op(&mut self, uuid: Uuid, name: &str, level: i32) {
+        match self.ops.iter_mut().find(|entry| entry.uuid == uuid) {
+            Some(entry) => entry.level = level,
+            None => self.ops.push(OpEntry { uuid, name: name.into(), level, bypasses_player_limit: false }),
+        }
+    }
+
+    pub fn deop(&mut self, uuid: Uuid) -> bool {
+        let len = self.ops.len();
+        self.ops.retain(|entry| entry.uuid != uuid);
+        self.ops.len() != len
+    }
+
+    /// Permissions for [`crate::join_queue::JoinGate`], ops which bypass the player limit get [`FULL_BYPASS_PERMISSION`]
+    pub fn login_permissions(&self) -> LoginPermissions {
+        let mut permissions = LoginPermissions::default();
+        for entry in self.ops.iter().filter(|entry| entry.bypasses_player_limit) {
+            permissions.grant(entry.uuid, FULL_BYPASS_PERMISSION);
+        }
+        permissions
+    }
+
+    pub fn banned_players(&self) -> &[PlayerBan] {
+        &self.banned_players
+    }
+
+    pub fn banned_ips(&self) -> &[IpBan] {
+        &self.banned_ips
+    }
+
+    /// Replaces the previous ban of the player
+    pub fn ban(&mut self, uuid: Uuid, name: &str, info: BanInfo) {
+        self.pardon(uuid);
+        self.banned_players.push(PlayerBan { uuid, name: name.into(), info });
+    }
+
+    pub fn pardon(&mut self, uuid: Uuid) -> bool {
+        let len = self.banned_players.len();
+        self.banned_players.retain(|ban| ban.uuid != uuid);
+        self.banned_players.len() != len
+    }
+
+    /// The ban of the player which has not expired yet
+    pub fn ban_of(&self, uuid: Uuid, now: SystemTime) -> Option<&PlayerBan> {
+        self.banned_players.iter().find(|ban| ban.uuid == uuid && !ban.info.is_expired(now))
+    }
+
+    pub fn ban_ip(&mut self, ip: &str, info: BanInfo) {
+        self.pardon_ip(ip);
+        self.banned_ips.push(IpBan { ip: ip.into(), info });
+    }
+
+    pub fn pardon_ip(&mut self, ip: &str) -> bool {
+        let len = self.banned_ips.len();
+        self.banned_ips.retain(|ban| ban.ip != ip);
+        self.banned_ips.len() != len
+    }
+
+    pub fn ip_ban_of(&self, ip: &str, now: SystemTime) -> Option<&IpBan> {
+        self.banned_ips.iter().find(|ban| ban.ip == ip && !ban.info.is_expired(now))
+    }
+
+    /// Removes expired bans, the lists should be saved if any ban is removed.
+    /// Returns the amount of removed bans
+    pub fn remove_expired(&mut self, now: SystemTime) -> usize {
+        let len = self.banned_players.len() + self.banned_ips.len();
+        self.banned_players.retain(|ban| !ban.info.is_expired(now));
+        self.banned_ips.retain(|ban| !ban.info.is_expired(now));
+        len - self.banned_players.len() - self.banned_ips.len()
+    }
+
+    /// Checks the player after the login like vanilla: player bans, the whitelist, then ip bans.
+    /// `ip` is the address of the player without the port, it is forwarded by the proxy if there is one
+    pub fn check_login(&self, profile: &LoginProfile, ip: &str, now: SystemTime) -> LoginCheck {
+        if let Some(ban) = self.ban_of(profile.uuid, now) {
+            return LoginCheck::Deny(ban.info.message("multiplayer.disconnect.banned.reason", "multiplayer.disconnect.banned.expiration"));
+        }
+        let op_level = self.op_level(profile.uuid);
+        if self.whitelist_enabled && op_level.is_none() && !self.is_whitelisted(profile.uuid) {
+            return LoginCheck::Deny(Component::translation("multiplayer.disconnect.not_whitelisted", vec![]));
+        }
+        if let Some(ban) = self.ip_ban_of(ip, now) {
+            return LoginCheck::Deny(ban.info.message("multiplayer.disconnect.banned_ip.reason", "multiplayer.disconnect.banned_ip.expiration"));
+        }
+        LoginCheck::Allow { op_level }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::login::offline_uuid;
+    use super::*;
+
+    #[test]
+    fn date_test() {
+        let date = parse_date("2023-01-05 14:03:22 +0100").unwrap();
+        assert_eq!(date, UNIX_EPOCH + Duration::from_secs(1672923802));
+        assert_eq!(format_date(date), "2023-01-05 13:03:22 +0000");
+        assert_eq!(parse_date(&format_date(UNIX_EPOCH)).unwrap(), UNIX_EPOCH);
+        assert_eq!(format_date(UNIX_EPOCH + Duration::from_secs(951782400)), "2000-02-29 00:00:00 +0000");
+        assert!(parse_date("2023-13-05 14:03:22 +0100").is_err());
+        assert!(parse_date("2023-01-05 14:03:22").is_err());
+    }
+
+    #[test]
+    fn json_lists_test() {
+        let now = UNIX_EPOCH + Duration::from_secs(1672923802);
+        let profile = |name: &str| LoginProfile { uuid: offline_uuid(name), name: name.into(), properties: Vec::new(), address: None };
+        let vanilla = r#"[{"uuid":"0a9e2d3e-0b8c-3d6b-8f70-5d3a34f1c2b1","name":"Steve","created":"2023-01-05 14:03:22 +0100","source":"Server","expires":"forever","reason":"Griefing"}]"#;
+        let bans: Vec<PlayerBan> = serde_json::from_str(vanilla).unwrap();
+        assert_eq!(bans[0].info.created, now);
+        assert_eq!(bans[0].info.expires, None);
+        assert_eq!(serde_json::to_value(&bans).unwrap(), serde_json::from_str::<serde_json::Value>(&vanilla.replace("14:03:22 +0100", "13:03:22 +0000")).unwrap());
+
+        let mut lists = JsonLists::new();
+        let (steve, alex, notch) = (profile("Steve"), profile("Alex"), profile("Notch"));
+        lists.ban(steve.uuid, &steve.name, BanInfo { reason: "Griefing".into(), ..BanInfo::new(now) });
+        lists.ban_ip("10.0.0.2", BanInfo { expires: Some(now + Duration::from_secs(60)), ..BanInfo::new(now) });
+        lists.op(notch.uuid, &notch.name, DEFAULT_OP_LEVEL);
+        assert!(lists.add_to_whitelist(alex.uuid, &alex.name));
+        assert!(!lists.add_to_whitelist(alex.uuid, &alex.name));
+        lists.whitelist_enabled = true;
+
+        let banned = BanInfo { reason: "Griefing".into(), ..BanInfo::new(now) }
+            .message("multiplayer.disconnect.banned.reason", "multiplayer.disconnect.banned.expiration");
+        assert_eq!(lists.check_login(&steve, "10.0.0.1", now), LoginCheck::Deny(banned));
+        assert_eq!(lists.check_login(&notch, "10.0.0.1", now), LoginCheck::Allow { op_level: Some(DEFAULT_OP_LEVEL) });
+        assert_eq!(lists.check_login(&alex, "10.0.0.1", now), LoginCheck::Allow { op_level: None });
+        assert!(matches!(lists.check_login(&profile("Herobrine"), "10.0.0.1", now), LoginCheck::Deny(_)));
+        assert!(matches!(lists.check_login(&alex, "10.0.0.2", now), LoginCheck::Deny(_)));
+        // The ip ban expires
+        let later = now + Duration::from_secs(60);
+        assert_eq!(lists.check_login(&alex, "10.0.0.2", later), LoginCheck::Allow { op_level: None });
+        assert_eq!(lists.remove_expired(later), 1);
+
+        let directory = std::env::temp_dir().join(format!("bird-json-lists-{}", std::process::id()));
+        lists.save(&directory).unwrap();
+        let mut loaded = JsonLists::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        loaded.whitelist_enabled = true;
+        assert_eq!(loaded, lists);
+        assert!(JsonLists::load(&directory).unwrap().ops().is_empty());
+        assert!(!lists.login_permissions().has(notch.uuid, FULL_BYPASS_PERMISSION));
+    }
+}
@@ -0,0 +1,307 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use bird_chat::color::Color;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::join_queue::LoginPermissions;
+use crate::protocol::*;
+
+/// File in the world directory
+pub const LOCATIONS_FILE: &'static str = "locations.json";
+pub const DEFAULT_HOME: &'static str = "home";
+pub const MAX_LOCATION_NAME_LENGTH: usize = 16;
+/// Players with this permission create and remove warps
+pub const MANAGE_WARPS_PERMISSION: &'static str = "bird.warp.manage";
+pub const HOME_COMMAND: &'static str = "home";
+pub const SET_HOME_COMMAND: &'static str = "sethome";
+pub const DELETE_HOME_COMMAND: &'static str = "delhome";
+pub const HOMES_COMMAND: &'static str = "homes";
+pub const WARP_COMMAND: &'static str = "warp";
+pub const SET_WARP_COMMAND: &'static str = "setwarp";
+pub const DELETE_WARP_COMMAND: &'static str = "delwarp";
+pub const WARPS_COMMAND: &'static str = "warps";
+const NAME_ARGUMENT: &'static str = "name";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Location {
+    pub world: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+/// Names are case insensitive and contain only letters, digits, `_` and `-`
+pub fn location_name(name: &str) -> anyhow::Result<String> {
+    let valid = !name.is_empty() && name.len() <= MAX_LOCATION_NAME_LENGTH
+        && name.chars().all(|symbol| symbol.is_ascii_alphanumeric() || symbol == '_' || symbol == '-');
+    match valid {
+        true => Ok(name.to_ascii_lowercase()),
+        false => Err(anyhow::Error::msg(format!("{} is not a valid name", name))),
+    }
+}
+
+/// Result of the command, the caller teleports the player if there is a location
+#[derive(Clone, PartialEq, Debug)]
+pub struct LocationOutput {
+    pub message: Component<'static>,
+    pub teleport: Option<Location>,
+}
+
+impl LocationOutput {
+    fn message(text: impl Into<Cow<'static, str>>, color: Color) -> Self {
+        let mut message = Component::text(text);
+        message.color = Some(color);
+        Self { message, teleport: None }
+    }
+
+    fn error(text: impl Into<Cow<'static, str>>) -> Self {
+        Self::message(text, Color::Red)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+struct LocationsData {
+    #[serde(default)]
+    homes: BTreeMap<Uuid, BTreeMap<String, Location>>,
+    #[serde(default)]
+    warps: BTreeMap<String, Location>,
+}
+
+/// Homes of players and global warps which are saved in the world directory
+#[derive(Clone, PartialEq, Debug)]
+pub struct NamedLocations {
+    /// Homes a player may have
+    pub max_homes: usize,
+    data: LocationsData,
+    dirty: bool,
+}
+
+impl Default for NamedLocations {
+    fn default() -> Self {
+        Self { max_homes: 3, data: LocationsData::default(), dirty: false }
+    }
+}
+
+impl NamedLocations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No locations if the file doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => LocationsData::default(),
+            Err(error) => return Err(error.into()),
+        };
+        Ok(Self { data, ..Self::default() })
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(&self.data)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn home(&self, player: Uuid, name: &str) -> Option<&Location> {
+        self.data.homes.get(&player)?.get(&location_name(name).ok()?)
+    }
+
+    /// Names of homes of the player in alphabetical order
+    pub fn homes(&self, player: Uuid) -> Vec<&str> {
+        self.data.homes.get(&player).map_or_else(Vec::new, |homes| homes.keys().map(String::as_str).collect())
+    }
+
+    /// Moves the home if it exists, a new home is not created if the player has [`NamedLocations::max_homes`]
+    pub fn set_home(&mut self, player: Uuid, name: &str, location: Location) -> anyhow::Result<()> {
+        let name = location_name(name)?;
+        let homes = self.data.homes.entry(player).or_default();
+        if !homes.contains_key(&name) && homes.len() >= self.max_homes {
+            return Err(anyhow::Error::msg(format!("You can't have more than {} homes", self.max_homes)));
+        }
+        homes.insert(name, location);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn remove_home(&mut self, player: Uuid, name: &str) -> bool {
+        let (homes, name) = match (self.data.homes.get_mut(&player), location_name(name)) {
+            (Some(homes), Ok(name)) => (homes, name),
+            _ => return false,
+        };
+        let removed = homes.remove(&name).is_some();
+        if homes.is_empty() {
+            self.data.homes.remove(&player);
+        }
+        self.dirty |= removed;
+        removed
+    }
+
+    pub fn warp(&self, name: &str) -> Option<&Location> {
+        self.data.warps.get(&location_name(name).ok()?)
+    }
+
+    pub fn warps(&self) -> Vec<&str> {
+        self.data.warps.keys().map(String::as_str).collect()
+    }
+
+    pub fn set_warp(&mut self, name: &str, location: Location) -> anyhow::Result<()> {
+        self.data.warps.insert(location_name(name)?, location);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn remove_warp(&mut self, name: &str) -> bool {
+        let removed = location_name(name).map_or(false, |name| self.data.warps.remove(&name).is_some());
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Handles the command without the slash, `location` is the current location of the player.
+    /// `None` if it is not a home or warp command
+    pub fn handle_command(
+        &mut self,
+        player: Uuid,
+        location: &Location,
+        command: &str,
+        permissions: &LoginPermissions,
+    ) -> Option<LocationOutput> {
+        let mut parts = command.split_whitespace();
+        let command = parts.next()?;
+        let argument = parts.next();
+        let home = argument.unwrap_or(DEFAULT_HOME);
+        let manage_warps = permissions.has(player, MANAGE_WARPS_PERMISSION);
+        let output = match (command, argument) {
+            (HOME_COMMAND, _) => match self.home(player, home) {
+                Some(home) => LocationOutput { teleport: Some(home.clone()), ..LocationOutput::message("Teleporting home", Color::Gold) },
+                None => LocationOutput::error(format!("Home {} doesn't exist", home)),
+            },
+            (SET_HOME_COMMAND, _) => match self.set_home(player, home, location.clone()) {
+                Ok(()) => LocationOutput::message(format!("Home {} is set", home), Color::Gold),
+                Err(error) => LocationOutput::error(error.to_string()),
+            },
+            (DELETE_HOME_COMMAND, _) => match self.remove_home(player, home) {
+                true => LocationOutput::message(format!("Home {} is removed", home), Color::Gold),
+                false => LocationOutput::error(format!("Home {} doesn't exist", home)),
+            },
+            (HOMES_COMMAND, _) => match self.homes(player) {
+                homes if homes.is_empty() => LocationOutput::message("You don't have homes", Color::Gold),
+                homes => LocationOutput::message(format!("Homes: {}", homes.join(", ")), Color::Gold),
+            },
+            (WARP_COMMAND, Some(name)) => match self.warp(name) {
+                Some(warp) => LocationOutput { teleport: Some(warp.clone()), ..LocationOutput::message(format!("Warping to {}", name), Color::Gold) },
+                None => LocationOutput::error(format!("Warp {} doesn't exist", name)),
+            },
+            (SET_WARP_COMMAND | DELETE_WARP_COMMAND, Some(_)) if !manage_warps =>
+                LocationOutput::error("You don't have permission to manage warps"),
+            (SET_WARP_COMMAND, Some(name)) => match self.set_warp(name, location.clone()) {
+                Ok(()) => LocationOutput::message(format!("Warp {} is set", name), Color::Gold),
+                Err(error) => LocationOutput::error(error.to_string()),
+            },
+            (DELETE_WARP_COMMAND, Some(name)) => match self.remove_warp(name) {
+                true => LocationOutput::message(format!("Warp {} is removed", name), Color::Gold),
+                false => LocationOutput::error(format!("Warp {} doesn't exist", name)),
+            },
+            (WARP_COMMAND | SET_WARP_COMMAND | DELETE_WARP_COMMAND, None) => LocationOutput::error(format!("Usage: /{} <name>", command)),
+            (WARPS_COMMAND, _) => match self.warps() {
+                warps if warps.is_empty() => LocationOutput::message("There are no warps", Color::Gold),
+                warps => LocationOutput::message(format!("Warps: {}", warps.join(", ")), Color::Gold),
+            },
+            _ => return None,
+        };
+        Some(output)
+    }
+
+    /// Appends nodes of the commands to the [`CommandsPS2C`] tree, returns the nodes which are children of the root.
+    /// Commands of warps are declared only for players who manage them
+    pub fn declare_commands(nodes: &mut Vec<BrigadierNode<'static>>, manage_warps: bool) -> Vec<i32> {
+        let mut commands = vec![
+            (HOME_COMMAND, true),
+            (SET_HOME_COMMAND, true),
+            (DELETE_HOME_COMMAND, true),
+            (HOMES_COMMAND, true),
+            (WARP_COMMAND, false),
+            (WARPS_COMMAND, true),
+        ];
+        if manage_warps {
+            commands.extend([(SET_WARP_COMMAND, false), (DELETE_WARP_COMMAND, false)]);
+        }
+        let mut literals = Vec::new();
+        for (command, executable) in commands {
+            let children = match command {
+                HOMES_COMMAND | WARPS_COMMAND => Cow::Borrowed(&[][..]),
+                _ => {
+                    nodes.push(BrigadierNode {
+                        executable: true,
+                        children: Cow::Borrowed(&[]),
+                        redirect_node: None,
+                        name: Some(NAME_ARGUMENT),
+                        parser: Some(BrigadierNodeParser::String(BrigadierNodeParserString::SingleWord)),
+                        suggestions_type: None,
+                    });
+                    Cow::Owned(vec![nodes.len() as i32 - 1])
+                }
+            };
+            literals.push(nodes.len() as i32);
+            nodes.push(BrigadierNode { executable, children, redirect_node: None, name: Some(command), parser: None, suggestions_type: None });
+        }
+        literals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(x: f64) -> Location {
+        Location { world: "world".into(), x, y: 64.0, z: 0.0, yaw: 90.0, pitch: 0.0 }
+    }
+
+    #[test]
+    fn locations_test() {
+        let (alex, steve) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut permissions = LoginPermissions::default();
+        permissions.grant(steve, MANAGE_WARPS_PERMISSION);
+        let mut locations = NamedLocations { max_homes: 2, ..NamedLocations::new() };
+        assert!(locations.handle_command(alex, &location(0.0), "spawn", &permissions).is_none());
+        locations.handle_command(alex, &location(1.0), "sethome", &permissions).unwrap();
+        locations.handle_command(alex, &location(2.0), "sethome Base", &permissions).unwrap();
+        assert_eq!(locations.homes(alex), ["base", "home"]);
+        // The limit doesn't prevent moving homes
+        assert!(locations.set_home(alex, "mine", location(3.0)).is_err());
+        locations.set_home(alex, "BASE", location(3.0)).unwrap();
+        assert!(locations.set_home(alex, "bad name", location(3.0)).is_err());
+        let output = locations.handle_command(alex, &location(0.0), "home base", &permissions).unwrap();
+        assert_eq!(output.teleport, Some(location(3.0)));
+        assert!(locations.handle_command(alex, &location(0.0), "home", &permissions).unwrap().teleport.is_some());
+        assert!(locations.home(steve, "home").is_none());
+
+        let denied = locations.handle_command(alex, &location(5.0), "setwarp shop", &permissions).unwrap();
+        assert_eq!(denied.message.color, Some(Color::Red));
+        locations.handle_command(steve, &location(5.0), "setwarp shop", &permissions).unwrap();
+        assert_eq!(locations.handle_command(alex, &location(0.0), "warp Shop", &permissions).unwrap().teleport, Some(location(5.0)));
+        assert!(locations.handle_command(alex, &location(0.0), "delhome base", &permissions).unwrap().message.color != Some(Color::Red));
+        assert!(locations.is_dirty());
+
+        let path = std::env::temp_dir().join(format!("bird-locations-{}.json", std::process::id()));
+        locations.save(&path).unwrap();
+        assert!(!locations.is_dirty());
+        let loaded = NamedLocations::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, NamedLocations { max_homes: 3, ..locations.clone() });
+        assert_eq!(loaded.warps(), ["shop"]);
+
+        let mut nodes = Vec::new();
+        assert_eq!(NamedLocations::declare_commands(&mut nodes, false).len(), 6);
+        assert_eq!(NamedLocations::declare_commands(&mut Vec::new(), true).len(), 8);
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use euclid::default::Vector2D;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use bird_protocol::anyhow;
+use crate::preload::StoredChunk;
+use crate::instrument::warn;
+use crate::trace::crc32;
+
+/// Regions are 32x32 chunks like in vanilla
+pub const REGION_SIZE: i32 = 32;
+pub const REGION_EXTENSION: &'static str = "bcr";
+/// Corrupted chunks are moved into this directory of the region directory
+pub const QUARANTINE_DIRECTORY: &'static str = "quarantine";
+const SECTOR_SIZE: usize = 4096;
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE) as usize;
+/// Sector offset (3 bytes), sector count and crc of the payload
+const ENTRY_SIZE: usize = 8;
+const HEADER_SECTORS: usize = CHUNKS_PER_REGION * ENTRY_SIZE / SECTOR_SIZE;
+/// Length of the payload which is followed by the compression and the data
+const LENGTH_SIZE: usize = 4;
+const MAX_CHUNK_SECTORS: usize = u8::MAX as usize;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct ChunkEntry {
+    offset: u32,
+    sectors: u8,
+    crc: u32,
+}
+
+impl ChunkEntry {
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            offset: u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]),
+            sectors: bytes[3],
+            crc: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let offset = self.offset.to_be_bytes();
+        let crc = self.crc.to_be_bytes();
+        [offset[1], offset[2], offset[3], self.sectors, crc[0], crc[1], crc[2], crc[3]]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sectors == 0
+    }
+
+    fn sectors(&self) -> std::ops::Range<usize> {
+        self.offset as usize..self.offset as usize + self.sectors as usize
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ChunkRead {
+    Missing,
+    Loaded(Vec<u8>),
+    /// Reason of the corruption, the chunk is not changed until it is quarantined or written again
+    Corrupted(String),
+}
+
+fn chunk_index(position: Vector2D<i32>) -> usize {
+    (position.x.rem_euclid(REGION_SIZE) + position.y.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+}
+
+pub fn region_position(chunk: Vector2D<i32>) -> Vector2D<i32> {
+    Vector2D::new(chunk.x.div_euclid(REGION_SIZE), chunk.y.div_euclid(REGION_SIZE))
+}
+
+pub fn region_path(directory: &Path, region: Vector2D<i32>) -> PathBuf {
+    directory.join(format!("r.{}.{}.{}", region.x, region.y, REGION_EXTENSION))
+}
+
+/// Region of chunks with checksums. Chunks are written into free sectors and the header entry is updated
+/// after the data is synced, so a crash while saving leaves the previous version of the chunk
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    entries: Vec<ChunkEntry>,
+    /// Sectors of the header and of chunks
+    used: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Creates the file if it doesn't exist
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
+        let length = file.metadata()?.len() as usize;
+        let mut header = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+        match length < header.len() {
+            true => {
+                file.write_all(&header)?;
+                file.sync_all()?;
+            }
+            false => file.read_exact(&mut header)?,
+        }
+        let entries: Vec<_> = header.chunks(ENTRY_SIZE).map(ChunkEntry::read).collect();
+        let mut used = vec![false; length.max(header.len()).div_ceil(SECTOR_SIZE)];
+        used[..HEADER_SECTORS].fill(true);
+        // Entries which point outside of the file are reported when the chunk is read
+        for entry in entries.iter().filter(|entry| !entry.is_empty()) {
+            if entry.offset as usize >= HEADER_SECTORS && entry.sectors().end <= used.len() {
+                used[entry.sectors()].fill(true);
+            }
+        }
+        Ok(Self { path: path.to_path_buf(), file, entries, used })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn contains(&self, position: Vector2D<i32>) -> bool {
+        !self.entries[chunk_index(position)].is_empty()
+    }
+
+    fn read_sectors(&mut self, entry: ChunkEntry) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![0; entry.sectors as usize * SECTOR_SIZE];
+        self.file.seek(SeekFrom::Start((entry.offset as usize * SECTOR_SIZE) as u64))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Position is the position of the chunk in the world
+    pub fn read_chunk(&mut self, position: Vector2D<i32>) -> anyhow::Result<ChunkRead> {
+        let entry = self.entries[chunk_index(position)];
+        if entry.is_empty() {
+            return Ok(ChunkRead::Missing);
+        }
+        if (entry.offset as usize) < HEADER_SECTORS || entry.sectors().end > self.used.len() {
+            return Ok(ChunkRead::Corrupted(format!("Sectors {:?} are outside of the file", entry.sectors())));
+        }
+        let bytes = self.read_sectors(entry)?;
+        let length = u32::from_be_bytes(bytes[..LENGTH_SIZE].try_into()?) as usize;
+        let payload = match bytes.get(LENGTH_SIZE..LENGTH_SIZE + length) {
+            Some(payload) if !payload.is_empty() => payload,
+            _ => return Ok(ChunkRead::Corrupted(format!("Length {} doesn't fit into {} sectors", length, entry.sectors))),
+        };
+        if crc32(payload) != entry.crc {
+            return Ok(ChunkRead::Corrupted("Checksum mismatch".into()));
+        }
+        match payload[0] {
+            COMPRESSION_ZLIB => {
+                let mut data = Vec::new();
+                match ZlibDecoder::new(&payload[1..]).read_to_end(&mut data) {
+                    Ok(_) => Ok(ChunkRead::Loaded(data)),
+                    Err(err) => Ok(ChunkRead::Corrupted(format!("Invalid zlib data: {}", err))),
+                }
+            }
+            COMPRESSION_NONE => Ok(ChunkRead::Loaded(payload[1..].to_vec())),
+            compression => Ok(ChunkRead::Corrupted(format!("Unknown compression {}", compression))),
+        }
+    }
+
+    /// First free sectors which fit the chunk, the file is extended if there are not enough of them
+    fn allocate(&self, sectors: usize) -> usize {
+        let mut run = 0;
+        for (index, used) in self.used.iter().enumerate() {
+            match used {
+                true => run = 0,
+                false => {
+                    run += 1;
+                    if run == sectors {
+                        return index + 1 - sectors;
+                    }
+                }
+            }
+        }
+        self.used.len() - run
+    }
+
+    /// Header entries are written in place, one entry never crosses a disk sector
+    fn write_entry(&mut self, index: usize, entry: ChunkEntry) -> anyhow::Result<()> {
+        self.file.seek(SeekFrom::Start((index * ENTRY_SIZE) as u64))?;
+        self.file.write_all(&entry.to_bytes())?;
+        self.file.sync_data()?;
+        let previous = std::mem::replace(&mut self.entries[index], entry);
+        if !previous.is_empty() && previous.sectors().end <= self.used.len() && previous.offset as usize >= HEADER_SECTORS {
+            self.used[previous.sectors()].fill(false);
+        }
+        if !entry.is_empty() {
+            if self.used.len() < entry.sectors().end {
+                self.used.resize(entry.sectors().end, false);
+            }
+            self.used[entry.sectors()].fill(true);
+        }
+        Ok(())
+    }
+
+    /// Data is written into free sectors and synced before the header points to it
+    pub fn write_chunk(&mut self, position: Vector2D<i32>, data: &[u8]) -> anyhow::Result<()> {
+        let mut encoder = ZlibEncoder::new(vec![COMPRESSION_ZLIB], Compression::default());
+        encoder.write_all(data)?;
+        let payload = encoder.finish()?;
+        let sectors = (LENGTH_SIZE + payload.len()).div_ceil(SECTOR_SIZE);
+        if sectors > MAX_CHUNK_SECTORS {
+            return Err(anyhow::Error::msg(format!("Chunk {:?} doesn't fit into {} sectors", position, MAX_CHUNK_SECTORS)));
+        }
+        let offset = self.allocate(sectors);
+        let mut bytes = Vec::with_capacity(sectors * SECTOR_SIZE);
+        bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes.resize(sectors * SECTOR_SIZE, 0);
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&bytes)?;
+        self.file.sync_data()?;
+        self.write_entry(chunk_index(position), ChunkEntry { offset: offset as u32, sectors: sectors as u8, crc: crc32(&payload) })
+    }
+
+    pub fn remove_chunk(&mut self, position: Vector2D<i32>) -> anyhow::Result<()> {
+        self.write_entry(chunk_index(position), ChunkEntry::default())
+    }
+
+    /// Copies the stored bytes of the chunk into the file and removes the chunk from the region
+    pub fn quarantine(&mut self, position: Vector2D<i32>, destination: &Path) -> anyhow::Result<()> {
+        let entry = self.entries[chunk_index(position)];
+        let file_length = self.file.metadata()?.len() as usize;
+        let start = (entry.offset as usize * SECTOR_SIZE).min(file_length);
+        let end = (entry.sectors().end * SECTOR_SIZE).min(file_length);
+        let mut bytes = vec![0; end - start];
+        self.file.seek(SeekFrom::Start(start as u64))?;
+        self.file.read_exact(&mut bytes)?;
+        let mut output = File::create(destination)?;
+        output.write_all(&bytes)?;
+        output.sync_all()?;
+        self.remove_chunk(position)
+    }
+
+    /// Rewrites chunks without gaps into a temporary file which replaces the region,
+    /// the old file is kept if the server stops while compacting
+    pub fn compact(&mut self) -> anyhow::Result<()> {
+        let temporary = self.path.with_extension(format!("{}_tmp", REGION_EXTENSION));
+        let mut output = File::create(&temporary)?;
+        let mut header = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+        let mut offset = HEADER_SECTORS;
+        let mut data = Vec::new();
+        for index in 0..self.entries.len() {
+            let entry = self.entries[index];
+            if entry.is_empty() || entry.sectors().end > self.used.len() || (entry.offset as usize) < HEADER_SECTORS {
+                continue;
+            }
+            data.extend_from_slice(&self.read_sectors(entry)?);
+            let moved = ChunkEntry { offset: offset as u32, ..entry };
+            header[index * ENTRY_SIZE..(index + 1) * ENTRY_SIZE].copy_from_slice(&moved.to_bytes());
+            offset += entry.sectors as usize;
+        }
+        output.write_all(&header)?;
+        output.write_all(&data)?;
+        output.sync_all()?;
+        fs::rename(&temporary, &self.path)?;
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Region files of one dimension, they are opened when their chunks are used
+pub struct RegionStorage {
+    directory: PathBuf,
+    regions: HashMap<Vector2D<i32>, RegionFile>,
+    quarantined: Vec<Vector2D<i32>>,
+}
+
+impl RegionStorage {
+    pub fn new(directory: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self { directory: directory.to_path_buf(), regions: HashMap::new(), quarantined: Vec::new() })
+    }
+
+    /// `None` if the region doesn't exist and it is not created
+    fn region(&mut self, region: Vector2D<i32>, create: bool) -> anyhow::Result<Option<&mut RegionFile>> {
+        if !self.regions.contains_key(&region) {
+            let path = region_path(&self.directory, region);
+            if !create && !path.exists() {
+                return Ok(None);
+            }
+            self.regions.insert(region, RegionFile::open(&path)?);
+        }
+        Ok(self.regions.get_mut(&region))
+    }
+
+    /// Corrupted chunks are quarantined and `None` is returned, so they are generated again
+    pub fn load(&mut self, position: Vector2D<i32>) -> anyhow::Result<Option<StoredChunk>> {
+        let quarantine = self.directory.join(QUARANTINE_DIRECTORY);
+        let region = match self.region(region_position(position), false)? {
+            Some(region) => region,
+            None => return Ok(None),
+        };
+        match region.read_chunk(position)? {
+            ChunkRead::Missing => Ok(None),
+            ChunkRead::Loaded(sections) => Ok(Some(StoredChunk { position, sections })),
+            ChunkRead::Corrupted(reason) => {
+                fs::create_dir_all(&quarantine)?;
+                let destination = quarantine.join(format!("c.{}.{}.chunk", position.x, position.y));
+                warn!("Chunk {:?} is corrupted ({}), it is moved to {}", position, reason, destination.display());
+                region.quarantine(position, &destination)?;
+                self.quarantined.push(position);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn save(&mut self, chunk: &StoredChunk) -> anyhow::Result<()> {
+        match self.region(region_position(chunk.position), true)? {
+            Some(region) => region.write_chunk(chunk.position, &chunk.sections),
+            None => Err(anyhow::Error::msg("Region is not created")),
+        }
+    }
+
+    /// Chunks which were quarantined since the storage was opened
+    pub fn quarantined(&self) -> &[Vector2D<i32>] {
+        &self.quarantined
+    }
+
+    /// Compacts opened regions, it is called when the world is saved before the stop
+    pub fn compact(&mut self) -> anyhow::Result<()> {
+        for region in self.regions.values_mut() {
+            region.compact()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_test() {
+        let directory = std::env::temp_dir().join(format!("bird-region-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let mut storage = RegionStorage::new(&directory).unwrap();
+        let chunk = |x: i32, z: i32, byte: u8, length: usize| StoredChunk { position: Vector2D::new(x, z), sections: vec![byte; length] };
+        assert_eq!(storage.load(Vector2D::new(0, 0)).unwrap(), None);
+        storage.save(&chunk(0, 0, 1, 10000)).unwrap();
+        storage.save(&chunk(-1, 31, 2, 100)).unwrap();
+        storage.save(&chunk(0, 0, 3, 100)).unwrap();
+        assert_eq!(storage.load(Vector2D::new(0, 0)).unwrap(), Some(chunk(0, 0, 3, 100)));
+        assert_eq!(storage.load(Vector2D::new(-1, 31)).unwrap(), Some(chunk(-1, 31, 2, 100)));
+
+        // Data which is written without the header update is ignored, like after a crash while saving
+        let path = region_path(&directory, Vector2D::new(0, 0));
+        let mut region = RegionFile::open(&path).unwrap();
+        let free = region.allocate(1);
+        region.file.seek(SeekFrom::Start((free * SECTOR_SIZE) as u64)).unwrap();
+        region.file.write_all(&[0xFF; SECTOR_SIZE]).unwrap();
+        assert_eq!(region.read_chunk(Vector2D::new(0, 0)).unwrap(), ChunkRead::Loaded(vec![3; 100]));
+        region.compact().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, (HEADER_SECTORS + 1) * SECTOR_SIZE);
+        assert_eq!(region.read_chunk(Vector2D::new(0, 0)).unwrap(), ChunkRead::Loaded(vec![3; 100]));
+
+        // The payload of the chunk is damaged
+        let entry = region.entries[0];
+        region.file.seek(SeekFrom::Start((entry.offset as usize * SECTOR_SIZE + LENGTH_SIZE + 2) as u64)).unwrap();
+        region.file.write_all(&[0xAB, 0xCD]).unwrap();
+        assert!(matches!(region.read_chunk(Vector2D::new(0, 0)).unwrap(), ChunkRead::Corrupted(_)));
+        drop(region);
+        let mut storage = RegionStorage::new(&directory).unwrap();
+        assert_eq!(storage.load(Vector2D::new(0, 0)).unwrap(), None);
+        assert_eq!(storage.quarantined(), [Vector2D::new(0, 0)]);
+        assert!(directory.join(QUARANTINE_DIRECTORY).join("c.0.0.chunk").exists());
+        assert_eq!(storage.load(Vector2D::new(0, 0)).unwrap(), None);
+        assert_eq!(storage.quarantined().len(), 1);
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,216 @@
+use uuid::Uuid;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::*;
+
+/// State of an object on clients which describes the packets that change it into another state
+pub trait SyncTarget: Clone + PartialEq {
+    /// Packets which show the state to players who don't see it
+    fn send_initial<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()>;
+
+    /// Packets which change the `previous` state into this one, it is called only if the states are different
+    fn send_diff<S: PacketSender>(&self, previous: &Self, sender: &mut S) -> anyhow::Result<()>;
+
+    /// Packets which remove the state from players
+    fn send_removal<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()>;
+}
+
+/// `None` is the state which players don't see
+impl<T: SyncTarget> SyncTarget for Option<T> {
+    fn send_initial<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        match self {
+            Some(value) => value.send_initial(sender),
+            None => Ok(()),
+        }
+    }
+
+    fn send_diff<S: PacketSender>(&self, previous: &Self, sender: &mut S) -> anyhow::Result<()> {
+        match (previous, self) {
+            (Some(previous), Some(value)) => value.send_diff(previous, sender),
+            (None, Some(value)) => value.send_initial(sender),
+            (Some(previous), None) => previous.send_removal(sender),
+            (None, None) => Ok(()),
+        }
+    }
+
+    fn send_removal<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        match self {
+            Some(value) => value.send_removal(sender),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Value and the state which was sent to its viewers last time. Changes of the value are sent by [`Synced::sync`]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Synced<T> {
+    value: T,
+    sent: Option<T>,
+}
+
+impl<T: SyncTarget> Synced<T> {
+    /// Nothing is sent until the first sync
+    pub fn new(value: T) -> Self {
+        Self { value, sent: None }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+    }
+
+    /// State which viewers see, `None` if it wasn't sent
+    pub fn sent(&self) -> Option<&T> {
+        self.sent.as_ref()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.sent.as_ref() != Some(&self.value)
+    }
+
+    /// Sends the difference between the sent state and the value, `false` if nothing changed
+    pub fn sync<S: PacketSender>(&mut self, viewers: &mut S) -> anyhow::Result<bool> {
+        match self.sent {
+            Some(ref sent) if *sent == self.value => return Ok(false),
+            Some(ref sent) => self.value.send_diff(sent, viewers)?,
+            None => self.value.send_initial(viewers)?,
+        }
+        self.sent = Some(self.value.clone());
+        Ok(true)
+    }
+
+    /// Shows the sent state to a new viewer, so it receives next changes together with other viewers
+    pub fn show<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        match self.sent {
+            Some(ref sent) => sent.send_initial(sender),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the sent state from one viewer
+    pub fn hide<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        match self.sent {
+            Some(ref sent) => sent.send_removal(sender),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the state from all viewers, the next sync sends it from the beginning
+    pub fn remove<S: PacketSender>(&mut self, viewers: &mut S) -> anyhow::Result<()> {
+        match self.sent.take() {
+            Some(sent) => sent.send_removal(viewers),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Boss bar which sends only changed parts
+#[derive(Clone, PartialEq, Debug)]
+pub struct BossBar {
+    pub uuid: Uuid,
+    pub title: Component<'static>,
+    /// From 0 to 1
+    pub health: f32,
+    pub color: BossBarColor,
+    pub division: BossBarDivision,
+    pub flags: BossBarFlags,
+}
+
+impl BossBar {
+    pub fn new(uuid: Uuid, title: Component<'static>) -> Self {
+        Self {
+            uuid,
+            title,
+            health: 1.0,
+            color: BossBarColor::Pink,
+            division: BossBarDivision::Zero,
+            flags: BossBarFlags::new(),
+        }
+    }
+}
+
+impl SyncTarget for BossBar {
+    fn send_initial<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&BossBarPS2C {
+            uuid: self.uuid,
+            action: BossBarAction::Add {
+                title: self.title.clone(),
+                health: self.health,
+                color: self.color,
+                division: self.division,
+                flags: self.flags,
+            },
+        })
+    }
+
+    fn send_diff<S: PacketSender>(&self, previous: &Self, sender: &mut S) -> anyhow::Result<()> {
+        if self.uuid != previous.uuid {
+            previous.send_removal(sender)?;
+            return self.send_initial(sender);
+        }
+        if self.health != previous.health {
+            sender.send_packet(&BossBarPS2C { uuid: self.uuid, action: BossBarAction::UpdateHealth { health: self.health } })?;
+        }
+        if self.title != previous.title {
+            sender.send_packet(&BossBarPS2C { uuid: self.uuid, action: BossBarAction::UpdateTitle { title: self.title.clone() } })?;
+        }
+        if (self.color, self.division) != (previous.color, previous.division) {
+            sender.send_packet(&BossBarPS2C {
+                uuid: self.uuid,
+                action: BossBarAction::UpdateStyle { color: self.color, division: self.division },
+            })?;
+        }
+        if self.flags != previous.flags {
+            sender.send_packet(&BossBarPS2C { uuid: self.uuid, action: BossBarAction::UpdateFlags { flags: self.flags } })?;
+        }
+        Ok(())
+    }
+
+    fn send_removal<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&BossBarPS2C { uuid: self.uuid, action: BossBarAction::Remove })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn synced_boss_bar_test() {
+        let mut queue = PacketQueue::new();
+        let mut bar = Synced::new(None);
+        assert!(bar.sync(&mut queue).unwrap());
+        assert!(queue.take().is_empty());
+        bar.set(Some(BossBar::new(Uuid::from_u128(1), Component::text("Title"))));
+        assert!(bar.is_dirty());
+        bar.sync(&mut queue).unwrap();
+        assert_eq!(queue.take().len(), 1);
+        assert!(!bar.sync(&mut queue).unwrap());
+
+        let value = bar.get_mut().as_mut().unwrap();
+        value.health = 0.5;
+        value.color = BossBarColor::Green;
+        bar.sync(&mut queue).unwrap();
+        // Health and style
+        assert_eq!(queue.take().len(), 2);
+        let mut viewer = PacketQueue::new();
+        bar.show(&mut viewer).unwrap();
+        assert_eq!(viewer.take().len(), 1);
+
+        bar.set(None);
+        bar.sync(&mut queue).unwrap();
+        assert_eq!(queue.take().len(), 1);
+        assert_eq!(bar.sent(), Some(&None));
+        bar.remove(&mut queue).unwrap();
+        assert!(queue.take().is_empty());
+    }
+}
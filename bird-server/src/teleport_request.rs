@@ -0,0 +1,378 @@
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use bird_chat::color::Color;
+use bird_chat::component::{ClickEvent, Component, HoverEvent};
+use crate::events::{EventPriority, EventResult};
+use crate::protocol::*;
+
+pub const TPA_COMMAND: &'static str = "tpa";
+pub const TPA_HERE_COMMAND: &'static str = "tpahere";
+pub const TPA_ACCEPT_COMMAND: &'static str = "tpaccept";
+pub const TPA_DENY_COMMAND: &'static str = "tpdeny";
+const PLAYER_ARGUMENT: &'static str = "player";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TeleportRequestConfig {
+    /// Requests which are not answered in this time are removed
+    pub expire_after: Duration,
+    /// Players may teleport to players in other worlds
+    pub cross_world: bool,
+}
+
+impl Default for TeleportRequestConfig {
+    fn default() -> Self {
+        Self { expire_after: Duration::from_secs(120), cross_world: true }
+    }
+}
+
+/// Online player which may send and answer requests
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TpaPlayer {
+    pub uuid: Uuid,
+    pub name: String,
+    pub world: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeleportDirection {
+    /// `/tpa`, the sender goes to the target
+    ToTarget,
+    /// `/tpahere`, the target comes to the sender
+    Here,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TeleportRequest {
+    pub sender: Uuid,
+    pub sender_name: String,
+    pub target: Uuid,
+    pub target_name: String,
+    pub direction: TeleportDirection,
+    pub sent: Instant,
+}
+
+impl TeleportRequest {
+    /// Player who is teleported and the player at whose position it appears
+    pub fn teleport(&self) -> Teleport {
+        match self.direction {
+            TeleportDirection::ToTarget => Teleport { player: self.sender, destination: self.target },
+            TeleportDirection::Here => Teleport { player: self.target, destination: self.sender },
+        }
+    }
+}
+
+/// Accepted request, the caller moves the player to the destination and to its world if it is another one
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Teleport {
+    pub player: Uuid,
+    pub destination: Uuid,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeleportRequestEvent<'a> {
+    /// Cancelling the event doesn't send the request
+    Request(&'a TeleportRequest),
+    /// Cancelling the event removes the request without the teleport
+    Accept(&'a TeleportRequest),
+    Deny(&'a TeleportRequest),
+    Expire(&'a TeleportRequest),
+}
+
+type TeleportRequestHandler = Box<dyn Fn(&TeleportRequestEvent) -> EventResult + Send + Sync>;
+
+/// Messages for players and the teleport which should be done
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TeleportOutput {
+    pub messages: Vec<(Uuid, Component<'static>)>,
+    pub teleport: Option<Teleport>,
+}
+
+impl TeleportOutput {
+    fn message(player: Uuid, message: Component<'static>) -> Self {
+        Self { messages: vec![(player, message)], teleport: None }
+    }
+}
+
+fn colored(text: impl Into<Cow<'static, str>>, color: Color) -> Component<'static> {
+    let mut component = Component::text(text);
+    component.color = Some(color);
+    component
+}
+
+fn error(text: impl Into<Cow<'static, str>>) -> Component<'static> {
+    colored(text, Color::Red)
+}
+
+/// Button which runs the command on click
+fn button(text: &'static str, color: Color, command: String, hover: &'static str) -> Component<'static> {
+    let mut button = colored(text, color);
+    button.bold = Some(true);
+    button.click_event = Some(ClickEvent::RunCommand(command.into()));
+    button.hover_event = Some(HoverEvent::ShowText(Box::new(Component::text(hover))));
+    button
+}
+
+/// Message of the target with accept and deny buttons
+fn request_message(request: &TeleportRequest) -> Component<'static> {
+    let text = match request.direction {
+        TeleportDirection::ToTarget => format!("{} has requested to teleport to you.\n", request.sender_name),
+        TeleportDirection::Here => format!("{} has requested that you teleport to them.\n", request.sender_name),
+    };
+    let mut message = colored(text, Color::Gold);
+    message.extra = Cow::Owned(vec![
+        button("[Accept]", Color::BrightGreen, format!("/{} {}", TPA_ACCEPT_COMMAND, request.sender_name), "Click to accept"),
+        Component::text(" "),
+        button("[Deny]", Color::Red, format!("/{} {}", TPA_DENY_COMMAND, request.sender_name), "Click to deny"),
+    ]);
+    message
+}
+
+/// Teleport requests between online players, `/tpa`, `/tpahere`, `/tpaccept` and `/tpdeny` are handled by it
+pub struct TeleportRequests {
+    pub config: TeleportRequestConfig,
+    requests: Vec<TeleportRequest>,
+    handlers: Vec<(EventPriority, TeleportRequestHandler)>,
+}
+
+impl TeleportRequests {
+    pub fn new(config: TeleportRequestConfig) -> Self {
+        Self { config, requests: Vec::new(), handlers: Vec::new() }
+    }
+
+    /// Handlers with the higher priority are called first, [`EventResult::Cancel`] skips the remaining ones
+    pub fn on_event<F>(&mut self, priority: EventPriority, handler: F) -> &mut Self
+        where F: Fn(&TeleportRequestEvent) -> EventResult + Send + Sync + 'static {
+        let index = self.handlers.partition_point(|(other, _)| *other >= priority);
+        self.handlers.insert(index, (priority, Box::new(handler)));
+        self
+    }
+
+    fn call(&self, event: TeleportRequestEvent) -> EventResult {
+        for (_, handler) in &self.handlers {
+            if handler(&event) == EventResult::Cancel {
+                return EventResult::Cancel;
+            }
+        }
+        EventResult::Continue
+    }
+
+    /// Requests which the player has not answered yet, the latest is the last
+    pub fn pending_for(&self, target: Uuid, now: Instant) -> impl Iterator<Item = &TeleportRequest> {
+        let expire_after = self.config.expire_after;
+        self.requests.iter().filter(move |request| request.target == target && now < request.sent + expire_after)
+    }
+
+    /// Replaces the previous request of the sender to the same target
+    pub fn request(&mut self, sender: &TpaPlayer, target: &TpaPlayer, direction: TeleportDirection, now: Instant) -> TeleportOutput {
+        if sender.uuid == target.uuid {
+            return TeleportOutput::message(sender.uuid, error("You can't teleport to yourself"));
+        }
+        if !self.config.cross_world && sender.world != target.world {
+            return TeleportOutput::message(sender.uuid, error(format!("{} is in another world", target.name)));
+        }
+        let request = TeleportRequest {
+            sender: sender.uuid,
+            sender_name: sender.name.clone(),
+            target: target.uuid,
+            target_name: target.name.clone(),
+            direction,
+            sent: now,
+        };
+        if self.call(TeleportRequestEvent::Request(&request)) == EventResult::Cancel {
+            return TeleportOutput::default();
+        }
+        self.requests.retain(|other| other.sender != sender.uuid || other.target != target.uuid);
+        let output = TeleportOutput {
+            messages: vec![
+                (sender.uuid, colored(format!("Request sent to {}", target.name), Color::Gold)),
+                (target.uuid, request_message(&request)),
+            ],
+            teleport: None,
+        };
+        self.requests.push(request);
+        output
+    }
+
+    /// Removes the request of the sender with the name or the latest request if there is no name
+    fn take_request(&mut self, target: Uuid, sender_name: Option<&str>, now: Instant) -> Option<TeleportRequest> {
+        let index = self.pending_for(target, now)
+            .enumerate()
+            .filter(|(_, request)| sender_name.map_or(true, |name| request.sender_name.eq_ignore_ascii_case(name)))
+            .last()
+            .map(|(index, _)| index)?;
+        let sent = self.pending_for(target, now).nth(index)?.clone();
+        self.requests.retain(|request| *request != sent);
+        Some(sent)
+    }
+
+    /// `players` are online players, the other player must still be online
+    pub fn accept(&mut self, target: &TpaPlayer, sender_name: Option<&str>, players: &[TpaPlayer], now: Instant) -> TeleportOutput {
+        let request = match self.take_request(target.uuid, sender_name, now) {
+            Some(request) => request,
+            None => return TeleportOutput::message(target.uuid, error("You don't have a pending teleport request")),
+        };
+        let sender = match players.iter().find(|player| player.uuid == request.sender) {
+            Some(sender) => sender,
+            None => return TeleportOutput::message(target.uuid, error(format!("{} is no longer online", request.sender_name))),
+        };
+        if !self.config.cross_world && sender.world != target.world {
+            return TeleportOutput::message(target.uuid, error(format!("{} is in another world", sender.name)));
+        }
+        if self.call(TeleportRequestEvent::Accept(&request)) == EventResult::Cancel {
+            return TeleportOutput::default();
+        }
+        TeleportOutput {
+            messages: vec![
+                (target.uuid, colored("Teleport request accepted", Color::Gold)),
+                (sender.uuid, colored(format!("{} accepted your teleport request", target.name), Color::Gold)),
+            ],
+            teleport: Some(request.teleport()),
+        }
+    }
+
+    pub fn deny(&mut self, target: &TpaPlayer, sender_name: Option<&str>, now: Instant) -> TeleportOutput {
+        let request = match self.take_request(target.uuid, sender_name, now) {
+            Some(request) => request,
+            None => return TeleportOutput::message(target.uuid, error("You don't have a pending teleport request")),
+        };
+        self.call(TeleportRequestEvent::Deny(&request));
+        TeleportOutput {
+            messages: vec![
+                (target.uuid, colored("Teleport request denied", Color::Gold)),
+                (request.sender, error(format!("{} denied your teleport request", target.name))),
+            ],
+            teleport: None,
+        }
+    }
+
+    /// Removes expired requests and notifies their senders
+    pub fn expire(&mut self, now: Instant) -> TeleportOutput {
+        let expire_after = self.config.expire_after;
+        let (expired, pending) = std::mem::take(&mut self.requests).into_iter()
+            .partition::<Vec<_>, _>(|request| now >= request.sent + expire_after);
+        self.requests = pending;
+        let mut output = TeleportOutput::default();
+        for request in expired {
+            self.call(TeleportRequestEvent::Expire(&request));
+            output.messages.push((request.sender, error(format!("Your teleport request to {} has expired", request.target_name))));
+        }
+        output
+    }
+
+    /// Requests of the player and to the player are removed when it leaves
+    pub fn remove_player(&mut self, uuid: Uuid) {
+        self.requests.retain(|request| request.sender != uuid && request.target != uuid);
+    }
+
+    /// Handles the command without the slash, `None` if it is not a teleport request command
+    pub fn handle_command(&mut self, player: &TpaPlayer, command: &str, players: &[TpaPlayer], now: Instant) -> Option<TeleportOutput> {
+        let mut parts = command.split_whitespace();
+        let name = parts.next()?;
+        let argument = parts.next();
+        let direction = match name {
+            TPA_COMMAND => TeleportDirection::ToTarget,
+            TPA_HERE_COMMAND => TeleportDirection::Here,
+            TPA_ACCEPT_COMMAND => return Some(self.accept(player, argument, players, now)),
+            TPA_DENY_COMMAND => return Some(self.deny(player, argument, now)),
+            _ => return None,
+        };
+        let output = match argument.map(|argument| players.iter().find(|other| other.name.eq_ignore_ascii_case(argument))) {
+            Some(Some(target)) => self.request(player, target, direction, now),
+            Some(None) => TeleportOutput::message(player.uuid, error("Player is not online")),
+            None => TeleportOutput::message(player.uuid, error(format!("Usage: /{} <player>", name))),
+        };
+        Some(output)
+    }
+
+    /// Appends nodes of the commands to the [`CommandsPS2C`] tree, returns the nodes which are children of the root
+    pub fn declare_commands(nodes: &mut Vec<BrigadierNode<'static>>) -> Vec<i32> {
+        let mut literals = Vec::new();
+        for (command, optional) in [(TPA_COMMAND, false), (TPA_HERE_COMMAND, false), (TPA_ACCEPT_COMMAND, true), (TPA_DENY_COMMAND, true)] {
+            let argument = nodes.len() as i32;
+            nodes.push(BrigadierNode {
+                executable: true,
+                children: Cow::Borrowed(&[]),
+                redirect_node: None,
+                name: Some(PLAYER_ARGUMENT),
+                parser: Some(BrigadierNodeParser::Entity(BrigadierNodeParseEntity::new().with_single(true).with_only_players(true))),
+                suggestions_type: None,
+            });
+            literals.push(nodes.len() as i32);
+            nodes.push(BrigadierNode {
+                executable: optional,
+                children: Cow::Owned(vec![argument]),
+                redirect_node: None,
+                name: Some(command),
+                parser: None,
+                suggestions_type: None,
+            });
+        }
+        literals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use super::*;
+
+    fn player(id: u128, name: &str, world: &str) -> TpaPlayer {
+        TpaPlayer { uuid: Uuid::from_u128(id), name: name.into(), world: world.into() }
+    }
+
+    #[test]
+    fn teleport_request_test() {
+        let now = Instant::now();
+        let (alex, steve, notch) = (player(1, "Alex", "world"), player(2, "Steve", "world_nether"), player(3, "Notch", "world"));
+        let players = [alex.clone(), steve.clone(), notch.clone()];
+        let mut requests = TeleportRequests::new(TeleportRequestConfig::default());
+        let expired = Arc::new(AtomicUsize::new(0));
+        let counter = expired.clone();
+        requests
+            .on_event(EventPriority::Normal, move |event| {
+                if let TeleportRequestEvent::Expire(_) = event {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+                EventResult::Continue
+            })
+            .on_event(EventPriority::High, |event| match event {
+                TeleportRequestEvent::Request(request) if request.target_name == "Notch" => EventResult::Cancel,
+                _ => EventResult::Continue,
+            });
+
+        assert_eq!(requests.handle_command(&alex, "say hi", &players, now), None);
+        let output = requests.handle_command(&alex, "tpa steve", &players, now).unwrap();
+        assert_eq!(output.messages.len(), 2);
+        let buttons = &output.messages[1].1.extra;
+        assert_eq!(buttons[0].click_event, Some(ClickEvent::RunCommand("/tpaccept Alex".into())));
+        assert_eq!(buttons[2].click_event, Some(ClickEvent::RunCommand("/tpdeny Alex".into())));
+        // Cancelled by the handler
+        assert!(requests.handle_command(&alex, "tpa Notch", &players, now).unwrap().messages.is_empty());
+        requests.handle_command(&notch, "tpahere Steve", &players, now);
+        assert_eq!(requests.pending_for(steve.uuid, now).count(), 2);
+
+        // The latest request is accepted without the name, players of other worlds are teleported
+        let output = requests.handle_command(&steve, "tpaccept", &players, now).unwrap();
+        assert_eq!(output.teleport, Some(Teleport { player: steve.uuid, destination: notch.uuid }));
+        let output = requests.handle_command(&steve, "tpdeny alex", &players, now).unwrap();
+        assert_eq!(output.teleport, None);
+        assert_eq!(output.messages[1].0, alex.uuid);
+        assert!(requests.handle_command(&steve, "tpaccept", &players, now).unwrap().teleport.is_none());
+
+        requests.config.cross_world = false;
+        assert!(requests.request(&alex, &steve, TeleportDirection::ToTarget, now).messages[0].1.color == Some(Color::Red));
+        requests.request(&notch, &alex, TeleportDirection::ToTarget, now);
+        let later = now + requests.config.expire_after;
+        assert!(requests.accept(&alex, None, &players, later).teleport.is_none());
+        assert_eq!(requests.expire(later).messages.len(), 1);
+        assert_eq!(expired.load(Ordering::Relaxed), 1);
+
+        let mut nodes = Vec::new();
+        let literals = TeleportRequests::declare_commands(&mut nodes);
+        assert_eq!(literals.len(), 4);
+        assert_eq!(nodes[literals[2] as usize].name, Some(TPA_ACCEPT_COMMAND));
+        assert!(nodes[literals[2] as usize].executable && !nodes[literals[0] as usize].executable);
+    }
+}
@@ -0,0 +1,4 @@
+/// Id of the item which is known to exist
+pub fn item(name: &str) -> i32 {
+    crate::inventory::item_id(name).unwrap()
+}
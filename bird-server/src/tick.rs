@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+use crate::instrument::warn;
+use crate::metrics::tps;
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(50);
+/// Ticks are skipped instead of caught up if the loop is behind by more than this like in vanilla
+pub const MAX_BEHIND: Duration = Duration::from_secs(2);
+/// Amount of ticks in the average of MSPT
+const MSPT_SAMPLES: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TaskId(u64);
+
+type Task<C> = Box<dyn FnMut(&mut C)>;
+
+struct ScheduledTask<C> {
+    /// Repeating tasks are scheduled again after this amount of ticks
+    period: Option<u64>,
+    task: Task<C>,
+}
+
+/// Tasks which are run by the game loop, `C` is the state which is passed to them.
+/// Tasks which are due at the same tick are run in the order they were scheduled
+pub struct Scheduler<C> {
+    tick: u64,
+    next_id: u64,
+    /// Keyed by the tick when the task is run and the id
+    tasks: BTreeMap<(u64, u64), ScheduledTask<C>>,
+}
+
+impl<C> Default for Scheduler<C> {
+    fn default() -> Self {
+        Self { tick: 0, next_id: 0, tasks: BTreeMap::new() }
+    }
+}
+
+impl<C> Scheduler<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Amount of ticks which were run
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    fn schedule(&mut self, delay: u64, period: Option<u64>, task: Task<C>) -> TaskId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.tasks.insert((self.tick + delay.max(1), id), ScheduledTask { period: period.map(|period| period.max(1)), task });
+        TaskId(id)
+    }
+
+    /// Task which is run every tick starting from the next one
+    pub fn every_tick(&mut self, task: impl FnMut(&mut C) + 'static) -> TaskId {
+        self.schedule(1, Some(1), Box::new(task))
+    }
+
+    /// Task which is run once after `delay` ticks, zero delay is the next tick
+    pub fn run_later(&mut self, delay: u64, task: impl FnMut(&mut C) + 'static) -> TaskId {
+        self.schedule(delay, None, Box::new(task))
+    }
+
+    pub fn run_repeating(&mut self, delay: u64, period: u64, task: impl FnMut(&mut C) + 'static) -> TaskId {
+        self.schedule(delay, Some(period), Box::new(task))
+    }
+
+    fn key(&self, id: TaskId) -> Option<(u64, u64)> {
+        self.tasks.keys().find(|(_, task_id)| *task_id == id.0).copied()
+    }
+
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        match self.key(id) {
+            Some(key) => self.tasks.remove(&key).is_some(),
+            None => false,
+        }
+    }
+
+    pub fn is_scheduled(&self, id: TaskId) -> bool {
+        self.key(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Runs the next tick, returns the amount of run tasks
+    pub fn tick(&mut self, context: &mut C) -> usize {
+        self.tick += 1;
+        let mut run = 0;
+        while let Some((due, id)) = self.tasks.keys().next().copied().filter(|(due, _)| *due <= self.tick) {
+            let mut scheduled = self.tasks.remove(&(due, id)).unwrap();
+            (scheduled.task)(context);
+            run += 1;
+            if let Some(period) = scheduled.period {
+                self.tasks.insert((self.tick + period, id), scheduled);
+            }
+        }
+        run
+    }
+}
+
+/// Amount of ticks which the loop must run now
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TickPoll {
+    pub run: u32,
+    /// Ticks which are not run because the loop is too far behind
+    pub skipped: u64,
+}
+
+/// Fixed rate clock, long ticks are caught up by running the next ticks without waiting
+#[derive(Clone, Debug)]
+pub struct TickClock {
+    next_tick: Instant,
+}
+
+impl TickClock {
+    pub fn new(now: Instant) -> Self {
+        Self { next_tick: now }
+    }
+
+    pub fn poll(&mut self, now: Instant) -> TickPoll {
+        if now < self.next_tick {
+            return TickPoll::default();
+        }
+        let behind = now - self.next_tick;
+        let ticks = (behind.as_nanos() / TICK_DURATION.as_nanos()) as u64 + 1;
+        if behind > MAX_BEHIND {
+            self.next_tick = now + TICK_DURATION;
+            return TickPoll { run: 1, skipped: ticks - 1 };
+        }
+        self.next_tick += TICK_DURATION * ticks as u32;
+        TickPoll { run: ticks as u32, skipped: 0 }
+    }
+
+    pub fn until_next_tick(&self, now: Instant) -> Duration {
+        self.next_tick.saturating_duration_since(now)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TickStatistics {
+    durations: VecDeque<Duration>,
+    ticks: u64,
+    skipped: u64,
+}
+
+impl TickStatistics {
+    pub fn record(&mut self, duration: Duration) {
+        if self.durations.len() == MSPT_SAMPLES {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+        self.ticks += 1;
+    }
+
+    pub fn record_skipped(&mut self, skipped: u64) {
+        self.skipped += skipped;
+    }
+
+    /// Average duration of the last ticks in milliseconds
+    pub fn mspt(&self) -> f64 {
+        match self.durations.is_empty() {
+            true => 0.0,
+            false => self.durations.iter().sum::<Duration>().as_secs_f64() * 1000.0 / self.durations.len() as f64,
+        }
+    }
+
+    pub fn tps(&self) -> f64 {
+        tps(self.mspt())
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+/// 20 TPS loop which runs the scheduler and measures ticks
+pub struct GameLoop<C> {
+    pub scheduler: Scheduler<C>,
+    clock: TickClock,
+    statistics: TickStatistics,
+}
+
+impl<C> GameLoop<C> {
+    pub fn new(now: Instant) -> Self {
+        Self { scheduler: Scheduler::new(), clock: TickClock::new(now), statistics: TickStatistics::default() }
+    }
+
+    pub fn statistics(&self) -> &TickStatistics {
+        &self.statistics
+    }
+
+    /// Runs ticks which are due, returns their amount
+    pub fn run_due(&mut self, context: &mut C, now: Instant) -> u32 {
+        let poll = self.clock.poll(now);
+        if poll.skipped > 0 {
+            warn!("Can't keep up! Skipping {} ticks", poll.skipped);
+            self.statistics.record_skipped(poll.skipped);
+        }
+        for _ in 0..poll.run {
+            let started = Instant::now();
+            self.scheduler.tick(context);
+            self.statistics.record(started.elapsed());
+        }
+        poll.run
+    }
+
+    pub fn until_next_tick(&self, now: Instant) -> Duration {
+        self.clock.until_next_tick(now)
+    }
+
+    /// Runs the loop on the current thread while `running` returns true
+    pub fn run(&mut self, context: &mut C, mut running: impl FnMut(&C) -> bool) {
+        while running(context) {
+            let now = Instant::now();
+            if self.run_due(context, now) == 0 {
+                std::thread::sleep(self.until_next_tick(now));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduler_test() {
+        let mut scheduler = Scheduler::<Vec<&'static str>>::new();
+        let mut log = Vec::new();
+        scheduler.every_tick(|log| log.push("tick"));
+        let later = scheduler.run_later(2, |log| log.push("later"));
+        let keep_alive = scheduler.run_repeating(1, 3, |log| log.push("keep alive"));
+        assert_eq!(scheduler.tick(&mut log), 2);
+        assert_eq!(log, ["tick", "keep alive"]);
+        log.clear();
+        scheduler.tick(&mut log);
+        assert_eq!(log, ["tick", "later"]);
+        assert!(!scheduler.is_scheduled(later));
+        scheduler.tick(&mut log);
+        log.clear();
+        scheduler.tick(&mut log);
+        assert_eq!(log, ["tick", "keep alive"]);
+        assert!(scheduler.cancel(keep_alive));
+        assert!(!scheduler.cancel(keep_alive));
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(scheduler.current_tick(), 4);
+    }
+
+    #[test]
+    fn tick_clock_test() {
+        let now = Instant::now();
+        let mut clock = TickClock::new(now);
+        assert_eq!(clock.poll(now), TickPoll { run: 1, skipped: 0 });
+        assert_eq!(clock.poll(now + Duration::from_millis(20)), TickPoll::default());
+        assert_eq!(clock.until_next_tick(now + Duration::from_millis(20)), Duration::from_millis(30));
+        // The tick took 170 ms, so 3 ticks are caught up
+        assert_eq!(clock.poll(now + Duration::from_millis(170)), TickPoll { run: 3, skipped: 0 });
+        assert_eq!(clock.poll(now + Duration::from_secs(5)), TickPoll { run: 1, skipped: 96 });
+        assert_eq!(clock.until_next_tick(now + Duration::from_secs(5)), TICK_DURATION);
+
+        let mut statistics = TickStatistics::default();
+        statistics.record(Duration::from_millis(20));
+        statistics.record(Duration::from_millis(180));
+        assert_eq!(statistics.mspt(), 100.0);
+        assert_eq!(statistics.tps(), 10.0);
+        assert_eq!(statistics.ticks(), 2);
+    }
+}
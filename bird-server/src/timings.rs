@@ -0,0 +1,253 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use bird_chat::component::Component;
+
+pub const DEFAULT_TARGET_MSPT: f64 = 50.0;
+
+/// Throttled types are ticked every `2^throttle` ticks
+const MAX_THROTTLE: u32 = 4;
+/// Amount of ticks in the average of MSPT
+const MSPT_SAMPLES: usize = 100;
+/// Only one type is throttled during this amount of ticks so the change is visible in MSPT
+const THROTTLE_INTERVAL: u32 = 20;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TickBudgetConfig {
+    pub target_mspt: f64,
+    /// Time which block entities of one chunk may take in one tick
+    pub chunk_budget: Duration,
+    /// Time which block entities of one type may take in one tick
+    pub type_budget: Duration,
+    /// Throttling is relaxed by one step after this amount of ticks under the target
+    pub recovery_ticks: u32,
+}
+
+impl Default for TickBudgetConfig {
+    fn default() -> Self {
+        Self {
+            target_mspt: DEFAULT_TARGET_MSPT,
+            chunk_budget: Duration::from_millis(2),
+            type_budget: Duration::from_millis(10),
+            recovery_ticks: 200,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TypeTimings {
+    pub count: u64,
+    pub total: Duration,
+    /// Ticks which were skipped because of budgets or throttling
+    pub skipped: u64,
+    pub throttle: u32,
+    /// Time spent in the current tick
+    current: Duration,
+}
+
+impl TypeTimings {
+    pub fn average_micros(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.total.as_secs_f64() * 1_000_000.0 / count as f64,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TimingsEntry {
+    pub kind: String,
+    pub timings: TypeTimings,
+}
+
+/// Tick budgets and profiler of block entities
+#[derive(Clone, PartialEq, Debug)]
+pub struct BlockEntityTimings {
+    pub config: TickBudgetConfig,
+    tick: u64,
+    types: HashMap<String, TypeTimings>,
+    mspt: VecDeque<f64>,
+    ticks_over_target: u32,
+    ticks_under_target: u32,
+}
+
+impl BlockEntityTimings {
+    pub fn new(config: TickBudgetConfig) -> Self {
+        Self {
+            config,
+            tick: 0,
+            types: HashMap::new(),
+            mspt: VecDeque::with_capacity(MSPT_SAMPLES),
+            ticks_over_target: 0,
+            ticks_under_target: 0,
+        }
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&TypeTimings> {
+        self.types.get(kind)
+    }
+
+    /// Returns false if the block entity must be skipped in the current tick,
+    /// `chunk_time` is the time already spent on the chunk
+    pub fn should_tick(&self, kind: &str, chunk_time: Duration) -> bool {
+        if chunk_time >= self.config.chunk_budget {
+            return false;
+        }
+        match self.types.get(kind) {
+            Some(timings) => timings.current < self.config.type_budget && self.tick % (1 << timings.throttle) == 0,
+            None => true,
+        }
+    }
+
+    pub fn record(&mut self, kind: &str, duration: Duration) {
+        let timings = self.entry(kind);
+        timings.count += 1;
+        timings.total += duration;
+        timings.current += duration;
+    }
+
+    pub fn skip(&mut self, kind: &str) {
+        self.entry(kind).skipped += 1;
+    }
+
+    fn entry(&mut self, kind: &str) -> &mut TypeTimings {
+        if !self.types.contains_key(kind) {
+            self.types.insert(kind.into(), TypeTimings::default());
+        }
+        self.types.get_mut(kind).unwrap()
+    }
+
+    /// Ticks block entities of one chunk, `entities` yields the type and the block entity
+    pub fn tick_chunk<'a, T, I, F>(&mut self, entities: I, mut tick: F)
+        where T: 'a, I: IntoIterator<Item=(&'a str, &'a mut T)>, F: FnMut(&mut T) {
+        let mut chunk_time = Duration::ZERO;
+        for (kind, entity) in entities {
+            if !self.should_tick(kind, chunk_time) {
+                self.skip(kind);
+                continue;
+            }
+            let start = Instant::now();
+            tick(entity);
+            let elapsed = start.elapsed();
+            chunk_time += elapsed;
+            self.record(kind, elapsed);
+        }
+    }
+
+    /// Must be called at the end of every server tick with its full duration
+    pub fn end_tick(&mut self, tick_duration: Duration) {
+        if self.mspt.len() == MSPT_SAMPLES {
+            self.mspt.pop_front();
+        }
+        self.mspt.push_back(tick_duration.as_secs_f64() * 1000.0);
+        if self.mspt() > self.config.target_mspt {
+            self.ticks_under_target = 0;
+            self.ticks_over_target += 1;
+            if self.ticks_over_target >= THROTTLE_INTERVAL {
+                self.ticks_over_target = 0;
+                self.throttle_most_expensive();
+            }
+        } else {
+            self.ticks_over_target = 0;
+            self.ticks_under_target += 1;
+            if self.ticks_under_target >= self.config.recovery_ticks {
+                self.ticks_under_target = 0;
+                for timings in self.types.values_mut() {
+                    timings.throttle = timings.throttle.saturating_sub(1);
+                }
+            }
+        }
+        for timings in self.types.values_mut() {
+            timings.current = Duration::ZERO;
+        }
+        self.tick += 1;
+    }
+
+    fn throttle_most_expensive(&mut self) {
+        let most_expensive = self.types.values_mut()
+            .filter(|timings| timings.throttle < MAX_THROTTLE)
+            .max_by_key(|timings| timings.total);
+        if let Some(timings) = most_expensive {
+            timings.throttle += 1;
+        }
+    }
+
+    /// Average milliseconds per tick of the last 100 ticks
+    pub fn mspt(&self) -> f64 {
+        match self.mspt.len() {
+            0 => 0.0,
+            len => self.mspt.iter().sum::<f64>() / len as f64,
+        }
+    }
+
+    /// Types sorted by the total time, the most expensive first
+    pub fn report(&self) -> Vec<TimingsEntry> {
+        let mut report: Vec<_> = self.types.iter()
+            .map(|(kind, timings)| TimingsEntry { kind: kind.clone(), timings: *timings })
+            .collect();
+        report.sort_by(|first, second| second.timings.total.cmp(&first.timings.total));
+        report
+    }
+
+    pub fn reset(&mut self) {
+        for timings in self.types.values_mut() {
+            *timings = TypeTimings { throttle: timings.throttle, ..Default::default() };
+        }
+        self.mspt.clear();
+    }
+
+    /// Handles `/timings [report|reset]`
+    pub fn handle_command(&mut self, arguments: &str) -> Component<'static> {
+        match arguments.trim() {
+            "" | "report" => Component::text(self.report_text()),
+            "reset" => {
+                self.reset();
+                Component::text("Timings were reset")
+            }
+            _ => Component::text("Usage: /timings [report|reset]"),
+        }
+    }
+
+    pub fn report_text(&self) -> String {
+        let mut text = format!("MSPT: {:.2} (target {:.2})", self.mspt(), self.config.target_mspt);
+        for entry in self.report() {
+            let timings = &entry.timings;
+            let _ = write!(
+                text, "\n{}: {} ticks, {:.1} µs average, {} skipped",
+                entry.kind, timings.count, timings.average_micros(), timings.skipped,
+            );
+            if timings.throttle > 0 {
+                let _ = write!(text, ", throttled to every {} ticks", 1 << timings.throttle);
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle_test() {
+        let mut timings = BlockEntityTimings::new(TickBudgetConfig { recovery_ticks: 10, ..Default::default() });
+        timings.record("minecraft:hopper", Duration::from_millis(30));
+        timings.record("minecraft:furnace", Duration::from_millis(1));
+        for _ in 0..THROTTLE_INTERVAL {
+            timings.end_tick(Duration::from_millis(60));
+        }
+        assert_eq!(timings.get("minecraft:hopper").unwrap().throttle, 1);
+        assert_eq!(timings.get("minecraft:furnace").unwrap().throttle, 0);
+        // Throttled hopper is ticked only in even ticks
+        timings.end_tick(Duration::from_millis(60));
+        assert!(!timings.should_tick("minecraft:hopper", Duration::ZERO));
+        assert!(timings.should_tick("minecraft:furnace", Duration::ZERO));
+        assert!(!timings.should_tick("minecraft:furnace", Duration::from_millis(2)));
+        assert_eq!(timings.report()[0].kind, "minecraft:hopper");
+        timings.reset();
+        for _ in 0..10 {
+            timings.end_tick(Duration::from_millis(10));
+        }
+        assert_eq!(timings.get("minecraft:hopper").unwrap().throttle, 0);
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write as _};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use bird_protocol::{anyhow, ProtocolPacketBound, ProtocolPacketState};
+use crate::registry::Packet;
+
+pub const DEFAULT_TRACE_PACKETS: usize = 256;
+pub const DEFAULT_TRACE_TRANSITIONS: usize = 16;
+/// Debug output of big packets like chunks is cut to this amount of chars
+pub const MAX_PACKET_SUMMARY: usize = 512;
+
+pub const SUMMARY_FILE: &'static str = "summary.txt";
+pub const PACKETS_FILE: &'static str = "packets.txt";
+pub const TRANSITIONS_FILE: &'static str = "transitions.txt";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceConfig {
+    /// Traces are collected only if they are enabled, they are off by default
+    pub enabled: bool,
+    pub max_packets: usize,
+    pub max_transitions: usize,
+    /// Bundles are saved here by [`TraceCollector::save`]
+    pub directory: PathBuf,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_packets: DEFAULT_TRACE_PACKETS,
+            max_transitions: DEFAULT_TRACE_TRANSITIONS,
+            directory: PathBuf::from("traces"),
+        }
+    }
+}
+
+/// Hash of the debug output, so configs don't have to implement `Hash`
+pub fn config_hash<T: Debug>(config: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(format!("{:?}", config).as_bytes());
+    hasher.finish()
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TracedPacket {
+    /// Time since the connection was opened
+    pub time: Duration,
+    pub state: ProtocolPacketState,
+    pub bound: ProtocolPacketBound,
+    pub id: i32,
+    /// Length of the frame without the length itself
+    pub length: usize,
+    pub summary: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StateTransition {
+    pub time: Duration,
+    pub from: ProtocolPacketState,
+    pub to: ProtocolPacketState,
+}
+
+/// Keeps the latest packets and state transitions of one connection,
+/// they are zipped into a bundle which is attached to bug reports
+#[derive(Clone, Debug)]
+pub struct TraceCollector {
+    config: TraceConfig,
+    connection: u64,
+    protocol_version: i32,
+    config_hash: u64,
+    opened: Instant,
+    packets: VecDeque<TracedPacket>,
+    transitions: VecDeque<StateTransition>,
+    /// Packets which were pushed out of the trace
+    dropped_packets: u64,
+}
+
+fn push_limited<T>(queue: &mut VecDeque<T>, limit: usize, value: T) -> bool {
+    if limit == 0 {
+        return false;
+    }
+    let full = queue.len() >= limit;
+    if full {
+        queue.pop_front();
+    }
+    queue.push_back(value);
+    full
+}
+
+fn summary(packet: &Packet) -> String {
+    let mut summary = format!("{:?}", packet);
+    if summary.len() > MAX_PACKET_SUMMARY {
+        let mut end = MAX_PACKET_SUMMARY;
+        while !summary.is_char_boundary(end) {
+            end -= 1;
+        }
+        summary.truncate(end);
+        summary.push_str("...");
+    }
+    summary
+}
+
+impl TraceCollector {
+    pub fn new(config: TraceConfig, connection: u64, protocol_version: i32, config_hash: u64, now: Instant) -> Self {
+        Self {
+            config,
+            connection,
+            protocol_version,
+            config_hash,
+            opened: now,
+            packets: VecDeque::new(),
+            transitions: VecDeque::new(),
+            dropped_packets: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn set_protocol_version(&mut self, protocol_version: i32) {
+        self.protocol_version = protocol_version;
+    }
+
+    pub fn packets(&self) -> impl Iterator<Item=&TracedPacket> {
+        self.packets.iter()
+    }
+
+    pub fn transitions(&self) -> impl Iterator<Item=&StateTransition> {
+        self.transitions.iter()
+    }
+
+    /// Records the decoded packet, `length` is the length of its frame
+    pub fn record_packet(&mut self, packet: &Packet, length: usize, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let (state, bound, id) = packet.key();
+        let traced = TracedPacket { time: now - self.opened, state, bound, id, length, summary: summary(packet) };
+        if push_limited(&mut self.packets, self.config.max_packets, traced) {
+            self.dropped_packets += 1;
+        }
+    }
+
+    /// Records the packet which is not known to the registry
+    pub fn record_unknown(&mut self, state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32, length: usize, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let traced = TracedPacket { time: now - self.opened, state, bound, id, length, summary: String::from("unknown") };
+        if push_limited(&mut self.packets, self.config.max_packets, traced) {
+            self.dropped_packets += 1;
+        }
+    }
+
+    pub fn record_transition(&mut self, from: ProtocolPacketState, to: ProtocolPacketState, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let transition = StateTransition { time: now - self.opened, from, to };
+        push_limited(&mut self.transitions, self.config.max_transitions, transition);
+    }
+
+    fn summary_file(&self, error: &anyhow::Error) -> String {
+        let mut summary = String::new();
+        // Writing into a string doesn't fail
+        let _ = writeln!(summary, "connection: {}", self.connection);
+        let _ = writeln!(summary, "protocol version: {}", self.protocol_version);
+        let _ = writeln!(summary, "config hash: {:016x}", self.config_hash);
+        let _ = writeln!(summary, "server version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(summary, "dropped packets: {}", self.dropped_packets);
+        let _ = writeln!(summary, "error: {:?}", error);
+        summary
+    }
+
+    fn packets_file(&self) -> String {
+        let mut file = String::new();
+        for packet in &self.packets {
+            let _ = writeln!(
+                file, "[{:>10.3}s] {:?} {:?} 0x{:02X} ({} bytes) {}",
+                packet.time.as_secs_f64(), packet.state, packet.bound, packet.id, packet.length, packet.summary,
+            );
+        }
+        file
+    }
+
+    fn transitions_file(&self) -> String {
+        let mut file = String::new();
+        for transition in &self.transitions {
+            let _ = writeln!(file, "[{:>10.3}s] {:?} -> {:?}", transition.time.as_secs_f64(), transition.from, transition.to);
+        }
+        file
+    }
+
+    /// Zip archive with the summary, packets and transitions
+    pub fn bundle(&self, error: &anyhow::Error) -> Vec<u8> {
+        let mut zip = StoredZip::new();
+        zip.add(SUMMARY_FILE, self.summary_file(error).as_bytes());
+        zip.add(PACKETS_FILE, self.packets_file().as_bytes());
+        zip.add(TRANSITIONS_FILE, self.transitions_file().as_bytes());
+        zip.finish()
+    }
+
+    /// Bundle of the connection which was closed by the error, `None` if tracing is disabled or there was no error
+    pub fn on_disconnect<T>(&self, result: &anyhow::Result<T>) -> Option<Vec<u8>> {
+        match result {
+            Err(error) if self.config.enabled => Some(self.bundle(error)),
+            _ => None,
+        }
+    }
+
+    /// Writes the bundle into the configured directory, returns the path of it
+    pub fn save(&self, error: &anyhow::Error, unix_time: u64) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(&self.config.directory)?;
+        let path = bundle_path(&self.config.directory, self.connection, unix_time);
+        std::fs::write(&path, self.bundle(error))?;
+        Ok(path)
+    }
+}
+
+pub fn bundle_path(directory: &Path, connection: u64, unix_time: u64) -> PathBuf {
+    directory.join(format!("trace-{}-{}.zip", unix_time, connection))
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = match value & 1 {
+                1 => 0xEDB88320 ^ (value >> 1),
+                _ => value >> 1,
+            };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+const LOCAL_FILE_SIGNATURE: u32 = 0x04034B50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014B50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054B50;
+/// Version 2.0 is needed to extract stored files
+const ZIP_VERSION: u16 = 20;
+/// 1980-01-01, bundles don't need real modification times
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Zip archive without compression, traces are small text files
+struct StoredZip {
+    bytes: Vec<u8>,
+    central_directory: Vec<u8>,
+    files: u16,
+}
+
+impl StoredZip {
+    fn new() -> Self {
+        Self { bytes: Vec::new(), central_directory: Vec::new(), files: 0 }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let offset = self.bytes.len() as u32;
+        let crc = crc32(data);
+        let header = |bytes: &mut Vec<u8>| {
+            bytes.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            bytes.extend_from_slice(&0u16.to_le_bytes()); // flags
+            bytes.extend_from_slice(&0u16.to_le_bytes()); // stored
+            bytes.extend_from_slice(&0u16.to_le_bytes()); // time
+            bytes.extend_from_slice(&DOS_DATE.to_le_bytes());
+            bytes.extend_from_slice(&crc.to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&0u16.to_le_bytes()); // extra field
+        };
+        self.bytes.extend_from_slice(&LOCAL_FILE_SIGNATURE.to_le_bytes());
+        header(&mut self.bytes);
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.extend_from_slice(data);
+
+        let directory = &mut self.central_directory;
+        directory.extend_from_slice(&CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        directory.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // made by
+        header(directory);
+        directory.extend_from_slice(&0u16.to_le_bytes()); // comment
+        directory.extend_from_slice(&0u16.to_le_bytes()); // disk
+        directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+        self.files += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(&self.central_directory);
+        self.bytes.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // disk
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // disk with the central directory
+        self.bytes.extend_from_slice(&self.files.to_le_bytes());
+        self.bytes.extend_from_slice(&self.files.to_le_bytes());
+        self.bytes.extend_from_slice(&(self.central_directory.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(&offset.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::StatusRequest;
+    use crate::registry::StatusC2SPacket;
+    use super::*;
+
+    #[test]
+    fn trace_bundle_test() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        let now = Instant::now();
+        let config = TraceConfig { enabled: true, max_packets: 2, ..Default::default() };
+        let mut trace = TraceCollector::new(config.clone(), 1, 761, config_hash(&config), now);
+        trace.record_transition(ProtocolPacketState::Handshake, ProtocolPacketState::Status, now);
+        for _ in 0..3 {
+            trace.record_packet(&Packet::StatusC2S(StatusC2SPacket::StatusRequest(StatusRequest)), 1, now);
+        }
+        assert_eq!(trace.packets().count(), 2);
+        assert_eq!(trace.transitions().count(), 1);
+        assert!(trace.on_disconnect(&Ok(())).is_none());
+        let bundle = trace.on_disconnect::<()>(&Err(anyhow::Error::msg("broken packet"))).unwrap();
+        assert_eq!(&bundle[..4], &LOCAL_FILE_SIGNATURE.to_le_bytes());
+        assert_eq!(&bundle[bundle.len() - 22..][..4], &END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        let text = String::from_utf8_lossy(&bundle);
+        assert!(text.contains("broken packet"));
+        assert!(text.contains("dropped packets: 1"));
+
+        let disabled = TraceCollector::new(TraceConfig::default(), 2, 761, 0, now);
+        assert!(disabled.on_disconnect::<()>(&Err(anyhow::Error::msg("error"))).is_none());
+    }
+}
@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+use bird_protocol::anyhow;
+use crate::protocol::*;
+
+pub const DEFAULT_TRADE_MAX_USES: i32 = 12;
+pub const DEFAULT_TRADE_XP: i32 = 1;
+pub const DEFAULT_TRADE_PRICE_MULTIPLIER: f32 = 0.05;
+pub const MAX_VILLAGER_LEVEL: i32 = 5;
+/// Experience which is needed for every villager level, starting with the first one
+pub const VILLAGER_LEVEL_EXPERIENCE: [i32; MAX_VILLAGER_LEVEL as usize] = [0, 10, 70, 150, 250];
+
+/// Level of the villager with the experience
+pub fn villager_level_for(experience: i32) -> i32 {
+    VILLAGER_LEVEL_EXPERIENCE.iter().filter(|&&needed| experience >= needed).count().max(1) as i32
+}
+
+/// One offer of the merchant, the trade is disabled when its uses reach the maximum
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trade {
+    pub input: OwnedSlot,
+    pub second_input: Option<OwnedSlot>,
+    pub output: OwnedSlot,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub xp: i32,
+    /// Added to the count of the first input, negative values make the trade cheaper
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+
+impl Trade {
+    pub fn new(input: OwnedSlot, second_input: Option<OwnedSlot>, output: OwnedSlot) -> Self {
+        Self {
+            input,
+            second_input,
+            output,
+            uses: 0,
+            max_uses: DEFAULT_TRADE_MAX_USES,
+            xp: DEFAULT_TRADE_XP,
+            special_price: 0,
+            price_multiplier: DEFAULT_TRADE_PRICE_MULTIPLIER,
+            demand: 0,
+        }
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.uses >= self.max_uses
+    }
+}
+
+/// Collects trades for [`MerchantOffersPS2C`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TradeListBuilder {
+    trades: Vec<Trade>,
+    villager_level: i32,
+    experience: i32,
+    is_regular_villager: bool,
+    can_restock: bool,
+}
+
+impl Default for TradeListBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeListBuilder {
+    /// Trade list of a regular villager on the first level
+    pub fn new() -> Self {
+        Self {
+            trades: Vec::new(),
+            villager_level: 1,
+            experience: 0,
+            is_regular_villager: true,
+            can_restock: true,
+        }
+    }
+
+    /// Trade list without the level and the experience bar
+    pub fn wandering() -> Self {
+        Self { is_regular_villager: false, can_restock: false, ..Self::new() }
+    }
+
+    pub fn villager(&mut self, level: i32, experience: i32) -> &mut Self {
+        self.villager_level = level;
+        self.experience = experience;
+        self.is_regular_villager = true;
+        self
+    }
+
+    /// Shows the restock message instead of the out of stock one for disabled trades
+    pub fn can_restock(&mut self, can_restock: bool) -> &mut Self {
+        self.can_restock = can_restock;
+        self
+    }
+
+    /// Adds the trade with the default uses, xp and price multiplier
+    pub fn trade(&mut self, input: OwnedSlot, second_input: Option<OwnedSlot>, output: OwnedSlot) -> &mut Self {
+        self.add(Trade::new(input, second_input, output))
+    }
+
+    pub fn add(&mut self, trade: Trade) -> &mut Self {
+        self.trades.push(trade);
+        self
+    }
+
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
+    /// Returns an error if an item of a trade is empty or the villager level is out of range.
+    /// An empty second input is sent as the absent one
+    pub fn build(&self, window_id: i32) -> anyhow::Result<MerchantOffersPS2C<'_>> {
+        if self.is_regular_villager && !(1..=MAX_VILLAGER_LEVEL).contains(&self.villager_level) {
+            return Err(anyhow::Error::msg(format!("Villager level {} is out of range", self.villager_level)));
+        }
+        let mut trades = Vec::with_capacity(self.trades.len());
+        for (index, trade) in self.trades.iter().enumerate() {
+            if trade.input.item_count <= 0 || trade.output.item_count <= 0 {
+                return Err(anyhow::Error::msg(format!("Trade {} has an empty item", index)));
+            }
+            if trade.max_uses <= 0 {
+                return Err(anyhow::Error::msg(format!("Trade {} can't be used", index)));
+            }
+            trades.push(MerchantOffersTrades {
+                input_item1: Some(trade.input.as_slot()),
+                output_item: Some(trade.output.as_slot()),
+                input_item2: trade.second_input.as_ref()
+                    .filter(|item| item.item_count > 0)
+                    .map(OwnedSlot::as_slot),
+                disabled: trade.is_disabled(),
+                already_used: trade.uses,
+                maximum_uses: trade.max_uses,
+                xp: trade.xp,
+                special_price: trade.special_price,
+                price_multiplier: trade.price_multiplier,
+                demand: trade.demand,
+            });
+        }
+        Ok(MerchantOffersPS2C {
+            window_id,
+            trades: Cow::Owned(trades),
+            villager_level: self.villager_level,
+            experience: self.experience,
+            is_regular_villager: self.is_regular_villager,
+            can_restock: self.can_restock,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::encode_packet;
+    use crate::registry::{Packet, PlayS2CPacket};
+    use super::*;
+
+    #[test]
+    fn trade_list_builder_test() {
+        assert_eq!(villager_level_for(0), 1);
+        assert_eq!(villager_level_for(70), 3);
+        assert_eq!(villager_level_for(1000), 5);
+
+        let mut used = Trade::new(OwnedSlot::new(1, 20), Some(OwnedSlot::new(2, 1)), OwnedSlot::new(3, 1));
+        used.uses = used.max_uses;
+        let mut builder = TradeListBuilder::new();
+        builder
+            .villager(3, 80)
+            .trade(OwnedSlot::new(1, 5), Some(OwnedSlot::new(2, 0)), OwnedSlot::new(4, 2))
+            .add(used);
+        let packet = builder.build(7).unwrap();
+        assert_eq!(packet.trades[0].input_item2, None);
+        assert!(!packet.trades[0].disabled);
+        assert_eq!(packet.trades[1].input_item2.as_ref().map(|item| item.item_id), Some(2));
+        assert!(packet.trades[1].disabled);
+
+        let bytes = encode_packet(&packet).unwrap();
+        match Packet::read(ProtocolPacketState::Play, ProtocolPacketBound::Client, &mut bytes.as_slice()).unwrap() {
+            Some(Packet::PlayS2C(PlayS2CPacket::MerchantOffers(read))) => assert_eq!(read, packet),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+
+        assert!(TradeListBuilder::wandering().build(0).is_ok());
+        assert!(TradeListBuilder::new().villager(6, 0).build(0).is_err());
+        assert!(TradeListBuilder::new().trade(OwnedSlot::new(1, 0), None, OwnedSlot::new(2, 1)).build(0).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use bird_protocol::{ProtocolPacket, ProtocolPacketBound, ProtocolPacketState};
+use bird_protocol::ProtocolPacketBound::*;
+use bird_protocol::ProtocolPacketState::*;
+use crate::status::PROTOCOL_VERSION;
+
+/// Protocol versions which are accepted in the handshake, the latest is the version of `bird-packets`
+pub const SUPPORTED_PROTOCOL_VERSIONS: [i32; 1] = [PROTOCOL_VERSION];
+
+/// Ids of packets from `first` to `last` of the latest version are shifted by `offset` in the other version,
+/// `None` offset means that packets don't exist in the other version
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PacketIdShift {
+    pub state: ProtocolPacketState,
+    pub bound: ProtocolPacketBound,
+    pub first: i32,
+    pub last: i32,
+    pub offset: Option<i32>,
+}
+
+pub const fn shift(state: ProtocolPacketState, bound: ProtocolPacketBound, first: i32, last: i32, offset: Option<i32>) -> PacketIdShift {
+    PacketIdShift { state, bound, first, last, offset }
+}
+
+/// Tables of other versions are added here and to [`SUPPORTED_PROTOCOL_VERSIONS`] together,
+/// ids of versions which can't be decoded are not kept
+const TABLES: &'static [PacketIdTable] = &[
+    PacketIdTable::new(PROTOCOL_VERSION, &[]),
+];
+
+/// Version which moves ids of the latest version, it is used to test the remapping
+#[cfg(test)]
+pub(crate) static TEST_TABLE: PacketIdTable = PacketIdTable::new(0, &[
+    shift(Play, Client, 0x00, 0x5F, Some(1)),
+    shift(Play, Client, 0x60, 0x60, None),
+    shift(Play, Server, 0x00, 0x1F, Some(1)),
+    shift(Play, Server, 0x20, 0x20, None),
+]);
+
+/// Maps packet ids of the latest version to ids of the other version
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PacketIdTable {
+    pub protocol_version: i32,
+    shifts: &'static [PacketIdShift],
+}
+
+impl PacketIdTable {
+    pub const fn new(protocol_version: i32, shifts: &'static [PacketIdShift]) -> Self {
+        Self { protocol_version, shifts }
+    }
+
+    /// Returns `None` if the version is not supported
+    pub fn for_version(protocol_version: i32) -> Option<&'static PacketIdTable> {
+        match SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version) {
+            true => TABLES.iter().find(|table| table.protocol_version == protocol_version),
+            false => None,
+        }
+    }
+
+    pub fn latest() -> &'static PacketIdTable {
+        Self::for_version(PROTOCOL_VERSION).unwrap()
+    }
+
+    /// Id of the packet in this version or `None` if the packet doesn't exist in it
+    pub fn id(&self, state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32) -> Option<i32> {
+        match self.shifts(state, bound).find(|shift| (shift.first..=shift.last).contains(&id)) {
+            Some(shift) => shift.offset.map(|offset| id + offset),
+            None => Some(id),
+        }
+    }
+
+    pub fn packet_id<P: ProtocolPacket>(&self) -> Option<i32> {
+        self.id(P::STATE, P::BOUND, P::ID)
+    }
+
+    /// Id of the packet in the latest version, used to decode received packets
+    pub fn latest_id(&self, state: ProtocolPacketState, bound: ProtocolPacketBound, id: i32) -> Option<i32> {
+        let mut shifts = self.shifts(state, bound);
+        if let Some(shift) = shifts.clone().find(|shift| match shift.offset {
+            Some(offset) => (shift.first + offset..=shift.last + offset).contains(&id),
+            None => false,
+        }) {
+            return Some(id - shift.offset.unwrap());
+        }
+        match shifts.any(|shift| (shift.first..=shift.last).contains(&id)) {
+            true => None,
+            false => Some(id),
+        }
+    }
+
+    fn shifts(&self, state: ProtocolPacketState, bound: ProtocolPacketBound) -> impl Iterator<Item=&'static PacketIdShift> + Clone {
+        self.shifts.iter().filter(move |shift| shift.state == state && shift.bound == bound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::*;
+    use super::*;
+
+    #[test]
+    fn packet_id_test() {
+        let table = &TEST_TABLE;
+        assert_eq!(table.packet_id::<PluginMessagePS2C>(), Some(0x16));
+        assert_eq!(table.packet_id::<UpdateTimePS2C>(), Some(0x5B));
+        assert_eq!(table.packet_id::<MoveVehiclePC2S>(), Some(0x18));
+        assert_eq!(table.packet_id::<PlayerSessionPC2S>(), None);
+        assert_eq!(table.id(Play, Client, 0x60), None);
+        assert_eq!(table.id(Play, Client, 0x61), Some(0x61));
+        assert_eq!(table.latest_id(Play, Server, 0x18), Some(0x17));
+        assert_eq!(table.latest_id(Play, Server, 0x00), None);
+        assert_eq!(table.latest_id(Play, Server, 0x21), Some(0x21));
+        for id in 0..0x6B {
+            if let Some(other) = table.id(Play, Client, id) {
+                assert_eq!(table.latest_id(Play, Client, other), Some(id));
+            }
+        }
+        assert_eq!(PacketIdTable::latest().packet_id::<PlayerSessionPC2S>(), Some(0x20));
+        assert_eq!(PacketIdTable::for_version(760), None);
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+use bird_chat::component::Component;
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+#[cfg(feature = "metrics")]
+use crate::metrics::ServerMetrics;
+use crate::protocol::*;
+use crate::status::VERSION_NAME;
+use crate::version::{PacketIdTable, SUPPORTED_PROTOCOL_VERSIONS};
+
+/// Names of [`SUPPORTED_PROTOCOL_VERSIONS`] in the same order
+pub const SUPPORTED_VERSION_NAMES: [&'static str; 1] = [VERSION_NAME];
+/// Replaced by the names of supported versions in configured messages
+pub const VERSIONS_PLACEHOLDER: &'static str = "{versions}";
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct VersionGateConfig {
+    /// Message for clients which are older than all supported versions,
+    /// `None` uses the vanilla translation which is localized by the client
+    pub outdated_client: Option<String>,
+    /// Message for clients which are newer than the server
+    pub outdated_server: Option<String>,
+}
+
+/// Rejects logins of unsupported protocol versions with a disconnect message instead of closing the socket
+#[derive(Debug, Default)]
+pub struct VersionGate {
+    pub config: VersionGateConfig,
+    /// Rejected logins are counted by the protocol version in the registry
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<Arc<ServerMetrics>>,
+}
+
+impl VersionGate {
+    pub fn new(config: VersionGateConfig) -> Self {
+        Self {
+            config,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    pub fn is_supported(protocol_version: i32) -> bool {
+        SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version)
+    }
+
+    /// Disconnect reason for the version, older clients are asked to use the oldest supported version
+    pub fn message(&self, protocol_version: i32) -> Component<'static> {
+        let outdated_client = protocol_version < SUPPORTED_PROTOCOL_VERSIONS[0];
+        let (configured, key, version) = match outdated_client {
+            true => (&self.config.outdated_client, "multiplayer.disconnect.outdated_client", SUPPORTED_VERSION_NAMES[0]),
+            false => (&self.config.outdated_server, "multiplayer.disconnect.outdated_server", VERSION_NAME),
+        };
+        match configured {
+            Some(message) => Component::text(message.replace(VERSIONS_PLACEHOLDER, &SUPPORTED_VERSION_NAMES.join(", "))),
+            None => Component::translation(key, vec![Component::text(version)]),
+        }
+    }
+
+    /// Returns the packet ids of the version. Status requests of any version are answered, so the client shows
+    /// that the version is incompatible. Logins of unsupported versions receive the disconnect, its id is the same in all versions
+    pub fn handle_handshake<S: PacketSender>(&self, handshake: &Handshake, sender: &mut S) -> anyhow::Result<Option<&'static PacketIdTable>> {
+        if let Some(table) = PacketIdTable::for_version(handshake.protocol_version) {
+            return Ok(Some(table));
+        }
+        if handshake.next_state == HandshakeNextState::Status {
+            return Ok(Some(PacketIdTable::latest()));
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(ref metrics) = self.metrics {
+            metrics.registry().record_rejected_login(handshake.protocol_version);
+        }
+        sender.send_packet(&LoginDisconnectLS2C { reason: self.message(handshake.protocol_version) })?;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bird_protocol::{ProtocolPacketBound, ProtocolPacketState};
+    use crate::connection::PacketQueue;
+    use crate::registry::{HandshakeC2SPacket, LoginC2SPacket, Packet};
+    use crate::status::PROTOCOL_VERSION;
+    use super::*;
+
+    #[test]
+    fn version_gate_test() {
+        let mut gate = VersionGate::new(VersionGateConfig::default());
+        #[cfg(feature = "metrics")]
+        let metrics = {
+            let metrics = Arc::new(ServerMetrics::new(std::time::Instant::now()));
+            gate.metrics = Some(metrics.clone());
+            metrics
+        };
+        let handshake = |protocol_version, next_state| Handshake { protocol_version, server_address: "localhost", server_port: 25565, next_state };
+        let mut queue = PacketQueue::new();
+        assert_eq!(gate.handle_handshake(&handshake(PROTOCOL_VERSION, HandshakeNextState::Login), &mut queue).unwrap(), Some(PacketIdTable::latest()));
+        assert!(gate.handle_handshake(&handshake(47, HandshakeNextState::Status), &mut queue).unwrap().is_some());
+        assert!(queue.take().is_empty());
+        assert!(gate.handle_handshake(&handshake(47, HandshakeNextState::Login), &mut queue).unwrap().is_none());
+        assert_eq!(queue.take().len(), 1);
+        assert_eq!(
+            gate.message(47),
+            Component::translation("multiplayer.disconnect.outdated_client", vec![Component::text("1.19.3")]),
+        );
+        gate.config.outdated_server = Some("Please use {versions}".into());
+        assert_eq!(gate.message(PROTOCOL_VERSION + 1), Component::text("Please use 1.19.3"));
+        gate.handle_handshake(&handshake(PROTOCOL_VERSION + 1, HandshakeNextState::Login), &mut queue).unwrap();
+        gate.handle_handshake(&handshake(47, HandshakeNextState::Login), &mut queue).unwrap();
+        #[cfg(feature = "metrics")]
+        assert_eq!(metrics.registry().rejected_logins(), vec![(47, 2), (PROTOCOL_VERSION + 1, 1)]);
+    }
+
+    #[test]
+    fn protocol_760_test() {
+        // Frames of a 1.19.2 client, the login start has no signature data and has the uuid
+        let mut handshake_frame = vec![0x00, 0xF8, 0x05, 0x09];
+        handshake_frame.extend_from_slice(b"localhost");
+        handshake_frame.extend_from_slice(&[0x63, 0xDD, 0x02]);
+        let mut start_frame = vec![0x00, 0x05];
+        start_frame.extend_from_slice(b"Notch");
+        start_frame.extend_from_slice(&[0x00, 0x01]);
+        start_frame.extend_from_slice(&[0x42; 16]);
+
+        let handshake = match Packet::read(ProtocolPacketState::Handshake, ProtocolPacketBound::Server, &mut handshake_frame.as_slice()).unwrap() {
+            Some(Packet::HandshakeC2S(HandshakeC2SPacket::Handshake(handshake))) => handshake,
+            other => panic!("Unexpected packet {:?}", other),
+        };
+        assert_eq!(handshake.protocol_version, 760);
+        // The signature flag is read as the uuid flag, so the uuid is left in the frame
+        let mut cursor = start_frame.as_slice();
+        match Packet::read(ProtocolPacketState::Login, ProtocolPacketBound::Server, &mut cursor).unwrap() {
+            Some(Packet::LoginC2S(LoginC2SPacket::LoginStart(start))) => assert_eq!(start.uuid, None),
+            other => panic!("Unexpected packet {:?}", other),
+        }
+        assert_eq!(cursor.len(), 17);
+
+        let gate = VersionGate::default();
+        let mut queue = PacketQueue::new();
+        assert!(!VersionGate::is_supported(760));
+        assert_eq!(gate.handle_handshake(&handshake, &mut queue).unwrap(), None);
+        assert_eq!(queue.take().len(), 1);
+        assert_eq!(
+            gate.message(760),
+            Component::translation("multiplayer.disconnect.outdated_client", vec![Component::text("1.19.3")]),
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::protocol::{SetRenderDistancePS2C, SetSimulationDistancePS2C};
+use crate::world::{ChunkSender, World};
+
+/// Vanilla client doesn't render less chunks
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+pub const MAX_VIEW_DISTANCE: u8 = 32;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ViewDistanceConfig {
+    pub view_distance: u8,
+    pub simulation_distance: u8,
+    /// Slow links are not reduced below this distance
+    pub min_view_distance: u8,
+    /// Bytes which may wait in the queue of the connection at the end of a second
+    pub max_backlog: u64,
+    /// The view distance is reduced by one after this amount of seconds over the backlog
+    pub slow_seconds: u32,
+    /// The reduction is relaxed by one after this amount of seconds under the backlog
+    pub recovery_seconds: u32,
+}
+
+impl Default for ViewDistanceConfig {
+    fn default() -> Self {
+        Self {
+            view_distance: 10,
+            simulation_distance: 10,
+            min_view_distance: 4,
+            max_backlog: 1 << 20,
+            slow_seconds: 3,
+            recovery_seconds: 30,
+        }
+    }
+}
+
+/// Distances which are set for one player instead of the global ones
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DistanceOverride {
+    pub view_distance: Option<u8>,
+    pub simulation_distance: Option<u8>,
+}
+
+/// Bandwidth accounting of one connection, the link is slow when queued bytes are not written in time
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LinkMonitor {
+    backlog: u64,
+    written: u64,
+    slow_seconds: u32,
+    fast_seconds: u32,
+    /// Chunks which are taken from the view distance
+    reduction: u8,
+}
+
+impl LinkMonitor {
+    pub fn record_queued(&mut self, bytes: u64) {
+        self.backlog += bytes;
+    }
+
+    pub fn record_written(&mut self, bytes: u64) {
+        self.backlog = self.backlog.saturating_sub(bytes);
+        self.written += bytes;
+    }
+
+    pub fn backlog(&self) -> u64 {
+        self.backlog
+    }
+
+    pub fn reduction(&self) -> u8 {
+        self.reduction
+    }
+
+    /// Returns bytes written during the second and updates the reduction
+    pub fn end_second(&mut self, config: &ViewDistanceConfig) -> u64 {
+        match self.backlog > config.max_backlog {
+            true => {
+                self.fast_seconds = 0;
+                self.slow_seconds += 1;
+                if self.slow_seconds >= config.slow_seconds {
+                    self.slow_seconds = 0;
+                    self.reduction = self.reduction.saturating_add(1).min(MAX_VIEW_DISTANCE);
+                }
+            }
+            false => {
+                self.slow_seconds = 0;
+                self.fast_seconds += 1;
+                if self.fast_seconds >= config.recovery_seconds {
+                    self.fast_seconds = 0;
+                    self.reduction = self.reduction.saturating_sub(1);
+                }
+            }
+        }
+        std::mem::take(&mut self.written)
+    }
+}
+
+/// Chunks and distances of one player. Distances are recomputed with [`PlayerView::apply`]
+/// after anything they depend on is changed, chunks are loaded and unloaded incrementally
+#[derive(Clone, Debug)]
+pub struct PlayerView {
+    pub chunks: ChunkSender,
+    pub link: LinkMonitor,
+    distance_override: DistanceOverride,
+    /// View distance of the client settings
+    client_view_distance: Option<u8>,
+    simulation_distance: u8,
+}
+
+impl PlayerView {
+    pub fn new(config: &ViewDistanceConfig) -> Self {
+        Self {
+            chunks: ChunkSender::new(config.view_distance.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE)),
+            link: LinkMonitor::default(),
+            distance_override: DistanceOverride::default(),
+            client_view_distance: None,
+            simulation_distance: config.simulation_distance,
+        }
+    }
+
+    pub fn view_distance(&self) -> u8 {
+        self.chunks.view_distance()
+    }
+
+    pub fn simulation_distance(&self) -> u8 {
+        self.simulation_distance
+    }
+
+    pub fn distance_override(&self) -> DistanceOverride {
+        self.distance_override
+    }
+
+    /// The server doesn't send more chunks than the client renders
+    pub fn effective_view_distance(&self, config: &ViewDistanceConfig) -> u8 {
+        let view_distance = self.distance_override.view_distance
+            .unwrap_or(config.view_distance)
+            .min(self.client_view_distance.unwrap_or(MAX_VIEW_DISTANCE));
+        let reduced = view_distance.saturating_sub(self.link.reduction()).max(config.min_view_distance);
+        reduced.min(view_distance).clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE)
+    }
+
+    /// Chunks further than the view distance are not simulated for the client
+    pub fn effective_simulation_distance(&self, config: &ViewDistanceConfig) -> u8 {
+        self.distance_override.simulation_distance
+            .unwrap_or(config.simulation_distance)
+            .min(self.effective_view_distance(config))
+    }
+
+    /// Sends changed distances and loads or unloads chunks of the changed view distance
+    pub fn apply<S: PacketSender>(&mut self, config: &ViewDistanceConfig, world: &World, sender: &mut S) -> anyhow::Result<()> {
+        let view_distance = self.effective_view_distance(config);
+        if view_distance != self.chunks.view_distance() {
+            sender.send_packet(&SetRenderDistancePS2C { view_distance: view_distance as i32 })?;
+            self.chunks.set_view_distance(world, view_distance, sender)?;
+        }
+        let simulation_distance = self.effective_simulation_distance(config);
+        if simulation_distance != self.simulation_distance {
+            self.simulation_distance = simulation_distance;
+            sender.send_packet(&SetSimulationDistancePS2C { simulation_distance: simulation_distance as i32 })?;
+        }
+        Ok(())
+    }
+
+    pub fn set_client_view_distance<S: PacketSender>(
+        &mut self,
+        config: &ViewDistanceConfig,
+        world: &World,
+        view_distance: u8,
+        sender: &mut S,
+    ) -> anyhow::Result<()> {
+        self.client_view_distance = Some(view_distance);
+        self.apply(config, world, sender)
+    }
+
+    pub fn set_override<S: PacketSender>(
+        &mut self,
+        config: &ViewDistanceConfig,
+        world: &World,
+        distance_override: DistanceOverride,
+        sender: &mut S,
+    ) -> anyhow::Result<()> {
+        self.distance_override = distance_override;
+        self.apply(config, world, sender)
+    }
+
+    /// Must be called once per second, the view distance follows the reduction of the link
+    pub fn end_second<S: PacketSender>(&mut self, config: &ViewDistanceConfig, world: &World, sender: &mut S) -> anyhow::Result<u64> {
+        let written = self.link.end_second(config);
+        self.apply(config, world, sender)?;
+        Ok(written)
+    }
+}
+
+/// Changes global distances, players without overrides get new distances
+pub fn set_global_distances<'a, S: PacketSender + 'a>(
+    config: &mut ViewDistanceConfig,
+    view_distance: u8,
+    simulation_distance: u8,
+    world: &World,
+    players: impl IntoIterator<Item=(&'a mut PlayerView, &'a mut S)>,
+) -> anyhow::Result<()> {
+    config.view_distance = view_distance;
+    config.simulation_distance = simulation_distance;
+    for (view, sender) in players {
+        view.apply(config, world, sender)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn view_distance_test() {
+        let mut world = World::new(1, 0);
+        for x in -6..=6 {
+            for z in -6..=6 {
+                world.get_or_create_chunk(Vector2D::new(x, z));
+            }
+        }
+        let mut config = ViewDistanceConfig { view_distance: 4, simulation_distance: 4, min_view_distance: 2, ..Default::default() };
+        let mut queue = PacketQueue::new();
+        let mut view = PlayerView::new(&config);
+        view.chunks.update(&world, Vector2D::zero(), &mut queue).unwrap();
+        // SetCenterChunk and 81 chunks
+        assert_eq!(queue.take().len(), 82);
+
+        view.set_client_view_distance(&config, &world, 3, &mut queue).unwrap();
+        // SetRenderDistance, 32 unloads and SetSimulationDistance
+        assert_eq!(queue.take().len(), 34);
+        assert_eq!(view.view_distance(), 3);
+        assert_eq!(view.simulation_distance(), 3);
+
+        let distance_override = DistanceOverride { view_distance: Some(2), simulation_distance: None };
+        view.set_override(&config, &world, distance_override, &mut queue).unwrap();
+        assert_eq!(view.view_distance(), 2);
+        assert!(!view.chunks.is_loaded(Vector2D::new(3, 0)));
+        queue.take();
+
+        view.set_override(&config, &world, DistanceOverride::default(), &mut queue).unwrap();
+        set_global_distances(&mut config, 6, 4, &world, [(&mut view, &mut queue)]).unwrap();
+        // The client still renders only 3 chunks
+        assert_eq!(view.view_distance(), 3);
+        view.set_client_view_distance(&config, &world, 12, &mut queue).unwrap();
+        assert_eq!(view.view_distance(), 6);
+        assert!(view.chunks.is_loaded(Vector2D::new(6, 6)));
+        queue.take();
+
+        view.link.record_queued(config.max_backlog + 1);
+        for _ in 0..config.slow_seconds {
+            view.end_second(&config, &world, &mut queue).unwrap();
+        }
+        assert_eq!(view.view_distance(), 5);
+        view.link.record_written(config.max_backlog + 1);
+        for _ in 0..config.recovery_seconds {
+            view.end_second(&config, &world, &mut queue).unwrap();
+        }
+        assert_eq!(view.view_distance(), 6);
+    }
+}
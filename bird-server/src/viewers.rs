@@ -0,0 +1,155 @@
+use euclid::default::Vector3D;
+use bird_protocol::{anyhow, ProtocolPacket, ProtocolPacketState, ProtocolWritable};
+use crate::connection::{CachedPacket, PacketQueue, PacketSender, QueuedPacket, raw_frame, SharedEncodedPacket};
+
+/// Player of a subsystem which receives its broadcasts, like a chat player or a viewer of entities
+pub trait Viewer {
+    fn packets(&mut self) -> &mut PacketQueue;
+
+    /// World of the player, `None` if the subsystem doesn't know it
+    fn world(&self) -> Option<&str> {
+        None
+    }
+
+    fn position(&self) -> Option<Vector3D<f64>> {
+        None
+    }
+}
+
+/// Queues of players which receive the same packets. Packets are encoded once and shared between queues
+pub struct Viewers<'a> {
+    queues: Vec<&'a mut PacketQueue>,
+}
+
+impl<'a> Viewers<'a> {
+    pub fn new(queues: impl IntoIterator<Item=&'a mut PacketQueue>) -> Self {
+        Self { queues: queues.into_iter().collect() }
+    }
+
+    pub fn all<V: Viewer + 'a>(players: impl IntoIterator<Item=&'a mut V>) -> Self {
+        Self::new(players.into_iter().map(Viewer::packets))
+    }
+
+    /// Players whose world is known and is the given one
+    pub fn in_world<V: Viewer + 'a>(players: impl IntoIterator<Item=&'a mut V>, world: &str) -> Self {
+        Self::matching(players, |player| player.world() == Some(world))
+    }
+
+    /// Players within the radius of the center, players of other worlds are skipped if the world is given
+    pub fn within_radius<V: Viewer + 'a>(
+        players: impl IntoIterator<Item=&'a mut V>,
+        world: Option<&str>,
+        center: Vector3D<f64>,
+        radius: f64,
+    ) -> Self {
+        Self::matching(players, |player| {
+            world.map_or(true, |world| player.world() == Some(world))
+                && player.position().map_or(false, |position| (position - center).square_length() <= radius * radius)
+        })
+    }
+
+    pub fn matching<V: Viewer + 'a>(players: impl IntoIterator<Item=&'a mut V>, mut predicate: impl FnMut(&V) -> bool) -> Self {
+        Self::new(players.into_iter().filter(|player| predicate(player)).map(Viewer::packets))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Encodes the packet once, nothing is encoded if there are no viewers
+    pub fn send<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()> {
+        if !self.queues.is_empty() {
+            self.send_shared(&SharedEncodedPacket::new(packet)?)?;
+        }
+        Ok(())
+    }
+
+    /// The cached packet is encoded only if it was invalidated and there are viewers
+    pub fn send_cached(
+        &mut self,
+        packet: &CachedPacket,
+        encode: impl FnOnce() -> anyhow::Result<SharedEncodedPacket>,
+    ) -> anyhow::Result<()> {
+        match self.queues.is_empty() {
+            true => Ok(()),
+            false => packet.send(self, encode),
+        }
+    }
+}
+
+impl PacketSender for Viewers<'_> {
+    fn send_packet<P: ProtocolPacket + ProtocolWritable>(&mut self, packet: &P) -> anyhow::Result<()> {
+        self.send(packet)
+    }
+
+    fn send_shared(&mut self, packet: &SharedEncodedPacket) -> anyhow::Result<()> {
+        for queue in &mut self.queues {
+            queue.push_shared(packet);
+        }
+        Ok(())
+    }
+
+    fn send_raw(&mut self, _state: ProtocolPacketState, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        if self.queues.is_empty() {
+            return Ok(());
+        }
+        let packet = SharedEncodedPacket::from_frame(raw_frame(id, body)?);
+        for queue in &mut self.queues {
+            queue.packets.push(QueuedPacket::Shared(packet.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::UpdateTimePS2C;
+    use super::*;
+
+    struct TestViewer {
+        packets: PacketQueue,
+        world: &'static str,
+        position: Vector3D<f64>,
+    }
+
+    impl Viewer for TestViewer {
+        fn packets(&mut self) -> &mut PacketQueue {
+            &mut self.packets
+        }
+
+        fn world(&self) -> Option<&str> {
+            Some(self.world)
+        }
+
+        fn position(&self) -> Option<Vector3D<f64>> {
+            Some(self.position)
+        }
+    }
+
+    #[test]
+    fn viewers_test() {
+        let viewer = |world, x| TestViewer { packets: PacketQueue::new(), world, position: Vector3D::new(x, 0.0, 0.0) };
+        let mut players = vec![viewer("overworld", 0.0), viewer("overworld", 20.0), viewer("the_nether", 1.0)];
+        let packet = UpdateTimePS2C { world_age: 0, time_of_day: 6000 };
+        assert_eq!(Viewers::all(&mut players).len(), 3);
+        assert_eq!(Viewers::in_world(&mut players, "the_nether").len(), 1);
+        assert_eq!(Viewers::within_radius(&mut players, None, Vector3D::zero(), 10.0).len(), 2);
+        let mut viewers = Viewers::within_radius(&mut players, Some("overworld"), Vector3D::zero(), 10.0);
+        assert_eq!(viewers.len(), 1);
+        viewers.send(&packet).unwrap();
+        Viewers::matching(&mut players, |player| player.position.x > 10.0).send(&packet).unwrap();
+        let counts: Vec<_> = players.iter_mut().map(|player| player.packets.take().len()).collect();
+        assert_eq!(counts, [1, 1, 0]);
+
+        let mut cached = CachedPacket::new();
+        Viewers::all(&mut players).send_cached(&cached, || SharedEncodedPacket::new(&packet)).unwrap();
+        Viewers::new(std::iter::empty()).send_cached(&cached, || Err(anyhow::Error::msg("encoded for nobody"))).unwrap();
+        assert!(cached.is_cached());
+        cached.invalidate();
+        assert!(Viewers::all(&mut players).send_cached(&cached, || Err(anyhow::Error::msg("failed"))).is_err());
+    }
+}
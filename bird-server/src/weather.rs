@@ -0,0 +1,404 @@
+use euclid::default::{Vector2D, Vector3D};
+use uuid::Uuid;
+use bird_data::{BiomeData, BiomePrecipitation, Block};
+use bird_protocol::anyhow;
+use crate::connection::PacketSender;
+use crate::entity::EntityKind;
+use crate::entity::tracker::TrackedEntity;
+use crate::protocol::{GameEventPS2C, UpdateTimePS2C};
+use crate::random::LegacyRandom;
+use crate::world::{block_position, is_air, World};
+
+pub const LIGHTNING_BOLT_ENTITY_TYPE: i32 = 49;
+pub const DAY_LENGTH: i64 = 24000;
+/// Mobs which are burning in daylight are set on fire for this amount of seconds
+pub const DAYLIGHT_BURN_SECONDS: i32 = 8;
+
+/// Vanilla sends the time once per second
+const TIME_UPDATE_INTERVAL: i64 = 20;
+const SNOW_TICK_CHANCE: i32 = 16;
+const LIGHTNING_CHANCE: i32 = 100000;
+const LIGHTNING_FIRE_SPREAD: usize = 4;
+/// Biomes which are colder are snowy
+const SNOW_TEMPERATURE: f32 = 0.15;
+const MAX_SNOW_LAYERS: i32 = 8;
+/// Rain and thunder levels change by this amount every tick
+const WEATHER_LEVEL_STEP: f32 = 0.01;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeatherConfig {
+    pub daylight_cycle: bool,
+    pub weather_cycle: bool,
+    pub mobs_burn_in_daylight: bool,
+    /// Maximum layers of snow which are accumulated during rain, 0 disables accumulation
+    pub snow_accumulation_height: i32,
+    pub lightning: bool,
+    /// Lightning sets fire around the strike position
+    pub lightning_fire: bool,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            daylight_cycle: true,
+            weather_cycle: true,
+            mobs_burn_in_daylight: true,
+            snow_accumulation_height: 1,
+            lightning: true,
+            lightning_fire: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+/// Changes made by [`WorldWeather::tick_chunk`], the caller sends block updates and spawns lightning bolts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeatherEvent {
+    BlockChanged {
+        position: Vector3D<i32>,
+        state: i32,
+    },
+    LightningStrike {
+        position: Vector3D<i32>,
+    },
+}
+
+pub fn biome_at(world: &World, position: Vector3D<i32>) -> Option<&'static BiomeData<'static>> {
+    world.get_biome(position).and_then(|biome| BiomeData::from_id(biome as u32))
+}
+
+/// There are no blocks except air above the position
+pub fn sky_visible(world: &World, position: Vector3D<i32>) -> bool {
+    world.top_block_y(position.x, position.z).map_or(true, |y| y < position.y)
+}
+
+/// Rain falls on the position if it is raining, snowy and dry biomes are skipped
+pub fn is_rained_on(world: &World, position: Vector3D<i32>) -> bool {
+    sky_visible(world, position) && biome_at(world, position).map_or(false, |biome| {
+        biome.precipitation == BiomePrecipitation::Rain && biome.temperature >= SNOW_TEMPERATURE
+    })
+}
+
+pub fn lightning_bolt(id: i32, uuid: Uuid, position: Vector3D<i32>) -> TrackedEntity {
+    TrackedEntity::new(id, uuid, EntityKind::Other {
+        entity_type: LIGHTNING_BOLT_ENTITY_TYPE,
+        data: 0,
+    }, Vector3D::new(position.x as f64 + 0.5, position.y as f64, position.z as f64 + 0.5))
+}
+
+/// Position above the highest block of the column, snow layers are replaced instead of being built on
+fn surface(world: &World, x: i32, z: i32) -> Option<Vector3D<i32>> {
+    let y = world.top_block_y(x, z)?;
+    let top = Vector3D::new(x, y, z);
+    match world.get_block_state(top).and_then(|state| Block::from_state(state as u32)) {
+        Some(Block::Snow { .. }) => Some(top),
+        _ => Some(top + Vector3D::new(0, 1, 0)),
+    }
+}
+
+fn can_support(world: &World, position: Vector3D<i32>) -> bool {
+    match world.get_block_state(position) {
+        Some(state) => !is_air(state) && !matches!(Block::from_state(state as u32), Some(Block::Ice | Block::PackedIce)),
+        None => false,
+    }
+}
+
+/// Time and weather of one world
+#[derive(Clone, PartialEq, Debug)]
+pub struct WorldWeather {
+    pub config: WeatherConfig,
+    world_age: i64,
+    time_of_day: i64,
+    raining: bool,
+    thundering: bool,
+    /// Ticks of forced clear weather, set by [`WorldWeather::set_weather`]
+    clear_time: i32,
+    /// Ticks until rain starts or stops
+    rain_time: i32,
+    /// Ticks until thunder starts or stops
+    thunder_time: i32,
+    rain_level: f32,
+    thunder_level: f32,
+}
+
+impl WorldWeather {
+    pub fn new(config: WeatherConfig) -> Self {
+        Self {
+            config,
+            world_age: 0,
+            time_of_day: 0,
+            raining: false,
+            thundering: false,
+            clear_time: 0,
+            rain_time: 0,
+            thunder_time: 0,
+            rain_level: 0.0,
+            thunder_level: 0.0,
+        }
+    }
+
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    pub fn time_of_day(&self) -> i64 {
+        self.time_of_day
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: i64) {
+        self.time_of_day = time_of_day;
+    }
+
+    pub fn is_raining(&self) -> bool {
+        self.rain_level > 0.2
+    }
+
+    pub fn is_thundering(&self) -> bool {
+        self.rain_level * self.thunder_level > 0.9
+    }
+
+    /// Changes the weather for the duration in ticks, the level of rain changes smoothly
+    pub fn set_weather(&mut self, weather: Weather, duration: i32) {
+        let (clear_time, raining, thundering) = match weather {
+            Weather::Clear => (duration, false, false),
+            Weather::Rain => (0, true, false),
+            Weather::Thunder => (0, true, true),
+        };
+        self.clear_time = clear_time;
+        self.rain_time = if raining { duration } else { 0 };
+        self.thunder_time = if thundering { duration } else { 0 };
+        self.raining = raining;
+        self.thundering = thundering;
+    }
+
+    /// Vanilla sky darkness from 0 (noon) to 11 (midnight), rain and thunder make the sky darker
+    pub fn sky_darken(&self) -> i32 {
+        let progress = (self.time_of_day.rem_euclid(DAY_LENGTH) as f64 / DAY_LENGTH as f64 - 0.25).rem_euclid(1.0);
+        let time = (progress * 2.0 + (0.5 - (progress * std::f64::consts::PI).cos() / 2.0)) / 3.0;
+        let rain = 1.0 - self.rain_level as f64 * 5.0 / 16.0;
+        let thunder = 1.0 - self.thunder_level as f64 * 5.0 / 16.0;
+        let light = 0.5 + 2.0 * (time * std::f64::consts::TAU).cos().clamp(-0.25, 0.25);
+        ((1.0 - light * rain * thunder) * 11.0) as i32
+    }
+
+    pub fn is_day(&self) -> bool {
+        self.sky_darken() < 4
+    }
+
+    pub fn time_packet(&self) -> UpdateTimePS2C {
+        UpdateTimePS2C {
+            world_age: self.world_age,
+            time_of_day: match self.config.daylight_cycle {
+                true => self.time_of_day,
+                false => -self.time_of_day.max(1),
+            },
+        }
+    }
+
+    /// Sends time and weather to the player which has joined the world
+    pub fn send_state<S: PacketSender>(&self, sender: &mut S) -> anyhow::Result<()> {
+        sender.send_packet(&self.time_packet())?;
+        if self.is_raining() {
+            sender.send_packet(&GameEventPS2C::BeginRaining)?;
+            sender.send_packet(&GameEventPS2C::RainLevelChange(self.rain_level))?;
+            sender.send_packet(&GameEventPS2C::ThunderLevelChange(self.thunder_level))?;
+        }
+        Ok(())
+    }
+
+    /// Advances time and weather, `broadcast` receives packets for all players in the world,
+    /// [`crate::viewers::Viewers`] encodes them once
+    pub fn tick<S: PacketSender>(&mut self, random: &mut LegacyRandom, broadcast: &mut S) -> anyhow::Result<()> {
+        self.world_age += 1;
+        if self.config.daylight_cycle {
+            self.time_of_day += 1;
+        }
+        if self.world_age % TIME_UPDATE_INTERVAL == 0 {
+            broadcast.send_packet(&self.time_packet())?;
+        }
+        if self.config.weather_cycle {
+            self.advance_weather_cycle(random);
+        }
+        let was_raining = self.is_raining();
+        let (rain_level, thunder_level) = (self.rain_level, self.thunder_level);
+        let step = |level: f32, increase: bool| match increase {
+            true => (level + WEATHER_LEVEL_STEP).min(1.0),
+            false => (level - WEATHER_LEVEL_STEP).max(0.0),
+        };
+        self.thunder_level = step(self.thunder_level, self.thundering);
+        self.rain_level = step(self.rain_level, self.raining);
+        if rain_level != self.rain_level {
+            broadcast.send_packet(&GameEventPS2C::RainLevelChange(self.rain_level))?;
+        }
+        if thunder_level != self.thunder_level {
+            broadcast.send_packet(&GameEventPS2C::ThunderLevelChange(self.thunder_level))?;
+        }
+        if was_raining != self.is_raining() {
+            broadcast.send_packet(&match was_raining {
+                true => GameEventPS2C::EndRaining,
+                false => GameEventPS2C::BeginRaining,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn advance_weather_cycle(&mut self, random: &mut LegacyRandom) {
+        if self.clear_time > 0 {
+            self.clear_time -= 1;
+            self.thunder_time = if self.thundering { 0 } else { 1 };
+            self.rain_time = if self.raining { 0 } else { 1 };
+            self.thundering = false;
+            self.raining = false;
+            return;
+        }
+        if self.thunder_time > 0 {
+            self.thunder_time -= 1;
+            if self.thunder_time == 0 {
+                self.thundering = !self.thundering;
+            }
+        } else {
+            self.thunder_time = match self.thundering {
+                true => random.next_between(3600, 15600),
+                false => random.next_between(12000, 180000),
+            };
+        }
+        if self.rain_time > 0 {
+            self.rain_time -= 1;
+            if self.rain_time == 0 {
+                self.raining = !self.raining;
+            }
+        } else {
+            self.rain_time = match self.raining {
+                true => random.next_between(12000, 24000),
+                false => random.next_between(12000, 180000),
+            };
+        }
+    }
+
+    /// Random tick of the chunk which accumulates snow and strikes lightning, changed blocks are already set in the world
+    pub fn tick_chunk(&self, world: &mut World, chunk: Vector2D<i32>, random: &mut LegacyRandom) -> Vec<WeatherEvent> {
+        let mut events = Vec::new();
+        if !self.is_raining() {
+            return events;
+        }
+        if self.config.lightning && self.is_thundering() && random.next_int(LIGHTNING_CHANCE) == 0 {
+            let x = chunk.x * 16 + random.next_int(16);
+            let z = chunk.y * 16 + random.next_int(16);
+            if let Some(position) = surface(world, x, z).filter(|position| is_rained_on(world, *position)) {
+                events.push(WeatherEvent::LightningStrike { position });
+                if self.config.lightning_fire {
+                    ignite(world, position, random, &mut events);
+                }
+            }
+        }
+        if self.config.snow_accumulation_height > 0 && random.next_int(SNOW_TICK_CHANCE) == 0 {
+            let x = chunk.x * 16 + random.next_int(16);
+            let z = chunk.y * 16 + random.next_int(16);
+            if let Some(position) = surface(world, x, z) {
+                if let Some(state) = self.accumulate_snow(world, position) {
+                    world.set_block_state(position, state);
+                    events.push(WeatherEvent::BlockChanged { position, state });
+                }
+            }
+        }
+        events
+    }
+
+    fn accumulate_snow(&self, world: &World, position: Vector3D<i32>) -> Option<i32> {
+        if biome_at(world, position)?.temperature >= SNOW_TEMPERATURE {
+            return None;
+        }
+        let state = world.get_block_state(position)?;
+        let max_layers = self.config.snow_accumulation_height.min(MAX_SNOW_LAYERS);
+        let snow = match Block::from_state(state as u32)? {
+            Block::Snow { layers } if layers < max_layers => Block::Snow { layers: layers + 1 },
+            _ if is_air(state) && can_support(world, position - Vector3D::new(0, 1, 0)) => Block::from_name("snow")?,
+            _ => return None,
+        };
+        snow.get_state().map(|state| state as i32)
+    }
+
+    /// Hook for undead mobs, `wet` is true if the mob is in water or wears a helmet
+    pub fn burns_in_daylight(&self, world: &World, eye_position: Vector3D<f64>, wet: bool) -> bool {
+        let position = block_position(eye_position);
+        self.config.mobs_burn_in_daylight
+            && self.is_day()
+            && !wet
+            && sky_visible(world, position)
+            && !(self.is_raining() && is_rained_on(world, position))
+    }
+}
+
+fn ignite(world: &mut World, position: Vector3D<i32>, random: &mut LegacyRandom, events: &mut Vec<WeatherEvent>) {
+    let fire = match Block::from_name("fire").and_then(|fire| fire.get_state()) {
+        Some(fire) => fire as i32,
+        None => return,
+    };
+    let mut place = |world: &mut World, position: Vector3D<i32>| {
+        if world.get_block_state(position).map_or(false, is_air) && can_support(world, position - Vector3D::new(0, 1, 0)) {
+            world.set_block_state(position, fire);
+            events.push(WeatherEvent::BlockChanged { position, state: fire });
+        }
+    };
+    place(world, position);
+    for _ in 0..LIGHTNING_FIRE_SPREAD {
+        let offset = Vector3D::new(random.next_int(3) - 1, random.next_int(3) - 1, random.next_int(3) - 1);
+        place(world, position + offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use crate::protocol::PalettedContainer;
+    use crate::world::{OVERWORLD_MIN_Y, OVERWORLD_SECTION_COUNT};
+    use super::*;
+
+    #[test]
+    fn day_test() {
+        let mut weather = WorldWeather::new(WeatherConfig::default());
+        weather.set_time_of_day(6000);
+        assert!(weather.is_day());
+        weather.set_time_of_day(18000);
+        assert!(!weather.is_day());
+    }
+
+    #[test]
+    fn rain_test() {
+        let mut weather = WorldWeather::new(WeatherConfig::default());
+        let mut random = LegacyRandom::new(0);
+        let mut queue = PacketQueue::new();
+        weather.set_weather(Weather::Rain, 1000);
+        for _ in 0..100 {
+            weather.tick(&mut random, &mut queue).unwrap();
+        }
+        assert!(weather.is_raining());
+        assert!(!weather.is_thundering());
+        // 100 level changes, 5 time updates and the start of the rain
+        assert_eq!(queue.take().len(), 106);
+    }
+
+    #[test]
+    fn snow_test() {
+        let mut world = World::new(OVERWORLD_SECTION_COUNT, OVERWORLD_MIN_Y);
+        let biome = BiomeData::from_name("snowy_plains").unwrap().id as i32;
+        world.get_or_create_chunk(Vector2D::new(0, 0)).sections[4].biomes = PalettedContainer::new_single(biome);
+        world.set_block_state(Vector3D::new(0, 0, 0), 1);
+        let weather = WorldWeather::new(WeatherConfig { snow_accumulation_height: 2, ..Default::default() });
+        let position = surface(&world, 0, 0).unwrap();
+        assert_eq!(position, Vector3D::new(0, 1, 0));
+        let snow = weather.accumulate_snow(&world, position).unwrap();
+        world.set_block_state(position, snow);
+        assert_eq!(surface(&world, 0, 0), Some(position));
+        let snow = weather.accumulate_snow(&world, position).unwrap();
+        assert_eq!(Block::from_state(snow as u32), Some(Block::Snow { layers: 2 }));
+        world.set_block_state(position, snow);
+        assert_eq!(weather.accumulate_snow(&world, position), None);
+    }
+}
@@ -0,0 +1,625 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use euclid::default::{Vector2D, Vector3D};
+use bird_data::Block;
+use bird_protocol::{anyhow, ProtocolWritable, ProtocolWriter};
+use crate::connection::{CachedPacket, PacketSender, SharedEncodedPacket};
+use crate::light::{ChunkLight, ChunkLightNbt};
+use crate::protocol::*;
+
+pub mod query;
+
+/// Amount of longs in a height map (256 entries, 9 bits each)
+pub const HEIGHT_MAP_LONGS: usize = CHUNK_DATA_HEIGHT_MAP_LONGS;
+const HEIGHT_MAP_BITS: usize = 9;
+const HEIGHTS_IN_LONG: usize = 64 / HEIGHT_MAP_BITS;
+
+/// Blocks which don't block motion in height maps, materials of bird_data don't tell it
+const NON_COLLIDING_BLOCKS: &'static [&'static str] = &[
+    "fire", "soul_fire", "snow", "ladder", "lever", "redstone_wire", "tripwire", "tripwire_hook", "rail",
+    "nether_portal", "end_portal", "end_gateway", "light", "structure_void", "scaffolding", "repeater",
+    "comparator", "flower_pot", "candle", "cobweb",
+];
+const NON_COLLIDING_SUFFIXES: &'static [&'static str] = &[
+    "torch", "_rail", "_sign", "_button", "_pressure_plate", "_banner", "_carpet", "_head", "_skull", "_candle",
+];
+/// Fluids and blocks which always contain water
+const FLUID_BLOCKS: &'static [&'static str] = &[
+    "water", "lava", "bubble_column", "kelp", "kelp_plant", "seagrass", "tall_seagrass",
+];
+
+pub const OVERWORLD_SECTION_COUNT: usize = 24;
+pub const OVERWORLD_MIN_Y: i32 = -64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HeightMapType {
+    /// Highest block which blocks motion or contains a fluid
+    MotionBlocking,
+    /// Highest block which is not air
+    WorldSurface,
+}
+
+impl HeightMapType {
+    pub const fn key(&self) -> &'static str {
+        match self {
+            HeightMapType::MotionBlocking => CHUNK_DATA_HEIGHT_MAP_KEY,
+            HeightMapType::WorldSurface => CHUNK_DATA_WORLD_SURFACE_KEY,
+        }
+    }
+
+    pub fn matches(&self, state: i32) -> bool {
+        match self {
+            HeightMapType::MotionBlocking => is_motion_blocking(state),
+            HeightMapType::WorldSurface => !is_air(state),
+        }
+    }
+}
+
+/// Heights of the 16x16 columns of a chunk. The height is `y` above the highest matching block
+/// counted from the bottom of the world, zero if there is no such block
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HeightMap {
+    ty: HeightMapType,
+    heights: [u16; 256],
+}
+
+impl HeightMap {
+    pub fn new(ty: HeightMapType) -> Self {
+        Self { ty, heights: [0; 256] }
+    }
+
+    pub fn compute(ty: HeightMapType, column: &ChunkColumn) -> Self {
+        let mut height_map = Self::new(ty);
+        let top = column.sections.len() * 16;
+        for z in 0..16 {
+            for x in 0..16 {
+                height_map.heights[z << 4 | x] = column.highest(ty, x, z, top);
+            }
+        }
+        height_map
+    }
+
+    pub fn ty(&self) -> HeightMapType {
+        self.ty
+    }
+
+    pub fn get(&self, x: usize, z: usize) -> u16 {
+        self.heights[z << 4 | x]
+    }
+
+    /// Packed in the layout of [`GapCompactLongsWriter`]
+    pub fn to_longs(&self) -> [u64; HEIGHT_MAP_LONGS] {
+        let mut longs = [0; HEIGHT_MAP_LONGS];
+        for (index, height) in self.heights.iter().enumerate() {
+            let shift = (index % HEIGHTS_IN_LONG) * HEIGHT_MAP_BITS;
+            longs[index / HEIGHTS_IN_LONG] |= (*height as u64) << shift;
+        }
+        longs
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChunkColumn {
+    pub sections: Vec<ChunkSectionData>,
+    motion_blocking: HeightMap,
+    world_surface: HeightMap,
+    /// Cached light, it is dropped when a block is changed
+    light: Option<ChunkLight>,
+    /// Chunk packet of the position in the world which is shared between viewers
+    packet: CachedPacket,
+}
+
+impl ChunkColumn {
+    pub fn empty_section() -> ChunkSectionData {
+        ChunkSectionData {
+            block_count: 0,
+            block_states: PalettedContainer::new_single(0),
+            biomes: PalettedContainer::new_single(0),
+        }
+    }
+
+    pub fn new(section_count: usize) -> Self {
+        Self {
+            sections: (0..section_count).map(|_| Self::empty_section()).collect(),
+            motion_blocking: HeightMap::new(HeightMapType::MotionBlocking),
+            world_surface: HeightMap::new(HeightMapType::WorldSurface),
+            light: None,
+            packet: CachedPacket::new(),
+        }
+    }
+
+    /// Column of the given sections with computed height maps
+    pub fn from_sections(sections: Vec<ChunkSectionData>) -> Self {
+        let mut column = Self::new(0);
+        column.sections = sections;
+        column.compute_height_maps();
+        column
+    }
+
+    pub fn height_map(&self, ty: HeightMapType) -> &HeightMap {
+        match ty {
+            HeightMapType::MotionBlocking => &self.motion_blocking,
+            HeightMapType::WorldSurface => &self.world_surface,
+        }
+    }
+
+    /// Must be called after sections are changed directly
+    pub fn compute_height_maps(&mut self) {
+        debug_assert!(self.sections.len() * 16 < 1 << HEIGHT_MAP_BITS);
+        self.motion_blocking = HeightMap::compute(HeightMapType::MotionBlocking, self);
+        self.world_surface = HeightMap::compute(HeightMapType::WorldSurface, self);
+        self.light = None;
+        self.packet.invalidate();
+    }
+
+    pub fn light(&self) -> Option<&ChunkLight> {
+        self.light.as_ref()
+    }
+
+    /// Computes the light if it is not cached
+    pub fn compute_light(&mut self) -> &ChunkLight {
+        if self.light.is_none() {
+            self.light = Some(ChunkLight::compute(self));
+            self.packet.invalidate();
+        }
+        self.light.as_ref().unwrap()
+    }
+
+    /// Uses the stored light if it is valid, otherwise computes it. Returns true if the stored light is used
+    pub fn load_light(&mut self, nbt: &ChunkLightNbt, min_section_y: i8) -> bool {
+        self.light = ChunkLight::from_nbt(nbt, min_section_y, self.sections.len());
+        self.packet.invalidate();
+        let loaded = self.light.is_some();
+        self.compute_light();
+        loaded
+    }
+
+    /// Light to store in the chunk nbt, `isLightOn` is false if the light is not computed
+    pub fn light_nbt(&self, min_section_y: i8) -> ChunkLightNbt {
+        match self.light {
+            Some(ref light) => light.to_nbt(min_section_y),
+            None => ChunkLightNbt { is_light_on: false, sections: Vec::new() },
+        }
+    }
+
+    /// Height of the highest matching block below `y`
+    fn highest(&self, ty: HeightMapType, x: usize, z: usize, below: usize) -> u16 {
+        (0..below).rev()
+            .find(|y| self.get_block_state(x, *y, z).map_or(false, |state| ty.matches(state)))
+            .map_or(0, |y| y as u16 + 1)
+    }
+
+    fn updated_height(&self, height_map: &HeightMap, x: usize, y: usize, z: usize, state: i32) -> u16 {
+        let height = height_map.get(x, z);
+        match height_map.ty.matches(state) {
+            true => height.max(y as u16 + 1),
+            // The highest block is removed, so the next one is searched below it
+            false if y as u16 + 1 == height => self.highest(height_map.ty, x, z, y),
+            false => height,
+        }
+    }
+
+    /// Coordinates are relative to the column, `y` is counted from the bottom of the lowest section
+    pub fn get_block_state(&self, x: usize, y: usize, z: usize) -> Option<i32> {
+        let section = self.sections.get(y >> 4)?;
+        Some(section.block_states.get(block_index(x, y, z)))
+    }
+
+    /// Biomes are stored for 4x4x4 cubes, coordinates are in blocks
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> Option<i32> {
+        let section = self.sections.get(y >> 4)?;
+        Some(section.biomes.get((((y >> 2) & 3) << 4) | ((z >> 2) << 2) | (x >> 2)))
+    }
+
+    /// Returns `y` of the highest block which is not air
+    pub fn top_block(&self, x: usize, z: usize) -> Option<usize> {
+        (self.world_surface.get(x, z) as usize).checked_sub(1)
+    }
+
+    pub fn set_block_state(&mut self, x: usize, y: usize, z: usize, state: i32) -> Option<i32> {
+        let section = self.sections.get_mut(y >> 4)?;
+        let index = block_index(x, y, z);
+        let previous = section.block_states.get(index);
+        section.block_states.set(index, state);
+        match (is_air(previous), is_air(state)) {
+            (true, false) => section.block_count += 1,
+            (false, true) => section.block_count -= 1,
+            _ => {}
+        }
+        let motion_blocking = self.updated_height(&self.motion_blocking, x, y, z, state);
+        let world_surface = self.updated_height(&self.world_surface, x, y, z, state);
+        self.motion_blocking.heights[z << 4 | x] = motion_blocking;
+        self.world_surface.heights[z << 4 | x] = world_surface;
+        if previous != state {
+            self.light = None;
+            self.packet.invalidate();
+        }
+        Some(previous)
+    }
+
+    pub fn write_sections<W: ProtocolWriter>(&self, writer: &mut W) -> anyhow::Result<()> {
+        for section in &self.sections {
+            section.write(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the chunk packet which is encoded again for every call, chunks of the world are sent by [`World::send_chunk`]
+    pub fn send<S: PacketSender>(&self, position: Vector2D<i32>, sender: &mut S) -> anyhow::Result<()> {
+        self.with_packet(position, |packet| sender.send_packet(packet))
+    }
+
+    fn with_packet<R>(
+        &self,
+        position: Vector2D<i32>,
+        handler: impl FnOnce(&ChunkDataAndUpdateLightPS2C<'_>) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut sections = Vec::new();
+        self.write_sections(&mut sections)?;
+        let motion_blocking = self.motion_blocking.to_longs();
+        let world_surface = self.world_surface.to_longs();
+        let masks = self.light.as_ref().map(ChunkLight::masks);
+        handler(&ChunkDataAndUpdateLightPS2C {
+            chunk: position,
+            chunk_data: ChunkData {
+                height_map: ChunkDataHeightMap::new(&motion_blocking, Some(&world_surface)),
+                chunk_sections: ChunkSectionsData { data: &sections },
+            },
+            block_entities: Cow::Borrowed(&[]),
+            light_data: match (&self.light, &masks) {
+                (Some(light), Some(masks)) => light.light_data(masks),
+                _ => LightData {
+                    trust_edges: true,
+                    sky_light_mask: BitSet::new_words(&[]),
+                    block_light_mask: BitSet::new_words(&[]),
+                    empty_sky_light_mask: BitSet::new_words(&[]),
+                    empty_block_light_mask: BitSet::new_words(&[]),
+                    sky_light_arrays: Cow::Borrowed(&[]),
+                    block_light_arrays: Cow::Borrowed(&[]),
+                },
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct World {
+    section_count: usize,
+    min_y: i32,
+    chunks: HashMap<Vector2D<i32>, ChunkColumn>,
+}
+
+impl World {
+    pub fn new(section_count: usize, min_y: i32) -> Self {
+        Self { section_count, min_y, chunks: HashMap::new() }
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.section_count
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// Returns `None` if the chunk is not loaded or the position is outside of the world height
+    pub fn get_block_state(&self, position: Vector3D<i32>) -> Option<i32> {
+        let y = position.y.checked_sub(self.min_y).filter(|y| *y >= 0)?;
+        self.get_chunk(Vector2D::new(position.x >> 4, position.z >> 4))?
+            .get_block_state((position.x & 15) as usize, y as usize, (position.z & 15) as usize)
+    }
+
+    pub fn get_biome(&self, position: Vector3D<i32>) -> Option<i32> {
+        let y = position.y.checked_sub(self.min_y).filter(|y| *y >= 0)?;
+        self.get_chunk(Vector2D::new(position.x >> 4, position.z >> 4))?
+            .get_biome((position.x & 15) as usize, y as usize, (position.z & 15) as usize)
+    }
+
+    /// Returns `y` of the highest block which is not air in the column
+    pub fn top_block_y(&self, x: i32, z: i32) -> Option<i32> {
+        self.get_chunk(Vector2D::new(x >> 4, z >> 4))?
+            .top_block((x & 15) as usize, (z & 15) as usize)
+            .map(|y| y as i32 + self.min_y)
+    }
+
+    /// Returns the previous state, chunk is not created if it is not loaded
+    pub fn set_block_state(&mut self, position: Vector3D<i32>, state: i32) -> Option<i32> {
+        let y = position.y.checked_sub(self.min_y).filter(|y| *y >= 0)?;
+        self.get_chunk_mut(Vector2D::new(position.x >> 4, position.z >> 4))?
+            .set_block_state((position.x & 15) as usize, y as usize, (position.z & 15) as usize, state)
+    }
+
+    pub fn get_chunk(&self, position: Vector2D<i32>) -> Option<&ChunkColumn> {
+        self.chunks.get(&position)
+    }
+
+    pub fn get_chunk_mut(&mut self, position: Vector2D<i32>) -> Option<&mut ChunkColumn> {
+        self.chunks.get_mut(&position)
+    }
+
+    pub fn get_or_create_chunk(&mut self, position: Vector2D<i32>) -> &mut ChunkColumn {
+        let section_count = self.section_count;
+        self.chunks.entry(position).or_insert_with(|| ChunkColumn::new(section_count))
+    }
+
+    /// The cached packet of the column is dropped, it could be encoded for another position
+    pub fn insert_chunk(&mut self, position: Vector2D<i32>, mut column: ChunkColumn) -> Option<ChunkColumn> {
+        debug_assert!(column.sections.len() == self.section_count);
+        column.packet.invalidate();
+        self.chunks.insert(position, column)
+    }
+
+    /// Sends the chunk packet which is encoded once for all viewers until the chunk is changed.
+    /// Does nothing if the chunk is not loaded
+    pub fn send_chunk<S: PacketSender>(&self, position: Vector2D<i32>, sender: &mut S) -> anyhow::Result<()> {
+        match self.get_chunk(position) {
+            Some(column) => column.packet.send(sender, || column.with_packet(position, |packet| SharedEncodedPacket::new(packet))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove_chunk(&mut self, position: Vector2D<i32>) -> Option<ChunkColumn> {
+        self.chunks.remove(&position)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item=(&Vector2D<i32>, &ChunkColumn)> {
+        self.chunks.iter()
+    }
+
+    /// Computes the light of chunks which were changed since the light was computed, returns their amount
+    pub fn compute_light(&mut self) -> usize {
+        let mut count = 0;
+        for column in self.chunks.values_mut().filter(|column| column.light().is_none()) {
+            column.compute_light();
+            count += 1;
+        }
+        count
+    }
+}
+
+fn block_index(x: usize, y: usize, z: usize) -> usize {
+    ((y & 15) << 8) | (z << 4) | x
+}
+
+pub fn is_air(state: i32) -> bool {
+    matches!(Block::from_state(state as u32), None | Some(Block::Air | Block::CaveAir | Block::VoidAir))
+}
+
+/// Blocks which are counted by `MOTION_BLOCKING`, collision is guessed by the material and the name.
+/// Waterlogged blocks are counted only if they block motion
+pub fn is_motion_blocking(state: i32) -> bool {
+    if is_air(state) {
+        return false;
+    }
+    let data = match Block::from_state(state as u32) {
+        Some(block) => block.get_data(),
+        None => return false,
+    };
+    if FLUID_BLOCKS.contains(&data.name) {
+        return true;
+    }
+    let non_colliding = ["plant", "vine", "coweb"].iter().any(|material| data.material.contains(material))
+        || NON_COLLIDING_BLOCKS.contains(&data.name)
+        || NON_COLLIDING_SUFFIXES.iter().any(|suffix| data.name.ends_with(suffix))
+        || data.name.starts_with("potted_");
+    !non_colliding
+}
+
+pub fn block_position(position: Vector3D<f64>) -> Vector3D<i32> {
+    position.floor().to_i32()
+}
+
+pub fn chunk_position(x: f64, z: f64) -> Vector2D<i32> {
+    Vector2D::new((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+}
+
+pub fn is_in_view(center: Vector2D<i32>, position: Vector2D<i32>, view_distance: u8) -> bool {
+    let view_distance = view_distance as i32;
+    (center.x - position.x).abs() <= view_distance && (center.y - position.y).abs() <= view_distance
+}
+
+/// Tracks chunks that were sent to one player
+#[derive(Clone, Debug)]
+pub struct ChunkSender {
+    view_distance: u8,
+    center: Option<Vector2D<i32>>,
+    loaded: HashSet<Vector2D<i32>>,
+}
+
+impl ChunkSender {
+    pub fn new(view_distance: u8) -> Self {
+        Self { view_distance, center: None, loaded: HashSet::new() }
+    }
+
+    pub fn view_distance(&self) -> u8 {
+        self.view_distance
+    }
+
+    pub fn center(&self) -> Option<Vector2D<i32>> {
+        self.center
+    }
+
+    pub fn is_loaded(&self, position: Vector2D<i32>) -> bool {
+        self.loaded.contains(&position)
+    }
+
+    pub fn loaded(&self) -> impl Iterator<Item=&Vector2D<i32>> {
+        self.loaded.iter()
+    }
+
+    /// Unloads chunks which are not in the new distance and sends new ones, loaded chunks inside it are kept.
+    /// The caller sends `SetRenderDistance`
+    pub fn set_view_distance<S: PacketSender>(&mut self, world: &World, view_distance: u8, sender: &mut S) -> anyhow::Result<()> {
+        if self.view_distance == view_distance {
+            return Ok(());
+        }
+        self.view_distance = view_distance;
+        self.sync(world, sender)
+    }
+
+    /// Sends `SetCenterChunk`, unloads chunks which are out of view and sends new ones nearest first.
+    /// Does nothing if the center is not changed
+    pub fn update<S: PacketSender>(&mut self, world: &World, center: Vector2D<i32>, sender: &mut S) -> anyhow::Result<()> {
+        if self.center == Some(center) {
+            return Ok(());
+        }
+        self.center = Some(center);
+        sender.send_packet(&SetCenterChunkPS2C { chunk_x: center.x, chunk_z: center.y })?;
+        self.sync(world, sender)
+    }
+
+    /// Unloads chunks which are out of view and sends missing chunks around the current center
+    pub fn sync<S: PacketSender>(&mut self, world: &World, sender: &mut S) -> anyhow::Result<()> {
+        let center = match self.center {
+            Some(center) => center,
+            None => return Ok(()),
+        };
+        let view_distance = self.view_distance;
+        let outside: Vec<_> = self.loaded.iter()
+            .filter(|position| !is_in_view(center, **position, view_distance))
+            .copied()
+            .collect();
+        for position in outside {
+            self.loaded.remove(&position);
+            sender.send_packet(&UnloadChunkPS2C { chunk_x: position.x, chunk_z: position.y })?;
+        }
+        let radius = view_distance as i32;
+        let mut missing = Vec::new();
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                let position = Vector2D::new(center.x + x, center.y + z);
+                if !self.loaded.contains(&position) {
+                    missing.push(position);
+                }
+            }
+        }
+        missing.sort_by_key(|position| (*position - center).square_length());
+        for position in missing {
+            if world.get_chunk(position).is_some() {
+                world.send_chunk(position, sender)?;
+                self.loaded.insert(position);
+            }
+        }
+        Ok(())
+    }
+
+    /// Must be called when the chunk is added to the world after the player has moved
+    pub fn on_chunk_loaded<S: PacketSender>(&mut self, world: &World, position: Vector2D<i32>, sender: &mut S) -> anyhow::Result<()> {
+        match self.center {
+            Some(center) if is_in_view(center, position, self.view_distance) && !self.loaded.contains(&position) => {
+                if world.get_chunk(position).is_some() {
+                    world.send_chunk(position, sender)?;
+                    self.loaded.insert(position);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Must be called when the chunk is removed from the world
+    pub fn on_chunk_unloaded<S: PacketSender>(&mut self, position: Vector2D<i32>, sender: &mut S) -> anyhow::Result<()> {
+        match self.loaded.remove(&position) {
+            true => sender.send_packet(&UnloadChunkPS2C { chunk_x: position.x, chunk_z: position.y }),
+            false => Ok(()),
+        }
+    }
+
+    pub fn unload_all<S: PacketSender>(&mut self, sender: &mut S) -> anyhow::Result<()> {
+        for position in self.loaded.drain() {
+            sender.send_packet(&UnloadChunkPS2C { chunk_x: position.x, chunk_z: position.y })?;
+        }
+        self.center = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::PacketQueue;
+    use super::*;
+
+    #[test]
+    fn chunk_sender_test() {
+        let mut world = World::new(OVERWORLD_SECTION_COUNT, OVERWORLD_MIN_Y);
+        for x in -3..=3 {
+            for z in -3..=3 {
+                world.get_or_create_chunk(Vector2D::new(x, z));
+            }
+        }
+        let mut queue = PacketQueue::new();
+        let mut chunk_sender = ChunkSender::new(1);
+        chunk_sender.update(&world, Vector2D::new(0, 0), &mut queue).unwrap();
+        // SetCenterChunk and 9 chunks
+        assert_eq!(queue.take().len(), 10);
+        assert!(chunk_sender.is_loaded(Vector2D::new(1, 1)));
+        chunk_sender.update(&world, Vector2D::new(0, 0), &mut queue).unwrap();
+        assert!(queue.take().is_empty());
+        chunk_sender.update(&world, Vector2D::new(1, 0), &mut queue).unwrap();
+        // SetCenterChunk, 3 unloads and 3 chunks
+        assert_eq!(queue.take().len(), 7);
+        assert!(!chunk_sender.is_loaded(Vector2D::new(-1, 0)));
+        assert!(chunk_sender.is_loaded(Vector2D::new(2, 0)));
+    }
+
+    #[test]
+    fn cached_chunk_packet_test() {
+        let mut world = World::new(OVERWORLD_SECTION_COUNT, OVERWORLD_MIN_Y);
+        world.get_or_create_chunk(Vector2D::zero());
+        let (mut first, mut second) = (PacketQueue::new(), PacketQueue::new());
+        world.send_chunk(Vector2D::zero(), &mut first).unwrap();
+        world.send_chunk(Vector2D::zero(), &mut second).unwrap();
+        assert!(std::ptr::eq(first.packets[0].frame(), second.packets[0].frame()));
+        world.set_block_state(Vector3D::new(0, 0, 0), 1);
+        assert!(!world.get_chunk(Vector2D::zero()).unwrap().packet.is_cached());
+        world.send_chunk(Vector2D::zero(), &mut second).unwrap();
+        assert_ne!(first.packets[0].frame(), second.packets[1].frame());
+        let mut queue = PacketQueue::new();
+        world.get_chunk(Vector2D::zero()).unwrap().send(Vector2D::zero(), &mut queue).unwrap();
+        assert_eq!(queue.take()[0], second.packets[1].frame());
+    }
+
+    #[test]
+    fn block_state_test() {
+        let mut world = World::new(OVERWORLD_SECTION_COUNT, OVERWORLD_MIN_Y);
+        let position = Vector3D::new(-1, -64, 17);
+        assert_eq!(world.set_block_state(position, 1), None);
+        world.get_or_create_chunk(Vector2D::new(-1, 1));
+        assert_eq!(world.set_block_state(position, 1), Some(0));
+        assert_eq!(world.get_block_state(position), Some(1));
+        assert_eq!(world.get_block_state(position + Vector3D::new(0, 1, 0)), Some(0));
+        assert_eq!(world.get_block_state(position - Vector3D::new(0, 1, 0)), None);
+        assert_eq!(world.get_chunk(Vector2D::new(-1, 1)).unwrap().sections[0].block_count, 1);
+    }
+
+    #[test]
+    fn height_map_test() {
+        let mut column = ChunkColumn::new(2);
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        let torch = Block::Torch.get_state().unwrap() as i32;
+        column.set_block_state(1, 3, 2, stone);
+        column.set_block_state(1, 20, 2, torch);
+        assert_eq!(column.height_map(HeightMapType::MotionBlocking).get(1, 2), 4);
+        assert_eq!(column.height_map(HeightMapType::WorldSurface).get(1, 2), 21);
+        assert_eq!(column.top_block(1, 2), Some(20));
+        column.set_block_state(1, 3, 2, 0);
+        assert_eq!(column.height_map(HeightMapType::MotionBlocking).get(1, 2), 0);
+        column.set_block_state(1, 20, 2, 0);
+        assert_eq!(column.top_block(1, 2), None);
+        column.set_block_state(0, 8, 0, stone);
+        column.set_block_state(15, 31, 0, stone);
+        let computed = HeightMap::compute(HeightMapType::MotionBlocking, &column);
+        assert_eq!(&computed, column.height_map(HeightMapType::MotionBlocking));
+        let longs = computed.to_longs();
+        // Entries start at the lowest bit, the top bit of each long is unused
+        assert_eq!(longs[0], 9);
+        // 15th entry is the second entry of the third long
+        assert_eq!(longs[2], 32 << 9);
+    }
+
+    #[test]
+    fn chunk_position_test() {
+        assert_eq!(chunk_position(0.5, 15.9), Vector2D::new(0, 0));
+        assert_eq!(chunk_position(-0.5, 16.0), Vector2D::new(-1, 1));
+    }
+}
@@ -0,0 +1,264 @@
+use euclid::default::{Box3D, Point3D, Vector3D};
+use bird_data::{Block, BlockShape};
+use crate::ecs::{Ecs, Handle};
+use crate::protocol::Direction;
+use super::{is_air, World, FLUID_BLOCKS};
+
+/// Directions are not normalized if they are shorter than this
+const MIN_DIRECTION_LENGTH: f64 = 1.0e-7;
+
+/// Bounding box of the entity whose feet are at the position, it is added to entities by the server
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EntityBounds {
+    pub position: Vector3D<f64>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl EntityBounds {
+    pub fn new(position: Vector3D<f64>, width: f64, height: f64) -> Self {
+        Self { position, width, height }
+    }
+
+    pub fn aabb(&self) -> Box3D<f64> {
+        let half = self.width / 2.0;
+        Box3D::new(
+            Point3D::new(self.position.x - half, self.position.y, self.position.z - half),
+            Point3D::new(self.position.x + half, self.position.y + self.height, self.position.z + half),
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockHit {
+    pub position: Vector3D<i32>,
+    pub state: i32,
+    /// Face of the block which is hit by the ray
+    pub face: Direction,
+    pub point: Vector3D<f64>,
+    pub distance: f64,
+}
+
+pub fn block_shape(state: i32) -> Option<BlockShape> {
+    BlockShape::from_state(u32::try_from(state).ok()?)
+}
+
+/// Collision boxes of the state relative to the block
+pub fn collision_boxes(state: i32) -> Vec<Box3D<f64>> {
+    block_shape(state).map_or_else(Vec::new, |shape| {
+        shape.collision.iter()
+            .map(|shape| Box3D::new(Point3D::new(shape[0], shape[1], shape[2]), Point3D::new(shape[3], shape[4], shape[5])))
+            .collect()
+    })
+}
+
+/// Entities can be placed into the block, it doesn't have collision and it is not a fluid
+pub fn is_passable(state: i32) -> bool {
+    let fluid = Block::from_state(state as u32).map_or(false, |block| FLUID_BLOCKS.contains(&block.get_data().name));
+    block_shape(state).map_or(false, |shape| shape.is_empty()) && !fluid
+}
+
+/// Placed blocks replace air and fluids
+pub fn is_replaceable(state: i32) -> bool {
+    is_air(state) || Block::from_state(state as u32).map_or(false, |block| FLUID_BLOCKS.contains(&block.get_data().name))
+}
+
+/// The block below can be stood on and the feet and the head of the player are in passable blocks.
+/// Unloaded blocks are not safe
+pub fn is_safe_standing(world: &World, position: Vector3D<i32>) -> bool {
+    let state = |offset: i32| world.get_block_state(position + Vector3D::new(0, offset, 0));
+    let ground = state(-1).and_then(block_shape).map_or(false, |shape| !shape.is_empty());
+    ground && state(0).map_or(false, is_passable) && state(1).map_or(false, is_passable)
+}
+
+/// Highest safe position in the column, players are moved here when the spawn point is obstructed
+pub fn find_safe_spawn(world: &World, x: i32, z: i32) -> Option<Vector3D<i32>> {
+    let top = world.top_block_y(x, z)?;
+    (world.min_y() + 1..=top + 1).rev()
+        .map(|y| Vector3D::new(x, y, z))
+        .find(|position| is_safe_standing(world, *position))
+}
+
+/// Loaded blocks whose cells intersect the box
+pub fn blocks_in_box(world: &World, bounds: Box3D<f64>) -> impl Iterator<Item=(Vector3D<i32>, i32)> + '_ {
+    let min = bounds.min.floor().cast::<i32>();
+    // Blocks which are only touched by the box are not included
+    let max = bounds.max.ceil().cast::<i32>() - Vector3D::splat(1);
+    (min.x..=max.x)
+        .flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)))
+        .flat_map(move |(x, y)| (min.z..=max.z).map(move |z| Vector3D::new(x, y, z)))
+        .filter_map(move |position| world.get_block_state(position).map(|state| (position, state)))
+}
+
+/// Collision boxes of blocks in world coordinates which intersect the box, unloaded blocks are air
+pub fn colliding_boxes(world: &World, bounds: Box3D<f64>) -> impl Iterator<Item=Box3D<f64>> + '_ {
+    blocks_in_box(world, bounds)
+        .flat_map(|(position, state)| {
+            let offset = position.cast::<f64>();
+            collision_boxes(state).into_iter().map(move |shape| shape.translate(offset))
+        })
+        .filter(move |shape| shape.intersects(&bounds))
+}
+
+pub fn intersects_blocks(world: &World, bounds: Box3D<f64>) -> bool {
+    colliding_boxes(world, bounds).next().is_some()
+}
+
+/// Distance along the ray to the box and the face which is entered first, `None` if the ray misses it
+/// or starts inside of it
+pub fn ray_box_intersection(origin: Vector3D<f64>, direction: Vector3D<f64>, bounds: &Box3D<f64>) -> Option<(f64, Direction)> {
+    let origin = origin.to_array();
+    let direction = direction.to_array();
+    let (min, max) = (bounds.min.to_array(), bounds.max.to_array());
+    // Faces which are entered by rays in the positive and the negative direction of the axis
+    let faces = [(Direction::West, Direction::East), (Direction::Down, Direction::Up), (Direction::North, Direction::South)];
+    let (mut enter, mut exit, mut face) = (f64::NEG_INFINITY, f64::INFINITY, None);
+    for (axis, (positive_face, negative_face)) in faces.into_iter().enumerate() {
+        if direction[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let (near, far) = ((min[axis] - origin[axis]) / direction[axis], (max[axis] - origin[axis]) / direction[axis]);
+        let (near, far, near_face) = match direction[axis] > 0.0 {
+            true => (near, far, positive_face),
+            false => (far, near, negative_face),
+        };
+        if near > enter {
+            enter = near;
+            face = Some(near_face);
+        }
+        exit = exit.min(far);
+    }
+    match enter <= exit && enter >= 0.0 {
+        true => face.map(|face| (enter, face)),
+        false => None,
+    }
+}
+
+/// First block which is hit by the ray, blocks without collision are skipped. It is used to check the reach
+/// of interactions, unloaded blocks are air
+pub fn raycast_blocks(world: &World, origin: Vector3D<f64>, direction: Vector3D<f64>, max_distance: f64) -> Option<BlockHit> {
+    if direction.length() < MIN_DIRECTION_LENGTH {
+        return None;
+    }
+    let direction = direction.normalize();
+    let mut block = origin.floor().cast::<i32>().to_array();
+    let (start, ray) = (origin.to_array(), direction.to_array());
+    let mut step = [0; 3];
+    let mut next_boundary = [f64::INFINITY; 3];
+    let mut boundary_delta = [f64::INFINITY; 3];
+    for (axis, ray) in ray.into_iter().enumerate() {
+        if ray > 0.0 {
+            step[axis] = 1;
+            next_boundary[axis] = (block[axis] as f64 + 1.0 - start[axis]) / ray;
+        } else if ray < 0.0 {
+            step[axis] = -1;
+            next_boundary[axis] = (block[axis] as f64 - start[axis]) / ray;
+        }
+        if step[axis] != 0 {
+            boundary_delta[axis] = 1.0 / ray.abs();
+        }
+    }
+    let mut distance = 0.0;
+    while distance <= max_distance {
+        let position = Vector3D::new(block[0], block[1], block[2]);
+        if let Some(state) = world.get_block_state(position) {
+            // Shapes are inside of the block, so the nearest hit in the first block with a hit is the nearest one
+            let hit = collision_boxes(state).into_iter()
+                .filter_map(|shape| ray_box_intersection(origin, direction, &shape.translate(position.cast())))
+                .filter(|(hit_distance, _)| *hit_distance <= max_distance)
+                .min_by(|(first, _), (second, _)| first.total_cmp(second));
+            if let Some((distance, face)) = hit {
+                return Some(BlockHit { position, state, face, point: origin + direction * distance, distance });
+            }
+        }
+        let axis = (0..3).min_by(|first, second| next_boundary[*first].total_cmp(&next_boundary[*second])).unwrap();
+        distance = next_boundary[axis];
+        block[axis] += step[axis];
+        next_boundary[axis] += boundary_delta[axis];
+    }
+    None
+}
+
+/// Entities with [`EntityBounds`] which intersect the box
+pub fn entities_in_box(ecs: &Ecs, bounds: Box3D<f64>) -> Vec<Handle> {
+    ecs.query::<EntityBounds>()
+        .filter(|(_, entity)| entity.aabb().intersects(&bounds))
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+/// Nearest entity which is hit by the ray and its distance, the entity which casts the ray is skipped
+pub fn raycast_entities(
+    ecs: &Ecs,
+    origin: Vector3D<f64>,
+    direction: Vector3D<f64>,
+    max_distance: f64,
+    except: Option<Handle>,
+) -> Option<(Handle, f64)> {
+    if direction.length() < MIN_DIRECTION_LENGTH {
+        return None;
+    }
+    let direction = direction.normalize();
+    ecs.query::<EntityBounds>()
+        .filter(|(handle, _)| Some(*handle) != except)
+        .filter_map(|(handle, entity)| {
+            ray_box_intersection(origin, direction, &entity.aabb()).map(|(distance, _)| (handle, distance))
+        })
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::default::Vector2D;
+    use crate::block_state::BlockState;
+    use super::*;
+
+    #[test]
+    fn world_query_test() {
+        let mut world = World::new(2, 0);
+        world.get_or_create_chunk(Vector2D::zero());
+        let stone = Block::Stone.get_state().unwrap() as i32;
+        let slab = BlockState::from_name("oak_slab").unwrap().with("type", "bottom").unwrap().id();
+        world.set_block_state(Vector3D::new(3, 1, 0), stone);
+        world.set_block_state(Vector3D::new(0, 0, 3), slab);
+        assert!(collision_boxes(Block::Water.get_state().unwrap() as i32).is_empty());
+
+        let hit = raycast_blocks(&world, Vector3D::new(0.5, 1.5, 0.5), Vector3D::new(1.0, 0.0, 0.0), 5.0).unwrap();
+        assert_eq!((hit.position, hit.face, hit.state), (Vector3D::new(3, 1, 0), Direction::West, stone));
+        assert!((hit.distance - 2.5).abs() < 1.0e-9);
+        assert!(raycast_blocks(&world, Vector3D::new(0.5, 1.5, 0.5), Vector3D::new(1.0, 0.0, 0.0), 2.0).is_none());
+        // The ray passes above the bottom slab
+        assert!(raycast_blocks(&world, Vector3D::new(0.5, 0.75, 0.5), Vector3D::new(0.0, 0.0, 1.0), 5.0).is_none());
+        let hit = raycast_blocks(&world, Vector3D::new(0.5, 1.5, 3.5), Vector3D::new(0.0, -1.0, 0.0), 5.0).unwrap();
+        assert_eq!((hit.position, hit.face), (Vector3D::new(0, 0, 3), Direction::Up));
+
+        let bounds = Box3D::new(Point3D::new(2.5, 1.0, 0.0), Point3D::new(3.5, 2.0, 1.0));
+        assert_eq!(blocks_in_box(&world, bounds).count(), 2);
+        assert!(intersects_blocks(&world, bounds));
+        // Touching the top of the slab is not a collision
+        assert!(!intersects_blocks(&world, Box3D::new(Point3D::new(0.0, 0.5, 3.0), Point3D::new(1.0, 1.5, 4.0))));
+
+        let stone_shape = block_shape(stone).unwrap();
+        assert!(stone_shape.is_full_cube() && stone_shape.suffocates() && stone_shape.occludes() && stone_shape.is_spawnable());
+        assert!(!block_shape(Block::Glass.get_state().unwrap() as i32).unwrap().suffocates());
+        assert!(!block_shape(slab).unwrap().is_spawnable());
+        assert!(!is_passable(Block::Water.get_state().unwrap() as i32) && is_passable(0));
+        assert_eq!(find_safe_spawn(&world, 3, 0), Some(Vector3D::new(3, 2, 0)));
+        assert_eq!(find_safe_spawn(&world, 0, 3), Some(Vector3D::new(0, 1, 3)));
+        assert_eq!(find_safe_spawn(&world, 5, 5), None);
+
+        let mut ecs = Ecs::new();
+        let (first, second) = (ecs.spawn(), ecs.spawn());
+        ecs.insert(first, EntityBounds::new(Vector3D::new(0.5, 0.0, 5.5), 0.6, 1.8)).unwrap();
+        ecs.insert(second, EntityBounds::new(Vector3D::new(0.5, 0.0, 8.5), 0.6, 1.8)).unwrap();
+        assert_eq!(entities_in_box(&ecs, Box3D::new(Point3D::new(0.0, 0.0, 5.0), Point3D::new(1.0, 1.0, 6.0))), [first]);
+        let origin = Vector3D::new(0.5, 1.0, 4.0);
+        assert_eq!(raycast_entities(&ecs, origin, Vector3D::new(0.0, 0.0, 1.0), 10.0, None).map(|(handle, _)| handle), Some(first));
+        assert_eq!(raycast_entities(&ecs, origin, Vector3D::new(0.0, 0.0, 1.0), 10.0, Some(first)).map(|(handle, _)| handle), Some(second));
+        assert!(raycast_entities(&ecs, origin, Vector3D::new(0.0, 0.0, 1.0), 1.0, None).is_none());
+    }
+}
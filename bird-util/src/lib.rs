@@ -1,3 +1,6 @@
+// Tests use float functions of std
+#![cfg_attr(not(test), no_std)]
+
 pub struct ConstAssert<const EXPR: bool>;
 
 pub trait ConstAssertTrue {}